@@ -0,0 +1,1778 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  ENSAMBLADOR Z80                                               ║
+//! ║  - Dos pasadas: etiquetas, locales (.bucle) y expresiones      ║
+//! ║  - ORG, DB, DW, DS, INCBIN, INCLUDE, IF/ELSE/ENDIF y MACRO     ║
+//! ║  - Salida ROM con cabecera "AB", BIN de BLOAD o .COM de DOS    ║
+//! ║  - Módulos reubicables: SECTION, PUBLIC, EXTERN y BANK()       ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::bload::BloadHeader;
+use crate::disasm::{decode, hex16, Operand};
+use crate::object::{Location, ObjectFile, ObjectSection, ObjectSymbol, RelocPart, RelocTarget, Relocation};
+use crate::rom::{RomHeader, ROM_HEADER_SIZE};
+use crate::symbols::{export_symbols, SymbolFormat, SymbolKind, SymbolTable};
+use crate::timing::timing;
+
+/// Anidamiento máximo de INCLUDE, macros y EQU que dependen de otros
+const MAX_DEPTH: usize = 16;
+/// Bytes por fila del listado
+const LISTING_BYTES: usize = 4;
+/// Nombre con el que se informa de los errores del fuente principal
+pub(crate) const MAIN_SOURCE: &str = "main.asm";
+const DIRECTIVES: [&str; 30] = [
+    "ORG", "DB", "DEFB", "BYTE", "DM", "DEFM", "DW", "DEFW", "WORD", "DS", "DEFS", "BLOCK", "EQU", "=", "DEFL",
+    "INCBIN", "INCLUDE", "IF", "IFDEF", "IFNDEF", "ELSE", "ENDIF", "MACRO", "ENDM", "END", "SECTION", "PUBLIC",
+    "GLOBAL", "EXTERN", "EXTRN",
+];
+/// Sección de un módulo reubicable antes del primer SECTION
+const DEFAULT_SECTION: &str = "CODE";
+const REGISTERS: [&str; 29] = [
+    "A", "B", "C", "D", "E", "H", "L", "I", "R", "F", "AF", "AF'", "BC", "DE", "HL", "SP", "IX", "IY", "IXH", "IXL",
+    "IYH", "IYL", "NZ", "Z", "NC", "PO", "PE", "P", "M",
+];
+
+// ═══════════════════════════════════════════════════════════════
+// FORMATOS DE SALIDA
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Cartucho con cabecera "AB" y relleno a 8, 16 o 32 KB
+    Rom,
+    /// Binario de BLOAD con cabecera de 7 bytes
+    Bin,
+    /// Programa de MSX-DOS cargado en 0100h
+    Com,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "rom" => Some(OutputFormat::Rom),
+            "bin" | "bload" => Some(OutputFormat::Bin),
+            "com" => Some(OutputFormat::Com),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Rom => "rom",
+            OutputFormat::Bin => "bin",
+            OutputFormat::Com => "com",
+        }
+    }
+
+    /// Origen si el fuente no tiene ORG: detrás de la cabecera del
+    /// cartucho, en la RAM alta para BLOAD o en la TPA de MSX-DOS
+    pub fn default_origin(&self) -> u16 {
+        match self {
+            OutputFormat::Rom => 0x4000 + ROM_HEADER_SIZE as u16,
+            OutputFormat::Bin => 0xC000,
+            OutputFormat::Com => 0x0100,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// RESULTADO
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AsmError {
+    /// Fichero del fuente (vacío si el error es de la salida)
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl AsmError {
+    pub fn new(message: &str) -> AsmError {
+        AsmError { file: String::new(), line: 0, message: message.to_string() }
+    }
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}:{}: {}", self.file, self.line, self.message)
+        }
+    }
+}
+
+/// Línea del fuente con la dirección y los bytes que generó
+#[derive(Clone, Debug)]
+pub struct AsmLine {
+    pub file: String,
+    pub line: usize,
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub source: String,
+    /// Los bytes son una instrucción (no DB, DW ni INCBIN)
+    pub instruction: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Assembly {
+    /// Primera dirección escrita
+    pub origin: u16,
+    /// Bytes desde `origin` hasta la última dirección escrita; los huecos
+    /// (DS sin valor, ORG hacia delante) quedan a cero
+    pub code: Vec<u8>,
+    /// Dirección de END, la etiqueta START o el origen
+    pub entry: u16,
+    /// Etiquetas y constantes definidas por el fuente
+    pub symbols: BTreeMap<String, u16>,
+    pub lines: Vec<AsmLine>,
+}
+
+impl Assembly {
+    /// Última dirección escrita
+    pub fn end(&self) -> u16 {
+        self.origin.wrapping_add(self.code.len().saturating_sub(1) as u16)
+    }
+
+    /// Página de 16 KB en la que empieza el cartucho
+    pub fn rom_base(&self) -> u16 {
+        self.origin & 0xC000
+    }
+
+    /// Cartucho relleno con FFh a 8, 16 o 32 KB (o múltiplos de 16 KB). Si
+    /// el código no empieza por "AB" se añade la cabecera con INIT en la
+    /// entrada, para lo que deben quedar libres los 16 primeros bytes
+    pub fn to_rom(&self) -> Result<Vec<u8>, String> {
+        if self.code.is_empty() {
+            return Err("No se generó código".to_string());
+        }
+        let base = self.rom_base();
+        let start = (self.origin - base) as usize;
+        let size = match start + self.code.len() {
+            0..=0x2000 => 0x2000,
+            0x2001..=0x4000 => 0x4000,
+            0x4001..=0x8000 => 0x8000,
+            used => used.div_ceil(0x4000) * 0x4000,
+        };
+        let mut rom = vec![0xFF; size];
+        rom[start..start + self.code.len()].copy_from_slice(&self.code);
+        if !rom.starts_with(b"AB") {
+            if start < ROM_HEADER_SIZE {
+                return Err(format!(
+                    "El código empieza en {} y ocupa la cabecera del cartucho (usa ORG {})",
+                    hex16(self.origin),
+                    hex16(base + ROM_HEADER_SIZE as u16)
+                ));
+            }
+            let header = RomHeader { init: self.entry, statement: 0, device: 0, text: 0 };
+            rom[..ROM_HEADER_SIZE].copy_from_slice(&header.to_bytes());
+        }
+        Ok(rom)
+    }
+
+    /// Fichero de BLOAD: cabecera FEh + inicio, fin y ejecución
+    pub fn to_bin(&self) -> Result<Vec<u8>, String> {
+        if self.code.is_empty() {
+            return Err("No se generó código".to_string());
+        }
+        let mut out = BloadHeader::new(self.origin, self.end(), self.entry).to_bytes().to_vec();
+        out.extend_from_slice(&self.code);
+        Ok(out)
+    }
+
+    /// Programa .COM: sin cabecera, cargado y ejecutado en 0100h
+    pub fn to_com(&self) -> Result<Vec<u8>, String> {
+        if self.code.is_empty() {
+            return Err("No se generó código".to_string());
+        }
+        if self.origin != 0x0100 {
+            return Err(format!("Un .COM se carga en 0100h y el código empieza en {}", hex16(self.origin)));
+        }
+        Ok(self.code.clone())
+    }
+
+    pub fn output(&self, format: OutputFormat) -> Result<Vec<u8>, String> {
+        match format {
+            OutputFormat::Rom => self.to_rom(),
+            OutputFormat::Bin => self.to_bin(),
+            OutputFormat::Com => self.to_com(),
+        }
+    }
+
+    /// Etiquetas y constantes para el desensamblador y el depurador
+    pub fn symbol_table(&self) -> SymbolTable {
+        let mut table = SymbolTable::new();
+        for (name, &address) in &self.symbols {
+            table.insert(address, name, SymbolKind::Label);
+        }
+        table
+    }
+
+    pub fn symbol_file(&self, format: SymbolFormat) -> String {
+        export_symbols(self.symbols.iter().map(|(name, &address)| (name.as_str(), address)), format)
+    }
+
+    /// Listado: línea, dirección, bytes (4 por fila), estados T de las
+    /// instrucciones y el fuente. Cada INCLUDE se marca al cambiar de fichero
+    pub fn listing(&self) -> String {
+        let mut out = String::new();
+        let mut file = "";
+        for line in &self.lines {
+            if line.file != file {
+                file = &line.file;
+                out.push_str(&format!("; ── {} ──\n", file));
+            }
+            let mut rows = line.bytes.chunks(LISTING_BYTES);
+            let first = rows.next().unwrap_or(&[]);
+            let address = if first.is_empty() { String::new() } else { format!("{:04X}", line.address) };
+            let tstates = if line.instruction { timing(&line.bytes).text() } else { String::new() };
+            out.push_str(&format!("{:>5}  {:<4}  {:<11} {:>5}  {}\n", line.line, address, hex_bytes(first), tstates, line.source));
+            let mut next = line.address as usize + first.len();
+            for row in rows {
+                out.push_str(&format!("       {:04X}  {}\n", next as u16, hex_bytes(row)));
+                next += row.len();
+            }
+        }
+        out
+    }
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" ")
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ENSAMBLADOR
+// ═══════════════════════════════════════════════════════════════
+
+pub struct Assembler {
+    format: OutputFormat,
+    /// Ficheros para INCLUDE e INCBIN
+    files: HashMap<String, Vec<u8>>,
+    /// Directorio donde buscar lo que no está en `files`
+    include_dir: Option<PathBuf>,
+    /// Símbolos conocidos de antemano; el fuente puede redefinirlos
+    predefined: Vec<(String, i64)>,
+}
+
+impl Assembler {
+    pub fn new(format: OutputFormat) -> Assembler {
+        Assembler { format, files: HashMap::new(), include_dir: None, predefined: Vec::new() }
+    }
+
+    pub fn file(mut self, name: &str, data: &[u8]) -> Self {
+        self.files.insert(name.to_string(), data.to_vec());
+        self
+    }
+
+    pub fn include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dir = Some(dir.into());
+        self
+    }
+
+    pub fn define(mut self, name: &str, value: i64) -> Self {
+        self.predefined.push((name.to_string(), value));
+        self
+    }
+
+    /// Predefinir las direcciones de una tabla (BIOS y variables del MSX)
+    pub fn symbols(mut self, table: &SymbolTable) -> Self {
+        self.predefined.extend(table.iter().map(|(address, symbol)| (symbol.name.clone(), address as i64)));
+        self
+    }
+
+    pub fn assemble(&self, source: &str) -> Result<Assembly, Vec<AsmError>> {
+        Ok(self.passes(MAIN_SOURCE, source, false)?.finish())
+    }
+
+    /// Ensamblar un módulo reubicable para el enlazador: sin ORG, con el
+    /// código repartido en secciones y los símbolos entre módulos
+    /// declarados con PUBLIC y EXTERN
+    pub fn assemble_object(&self, module: &str, source: &str) -> Result<ObjectFile, Vec<AsmError>> {
+        self.passes(module, source, true)?.finish_object(module)
+    }
+
+    /// Ensamblar uno de los ficheros añadidos con `file`
+    pub fn assemble_file(&self, name: &str) -> Result<Assembly, Vec<AsmError>> {
+        let data = self.read(name).map_err(|e| vec![AsmError::new(&e)])?;
+        self.assemble(&String::from_utf8_lossy(&data))
+    }
+
+    fn passes(&self, file: &str, source: &str, relocatable: bool) -> Result<Pass<'_>, Vec<AsmError>> {
+        let mut pass = Pass::new(self, 1, HashMap::new(), relocatable);
+        pass.run(file, source);
+        if !pass.errors.is_empty() {
+            return Err(pass.errors);
+        }
+        let mut pass = Pass::new(self, 2, pass.symbols, relocatable);
+        pass.run(file, source);
+        if !pass.errors.is_empty() {
+            return Err(pass.errors);
+        }
+        Ok(pass)
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>, String> {
+        if let Some(data) = self.files.get(name) {
+            return Ok(data.clone());
+        }
+        if let Some((_, data)) = self.files.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            return Ok(data.clone());
+        }
+        self.include_dir
+            .as_ref()
+            .and_then(|dir| std::fs::read(dir.join(name)).ok())
+            .ok_or_else(|| format!("No se encuentra el fichero {}", name))
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TABLA DE CODIFICACIÓN
+// ═══════════════════════════════════════════════════════════════
+
+/// Forma de un operando en la tabla de codificación
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pattern {
+    Register(&'static str),
+    Indirect(&'static str),
+    /// (IX+d) o (IY+d)
+    Indexed(&'static str),
+    /// Valor fijo: bit, modo de interrupción, RST
+    Number(u8),
+    Byte,
+    Word,
+    Memory,
+    Port,
+    /// Destino de JR o DJNZ
+    Relative,
+    /// Destino de JP o CALL
+    Absolute,
+}
+
+impl Pattern {
+    fn from_operand(mnemonic: &str, operand: &Operand) -> Pattern {
+        match *operand {
+            Operand::Register(register) => Pattern::Register(register),
+            Operand::Indirect(register) => Pattern::Indirect(register),
+            Operand::Indexed(register, _) => Pattern::Indexed(register),
+            Operand::Number(n) | Operand::Restart(n) => Pattern::Number(n),
+            Operand::Byte(_) => Pattern::Byte,
+            Operand::Word(_) => Pattern::Word,
+            Operand::Memory(_) => Pattern::Memory,
+            Operand::Port(_) => Pattern::Port,
+            Operand::Target(_) if mnemonic == "JR" || mnemonic == "DJNZ" => Pattern::Relative,
+            Operand::Target(_) => Pattern::Absolute,
+        }
+    }
+
+    /// Bytes que ocupa el operando detrás del opcode
+    fn size(&self) -> usize {
+        match self {
+            Pattern::Indexed(_) | Pattern::Byte | Pattern::Port | Pattern::Relative => 1,
+            Pattern::Word | Pattern::Memory | Pattern::Absolute => 2,
+            _ => 0,
+        }
+    }
+}
+
+struct Encoding {
+    /// Prefijos y opcode; en DD CB y FD CB el desplazamiento va entre el
+    /// segundo byte y el opcode
+    opcode: Vec<u8>,
+    patterns: Vec<Pattern>,
+}
+
+impl Encoding {
+    fn len(&self) -> usize {
+        let displacement = self.is_indexed_cb() as usize;
+        self.opcode.len() - displacement + self.patterns.iter().map(Pattern::size).sum::<usize>()
+    }
+
+    fn is_indexed_cb(&self) -> bool {
+        self.opcode.len() == 3 && self.opcode[1] == 0xCB
+    }
+}
+
+/// La tabla sale del propio desensamblador: cada opcode se decodifica y
+/// sus operandos se convierten en patrones, así que ambos aceptan las
+/// mismas instrucciones. Si dos opcodes dan la misma instrucción se queda
+/// el primero, que es el documentado
+fn encodings() -> &'static HashMap<&'static str, Vec<Encoding>> {
+    static TABLE: OnceLock<HashMap<&'static str, Vec<Encoding>>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let prefixes = [0xCB, 0xED, 0xDD, 0xFD];
+        let mut opcodes: Vec<Vec<u8>> = (0..=255u8).filter(|op| !prefixes.contains(op)).map(|op| vec![op]).collect();
+        for prefix in prefixes {
+            opcodes.extend((0..=255u8).filter(|&op| op != 0xCB || prefix == 0xCB).map(|op| vec![prefix, op]));
+        }
+        for prefix in [0xDD, 0xFD] {
+            // Con z = 6 la forma documentada, antes que las que copian en
+            // un registro
+            let mut ops: Vec<u8> = (0..=255).collect();
+            ops.sort_by_key(|op| op & 7 != 6);
+            opcodes.extend(ops.into_iter().map(|op| vec![prefix, 0xCB, op]));
+        }
+
+        let mut table: HashMap<&'static str, Vec<Encoding>> = HashMap::new();
+        for opcode in opcodes {
+            let code = match opcode[..] {
+                [prefix, 0xCB, op] => vec![prefix, 0xCB, 0, op],
+                _ => [&opcode[..], &[0, 0, 0]].concat(),
+            };
+            let instruction = decode(&code, 0);
+            if instruction.is_data() {
+                continue;
+            }
+            let patterns: Vec<Pattern> =
+                instruction.operands.iter().map(|op| Pattern::from_operand(instruction.mnemonic, op)).collect();
+            let entries = table.entry(instruction.mnemonic).or_default();
+            if !entries.iter().any(|e| e.patterns == patterns) {
+                entries.push(Encoding { opcode, patterns });
+            }
+        }
+        table
+    })
+}
+
+/// Operando tal como aparece en el fuente
+#[derive(Clone, Debug)]
+enum Arg<'a> {
+    Register(String),
+    Indirect(String),
+    Indexed(String, &'a str),
+    /// Expresión entre paréntesis: memoria o puerto
+    Memory(&'a str),
+    Value(&'a str),
+}
+
+impl<'a> Arg<'a> {
+    fn parse(text: &'a str) -> Arg<'a> {
+        let upper = text.to_ascii_uppercase();
+        if REGISTERS.contains(&upper.as_str()) {
+            return Arg::Register(upper);
+        }
+        let Some(inner) = enclosed(text) else {
+            return Arg::Value(text);
+        };
+        let upper = inner.to_ascii_uppercase();
+        if ["HL", "BC", "DE", "SP", "C", "IX", "IY"].contains(&upper.as_str()) {
+            return Arg::Indirect(upper);
+        }
+        if upper.starts_with("IX") || upper.starts_with("IY") {
+            let displacement = inner[2..].trim_start();
+            if displacement.starts_with(['+', '-']) {
+                return Arg::Indexed(upper[..2].to_string(), displacement);
+            }
+        }
+        Arg::Memory(inner)
+    }
+
+    fn is_register(&self, name: &str) -> bool {
+        matches!(self, Arg::Register(register) if register == name)
+    }
+}
+
+/// Contenido de "( … )" si los paréntesis exteriores se corresponden
+fn enclosed(text: &str) -> Option<&str> {
+    let inner = text.strip_prefix('(')?.strip_suffix(')')?;
+    let mut depth = 0i32;
+    for c in inner.chars() {
+        depth += match c {
+            '(' => 1,
+            ')' => -1,
+            _ => 0,
+        };
+        if depth < 0 {
+            return None;
+        }
+    }
+    Some(inner.trim())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// EXPRESIONES
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(i64),
+    Symbol(String),
+    /// $: dirección actual
+    Here,
+    Operator(&'static str),
+    Open,
+    Close,
+}
+
+/// Por niveles de precedencia, de menor a mayor
+const OPERATORS: [&[&str]; 10] = [
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!=", "<>", "="],
+    &["<=", ">=", "<", ">"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+const UNARY: [&str; 4] = ["-", "+", "~", "!"];
+const NOT_RELOCATABLE: &str = "Expresión no reubicable: a una etiqueta de sección o externa solo se le suma o resta una constante";
+
+/// Valor de una expresión. En un módulo reubicable puede depender de
+/// dónde quede una sección o un símbolo externo; `number` es entonces lo
+/// que el enlazador suma a esa dirección
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Value {
+    number: i64,
+    reloc: Option<(RelocTarget, RelocPart)>,
+}
+
+impl Value {
+    fn absolute(number: i64) -> Value {
+        Value { number, reloc: None }
+    }
+
+    /// Solo las operaciones que el enlazador sabe completar: sumar o
+    /// restar una constante y restar dos direcciones de la misma sección
+    fn combine(op: &str, a: Value, b: Value) -> Result<Value, String> {
+        let number = apply(op, a.number, b.number)?;
+        let word = |reloc: Option<(RelocTarget, RelocPart)>| reloc.filter(|(_, part)| *part == RelocPart::Word);
+        let reloc = match (op, a.reloc, b.reloc) {
+            (_, None, None) => None,
+            ("+" | "-", Some(_), None) if word(a.reloc).is_some() => a.reloc,
+            ("+", None, Some(_)) if word(b.reloc).is_some() => b.reloc,
+            ("-", Some(x), Some(y)) if x == y && word(a.reloc).is_some() => None,
+            _ => return Err(NOT_RELOCATABLE.to_string()),
+        };
+        Ok(Value { number, reloc })
+    }
+
+    /// LOW(), HIGH() o BANK()
+    fn part(self, part: RelocPart) -> Result<Value, String> {
+        match (self.reloc, part) {
+            (Some((target, RelocPart::Word)), _) => Ok(Value { number: self.number, reloc: Some((target, part)) }),
+            (Some(_), _) => Err(NOT_RELOCATABLE.to_string()),
+            (None, RelocPart::Low) => Ok(Value::absolute(self.number & 0xFF)),
+            (None, RelocPart::High) => Ok(Value::absolute((self.number >> 8) & 0xFF)),
+            (None, _) => Err("BANK() necesita una etiqueta de un módulo reubicable".to_string()),
+        }
+    }
+
+    /// Destino de CALL o JP: el enlazador puede desviarlo a otro banco
+    fn as_call(self) -> Value {
+        match self.reloc {
+            Some((target, RelocPart::Word)) => Value { reloc: Some((target, RelocPart::Call)), ..self },
+            _ => self,
+        }
+    }
+}
+
+/// Lo necesario para evaluar: dirección actual (y su sección en un
+/// módulo reubicable), etiqueta global para las locales y búsqueda de
+/// símbolos. Con `strict` un símbolo desconocido es un error; si no, la
+/// expresión queda sin valor
+struct Context<'a> {
+    address: u16,
+    section: Option<u16>,
+    scope: &'a str,
+    strict: bool,
+    lookup: &'a dyn Fn(&str) -> Option<Value>,
+}
+
+/// Nombre completo de un símbolo: las locales cuelgan de la última global
+fn qualify(name: &str, scope: &str) -> String {
+    if name.starts_with('.') {
+        format!("{}{}", scope, name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn symbol_key(name: &str, scope: &str) -> String {
+    qualify(name, scope).to_ascii_uppercase()
+}
+
+/// Número con sufijo h, b, o/q, prefijo 0x o 0b, o decimal
+pub(crate) fn parse_number(word: &str) -> Result<i64, String> {
+    let lower = word.to_ascii_lowercase();
+    let binary = |digits: &str| !digits.is_empty() && digits.chars().all(|c| c == '0' || c == '1');
+    let (digits, radix) = if let Some(hex) = lower.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(hex) = lower.strip_suffix('h') {
+        (hex, 16)
+    } else if let Some(bin) = lower.strip_suffix('b').filter(|d| binary(d)) {
+        (bin, 2)
+    } else if let Some(bin) = lower.strip_prefix("0b").filter(|d| binary(d)) {
+        (bin, 2)
+    } else if let Some(octal) = lower.strip_suffix(['o', 'q']) {
+        (octal, 8)
+    } else {
+        (lower.as_str(), 10)
+    };
+    i64::from_str_radix(digits, radix).map_err(|_| format!("Número no válido: {}", word))
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let operand_expected = matches!(tokens.last(), None | Some(Token::Operator(_)) | Some(Token::Open));
+        let word = |from: usize, valid: &dyn Fn(char) -> bool| -> String {
+            chars[from..].iter().take_while(|&&c| valid(c)).collect()
+        };
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            '\'' | '"' => {
+                let close = chars[i + 1..]
+                    .iter()
+                    .position(|&q| q == c)
+                    .ok_or_else(|| format!("Falta cerrar la comilla en {}", text.trim()))?;
+                if close != 1 {
+                    return Err(format!("Se esperaba un solo carácter en {}", text.trim()));
+                }
+                tokens.push(Token::Number(chars[i + 1] as i64));
+                i += 3;
+            }
+            '$' | '#' | '%' if c != '%' || operand_expected => {
+                let radix = if c == '%' { 2 } else { 16 };
+                let digits = word(i + 1, &|d| d.is_digit(radix));
+                if digits.is_empty() && c == '$' {
+                    tokens.push(Token::Here);
+                } else {
+                    let value =
+                        i64::from_str_radix(&digits, radix).map_err(|_| format!("Número no válido en {}", text.trim()))?;
+                    tokens.push(Token::Number(value));
+                }
+                i += 1 + digits.len();
+            }
+            _ if c.is_ascii_digit() => {
+                let number = word(i, &|d| d.is_ascii_alphanumeric());
+                tokens.push(Token::Number(parse_number(&number)?));
+                i += number.len();
+            }
+            _ if c.is_alphabetic() || c == '_' || c == '.' || c == '@' => {
+                let name = word(i, &|d| d.is_alphanumeric() || matches!(d, '_' | '.' | '@' | '?'));
+                i += name.chars().count();
+                tokens.push(Token::Symbol(name));
+            }
+            _ => {
+                let next = chars.get(i + 1).copied().unwrap_or(' ');
+                let mut operators = OPERATORS.iter().flat_map(|level| level.iter()).chain(UNARY.iter());
+                // Los de dos caracteres antes que sus prefijos
+                let operator = operators
+                    .clone()
+                    .find(|op| op.len() == 2 && op.starts_with(c) && op.ends_with(next))
+                    .or_else(|| operators.find(|op| op.len() == 1 && op.starts_with(c)))
+                    .ok_or_else(|| format!("Carácter inesperado en la expresión: {}", c))?;
+                tokens.push(Token::Operator(operator));
+                i += operator.len();
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a, 'b> {
+    tokens: &'a [Token],
+    pos: usize,
+    context: &'a Context<'b>,
+}
+
+impl Parser<'_, '_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Option<Value>, String> {
+        if level == OPERATORS.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some(&Token::Operator(op)) = self.peek() {
+            if !OPERATORS[level].contains(&op) {
+                break;
+            }
+            self.pos += 1;
+            let right = self.binary(level + 1)?;
+            left = match (left, right) {
+                (Some(a), Some(b)) => Some(Value::combine(op, a, b)?),
+                _ => None,
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Option<Value>, String> {
+        if let Some(&Token::Operator(op)) = self.peek() {
+            if UNARY.contains(&op) {
+                self.pos += 1;
+                return match self.unary()? {
+                    Some(v) if op == "+" => Ok(Some(v)),
+                    Some(v) if v.reloc.is_some() => Err(NOT_RELOCATABLE.to_string()),
+                    value => Ok(value.map(|v| {
+                        Value::absolute(match op {
+                            "-" => v.number.wrapping_neg(),
+                            "~" => !v.number,
+                            _ => (v.number == 0) as i64,
+                        })
+                    })),
+                };
+            }
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Option<Value>, String> {
+        let token = self.peek().cloned().ok_or("Expresión incompleta")?;
+        self.pos += 1;
+        match token {
+            Token::Number(value) => Ok(Some(Value::absolute(value))),
+            Token::Here => Ok(Some(Value {
+                number: self.context.address as i64,
+                reloc: self.context.section.map(|s| (RelocTarget::Section(s), RelocPart::Word)),
+            })),
+            Token::Open => {
+                let value = self.binary(0)?;
+                self.close()?;
+                Ok(value)
+            }
+            Token::Symbol(name) if self.peek() == Some(&Token::Open) => {
+                let part = match name.to_ascii_uppercase().as_str() {
+                    "LOW" => RelocPart::Low,
+                    "HIGH" => RelocPart::High,
+                    "BANK" => RelocPart::Bank,
+                    _ => return Err(format!("Función desconocida: {}", name)),
+                };
+                self.pos += 1;
+                let value = self.binary(0)?;
+                self.close()?;
+                value.map(|v| v.part(part)).transpose()
+            }
+            Token::Symbol(name) => match (self.context.lookup)(&symbol_key(&name, self.context.scope)) {
+                Some(value) => Ok(Some(value)),
+                None if self.context.strict => Err(format!("Símbolo no definido: {}", name)),
+                None => Ok(None),
+            },
+            _ => Err("Expresión no válida".to_string()),
+        }
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        if self.peek() != Some(&Token::Close) {
+            return Err("Falta cerrar un paréntesis".to_string());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+}
+
+fn apply(op: &str, a: i64, b: i64) -> Result<i64, String> {
+    Ok(match op {
+        "||" => (a != 0 || b != 0) as i64,
+        "&&" => (a != 0 && b != 0) as i64,
+        "|" => a | b,
+        "^" => a ^ b,
+        "&" => a & b,
+        "==" | "=" => (a == b) as i64,
+        "!=" | "<>" => (a != b) as i64,
+        "<=" => (a <= b) as i64,
+        ">=" => (a >= b) as i64,
+        "<" => (a < b) as i64,
+        ">" => (a > b) as i64,
+        "<<" => a.wrapping_shl(b as u32),
+        ">>" => a.wrapping_shr(b as u32),
+        "+" => a.wrapping_add(b),
+        "-" => a.wrapping_sub(b),
+        "*" => a.wrapping_mul(b),
+        "/" => a.checked_div(b).ok_or_else(division_error)?,
+        _ => a.checked_rem(b).ok_or_else(division_error)?,
+    })
+}
+
+fn division_error() -> String {
+    "División por cero o desbordada".to_string()
+}
+
+fn evaluate(text: &str, context: &Context) -> Result<Option<Value>, String> {
+    let tokens = tokenize(text)?;
+    if tokens.is_empty() {
+        return Err("Falta una expresión".to_string());
+    }
+    let mut parser = Parser { tokens: &tokens, pos: 0, context };
+    let value = parser.binary(0)?;
+    if parser.pos < tokens.len() {
+        return Err(format!("Expresión no válida: {}", text.trim()));
+    }
+    Ok(value)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ANÁLISIS DE LÍNEAS
+// ═══════════════════════════════════════════════════════════════
+
+/// Para cada carácter, si está fuera de comillas. El apóstrofo de AF' no
+/// abre una cadena
+fn outside_quotes(text: &str) -> Vec<bool> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut quote: Option<char> = None;
+    let mut outside = Vec::with_capacity(chars.len());
+    for (i, &c) in chars.iter().enumerate() {
+        match quote {
+            Some(q) => {
+                outside.push(false);
+                if c == q {
+                    quote = None;
+                }
+            }
+            None => {
+                let after_af = i >= 2
+                    && chars[i - 2..i].iter().collect::<String>().eq_ignore_ascii_case("AF")
+                    && (i == 2 || !chars[i - 3].is_alphanumeric());
+                if (c == '\'' && !after_af) || c == '"' {
+                    quote = Some(c);
+                    outside.push(false);
+                } else {
+                    outside.push(true);
+                }
+            }
+        }
+    }
+    outside
+}
+
+fn strip_comment(text: &str) -> &str {
+    let outside = outside_quotes(text);
+    match text.char_indices().zip(outside).find(|&((_, c), out)| out && c == ';') {
+        Some(((at, _), _)) => &text[..at],
+        None => text,
+    }
+}
+
+/// Separar por comas que no estén entre comillas ni paréntesis
+fn split_args(text: &str) -> Vec<&str> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    let outside = outside_quotes(text);
+    let mut args = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for ((at, c), out) in text.char_indices().zip(outside) {
+        match c {
+            '(' if out => depth += 1,
+            ')' if out => depth -= 1,
+            ',' if out && depth == 0 => {
+                args.push(text[start..at].trim());
+                start = at + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(text[start..].trim());
+    args
+}
+
+/// Cadena entre comillas como única expresión
+fn string_literal(text: &str) -> Option<&str> {
+    let quote = text.chars().next().filter(|&c| c == '"' || c == '\'')?;
+    let inner = text[1..].strip_suffix(quote)?;
+    (!inner.contains(quote)).then_some(inner)
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '@' | '?')
+}
+
+/// Sustituir los parámetros de una macro por los argumentos; \@ da un
+/// número distinto en cada expansión para las etiquetas internas
+fn substitute(line: &str, params: &[String], args: &[&str], unique: usize) -> String {
+    let line = line.replace("\\@", &unique.to_string());
+    let outside = outside_quotes(&line);
+    let mut out = String::with_capacity(line.len());
+    let mut word = String::new();
+    let flush = |word: &mut String, out: &mut String| {
+        match params.iter().position(|p| p.eq_ignore_ascii_case(word)) {
+            Some(i) => out.push_str(args.get(i).copied().unwrap_or("")),
+            None => out.push_str(word),
+        }
+        word.clear();
+    };
+    for (c, out_of_quotes) in line.chars().zip(outside) {
+        if out_of_quotes && is_identifier_char(c) {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+// ═══════════════════════════════════════════════════════════════
+// PASADAS
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SymbolClass {
+    Label,
+    Equ,
+    /// DEFL o "=": se puede redefinir
+    Set,
+    Predefined,
+    /// EXTERN: lo resuelve el enlazador
+    Extern,
+}
+
+#[derive(Clone, Debug)]
+struct AsmSymbol {
+    name: String,
+    value: Option<Value>,
+    pending: Option<Pending>,
+    class: SymbolClass,
+    /// Pasada en que se definió (0 los predefinidos)
+    pass: u8,
+}
+
+/// EQU que en la primera pasada dependía de símbolos aún sin valor:
+/// expresión y dónde se definió, para evaluarla igual más tarde
+#[derive(Clone, Debug)]
+struct Pending {
+    expression: String,
+    scope: String,
+    address: u16,
+    section: Option<u16>,
+}
+
+/// Sección de un módulo reubicable; la activa tiene sus bytes en la
+/// memoria de la pasada y aquí quedan vacíos
+struct SectionState {
+    name: String,
+    address: u32,
+    memory: Vec<u8>,
+    written: Vec<bool>,
+}
+
+impl SectionState {
+    fn new(name: &str) -> SectionState {
+        SectionState { name: name.to_string(), address: 0, memory: vec![0; 0x10000], written: vec![false; 0x10000] }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Macro {
+    params: Vec<String>,
+    body: Vec<String>,
+}
+
+/// Bloque IF: si se ensambla, si ya se tomó una rama y si el bloque que
+/// lo contiene estaba activo
+struct Conditional {
+    active: bool,
+    taken: bool,
+    outer: bool,
+}
+
+struct Recording {
+    name: String,
+    params: Vec<String>,
+    body: Vec<String>,
+    nesting: usize,
+}
+
+struct Pass<'a> {
+    assembler: &'a Assembler,
+    pass: u8,
+    symbols: HashMap<String, AsmSymbol>,
+    address: u32,
+    memory: Vec<u8>,
+    written: Vec<bool>,
+    macros: HashMap<String, Macro>,
+    conditionals: Vec<Conditional>,
+    recording: Option<Recording>,
+    scope: String,
+    expansions: usize,
+    entry: Option<Value>,
+    ended: bool,
+    file: String,
+    line: usize,
+    /// Bytes generados por la línea en curso y si son una instrucción
+    current: Vec<u8>,
+    is_instruction: bool,
+    lines: Vec<AsmLine>,
+    errors: Vec<AsmError>,
+    /// En un módulo reubicable: secciones (la activa en `section`),
+    /// externos en orden de declaración, PUBLIC con su fichero y línea, y
+    /// las posiciones que completará el enlazador
+    sections: Vec<SectionState>,
+    section: Option<u16>,
+    externs: Vec<String>,
+    publics: Vec<(String, String, usize)>,
+    relocations: Vec<Relocation>,
+}
+
+impl<'a> Pass<'a> {
+    fn new(assembler: &'a Assembler, pass: u8, mut symbols: HashMap<String, AsmSymbol>, relocatable: bool) -> Pass<'a> {
+        for (name, value) in &assembler.predefined {
+            symbols.entry(name.to_ascii_uppercase()).or_insert(AsmSymbol {
+                name: name.clone(),
+                value: Some(Value::absolute(*value)),
+                pending: None,
+                class: SymbolClass::Predefined,
+                pass: 0,
+            });
+        }
+        let (address, section, sections) = match relocatable {
+            true => (0, Some(0), vec![SectionState { memory: Vec::new(), written: Vec::new(), ..SectionState::new(DEFAULT_SECTION) }]),
+            false => (assembler.format.default_origin() as u32, None, Vec::new()),
+        };
+        Pass {
+            assembler,
+            pass,
+            symbols,
+            address,
+            memory: vec![0; 0x10000],
+            written: vec![false; 0x10000],
+            macros: HashMap::new(),
+            conditionals: Vec::new(),
+            recording: None,
+            scope: String::new(),
+            expansions: 0,
+            entry: None,
+            ended: false,
+            file: String::new(),
+            line: 0,
+            current: Vec::new(),
+            is_instruction: false,
+            lines: Vec::new(),
+            errors: Vec::new(),
+            sections,
+            section,
+            externs: Vec::new(),
+            publics: Vec::new(),
+            relocations: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, file: &str, source: &str) {
+        self.source(file, source, 0);
+        if self.recording.is_some() {
+            self.error("Falta ENDM".to_string());
+        }
+        if !self.conditionals.is_empty() {
+            self.error("Falta ENDIF".to_string());
+        }
+    }
+
+    fn source(&mut self, file: &str, source: &str, depth: usize) {
+        for (number, text) in source.lines().enumerate() {
+            if self.ended {
+                return;
+            }
+            self.file = file.to_string();
+            self.line = number + 1;
+            self.statement(text, depth);
+        }
+    }
+
+    fn error(&mut self, message: String) {
+        self.errors.push(AsmError { file: self.file.clone(), line: self.line, message });
+    }
+
+    fn active(&self) -> bool {
+        self.conditionals.last().is_none_or(|c| c.active)
+    }
+
+    fn is_keyword(&self, word: &str) -> bool {
+        let upper = word.to_ascii_uppercase();
+        let directive = upper.strip_prefix('.').unwrap_or(&upper);
+        DIRECTIVES.contains(&directive) || encodings().contains_key(upper.as_str()) || self.macros.contains_key(&upper)
+    }
+
+    /// Etiqueta, operación y argumentos. Una etiqueta lleva ":" o empieza
+    /// en la primera columna; sin columna también antes de EQU, = o MACRO
+    fn split<'t>(&self, code: &'t str) -> (Option<&'t str>, String, &'t str) {
+        let first_column = code.starts_with(|c: char| !c.is_whitespace());
+        let text = code.trim();
+        let end = text.find(|c: char| c.is_whitespace() || c == ':' || c == '=').unwrap_or(text.len());
+        let (word, after) = text.split_at(end);
+        let mut label = None;
+        let mut rest = text;
+        if let Some(after_colon) = after.strip_prefix(':') {
+            label = Some(word);
+            rest = after_colon.trim_start();
+        } else if !word.is_empty() {
+            let next = after.trim_start();
+            let next_word = next.split(|c: char| c.is_whitespace()).next().unwrap_or("").to_ascii_uppercase();
+            let defines = next.starts_with('=') || ["EQU", "DEFL", "MACRO"].contains(&next_word.as_str());
+            if (first_column && !self.is_keyword(word)) || defines {
+                label = Some(word);
+                rest = next;
+            }
+        }
+        let (op, args) = match rest.strip_prefix('=') {
+            Some(args) => ("=", args),
+            None => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+        };
+        let op = op.to_ascii_uppercase();
+        let op = match op.strip_prefix('.') {
+            Some(directive) if DIRECTIVES.contains(&directive) => directive.to_string(),
+            _ => op,
+        };
+        (label, op, args.trim())
+    }
+
+    fn statement(&mut self, text: &str, depth: usize) {
+        let code = strip_comment(text);
+        if self.record(code) {
+            return;
+        }
+        // La línea va antes que las que genere (macros, INCLUDE)
+        let index = self.lines.len();
+        if self.pass == 2 {
+            let (file, line, address) = (self.file.clone(), self.line, self.address as u16);
+            let source = text.to_string();
+            self.lines.push(AsmLine { file, line, address, bytes: Vec::new(), source, instruction: false });
+        }
+        let outer = (std::mem::take(&mut self.current), std::mem::replace(&mut self.is_instruction, false));
+        self.execute(code, depth);
+        let bytes = std::mem::replace(&mut self.current, outer.0);
+        let instruction = std::mem::replace(&mut self.is_instruction, outer.1);
+        if let Some(line) = self.lines.get_mut(index).filter(|_| self.pass == 2) {
+            line.bytes = bytes;
+            line.instruction = instruction;
+        }
+    }
+
+    /// Guardar las líneas del cuerpo de una macro hasta su ENDM
+    fn record(&mut self, code: &str) -> bool {
+        let (_, op, _) = self.split(code);
+        let Some(recording) = self.recording.as_mut() else {
+            return false;
+        };
+        match op.as_str() {
+            "MACRO" => recording.nesting += 1,
+            "ENDM" if recording.nesting == 0 => {
+                let recording = self.recording.take().expect("macro en curso");
+                let definition = Macro { params: recording.params, body: recording.body };
+                self.macros.insert(recording.name.to_ascii_uppercase(), definition);
+                return true;
+            }
+            "ENDM" => recording.nesting -= 1,
+            _ => {}
+        }
+        recording.body.push(code.to_string());
+        true
+    }
+
+    fn execute(&mut self, code: &str, depth: usize) {
+        let (label, op, args) = self.split(code);
+        if self.conditional(&op, args) || !self.active() {
+            return;
+        }
+        match op.as_str() {
+            "MACRO" => {
+                let mut params: Vec<String> = split_args(args).iter().map(|p| p.to_string()).collect();
+                let name = match label {
+                    Some(name) => name.to_string(),
+                    None if !params.is_empty() => {
+                        // MACRO nombre p1, p2: el nombre va pegado al primer parámetro
+                        let first = params.remove(0);
+                        let (name, param) = first.split_once(char::is_whitespace).unwrap_or((&first, ""));
+                        if !param.trim().is_empty() {
+                            params.insert(0, param.trim().to_string());
+                        }
+                        name.to_string()
+                    }
+                    None => return self.error("MACRO sin nombre".to_string()),
+                };
+                self.recording = Some(Recording { name, params, body: Vec::new(), nesting: 0 });
+                return;
+            }
+            "EQU" | "=" | "DEFL" => {
+                let Some(name) = label else {
+                    return self.error(format!("{} sin nombre", op));
+                };
+                let class = if op == "EQU" { SymbolClass::Equ } else { SymbolClass::Set };
+                return self.define_value(name, args, class);
+            }
+            _ => {}
+        }
+        if let Some(name) = label {
+            self.define(name, Some(self.here()), None, SymbolClass::Label);
+            if !name.starts_with('.') {
+                self.scope = name.to_string();
+            }
+        }
+        match op.as_str() {
+            "" => {}
+            "ORG" if self.section.is_some() => {
+                self.error("ORG no se usa en un módulo reubicable: el guion del enlazador coloca las secciones".to_string())
+            }
+            "ORG" => {
+                if let Some(address) = self.known(args, "ORG") {
+                    match u16::try_from(address) {
+                        Ok(address) => self.address = address as u32,
+                        Err(_) => self.error(format!("ORG fuera de 64 KB: {}", address)),
+                    }
+                }
+            }
+            "DB" | "DEFB" | "BYTE" | "DM" | "DEFM" => {
+                for arg in split_args(args) {
+                    match string_literal(arg) {
+                        Some(text) => self.emit(text.as_bytes()),
+                        None => {
+                            let value = self.operand(arg);
+                            let byte = self.byte(value, 0);
+                            self.emit(&[byte]);
+                        }
+                    }
+                }
+            }
+            "DW" | "DEFW" | "WORD" => {
+                for arg in split_args(args) {
+                    let value = self.operand(arg);
+                    let word = self.word(value, 0);
+                    self.emit(&word.to_le_bytes());
+                }
+            }
+            "DS" | "DEFS" | "BLOCK" => self.reserve(&split_args(args)),
+            "INCBIN" => self.incbin(&split_args(args)),
+            "INCLUDE" => {
+                let name = string_literal(args).unwrap_or(args);
+                if depth >= MAX_DEPTH {
+                    return self.error("Demasiados INCLUDE anidados".to_string());
+                }
+                match self.assembler.read(name) {
+                    Ok(data) => {
+                        let (file, line) = (self.file.clone(), self.line);
+                        self.source(name, &String::from_utf8_lossy(&data), depth + 1);
+                        self.file = file;
+                        self.line = line;
+                    }
+                    Err(e) => self.error(e),
+                }
+            }
+            "END" => {
+                if !args.is_empty() {
+                    self.entry = self.operand(args);
+                }
+                self.ended = true;
+            }
+            "SECTION" | "EXTERN" | "EXTRN" if self.section.is_none() => {
+                self.error(format!("{} solo se usa en módulos reubicables", op))
+            }
+            // Sin enlazador no hay nada que exportar
+            "PUBLIC" | "GLOBAL" if self.section.is_none() => {}
+            "SECTION" if args.is_empty() => self.error("SECTION sin nombre".to_string()),
+            "SECTION" => self.enter_section(args),
+            "PUBLIC" | "GLOBAL" => {
+                for name in split_args(args) {
+                    self.publics.push((name.to_string(), self.file.clone(), self.line));
+                }
+            }
+            "EXTERN" | "EXTRN" => {
+                for name in split_args(args) {
+                    let target = RelocTarget::Extern(self.externs.len() as u16);
+                    let value = Value { number: 0, reloc: Some((target, RelocPart::Word)) };
+                    self.define(name, Some(value), None, SymbolClass::Extern);
+                    self.externs.push(name.to_string());
+                }
+            }
+            "ENDM" => self.error("ENDM sin MACRO".to_string()),
+            _ if self.macros.contains_key(&op) => self.expand(&op, args, depth),
+            _ => self.instruction(&op, args),
+        }
+    }
+
+    /// IF, IFDEF, IFNDEF, ELSE y ENDIF se interpretan aunque el bloque
+    /// esté desactivado, para llevar la cuenta del anidamiento
+    fn conditional(&mut self, op: &str, args: &str) -> bool {
+        match op {
+            "IF" | "IFDEF" | "IFNDEF" => {
+                let outer = self.active();
+                let condition = outer
+                    && match op {
+                        "IF" => self.known(args, "IF").is_some_and(|v| v != 0),
+                        _ => self.is_defined(args) == (op == "IFDEF"),
+                    };
+                self.conditionals.push(Conditional { active: condition, taken: condition, outer });
+            }
+            "ELSE" => match self.conditionals.last_mut() {
+                Some(block) => {
+                    block.active = block.outer && !block.taken;
+                    block.taken = true;
+                }
+                None => self.error("ELSE sin IF".to_string()),
+            },
+            "ENDIF" => {
+                if self.conditionals.pop().is_none() {
+                    self.error("ENDIF sin IF".to_string());
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Definido en esta pasada antes de la línea actual, o predefinido
+    fn is_defined(&self, name: &str) -> bool {
+        self.symbols
+            .get(&symbol_key(name.trim(), &self.scope))
+            .is_some_and(|s| s.pass == self.pass || s.class == SymbolClass::Predefined)
+    }
+
+    // ── Símbolos ────────────────────────────────────────────────
+
+    fn lookup(&self, key: &str, depth: usize) -> Option<Value> {
+        let symbol = self.symbols.get(key)?;
+        if symbol.value.is_some() || depth >= MAX_DEPTH {
+            return symbol.value;
+        }
+        let pending = symbol.pending.as_ref()?;
+        let lookup = |key: &str| self.lookup(key, depth + 1);
+        let context = Context {
+            address: pending.address,
+            section: pending.section,
+            scope: &pending.scope,
+            strict: false,
+            lookup: &lookup,
+        };
+        evaluate(&pending.expression, &context).ok().flatten()
+    }
+
+    fn evaluate(&self, text: &str) -> Result<Option<Value>, String> {
+        let lookup = |key: &str| self.lookup(key, 0);
+        let context = Context {
+            address: self.address as u16,
+            section: self.section,
+            scope: &self.scope,
+            strict: self.pass == 2,
+            lookup: &lookup,
+        };
+        evaluate(text, &context)
+    }
+
+    /// Dirección actual, relativa a la sección en un módulo reubicable
+    fn here(&self) -> Value {
+        Value {
+            number: self.address as i64,
+            reloc: self.section.map(|s| (RelocTarget::Section(s), RelocPart::Word)),
+        }
+    }
+
+    /// Valor de una expresión; sin valor si aún no se conoce (primera
+    /// pasada) o si hay un error, que queda anotado
+    fn operand(&mut self, text: &str) -> Option<Value> {
+        self.evaluate(text).unwrap_or_else(|e| {
+            self.error(e);
+            None
+        })
+    }
+
+    /// Como `operand`, pero el valor no puede depender del enlazador
+    fn value(&mut self, text: &str) -> Option<i64> {
+        let value = self.operand(text)?;
+        if value.reloc.is_some() {
+            self.error(format!("Se necesita un valor absoluto: {}", text.trim()));
+            return None;
+        }
+        Some(value.number)
+    }
+
+    /// Valor absoluto que ya debe conocerse en la primera pasada
+    fn known(&mut self, text: &str, directive: &str) -> Option<i64> {
+        match self.evaluate(text) {
+            Ok(None) => {
+                self.error(format!("{} necesita un valor conocido: {}", directive, text.trim()));
+                None
+            }
+            Ok(Some(value)) if value.reloc.is_some() => {
+                self.error(format!("{} necesita un valor absoluto: {}", directive, text.trim()));
+                None
+            }
+            Ok(value) => value.map(|v| v.number),
+            Err(e) => {
+                self.error(e);
+                None
+            }
+        }
+    }
+
+    fn define(&mut self, name: &str, value: Option<Value>, pending: Option<Pending>, class: SymbolClass) {
+        let full = qualify(name, &self.scope);
+        let key = full.to_ascii_uppercase();
+        if let Some(previous) = self.symbols.get(&key) {
+            let redefinable = class == SymbolClass::Set || previous.class == SymbolClass::Predefined;
+            if previous.pass == self.pass && !redefinable {
+                return self.error(format!("Símbolo duplicado: {}", full));
+            }
+            if self.pass == 2 && class == SymbolClass::Label && previous.value != value {
+                self.error(format!("Error de fase en {}: la dirección cambió entre pasadas", full));
+            }
+        }
+        self.symbols.insert(key, AsmSymbol { name: full, value, pending, class, pass: self.pass });
+    }
+
+    fn define_value(&mut self, name: &str, expression: &str, class: SymbolClass) {
+        let value = self.operand(expression);
+        let pending = match value {
+            None if self.pass == 1 => Some(Pending {
+                expression: expression.to_string(),
+                scope: self.scope.clone(),
+                address: self.address as u16,
+                section: self.section,
+            }),
+            _ => None,
+        };
+        self.define(name, value, pending, class);
+    }
+
+    // ── Emisión ─────────────────────────────────────────────────
+
+    fn emit(&mut self, bytes: &[u8]) {
+        let address = self.address as usize;
+        if address + bytes.len() > 0x10000 {
+            self.address = 0x10000;
+            return self.error("El código pasa de FFFFh".to_string());
+        }
+        if self.pass == 2 {
+            if let Some(i) = (address..address + bytes.len()).find(|&a| self.written[a]) {
+                self.error(format!("Se escribe dos veces en {}", hex16(i as u16)));
+            }
+            self.memory[address..address + bytes.len()].copy_from_slice(bytes);
+            self.written[address..address + bytes.len()].fill(true);
+            self.current.extend_from_slice(bytes);
+        }
+        self.address += bytes.len() as u32;
+    }
+
+    /// Byte `at` posiciones detrás de la dirección actual; si es
+    /// reubicable se anota para el enlazador
+    fn byte(&mut self, value: Option<Value>, at: usize) -> u8 {
+        let value = value.unwrap_or(Value::absolute(0));
+        match value.reloc {
+            None if !(-128..=255).contains(&value.number) => {
+                self.error(format!("Valor fuera de 8 bits: {}", value.number))
+            }
+            None => {}
+            Some((_, RelocPart::Word | RelocPart::Call)) => {
+                self.error("Una dirección reubicable no cabe en un byte: usa LOW(), HIGH() o BANK()".to_string())
+            }
+            Some((target, part)) => {
+                self.relocate(at, target, part, value.number);
+                if part == RelocPart::High {
+                    return (value.number >> 8) as u8;
+                }
+            }
+        }
+        value.number as u8
+    }
+
+    fn word(&mut self, value: Option<Value>, at: usize) -> u16 {
+        let value = value.unwrap_or(Value::absolute(0));
+        match value.reloc {
+            None if !(-32768..=65535).contains(&value.number) => {
+                self.error(format!("Valor fuera de 16 bits: {}", value.number))
+            }
+            None => {}
+            Some((target, part @ (RelocPart::Word | RelocPart::Call))) => self.relocate(at, target, part, value.number),
+            Some(_) => self.error("LOW(), HIGH() y BANK() dan un byte, no una palabra".to_string()),
+        }
+        value.number as u16
+    }
+
+    fn relocate(&mut self, at: usize, target: RelocTarget, part: RelocPart, addend: i64) {
+        if let (2, Some(section)) = (self.pass, self.section) {
+            let offset = (self.address as usize + at) as u16;
+            self.relocations.push(Relocation { section, offset, part, target, addend: addend as u16 });
+        }
+    }
+
+    /// Dejar los bytes y la dirección de la sección activa en su estado
+    fn park_section(&mut self, index: usize) {
+        let saved = &mut self.sections[index];
+        saved.address = self.address;
+        std::mem::swap(&mut saved.memory, &mut self.memory);
+        std::mem::swap(&mut saved.written, &mut self.written);
+    }
+
+    /// Guardar la sección activa y pasar a otra, que se crea si no existe
+    fn enter_section(&mut self, name: &str) {
+        let current = self.section.expect("módulo reubicable") as usize;
+        let index = match self.sections.iter().position(|s| s.name.eq_ignore_ascii_case(name)) {
+            Some(index) => index,
+            None => {
+                self.sections.push(SectionState::new(name));
+                self.sections.len() - 1
+            }
+        };
+        self.park_section(current);
+        let next = &mut self.sections[index];
+        self.address = next.address;
+        std::mem::swap(&mut next.memory, &mut self.memory);
+        std::mem::swap(&mut next.written, &mut self.written);
+        self.section = Some(index as u16);
+    }
+
+    /// DS n[,valor]: sin valor solo reserva, para no llenar de ceros las
+    /// variables en RAM
+    fn reserve(&mut self, args: &[&str]) {
+        let Some(count) = args.first().and_then(|count| self.known(count, "DS")) else {
+            return;
+        };
+        if !(0..=0x10000).contains(&count) {
+            return self.error(format!("Tamaño de DS no válido: {}", count));
+        }
+        match args.get(1) {
+            Some(fill) => {
+                let value = self.value(fill).map(Value::absolute);
+                let fill = self.byte(value, 0);
+                self.emit(&vec![fill; count as usize]);
+            }
+            None if self.address as i64 + count > 0x10000 => self.error("El código pasa de FFFFh".to_string()),
+            None => self.address += count as u32,
+        }
+    }
+
+    /// INCBIN "fichero"[,desde[,longitud]]
+    fn incbin(&mut self, args: &[&str]) {
+        let Some(name) = args.first().map(|arg| string_literal(arg).unwrap_or(arg)) else {
+            return self.error("INCBIN sin fichero".to_string());
+        };
+        let data = match self.assembler.read(name) {
+            Ok(data) => data,
+            Err(e) => return self.error(e),
+        };
+        let skip = args.get(1).map_or(0, |skip| self.known(skip, "INCBIN").unwrap_or(0));
+        let length = args.get(2).map(|length| self.known(length, "INCBIN").unwrap_or(0));
+        if skip < 0 || length.is_some_and(|length| length < 0) {
+            return self.error("INCBIN con desplazamiento o longitud negativos".to_string());
+        }
+        let skip = usize::try_from(skip).unwrap_or(usize::MAX);
+        let length = match length {
+            Some(length) => usize::try_from(length).unwrap_or(usize::MAX),
+            None => data.len().saturating_sub(skip),
+        };
+        match skip.checked_add(length).and_then(|end| data.get(skip..end)) {
+            Some(bytes) => self.emit(bytes),
+            None => self.error(format!("{} tiene {} bytes: no llega a {}", name, data.len(), skip.saturating_add(length))),
+        }
+    }
+
+    fn expand(&mut self, name: &str, args: &str, depth: usize) {
+        if depth >= MAX_DEPTH {
+            return self.error(format!("Demasiadas macros anidadas en {}", name));
+        }
+        let definition = self.macros[name].clone();
+        let args = split_args(args);
+        self.expansions += 1;
+        for line in &definition.body {
+            let text = substitute(line, &definition.params, &args, self.expansions);
+            self.statement(&text, depth + 1);
+            if self.ended {
+                return;
+            }
+        }
+    }
+
+    // ── Instrucciones ───────────────────────────────────────────
+
+    fn instruction(&mut self, mnemonic: &str, args: &str) {
+        let Some(encodings) = encodings().get(mnemonic) else {
+            return self.error(format!("Instrucción desconocida: {}", mnemonic));
+        };
+        let mut parsed: Vec<Arg> = split_args(args).into_iter().map(Arg::parse).collect();
+        // Formas alternativas: "SUB A,B" por "SUB B" y "ADD B" por "ADD A,B"
+        let mut found = self.select(encodings, &parsed);
+        if found.is_none() && ["SUB", "AND", "XOR", "OR", "CP"].contains(&mnemonic) && parsed.len() == 2 && parsed[0].is_register("A") {
+            parsed.remove(0);
+            found = self.select(encodings, &parsed);
+        }
+        if found.is_none() && ["ADD", "ADC", "SBC"].contains(&mnemonic) && parsed.len() == 1 {
+            parsed.insert(0, Arg::Register("A".to_string()));
+            found = self.select(encodings, &parsed);
+        }
+        let Some(encoding) = found else {
+            return self.error(format!("Operandos no válidos para {}: {}", mnemonic, args));
+        };
+        let bytes = self.encode(encoding, &parsed);
+        self.emit(&bytes);
+        self.is_instruction = true;
+    }
+
+    fn select<'e>(&self, encodings: &'e [Encoding], args: &[Arg]) -> Option<&'e Encoding> {
+        encodings.iter().find(|encoding| {
+            encoding.patterns.len() == args.len()
+                && encoding.patterns.iter().zip(args).all(|(pattern, arg)| self.matches(*pattern, arg))
+        })
+    }
+
+    fn matches(&self, pattern: Pattern, arg: &Arg) -> bool {
+        match (pattern, arg) {
+            (Pattern::Register(p), Arg::Register(a)) | (Pattern::Indirect(p), Arg::Indirect(a)) => p == a,
+            // (IX) es (IX+0)
+            (Pattern::Indexed(p), Arg::Indexed(a, _) | Arg::Indirect(a)) => p == a,
+            // En la primera pasada un valor aún desconocido vale para
+            // cualquier número: todas las variantes miden lo mismo
+            (Pattern::Number(n), Arg::Value(text)) => match self.evaluate(text) {
+                Ok(Some(value)) => value.reloc.is_none() && value.number == n as i64,
+                Ok(None) => self.pass == 1,
+                Err(_) => false,
+            },
+            (Pattern::Byte | Pattern::Word | Pattern::Relative | Pattern::Absolute, Arg::Value(_)) => true,
+            (Pattern::Memory | Pattern::Port, Arg::Memory(_)) => true,
+            _ => false,
+        }
+    }
+
+    fn encode(&mut self, encoding: &Encoding, args: &[Arg]) -> Vec<u8> {
+        let mut fields = Vec::new();
+        for (pattern, arg) in encoding.patterns.iter().zip(args) {
+            // Posición del campo en la instrucción, para las reubicaciones
+            let at = encoding.opcode.len() + fields.len();
+            match (pattern, arg) {
+                (Pattern::Indexed(_), Arg::Indexed(_, text)) => {
+                    let d = self.value(text).unwrap_or(0);
+                    if !(-128..=127).contains(&d) {
+                        self.error(format!("Desplazamiento fuera de rango: {}", d));
+                    }
+                    fields.push(d as u8);
+                }
+                (Pattern::Indexed(_), _) => fields.push(0),
+                (Pattern::Byte, Arg::Value(text)) | (Pattern::Port, Arg::Memory(text)) => {
+                    let value = self.operand(text);
+                    fields.push(self.byte(value, at));
+                }
+                (Pattern::Word, Arg::Value(text)) | (Pattern::Memory, Arg::Memory(text)) => {
+                    let value = self.operand(text);
+                    fields.extend(self.word(value, at).to_le_bytes());
+                }
+                (Pattern::Absolute, Arg::Value(text)) => {
+                    let value = self.operand(text).map(Value::as_call);
+                    fields.extend(self.word(value, at).to_le_bytes());
+                }
+                (Pattern::Relative, Arg::Value(text)) => {
+                    let next = Value { number: self.address as i64 + encoding.len() as i64, ..self.here() };
+                    let offset = match self.operand(text).map(|target| Value::combine("-", target, next)) {
+                        Some(Ok(Value { number, reloc: None })) => number,
+                        Some(_) => {
+                            self.error(format!("Salto relativo a otra sección o a un externo: {}", text));
+                            0
+                        }
+                        None => 0,
+                    };
+                    if !(-128..=127).contains(&offset) {
+                        self.error(format!("Salto relativo fuera de alcance: {} bytes", offset));
+                    }
+                    fields.push(offset as u8);
+                }
+                _ => {}
+            }
+        }
+        match encoding.opcode[..] {
+            [prefix, 0xCB, op] => vec![prefix, 0xCB, fields[0], op],
+            _ => [&encoding.opcode[..], &fields[..]].concat(),
+        }
+    }
+
+    fn finish(self) -> Assembly {
+        let first = self.written.iter().position(|&w| w);
+        let last = self.written.iter().rposition(|&w| w);
+        let (origin, code) = match (first, last) {
+            (Some(first), Some(last)) => (first as u16, self.memory[first..=last].to_vec()),
+            _ => (self.assembler.format.default_origin(), Vec::new()),
+        };
+        let entry = self
+            .entry
+            .or_else(|| self.lookup("START", 0).filter(|_| self.symbols["START"].class != SymbolClass::Predefined))
+            .map(|v| v.number as u16)
+            .unwrap_or(origin);
+        let symbols = self
+            .symbols
+            .values()
+            .filter(|s| s.class != SymbolClass::Predefined)
+            .filter_map(|s| Some((s.name.clone(), s.value?.number as u16)))
+            .collect();
+        Assembly { origin, code, entry, symbols, lines: self.lines }
+    }
+
+    fn finish_object(mut self, module: &str) -> Result<ObjectFile, Vec<AsmError>> {
+        self.park_section(self.section.expect("módulo reubicable") as usize);
+        let mut object = ObjectFile { module: module.to_string(), ..ObjectFile::default() };
+        for state in &self.sections {
+            let Ok(size) = u16::try_from(state.address) else {
+                self.errors.push(AsmError::new(&format!("La sección {} ocupa 64 KB", state.name)));
+                continue;
+            };
+            let used = state.written.iter().rposition(|&w| w).map_or(0, |last| last + 1);
+            object.sections.push(ObjectSection { name: state.name.clone(), size, data: state.memory[..used].to_vec() });
+        }
+        object.externs = self.externs.clone();
+
+        let publics: Vec<String> = self.publics.iter().map(|(name, _, _)| name.to_ascii_uppercase()).collect();
+        for (name, file, line) in &self.publics {
+            let message = match self.symbols.get(&name.to_ascii_uppercase()) {
+                None => format!("PUBLIC de un símbolo no definido: {}", name),
+                Some(symbol) if symbol.class == SymbolClass::Extern => format!("{} es externo: no puede ser PUBLIC", name),
+                Some(symbol) if symbol.value.and_then(location).is_none() => {
+                    format!("{} depende de un externo o de LOW/HIGH/BANK: no se puede exportar", name)
+                }
+                Some(_) => continue,
+            };
+            self.errors.push(AsmError { file: file.clone(), line: *line, message });
+        }
+        let mut symbols: Vec<ObjectSymbol> = self
+            .symbols
+            .iter()
+            .filter(|(_, s)| !matches!(s.class, SymbolClass::Predefined | SymbolClass::Extern))
+            .filter_map(|(key, s)| {
+                let location = location(s.value?)?;
+                Some(ObjectSymbol { name: s.name.clone(), public: publics.contains(key), location })
+            })
+            .collect();
+        symbols.sort_by(|a, b| a.name.cmp(&b.name));
+        object.symbols = symbols;
+        object.relocations = std::mem::take(&mut self.relocations);
+        object.entry = match self.entry {
+            Some(value) => match location(value) {
+                Some(location) => Some(location),
+                None => {
+                    self.errors.push(AsmError::new("La dirección de END no puede ser externa"));
+                    None
+                }
+            },
+            None => None,
+        };
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+        Ok(object)
+    }
+}
+
+/// Ubicación en el fichero objeto de un valor: absoluto o dentro de una
+/// de las secciones del módulo
+fn location(value: Value) -> Option<Location> {
+    match value.reloc {
+        None => Some(Location::Absolute(value.number as u16)),
+        Some((RelocTarget::Section(section), RelocPart::Word)) => Some(Location::Section(section, value.number as u16)),
+        Some(_) => None,
+    }
+}
@@ -0,0 +1,63 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  CABECERA BLOAD/BSAVE                                          ║
+//! ║  FEh + inicio + fin + ejecución (little-endian, fin incluido)  ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+/// Byte identificador de los ficheros BSAVE en disco
+pub const BLOAD_ID: u8 = 0xFE;
+
+/// Tamaño de la cabecera completa en disco (con el identificador)
+pub const BLOAD_HEADER_SIZE: usize = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BloadHeader {
+    pub start: u16,
+    pub end: u16,
+    pub exec: u16,
+}
+
+impl BloadHeader {
+    pub fn new(start: u16, end: u16, exec: u16) -> BloadHeader {
+        BloadHeader { start, end, exec }
+    }
+
+    /// Leer la cabecera de un fichero de disco; devuelve también el cuerpo
+    pub fn parse(data: &[u8]) -> Option<(BloadHeader, &[u8])> {
+        if data.len() < BLOAD_HEADER_SIZE || data[0] != BLOAD_ID {
+            return None;
+        }
+        let header = BloadHeader::parse_raw(&data[1..])?;
+        Some((header, &data[BLOAD_HEADER_SIZE..]))
+    }
+
+    /// Leer las tres direcciones sin identificador (formato de bloque de cinta)
+    pub fn parse_raw(data: &[u8]) -> Option<BloadHeader> {
+        if data.len() < 6 {
+            return None;
+        }
+        let word = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        let header = BloadHeader::new(word(0), word(2), word(4));
+        if header.end < header.start {
+            return None;
+        }
+        Some(header)
+    }
+
+    /// Bytes que ocupa el bloque en memoria (la dirección final está incluida)
+    pub fn length(&self) -> usize {
+        (self.end - self.start) as usize + 1
+    }
+
+    pub fn to_raw_bytes(&self) -> [u8; 6] {
+        let [s0, s1] = self.start.to_le_bytes();
+        let [e0, e1] = self.end.to_le_bytes();
+        let [x0, x1] = self.exec.to_le_bytes();
+        [s0, s1, e0, e1, x0, x1]
+    }
+
+    pub fn to_bytes(&self) -> [u8; BLOAD_HEADER_SIZE] {
+        let mut out = [BLOAD_ID; BLOAD_HEADER_SIZE];
+        out[1..].copy_from_slice(&self.to_raw_bytes());
+        out
+    }
+}
@@ -0,0 +1,106 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  BUS DE E/S DEL MSX2                                           ║
+//! ║  Decodifica los puertos Z80 hacia cada chip del sistema        ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::fdc::DiskController;
+use crate::ppi::Ppi8255;
+use crate::psg::Psg;
+use crate::rtc::Rp5c01;
+use crate::slots::{SlotContent, SlotId, SlotSystem};
+use crate::vdp::V9938;
+
+pub struct MsxBus {
+    pub ppi: Ppi8255,
+    pub psg: Psg,
+    pub rtc: Rp5c01,
+    pub slots: SlotSystem,
+    /// Controlador de disco; sus registros aparecen en el slot de la Disk-ROM
+    pub disk: DiskController,
+    pub vdp: V9938,
+}
+
+impl MsxBus {
+    pub fn new() -> MsxBus {
+        MsxBus {
+            ppi: Ppi8255::default(),
+            psg: Psg::new(),
+            rtc: Rp5c01::default(),
+            slots: SlotSystem::new(),
+            disk: DiskController::new(),
+            vdp: V9938::new(),
+        }
+    }
+
+    /// Lectura de memoria a través del sistema de slots
+    pub fn mem_read(&mut self, address: u16) -> u8 {
+        let primary = self.ppi.primary_slot_register();
+        if let Some(value) = self.slots.read_secondary_register(address, primary) {
+            return value;
+        }
+        let slot = self.slots.resolve(address, primary);
+        match self.slots.content(slot) {
+            SlotContent::DiskRom(_) if DiskController::is_register(address) => self.disk.read(address),
+            content => content.read(address),
+        }
+    }
+
+    /// Lectura sin efectos: los registros del FDC no se tocan y se ve el
+    /// contenido de la Disk-ROM (para el depurador)
+    pub fn peek(&self, address: u16) -> u8 {
+        let primary = self.ppi.primary_slot_register();
+        if let Some(value) = self.slots.read_secondary_register(address, primary) {
+            return value;
+        }
+        self.slots.content(self.slots.resolve(address, primary)).read(address)
+    }
+
+    /// Slot visible en una dirección y banco de la ROM que hay en ella
+    pub fn bank(&self, address: u16) -> (SlotId, Option<u16>) {
+        let slot = self.slots.resolve(address, self.ppi.primary_slot_register());
+        (slot, self.slots.content(slot).bank(address))
+    }
+
+    /// Escritura en memoria a través del sistema de slots
+    pub fn mem_write(&mut self, address: u16, value: u8) {
+        let primary = self.ppi.primary_slot_register();
+        if self.slots.write_secondary_register(address, value, primary) {
+            return;
+        }
+        let slot = self.slots.resolve(address, primary);
+        match self.slots.content_mut(slot) {
+            SlotContent::DiskRom(_) if DiskController::is_register(address) => {
+                self.disk.write(address, value)
+            }
+            content => content.write(address, value),
+        }
+    }
+
+    /// Lectura de un puerto de E/S; los puertos sin dispositivo devuelven 0xFF
+    pub fn io_read(&mut self, port: u8) -> u8 {
+        match port {
+            0x98..=0x9B => self.vdp.read(port),
+            0xA0..=0xA2 => self.psg.read(port),
+            0xA8..=0xAB => self.ppi.read(port),
+            0xB4 | 0xB5 => self.rtc.read(port),
+            _ => 0xFF,
+        }
+    }
+
+    /// Escritura en un puerto de E/S; los puertos sin dispositivo se ignoran
+    pub fn io_write(&mut self, port: u8, value: u8) {
+        match port {
+            0x98..=0x9B => self.vdp.write(port, value),
+            0xA0..=0xA2 => self.psg.write(port, value),
+            0xA8..=0xAB => self.ppi.write(port, value),
+            0xB4 | 0xB5 => self.rtc.write(port, value),
+            _ => {}
+        }
+    }
+}
+
+impl Default for MsxBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,261 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  IMÁGENES DE CINTA .CAS                                        ║
+//! ║  - Bloques separados por la cabecera 1F A6 DE BA CC 13 7D 74   ║
+//! ║  - Ficheros binarios (BLOAD), BASIC (CLOAD) y ASCII (RUN)      ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::bload::BloadHeader;
+
+/// Marca que precede a cada bloque (equivale al tono de sincronía de la cinta)
+pub const CAS_HEADER: [u8; 8] = [0x1F, 0xA6, 0xDE, 0xBA, 0xCC, 0x13, 0x7D, 0x74];
+
+const ID_BINARY: u8 = 0xD0;
+const ID_BASIC: u8 = 0xD3;
+const ID_ASCII: u8 = 0xEA;
+const ASCII_EOF: u8 = 0x1A;
+
+/// Dirección de TXTTAB donde CLOAD deja el programa BASIC
+pub const BASIC_TEXT_START: u16 = 0x8001;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CasFileType {
+    Binary,
+    Basic,
+    Ascii,
+    /// Bloques sin cabecera de fichero (cargadores propios del juego)
+    Custom,
+}
+
+impl CasFileType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CasFileType::Binary => "binary",
+            CasFileType::Basic => "basic",
+            CasFileType::Ascii => "ascii",
+            CasFileType::Custom => "custom",
+        }
+    }
+
+    /// Byte repetido 10 veces al principio del bloque de cabecera
+    pub fn header_id(&self) -> Option<u8> {
+        match self {
+            CasFileType::Binary => Some(ID_BINARY),
+            CasFileType::Basic => Some(ID_BASIC),
+            CasFileType::Ascii => Some(ID_ASCII),
+            CasFileType::Custom => None,
+        }
+    }
+}
+
+/// Bloque de la cinta: lo que va entre dos marcas de sincronía
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CasBlock {
+    /// Posición de la marca dentro del fichero .CAS
+    pub offset: usize,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CasFile {
+    pub file_type: CasFileType,
+    /// Nombre de 6 caracteres sin espacios finales
+    pub name: String,
+    /// Bloques de datos (sin el bloque de cabecera de fichero)
+    pub blocks: Vec<CasBlock>,
+}
+
+impl CasFile {
+    /// Direcciones de inicio, fin y ejecución de un fichero binario
+    pub fn bload_header(&self) -> Option<BloadHeader> {
+        match self.file_type {
+            CasFileType::Binary => BloadHeader::parse_raw(&self.blocks.first()?.data),
+            _ => None,
+        }
+    }
+
+    /// Contenido útil del fichero, sin relleno ni cabeceras
+    pub fn contents(&self) -> Vec<u8> {
+        match self.file_type {
+            CasFileType::Binary => match (self.bload_header(), self.blocks.first()) {
+                (Some(header), Some(block)) => {
+                    let body = &block.data[6..];
+                    body[..header.length().min(body.len())].to_vec()
+                }
+                _ => Vec::new(),
+            },
+            CasFileType::Basic => {
+                let data = self.blocks.first().map(|b| b.data.clone()).unwrap_or_default();
+                // El programa acaba en el enlace nulo (00 00) tras el último 00 de línea
+                match find_basic_end(&data) {
+                    Some(end) => data[..end].to_vec(),
+                    None => data,
+                }
+            }
+            CasFileType::Ascii => {
+                let mut out = Vec::new();
+                for block in &self.blocks {
+                    match block.data.iter().position(|&b| b == ASCII_EOF) {
+                        Some(eof) => {
+                            out.extend_from_slice(&block.data[..eof]);
+                            break;
+                        }
+                        None => out.extend_from_slice(&block.data),
+                    }
+                }
+                out
+            }
+            CasFileType::Custom => self.blocks.iter().flat_map(|b| b.data.clone()).collect(),
+        }
+    }
+
+    /// Orden de BASIC con que se carga el fichero desde cinta
+    pub fn loader_command(&self) -> String {
+        match self.file_type {
+            CasFileType::Binary => format!("BLOAD\"CAS:{}\",R", self.name),
+            CasFileType::Basic => format!("CLOAD\"{}\":RUN", self.name),
+            CasFileType::Ascii => format!("RUN\"CAS:{}\"", self.name),
+            CasFileType::Custom => String::new(),
+        }
+    }
+}
+
+/// Fin de un programa BASIC tokenizado: tres ceros seguidos (fin de línea + enlace nulo)
+fn find_basic_end(data: &[u8]) -> Option<usize> {
+    data.windows(3).position(|w| w == [0, 0, 0]).map(|p| p + 3)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// IMAGEN .CAS
+// ═══════════════════════════════════════════════════════════════
+
+pub struct CasImage {
+    blocks: Vec<CasBlock>,
+    files: Vec<CasFile>,
+}
+
+impl CasImage {
+    /// Partir el fichero en bloques y agruparlos en ficheros
+    pub fn parse(data: &[u8]) -> Result<CasImage, String> {
+        // Las marcas van alineadas a 8 bytes; si no aparece ninguna así, se buscan en cualquier posición
+        let mut offsets = find_headers(data, 8);
+        if offsets.is_empty() {
+            offsets = find_headers(data, 1);
+        }
+        if offsets.is_empty() {
+            return Err("No es una imagen CAS: no se encontró ninguna cabecera de bloque".to_string());
+        }
+
+        let blocks: Vec<CasBlock> = offsets
+            .iter()
+            .enumerate()
+            .map(|(i, &offset)| {
+                let end = offsets.get(i + 1).copied().unwrap_or(data.len());
+                CasBlock {
+                    offset,
+                    data: data[offset + CAS_HEADER.len()..end].to_vec(),
+                }
+            })
+            .collect();
+
+        let files = group_files(&blocks);
+        Ok(CasImage { blocks, files })
+    }
+
+    /// Construir una imagen a partir de bloques (p. ej. decodificados de audio)
+    pub fn from_blocks(block_data: Vec<Vec<u8>>) -> CasImage {
+        let mut blocks = Vec::new();
+        let mut offset = 0;
+        for data in block_data {
+            let len = CAS_HEADER.len() + data.len();
+            blocks.push(CasBlock { offset, data });
+            offset += len.next_multiple_of(8);
+        }
+        let files = group_files(&blocks);
+        CasImage { blocks, files }
+    }
+
+    pub fn blocks(&self) -> &[CasBlock] {
+        &self.blocks
+    }
+
+    pub fn files(&self) -> &[CasFile] {
+        &self.files
+    }
+
+    /// Serializar como .CAS, rellenando con ceros para alinear cada marca a 8 bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for block in &self.blocks {
+            out.resize(out.len().next_multiple_of(8), 0);
+            out.extend_from_slice(&CAS_HEADER);
+            out.extend_from_slice(&block.data);
+        }
+        out
+    }
+}
+
+fn find_headers(data: &[u8], step: usize) -> Vec<usize> {
+    (0..data.len().saturating_sub(CAS_HEADER.len() - 1))
+        .step_by(step)
+        .filter(|&i| data[i..i + CAS_HEADER.len()] == CAS_HEADER)
+        .collect()
+}
+
+/// Tipo de un bloque de cabecera de fichero: 10 bytes iguales + nombre de 6
+pub fn file_header_type(data: &[u8]) -> Option<CasFileType> {
+    if data.len() < 16 || data[..10].iter().any(|&b| b != data[0]) {
+        return None;
+    }
+    match data[0] {
+        ID_BINARY => Some(CasFileType::Binary),
+        ID_BASIC => Some(CasFileType::Basic),
+        ID_ASCII => Some(CasFileType::Ascii),
+        _ => None,
+    }
+}
+
+fn group_files(blocks: &[CasBlock]) -> Vec<CasFile> {
+    let mut files = Vec::new();
+    let mut i = 0;
+    while i < blocks.len() {
+        match file_header_type(&blocks[i].data) {
+            Some(file_type) => {
+                let name = String::from_utf8_lossy(&blocks[i].data[10..16])
+                    .trim_end()
+                    .to_string();
+                let mut data_blocks = Vec::new();
+                i += 1;
+
+                if file_type == CasFileType::Ascii {
+                    // Bloques de 256 bytes hasta el que contiene el EOF
+                    while i < blocks.len() && file_header_type(&blocks[i].data).is_none() {
+                        let has_eof = blocks[i].data.contains(&ASCII_EOF);
+                        data_blocks.push(blocks[i].clone());
+                        i += 1;
+                        if has_eof {
+                            break;
+                        }
+                    }
+                } else if i < blocks.len() && file_header_type(&blocks[i].data).is_none() {
+                    data_blocks.push(blocks[i].clone());
+                    i += 1;
+                }
+
+                files.push(CasFile {
+                    file_type,
+                    name,
+                    blocks: data_blocks,
+                });
+            }
+            None => {
+                files.push(CasFile {
+                    file_type: CasFileType::Custom,
+                    name: String::new(),
+                    blocks: vec![blocks[i].clone()],
+                });
+                i += 1;
+            }
+        }
+    }
+    files
+}
@@ -0,0 +1,232 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  REGISTRO DE CÓDIGO Y DATOS (CDL)                              ║
+//! ║  - Marca cada byte de un cartucho según se usa al ejecutarlo   ║
+//! ║  - Código ejecutado, datos leídos y bytes enviados al VDP      ║
+//! ║  - Por posición en la ROM: cubre todos los bancos del mapper   ║
+//! ║  - Pistas para el desensamblador y el buscador de gráficos     ║
+//! ╚════════════════════════════════════════════════════════════════╝
+//!
+//! Formato del fichero, enteros en little endian:
+//!
+//! ```text
+//! "MSXCDL" 01      firma y versión
+//! u8               slot del cartucho: primario | subslot << 2
+//! u32              tamaño de la ROM
+//! tamaño × u8      una marca por byte de la ROM, en el orden del fichero
+//!                  .rom (el banco n de 8 KB empieza en n × 2000h)
+//!
+//! marca: bit 0  ejecutado (opcode, desplazamiento o dato inmediato)
+//!        bit 1  leído como dato
+//!        bit 2  enviado a la VRAM por el puerto 98h
+//!        bit 3  primer byte de una instrucción
+//!        bit 4  enviado al VDP por el puerto 99h (registros, dirección)
+//!        bits 5-7 a 0
+//! ```
+//!
+//! Un byte cuenta como enviado al VDP si se leyó de la ROM y el mismo
+//! valor sale por el puerto en las instrucciones siguientes (LD A,(HL) /
+//! OUT (98h),A, OUTI, OTIR...). Lo que pasa antes por la RAM, como los
+//! gráficos comprimidos, queda solo como leído
+
+use crate::bus::MsxBus;
+use crate::machine::{Access, AccessKind};
+use crate::mapper::{PAGE_SIZE, PAGES};
+use crate::slots::SlotId;
+use std::collections::VecDeque;
+
+pub const CDL_CODE: u8 = 0x01;
+pub const CDL_DATA: u8 = 0x02;
+pub const CDL_VRAM: u8 = 0x04;
+pub const CDL_OPCODE: u8 = 0x08;
+pub const CDL_VDP: u8 = 0x10;
+
+/// Firma al principio de cada fichero CDL
+pub const CDL_MAGIC: &[u8; 6] = b"MSXCDL";
+const CDL_VERSION: u8 = 1;
+const HEADER_SIZE: usize = 12;
+
+/// Lecturas de la ROM que se recuerdan para casarlas con un OUT
+const RECENT_READS: usize = 16;
+/// Instrucciones como máximo entre la lectura y el OUT
+const RECENT_STEPS: u64 = 8;
+/// Bytes sin marcar que se saltan dentro de una subida a la VRAM
+const UPLOAD_GAP: usize = 16;
+/// Longitud mínima de una subida a la VRAM para proponerla como gráfico
+const MIN_UPLOAD: usize = 8;
+
+/// Marcas de un banco del cartucho
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BankMarks {
+    pub bank: usize,
+    pub code: usize,
+    pub data: usize,
+    pub vram: usize,
+    pub vdp: usize,
+}
+
+/// Marcas de los bytes de la ROM de un slot, acumuladas paso a paso
+#[derive(Clone, Debug)]
+pub struct CodeDataLog {
+    pub slot: SlotId,
+    marks: Vec<u8>,
+    /// Lecturas recientes de la ROM: (posición, valor, paso)
+    recent: VecDeque<(usize, u8, u64)>,
+    steps: u64,
+    /// En pausa (false) no se marca nada
+    pub active: bool,
+}
+
+impl CodeDataLog {
+    /// Registro vacío para la ROM de `size` bytes del slot `slot`
+    pub fn new(slot: SlotId, size: usize) -> CodeDataLog {
+        CodeDataLog { slot, marks: vec![0; size], recent: VecDeque::new(), steps: 0, active: true }
+    }
+
+    /// Posición en la ROM del principio de cada página de 8 KB que muestra
+    /// el cartucho; se toma antes de cada paso
+    pub fn pages(&self, bus: &MsxBus) -> [Option<usize>; PAGES] {
+        let primary = bus.ppi.primary_slot_register();
+        std::array::from_fn(|page| {
+            let address = (page * PAGE_SIZE) as u16;
+            let slot = bus.slots.resolve(address, primary);
+            let ours = slot.primary == self.slot.primary
+                && (!bus.slots.is_expanded(slot.primary) || slot.secondary == self.slot.secondary);
+            ours.then(|| bus.slots.content(slot).rom_offset(address)).flatten()
+        })
+    }
+
+    /// Marcar los accesos de un paso con las páginas de antes del paso
+    pub fn record(&mut self, pages: &[Option<usize>; PAGES], accesses: &[Access]) {
+        if !self.active {
+            return;
+        }
+        self.steps += 1;
+        let size = self.marks.len();
+        let offset_of = |address: u16| {
+            pages[address as usize / PAGE_SIZE]
+                .map(|base| base + address as usize % PAGE_SIZE)
+                .filter(|&offset| offset < size)
+        };
+        let mut opcode = true;
+        for access in accesses {
+            match access.kind {
+                AccessKind::Fetch => {
+                    if let Some(offset) = offset_of(access.address) {
+                        self.marks[offset] |= if opcode { CDL_CODE | CDL_OPCODE } else { CDL_CODE };
+                    }
+                    opcode = false;
+                }
+                AccessKind::Read => {
+                    if let Some(offset) = offset_of(access.address) {
+                        self.marks[offset] |= CDL_DATA;
+                        if self.recent.len() == RECENT_READS {
+                            self.recent.pop_front();
+                        }
+                        self.recent.push_back((offset, access.value, self.steps));
+                    }
+                }
+                AccessKind::Out if matches!(access.address, 0x98 | 0x99) => {
+                    let steps = self.steps;
+                    let found = self
+                        .recent
+                        .iter()
+                        .rposition(|&(_, value, step)| value == access.value && steps - step <= RECENT_STEPS);
+                    if let Some((offset, _, _)) = found.and_then(|i| self.recent.remove(i)) {
+                        self.marks[offset] |= if access.address == 0x98 { CDL_VRAM } else { CDL_VDP };
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn marks(&self) -> &[u8] {
+        &self.marks
+    }
+
+    pub fn flags(&self, offset: usize) -> u8 {
+        self.marks.get(offset).copied().unwrap_or(0)
+    }
+
+    /// Bytes con alguna de las marcas de `flags`
+    pub fn count(&self, flags: u8) -> usize {
+        self.marks.iter().filter(|&&m| m & flags != 0).count()
+    }
+
+    /// Sumar las marcas de otra sesión sobre la misma ROM
+    pub fn merge(&mut self, marks: &[u8]) -> Result<(), String> {
+        if marks.len() != self.marks.len() {
+            return Err(format!("El CDL es de otra ROM ({} bytes, se esperaban {})", marks.len(), self.marks.len()));
+        }
+        for (mark, &other) in self.marks.iter_mut().zip(marks) {
+            *mark |= other;
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.marks.fill(0);
+        self.recent.clear();
+    }
+
+    /// Resumen por bancos de `bank_size` bytes
+    pub fn banks(&self, bank_size: usize) -> Vec<BankMarks> {
+        self.marks
+            .chunks(bank_size.max(1))
+            .enumerate()
+            .map(|(bank, marks)| {
+                let count = |flag: u8| marks.iter().filter(|&&m| m & flag != 0).count();
+                BankMarks { bank, code: count(CDL_CODE), data: count(CDL_DATA), vram: count(CDL_VRAM), vdp: count(CDL_VDP) }
+            })
+            .collect()
+    }
+
+    /// Fichero en el formato descrito arriba
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE + self.marks.len());
+        out.extend_from_slice(CDL_MAGIC);
+        out.push(CDL_VERSION);
+        out.push((self.slot.primary & 3) | (self.slot.secondary & 3) << 2);
+        out.extend_from_slice(&(self.marks.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.marks);
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<CodeDataLog, String> {
+        let body = data.strip_prefix(CDL_MAGIC.as_slice()).ok_or_else(|| "No es un fichero CDL".to_string())?;
+        if body.len() < HEADER_SIZE - CDL_MAGIC.len() {
+            return Err("Cabecera de CDL incompleta".to_string());
+        }
+        if body[0] != CDL_VERSION {
+            return Err(format!("Versión de CDL no soportada: {}", body[0]));
+        }
+        let slot = SlotId { primary: body[1] & 3, secondary: (body[1] >> 2) & 3 };
+        let size = u32::from_le_bytes([body[2], body[3], body[4], body[5]]) as usize;
+        let marks = &body[6..];
+        if marks.len() != size {
+            return Err(format!("CDL truncado: {} marcas de {}", marks.len(), size));
+        }
+        let mut log = CodeDataLog::new(slot, size);
+        log.marks.copy_from_slice(marks);
+        Ok(log)
+    }
+}
+
+/// Tramos de la ROM enviados a la VRAM: (posición, longitud). Se juntan
+/// los huecos cortos que no son código
+pub fn uploads(marks: &[u8]) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (offset, _) in marks.iter().enumerate().filter(|(_, &m)| m & CDL_VRAM != 0) {
+        match runs.last_mut() {
+            Some((start, length))
+                if offset - (*start + *length) <= UPLOAD_GAP
+                    && marks[*start + *length..offset].iter().all(|&m| m & CDL_CODE == 0) =>
+            {
+                *length = offset + 1 - *start;
+            }
+            _ => runs.push((offset, 1)),
+        }
+    }
+    runs.retain(|&(_, length)| length >= MIN_UPLOAD);
+    runs
+}
@@ -0,0 +1,377 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  SEPARACIÓN DE CÓDIGO Y DATOS                                  ║
+//! ║  - Recorrido recursivo desde INIT, el reset o entradas dadas   ║
+//! ║  - Saltos, llamadas, RST y tablas de saltos                    ║
+//! ║  - Seguimiento de las escrituras al mapper de las MegaROM      ║
+//! ║  - Lo no alcanzado se clasifica como texto, gráficos o datos   ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::cdl::{CDL_CODE, CDL_DATA, CDL_OPCODE, CDL_VDP, CDL_VRAM};
+use crate::disasm::{decode, Flow, Instruction, Operand};
+use crate::gfxfinder::GraphicsFinder;
+use crate::mapper::{MapperType, PageMap, PAGE_SIZE};
+use crate::rom::RomHeader;
+use crate::symbols::BIOS_ENTRIES;
+
+/// Entradas como máximo de una tabla de saltos
+const MAX_TABLE: usize = 128;
+/// Caracteres imprimibles seguidos para considerar texto
+const MIN_TEXT: usize = 8;
+/// Zona de ganchos de la BIOS (H.KEYI a H.PHYD)
+const HOOKS: std::ops::RangeInclusive<u16> = 0xFD9A..=0xFFCA;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteKind {
+    #[default]
+    Unknown,
+    Code,
+    Data,
+    Text,
+    Graphics,
+}
+
+impl ByteKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ByteKind::Unknown => "unknown",
+            ByteKind::Code => "code",
+            ByteKind::Data => "data",
+            ByteKind::Text => "text",
+            ByteKind::Graphics => "graphics",
+        }
+    }
+
+    /// Valor por byte en la exportación del mapa
+    pub fn code(&self) -> u8 {
+        match self {
+            ByteKind::Unknown => 0,
+            ByteKind::Code => 1,
+            ByteKind::Data => 2,
+            ByteKind::Text => 3,
+            ByteKind::Graphics => 4,
+        }
+    }
+}
+
+/// Escritura a un registro del mapper encontrada en el recorrido
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BankSwitch {
+    /// Dirección de la instrucción LD (nn),A
+    pub address: u16,
+    /// Offset de la instrucción en la ROM
+    pub offset: usize,
+    /// Dirección base de la página que cambia
+    pub page: u16,
+    /// Banco elegido, si A tenía un valor conocido
+    pub bank: Option<u16>,
+}
+
+/// Resultado del análisis: una clase por byte de la ROM
+#[derive(Clone, Debug)]
+pub struct CodeMap {
+    pub mapper: MapperType,
+    pub kinds: Vec<ByteKind>,
+    /// Offsets donde empieza una instrucción
+    pub instructions: BTreeSet<usize>,
+    /// Entradas y destinos de saltos: offset → dirección del Z80
+    pub labels: BTreeMap<usize, u16>,
+    pub switches: Vec<BankSwitch>,
+    /// Banco de 8 KB → dirección de la página donde se ejecutó
+    pub bank_origins: BTreeMap<usize, u16>,
+}
+
+impl CodeMap {
+    pub fn kind(&self, offset: usize) -> ByteKind {
+        self.kinds.get(offset).copied().unwrap_or_default()
+    }
+
+    pub fn count(&self, kind: ByteKind) -> usize {
+        self.kinds.iter().filter(|&&k| k == kind).count()
+    }
+
+    /// Dirección donde se ve el banco de 8 KB que contiene `offset`
+    pub fn origin_of(&self, offset: usize) -> Option<u16> {
+        self.bank_origins.get(&(offset / PAGE_SIZE)).copied()
+    }
+
+    /// Una clase por byte (0 desconocido, 1 código, 2 datos, 3 texto,
+    /// 4 gráficos)
+    pub fn export(&self) -> Vec<u8> {
+        self.kinds.iter().map(ByteKind::code).collect()
+    }
+
+    /// Corregir el mapa con las marcas de un CDL: lo ejecutado es código
+    /// aunque el recorrido no llegara y lo que solo se leyó o se envió al
+    /// VDP deja de serlo. Las instrucciones del recorrido que pisan datos
+    /// o se solapan con una ejecutada se quitan
+    pub fn apply_cdl(&mut self, rom: &[u8], marks: &[u8]) {
+        let len = self.kinds.len().min(marks.len()).min(rom.len());
+        for (kind, &mark) in self.kinds.iter_mut().zip(&marks[..len]) {
+            if mark & CDL_CODE != 0 {
+                *kind = ByteKind::Code;
+            } else if mark & CDL_VRAM != 0 {
+                *kind = ByteKind::Graphics;
+            } else if mark & (CDL_DATA | CDL_VDP) != 0 {
+                *kind = ByteKind::Data;
+            }
+        }
+        let mark = |offset: usize| marks.get(offset).copied().unwrap_or(0);
+        let kinds = &self.kinds;
+        self.instructions.retain(|&offset| {
+            let length = decode(&rom[offset..(offset + 4).min(rom.len())], 0).len();
+            let covered = offset..(offset + length).min(kinds.len());
+            // Un opcode ejecutado nunca cae en medio de otra instrucción
+            let inside = (offset + 1..covered.end).any(|o| mark(o) & CDL_OPCODE != 0);
+            let operand = mark(offset) & (CDL_CODE | CDL_OPCODE) == CDL_CODE;
+            covered.clone().all(|o| kinds[o] == ByteKind::Code) && !inside && !operand
+        });
+        self.instructions.extend((0..len).filter(|&offset| marks[offset] & CDL_OPCODE != 0));
+    }
+
+    /// Marcar como texto las rachas imprimibles, como gráficos lo que el
+    /// buscador propone fuera del código y el resto como datos
+    pub fn classify_data(&mut self, rom: &[u8]) {
+        let mut start = 0;
+        while start < self.kinds.len() {
+            let printable = |i: usize| self.kinds[i] == ByteKind::Unknown && (0x20..0x7F).contains(&rom[i]);
+            let mut end = start;
+            while end < self.kinds.len() && printable(end) {
+                end += 1;
+            }
+            if end - start >= MIN_TEXT {
+                self.kinds[start..end].fill(ByteKind::Text);
+            }
+            start = end + 1;
+        }
+
+        for region in GraphicsFinder::default().skip_code(self).find(rom) {
+            for kind in &mut self.kinds[region.offset..region.offset + region.length] {
+                if *kind == ByteKind::Unknown {
+                    *kind = ByteKind::Graphics;
+                }
+            }
+        }
+        for kind in &mut self.kinds {
+            if *kind == ByteKind::Unknown {
+                *kind = ByteKind::Data;
+            }
+        }
+    }
+}
+
+/// Un camino pendiente: dirección y bancos visibles al llegar a ella
+struct Walk {
+    address: u16,
+    pages: PageMap,
+}
+
+/// Analizador de flujo de una ROM o MegaROM
+pub struct CodeAnalyzer {
+    mapper: MapperType,
+    origin: u16,
+    entries: Vec<u16>,
+    /// Marcas de un CDL grabado al ejecutar la ROM
+    cdl: Vec<u8>,
+}
+
+impl CodeAnalyzer {
+    /// `origin` solo cuenta para ROMs sin mapper
+    pub fn new(mapper: MapperType, origin: u16) -> Self {
+        CodeAnalyzer { mapper, origin, entries: Vec::new(), cdl: Vec::new() }
+    }
+
+    /// Cartucho: mapper detectado, página y entradas de la cabecera
+    pub fn for_rom(rom: &[u8]) -> Self {
+        let mapper = MapperType::guess(rom);
+        match RomHeader::parse(rom) {
+            Some(header) => header
+                .entries()
+                .into_iter()
+                .fold(CodeAnalyzer::new(mapper, header.origin(rom.len())), |a, (address, _)| a.entry(address)),
+            None => CodeAnalyzer::new(mapper, 0x4000),
+        }
+    }
+
+    /// BIOS en 0000h: el vector de reset y la tabla de saltos
+    pub fn for_bios() -> Self {
+        BIOS_ENTRIES
+            .iter()
+            .fold(CodeAnalyzer::new(MapperType::Plain, 0x0000), |a, &(address, _)| a.entry(address))
+    }
+
+    pub fn entry(mut self, address: u16) -> Self {
+        self.entries.push(address);
+        self
+    }
+
+    /// Completar el recorrido con las marcas de un CDL (ver `cdl`)
+    pub fn cdl(mut self, marks: &[u8]) -> Self {
+        self.cdl = marks.to_vec();
+        self
+    }
+
+    /// Recorrer el código alcanzable (y lo que marque el CDL); los bytes no
+    /// alcanzados quedan como desconocidos hasta `CodeMap::classify_data`
+    pub fn trace(&self, rom: &[u8]) -> CodeMap {
+        let mut map = CodeMap {
+            mapper: self.mapper,
+            kinds: vec![ByteKind::Unknown; rom.len()],
+            instructions: BTreeSet::new(),
+            labels: BTreeMap::new(),
+            switches: Vec::new(),
+            bank_origins: BTreeMap::new(),
+        };
+        let pages = self.mapper.initial_pages(rom.len(), self.origin);
+        let mut queue: Vec<Walk> = self.entries.iter().rev().map(|&address| Walk { address, pages }).collect();
+        while let Some(walk) = queue.pop() {
+            if let Some(offset) = resolve(rom, &walk.pages, walk.address) {
+                map.labels.entry(offset).or_insert(walk.address);
+            }
+            self.walk(rom, walk, &mut map, &mut queue);
+        }
+        if !self.cdl.is_empty() {
+            map.apply_cdl(rom, &self.cdl);
+        }
+        map
+    }
+
+    /// Recorrido completo con la clasificación del resto de bytes
+    pub fn analyze(&self, rom: &[u8]) -> CodeMap {
+        let mut map = self.trace(rom);
+        map.classify_data(rom);
+        map
+    }
+
+    /// Seguir un camino hasta un salto incondicional, un retorno, código ya
+    /// visto o algo que no decodifica
+    fn walk(&self, rom: &[u8], walk: Walk, map: &mut CodeMap, queue: &mut Vec<Walk>) {
+        let Walk { mut address, mut pages } = walk;
+        // Valor conocido de A y último puntero cargado (para JP (HL))
+        let mut a: Option<u8> = None;
+        let mut pointer: Option<u16> = None;
+
+        loop {
+            let Some(offset) = resolve(rom, &pages, address) else { return };
+            if map.kinds[offset] != ByteKind::Unknown {
+                return;
+            }
+            let code: Vec<u8> = (0..4u16)
+                .map_while(|i| resolve(rom, &pages, address.wrapping_add(i)).map(|o| rom[o]))
+                .collect();
+            let instruction = decode(&code, address);
+            let offsets: Vec<usize> =
+                (0..instruction.len() as u16).filter_map(|i| resolve(rom, &pages, address.wrapping_add(i))).collect();
+            if instruction.is_data() || offsets.iter().any(|&o| map.kinds[o] != ByteKind::Unknown) {
+                return;
+            }
+            for &o in &offsets {
+                map.kinds[o] = ByteKind::Code;
+            }
+            map.instructions.insert(offset);
+            map.bank_origins
+                .entry(offset / PAGE_SIZE)
+                .or_insert(address & !(PAGE_SIZE as u16 - 1));
+
+            // LD (nn),A sobre un registro del mapper
+            if let (Some(target), Some(Operand::Register("A"))) = (instruction.memory(), instruction.operands.get(1)) {
+                if let Some(page) = self.mapper.switch(&mut pages, rom.len(), target, a) {
+                    map.switches.push(BankSwitch {
+                        address,
+                        offset,
+                        page: (page * PAGE_SIZE) as u16,
+                        bank: pages[page],
+                    });
+                }
+            }
+            // Rutina instalada en un gancho (H.TIMI, H.KEYI...): se
+            // ejecutará desde la interrupción
+            if let ("LD", [Operand::Memory(hook), Operand::Register("HL" | "DE")], Some(routine)) =
+                (instruction.mnemonic, instruction.operands.as_slice(), pointer)
+            {
+                if HOOKS.contains(hook) && resolve(rom, &pages, routine).is_some() {
+                    queue.push(Walk { address: routine, pages });
+                }
+            }
+            a = track_a(&instruction, a);
+            if let ("LD", [Operand::Register("HL" | "DE" | "IX" | "IY"), Operand::Word(value)]) =
+                (instruction.mnemonic, instruction.operands.as_slice())
+            {
+                pointer = Some(*value);
+            }
+
+            let target = instruction.target().filter(|&t| resolve(rom, &pages, t).is_some());
+            if let Some(target) = target {
+                queue.push(Walk { address: target, pages });
+            }
+            match instruction.flow {
+                Flow::Jump | Flow::Return => return,
+                Flow::IndirectJump => {
+                    if let Some(table) = pointer {
+                        jump_table(rom, &pages, table, map, queue);
+                    }
+                    return;
+                }
+                _ => {}
+            }
+            address = address.wrapping_add(instruction.len() as u16);
+        }
+    }
+}
+
+/// Offset en la ROM de una dirección con los bancos dados; None fuera de
+/// la ROM (la última página puede quedar a medias)
+fn resolve(rom: &[u8], pages: &PageMap, address: u16) -> Option<usize> {
+    let bank = pages[address as usize / PAGE_SIZE]?;
+    Some(bank as usize * PAGE_SIZE + (address as usize % PAGE_SIZE)).filter(|&offset| offset < rom.len())
+}
+
+/// Nuevo valor conocido del acumulador tras la instrucción
+fn track_a(instruction: &Instruction, a: Option<u8>) -> Option<u8> {
+    match (instruction.mnemonic, instruction.operands.as_slice()) {
+        ("LD", [Operand::Register("A"), Operand::Byte(value)]) => Some(*value),
+        ("XOR", [Operand::Register("A")]) | ("SUB", [Operand::Register("A")]) => Some(0),
+        ("INC", [Operand::Register("A")]) => a.map(|v| v.wrapping_add(1)),
+        ("DEC", [Operand::Register("A")]) => a.map(|v| v.wrapping_sub(1)),
+        // Lo que escribe en A, toca AF o llama a otra rutina la deja indefinida
+        (_, [Operand::Register("A" | "AF"), ..])
+        | ("SUB" | "AND" | "XOR" | "OR", _)
+        // Prefijo CB con A como destino: SET/RES b,A y las DD CB que copian en A
+        | ("SET" | "RES" | "RLC" | "RRC" | "RL" | "RR" | "SLA" | "SRA" | "SLL" | "SRL", [.., Operand::Register("A")])
+        | ("RLCA" | "RRCA" | "RLA" | "RRA" | "DAA" | "CPL" | "NEG" | "RLD" | "RRD" | "EX", _) => None,
+        _ if matches!(instruction.flow, Flow::Call | Flow::ConditionalCall) => None,
+        _ => a,
+    }
+}
+
+/// Tabla de saltos tras un JP (HL): instrucciones JP seguidas o punteros
+/// de 16 bits mientras apunten a la ROM y no pisen código
+fn jump_table(rom: &[u8], pages: &PageMap, table: u16, map: &mut CodeMap, queue: &mut Vec<Walk>) {
+    let mut address = table;
+    let first = resolve(rom, pages, table).map(|o| rom[o]);
+    for _ in 0..MAX_TABLE {
+        if first == Some(0xC3) {
+            if resolve(rom, pages, address).map(|o| rom[o]) != Some(0xC3) {
+                return;
+            }
+            queue.push(Walk { address, pages: *pages });
+            address = address.wrapping_add(3);
+            continue;
+        }
+        let (Some(low), Some(high)) = (resolve(rom, pages, address), resolve(rom, pages, address.wrapping_add(1))) else {
+            return;
+        };
+        if map.kinds[low] != ByteKind::Unknown || map.kinds[high] != ByteKind::Unknown {
+            return;
+        }
+        let target = u16::from_le_bytes([rom[low], rom[high]]);
+        if resolve(rom, pages, target).is_none() {
+            return;
+        }
+        map.kinds[low] = ByteKind::Data;
+        map.kinds[high] = ByteKind::Data;
+        queue.push(Walk { address: target, pages: *pages });
+        address = address.wrapping_add(2);
+    }
+}
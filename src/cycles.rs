@@ -0,0 +1,538 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  ANÁLISIS ESTÁTICO DE TIEMPOS                                  ║
+//! ║  - Bloques básicos de una rutina con sus estados T             ║
+//! ║  - Mínimo y máximo de cada camino hasta la salida              ║
+//! ║  - Espera M1 del MSX incluida                                  ║
+//! ║  - Escrituras al VDP más juntas de lo que admite el V9938      ║
+//! ╚════════════════════════════════════════════════════════════════╝
+//!
+//! Los bucles no se desenrollan: cada camino pasa una sola vez por cada
+//! bloque y los bucles se listan aparte con lo que cuesta una vuelta.
+//! LDIR, OTIR y el resto de repeticiones cuentan también una vuelta. Las
+//! llamadas a rutinas del mismo código suman lo que tarda la rutina; las
+//! de fuera (BIOS, RST) solo el CALL.
+//!
+//! La distancia entre accesos al VDP se mide de inicio a inicio de
+//! instrucción por el camino más rápido, vueltas de bucle incluidas
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::disasm::{decode, hex16, hex8, Flow, Instruction, Operand};
+use crate::symbols::SymbolTable;
+use crate::timing::{msx_timing, timing, Timing};
+
+/// Caminos enumerados como máximo y bloques visitados buscándolos
+const MAX_PATHS: usize = 256;
+const MAX_VISITS: usize = 20_000;
+/// Puertos del VDP y el de datos de la VRAM
+const VDP_PORTS: std::ops::RangeInclusive<u8> = 0x98..=0x9B;
+const VRAM_PORT: u8 = 0x98;
+
+/// Estados T mínimos entre un acceso al VDP y la siguiente escritura en
+/// la VRAM con la pantalla activa (Z80 a 3,58 MHz, espera M1 incluida).
+/// Los modos del TMS9918 son los más lentos; los de mapa de bits del
+/// V9938 admiten OUTI seguidos
+pub fn vram_gap(screen: u8) -> Option<u32> {
+    match screen {
+        0 => Some(20),
+        1..=4 => Some(29),
+        5..=8 | 10..=12 => Some(15),
+        _ => None,
+    }
+}
+
+/// Salida de un bloque: el bloque siguiente (None si deja la rutina) y
+/// lo que tarda el bloque saliendo por ahí
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockExit {
+    pub target: Option<u16>,
+    pub timing: Timing,
+}
+
+#[derive(Clone, Debug)]
+pub struct BasicBlock {
+    pub start: u16,
+    pub instructions: Vec<Instruction>,
+    /// Estados T de cada instrucción, con lo que tarda la rutina llamada
+    pub costs: Vec<Timing>,
+    /// Mínimo y máximo por cualquiera de sus salidas
+    pub timing: Timing,
+    pub exits: Vec<BlockExit>,
+    /// Tiene LDIR, OTIR u otra instrucción que se repite
+    pub repeats: bool,
+}
+
+impl BasicBlock {
+    /// Dirección siguiente a la última instrucción
+    pub fn end(&self) -> u16 {
+        self.instructions.last().map_or(self.start, |i| i.address.wrapping_add(i.len() as u16))
+    }
+}
+
+/// Camino desde la entrada hasta una salida de la rutina
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathTiming {
+    pub blocks: Vec<u16>,
+    pub timing: Timing,
+}
+
+/// Bucle: salto de `tail` hacia atrás a `head` y lo que tarda una vuelta
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoopTiming {
+    pub head: u16,
+    pub tail: u16,
+    pub iteration: Timing,
+}
+
+/// Escritura al VDP demasiado cerca del acceso anterior
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VdpWarning {
+    pub address: u16,
+    /// Instrucción del acceso anterior (la misma en OTIR y OTDR)
+    pub previous: u16,
+    pub port: u8,
+    pub tstates: u32,
+    pub required: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct CycleReport {
+    pub entry: u16,
+    /// Ordenados por dirección
+    pub blocks: Vec<BasicBlock>,
+    pub paths: Vec<PathTiming>,
+    pub loops: Vec<LoopTiming>,
+    /// Mínimo y máximo entre todos los caminos; None si ninguno sale
+    pub total: Option<Timing>,
+    /// Había más caminos de los que se enumeran
+    pub truncated: bool,
+    /// Destinos fuera del código: llamadas y saltos que no se cuentan
+    pub external: Vec<u16>,
+    pub warnings: Vec<VdpWarning>,
+}
+
+impl CycleReport {
+    pub fn block(&self, start: u16) -> Option<&BasicBlock> {
+        self.blocks.iter().find(|b| b.start == start)
+    }
+
+    /// Informe en texto con los nombres de `symbols`
+    pub fn text(&self, symbols: &SymbolTable) -> String {
+        let name = |address: u16| symbols.describe(address).unwrap_or_else(|| hex16(address));
+        let total = self.total.map_or("sin salida".to_string(), |t| format!("{}-{} estados T", t.min, t.max));
+        let mut out = format!("; Rutina {}: {}, {} caminos", name(self.entry), total, self.paths.len());
+        if self.truncated {
+            out.push_str(" (hay más)");
+        }
+        out.push_str("\n; Bloques\n");
+        for block in &self.blocks {
+            let exits: Vec<String> = block.exits.iter().map(|e| e.target.map_or("salida".to_string(), name)).collect();
+            out.push_str(&format!(
+                "{:<16} {:>3} instr {:>9}  → {}{}\n",
+                name(block.start),
+                block.instructions.len(),
+                format!("{}-{}", block.timing.min, block.timing.max),
+                exits.join(", "),
+                if block.repeats { "  (repite)" } else { "" }
+            ));
+        }
+        out.push_str("; Caminos\n");
+        for path in &self.paths {
+            let blocks: Vec<String> = path.blocks.iter().map(|&b| name(b)).collect();
+            out.push_str(&format!("{:>9}  {}\n", format!("{}-{}", path.timing.min, path.timing.max), blocks.join(" → ")));
+        }
+        if !self.loops.is_empty() {
+            out.push_str("; Bucles\n");
+            for l in &self.loops {
+                out.push_str(&format!(
+                    "{} → {}: vuelta de {}-{}\n",
+                    name(l.tail),
+                    name(l.head),
+                    l.iteration.min,
+                    l.iteration.max
+                ));
+            }
+        }
+        if !self.warnings.is_empty() {
+            out.push_str("; VDP\n");
+            for w in &self.warnings {
+                out.push_str(&format!(
+                    "{}: escritura en {} a {} estados T de {} (mínimo {})\n",
+                    name(w.address),
+                    hex8(w.port),
+                    w.tstates,
+                    name(w.previous),
+                    w.required
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// Analizador de tiempos de una rutina en código ensamblado o
+/// desensamblado
+pub struct CycleAnalyzer {
+    m1_wait: bool,
+    vram_gap: Option<u32>,
+}
+
+impl CycleAnalyzer {
+    /// Con la espera M1 y sin comprobar el VDP
+    pub fn new() -> Self {
+        CycleAnalyzer { m1_wait: true, vram_gap: None }
+    }
+
+    /// Sumar o no la espera M1 (sin ella, los tiempos de un Z80 normal)
+    pub fn m1_wait(mut self, enabled: bool) -> Self {
+        self.m1_wait = enabled;
+        self
+    }
+
+    /// Comprobar las escrituras al VDP con el mínimo del modo de pantalla
+    pub fn screen(mut self, screen: u8) -> Self {
+        self.vram_gap = vram_gap(screen);
+        self
+    }
+
+    pub fn vram_gap(mut self, tstates: u32) -> Self {
+        self.vram_gap = Some(tstates);
+        self
+    }
+
+    /// Analizar la rutina que empieza en `entry` dentro de `data`, cargado
+    /// en `origin`
+    pub fn analyze(&self, data: &[u8], origin: u16, entry: u16) -> CycleReport {
+        let mut code = Code {
+            analyzer: self,
+            data,
+            origin,
+            callees: BTreeMap::new(),
+            external: BTreeSet::new(),
+        };
+        let routine = code.routine(entry);
+        let warnings = match self.vram_gap {
+            Some(gap) if routine.blocks.contains_key(&entry) => vdp_warnings(&routine.blocks, entry, gap),
+            _ => Vec::new(),
+        };
+        CycleReport {
+            entry,
+            blocks: routine.blocks.into_values().collect(),
+            paths: routine.paths,
+            loops: routine.loops,
+            total: routine.total,
+            truncated: routine.truncated,
+            external: code.external.into_iter().collect(),
+            warnings,
+        }
+    }
+}
+
+impl Default for CycleAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Routine {
+    blocks: BTreeMap<u16, BasicBlock>,
+    paths: Vec<PathTiming>,
+    loops: Vec<LoopTiming>,
+    total: Option<Timing>,
+    truncated: bool,
+}
+
+/// El código analizado, con lo que tarda cada rutina llamada ya vista
+struct Code<'a> {
+    analyzer: &'a CycleAnalyzer,
+    data: &'a [u8],
+    origin: u16,
+    /// None mientras se analiza (recursión) o si no tiene salida
+    callees: BTreeMap<u16, Option<Timing>>,
+    external: BTreeSet<u16>,
+}
+
+impl Code<'_> {
+    fn contains(&self, address: u16) -> bool {
+        (address.wrapping_sub(self.origin) as usize) < self.data.len()
+    }
+
+    fn fetch(&self, address: u16) -> Option<Instruction> {
+        if !self.contains(address) {
+            return None;
+        }
+        let offset = address.wrapping_sub(self.origin) as usize;
+        let instruction = decode(&self.data[offset..(offset + 4).min(self.data.len())], address);
+        (!instruction.is_data()).then_some(instruction)
+    }
+
+    fn cost(&mut self, instruction: &Instruction) -> Timing {
+        let cost = match self.analyzer.m1_wait {
+            true => msx_timing(&instruction.bytes),
+            false => timing(&instruction.bytes),
+        };
+        if !matches!(instruction.flow, Flow::Call | Flow::ConditionalCall) {
+            return cost;
+        }
+        match (instruction.flow, instruction.target().and_then(|t| self.callee(t))) {
+            (_, None) => cost,
+            (Flow::Call, Some(routine)) => cost + routine,
+            (_, Some(routine)) => Timing::new(cost.min, cost.max + routine.max),
+        }
+    }
+
+    fn callee(&mut self, address: u16) -> Option<Timing> {
+        if !self.contains(address) {
+            self.external.insert(address);
+            return None;
+        }
+        if let Some(&total) = self.callees.get(&address) {
+            return total;
+        }
+        self.callees.insert(address, None);
+        let total = self.routine(address).total;
+        self.callees.insert(address, total);
+        total
+    }
+
+    fn routine(&mut self, entry: u16) -> Routine {
+        // Instrucciones alcanzables sin entrar en las llamadas
+        let mut instructions: BTreeMap<u16, Instruction> = BTreeMap::new();
+        let mut leaders = BTreeSet::from([entry]);
+        let mut queue = vec![entry];
+        while let Some(address) = queue.pop() {
+            if instructions.contains_key(&address) {
+                continue;
+            }
+            let Some(instruction) = self.fetch(address) else { continue };
+            let next = address.wrapping_add(instruction.len() as u16);
+            if matches!(instruction.flow, Flow::Jump | Flow::Branch) {
+                match instruction.target() {
+                    Some(target) if self.contains(target) => {
+                        leaders.insert(target);
+                        queue.push(target);
+                    }
+                    Some(target) => {
+                        self.external.insert(target);
+                    }
+                    None => {}
+                }
+            }
+            if ends_block(instruction.flow) {
+                leaders.insert(next);
+            }
+            if instruction.flow.falls_through() {
+                queue.push(next);
+            }
+            instructions.insert(address, instruction);
+        }
+
+        let mut blocks = BTreeMap::new();
+        for &start in leaders.iter().filter(|a| instructions.contains_key(a)) {
+            let block = self.block(start, &instructions, &leaders);
+            blocks.insert(start, block);
+        }
+        let mut paths = Paths { blocks: &blocks, paths: Vec::new(), loops: BTreeMap::new(), visits: 0, truncated: false };
+        if blocks.contains_key(&entry) {
+            paths.visit(entry, &mut Vec::new(), Timing::fixed(0));
+        }
+        let Paths { paths, loops, truncated, .. } = paths;
+        let total = paths.iter().map(|p| p.timing).reduce(|a, b| Timing::new(a.min.min(b.min), a.max.max(b.max)));
+        let loops = loops
+            .into_iter()
+            .map(|((head, tail), iteration)| LoopTiming { head, tail, iteration })
+            .collect();
+        Routine { blocks, paths, loops, total, truncated }
+    }
+
+    fn block(&mut self, start: u16, instructions: &BTreeMap<u16, Instruction>, leaders: &BTreeSet<u16>) -> BasicBlock {
+        let mut list = Vec::new();
+        let mut costs = Vec::new();
+        let mut address = start;
+        loop {
+            let instruction = instructions[&address].clone();
+            let next = address.wrapping_add(instruction.len() as u16);
+            costs.push(self.cost(&instruction));
+            let flow = instruction.flow;
+            list.push(instruction);
+            if ends_block(flow) || !instructions.contains_key(&next) || leaders.contains(&next) {
+                break;
+            }
+            address = next;
+        }
+
+        let body = costs[..costs.len() - 1].iter().fold(Timing::fixed(0), |a, &b| a + b);
+        let (last, cost) = (list.last().unwrap(), *costs.last().unwrap());
+        let next = last.address.wrapping_add(last.len() as u16);
+        let local = |address: Option<u16>| address.filter(|a| instructions.contains_key(a));
+        let exit = |target: Option<u16>, timing: Timing| BlockExit { target: local(target), timing: body + timing };
+        let exits = match last.flow {
+            Flow::Jump => vec![exit(last.target(), cost)],
+            Flow::Return | Flow::IndirectJump => vec![exit(None, cost)],
+            Flow::Branch => vec![
+                exit(last.target(), Timing::fixed(cost.max)),
+                exit(Some(next), Timing::fixed(cost.min)),
+            ],
+            Flow::ConditionalReturn => vec![exit(None, Timing::fixed(cost.max)), exit(Some(next), Timing::fixed(cost.min))],
+            _ => vec![exit(Some(next), cost)],
+        };
+        let span = Timing::new(
+            exits.iter().map(|e| e.timing.min).min().unwrap_or(0),
+            exits.iter().map(|e| e.timing.max).max().unwrap_or(0),
+        );
+        let repeats = list.iter().any(|i| i.flow == Flow::Next && {
+            let t = timing(&i.bytes);
+            t.min != t.max
+        });
+        BasicBlock { start, instructions: list, costs, timing: span, exits, repeats }
+    }
+}
+
+/// Instrucciones tras las que empieza otro bloque
+fn ends_block(flow: Flow) -> bool {
+    matches!(flow, Flow::Jump | Flow::Branch | Flow::Return | Flow::ConditionalReturn | Flow::IndirectJump)
+}
+
+/// Búsqueda en profundidad de los caminos sin repetir bloque
+struct Paths<'a> {
+    blocks: &'a BTreeMap<u16, BasicBlock>,
+    paths: Vec<PathTiming>,
+    /// (cabeza, cola) → vuelta más corta y más larga
+    loops: BTreeMap<(u16, u16), Timing>,
+    visits: usize,
+    truncated: bool,
+}
+
+impl Paths<'_> {
+    /// `path` lleva cada bloque con el tiempo transcurrido al entrar
+    fn visit(&mut self, start: u16, path: &mut Vec<(u16, Timing)>, elapsed: Timing) {
+        self.visits += 1;
+        if self.paths.len() >= MAX_PATHS || self.visits > MAX_VISITS {
+            self.truncated = true;
+            return;
+        }
+        path.push((start, elapsed));
+        let blocks = self.blocks;
+        for exit in &blocks[&start].exits {
+            let total = elapsed + exit.timing;
+            match exit.target {
+                None if self.paths.len() < MAX_PATHS => self.paths.push(PathTiming {
+                    blocks: path.iter().map(|&(b, _)| b).collect(),
+                    timing: total,
+                }),
+                None => self.truncated = true,
+                Some(next) => match path.iter().find(|&&(b, _)| b == next) {
+                    Some(&(head, at)) => {
+                        let iteration = Timing::new(total.min - at.min, total.max - at.max);
+                        self.loops
+                            .entry((head, start))
+                            .and_modify(|t| *t = Timing::new(t.min.min(iteration.min), t.max.max(iteration.max)))
+                            .or_insert(iteration);
+                    }
+                    None => self.visit(next, path, total),
+                },
+            }
+        }
+        path.pop();
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ACCESOS AL VDP
+// ═══════════════════════════════════════════════════════════════
+
+/// Lo que se sabe al empezar un bloque: el acceso al VDP más cercano por
+/// el camino más rápido (estados T e instrucción) y el valor de C
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct VdpState {
+    since: Option<(u32, u16)>,
+    c: Option<u8>,
+}
+
+impl VdpState {
+    fn merge(self, other: VdpState) -> VdpState {
+        let since = match (self.since, other.since) {
+            (Some(a), Some(b)) => Some(if b.0 < a.0 { b } else { a }),
+            (a, b) => a.or(b),
+        };
+        VdpState { since, c: if self.c == other.c { self.c } else { None } }
+    }
+}
+
+/// Propagar los estados hasta que no cambian y revisar cada escritura
+/// en la VRAM con el estado final
+fn vdp_warnings(blocks: &BTreeMap<u16, BasicBlock>, entry: u16, gap: u32) -> Vec<VdpWarning> {
+    let mut states = BTreeMap::from([(entry, VdpState { since: None, c: None })]);
+    let mut queue = vec![entry];
+    while let Some(start) = queue.pop() {
+        for (target, out) in run_block(&blocks[&start], states[&start], gap, &mut Vec::new()) {
+            let Some(target) = target else { continue };
+            let merged = states.get(&target).map_or(out, |&old| old.merge(out));
+            if states.get(&target) != Some(&merged) {
+                states.insert(target, merged);
+                queue.push(target);
+            }
+        }
+    }
+    let mut warnings = Vec::new();
+    for (start, &state) in &states {
+        run_block(&blocks[start], state, gap, &mut warnings);
+    }
+    warnings.sort_by_key(|w| w.address);
+    warnings
+}
+
+/// Recorrer un bloque desde `state`; devuelve el estado en cada salida
+fn run_block(block: &BasicBlock, mut state: VdpState, gap: u32, warnings: &mut Vec<VdpWarning>) -> Vec<(Option<u16>, VdpState)> {
+    let last = block.instructions.len() - 1;
+    for (i, (instruction, cost)) in block.instructions.iter().zip(&block.costs).enumerate() {
+        if let Some(port) = vdp_port(instruction, state.c) {
+            if port == VRAM_PORT {
+                if let Some((tstates, previous)) = state.since.filter(|&(t, _)| t < gap) {
+                    warnings.push(VdpWarning { address: instruction.address, previous, port, tstates, required: gap });
+                }
+                // OTIR y OTDR vuelven a escribir tras cada vuelta
+                if instruction.flow == Flow::Next && cost.min != cost.max && cost.max < gap {
+                    let address = instruction.address;
+                    warnings.push(VdpWarning { address, previous: address, port, tstates: cost.max, required: gap });
+                }
+            }
+            state.since = Some((0, instruction.address));
+        }
+        state.c = track_c(instruction, state.c);
+        if i < last {
+            state.since = state.since.map(|(t, at)| (t + cost.min, at));
+        }
+    }
+    let body: u32 = block.costs[..last].iter().map(|c| c.min).sum();
+    block
+        .exits
+        .iter()
+        .map(|exit| {
+            let since = state.since.map(|(t, at)| (t + exit.timing.min - body, at));
+            (exit.target, VdpState { since, ..state })
+        })
+        .collect()
+}
+
+/// Puerto del VDP en el que escribe la instrucción, si se sabe
+fn vdp_port(instruction: &Instruction, c: Option<u8>) -> Option<u8> {
+    let port = match (instruction.mnemonic, instruction.operands.as_slice()) {
+        ("OUT", [Operand::Port(port), _]) => Some(*port),
+        ("OUT", [Operand::Indirect("C"), _]) | ("OUTI" | "OUTD" | "OTIR" | "OTDR", []) => c,
+        _ => None,
+    }?;
+    VDP_PORTS.contains(&port).then_some(port)
+}
+
+/// Valor conocido de C tras la instrucción
+fn track_c(instruction: &Instruction, c: Option<u8>) -> Option<u8> {
+    match (instruction.mnemonic, instruction.operands.as_slice()) {
+        ("LD", [Operand::Register("C"), Operand::Byte(value)]) => Some(*value),
+        ("LD", [Operand::Register("BC"), Operand::Word(value)]) => Some(*value as u8),
+        ("SUB" | "AND" | "XOR" | "OR" | "CP" | "PUSH", _) => c,
+        (_, [Operand::Register("C" | "BC"), ..]) | ("EXX", _) => None,
+        _ if matches!(instruction.flow, Flow::Call | Flow::ConditionalCall) => None,
+        _ => c,
+    }
+}
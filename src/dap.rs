@@ -0,0 +1,877 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  SERVIDOR DAP (DEBUG ADAPTER PROTOCOL)                         ║
+//! ║  - Depuración a nivel de fuente .asm desde el editor           ║
+//! ║  - Puntos de ruptura por línea (con condición) y por etiqueta  ║
+//! ║  - Variables: registros, flags, registros del VDP y slots      ║
+//! ║  - Lectura y escritura de memoria, evaluación de expresiones   ║
+//! ║  - Mensajes con Content-Length por la entrada/salida estándar  ║
+//! ╚════════════════════════════════════════════════════════════════╝
+//!
+//! `launch` recibe `program` (el .asm principal; los INCLUDE se buscan
+//! en su directorio), `format` ("bin" por defecto), `stopOnEntry` y,
+//! opcionalmente, `source` con el texto para no leerlo del disco
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+
+use crate::asm::{Assembler, OutputFormat, MAIN_SOURCE};
+use crate::debugger::{BreakKind, Condition, Debugger, Stop, StopReason};
+use crate::disasm::{hex16, hex8};
+use crate::json_escape;
+use crate::link::number;
+use crate::machine::{Machine, FRAME_TSTATES};
+use crate::symbols::{SymbolKind, SymbolTable};
+use crate::z80::{FLAG_NAMES, REGISTER_NAMES};
+
+/// El Z80 es el único hilo
+const THREAD_ID: u32 = 1;
+
+/// Referencias de los grupos de variables
+const REGISTERS: u64 = 1;
+const FLAGS: u64 = 2;
+const VDP: u64 = 3;
+const SLOTS: u64 = 4;
+
+/// Mayor cuerpo que se acepta (un launch lleva el fuente entero)
+const MAX_MESSAGE: usize = 4 << 20;
+/// Anidamiento máximo de las peticiones en JSON
+const MAX_DEPTH: usize = 64;
+
+pub struct DapServer {
+    seq: u64,
+    /// Fichero principal tal como lo nombra el editor, y su directorio
+    program: String,
+    dir: PathBuf,
+    symbols: SymbolTable,
+    labels: BTreeMap<String, u16>,
+    /// Puntos de ruptura de cada fichero (en minúsculas) y de etiquetas
+    breakpoints: HashMap<String, Vec<u32>>,
+    functions: Vec<u32>,
+    /// Estados T como mucho por cada "continue"
+    limit: Option<u64>,
+    running: bool,
+    spent: u64,
+    stop_on_entry: bool,
+    /// 1 si el editor cuenta las líneas desde 1, 0 si desde 0
+    line_base: usize,
+    done: bool,
+}
+
+/// Eventos que acompañan a una respuesta, en orden
+type Events = Vec<(&'static str, String)>;
+
+impl DapServer {
+    pub fn new() -> Self {
+        DapServer {
+            seq: 0,
+            program: MAIN_SOURCE.to_string(),
+            dir: PathBuf::new(),
+            symbols: SymbolTable::msx(),
+            labels: BTreeMap::new(),
+            breakpoints: HashMap::new(),
+            functions: Vec::new(),
+            limit: None,
+            running: false,
+            spent: 0,
+            stop_on_entry: false,
+            line_base: 1,
+            done: false,
+        }
+    }
+
+    /// Parar con "pause" tras `tstates` sin llegar a un punto de ruptura
+    pub fn limit(mut self, tstates: u64) -> Self {
+        self.limit = Some(tstates);
+        self
+    }
+
+    /// Atender al editor hasta "disconnect" o el fin de la entrada. La
+    /// entrada se lee en otro hilo para poder recibir "pause" mientras la
+    /// máquina corre
+    pub fn serve(
+        &mut self,
+        machine: &mut Machine,
+        debugger: &mut Debugger,
+        input: impl Read + Send + 'static,
+        output: &mut impl Write,
+    ) -> io::Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(input);
+            while let Ok(Some(message)) = read_message(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        self.done = false;
+        while !self.done {
+            let messages = if self.running {
+                match receiver.try_recv() {
+                    Ok(message) => self.request(machine, debugger, &message),
+                    Err(TryRecvError::Empty) => self.advance(machine, debugger),
+                    Err(TryRecvError::Disconnected) => return Ok(()),
+                }
+            } else {
+                match receiver.recv() {
+                    Ok(message) => self.request(machine, debugger, &message),
+                    Err(_) => return Ok(()),
+                }
+            };
+            for message in messages {
+                output.write_all(&encode(&message))?;
+            }
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Atender una petición y devolver la respuesta y los eventos; si la
+    /// petición deja la máquina corriendo, corre hasta que pare (conviene
+    /// fijar `limit`)
+    pub fn handle(&mut self, machine: &mut Machine, debugger: &mut Debugger, request: &str) -> Vec<String> {
+        let mut messages = self.request(machine, debugger, request);
+        while self.running {
+            messages.extend(self.advance(machine, debugger));
+        }
+        messages
+    }
+
+    fn request(&mut self, machine: &mut Machine, debugger: &mut Debugger, text: &str) -> Vec<String> {
+        let Ok(request) = Json::parse(text) else { return Vec::new() };
+        let command = request.get("command").as_str().unwrap_or("").to_string();
+        let seq = request.get("seq").as_u64().unwrap_or(0);
+        let args = request.get("arguments");
+        let mut events = Events::new();
+        let result = match command.as_str() {
+            "initialize" => {
+                self.line_base = args.get("linesStartAt1").as_bool().map_or(1, usize::from);
+                Ok(concat!(
+                    r#"{"supportsConfigurationDoneRequest":true,"supportsConditionalBreakpoints":true,"#,
+                    r#""supportsFunctionBreakpoints":true,"supportsSetVariable":true,"supportsEvaluateForHovers":true,"#,
+                    r#""supportsReadMemoryRequest":true,"supportsWriteMemoryRequest":true,"supportsTerminateRequest":true}"#
+                )
+                .to_string())
+            }
+            "launch" => self.launch(machine, debugger, args, &mut events),
+            "setBreakpoints" => Ok(self.set_breakpoints(debugger, args)),
+            "setFunctionBreakpoints" => Ok(self.set_function_breakpoints(debugger, args)),
+            "setExceptionBreakpoints" => Ok(r#"{"breakpoints":[]}"#.to_string()),
+            "configurationDone" => {
+                if self.stop_on_entry {
+                    events.push(("stopped", stopped_body("entry", None, "")));
+                } else {
+                    self.resume();
+                }
+                Ok("{}".to_string())
+            }
+            "threads" => Ok(format!(r#"{{"threads":[{{"id":{},"name":"Z80"}}]}}"#, THREAD_ID)),
+            "stackTrace" => Ok(self.stack_trace(machine, debugger)),
+            "scopes" => Ok(format!(
+                r#"{{"scopes":[{},{},{},{}]}}"#,
+                scope("Registros", REGISTERS),
+                scope("Flags", FLAGS),
+                scope("VDP", VDP),
+                scope("Slots", SLOTS)
+            )),
+            "variables" => variables(machine, args.get("variablesReference").as_u64().unwrap_or(0))
+                .map(|items| format!(r#"{{"variables":[{}]}}"#, items.join(","))),
+            "setVariable" => set_variable(
+                machine,
+                args.get("variablesReference").as_u64().unwrap_or(0),
+                args.get("name").as_str().unwrap_or(""),
+                args.get("value").as_str().unwrap_or(""),
+            )
+            .map(|value| format!(r#"{{"value":"{}"}}"#, value)),
+            "readMemory" => self.read_memory(machine, args),
+            "writeMemory" => self.write_memory(machine, args),
+            "evaluate" => self.evaluate(machine, args.get("expression").as_str().unwrap_or("")),
+            "continue" => {
+                self.resume();
+                Ok(r#"{"allThreadsContinued":true}"#.to_string())
+            }
+            "next" | "stepIn" | "stepOut" => {
+                let limit = self.limit.unwrap_or(u64::MAX);
+                let stop = match command.as_str() {
+                    "next" => debugger.step_over(machine, limit),
+                    "stepIn" => debugger.step_into(machine),
+                    _ => debugger.step_out(machine, limit),
+                };
+                events.push(("stopped", self.stopped(debugger, &stop)));
+                Ok("{}".to_string())
+            }
+            "pause" => {
+                self.running = false;
+                events.push(("stopped", stopped_body("pause", None, "")));
+                Ok("{}".to_string())
+            }
+            "disconnect" | "terminate" => {
+                self.running = false;
+                self.done = true;
+                if command == "terminate" {
+                    events.push(("terminated", "{}".to_string()));
+                }
+                Ok("{}".to_string())
+            }
+            _ => Err(format!("Petición no soportada: {}", command)),
+        };
+        let mut messages = vec![self.response(seq, &command, result)];
+        for (event, body) in events {
+            messages.push(self.event(event, &body));
+        }
+        messages
+    }
+
+    fn launch(&mut self, machine: &mut Machine, debugger: &mut Debugger, args: &Json, events: &mut Events) -> Result<String, String> {
+        let program = args.get("program").as_str().ok_or("Falta \"program\" con el fichero .asm")?;
+        let format = args.get("format").as_str().unwrap_or("bin");
+        let format = OutputFormat::from_name(format).ok_or_else(|| format!("Formato de salida desconocido: {}", format))?;
+        let path = Path::new(program);
+        self.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let source = match args.get("source").as_str() {
+            Some(source) => source.to_string(),
+            None => std::fs::read_to_string(path).map_err(|e| format!("No se puede leer {}: {}", program, e))?,
+        };
+        let assembly = Assembler::new(format)
+            .symbols(&SymbolTable::msx())
+            .include_dir(self.dir.clone())
+            .assemble(&source)
+            .map_err(|errors| {
+                let lines: Vec<String> = errors.iter().map(|e| format!("{}:{}: {}", e.file, e.line, e.message)).collect();
+                lines.join("\n")
+            })?;
+        machine.load(assembly.origin, &assembly.code);
+        machine.cpu.regs.pc = assembly.entry;
+        machine.cpu.halted = false;
+        debugger.set_source(&assembly);
+        self.symbols = SymbolTable::msx();
+        for (name, &address) in &assembly.symbols {
+            self.symbols.insert(address, name, SymbolKind::Label);
+        }
+        self.labels = assembly.symbols;
+        self.program = program.to_string();
+        self.stop_on_entry = args.get("stopOnEntry").as_bool().unwrap_or(false);
+        // Ya hay fuente: el editor puede mandar los puntos de ruptura
+        events.push(("initialized", "{}".to_string()));
+        Ok("{}".to_string())
+    }
+
+    fn set_breakpoints(&mut self, debugger: &mut Debugger, args: &Json) -> String {
+        let source = args.get("source");
+        let path = source.get("path").as_str().or(source.get("name").as_str()).unwrap_or("");
+        let file = self.file_of(path);
+        for id in self.breakpoints.remove(&file.to_ascii_lowercase()).unwrap_or_default() {
+            debugger.remove_breakpoint(id);
+        }
+        let mut ids = Vec::new();
+        let items: Vec<String> = args
+            .get("breakpoints")
+            .as_array()
+            .iter()
+            .map(|breakpoint| {
+                let requested = breakpoint.get("line").as_u64().unwrap_or(0) as usize;
+                let line = (requested + 1).saturating_sub(self.line_base);
+                let condition = breakpoint.get("condition").as_str().unwrap_or("");
+                let added = debugger
+                    .line_address(&file, line)
+                    .ok_or_else(|| "No hay código en esa línea ni después".to_string())
+                    .and_then(|address| Ok((debugger.add_breakpoint(BreakKind::Execute(address), condition)?, address)));
+                match added {
+                    Ok((id, address)) => {
+                        ids.push(id);
+                        let line = debugger.source_line(address).map_or(line, |l| l.line);
+                        format!(r#"{{"id":{},"verified":true,"line":{}}}"#, id, self.client_line(line))
+                    }
+                    Err(e) => format!(r#"{{"verified":false,"line":{},"message":"{}"}}"#, requested, json_escape(&e)),
+                }
+            })
+            .collect();
+        self.breakpoints.insert(file.to_ascii_lowercase(), ids);
+        format!(r#"{{"breakpoints":[{}]}}"#, items.join(","))
+    }
+
+    /// Puntos de ruptura en etiquetas del programa o de la BIOS
+    fn set_function_breakpoints(&mut self, debugger: &mut Debugger, args: &Json) -> String {
+        for id in self.functions.drain(..) {
+            debugger.remove_breakpoint(id);
+        }
+        let items: Vec<String> = args
+            .get("breakpoints")
+            .as_array()
+            .iter()
+            .map(|breakpoint| {
+                let name = breakpoint.get("name").as_str().unwrap_or("");
+                let condition = breakpoint.get("condition").as_str().unwrap_or("");
+                let added = self
+                    .label(name)
+                    .ok_or_else(|| format!("Etiqueta desconocida: {}", name))
+                    .and_then(|address| debugger.add_breakpoint(BreakKind::Execute(address), condition));
+                match added {
+                    Ok(id) => {
+                        self.functions.push(id);
+                        format!(r#"{{"id":{},"verified":true}}"#, id)
+                    }
+                    Err(e) => format!(r#"{{"verified":false,"message":"{}"}}"#, json_escape(&e)),
+                }
+            })
+            .collect();
+        format!(r#"{{"breakpoints":[{}]}}"#, items.join(","))
+    }
+
+    /// Un solo marco: la instrucción actual con su línea del fuente
+    fn stack_trace(&self, machine: &Machine, debugger: &Debugger) -> String {
+        let pc = machine.cpu.regs.pc;
+        let name = self.symbols.describe(pc).unwrap_or_else(|| hex16(pc));
+        let location = match debugger.source_line(pc) {
+            Some(line) => format!(
+                r#""line":{},"column":1,"source":{{"name":"{}","path":"{}"}}"#,
+                self.client_line(line.line),
+                json_escape(&line.file),
+                json_escape(&self.path_of(&line.file))
+            ),
+            None => r#""line":0,"column":0,"presentationHint":"subtle""#.to_string(),
+        };
+        format!(
+            r#"{{"stackFrames":[{{"id":1,"name":"{}",{},"instructionPointerReference":"{}"}}],"totalFrames":1}}"#,
+            json_escape(&name),
+            location,
+            reference(pc)
+        )
+    }
+
+    fn read_memory(&self, machine: &Machine, args: &Json) -> Result<String, String> {
+        let address = self.address(args.get("memoryReference").as_str().unwrap_or(""))?;
+        let address = address.wrapping_add(args.get("offset").as_i64().unwrap_or(0) as u16);
+        let count = args.get("count").as_u64().unwrap_or(0).min(0x10000) as usize;
+        let data: Vec<u8> = (0..count).map(|i| machine.bus.peek(address.wrapping_add(i as u16))).collect();
+        Ok(format!(
+            r#"{{"address":"{}","data":"{}","unreadableBytes":0}}"#,
+            reference(address),
+            base64_encode(&data)
+        ))
+    }
+
+    fn write_memory(&self, machine: &mut Machine, args: &Json) -> Result<String, String> {
+        let address = self.address(args.get("memoryReference").as_str().unwrap_or(""))?;
+        let address = address.wrapping_add(args.get("offset").as_i64().unwrap_or(0) as u16);
+        let data = base64_decode(args.get("data").as_str().unwrap_or("")).ok_or("Datos en base64 no válidos")?;
+        machine.load(address, &data);
+        Ok(format!(r#"{{"bytesWritten":{}}}"#, data.len()))
+    }
+
+    /// Una etiqueta vale su dirección; lo demás se evalúa como una
+    /// condición del depurador (registros, PEEK, VDP...)
+    fn evaluate(&self, machine: &Machine, expression: &str) -> Result<String, String> {
+        if let Some(address) = self.label(expression.trim()) {
+            return Ok(format!(
+                r#"{{"result":"{}","variablesReference":0,"memoryReference":"{}"}}"#,
+                hex16(address),
+                reference(address)
+            ));
+        }
+        let value = Condition::parse(expression)?.value(machine, None);
+        Ok(format!(r#"{{"result":"{}","variablesReference":0}}"#, format_value(value)))
+    }
+
+    fn label(&self, name: &str) -> Option<u16> {
+        self.labels
+            .iter()
+            .find(|(label, _)| label.eq_ignore_ascii_case(name))
+            .map(|(_, &address)| address)
+            .or_else(|| self.symbols.address_of(name))
+    }
+
+    /// Referencia de memoria: "0xC000", una etiqueta o un número
+    fn address(&self, text: &str) -> Result<u16, String> {
+        let text = text.trim();
+        if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+            return u16::from_str_radix(hex, 16).map_err(|_| format!("Dirección no válida: {}", text));
+        }
+        self.label(text).map_or_else(|| number(text), Ok)
+    }
+
+    fn resume(&mut self) {
+        self.running = true;
+        self.spent = 0;
+    }
+
+    /// Correr un cuadro (o lo que quede de `limit`)
+    fn advance(&mut self, machine: &mut Machine, debugger: &mut Debugger) -> Vec<String> {
+        let slice = match self.limit {
+            Some(limit) => (limit - self.spent.min(limit)).clamp(1, FRAME_TSTATES as u64),
+            None => FRAME_TSTATES as u64,
+        };
+        let stop = debugger.run(machine, slice);
+        self.spent += stop.tstates;
+        if stop.reason == StopReason::Limit && self.limit.is_none_or(|limit| self.spent < limit) {
+            return Vec::new();
+        }
+        self.running = false;
+        let body = self.stopped(debugger, &stop);
+        vec![self.event("stopped", &body)]
+    }
+
+    fn stopped(&self, debugger: &Debugger, stop: &Stop) -> String {
+        match stop.reason {
+            StopReason::Breakpoint(id) => {
+                let kind = debugger.breakpoints().iter().find(|b| b.id == id).map(|b| b.kind);
+                match (kind, stop.access) {
+                    (Some(kind), Some(access)) if !matches!(kind, BreakKind::Execute(_)) => {
+                        let description = format!("{} {} = {}", access.kind.name(), hex16(access.address), hex8(access.value));
+                        stopped_body("data breakpoint", Some(id), &description)
+                    }
+                    _ => stopped_body("breakpoint", Some(id), ""),
+                }
+            }
+            StopReason::Limit => stopped_body("pause", None, ""),
+            StopReason::Step | StopReason::Target => stopped_body("step", None, ""),
+        }
+    }
+
+    fn response(&mut self, request_seq: u64, command: &str, result: Result<String, String>) -> String {
+        self.seq += 1;
+        let head = format!(
+            r#"{{"seq":{},"type":"response","request_seq":{},"command":"{}","#,
+            self.seq,
+            request_seq,
+            json_escape(command)
+        );
+        match result {
+            Ok(body) => format!(r#"{}"success":true,"body":{}}}"#, head, body),
+            Err(message) => format!(r#"{}"success":false,"message":"{}"}}"#, head, json_escape(&message)),
+        }
+    }
+
+    fn event(&mut self, event: &str, body: &str) -> String {
+        self.seq += 1;
+        format!(r#"{{"seq":{},"type":"event","event":"{}","body":{}}}"#, self.seq, event, body)
+    }
+
+    fn client_line(&self, line: usize) -> usize {
+        (line + self.line_base).saturating_sub(1)
+    }
+
+    /// Nombre del fichero para el ensamblador a partir de la ruta del editor
+    fn file_of(&self, path: &str) -> String {
+        let normalize = |p: &str| p.replace('\\', "/");
+        if normalize(path) == normalize(&self.program) {
+            return MAIN_SOURCE.to_string();
+        }
+        let full = Path::new(path);
+        normalize(&full.strip_prefix(&self.dir).unwrap_or(full).to_string_lossy())
+    }
+
+    fn path_of(&self, file: &str) -> String {
+        match file {
+            MAIN_SOURCE => self.program.clone(),
+            _ => self.dir.join(file).to_string_lossy().into_owned(),
+        }
+    }
+}
+
+impl Default for DapServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn stopped_body(reason: &str, breakpoint: Option<u32>, description: &str) -> String {
+    let hit = breakpoint.map_or(String::new(), |id| format!(r#","hitBreakpointIds":[{}]"#, id));
+    let description = match description {
+        "" => String::new(),
+        text => format!(r#","description":"{}""#, json_escape(text)),
+    };
+    format!(
+        r#"{{"reason":"{}","threadId":{},"allThreadsStopped":true{}{}}}"#,
+        reason, THREAD_ID, hit, description
+    )
+}
+
+fn scope(name: &str, reference: u64) -> String {
+    format!(r#"{{"name":"{}","variablesReference":{},"expensive":false}}"#, name, reference)
+}
+
+/// Dirección como referencia de memoria de DAP
+fn reference(address: u16) -> String {
+    format!("0x{:04X}", address)
+}
+
+fn format_value(value: i64) -> String {
+    match u16::try_from(value) {
+        Ok(word) => format!("{} ({})", hex16(word), value),
+        Err(_) => value.to_string(),
+    }
+}
+
+fn variable(name: &str, value: &str, memory: Option<u16>) -> String {
+    let memory = memory.map_or(String::new(), |a| format!(r#","memoryReference":"{}""#, reference(a)));
+    format!(
+        r#"{{"name":"{}","value":"{}","variablesReference":0{}}}"#,
+        json_escape(name),
+        json_escape(value),
+        memory
+    )
+}
+
+fn register_text(name: &str, value: u16) -> String {
+    match name {
+        "I" | "R" => hex8(value as u8),
+        "IM" | "IFF" => value.to_string(),
+        _ => hex16(value),
+    }
+}
+
+fn variables(machine: &Machine, reference: u64) -> Result<Vec<String>, String> {
+    let regs = &machine.cpu.regs;
+    let vdp = &machine.bus.vdp;
+    Ok(match reference {
+        REGISTERS => REGISTER_NAMES
+            .iter()
+            .map(|name| {
+                let value = regs.get(name).unwrap_or(0);
+                let memory = (!matches!(*name, "I" | "R" | "IM" | "IFF")).then_some(value);
+                variable(name, &register_text(name, value), memory)
+            })
+            .collect(),
+        FLAGS => FLAG_NAMES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| variable(name, if regs.f & (0x80 >> i) != 0 { "1" } else { "0" }, None))
+            .collect(),
+        VDP => {
+            let screen = vdp.screen_mode().map_or("?".to_string(), |s| s.to_string());
+            let mut items = vec![variable("SCREEN", &screen, None), variable("VRAM", &format!("{:05X}h", vdp.address), None)];
+            items.extend(vdp.registers.iter().enumerate().map(|(i, &v)| variable(&format!("R#{}", i), &hex8(v), None)));
+            items.extend(vdp.status.iter().enumerate().map(|(i, &v)| variable(&format!("S#{}", i), &hex8(v), None)));
+            items
+        }
+        SLOTS => {
+            let primary = machine.bus.ppi.primary_slot_register();
+            let slots = &machine.bus.slots;
+            let mut items = vec![variable("A8h", &hex8(primary), None)];
+            items.extend((0..4u16).map(|page| {
+                let slot = slots.resolve(page << 14, primary);
+                let id = match slots.is_expanded(slot.primary) {
+                    true => format!("{}-{}", slot.primary, slot.secondary),
+                    false => slot.primary.to_string(),
+                };
+                let name = format!("Página {} ({})", page, hex16(page << 14));
+                variable(&name, &format!("{} {}", id, slots.content(slot).name()), None)
+            }));
+            items
+        }
+        _ => return Err(format!("Referencia de variables desconocida: {}", reference)),
+    })
+}
+
+fn set_variable(machine: &mut Machine, reference: u64, name: &str, value: &str) -> Result<String, String> {
+    let parsed = number(value.trim())?;
+    let regs = &mut machine.cpu.regs;
+    match reference {
+        REGISTERS if regs.set(name, parsed) => Ok(register_text(name, regs.get(name).unwrap_or(0))),
+        FLAGS => {
+            let bit = FLAG_NAMES.iter().position(|f| f.eq_ignore_ascii_case(name)).ok_or("Flag desconocido")?;
+            match parsed {
+                0 => regs.f &= !(0x80 >> bit),
+                _ => regs.f |= 0x80 >> bit,
+            }
+            Ok(((regs.f >> (7 - bit)) & 1).to_string())
+        }
+        VDP => {
+            let vdp = &mut machine.bus.vdp;
+            let slot = match (name.get(..2), name.get(2..).and_then(|n| n.parse::<usize>().ok())) {
+                (Some("R#"), Some(n)) => vdp.registers.get_mut(n),
+                (Some("S#"), Some(n)) => vdp.status.get_mut(n),
+                _ => None,
+            };
+            let slot = slot.ok_or_else(|| format!("Registro del VDP desconocido: {}", name))?;
+            *slot = parsed as u8;
+            Ok(hex8(*slot))
+        }
+        SLOTS if name == "A8h" => {
+            machine.bus.io_write(0xA8, parsed as u8);
+            Ok(hex8(machine.bus.ppi.primary_slot_register()))
+        }
+        _ => Err(format!("No se puede cambiar {}", name)),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// MENSAJES
+// ═══════════════════════════════════════════════════════════════
+
+/// Mensaje con su cabecera Content-Length
+pub fn encode(message: &str) -> Vec<u8> {
+    format!("Content-Length: {}\r\n\r\n{}", message.len(), message).into_bytes()
+}
+
+/// Leer un mensaje; None al acabarse la entrada y error si es demasiado
+/// grande
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.unwrap_or(0);
+    if length > MAX_MESSAGE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Mensaje de {} bytes: el máximo es {}", length, MAX_MESSAGE),
+        ));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => out.push(BASE64[(bits >> (18 - 6 * i)) as usize & 63] as char),
+                false => out.push('='),
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut bits, mut count) = (0u32, 0);
+    for c in text.bytes().filter(|&c| !c.is_ascii_whitespace() && c != b'=') {
+        bits = (bits << 6) | BASE64.iter().position(|&b| b == c)? as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    Some(out)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// LECTOR DE JSON
+// ═══════════════════════════════════════════════════════════════
+
+/// Lo justo para leer las peticiones del editor
+#[derive(Clone, Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+static NULL: Json = Json::Null;
+
+impl Json {
+    fn parse(text: &str) -> Result<Json, String> {
+        let mut reader = JsonReader { chars: text.chars().collect(), pos: 0 };
+        let value = reader.value(0)?;
+        reader.skip();
+        match reader.pos == reader.chars.len() {
+            true => Ok(value),
+            false => Err("Sobra texto tras el JSON".to_string()),
+        }
+    }
+
+    /// Campo de un objeto, o null
+    fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map_or(&NULL, |(_, v)| v),
+            _ => &NULL,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number(n) if n.fract() == 0.0 => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        self.as_i64().and_then(|n| u64::try_from(n).ok())
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+struct JsonReader {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonReader {
+    fn skip(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip();
+        let found = self.chars.get(self.pos) == Some(&c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err("JSON demasiado anidado".to_string());
+        }
+        self.skip();
+        let invalid = || "JSON no válido".to_string();
+        match self.chars.get(self.pos).copied().ok_or_else(invalid)? {
+            '{' => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if self.eat('}') {
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip();
+                    let key = self.string()?;
+                    if !self.eat(':') {
+                        return Err(invalid());
+                    }
+                    fields.push((key, self.value(depth + 1)?));
+                    if self.eat('}') {
+                        return Ok(Json::Object(fields));
+                    }
+                    if !self.eat(',') {
+                        return Err(invalid());
+                    }
+                }
+            }
+            '[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.eat(']') {
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    if self.eat(']') {
+                        return Ok(Json::Array(items));
+                    }
+                    if !self.eat(',') {
+                        return Err(invalid());
+                    }
+                }
+            }
+            '"' => self.string().map(Json::Text),
+            c if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.chars.get(self.pos).is_some_and(|c| "+-.eE".contains(*c) || c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                text.parse().map(Json::Number).map_err(|_| invalid())
+            }
+            _ => {
+                for (word, value) in [("true", Json::Bool(true)), ("false", Json::Bool(false)), ("null", Json::Null)] {
+                    let end = self.pos + word.len();
+                    if self.chars.get(self.pos..end).is_some_and(|s| s.iter().copied().eq(word.chars())) {
+                        self.pos = end;
+                        return Ok(value);
+                    }
+                }
+                Err(invalid())
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let invalid = || "Cadena JSON no válida".to_string();
+        if self.chars.get(self.pos) != Some(&'"') {
+            return Err(invalid());
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = *self.chars.get(self.pos).ok_or_else(invalid)?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escape = *self.chars.get(self.pos).ok_or_else(invalid)?;
+                    self.pos += 1;
+                    match escape {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'u' => {
+                            let mut code = self.hex4().ok_or_else(invalid)?;
+                            // Pareja de sustitutos UTF-16
+                            if (0xD800..0xDC00).contains(&code) && self.chars.get(self.pos..self.pos + 2) == Some(&['\\', 'u']) {
+                                self.pos += 2;
+                                let low = self.hex4().ok_or_else(invalid)?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        }
+                        other => out.push(other),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits: String = self.chars.get(self.pos..self.pos + 4)?.iter().collect();
+        self.pos += 4;
+        u32::from_str_radix(&digits, 16).ok()
+    }
+}
@@ -0,0 +1,628 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  DEPURADOR                                                     ║
+//! ║  - Puntos de ruptura de ejecución, memoria, E/S y registros    ║
+//! ║    del VDP, con condición opcional                             ║
+//! ║  - Paso a paso: entrar, saltar, salir y hasta una línea        ║
+//! ║  - Edición de registros y memoria                              ║
+//! ╚════════════════════════════════════════════════════════════════╝
+//!
+//! Las condiciones son expresiones con los registros (A, HL, IX, AF'...),
+//! los flags (ZF, CF, SF, PF, HF, NF), PEEK(dir), DPEEK(dir) y VDP(n). En
+//! los puntos de acceso VALUE es el byte leído o escrito y ADDRESS la
+//! dirección, el puerto o el registro del VDP. Operadores de menor a
+//! mayor prioridad: `||`, `&&`, comparaciones (`=` `==` `<>` `!=` `<`
+//! `<=` `>` `>=`), `|` `^` `&`, `+` `-`, `*` `/` y los unarios `-` `!`
+//! `~`. Los números se escriben como en el ensamblador
+
+use crate::asm::Assembly;
+use crate::disasm::{decode, hex16, hex8, Flow};
+use crate::link::{number, range_of};
+use crate::machine::{Access, AccessKind, Machine, StepInfo};
+use crate::z80::{Registers, FLAG_C, FLAG_H, FLAG_N, FLAG_PV, FLAG_S, FLAG_Z};
+
+/// Qué vigila un punto de ruptura
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakKind {
+    /// Antes de ejecutar la instrucción de esa dirección
+    Execute(u16),
+    /// Lectura, escritura o cualquiera de las dos en un rango de memoria
+    Read(u16, u16),
+    Write(u16, u16),
+    Access(u16, u16),
+    /// IN, OUT o cualquiera de los dos en un rango de puertos
+    PortIn(u8, u8),
+    PortOut(u8, u8),
+    Port(u8, u8),
+    /// Escritura en un registro de control del VDP
+    VdpRegister(u8),
+}
+
+impl BreakKind {
+    /// Tipo ("exec", "read", "write", "access", "in", "out", "io", "vdp")
+    /// y dirección, rango ("C000h-C0FFh"), puerto o registro
+    pub fn parse(kind: &str, target: &str) -> Result<BreakKind, String> {
+        let target = target.trim();
+        let range = || -> Result<(u16, u16), String> {
+            if target.contains('-') {
+                range_of(target)
+            } else {
+                number(target).map(|n| (n, n))
+            }
+        };
+        let ports = || -> Result<(u8, u8), String> {
+            let (start, end) = range()?;
+            match (u8::try_from(start), u8::try_from(end)) {
+                (Ok(start), Ok(end)) => Ok((start, end)),
+                _ => Err(format!("Puerto fuera de 8 bits: {}", target)),
+            }
+        };
+        Ok(match kind.to_ascii_lowercase().as_str() {
+            "exec" | "execute" | "x" => BreakKind::Execute(number(target)?),
+            "read" | "r" => {
+                let (start, end) = range()?;
+                BreakKind::Read(start, end)
+            }
+            "write" | "w" => {
+                let (start, end) = range()?;
+                BreakKind::Write(start, end)
+            }
+            "access" | "rw" => {
+                let (start, end) = range()?;
+                BreakKind::Access(start, end)
+            }
+            "in" => {
+                let (start, end) = ports()?;
+                BreakKind::PortIn(start, end)
+            }
+            "out" => {
+                let (start, end) = ports()?;
+                BreakKind::PortOut(start, end)
+            }
+            "io" | "port" => {
+                let (start, end) = ports()?;
+                BreakKind::Port(start, end)
+            }
+            "vdp" => match number(target)? {
+                register @ 0..=46 => BreakKind::VdpRegister(register as u8),
+                _ => return Err(format!("Registro del VDP no válido: {}", target)),
+            },
+            _ => return Err(format!("Tipo de punto de ruptura desconocido: {}", kind)),
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BreakKind::Execute(_) => "exec",
+            BreakKind::Read(..) => "read",
+            BreakKind::Write(..) => "write",
+            BreakKind::Access(..) => "access",
+            BreakKind::PortIn(..) => "in",
+            BreakKind::PortOut(..) => "out",
+            BreakKind::Port(..) => "io",
+            BreakKind::VdpRegister(_) => "vdp",
+        }
+    }
+
+    /// Dirección, rango, puerto o registro en texto
+    pub fn target(&self) -> String {
+        let range = |start: u16, end: u16| match start == end {
+            true => hex16(start),
+            false => format!("{}-{}", hex16(start), hex16(end)),
+        };
+        let ports = |start: u8, end: u8| match start == end {
+            true => hex8(start),
+            false => format!("{}-{}", hex8(start), hex8(end)),
+        };
+        match *self {
+            BreakKind::Execute(address) => hex16(address),
+            BreakKind::Read(start, end) | BreakKind::Write(start, end) | BreakKind::Access(start, end) => {
+                range(start, end)
+            }
+            BreakKind::PortIn(start, end) | BreakKind::PortOut(start, end) | BreakKind::Port(start, end) => {
+                ports(start, end)
+            }
+            BreakKind::VdpRegister(register) => format!("R#{}", register),
+        }
+    }
+
+    /// El acceso dispara este punto de ruptura (sin mirar la condición)
+    fn matches(&self, access: &Access) -> bool {
+        let address = access.address;
+        let port = address as u8;
+        match (*self, access.kind) {
+            (BreakKind::Read(start, end), AccessKind::Read)
+            | (BreakKind::Write(start, end), AccessKind::Write)
+            | (BreakKind::Access(start, end), AccessKind::Read | AccessKind::Write) => (start..=end).contains(&address),
+            (BreakKind::PortIn(start, end), AccessKind::In)
+            | (BreakKind::PortOut(start, end), AccessKind::Out)
+            | (BreakKind::Port(start, end), AccessKind::In | AccessKind::Out) => (start..=end).contains(&port),
+            (BreakKind::VdpRegister(register), AccessKind::VdpRegister) => address == register as u16,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Breakpoint {
+    pub id: u32,
+    pub kind: BreakKind,
+    pub condition: Option<Condition>,
+    pub enabled: bool,
+    /// Veces que se ha parado aquí
+    pub hits: u32,
+}
+
+// ═══════════════════════════════════════════════════════════════
+// CONDICIONES
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Number(i64),
+    Register(String),
+    Flag(u8),
+    Value,
+    Address,
+    Peek(Box<Expr>),
+    Dpeek(Box<Expr>),
+    Vdp(Box<Expr>),
+    Unary(char, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+}
+
+/// Operadores binarios por nivel de prioridad, de menor a mayor
+const LEVELS: [&[&str]; 6] = [
+    &["||"],
+    &["&&"],
+    &["==", "<>", "!=", "<=", ">=", "=", "<", ">"],
+    &["|", "^", "&"],
+    &["+", "-"],
+    &["*", "/"],
+];
+
+/// Condición de un punto de ruptura, ya comprobada
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    text: String,
+    expr: Expr,
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Condition, String> {
+        let mut parser = Parser { tokens: tokenize(text)?, pos: 0 };
+        let expr = parser.expression(0)?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Condition { text: text.trim().to_string(), expr }),
+            Some(token) => Err(format!("Sobra {} en la condición", token)),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Valor de la condición con la máquina parada; `access` es el acceso
+    /// que disparó el punto (VALUE y ADDRESS valen 0 sin él)
+    pub fn holds(&self, machine: &Machine, access: Option<&Access>) -> bool {
+        self.value(machine, access) != 0
+    }
+
+    /// Valor numérico de la expresión (las comparaciones valen 0 o 1)
+    pub fn value(&self, machine: &Machine, access: Option<&Access>) -> i64 {
+        evaluate(&self.expr, machine, access)
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let two: String = chars[i..chars.len().min(i + 2)].iter().collect();
+        if c.is_whitespace() {
+            i += 1;
+        } else if ["||", "&&", "==", "<>", "!=", "<=", ">="].contains(&two.as_str()) {
+            tokens.push(two);
+            i += 2;
+        } else if "()=<>|^&+-*/!~".contains(c) {
+            tokens.push(c.to_string());
+            i += 1;
+        } else if c.is_ascii_alphanumeric() || c == '_' || c == '$' || c == '#' {
+            let word: String = chars[i..]
+                .iter()
+                .enumerate()
+                .take_while(|&(n, &c)| c.is_ascii_alphanumeric() || c == '_' || (n == 0 && (c == '$' || c == '#')))
+                .map(|(_, &c)| c)
+                .collect();
+            i += word.chars().count();
+            // AF', BC', DE' y HL'
+            if chars.get(i) == Some(&'\'') {
+                tokens.push(format!("{}'", word));
+                i += 1;
+            } else {
+                tokens.push(word);
+            }
+        } else {
+            return Err(format!("Carácter no válido en la condición: {}", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or("La condición está incompleta")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.next()? {
+            found if found == token => Ok(()),
+            found => Err(format!("Se esperaba {} y hay {}", token, found)),
+        }
+    }
+
+    fn expression(&mut self, level: usize) -> Result<Expr, String> {
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut left = self.expression(level + 1)?;
+        while let Some(&op) = LEVELS[level].iter().find(|&&op| self.peek() == Some(op)) {
+            self.pos += 1;
+            let right = self.expression(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(op @ ("-" | "!" | "~")) => {
+                let op = op.chars().next().unwrap_or('-');
+                self.pos += 1;
+                Ok(Expr::Unary(op, Box::new(self.unary()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self.next()?;
+        if token == "(" {
+            let inner = self.expression(0)?;
+            self.expect(")")?;
+            return Ok(inner);
+        }
+        if token.starts_with(|c: char| c.is_ascii_digit() || c == '$' || c == '#') {
+            return number(&token).map(|n| Expr::Number(n as i64));
+        }
+        let upper = token.to_ascii_uppercase();
+        let function = |parser: &mut Parser, make: fn(Box<Expr>) -> Expr| -> Result<Expr, String> {
+            parser.expect("(")?;
+            let argument = parser.expression(0)?;
+            parser.expect(")")?;
+            Ok(make(Box::new(argument)))
+        };
+        match upper.as_str() {
+            "PEEK" => function(self, Expr::Peek),
+            "DPEEK" => function(self, Expr::Dpeek),
+            "VDP" => function(self, Expr::Vdp),
+            "VALUE" => Ok(Expr::Value),
+            "ADDRESS" => Ok(Expr::Address),
+            "ZF" => Ok(Expr::Flag(FLAG_Z)),
+            "CF" => Ok(Expr::Flag(FLAG_C)),
+            "SF" => Ok(Expr::Flag(FLAG_S)),
+            "PF" | "VF" => Ok(Expr::Flag(FLAG_PV)),
+            "HF" => Ok(Expr::Flag(FLAG_H)),
+            "NF" => Ok(Expr::Flag(FLAG_N)),
+            _ if Registers::default().get(&upper).is_some() => Ok(Expr::Register(upper)),
+            _ => Err(format!("Nombre desconocido en la condición: {}", token)),
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, machine: &Machine, access: Option<&Access>) -> i64 {
+    let eval = |e: &Expr| evaluate(e, machine, access);
+    let peek = |address: i64| machine.bus.peek(address as u16) as i64;
+    match expr {
+        Expr::Number(n) => *n,
+        Expr::Register(name) => machine.cpu.regs.get(name).unwrap_or(0) as i64,
+        Expr::Flag(bit) => (machine.cpu.regs.f & bit != 0) as i64,
+        Expr::Value => access.map_or(0, |a| a.value as i64),
+        Expr::Address => access.map_or(0, |a| a.address as i64),
+        Expr::Peek(address) => peek(eval(address)),
+        Expr::Dpeek(address) => {
+            let address = eval(address);
+            peek(address) | (peek(address + 1) << 8)
+        }
+        Expr::Vdp(register) => {
+            let registers = &machine.bus.vdp.registers;
+            usize::try_from(eval(register)).ok().and_then(|r| registers.get(r)).map_or(0, |&v| v as i64)
+        }
+        Expr::Unary(op, operand) => {
+            let value = eval(operand);
+            match op {
+                '-' => value.wrapping_neg(),
+                '!' => (value == 0) as i64,
+                _ => !value & 0xFFFF,
+            }
+        }
+        Expr::Binary(op, left, right) => {
+            let (a, b) = (eval(left), eval(right));
+            match *op {
+                "||" => (a != 0 || b != 0) as i64,
+                "&&" => (a != 0 && b != 0) as i64,
+                "==" | "=" => (a == b) as i64,
+                "!=" | "<>" => (a != b) as i64,
+                "<" => (a < b) as i64,
+                "<=" => (a <= b) as i64,
+                ">" => (a > b) as i64,
+                ">=" => (a >= b) as i64,
+                "|" => a | b,
+                "^" => a ^ b,
+                "&" => a & b,
+                "+" => a.wrapping_add(b),
+                "-" => a.wrapping_sub(b),
+                "*" => a.wrapping_mul(b),
+                _ => a.checked_div(b).unwrap_or(0),
+            }
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// EJECUCIÓN CONTROLADA
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// Terminó el paso pedido
+    Step,
+    /// Saltó un punto de ruptura
+    Breakpoint(u32),
+    /// Llegó a la dirección o línea pedida
+    Target,
+    /// Se acabaron los estados T permitidos
+    Limit,
+}
+
+impl StopReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StopReason::Step => "step",
+            StopReason::Breakpoint(_) => "breakpoint",
+            StopReason::Target => "target",
+            StopReason::Limit => "limit",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stop {
+    pub reason: StopReason,
+    pub pc: u16,
+    /// Estados T ejecutados hasta parar
+    pub tstates: u64,
+    /// Acceso que disparó un punto de ruptura de memoria, E/S o VDP
+    pub access: Option<Access>,
+}
+
+/// Línea del fuente que generó código
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLine {
+    pub file: String,
+    pub line: usize,
+    pub address: u16,
+}
+
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    next_id: u32,
+    lines: Vec<SourceLine>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Debugger { breakpoints: Vec::new(), next_id: 1, lines: Vec::new() }
+    }
+
+    /// Añadir un punto de ruptura; la condición vacía es "siempre"
+    pub fn add_breakpoint(&mut self, kind: BreakKind, condition: &str) -> Result<u32, String> {
+        let condition = match condition.trim() {
+            "" => None,
+            text => Some(Condition::parse(text)?),
+        };
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push(Breakpoint { id, kind, condition, enabled: true, hits: 0 });
+        Ok(id)
+    }
+
+    pub fn remove_breakpoint(&mut self, id: u32) -> bool {
+        let before = self.breakpoints.len();
+        self.breakpoints.retain(|b| b.id != id);
+        self.breakpoints.len() != before
+    }
+
+    pub fn enable_breakpoint(&mut self, id: u32, enabled: bool) -> bool {
+        match self.breakpoints.iter_mut().find(|b| b.id == id) {
+            Some(breakpoint) => {
+                breakpoint.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Tomar del ensamblado la correspondencia entre líneas y direcciones
+    pub fn set_source(&mut self, assembly: &Assembly) {
+        self.lines = assembly
+            .lines
+            .iter()
+            .filter(|l| l.instruction && !l.bytes.is_empty())
+            .map(|l| SourceLine { file: l.file.clone(), line: l.line, address: l.address })
+            .collect();
+    }
+
+    pub fn source_lines(&self) -> &[SourceLine] {
+        &self.lines
+    }
+
+    /// Dirección de la primera línea con código desde `line` en adelante
+    pub fn line_address(&self, file: &str, line: usize) -> Option<u16> {
+        self.lines
+            .iter()
+            .filter(|l| l.file.eq_ignore_ascii_case(file) && l.line >= line)
+            .min_by_key(|l| l.line)
+            .map(|l| l.address)
+    }
+
+    /// Línea del fuente de la instrucción que empieza en `address`
+    pub fn source_line(&self, address: u16) -> Option<&SourceLine> {
+        self.lines.iter().find(|l| l.address == address)
+    }
+
+    /// Ejecutar una instrucción (o aceptar una interrupción)
+    pub fn step_into(&mut self, machine: &mut Machine) -> Stop {
+        self.execute(machine, u64::MAX, StopReason::Step, |_, _| true)
+    }
+
+    /// Como `step_into`, pero CALL, RST y las instrucciones que se repiten
+    /// se ejecutan enteras
+    pub fn step_over(&mut self, machine: &mut Machine, limit: u64) -> Stop {
+        let pc = machine.cpu.regs.pc;
+        let code: Vec<u8> = (0..4).map(|i| machine.bus.peek(pc.wrapping_add(i))).collect();
+        let instruction = decode(&code, pc);
+        let repeats = matches!(instruction.mnemonic, "LDIR" | "LDDR" | "CPIR" | "CPDR" | "INIR" | "INDR" | "OTIR" | "OTDR");
+        if !repeats && !matches!(instruction.flow, Flow::Call | Flow::ConditionalCall) {
+            return self.step_into(machine);
+        }
+        let next = pc.wrapping_add(instruction.len() as u16);
+        let sp = machine.cpu.regs.sp;
+        self.execute(machine, limit, StopReason::Step, |m, _| m.cpu.regs.pc == next && m.cpu.regs.sp >= sp)
+    }
+
+    /// Ejecutar hasta volver de la rutina actual
+    pub fn step_out(&mut self, machine: &mut Machine, limit: u64) -> Stop {
+        let sp = machine.cpu.regs.sp;
+        self.execute(machine, limit, StopReason::Step, |m, info| {
+            !info.interrupt && is_return(&m.accesses) && m.cpu.regs.sp > sp
+        })
+    }
+
+    /// Ejecutar hasta un punto de ruptura o agotar `limit` estados T
+    pub fn run(&mut self, machine: &mut Machine, limit: u64) -> Stop {
+        self.execute(machine, limit, StopReason::Limit, |_, _| false)
+    }
+
+    pub fn run_to(&mut self, machine: &mut Machine, address: u16, limit: u64) -> Stop {
+        self.execute(machine, limit, StopReason::Target, |m, _| m.cpu.regs.pc == address)
+    }
+
+    pub fn run_to_line(&mut self, machine: &mut Machine, file: &str, line: usize, limit: u64) -> Result<Stop, String> {
+        let address = self
+            .line_address(file, line)
+            .ok_or_else(|| format!("No hay código en {}:{}", file, line))?;
+        Ok(self.run_to(machine, address, limit))
+    }
+
+    /// Bucle común: los puntos de ejecución se miran antes de cada
+    /// instrucción salvo la primera (para poder seguir desde uno), los de
+    /// acceso después, y `done` decide si se llegó al destino
+    fn execute(
+        &mut self,
+        machine: &mut Machine,
+        limit: u64,
+        reason: StopReason,
+        mut done: impl FnMut(&Machine, &StepInfo) -> bool,
+    ) -> Stop {
+        let start = machine.cycles;
+        let stop = |machine: &Machine, reason: StopReason, access: Option<Access>| Stop {
+            reason,
+            pc: machine.cpu.regs.pc,
+            tstates: machine.cycles - start,
+            access,
+        };
+        let mut first = true;
+        loop {
+            if !first {
+                if let Some(id) = self.check_execute(machine) {
+                    return stop(machine, StopReason::Breakpoint(id), None);
+                }
+                if machine.cycles - start >= limit {
+                    return stop(machine, StopReason::Limit, None);
+                }
+            }
+            first = false;
+            let info = machine.step();
+            if let Some((id, access)) = self.check_accesses(machine) {
+                return stop(machine, StopReason::Breakpoint(id), Some(access));
+            }
+            if done(machine, &info) {
+                return stop(machine, reason, None);
+            }
+        }
+    }
+
+    fn check_execute(&mut self, machine: &Machine) -> Option<u32> {
+        let pc = machine.cpu.regs.pc;
+        let breakpoint = self.breakpoints.iter_mut().find(|b| {
+            b.enabled
+                && b.kind == BreakKind::Execute(pc)
+                && b.condition.as_ref().is_none_or(|c| c.holds(machine, None))
+        })?;
+        breakpoint.hits += 1;
+        Some(breakpoint.id)
+    }
+
+    fn check_accesses(&mut self, machine: &Machine) -> Option<(u32, Access)> {
+        for access in machine.accesses.iter().filter(|a| a.kind != AccessKind::Fetch) {
+            let hit = self.breakpoints.iter_mut().find(|b| {
+                b.enabled
+                    && b.kind.matches(access)
+                    && b.condition.as_ref().is_none_or(|c| c.holds(machine, Some(access)))
+            });
+            if let Some(breakpoint) = hit {
+                breakpoint.hits += 1;
+                return Some((breakpoint.id, *access));
+            }
+        }
+        None
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// La instrucción ejecutada fue RET, RET cc, RETI o RETN (por sus bytes)
+fn is_return(accesses: &[Access]) -> bool {
+    let mut code = accesses.iter().filter(|a| a.kind == AccessKind::Fetch).map(|a| a.value);
+    match (code.next(), code.next()) {
+        (Some(0xC9), _) => true,
+        (Some(op), _) if op & 0xC7 == 0xC0 => true,
+        (Some(0xED), Some(op)) => op & 0xC7 == 0x45,
+        _ => false,
+    }
+}
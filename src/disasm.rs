@@ -0,0 +1,724 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  DESENSAMBLADOR Z80                                            ║
+//! ║  - Tablas sin prefijo, CB, ED, DD/FD y DDCB/FDCB               ║
+//! ║  - Opcodes no documentados: IXH/IXL, SLL, IN F,(C), OUT (C),0  ║
+//! ║  - Etiquetas, referencias cruzadas y símbolos del MSX          ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::codemap::{ByteKind, CodeMap};
+use crate::symbols::{port_name, SymbolKind, SymbolTable};
+
+const R: [&str; 8] = ["B", "C", "D", "E", "H", "L", "(HL)", "A"];
+const RP: [&str; 4] = ["BC", "DE", "HL", "SP"];
+const RP2: [&str; 4] = ["BC", "DE", "HL", "AF"];
+const CC: [&str; 8] = ["NZ", "Z", "NC", "C", "PO", "PE", "P", "M"];
+/// Operaciones aritméticas; el booleano indica si se escribe "A,"
+const ALU: [(&str, bool); 8] = [
+    ("ADD", true),
+    ("ADC", true),
+    ("SUB", false),
+    ("SBC", true),
+    ("AND", false),
+    ("XOR", false),
+    ("OR", false),
+    ("CP", false),
+];
+const ROT: [&str; 8] = ["RLC", "RRC", "RL", "RR", "SLA", "SRA", "SLL", "SRL"];
+const ACC: [&str; 8] = ["RLCA", "RRCA", "RLA", "RRA", "DAA", "CPL", "SCF", "CCF"];
+const BLOCK: [[&str; 4]; 4] = [
+    ["LDI", "CPI", "INI", "OUTI"],
+    ["LDD", "CPD", "IND", "OUTD"],
+    ["LDIR", "CPIR", "INIR", "OTIR"],
+    ["LDDR", "CPDR", "INDR", "OTDR"],
+];
+/// Bytes por línea de datos y caracteres por línea de texto en el listado
+const DATA_LINE: usize = 8;
+const TEXT_LINE: usize = 32;
+/// Referencias mostradas junto a cada etiqueta
+const MAX_XREFS: usize = 8;
+
+/// Cómo sigue la ejecución después de una instrucción
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    Next,
+    /// JP o JR incondicional
+    Jump,
+    /// Salto condicional o DJNZ
+    Branch,
+    /// CALL o RST
+    Call,
+    ConditionalCall,
+    /// RET, RETI o RETN
+    Return,
+    ConditionalReturn,
+    /// JP (HL), JP (IX) o JP (IY): destino desconocido
+    IndirectJump,
+}
+
+impl Flow {
+    /// La instrucción siguiente puede ejecutarse a continuación
+    pub fn falls_through(&self) -> bool {
+        !matches!(self, Flow::Jump | Flow::Return | Flow::IndirectJump)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    /// Registro o condición
+    Register(&'static str),
+    /// (HL), (BC), (DE), (SP), (C), (IX)...
+    Indirect(&'static str),
+    Indexed(&'static str, i8),
+    /// Número pequeño en decimal: bit, modo de interrupción
+    Number(u8),
+    Byte(u8),
+    Word(u16),
+    /// Dirección de memoria entre paréntesis
+    Memory(u16),
+    Port(u8),
+    /// Destino de JP, JR, DJNZ o CALL
+    Target(u16),
+    Restart(u8),
+}
+
+#[derive(Clone, Debug)]
+pub struct Instruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub mnemonic: &'static str,
+    pub operands: Vec<Operand>,
+    pub flow: Flow,
+}
+
+impl Instruction {
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Byte suelto que no forma una instrucción válida
+    pub fn is_data(&self) -> bool {
+        self.mnemonic == "DB"
+    }
+
+    /// Destino de un salto, llamada o RST
+    pub fn target(&self) -> Option<u16> {
+        self.operands.iter().find_map(|op| match *op {
+            Operand::Target(address) => Some(address),
+            Operand::Restart(address) => Some(address as u16),
+            _ => None,
+        })
+    }
+
+    /// Dirección leída o escrita con (nn)
+    pub fn memory(&self) -> Option<u16> {
+        self.operands.iter().find_map(|op| match *op {
+            Operand::Memory(address) => Some(address),
+            _ => None,
+        })
+    }
+
+    /// Puerto de IN A,(n) u OUT (n),A
+    pub fn port(&self) -> Option<u8> {
+        self.operands.iter().find_map(|op| match *op {
+            Operand::Port(port) => Some(port),
+            _ => None,
+        })
+    }
+
+    /// Texto en sintaxis Zilog con los nombres de `symbols`
+    pub fn text(&self, symbols: &SymbolTable) -> String {
+        let operands: Vec<String> = self.operands.iter().map(|op| format_operand(op, symbols)).collect();
+        if operands.is_empty() {
+            self.mnemonic.to_string()
+        } else {
+            format!("{} {}", self.mnemonic, operands.join(","))
+        }
+    }
+}
+
+/// Hexadecimal de 8 bits al estilo de los ensambladores MSX ("0C9h")
+pub fn hex8(value: u8) -> String {
+    with_leading_digit(format!("{:02X}h", value))
+}
+
+pub fn hex16(value: u16) -> String {
+    with_leading_digit(format!("{:04X}h", value))
+}
+
+fn with_leading_digit(text: String) -> String {
+    if text.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        format!("0{}", text)
+    } else {
+        text
+    }
+}
+
+fn format_operand(operand: &Operand, symbols: &SymbolTable) -> String {
+    let name = |address: u16| symbols.name(address).map(str::to_string).unwrap_or_else(|| hex16(address));
+    match *operand {
+        Operand::Register(register) => register.to_string(),
+        Operand::Indirect(register) => format!("({})", register),
+        Operand::Indexed(register, d) if d < 0 => format!("({}-{})", register, hex8(d.unsigned_abs())),
+        Operand::Indexed(register, d) => format!("({}+{})", register, hex8(d as u8)),
+        Operand::Number(n) => n.to_string(),
+        Operand::Byte(value) => hex8(value),
+        // Las rutinas de la BIOS están en direcciones bajas que también
+        // son constantes corrientes: no se nombran en LD rr,nn
+        Operand::Word(value) => match symbols.get(value) {
+            Some(symbol) if symbol.kind != SymbolKind::Bios => symbol.name.clone(),
+            _ => hex16(value),
+        },
+        Operand::Memory(address) => format!("({})", name(address)),
+        Operand::Port(port) => format!("({})", port_name(port).map(str::to_string).unwrap_or_else(|| hex8(port))),
+        Operand::Target(address) => name(address),
+        Operand::Restart(address) => hex8(address),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// DECODIFICADOR
+// ═══════════════════════════════════════════════════════════════
+
+type Decoded = (&'static str, Vec<Operand>, Flow);
+
+/// Decodificar la instrucción que empieza en `code[0]`. Lo que no forma
+/// una instrucción (prefijo sin efecto, ED inválido o datos cortados) sale
+/// como DB
+pub fn decode(code: &[u8], address: u16) -> Instruction {
+    let mut decoder = Decoder { code, pos: 0, address, index: None, displacement: None, uses_index: false };
+    let (mnemonic, operands, flow, len) = match decoder.prefixed() {
+        Some((mnemonic, operands, flow)) => (mnemonic, operands, flow, decoder.pos),
+        None => ("DB", code.first().map(|&b| vec![Operand::Byte(b)]).unwrap_or_default(), Flow::Next, 1),
+    };
+    Instruction {
+        address,
+        bytes: code[..len.min(code.len())].to_vec(),
+        mnemonic,
+        operands,
+        flow,
+    }
+}
+
+struct Decoder<'a> {
+    code: &'a [u8],
+    pos: usize,
+    address: u16,
+    /// "IX" o "IY" tras un prefijo DD o FD
+    index: Option<&'static str>,
+    displacement: Option<i8>,
+    /// El prefijo cambió algún operando; si no, el prefijo va como DB
+    uses_index: bool,
+}
+
+impl Decoder<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.code.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn word(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes([self.byte()?, self.byte()?]))
+    }
+
+    fn relative(&mut self) -> Option<Operand> {
+        let d = self.byte()? as i8;
+        Some(Operand::Target(self.address.wrapping_add(self.pos as u16).wrapping_add(d as u16)))
+    }
+
+    fn indexed(&mut self, index: &'static str) -> Option<Operand> {
+        self.uses_index = true;
+        if self.displacement.is_none() {
+            self.displacement = Some(self.byte()? as i8);
+        }
+        Some(Operand::Indexed(index, self.displacement?))
+    }
+
+    /// Registro r[i]; con prefijo, H y L pasan a IXH/IXL salvo si la
+    /// instrucción ya usa (IX+d)
+    fn reg(&mut self, i: u8, has_memory: bool) -> Option<Operand> {
+        match (i, self.index) {
+            (6, Some(index)) => self.indexed(index),
+            (6, None) => Some(Operand::Indirect("HL")),
+            (4 | 5, Some(index)) if !has_memory => {
+                self.uses_index = true;
+                Some(Operand::Register(match (index, i) {
+                    ("IX", 4) => "IXH",
+                    ("IX", _) => "IXL",
+                    (_, 4) => "IYH",
+                    _ => "IYL",
+                }))
+            }
+            _ => Some(Operand::Register(R[i as usize])),
+        }
+    }
+
+    fn pair(&mut self, p: u8, table: [&'static str; 4]) -> Operand {
+        match self.index {
+            Some(index) if p == 2 => {
+                self.uses_index = true;
+                Operand::Register(index)
+            }
+            _ => Operand::Register(table[p as usize]),
+        }
+    }
+
+    fn prefixed(&mut self) -> Option<Decoded> {
+        match self.byte()? {
+            0xCB => self.cb(),
+            0xED => self.ed(),
+            prefix @ (0xDD | 0xFD) => {
+                self.index = Some(if prefix == 0xDD { "IX" } else { "IY" });
+                let decoded = match self.byte()? {
+                    0xDD | 0xED | 0xFD => None,
+                    0xCB => self.indexed_cb(),
+                    op => self.main(op),
+                };
+                decoded.filter(|_| self.uses_index)
+            }
+            op => self.main(op),
+        }
+    }
+
+    fn main(&mut self, op: u8) -> Option<Decoded> {
+        use Operand::{Byte, Indirect, Memory, Port, Register, Restart, Target, Word};
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+        let next = |mnemonic, operands| Some((mnemonic, operands, Flow::Next));
+
+        match (x, z) {
+            (0, 0) => match y {
+                0 => next("NOP", vec![]),
+                1 => next("EX", vec![Register("AF"), Register("AF'")]),
+                2 => Some(("DJNZ", vec![self.relative()?], Flow::Branch)),
+                3 => Some(("JR", vec![self.relative()?], Flow::Jump)),
+                _ => Some(("JR", vec![Register(CC[y as usize - 4]), self.relative()?], Flow::Branch)),
+            },
+            (0, 1) if q == 0 => next("LD", vec![self.pair(p, RP), Word(self.word()?)]),
+            (0, 1) => next("ADD", vec![self.pair(2, RP), self.pair(p, RP)]),
+            (0, 2) => {
+                let (memory, register) = match p {
+                    0 => (Indirect("BC"), Register("A")),
+                    1 => (Indirect("DE"), Register("A")),
+                    2 => (Memory(self.word()?), self.pair(2, RP)),
+                    _ => (Memory(self.word()?), Register("A")),
+                };
+                if q == 0 {
+                    next("LD", vec![memory, register])
+                } else {
+                    next("LD", vec![register, memory])
+                }
+            }
+            (0, 3) => next(if q == 0 { "INC" } else { "DEC" }, vec![self.pair(p, RP)]),
+            (0, 4) => next("INC", vec![self.reg(y, y == 6)?]),
+            (0, 5) => next("DEC", vec![self.reg(y, y == 6)?]),
+            (0, 6) => next("LD", vec![self.reg(y, y == 6)?, Byte(self.byte()?)]),
+            (0, _) => next(ACC[y as usize], vec![]),
+            (1, _) if op == 0x76 => next("HALT", vec![]),
+            (1, _) => {
+                let has_memory = y == 6 || z == 6;
+                next("LD", vec![self.reg(y, has_memory)?, self.reg(z, has_memory)?])
+            }
+            (2, _) => {
+                let source = self.reg(z, z == 6)?;
+                next(ALU[y as usize].0, alu_operands(y, source))
+            }
+            (_, 0) => Some(("RET", vec![Register(CC[y as usize])], Flow::ConditionalReturn)),
+            (_, 1) if q == 0 => next("POP", vec![self.pair(p, RP2)]),
+            (_, 1) => match p {
+                0 => Some(("RET", vec![], Flow::Return)),
+                1 => next("EXX", vec![]),
+                2 => {
+                    let target = match self.pair(2, RP) {
+                        Register(register) => Indirect(register),
+                        other => other,
+                    };
+                    Some(("JP", vec![target], Flow::IndirectJump))
+                }
+                _ => next("LD", vec![Register("SP"), self.pair(2, RP)]),
+            },
+            (_, 2) => Some(("JP", vec![Register(CC[y as usize]), Target(self.word()?)], Flow::Branch)),
+            (_, 3) => match y {
+                0 => Some(("JP", vec![Target(self.word()?)], Flow::Jump)),
+                2 => next("OUT", vec![Port(self.byte()?), Register("A")]),
+                3 => next("IN", vec![Register("A"), Port(self.byte()?)]),
+                4 => next("EX", vec![Indirect("SP"), self.pair(2, RP)]),
+                5 => next("EX", vec![Register("DE"), Register("HL")]),
+                6 => next("DI", vec![]),
+                7 => next("EI", vec![]),
+                _ => None,
+            },
+            (_, 4) => Some(("CALL", vec![Register(CC[y as usize]), Target(self.word()?)], Flow::ConditionalCall)),
+            (_, 5) if q == 0 => next("PUSH", vec![self.pair(p, RP2)]),
+            (_, 5) if p == 0 => Some(("CALL", vec![Target(self.word()?)], Flow::Call)),
+            (_, 5) => None,
+            (_, 6) => next(ALU[y as usize].0, alu_operands(y, Byte(self.byte()?))),
+            _ => Some(("RST", vec![Restart(y * 8)], Flow::Call)),
+        }
+    }
+
+    fn cb(&mut self) -> Option<Decoded> {
+        let op = self.byte()?;
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let target = self.reg(z, true)?;
+        Some(match x {
+            0 => (ROT[y as usize], vec![target], Flow::Next),
+            1 => ("BIT", vec![Operand::Number(y), target], Flow::Next),
+            2 => ("RES", vec![Operand::Number(y), target], Flow::Next),
+            _ => ("SET", vec![Operand::Number(y), target], Flow::Next),
+        })
+    }
+
+    /// DD CB d op: el desplazamiento va antes del opcode; con z distinto de
+    /// 6 el resultado se copia además en un registro (no documentado)
+    fn indexed_cb(&mut self) -> Option<Decoded> {
+        let index = self.index?;
+        let target = self.indexed(index)?;
+        let op = self.byte()?;
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let copy = (z != 6).then(|| Operand::Register(R[z as usize]));
+        let mut operands = match x {
+            0 => vec![target],
+            _ => vec![Operand::Number(y), target],
+        };
+        if x != 1 {
+            operands.extend(copy);
+        }
+        Some(match x {
+            0 => (ROT[y as usize], operands, Flow::Next),
+            1 => ("BIT", operands, Flow::Next),
+            2 => ("RES", operands, Flow::Next),
+            _ => ("SET", operands, Flow::Next),
+        })
+    }
+
+    fn ed(&mut self) -> Option<Decoded> {
+        use Operand::{Indirect, Memory, Number, Register};
+        let op = self.byte()?;
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+        let next = |mnemonic, operands| Some((mnemonic, operands, Flow::Next));
+
+        match (x, z) {
+            (1, 0) if y == 6 => next("IN", vec![Register("F"), Indirect("C")]),
+            (1, 0) => next("IN", vec![Register(R[y as usize]), Indirect("C")]),
+            (1, 1) if y == 6 => next("OUT", vec![Indirect("C"), Number(0)]),
+            (1, 1) => next("OUT", vec![Indirect("C"), Register(R[y as usize])]),
+            (1, 2) => next(if q == 0 { "SBC" } else { "ADC" }, vec![Register("HL"), Register(RP[p as usize])]),
+            (1, 3) if q == 0 => next("LD", vec![Memory(self.word()?), Register(RP[p as usize])]),
+            (1, 3) => next("LD", vec![Register(RP[p as usize]), Memory(self.word()?)]),
+            (1, 4) => next("NEG", vec![]),
+            (1, 5) => Some((if y == 1 { "RETI" } else { "RETN" }, vec![], Flow::Return)),
+            (1, 6) => next("IM", vec![Number([0, 0, 1, 2][y as usize & 3])]),
+            (1, 7) if y < 6 => match y {
+                0 => next("LD", vec![Register("I"), Register("A")]),
+                1 => next("LD", vec![Register("R"), Register("A")]),
+                2 => next("LD", vec![Register("A"), Register("I")]),
+                3 => next("LD", vec![Register("A"), Register("R")]),
+                4 => next("RRD", vec![]),
+                _ => next("RLD", vec![]),
+            },
+            (2, 0..=3) if y >= 4 => next(BLOCK[y as usize - 4][z as usize], vec![]),
+            _ => next("DB", vec![Operand::Byte(0xED), Operand::Byte(op)]),
+        }
+    }
+}
+
+fn alu_operands(y: u8, source: Operand) -> Vec<Operand> {
+    if ALU[y as usize].1 {
+        vec![Operand::Register("A"), source]
+    } else {
+        vec![source]
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// LISTADOS
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Debug)]
+pub enum Line {
+    Data { address: u16, bytes: Vec<u8> },
+    /// Texto ASCII según el mapa de código
+    Text { address: u16, bytes: Vec<u8> },
+    Code(Instruction),
+}
+
+impl Line {
+    pub fn address(&self) -> u16 {
+        match self {
+            Line::Data { address, .. } | Line::Text { address, .. } => *address,
+            Line::Code(instruction) => instruction.address,
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Line::Data { bytes, .. } | Line::Text { bytes, .. } => bytes,
+            Line::Code(instruction) => &instruction.bytes,
+        }
+    }
+
+    pub fn text(&self, symbols: &SymbolTable) -> String {
+        match self {
+            Line::Data { bytes, .. } => {
+                let values: Vec<String> = bytes.iter().map(|&b| hex8(b)).collect();
+                format!("DB {}", values.join(","))
+            }
+            // Las comillas no caben dentro de la cadena: van como número
+            Line::Text { bytes, .. } => {
+                let mut items: Vec<String> = Vec::new();
+                for run in bytes.chunk_by(|a, b| (*a == b'"') == (*b == b'"')) {
+                    if run[0] == b'"' {
+                        items.extend(run.iter().map(|&b| hex8(b)));
+                    } else {
+                        items.push(format!("\"{}\"", String::from_utf8_lossy(run)));
+                    }
+                }
+                format!("DB {}", items.join(","))
+            }
+            Line::Code(instruction) => instruction.text(symbols),
+        }
+    }
+}
+
+/// Resultado de desensamblar un bloque cargado en `origin`
+#[derive(Clone, Debug)]
+pub struct Disassembly {
+    pub origin: u16,
+    pub start: u16,
+    pub lines: Vec<Line>,
+    /// Símbolos de partida más las etiquetas generadas
+    pub symbols: SymbolTable,
+    /// Dirección referenciada → instrucciones que la referencian
+    pub xrefs: BTreeMap<u16, Vec<u16>>,
+}
+
+impl Disassembly {
+    /// Primera dirección fuera del bloque
+    pub fn end(&self) -> usize {
+        self.lines.last().map(|l| l.address() as usize + l.bytes().len()).unwrap_or(self.origin as usize)
+    }
+
+    fn contains(&self, address: u16) -> bool {
+        (self.origin as usize..self.end()).contains(&(address as usize))
+    }
+
+    /// Nombre de una dirección del bloque
+    pub fn label(&self, address: u16) -> Option<&str> {
+        self.symbols.name(address).filter(|_| self.contains(address))
+    }
+
+    pub fn instruction_at(&self, address: u16) -> Option<&Instruction> {
+        self.lines.iter().find_map(|line| match line {
+            Line::Code(instruction) if instruction.address == address => Some(instruction),
+            _ => None,
+        })
+    }
+
+    /// Listado ensamblable: EQU de los símbolos externos y puertos usados,
+    /// ORG y una línea por instrucción con dirección y bytes en comentario
+    pub fn listing(&self) -> String {
+        let mut out = format!(
+            "; Desensamblado de {}-{}, entrada en {}\n",
+            hex16(self.origin),
+            hex16(self.end().saturating_sub(1).max(self.origin as usize) as u16),
+            hex16(self.start)
+        );
+
+        let boundaries: BTreeSet<u16> = self.lines.iter().map(Line::address).collect();
+        let mut equates: Vec<(String, String)> = self
+            .xrefs
+            .keys()
+            .filter(|&&address| !self.contains(address) || !boundaries.contains(&address))
+            .filter_map(|&address| Some((self.symbols.name(address)?.to_string(), hex16(address))))
+            .collect();
+        let ports: BTreeSet<u8> = self
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Code(instruction) => instruction.port(),
+                _ => None,
+            })
+            .collect();
+        equates.extend(ports.into_iter().filter_map(|port| Some((port_name(port)?.to_string(), hex8(port)))));
+        if !equates.is_empty() {
+            out.push('\n');
+            for (name, value) in equates {
+                out.push_str(&format!("{:<12} EQU {}\n", name, value));
+            }
+        }
+
+        out.push_str(&format!("\n        ORG {}\n", hex16(self.origin)));
+        for line in &self.lines {
+            let address = line.address();
+            if let Some(name) = self.label(address) {
+                let label = format!("{}:", name);
+                match self.xrefs.get(&address) {
+                    Some(from) => {
+                        let mut refs: Vec<String> = from.iter().take(MAX_XREFS).map(|&a| hex16(a)).collect();
+                        if from.len() > MAX_XREFS {
+                            refs.push("...".to_string());
+                        }
+                        out.push_str(&format!("{:<40}; XREF {}\n", label, refs.join(", ")));
+                    }
+                    None => out.push_str(&format!("{}\n", label)),
+                }
+            }
+            let bytes: Vec<String> = line.bytes().iter().map(|b| format!("{:02X}", b)).collect();
+            out.push_str(&format!(
+                "        {:<31} ; {:04X}  {}\n",
+                line.text(&self.symbols),
+                address,
+                bytes.join(" ")
+            ));
+        }
+        out
+    }
+}
+
+/// Desensamblador lineal o guiado por el mapa del análisis de flujo
+pub struct Disassembler {
+    symbols: SymbolTable,
+    entries: Vec<(u16, String)>,
+}
+
+impl Disassembler {
+    pub fn new(symbols: SymbolTable) -> Self {
+        Disassembler { symbols, entries: Vec::new() }
+    }
+
+    /// Nombrar un punto de entrada conocido (INIT, STATEMENT...)
+    pub fn entry(mut self, address: u16, name: &str) -> Self {
+        self.entries.push((address, name.to_string()));
+        self
+    }
+
+    /// Lo anterior a `start` sale como datos; desde `start` hasta el final,
+    /// instrucciones
+    pub fn disassemble(&self, data: &[u8], origin: u16, start: u16) -> Disassembly {
+        let data = &data[..data.len().min(0x10000 - origin as usize)];
+        let start_offset = (start.wrapping_sub(origin) as usize).min(data.len());
+
+        let mut kinds = vec![ByteKind::Data; data.len()];
+        let mut starts = BTreeSet::new();
+        let mut offset = start_offset;
+        while offset < data.len() {
+            let len = decode(&data[offset..], origin.wrapping_add(offset as u16)).len();
+            kinds[offset..offset + len].fill(ByteKind::Code);
+            starts.insert(offset);
+            offset += len;
+        }
+        self.layout(data, origin, start, &kinds, &starts, &[])
+    }
+
+    /// `length` bytes de la ROM desde `offset`, vistos en `origin`: solo hay
+    /// instrucciones donde el recorrido las encontró; el resto sale como
+    /// texto o datos
+    pub fn disassemble_map(&self, rom: &[u8], map: &CodeMap, offset: usize, length: usize, origin: u16) -> Disassembly {
+        let end = (offset + length).min(rom.len()).min(offset + 0x10000 - origin as usize);
+        let offset = offset.min(end);
+        let starts: BTreeSet<usize> = map.instructions.range(offset..end).map(|o| o - offset).collect();
+        let address = |o: usize| origin.wrapping_add((o - offset) as u16);
+        let labels: Vec<u16> = map.labels.range(offset..end).map(|(&o, _)| address(o)).collect();
+        let start = labels.first().copied().unwrap_or(origin);
+        self.layout(&rom[offset..end], origin, start, &map.kinds[offset..end], &starts, &labels)
+    }
+
+    fn layout(
+        &self,
+        data: &[u8],
+        origin: u16,
+        start: u16,
+        kinds: &[ByteKind],
+        starts: &BTreeSet<usize>,
+        labels: &[u16],
+    ) -> Disassembly {
+        let code: BTreeMap<usize, Instruction> = starts
+            .iter()
+            .map(|&o| (o, decode(&data[o..], origin.wrapping_add(o as u16))))
+            .collect();
+
+        let end = origin as usize + data.len();
+        let in_range = |address: u16| (origin as usize..end).contains(&(address as usize));
+        let mut symbols = self.symbols.clone();
+        for (address, name) in &self.entries {
+            symbols.insert(*address, name, SymbolKind::Label);
+        }
+        if symbols.get(start).is_none() && in_range(start) {
+            symbols.insert(start, "START", SymbolKind::Label);
+        }
+        for &address in labels {
+            if symbols.get(address).is_none() {
+                symbols.insert(address, &format!("L{:04X}", address), SymbolKind::Label);
+            }
+        }
+
+        // Saltos y accesos a memoria dentro del bloque crean etiquetas; las
+        // constantes de 16 bits solo cuentan si ya tienen nombre
+        let mut xrefs: BTreeMap<u16, Vec<u16>> = BTreeMap::new();
+        for instruction in code.values() {
+            if let Some(address) = instruction.target().or(instruction.memory()) {
+                if in_range(address) && symbols.get(address).is_none() {
+                    symbols.insert(address, &format!("L{:04X}", address), SymbolKind::Label);
+                }
+                xrefs.entry(address).or_default().push(instruction.address);
+            }
+        }
+        for instruction in code.values() {
+            for operand in &instruction.operands {
+                if let Operand::Word(value) = *operand {
+                    if symbols.get(value).is_some_and(|s| s.kind != SymbolKind::Bios) {
+                        xrefs.entry(value).or_default().push(instruction.address);
+                    }
+                }
+            }
+        }
+        for from in xrefs.values_mut() {
+            from.sort_unstable();
+        }
+
+        // Los datos se cortan en cada etiqueta y cambio de clase para que
+        // cada etiqueta tenga su línea
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            if let Some(instruction) = code.get(&offset) {
+                offset += instruction.len();
+                lines.push(Line::Code(instruction.clone()));
+                continue;
+            }
+            let kind = kinds[offset];
+            let limit = if kind == ByteKind::Text { TEXT_LINE } else { DATA_LINE };
+            let mut next = offset + 1;
+            while next < data.len()
+                && next - offset < limit
+                && kinds[next] == kind
+                && !code.contains_key(&next)
+                && symbols.get(origin.wrapping_add(next as u16)).is_none()
+            {
+                next += 1;
+            }
+            let (address, bytes) = (origin.wrapping_add(offset as u16), data[offset..next].to_vec());
+            lines.push(match kind {
+                ByteKind::Text => Line::Text { address, bytes },
+                _ => Line::Data { address, bytes },
+            });
+            offset = next;
+        }
+
+        Disassembly { origin, start, lines, symbols, xrefs }
+    }
+}
+
+impl Default for Disassembler {
+    fn default() -> Self {
+        Disassembler::new(SymbolTable::msx())
+    }
+}
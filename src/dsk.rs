@@ -0,0 +1,563 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  IMÁGENES DE DISCO .DSK (FAT12)                                ║
+//! ║  - Validación del sector de arranque y BPB                     ║
+//! ║  - Listado de directorios con tamaños y fechas                 ║
+//! ║  - Extracción e inyección de ficheros                          ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::rtc::RtcDateTime;
+use std::collections::HashSet;
+
+pub const SECTOR_SIZE: usize = 512;
+pub const DISK_360K: usize = 368_640;
+pub const DISK_720K: usize = 737_280;
+
+const DIR_ENTRY_SIZE: usize = 32;
+const ATTR_VOLUME: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_ARCHIVE: u8 = 0x20;
+const FAT12_END: u16 = 0xFFF;
+
+// ═══════════════════════════════════════════════════════════════
+// GEOMETRÍA (BPB)
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiskGeometry {
+    pub bytes_per_sector: u16,
+    pub sectors_per_cluster: u8,
+    pub reserved_sectors: u16,
+    pub fat_count: u8,
+    pub root_entries: u16,
+    pub total_sectors: u16,
+    pub media: u8,
+    pub sectors_per_fat: u16,
+    pub sectors_per_track: u16,
+    pub heads: u16,
+}
+
+impl DiskGeometry {
+    /// Geometría estándar según el byte de medio (discos MSX-DOS 1 sin BPB)
+    pub fn from_media(media: u8) -> Option<DiskGeometry> {
+        let (heads, total, spf) = match media {
+            0xF8 => (1, 720, 2),
+            0xF9 => (2, 1440, 3),
+            _ => return None,
+        };
+        Some(DiskGeometry {
+            bytes_per_sector: SECTOR_SIZE as u16,
+            sectors_per_cluster: 2,
+            reserved_sectors: 1,
+            fat_count: 2,
+            root_entries: 112,
+            total_sectors: total,
+            media,
+            sectors_per_fat: spf,
+            sectors_per_track: 9,
+            heads,
+        })
+    }
+
+    fn from_boot_sector(boot: &[u8]) -> DiskGeometry {
+        let word = |i: usize| u16::from_le_bytes([boot[i], boot[i + 1]]);
+        DiskGeometry {
+            bytes_per_sector: word(0x0B),
+            sectors_per_cluster: boot[0x0D],
+            reserved_sectors: word(0x0E),
+            fat_count: boot[0x10],
+            root_entries: word(0x11),
+            total_sectors: word(0x13),
+            media: boot[0x15],
+            sectors_per_fat: word(0x16),
+            sectors_per_track: word(0x18),
+            heads: word(0x1A),
+        }
+    }
+
+    fn is_plausible(&self, image_size: usize) -> bool {
+        self.bytes_per_sector as usize == SECTOR_SIZE
+            && self.sectors_per_cluster.is_power_of_two()
+            && (1..=2).contains(&self.fat_count)
+            && self.reserved_sectors >= 1
+            && self.root_entries > 0
+            && (self.root_entries as usize).is_multiple_of(SECTOR_SIZE / DIR_ENTRY_SIZE)
+            && self.sectors_per_fat > 0
+            && self.sectors_per_track > 0
+            && (1..=2).contains(&self.heads)
+            && self.total_sectors as usize * SECTOR_SIZE <= image_size
+            && self.data_start() < self.total_sectors as usize
+    }
+
+    pub fn fat_start(&self) -> usize {
+        self.reserved_sectors as usize
+    }
+
+    pub fn root_start(&self) -> usize {
+        self.fat_start() + self.fat_count as usize * self.sectors_per_fat as usize
+    }
+
+    pub fn root_sectors(&self) -> usize {
+        self.root_entries as usize * DIR_ENTRY_SIZE / SECTOR_SIZE
+    }
+
+    pub fn data_start(&self) -> usize {
+        self.root_start() + self.root_sectors()
+    }
+
+    pub fn cluster_size(&self) -> usize {
+        self.sectors_per_cluster as usize * SECTOR_SIZE
+    }
+
+    /// Número de clusters de datos (el primero es el 2)
+    pub fn cluster_count(&self) -> usize {
+        (self.total_sectors as usize - self.data_start()) / self.sectors_per_cluster as usize
+    }
+
+    pub fn tracks(&self) -> usize {
+        self.total_sectors as usize / (self.sectors_per_track as usize * self.heads as usize)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ENTRADAS DE DIRECTORIO
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    /// Ruta con '/' como separador para los subdirectorios de MSX-DOS 2
+    pub path: String,
+    pub attributes: u8,
+    pub size: u32,
+    pub first_cluster: u16,
+    pub modified: RtcDateTime,
+}
+
+impl DirEntry {
+    pub fn is_directory(&self) -> bool {
+        self.attributes & ATTR_DIRECTORY != 0
+    }
+
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+fn decode_fat_datetime(date: u16, time: u16) -> RtcDateTime {
+    RtcDateTime {
+        year: 1980 + (date >> 9),
+        month: ((date >> 5) & 0x0F).max(1) as u8,
+        day: (date & 0x1F).max(1) as u8,
+        hour: (time >> 11) as u8,
+        minute: ((time >> 5) & 0x3F) as u8,
+        second: ((time & 0x1F) * 2) as u8,
+    }
+}
+
+fn encode_fat_datetime(dt: RtcDateTime) -> (u16, u16) {
+    let year = dt.year.saturating_sub(1980).min(127);
+    let date = (year << 9) | ((dt.month as u16) << 5) | dt.day as u16;
+    let time = ((dt.hour as u16) << 11) | ((dt.minute as u16) << 5) | (dt.second as u16 / 2);
+    (date, time)
+}
+
+/// Convertir "juego.bin" al campo 8.3 en mayúsculas con relleno de espacios
+pub fn to_short_name(name: &str) -> Result<[u8; 11], String> {
+    let upper = name.trim().to_ascii_uppercase();
+    let (base, ext) = match upper.rsplit_once('.') {
+        Some((b, e)) => (b.to_string(), e.to_string()),
+        None => (upper.clone(), String::new()),
+    };
+
+    let valid = |s: &str| {
+        s.bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b"!#$%&'()-@^_`{}~".contains(&c))
+    };
+    if base.is_empty() || base.len() > 8 || ext.len() > 3 || !valid(&base) || !valid(&ext) {
+        return Err(format!("Nombre de fichero inválido para FAT12: '{}'", name));
+    }
+
+    let mut out = [b' '; 11];
+    out[..base.len()].copy_from_slice(base.as_bytes());
+    out[8..8 + ext.len()].copy_from_slice(ext.as_bytes());
+    Ok(out)
+}
+
+fn from_short_name(raw: &[u8]) -> String {
+    let base = String::from_utf8_lossy(&raw[0..8]).trim_end().to_string();
+    let ext = String::from_utf8_lossy(&raw[8..11]).trim_end().to_string();
+    if ext.is_empty() {
+        base
+    } else {
+        format!("{}.{}", base, ext)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// IMAGEN DE DISCO
+// ═══════════════════════════════════════════════════════════════
+
+pub struct DiskImage {
+    data: Vec<u8>,
+    geometry: DiskGeometry,
+}
+
+impl DiskImage {
+    /// Validar el sector de arranque y cargar la imagen
+    pub fn from_bytes(data: &[u8]) -> Result<DiskImage, String> {
+        if data.len() < SECTOR_SIZE * 2 || !data.len().is_multiple_of(SECTOR_SIZE) {
+            return Err(format!(
+                "Tamaño de imagen de disco inválido ({} bytes)",
+                data.len()
+            ));
+        }
+
+        let bpb = DiskGeometry::from_boot_sector(&data[..SECTOR_SIZE]);
+        let geometry = if bpb.is_plausible(data.len()) {
+            bpb
+        } else {
+            // MSX-DOS 1 no siempre escribe un BPB: se recurre al byte de medio de la FAT
+            match DiskGeometry::from_media(data[SECTOR_SIZE]) {
+                Some(g) if g.is_plausible(data.len()) => g,
+                _ => {
+                    return Err(
+                        "Sector de arranque inválido: BPB incoherente y medio desconocido"
+                            .to_string(),
+                    )
+                }
+            }
+        };
+
+        Ok(DiskImage {
+            data: data.to_vec(),
+            geometry,
+        })
+    }
+
+    /// Crear una imagen formateada vacía de 360 KB (simple cara) o 720 KB
+    pub fn blank(double_sided: bool) -> DiskImage {
+        let geometry = DiskGeometry::from_media(if double_sided { 0xF9 } else { 0xF8 })
+            .expect("geometría estándar de 360/720 KB");
+        let mut data = vec![0u8; geometry.total_sectors as usize * SECTOR_SIZE];
+
+        // Sector de arranque: salto, OEM y BPB; el código queda a RET
+        data[0..3].copy_from_slice(&[0xEB, 0xFE, 0x90]);
+        data[3..11].copy_from_slice(b"MSX_DSK ");
+        data[0x0B..0x0D].copy_from_slice(&geometry.bytes_per_sector.to_le_bytes());
+        data[0x0D] = geometry.sectors_per_cluster;
+        data[0x0E..0x10].copy_from_slice(&geometry.reserved_sectors.to_le_bytes());
+        data[0x10] = geometry.fat_count;
+        data[0x11..0x13].copy_from_slice(&geometry.root_entries.to_le_bytes());
+        data[0x13..0x15].copy_from_slice(&geometry.total_sectors.to_le_bytes());
+        data[0x15] = geometry.media;
+        data[0x16..0x18].copy_from_slice(&geometry.sectors_per_fat.to_le_bytes());
+        data[0x18..0x1A].copy_from_slice(&geometry.sectors_per_track.to_le_bytes());
+        data[0x1A..0x1C].copy_from_slice(&geometry.heads.to_le_bytes());
+        data[0x1E] = 0xC9;
+
+        let mut image = DiskImage { data, geometry };
+        for fat in 0..geometry.fat_count as usize {
+            let start = (geometry.fat_start() + fat * geometry.sectors_per_fat as usize) * SECTOR_SIZE;
+            image.data[start..start + 3].copy_from_slice(&[geometry.media, 0xFF, 0xFF]);
+        }
+        image
+    }
+
+    pub fn geometry(&self) -> &DiskGeometry {
+        &self.geometry
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn sector_count(&self) -> usize {
+        self.data.len() / SECTOR_SIZE
+    }
+
+    /// Sector lógico (0 = sector de arranque)
+    pub fn sector(&self, index: usize) -> Option<&[u8]> {
+        self.data.get(index * SECTOR_SIZE..(index + 1) * SECTOR_SIZE)
+    }
+
+    pub fn write_sector(&mut self, index: usize, bytes: &[u8]) -> Result<(), String> {
+        if bytes.len() != SECTOR_SIZE {
+            return Err(format!("Un sector ocupa {} bytes, no {}", SECTOR_SIZE, bytes.len()));
+        }
+        match self.data.get_mut(index * SECTOR_SIZE..(index + 1) * SECTOR_SIZE) {
+            Some(dst) => {
+                dst.copy_from_slice(bytes);
+                Ok(())
+            }
+            None => Err(format!("Sector {} fuera de la imagen", index)),
+        }
+    }
+
+    /// Sector lógico a partir de pista, cara y sector físico (base 1)
+    pub fn logical_sector(&self, track: usize, side: usize, sector: usize) -> Option<usize> {
+        let g = &self.geometry;
+        if sector == 0 || sector > g.sectors_per_track as usize || side >= g.heads as usize {
+            return None;
+        }
+        let index = (track * g.heads as usize + side) * g.sectors_per_track as usize + sector - 1;
+        (index < self.sector_count()).then_some(index)
+    }
+
+    // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+    // FAT12
+    // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+    pub fn fat_entry(&self, cluster: u16) -> u16 {
+        let offset = self.geometry.fat_start() * SECTOR_SIZE + cluster as usize * 3 / 2;
+        let pair = u16::from_le_bytes([self.data[offset], self.data[offset + 1]]);
+        if cluster & 1 == 0 {
+            pair & 0x0FFF
+        } else {
+            pair >> 4
+        }
+    }
+
+    fn set_fat_entry(&mut self, cluster: u16, value: u16) {
+        let g = self.geometry;
+        for fat in 0..g.fat_count as usize {
+            let offset = (g.fat_start() + fat * g.sectors_per_fat as usize) * SECTOR_SIZE
+                + cluster as usize * 3 / 2;
+            let pair = u16::from_le_bytes([self.data[offset], self.data[offset + 1]]);
+            let pair = if cluster & 1 == 0 {
+                (pair & 0xF000) | (value & 0x0FFF)
+            } else {
+                (pair & 0x000F) | (value << 4)
+            };
+            self.data[offset..offset + 2].copy_from_slice(&pair.to_le_bytes());
+        }
+    }
+
+    fn is_valid_cluster(&self, cluster: u16) -> bool {
+        cluster >= 2 && (cluster as usize) < self.geometry.cluster_count() + 2
+    }
+
+    /// Cadena de clusters que empieza en `first`, protegida contra bucles
+    pub fn cluster_chain(&self, first: u16) -> Result<Vec<u16>, String> {
+        let mut chain = Vec::new();
+        let mut visited = HashSet::new();
+        let mut cluster = first;
+        while self.is_valid_cluster(cluster) {
+            if !visited.insert(cluster) {
+                return Err(format!("Cadena FAT circular desde el cluster {}", first));
+            }
+            chain.push(cluster);
+            cluster = self.fat_entry(cluster);
+        }
+        if cluster < 0xFF8 && !chain.is_empty() && cluster != 0 {
+            return Err(format!("Cluster {} fuera de rango en la FAT", cluster));
+        }
+        Ok(chain)
+    }
+
+    fn cluster_offset(&self, cluster: u16) -> usize {
+        (self.geometry.data_start()
+            + (cluster as usize - 2) * self.geometry.sectors_per_cluster as usize)
+            * SECTOR_SIZE
+    }
+
+    fn read_chain(&self, first: u16) -> Result<Vec<u8>, String> {
+        let size = self.geometry.cluster_size();
+        let mut out = Vec::new();
+        for cluster in self.cluster_chain(first)? {
+            let offset = self.cluster_offset(cluster);
+            out.extend_from_slice(&self.data[offset..offset + size]);
+        }
+        Ok(out)
+    }
+
+    pub fn free_clusters(&self) -> usize {
+        (2..self.geometry.cluster_count() as u16 + 2)
+            .filter(|&c| self.fat_entry(c) == 0)
+            .count()
+    }
+
+    /// Espacio libre en bytes
+    pub fn free_space(&self) -> usize {
+        self.free_clusters() * self.geometry.cluster_size()
+    }
+
+    // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+    // DIRECTORIOS Y FICHEROS
+    // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+    /// Todos los ficheros y subdirectorios, recorriendo el árbol completo
+    pub fn list_files(&self) -> Result<Vec<DirEntry>, String> {
+        let mut out = Vec::new();
+        let root = self.root_directory_bytes();
+        self.collect_entries(&root, "", &mut out, &mut HashSet::new(), 0)?;
+        Ok(out)
+    }
+
+    fn root_directory_bytes(&self) -> Vec<u8> {
+        let start = self.geometry.root_start() * SECTOR_SIZE;
+        self.data[start..start + self.geometry.root_sectors() * SECTOR_SIZE].to_vec()
+    }
+
+    fn collect_entries(
+        &self,
+        dir: &[u8],
+        prefix: &str,
+        out: &mut Vec<DirEntry>,
+        visited: &mut HashSet<u16>,
+        depth: usize,
+    ) -> Result<(), String> {
+        if depth > 16 {
+            return Err("Árbol de directorios demasiado profundo".to_string());
+        }
+
+        for raw in dir.chunks_exact(DIR_ENTRY_SIZE) {
+            match raw[0] {
+                0x00 => break,
+                0xE5 | b'.' => continue,
+                _ => {}
+            }
+            let attributes = raw[11];
+            if attributes & ATTR_VOLUME != 0 {
+                continue;
+            }
+
+            let word = |i: usize| u16::from_le_bytes([raw[i], raw[i + 1]]);
+            let entry = DirEntry {
+                path: format!("{}{}", prefix, from_short_name(&raw[0..11])),
+                attributes,
+                size: u32::from_le_bytes([raw[28], raw[29], raw[30], raw[31]]),
+                first_cluster: word(26),
+                modified: decode_fat_datetime(word(24), word(22)),
+            };
+
+            if entry.is_directory() && self.is_valid_cluster(entry.first_cluster) {
+                // Un directorio que apunta a uno ya recorrido (a su padre o a
+                // un cluster compartido) se lista pero no se vuelve a entrar
+                let chain = self.cluster_chain(entry.first_cluster)?;
+                if chain.iter().any(|cluster| visited.contains(cluster)) {
+                    out.push(entry);
+                    continue;
+                }
+                visited.extend(chain);
+                let sub = self.read_chain(entry.first_cluster)?;
+                let sub_prefix = format!("{}/", entry.path);
+                out.push(entry);
+                self.collect_entries(&sub, &sub_prefix, out, visited, depth + 1)?;
+            } else {
+                out.push(entry);
+            }
+        }
+        Ok(())
+    }
+
+    /// Buscar un fichero por ruta sin distinguir mayúsculas
+    pub fn find_file(&self, path: &str) -> Result<DirEntry, String> {
+        let wanted = path.trim_start_matches('/').replace('\\', "/");
+        self.list_files()?
+            .into_iter()
+            .find(|e| e.path.eq_ignore_ascii_case(&wanted))
+            .ok_or_else(|| format!("Fichero no encontrado en el disco: '{}'", path))
+    }
+
+    /// Leer el contenido completo de un fichero
+    pub fn extract_file(&self, path: &str) -> Result<Vec<u8>, String> {
+        let entry = self.find_file(path)?;
+        if entry.is_directory() {
+            return Err(format!("'{}' es un directorio", path));
+        }
+        if entry.size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut data = self.read_chain(entry.first_cluster)?;
+        if data.len() < entry.size as usize {
+            return Err(format!(
+                "Cadena FAT corta para '{}': {} de {} bytes",
+                path,
+                data.len(),
+                entry.size
+            ));
+        }
+        data.truncate(entry.size as usize);
+        Ok(data)
+    }
+
+    /// Escribir un fichero en el directorio raíz, reemplazando uno existente
+    pub fn inject_file(
+        &mut self,
+        name: &str,
+        contents: &[u8],
+        modified: RtcDateTime,
+    ) -> Result<(), String> {
+        let short = to_short_name(name)?;
+        let root_offset = self.geometry.root_start() * SECTOR_SIZE;
+        let entries = self.geometry.root_entries as usize;
+
+        // Buscar entrada existente o la primera libre
+        let mut existing = None;
+        let mut free = None;
+        for i in 0..entries {
+            let raw = &self.data[root_offset + i * DIR_ENTRY_SIZE..][..DIR_ENTRY_SIZE];
+            match raw[0] {
+                0x00 | 0xE5 => {
+                    free.get_or_insert(i);
+                }
+                _ if raw[0..11] == short && raw[11] & (ATTR_VOLUME | ATTR_DIRECTORY) == 0 => {
+                    existing = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let slot = existing
+            .or(free)
+            .ok_or_else(|| "Directorio raíz lleno".to_string())?;
+
+        // Liberar la cadena anterior antes de calcular el espacio
+        let old_chain = match existing {
+            Some(i) => {
+                let raw = &self.data[root_offset + i * DIR_ENTRY_SIZE..];
+                self.cluster_chain(u16::from_le_bytes([raw[26], raw[27]]))?
+            }
+            None => Vec::new(),
+        };
+        let cluster_size = self.geometry.cluster_size();
+        let needed = contents.len().div_ceil(cluster_size);
+        if needed > self.free_clusters() + old_chain.len() {
+            return Err(format!(
+                "Espacio insuficiente: {} bytes necesarios, {} libres",
+                contents.len(),
+                self.free_space() + old_chain.len() * cluster_size
+            ));
+        }
+        for &cluster in &old_chain {
+            self.set_fat_entry(cluster, 0);
+        }
+
+        // Reservar clusters libres en orden y enlazarlos
+        let clusters: Vec<u16> = (2..self.geometry.cluster_count() as u16 + 2)
+            .filter(|&c| self.fat_entry(c) == 0)
+            .take(needed)
+            .collect();
+        for (i, &cluster) in clusters.iter().enumerate() {
+            let next = clusters.get(i + 1).copied().unwrap_or(FAT12_END);
+            self.set_fat_entry(cluster, next);
+
+            let offset = self.cluster_offset(cluster);
+            let chunk = &contents[i * cluster_size..contents.len().min((i + 1) * cluster_size)];
+            self.data[offset..offset + cluster_size].fill(0);
+            self.data[offset..offset + chunk.len()].copy_from_slice(chunk);
+        }
+
+        let (date, time) = encode_fat_datetime(modified);
+        let first = clusters.first().copied().unwrap_or(0);
+        let entry = &mut self.data[root_offset + slot * DIR_ENTRY_SIZE..][..DIR_ENTRY_SIZE];
+        entry.fill(0);
+        entry[0..11].copy_from_slice(&short);
+        entry[11] = ATTR_ARCHIVE;
+        entry[22..24].copy_from_slice(&time.to_le_bytes());
+        entry[24..26].copy_from_slice(&date.to_le_bytes());
+        entry[26..28].copy_from_slice(&first.to_le_bytes());
+        entry[28..32].copy_from_slice(&(contents.len() as u32).to_le_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,576 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  CONTROLADOR DE DISQUETE WD2793 + INTERFAZ DISK-ROM            ║
+//! ║  Registros estilo Philips (NMS 8250/8255, VG-8235 y clónicos)  ║
+//! ║  en xFF8h-xFFFh de cada página del slot de la Disk-ROM:        ║
+//! ║  - xFF8h estado/comando  - xFF9h pista  - xFFAh sector         ║
+//! ║  - xFFBh datos  - xFFCh cara  - xFFDh unidad y motor           ║
+//! ║  - xFFFh líneas DRQ/INTRQ (lógica negativa)                    ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::dsk::{DiskImage, SECTOR_SIZE};
+
+// Bits del registro de estado
+pub const ST_BUSY: u8 = 0x01;
+pub const ST_INDEX: u8 = 0x02; // Tipo I
+pub const ST_DRQ: u8 = 0x02; // Tipo II/III
+pub const ST_TRACK0: u8 = 0x04;
+pub const ST_CRC_ERROR: u8 = 0x08;
+pub const ST_SEEK_ERROR: u8 = 0x10;
+pub const ST_RECORD_NOT_FOUND: u8 = 0x10;
+pub const ST_HEAD_LOADED: u8 = 0x20;
+pub const ST_WRITE_PROTECT: u8 = 0x40;
+pub const ST_NOT_READY: u8 = 0x80;
+
+/// Bytes que acepta un Write Track antes de dar la pista por escrita
+const RAW_TRACK_SIZE: usize = 6250;
+
+/// Pistas que recorre el cabezal como máximo
+const MAX_TRACK: u8 = 82;
+
+// ═══════════════════════════════════════════════════════════════
+// UNIDAD DE DISCO
+// ═══════════════════════════════════════════════════════════════
+
+pub struct DiskDrive {
+    image: Option<DiskImage>,
+    pub write_protected: bool,
+    changed: bool,
+    head_track: u8,
+}
+
+impl DiskDrive {
+    pub fn new() -> DiskDrive {
+        DiskDrive {
+            image: None,
+            write_protected: false,
+            changed: false,
+            head_track: 0,
+        }
+    }
+
+    /// Insertar un disco, devolviendo el anterior si lo había
+    pub fn insert(&mut self, image: DiskImage) -> Option<DiskImage> {
+        self.changed = true;
+        self.image.replace(image)
+    }
+
+    pub fn eject(&mut self) -> Option<DiskImage> {
+        if self.image.is_some() {
+            self.changed = true;
+        }
+        self.image.take()
+    }
+
+    pub fn has_disk(&self) -> bool {
+        self.image.is_some()
+    }
+
+    pub fn image(&self) -> Option<&DiskImage> {
+        self.image.as_ref()
+    }
+
+    pub fn image_mut(&mut self) -> Option<&mut DiskImage> {
+        self.image.as_mut()
+    }
+
+    /// Consultar y limpiar la marca de cambio de disco.
+    ///
+    /// El interfaz Philips no lleva la señal de cambio al bus (su rutina
+    /// DSKCHG responde "no se sabe" y DOS relee la FAT); la marca queda
+    /// para el anfitrión y para la rutina DSKCHG de una Disk-ROM parcheada.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
+
+    pub fn head_track(&self) -> u8 {
+        self.head_track
+    }
+}
+
+impl Default for DiskDrive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// CHIP WD2793
+// ═══════════════════════════════════════════════════════════════
+
+enum Transfer {
+    Idle,
+    Read { buffer: Vec<u8>, pos: usize },
+    Write { buffer: Vec<u8>, sector: usize },
+    WriteTrack { buffer: Vec<u8> },
+}
+
+pub struct Wd2793 {
+    status: u8,
+    track: u8,
+    sector: u8,
+    data: u8,
+    command: u8,
+    step_in: bool,
+    intrq: bool,
+    multiple: bool,
+    transfer: Transfer,
+    index_counter: u32,
+}
+
+impl Wd2793 {
+    pub fn new() -> Wd2793 {
+        Wd2793 {
+            status: 0,
+            track: 0,
+            sector: 1,
+            data: 0,
+            command: 0,
+            step_in: true,
+            intrq: false,
+            multiple: false,
+            transfer: Transfer::Idle,
+            index_counter: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Wd2793::new();
+    }
+
+    pub fn intrq(&self) -> bool {
+        self.intrq
+    }
+
+    pub fn drq(&self) -> bool {
+        !matches!(self.transfer, Transfer::Idle)
+    }
+
+    pub fn track_register(&self) -> u8 {
+        self.track
+    }
+
+    pub fn sector_register(&self) -> u8 {
+        self.sector
+    }
+
+    /// Leer el estado limpia INTRQ; en tipo I el bit de índice simula la rotación
+    pub fn read_status(&mut self, drive: &DiskDrive) -> u8 {
+        self.intrq = false;
+        let mut status = self.status;
+
+        if !drive.has_disk() {
+            status |= ST_NOT_READY;
+        }
+        if self.is_type1() {
+            self.index_counter = self.index_counter.wrapping_add(1);
+            status &= !(ST_INDEX | ST_TRACK0 | ST_WRITE_PROTECT);
+            if drive.has_disk() && self.index_counter % 64 < 4 {
+                status |= ST_INDEX;
+            }
+            if drive.head_track == 0 {
+                status |= ST_TRACK0;
+            }
+            if drive.write_protected {
+                status |= ST_WRITE_PROTECT;
+            }
+        } else if self.drq() {
+            status |= ST_DRQ;
+        }
+        status
+    }
+
+    pub fn write_track(&mut self, value: u8) {
+        self.track = value;
+    }
+
+    pub fn write_sector(&mut self, value: u8) {
+        self.sector = value;
+    }
+
+    pub fn read_data(&mut self, drive: &mut DiskDrive, side: u8) -> u8 {
+        if let Transfer::Read { buffer, pos } = &mut self.transfer {
+            self.data = buffer[*pos];
+            *pos += 1;
+            if *pos == buffer.len() {
+                self.finish_read(drive, side);
+            }
+        }
+        self.data
+    }
+
+    pub fn write_data(&mut self, drive: &mut DiskDrive, side: u8, value: u8) {
+        self.data = value;
+        match &mut self.transfer {
+            Transfer::Write { buffer, sector } => {
+                buffer.push(value);
+                if buffer.len() == SECTOR_SIZE {
+                    let (buffer, sector) = (std::mem::take(buffer), *sector);
+                    let result = drive
+                        .image_mut()
+                        .map(|image| image.write_sector(sector, &buffer));
+                    self.transfer = Transfer::Idle;
+                    match result {
+                        Some(Ok(())) if self.multiple => {
+                            self.sector = self.sector.wrapping_add(1);
+                            self.start_write(drive, side);
+                        }
+                        Some(Ok(())) => self.complete(0),
+                        _ => self.complete(ST_RECORD_NOT_FOUND),
+                    }
+                }
+            }
+            Transfer::WriteTrack { buffer } => {
+                buffer.push(value);
+                if buffer.len() == RAW_TRACK_SIZE {
+                    let buffer = std::mem::take(buffer);
+                    self.transfer = Transfer::Idle;
+                    self.format_track(drive, side, &buffer);
+                    self.complete(0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn write_command(&mut self, drive: &mut DiskDrive, side: u8, command: u8) {
+        // Force Interrupt se acepta incluso con el chip ocupado
+        if command & 0xF0 == 0xD0 {
+            let was_busy = self.status & ST_BUSY != 0;
+            self.transfer = Transfer::Idle;
+            self.status &= !ST_BUSY;
+            if !was_busy {
+                self.command = command;
+            }
+            self.intrq = command & 0x0F != 0;
+            return;
+        }
+        if self.status & ST_BUSY != 0 {
+            return;
+        }
+
+        self.command = command;
+        self.intrq = false;
+        self.status = ST_BUSY;
+
+        match command >> 4 {
+            0x0..=0x7 => self.type1(drive, command),
+            0x8 | 0x9 => {
+                self.multiple = command & 0x10 != 0;
+                self.start_read(drive, side);
+            }
+            0xA | 0xB => {
+                self.multiple = command & 0x10 != 0;
+                if !drive.has_disk() {
+                    self.complete(ST_NOT_READY);
+                } else if drive.write_protected {
+                    self.complete(ST_WRITE_PROTECT);
+                } else {
+                    self.start_write(drive, side);
+                }
+            }
+            0xC => self.read_address(drive, side),
+            0xE => {
+                // Read Track no se emula: se devuelve la pista vacía
+                self.transfer = Transfer::Read {
+                    buffer: vec![0x4E; RAW_TRACK_SIZE],
+                    pos: 0,
+                };
+            }
+            _ => {
+                if !drive.has_disk() {
+                    self.complete(ST_NOT_READY);
+                } else if drive.write_protected {
+                    self.complete(ST_WRITE_PROTECT);
+                } else {
+                    self.transfer = Transfer::WriteTrack { buffer: Vec::new() };
+                }
+            }
+        }
+    }
+
+    // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+    // COMANDOS
+    // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+    fn is_type1(&self) -> bool {
+        self.command & 0x80 == 0 || self.command & 0xF0 == 0xD0
+    }
+
+    /// Restore, Seek y Step: el cabezal se mueve al instante
+    fn type1(&mut self, drive: &mut DiskDrive, command: u8) {
+        let update_track = command & 0x10 != 0;
+        match command >> 5 {
+            0 => {
+                if command & 0x10 == 0 {
+                    // Restore
+                    drive.head_track = 0;
+                    self.track = 0;
+                } else {
+                    // Seek: destino en el registro de datos
+                    let delta = self.data as i16 - self.track as i16;
+                    drive.head_track = (drive.head_track as i16 + delta).clamp(0, MAX_TRACK as i16) as u8;
+                    self.step_in = delta >= 0;
+                    self.track = self.data;
+                }
+            }
+            direction => {
+                match direction {
+                    2 => self.step_in = true,
+                    3 => self.step_in = false,
+                    _ => {}
+                }
+                if self.step_in {
+                    drive.head_track = (drive.head_track + 1).min(MAX_TRACK);
+                } else {
+                    drive.head_track = drive.head_track.saturating_sub(1);
+                }
+                if update_track {
+                    self.track = if self.step_in {
+                        self.track.wrapping_add(1)
+                    } else {
+                        self.track.wrapping_sub(1)
+                    };
+                }
+            }
+        }
+
+        let mut status = if command & 0x08 != 0 { ST_HEAD_LOADED } else { 0 };
+        // Verificación: la pista del registro debe existir bajo el cabezal
+        if command & 0x04 != 0 {
+            let valid = drive
+                .image()
+                .map(|image| self.track == drive.head_track && (drive.head_track as usize) < image.geometry().tracks())
+                .unwrap_or(false);
+            if !valid {
+                status |= ST_SEEK_ERROR;
+            }
+        }
+        self.complete(status);
+    }
+
+    /// Sector lógico del ID actual, comprobando pista y cara como el chip
+    fn locate(&self, drive: &DiskDrive, side: u8) -> Result<usize, u8> {
+        let image = drive.image().ok_or(ST_NOT_READY)?;
+        if self.command & 0x02 != 0 && ((self.command >> 3) & 1) != side {
+            return Err(ST_RECORD_NOT_FOUND);
+        }
+        if self.track != drive.head_track {
+            return Err(ST_RECORD_NOT_FOUND);
+        }
+        image
+            .logical_sector(drive.head_track as usize, side as usize, self.sector as usize)
+            .ok_or(ST_RECORD_NOT_FOUND)
+    }
+
+    fn start_read(&mut self, drive: &DiskDrive, side: u8) {
+        match self.locate(drive, side) {
+            Ok(index) => {
+                let buffer = drive
+                    .image()
+                    .and_then(|image| image.sector(index))
+                    .map(|s| s.to_vec())
+                    .unwrap_or_default();
+                self.transfer = Transfer::Read { buffer, pos: 0 };
+            }
+            Err(status) => self.complete(status),
+        }
+    }
+
+    fn finish_read(&mut self, drive: &DiskDrive, side: u8) {
+        self.transfer = Transfer::Idle;
+        if self.multiple {
+            self.sector = self.sector.wrapping_add(1);
+            match self.locate(drive, side) {
+                Ok(_) => self.start_read(drive, side),
+                // Las lecturas múltiples terminan al no encontrar el siguiente sector
+                Err(status) => self.complete(status),
+            }
+        } else {
+            self.complete(0);
+        }
+    }
+
+    fn start_write(&mut self, drive: &DiskDrive, side: u8) {
+        match self.locate(drive, side) {
+            Ok(sector) => {
+                self.transfer = Transfer::Write {
+                    buffer: Vec::with_capacity(SECTOR_SIZE),
+                    sector,
+                };
+            }
+            Err(status) => self.complete(status),
+        }
+    }
+
+    /// Read Address: pista, cara, sector, tamaño y CRC del siguiente ID
+    fn read_address(&mut self, drive: &DiskDrive, side: u8) {
+        let Some(image) = drive.image() else {
+            self.complete(ST_NOT_READY);
+            return;
+        };
+        let spt = image.geometry().sectors_per_track as u32;
+        let sector = (self.index_counter % spt) as u8 + 1;
+        let id = [drive.head_track, side, sector, 2];
+        let crc = crc16_ccitt(&[0xA1, 0xA1, 0xA1, 0xFE, id[0], id[1], id[2], id[3]]);
+        let [crc_hi, crc_lo] = crc.to_be_bytes();
+        self.sector = drive.head_track;
+        self.transfer = Transfer::Read {
+            buffer: vec![id[0], id[1], id[2], id[3], crc_hi, crc_lo],
+            pos: 0,
+        };
+    }
+
+    /// Interpretar los datos de un Write Track: cada ID (F5 F5 F5 FE) fija
+    /// el sector y el DAM siguiente (FB) trae sus datos.
+    fn format_track(&mut self, drive: &mut DiskDrive, side: u8, raw: &[u8]) {
+        let head_track = drive.head_track as usize;
+        let Some(image) = drive.image_mut() else {
+            return;
+        };
+
+        let mut i = 0;
+        let mut current_id: Option<(u8, usize)> = None;
+        while i < raw.len() {
+            let after_sync = i > 0 && raw[i - 1] == 0xF5;
+            match raw[i] {
+                0xFE if after_sync && i + 4 < raw.len() => {
+                    current_id = Some((raw[i + 3], 128usize << (raw[i + 4] & 3)));
+                    i += 5;
+                }
+                0xFB if after_sync => {
+                    if let Some((sector, size)) = current_id.take() {
+                        let end = (i + 1 + size).min(raw.len());
+                        let mut data = raw[i + 1..end].to_vec();
+                        data.resize(SECTOR_SIZE, 0);
+                        if let Some(index) = image.logical_sector(head_track, side as usize, sector as usize) {
+                            // Un sector fuera de la geometría se ignora como en un disco real
+                            let _ = image.write_sector(index, &data[..SECTOR_SIZE]);
+                        }
+                        i = end;
+                    } else {
+                        i += 1;
+                    }
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    fn complete(&mut self, status: u8) {
+        self.transfer = Transfer::Idle;
+        self.status = status & !ST_BUSY;
+        self.intrq = true;
+    }
+}
+
+impl Default for Wd2793 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// CRC-CCITT de los campos ID y datos del formato MFM
+pub fn crc16_ccitt(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+// ═══════════════════════════════════════════════════════════════
+// INTERFAZ DEL CARTUCHO (ESTILO PHILIPS)
+// ═══════════════════════════════════════════════════════════════
+
+pub struct DiskController {
+    pub fdc: Wd2793,
+    pub drives: [DiskDrive; 2],
+    side: u8,
+    drive_latch: u8,
+}
+
+impl DiskController {
+    pub fn new() -> DiskController {
+        DiskController {
+            fdc: Wd2793::new(),
+            drives: [DiskDrive::new(), DiskDrive::new()],
+            side: 0,
+            drive_latch: 0,
+        }
+    }
+
+    /// ¿La dirección cae en la ventana de registros (xFF8h-xFFFh)?
+    pub fn is_register(address: u16) -> bool {
+        address & 0x3FF8 == 0x3FF8
+    }
+
+    /// Unidad seleccionada por xFFDh: 0 y 2 = A, 1 = B, 3 = ninguna
+    pub fn selected_drive(&self) -> Option<usize> {
+        match self.drive_latch & 0x03 {
+            0 | 2 => Some(0),
+            1 => Some(1),
+            _ => None,
+        }
+    }
+
+    pub fn motor_on(&self) -> bool {
+        self.drive_latch & 0x80 != 0
+    }
+
+    pub fn read(&mut self, address: u16) -> u8 {
+        let side = self.side;
+        let mut none = DiskDrive::new();
+        let drive = match self.selected_drive() {
+            Some(d) => &mut self.drives[d],
+            None => &mut none,
+        };
+
+        match address & 0x07 {
+            0 => self.fdc.read_status(drive),
+            1 => self.fdc.track_register(),
+            2 => self.fdc.sector_register(),
+            3 => self.fdc.read_data(drive, side),
+            4 => 0xFE | self.side,
+            5 => self.drive_latch,
+            6 => 0xFF,
+            _ => {
+                let mut lines = 0x3F;
+                if !self.fdc.drq() {
+                    lines |= 0x40;
+                }
+                if !self.fdc.intrq() {
+                    lines |= 0x80;
+                }
+                lines
+            }
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        let side = self.side;
+        let mut none = DiskDrive::new();
+        let drive = match self.selected_drive() {
+            Some(d) => &mut self.drives[d],
+            None => &mut none,
+        };
+
+        match address & 0x07 {
+            0 => self.fdc.write_command(drive, side, value),
+            1 => self.fdc.write_track(value),
+            2 => self.fdc.write_sector(value),
+            3 => self.fdc.write_data(drive, side, value),
+            4 => self.side = value & 0x01,
+            5 => self.drive_latch = value,
+            _ => {}
+        }
+    }
+}
+
+impl Default for DiskController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
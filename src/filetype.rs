@@ -0,0 +1,115 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  DETECCIÓN DEL TIPO DE FICHERO                                 ║
+//! ║  - Por contenido (cabecera AB, BIOS, CAS, DSK, BSAVE)          ║
+//! ║  - Por extensión cuando el contenido no es concluyente         ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::bload::BloadHeader;
+use crate::cas::CAS_HEADER;
+use crate::dsk::{DISK_360K, DISK_720K};
+
+/// Bytes del principio que bastan para reconocer cualquier tipo
+pub const SNIFF_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// Cartucho con cabecera "AB"
+    Rom,
+    /// BIOS: empieza con DI / JP
+    Bios,
+    Disk,
+    Tape,
+    /// Volcado de VRAM guardado con BSAVE ,S o formato de pantalla
+    Graphics,
+    /// Programa BLOAD
+    Binary,
+    Unknown,
+}
+
+impl FileKind {
+    pub fn from_name(name: &str) -> Option<FileKind> {
+        match name.to_ascii_lowercase().as_str() {
+            "rom" => Some(FileKind::Rom),
+            "bios" => Some(FileKind::Bios),
+            "disk" | "dsk" => Some(FileKind::Disk),
+            "tape" | "cas" => Some(FileKind::Tape),
+            "graphics" => Some(FileKind::Graphics),
+            "binary" | "bin" => Some(FileKind::Binary),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileKind::Rom => "rom",
+            FileKind::Bios => "bios",
+            FileKind::Disk => "disk",
+            FileKind::Tape => "tape",
+            FileKind::Graphics => "graphics",
+            FileKind::Binary => "binary",
+            FileKind::Unknown => "unknown",
+        }
+    }
+
+    /// Tipo sugerido por la extensión del nombre
+    pub fn from_extension(filename: &str) -> FileKind {
+        let extension = filename
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "rom" | "mx1" | "mx2" | "ri" => FileKind::Rom,
+            "dsk" | "di1" | "di2" => FileKind::Disk,
+            "cas" => FileKind::Tape,
+            "sc2" | "sc3" | "sc4" | "sc5" | "sc6" | "sc7" | "sc8" | "sca" | "scc" | "sr5"
+            | "sr7" | "sr8" | "grp" | "ge5" | "ge7" | "ge8" | "pic" => FileKind::Graphics,
+            "bin" => FileKind::Binary,
+            _ => FileKind::Unknown,
+        }
+    }
+
+    /// Tipo deducido solo del contenido
+    pub fn sniff(data: &[u8]) -> FileKind {
+        FileKind::sniff_head(data, data.len())
+    }
+
+    /// Tipo deducido de los primeros bytes (`SNIFF_SIZE` bastan) y del
+    /// tamaño total, sin tener el fichero entero
+    pub fn sniff_head(data: &[u8], size: usize) -> FileKind {
+        if data.starts_with(&CAS_HEADER) {
+            return FileKind::Tape;
+        }
+        if size == DISK_360K || size == DISK_720K {
+            return FileKind::Disk;
+        }
+        // DI + JP: el arranque de la BIOS del MSX en 0000h
+        if size >= 0x8000 && data.starts_with(&[0xF3, 0xC3]) {
+            return FileKind::Bios;
+        }
+        if data.starts_with(b"AB") {
+            return FileKind::Rom;
+        }
+        if let Some((header, _)) = BloadHeader::parse(data) {
+            // Los volcados de VRAM se guardan desde 0000h y no tienen ejecución
+            return if header.exec == 0 && header.start < 0x8000 {
+                FileKind::Graphics
+            } else {
+                FileKind::Binary
+            };
+        }
+        FileKind::Unknown
+    }
+
+    /// El contenido manda; la extensión solo decide si aquel no es concluyente
+    pub fn classify(filename: &str, data: &[u8]) -> FileKind {
+        FileKind::classify_head(filename, data, data.len())
+    }
+
+    /// Como `classify` con solo el principio del fichero
+    pub fn classify_head(filename: &str, data: &[u8], size: usize) -> FileKind {
+        match FileKind::sniff_head(data, size) {
+            FileKind::Unknown => FileKind::from_extension(filename),
+            kind => kind,
+        }
+    }
+}
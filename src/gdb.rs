@@ -0,0 +1,502 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  SERVIDOR GDB (REMOTE SERIAL PROTOCOL)                         ║
+//! ║  - Registros, memoria, puntos de ruptura y paso a paso         ║
+//! ║  - Por TCP (un cliente) o por la entrada y salida estándar     ║
+//! ║  - Ctrl-C para parar la máquina mientras corre (TCP)           ║
+//! ║  - Descripción del objetivo como arquitectura z80              ║
+//! ╚════════════════════════════════════════════════════════════════╝
+//!
+//! Los registros van en el orden del objetivo z80 de GDB: AF, BC, DE,
+//! HL, SP, PC, IX, IY, AF', BC', DE', HL' e IR, de 16 bits en little
+//! endian. Desde GDB: `set architecture z80` y `target remote :puerto`
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use crate::debugger::{BreakKind, Debugger, Stop, StopReason};
+use crate::machine::{Machine, FRAME_TSTATES};
+
+/// Registros en el orden de GDB
+pub const GDB_REGISTERS: [&str; 13] = ["AF", "BC", "DE", "HL", "SP", "PC", "IX", "IY", "AF'", "BC'", "DE'", "HL'", "IR"];
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?><!DOCTYPE target SYSTEM "gdb-target.dtd"><target version="1.0"><architecture>z80</architecture></target>"#;
+
+/// Tamaño máximo de paquete que anunciamos
+const PACKET_SIZE: usize = 0x1000;
+
+/// Conexión con GDB: además de leer y escribir, mira sin bloquear si
+/// llegó un Ctrl-C mientras la máquina corre
+pub trait Connection: Read + Write {
+    fn interrupted(&mut self) -> bool {
+        false
+    }
+}
+
+impl Connection for TcpStream {
+    fn interrupted(&mut self) -> bool {
+        let mut byte = [0u8];
+        if self.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let interrupt = matches!(self.peek(&mut byte), Ok(1)) && byte[0] == 0x03;
+        if interrupt {
+            let _ = self.read(&mut byte);
+        }
+        let _ = self.set_nonblocking(false);
+        interrupt
+    }
+}
+
+/// Entrada y salida estándar, para lanzar el servidor desde GDB con
+/// `target remote | programa`
+pub struct Stdio;
+
+impl Read for Stdio {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::stdin().read(buf)
+    }
+}
+
+impl Write for Stdio {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stdout().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+impl Connection for Stdio {}
+
+/// Respuesta a un paquete
+enum Reply {
+    Packet(String),
+    /// Responder y cerrar la sesión (D)
+    Last(String),
+    /// Cerrar sin responder (k)
+    Close,
+}
+
+pub struct GdbStub {
+    /// Puntos de GDB (tipo, dirección, longitud) → número en el depurador
+    breakpoints: HashMap<(u8, u16, u16), u32>,
+    /// Estados T como mucho por cada "c"; sin límite solo para con un
+    /// punto de ruptura o Ctrl-C
+    limit: Option<u64>,
+    ack: bool,
+    last_stop: String,
+}
+
+impl GdbStub {
+    pub fn new() -> Self {
+        GdbStub {
+            breakpoints: HashMap::new(),
+            limit: None,
+            ack: true,
+            last_stop: "S05".to_string(),
+        }
+    }
+
+    pub fn limit(mut self, tstates: u64) -> Self {
+        self.limit = Some(tstates);
+        self
+    }
+
+    /// Atender a un solo cliente en `listener` hasta que se desconecte
+    pub fn accept(&mut self, machine: &mut Machine, debugger: &mut Debugger, listener: &TcpListener) -> io::Result<()> {
+        let (mut stream, _) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        self.ack = true;
+        self.serve(machine, debugger, &mut stream)
+    }
+
+    /// Bucle del protocolo sobre una conexión: paquetes `$...#cc`, acuses
+    /// `+` y `-` y Ctrl-C
+    pub fn serve(&mut self, machine: &mut Machine, debugger: &mut Debugger, conn: &mut impl Connection) -> io::Result<()> {
+        let mut last = Vec::new();
+        loop {
+            let mut byte = [0u8];
+            let mut no_ack = false;
+            if conn.read(&mut byte)? == 0 {
+                return Ok(());
+            }
+            let reply = match byte[0] {
+                b'$' => {
+                    let Some((packet, valid)) = read_packet(conn)? else { return Ok(()) };
+                    if self.ack {
+                        conn.write_all(if valid { b"+" } else { b"-" })?;
+                    }
+                    if !valid {
+                        continue;
+                    }
+                    let packet = String::from_utf8_lossy(&packet).into_owned();
+                    no_ack = packet == "QStartNoAckMode";
+                    self.respond(machine, debugger, &packet, &mut || conn.interrupted())
+                }
+                // Ctrl-C con la máquina ya parada
+                0x03 => {
+                    self.last_stop = "S02".to_string();
+                    Reply::Packet(self.last_stop.clone())
+                }
+                b'-' => {
+                    conn.write_all(&last)?;
+                    conn.flush()?;
+                    continue;
+                }
+                _ => continue,
+            };
+            let (text, close) = match reply {
+                Reply::Packet(text) => (text, false),
+                Reply::Last(text) => (text, true),
+                Reply::Close => return Ok(()),
+            };
+            last = frame(&text);
+            conn.write_all(&last)?;
+            conn.flush()?;
+            // El OK de QStartNoAckMode aún espera acuse; a partir de ahí, no
+            if no_ack {
+                self.ack = false;
+            }
+            if close {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Respuesta a un paquete sin el marco `$...#cc`; vacía si no se
+    /// conoce, como manda el protocolo
+    pub fn handle(&mut self, machine: &mut Machine, debugger: &mut Debugger, packet: &str) -> String {
+        match self.respond(machine, debugger, packet, &mut || false) {
+            Reply::Packet(text) | Reply::Last(text) => text,
+            Reply::Close => String::new(),
+        }
+    }
+
+    fn respond(
+        &mut self,
+        machine: &mut Machine,
+        debugger: &mut Debugger,
+        packet: &str,
+        interrupted: &mut dyn FnMut() -> bool,
+    ) -> Reply {
+        let error = || "E01".to_string();
+        let (command, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        Reply::Packet(match command {
+            "?" => self.last_stop.clone(),
+            "g" => GDB_REGISTERS.iter().map(|name| hex_le(register(machine, name))).collect(),
+            "G" => match write_registers(machine, args) {
+                true => "OK".to_string(),
+                false => error(),
+            },
+            "p" => match usize::from_str_radix(args, 16).ok().and_then(|n| GDB_REGISTERS.get(n)) {
+                Some(name) => hex_le(register(machine, name)),
+                None => error(),
+            },
+            "P" => {
+                let written = args.split_once('=').and_then(|(n, value)| {
+                    let name = GDB_REGISTERS.get(usize::from_str_radix(n, 16).ok()?)?;
+                    set_register(machine, name, parse_le(value)?);
+                    Some(())
+                });
+                written.map_or_else(error, |_| "OK".to_string())
+            }
+            "m" => match address_length(args) {
+                Some((address, length)) if length <= PACKET_SIZE / 2 => (0..length)
+                    .map(|i| format!("{:02x}", machine.bus.peek(address.wrapping_add(i as u16))))
+                    .collect(),
+                _ => error(),
+            },
+            "M" => {
+                let data = args.split_once(':').and_then(|(range, data)| {
+                    let (address, length) = address_length(range)?;
+                    let bytes = parse_hex(data)?;
+                    (bytes.len() == length).then_some((address, bytes))
+                });
+                match data {
+                    Some((address, bytes)) => {
+                        machine.load(address, &bytes);
+                        "OK".to_string()
+                    }
+                    None => error(),
+                }
+            }
+            "Z" | "z" => match self.breakpoint(debugger, command == "Z", args) {
+                Some(true) => "OK".to_string(),
+                Some(false) => error(),
+                // Tipo que no soportamos
+                None => String::new(),
+            },
+            "c" | "C" | "s" | "S" => {
+                // "C sig;addr" y "S sig;addr": la señal no importa
+                let address = match command {
+                    "c" | "s" => args,
+                    _ => args.split_once(';').map_or("", |(_, address)| address),
+                };
+                if let Ok(pc) = u16::from_str_radix(address, 16) {
+                    machine.cpu.regs.pc = pc;
+                }
+                self.resume(machine, debugger, command.eq_ignore_ascii_case("s"), interrupted)
+            }
+            "v" if args == "Cont?" => "vCont;c;C;s;S".to_string(),
+            "v" if args.starts_with("Cont;") => {
+                let action = args[5..].split(';').next().unwrap_or("c");
+                self.resume(machine, debugger, action.starts_with(['s', 'S']), interrupted)
+            }
+            "v" if args.starts_with("Kill") => return Reply::Last("OK".to_string()),
+            "H" | "T" => "OK".to_string(),
+            "D" => {
+                self.release(debugger);
+                return Reply::Last("OK".to_string());
+            }
+            "k" => {
+                self.release(debugger);
+                return Reply::Close;
+            }
+            "q" | "Q" => self.query(machine, packet),
+            _ => String::new(),
+        })
+    }
+
+    fn query(&mut self, machine: &mut Machine, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!(
+                "PacketSize={:x};qXfer:features:read+;swbreak+;hwbreak+;QStartNoAckMode+",
+                PACKET_SIZE
+            );
+        }
+        if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            let Some((offset, length)) = range.split_once(',') else { return "E01".to_string() };
+            let (Ok(offset), Ok(length)) = (usize::from_str_radix(offset, 16), usize::from_str_radix(length, 16)) else {
+                return "E01".to_string();
+            };
+            let chunk = TARGET_XML.get(offset.min(TARGET_XML.len())..).unwrap_or("");
+            let more = chunk.len() > length;
+            return format!("{}{}", if more { 'm' } else { 'l' }, &chunk[..chunk.len().min(length)]);
+        }
+        if let Some(command) = packet.strip_prefix("qRcmd,") {
+            let command = parse_hex(command).map(|b| String::from_utf8_lossy(&b).trim().to_ascii_lowercase());
+            return match command.as_deref() {
+                Some("reset") => {
+                    machine.cpu.reset();
+                    self.last_stop = "S05".to_string();
+                    "OK".to_string()
+                }
+                _ => hex_text("Órdenes: reset\n"),
+            };
+        }
+        match packet {
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            "qOffsets" => "Text=0;Data=0;Bss=0".to_string(),
+            "QStartNoAckMode" => "OK".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Z/z tipo,dirección,longitud: 0 y 1 de ejecución, 2 escritura, 3
+    /// lectura y 4 acceso. None si el tipo no se soporta
+    fn breakpoint(&mut self, debugger: &mut Debugger, insert: bool, args: &str) -> Option<bool> {
+        let mut fields = args.split([',', ';']);
+        let kind: u8 = fields.next()?.parse().ok()?;
+        if kind > 4 {
+            return None;
+        }
+        let address = fields.next().and_then(|a| u16::from_str_radix(a, 16).ok());
+        let length = fields.next().and_then(|l| u16::from_str_radix(l, 16).ok());
+        let (Some(address), Some(length)) = (address, length) else { return Some(false) };
+        let key = (kind, address, length);
+        if !insert {
+            return Some(self.breakpoints.remove(&key).is_some_and(|id| debugger.remove_breakpoint(id)));
+        }
+        if self.breakpoints.contains_key(&key) {
+            return Some(true);
+        }
+        let end = address.saturating_add(length.max(1) - 1);
+        let kind = match kind {
+            0 | 1 => BreakKind::Execute(address),
+            2 => BreakKind::Write(address, end),
+            3 => BreakKind::Read(address, end),
+            _ => BreakKind::Access(address, end),
+        };
+        let id = debugger.add_breakpoint(kind, "").ok()?;
+        self.breakpoints.insert(key, id);
+        Some(true)
+    }
+
+    /// Quitar los puntos de ruptura puestos desde GDB al desconectarse
+    fn release(&mut self, debugger: &mut Debugger) {
+        for (_, id) in self.breakpoints.drain() {
+            debugger.remove_breakpoint(id);
+        }
+    }
+
+    /// Paso o continuar, por tramos de un cuadro para atender Ctrl-C
+    fn resume(
+        &mut self,
+        machine: &mut Machine,
+        debugger: &mut Debugger,
+        step: bool,
+        interrupted: &mut dyn FnMut() -> bool,
+    ) -> String {
+        let mut spent = 0u64;
+        let stop = loop {
+            if step {
+                break Some(debugger.step_into(machine));
+            }
+            let slice = self.limit.map_or(FRAME_TSTATES as u64, |l| (l - spent.min(l)).min(FRAME_TSTATES as u64));
+            let stop = debugger.run(machine, slice.max(1));
+            spent += stop.tstates;
+            if stop.reason != StopReason::Limit {
+                break Some(stop);
+            }
+            if interrupted() {
+                break None;
+            }
+            if self.limit.is_some_and(|limit| spent >= limit) {
+                break Some(stop);
+            }
+        };
+        self.last_stop = match stop {
+            Some(stop) => stop_reply(debugger, &stop),
+            None => "S02".to_string(),
+        };
+        self.last_stop.clone()
+    }
+}
+
+impl Default for GdbStub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Respuesta de parada: los puntos de memoria dicen qué dirección tocó
+fn stop_reply(debugger: &Debugger, stop: &Stop) -> String {
+    let StopReason::Breakpoint(id) = stop.reason else { return "S05".to_string() };
+    let kind = debugger.breakpoints().iter().find(|b| b.id == id).map(|b| b.kind);
+    match (kind, stop.access) {
+        (Some(BreakKind::Write(..)), Some(access)) => format!("T05watch:{:x};", access.address),
+        (Some(BreakKind::Read(..)), Some(access)) => format!("T05rwatch:{:x};", access.address),
+        (Some(BreakKind::Access(..)), Some(access)) => format!("T05awatch:{:x};", access.address),
+        (Some(BreakKind::Execute(_)), _) => "T05swbreak:;".to_string(),
+        _ => "S05".to_string(),
+    }
+}
+
+fn register(machine: &Machine, name: &str) -> u16 {
+    let regs = &machine.cpu.regs;
+    match name {
+        "IR" => (regs.i as u16) << 8 | regs.r as u16,
+        _ => regs.get(name).unwrap_or(0),
+    }
+}
+
+fn set_register(machine: &mut Machine, name: &str, value: u16) {
+    let regs = &mut machine.cpu.regs;
+    match name {
+        "IR" => {
+            regs.i = (value >> 8) as u8;
+            regs.r = value as u8;
+        }
+        _ => {
+            regs.set(name, value);
+        }
+    }
+}
+
+fn write_registers(machine: &mut Machine, hex: &str) -> bool {
+    if hex.len() < GDB_REGISTERS.len() * 4 {
+        return false;
+    }
+    let values: Option<Vec<u16>> = (0..GDB_REGISTERS.len()).map(|i| parse_le(hex.get(i * 4..i * 4 + 4)?)).collect();
+    let Some(values) = values else { return false };
+    for (name, value) in GDB_REGISTERS.iter().zip(values) {
+        set_register(machine, name, value);
+    }
+    true
+}
+
+/// Palabra en hexadecimal con el byte bajo primero
+fn hex_le(value: u16) -> String {
+    format!("{:02x}{:02x}", value & 0xFF, value >> 8)
+}
+
+fn parse_le(hex: &str) -> Option<u16> {
+    let bytes = parse_hex(hex)?;
+    match bytes[..] {
+        [low] => Some(low as u16),
+        [low, high] => Some(u16::from_le_bytes([low, high])),
+        _ => None,
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn hex_text(text: &str) -> String {
+    text.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// "dirección,longitud" en hexadecimal
+fn address_length(args: &str) -> Option<(u16, usize)> {
+    let (address, length) = args.split_once(',')?;
+    Some((u16::from_str_radix(address, 16).ok()?, usize::from_str_radix(length, 16).ok()?))
+}
+
+/// Paquete con su marco: `$datos#cc`, escapando `$`, `#`, `}` y `*`
+pub fn frame(packet: &str) -> Vec<u8> {
+    let mut body = Vec::with_capacity(packet.len() + 4);
+    for &byte in packet.as_bytes() {
+        match byte {
+            b'$' | b'#' | b'}' | b'*' => body.extend([b'}', byte ^ 0x20]),
+            _ => body.push(byte),
+        }
+    }
+    let sum = body.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+    let mut out = vec![b'$'];
+    out.extend(body);
+    out.extend(format!("#{:02x}", sum).bytes());
+    out
+}
+
+/// Leer tras el `$` hasta `#cc`; devuelve los datos sin escapes y si la
+/// suma cuadra. None si se cierra la conexión a medias
+fn read_packet(conn: &mut impl Read) -> io::Result<Option<(Vec<u8>, bool)>> {
+    let mut raw = Vec::new();
+    let mut byte = [0u8];
+    loop {
+        if conn.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        if byte[0] == b'#' {
+            break;
+        }
+        raw.push(byte[0]);
+    }
+    let mut checksum = [0u8; 2];
+    for digit in checksum.iter_mut() {
+        if conn.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        *digit = byte[0];
+    }
+    let sum = raw.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+    let valid = std::str::from_utf8(&checksum).ok().and_then(|c| u8::from_str_radix(c, 16).ok()) == Some(sum);
+    let mut packet = Vec::with_capacity(raw.len());
+    let mut bytes = raw.into_iter();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'}' => packet.push(bytes.next().unwrap_or(0) ^ 0x20),
+            _ => packet.push(byte),
+        }
+    }
+    Ok(Some((packet, valid)))
+}
@@ -0,0 +1,377 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  BUSCADOR DE GRÁFICOS EN ROMS                                  ║
+//! ║  - Ventanas deslizantes por ROM o por banco de MegaROM         ║
+//! ║  - Entropía, repetición de tiles y coherencia de paleta        ║
+//! ║  - Sondeo de flujos comprimidos en las zonas de alta entropía  ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::cdl::{uploads, CDL_CODE};
+use crate::codemap::{ByteKind, CodeMap};
+use crate::packers::{self, Packer};
+use crate::screen::ScreenMode;
+
+/// Tamaño de la ventana de análisis y paso entre ventanas
+pub const WINDOW: usize = 1024;
+pub const STEP: usize = 256;
+/// Banco por defecto de las MegaROM (ASCII 8K y Konami)
+pub const MEGAROM_BANK: usize = 0x2000;
+/// Puntuación mínima para proponer una región
+const MIN_SCORE: u32 = 50;
+/// Rachas de un mismo byte a partir de las cuales se consideran relleno
+const PADDING_RUN: usize = 32;
+/// Entropía a partir de la cual se buscan flujos comprimidos
+const PACKED_ENTROPY: f32 = 6.0;
+/// Tamaño mínimo de salida para dar por bueno un flujo comprimido
+const MIN_PACKED_OUTPUT: usize = 512;
+/// Fracción de código a partir de la cual se descarta una región
+const MAX_CODE_FRACTION: f32 = 0.125;
+
+/// Opcodes Z80 más frecuentes en código real (CALL, RET, LD, JR, PUSH...)
+pub(crate) const COMMON_OPCODES: [u8; 36] = [
+    0xCD, 0xC9, 0x3E, 0x21, 0x11, 0x01, 0x32, 0x3A, 0xC3, 0x18, 0x20, 0x28, 0x30, 0x38, 0x10, 0x7E,
+    0x77, 0x23, 0xE5, 0xE1, 0xD5, 0xD1, 0xC5, 0xC1, 0xF5, 0xF1, 0xAF, 0xFE, 0x06, 0x0E, 0x16, 0x1E,
+    0xED, 0xDD, 0xFD, 0xCB,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// Bitmap de 4 bits por píxel (SCREEN 5/7)
+    Raw4bpp,
+    /// Patrones de 8x8 de SCREEN 2 / 4
+    Screen2Patterns,
+    /// Patrones de sprites de 16x16
+    SpritePatterns,
+    Compressed { packer: Packer, screen: Option<ScreenMode> },
+}
+
+impl DecodeMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DecodeMode::Raw4bpp => "raw4bpp",
+            DecodeMode::Screen2Patterns => "sc2",
+            DecodeMode::SpritePatterns => "sprites",
+            DecodeMode::Compressed { .. } => "compressed",
+        }
+    }
+}
+
+/// Región candidata; `offset` es relativo al fichero y `bank_offset` al banco
+#[derive(Clone, Debug)]
+pub struct GraphicsRegion {
+    pub bank: usize,
+    pub offset: usize,
+    pub bank_offset: usize,
+    pub length: usize,
+    pub mode: DecodeMode,
+    /// 0-100
+    pub score: u32,
+    pub entropy: f32,
+}
+
+/// Entropía de Shannon en bits por byte (0-8)
+pub fn entropy(data: &[u8]) -> f32 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u32; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let total = data.len() as f32;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f32 / total;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// Medidas de una ventana, todas normalizadas a 0-1 salvo la entropía
+struct WindowStats {
+    entropy: f32,
+    /// Fracción de la ventana ocupada por relleno
+    padding: f32,
+    /// Fracción del byte más repetido (relleno si domina)
+    fill: f32,
+    zeros: f32,
+    /// Cambios de bit medios dentro de cada byte, dividido entre 7
+    transitions: f32,
+    /// Tiles de 8 bytes que se repiten dentro de la ventana
+    tile_repeat: f32,
+    /// Nibbles iguales a su vecino, sin contar 00h ni FFh
+    nibble_coherence: f32,
+    /// Valores de nibble distintos en los bytes coherentes
+    nibble_colors: usize,
+    opcodes: f32,
+}
+
+impl WindowStats {
+    fn measure(window: &[u8]) -> WindowStats {
+        // Las rachas largas de un mismo byte son relleno y no cuentan
+        let mut content = Vec::with_capacity(window.len());
+        for run in window.chunk_by(|a, b| a == b) {
+            if run.len() < PADDING_RUN {
+                content.extend_from_slice(run);
+            }
+        }
+        let padding = 1.0 - content.len() as f32 / window.len() as f32;
+        let window = &content[..];
+        let total = window.len().max(1) as f32;
+        let mut counts = [0u32; 256];
+        let mut transitions = 0;
+        let mut opcodes = 0;
+        for &byte in window {
+            counts[byte as usize] += 1;
+            transitions += (byte ^ (byte >> 1)).count_ones() - (byte >> 7) as u32;
+            opcodes += COMMON_OPCODES.contains(&byte) as u32;
+        }
+
+        let mut tiles: Vec<&[u8]> = window.chunks_exact(8).collect();
+        let tile_count = tiles.len().max(1) as f32;
+        tiles.sort_unstable();
+        let repeated = (0..tiles.len())
+            .filter(|&i| (i > 0 && tiles[i - 1] == tiles[i]) || (i + 1 < tiles.len() && tiles[i + 1] == tiles[i]))
+            .count();
+
+        let mut coherent = 0;
+        let mut colored = 0;
+        let mut colors = 0u16;
+        for (i, &byte) in window.iter().enumerate() {
+            if byte == 0x00 || byte == 0xFF {
+                continue;
+            }
+            colored += 1;
+            let follows = i > 0 && window[i - 1] & 0x0F == byte >> 4;
+            if byte >> 4 == byte & 0x0F || follows {
+                coherent += 1;
+                colors |= (1 << (byte >> 4)) | (1 << (byte & 0x0F));
+            }
+        }
+
+        WindowStats {
+            entropy: entropy(window),
+            padding,
+            fill: *counts.iter().max().unwrap() as f32 / total,
+            zeros: counts[0] as f32 / total,
+            transitions: transitions as f32 / total / 7.0,
+            tile_repeat: repeated as f32 / tile_count,
+            nibble_coherence: if colored > 0 { coherent as f32 / colored as f32 } else { 0.0 },
+            nibble_colors: colors.count_ones() as usize,
+            opcodes: opcodes as f32 / total,
+        }
+    }
+
+    /// Mejor modo sin compresión y su puntuación
+    fn classify(&self) -> Option<(DecodeMode, u32)> {
+        if self.padding > 0.5 || self.fill > 0.7 || self.entropy < 0.5 {
+            return None;
+        }
+        let ramp = |value: f32, low: f32, high: f32| ((value - low) / (high - low)).clamp(0.0, 1.0);
+        // El código está lleno de opcodes comunes (~14% en datos al azar);
+        // 18h, 7Eh o C3h también son filas de dibujo habituales, así que
+        // solo penaliza cuando abundan mucho
+        let not_code = 1.0 - ramp(self.opcodes, 0.30, 0.50);
+        // Los dibujos tienen pocos cambios de bit dentro de cada byte
+        let drawn = 1.0 - ramp(self.transitions, 0.2, 0.45);
+        let repeat = ramp(self.tile_repeat, 0.0, 0.5);
+
+        let patterns = (0.5 * drawn + 0.5 * repeat) * not_code;
+        let bitmap = if self.nibble_colors >= 3 {
+            ramp(self.nibble_coherence, 0.2, 0.6) * not_code
+        } else {
+            0.0
+        };
+        let sprites = drawn * ramp(self.zeros, 0.15, 0.35) * (1.0 - 0.6 * repeat) * not_code;
+
+        [
+            (DecodeMode::Screen2Patterns, patterns),
+            (DecodeMode::Raw4bpp, bitmap),
+            (DecodeMode::SpritePatterns, sprites),
+        ]
+        .into_iter()
+        .map(|(mode, score)| (mode, (score * 100.0).round() as u32))
+        .max_by_key(|&(_, score)| score)
+        .filter(|&(_, score)| score >= MIN_SCORE)
+    }
+}
+
+/// Analizador de gráficos sobre una ROM completa o troceada en bancos
+pub struct GraphicsFinder {
+    bank_size: usize,
+    /// Bytes que el análisis de flujo marcó como código
+    code: Vec<bool>,
+    /// Tramos que un CDL vio subir a la VRAM: (posición, longitud)
+    uploads: Vec<(usize, usize)>,
+}
+
+impl GraphicsFinder {
+    /// `bank_size` 0 elige solo: bancos de 8 KB si la ROM no cabe en 48 KB
+    pub fn new(bank_size: usize) -> Self {
+        GraphicsFinder { bank_size, code: Vec::new(), uploads: Vec::new() }
+    }
+
+    /// Descartar las regiones que son sobre todo código según `map`
+    pub fn skip_code(mut self, map: &CodeMap) -> Self {
+        self.code = map.kinds.iter().map(|&k| k == ByteKind::Code).collect();
+        self
+    }
+
+    /// Usar las marcas de un CDL: lo ejecutado cuenta como código y lo que
+    /// se subió a la VRAM sale como región segura, con puntuación 100
+    pub fn hints(mut self, marks: &[u8]) -> Self {
+        if self.code.len() < marks.len() {
+            self.code.resize(marks.len(), false);
+        }
+        for (code, &mark) in self.code.iter_mut().zip(marks) {
+            *code |= mark & CDL_CODE != 0;
+        }
+        self.uploads = uploads(marks);
+        self
+    }
+
+    fn bank_size_for(&self, length: usize) -> usize {
+        match self.bank_size {
+            0 if length > 0xC000 => MEGAROM_BANK,
+            0 => length.max(1),
+            size => size,
+        }
+    }
+
+    fn banks<'a>(&self, rom: &'a [u8]) -> Vec<&'a [u8]> {
+        rom.chunks(self.bank_size_for(rom.len())).collect()
+    }
+
+    /// Regiones candidatas ordenadas de mayor a menor puntuación, con las
+    /// subidas a la VRAM de las pistas del CDL delante
+    pub fn find(&self, rom: &[u8]) -> Vec<GraphicsRegion> {
+        let mut regions = Vec::new();
+        let mut base = 0;
+        for (bank, data) in self.banks(rom).into_iter().enumerate() {
+            regions.extend(scan_bank(data).into_iter().map(|mut region| {
+                region.bank = bank;
+                region.offset = base + region.bank_offset;
+                region
+            }));
+            base += data.len();
+        }
+        if !self.code.is_empty() {
+            regions.retain(|region| {
+                let end = (region.offset + region.length).min(self.code.len());
+                let code = self.code.get(region.offset..end).map_or(0, |c| c.iter().filter(|&&b| b).count());
+                code as f32 <= region.length as f32 * MAX_CODE_FRACTION
+            });
+        }
+        regions.sort_by(|a, b| b.score.cmp(&a.score).then(a.offset.cmp(&b.offset)));
+        // Lo visto en la VRAM va primero y sustituye a lo que se solape
+        let size = self.bank_size_for(rom.len());
+        let mut seen = Vec::new();
+        for &(offset, length) in &self.uploads {
+            let Some(data) = rom.get(offset..(offset + length).min(rom.len())) else {
+                continue;
+            };
+            regions.retain(|r| r.offset + r.length <= offset || offset + data.len() <= r.offset);
+            let mode = WindowStats::measure(data).classify().map_or(DecodeMode::Screen2Patterns, |(mode, _)| mode);
+            seen.push(GraphicsRegion {
+                bank: offset / size,
+                offset,
+                bank_offset: offset % size,
+                length: data.len(),
+                mode,
+                score: 100,
+                entropy: entropy(data),
+            });
+        }
+        seen.extend(regions);
+        seen
+    }
+}
+
+impl Default for GraphicsFinder {
+    fn default() -> Self {
+        GraphicsFinder::new(0)
+    }
+}
+
+/// Flujo comprimido que empieza en `offset` y descomprime a algo con forma
+/// de gráfico; primero los que dan un tamaño de pantalla. RLE no se prueba:
+/// casi cualquier secuencia es PackBits válido
+fn packed_stream(data: &[u8], offset: usize) -> Option<(DecodeMode, usize, u32)> {
+    let mut best: Option<(DecodeMode, usize, u32)> = None;
+    for packer in Packer::ALL.into_iter().filter(|&p| p != Packer::Rle) {
+        let Ok(unpacked) = packers::unpack(packer, &data[offset..]) else {
+            continue;
+        };
+        let size = unpacked.data.len();
+        if size < MIN_PACKED_OUTPUT || !size.is_multiple_of(8) || size * 2 < unpacked.consumed * 3 {
+            continue;
+        }
+        let screen = ScreenMode::guess(size);
+        let score = if screen.is_some() { 95 } else { 80 };
+        if best.is_none_or(|(_, _, s)| score > s) {
+            best = Some((DecodeMode::Compressed { packer, screen }, unpacked.consumed, score));
+        }
+    }
+    best
+}
+
+fn scan_bank(data: &[u8]) -> Vec<GraphicsRegion> {
+    let mut regions: Vec<GraphicsRegion> = Vec::new();
+    let mut start = 0;
+    // Final de la última región comprimida: lo que queda dentro ya se explicó
+    let mut packed_end = 0;
+
+    while start < data.len() {
+        let end = (start + WINDOW).min(data.len());
+        let window = &data[start..end];
+        let stats = WindowStats::measure(window);
+
+        if stats.entropy >= PACKED_ENTROPY {
+            for offset in start.max(packed_end)..(start + STEP).min(data.len()) {
+                if let Some((mode, consumed, score)) = packed_stream(data, offset) {
+                    regions.push(GraphicsRegion {
+                        bank: 0,
+                        offset: 0,
+                        bank_offset: offset,
+                        length: consumed,
+                        mode,
+                        score,
+                        entropy: entropy(&data[offset..offset + consumed]),
+                    });
+                    packed_end = offset + consumed;
+                    break;
+                }
+            }
+        }
+
+        if end > packed_end {
+            if let Some((mode, score)) = stats.classify() {
+                match regions.last_mut() {
+                    // Ventanas solapadas del mismo modo forman una sola región
+                    Some(last) if last.mode == mode && last.bank_offset + last.length >= start => {
+                        let windows = last.length.saturating_sub(WINDOW) / STEP + 1;
+                        last.score = (last.score * windows as u32 + score) / (windows as u32 + 1);
+                        last.length = end - last.bank_offset;
+                        last.entropy = entropy(&data[last.bank_offset..end]);
+                    }
+                    _ => regions.push(GraphicsRegion {
+                        bank: 0,
+                        offset: 0,
+                        bank_offset: start,
+                        length: end - start,
+                        mode,
+                        score,
+                        entropy: stats.entropy,
+                    }),
+                }
+            }
+        }
+
+        if end == data.len() {
+            break;
+        }
+        start += STEP;
+    }
+    regions
+}
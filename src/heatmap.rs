@@ -0,0 +1,195 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  MAPA DE CALOR DE ROMS Y BIOS                                  ║
+//! ║  - Entropía y clases de byte por bloque                        ║
+//! ║  - Imagen RGBA: una celda por bloque, una fila cada 2 KB       ║
+//! ║  - Filas anotadas con regiones del mapa de memoria o bancos    ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::gfxfinder::{entropy, COMMON_OPCODES};
+
+/// Bytes por celda, celdas por fila y píxeles por celda
+pub const BLOCK_SIZE: usize = 64;
+pub const COLUMNS: usize = 32;
+pub const CELL: usize = 8;
+/// Franja de la izquierda con el color de la región de cada fila
+pub const GUTTER: usize = 8;
+/// Bytes que cubre cada fila de la imagen
+pub const ROW_BYTES: usize = BLOCK_SIZE * COLUMNS;
+
+/// Entropía a partir de la cual un bloque parece comprimido
+const PACKED_ENTROPY: f32 = 7.2;
+
+/// Colores de la franja de regiones, en rotación
+const REGION_COLORS: [[u8; 3]; 6] = [
+    [230, 126, 34],
+    [52, 152, 219],
+    [46, 204, 113],
+    [155, 89, 182],
+    [241, 196, 15],
+    [231, 76, 60],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockClass {
+    /// 00h o FFh casi en exclusiva
+    Padding,
+    /// ASCII imprimible: mensajes, nombres, BASIC
+    Text,
+    Code,
+    /// Alta entropía: comprimido o aleatorio
+    Packed,
+    /// Lo demás: tablas, gráficos, música
+    Data,
+}
+
+impl BlockClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockClass::Padding => "padding",
+            BlockClass::Text => "text",
+            BlockClass::Code => "code",
+            BlockClass::Packed => "packed",
+            BlockClass::Data => "data",
+        }
+    }
+
+    fn color(&self) -> [u8; 3] {
+        match self {
+            BlockClass::Padding => [40, 40, 40],
+            BlockClass::Text => [240, 210, 60],
+            BlockClass::Code => [70, 130, 255],
+            BlockClass::Packed => [235, 60, 60],
+            BlockClass::Data => [60, 200, 100],
+        }
+    }
+}
+
+/// Estadísticas de un bloque; las fracciones van de 0 a 1
+#[derive(Clone, Debug)]
+pub struct BlockStats {
+    pub offset: usize,
+    pub entropy: f32,
+    pub padding: f32,
+    pub text: f32,
+    pub code: f32,
+    pub class: BlockClass,
+}
+
+impl BlockStats {
+    pub fn measure(offset: usize, block: &[u8]) -> BlockStats {
+        let total = block.len().max(1) as f32;
+        let count = |test: fn(u8) -> bool| block.iter().filter(|&&b| test(b)).count() as f32 / total;
+        let padding = count(|b| b == 0x00 || b == 0xFF);
+        let text = count(|b| (0x20..0x7F).contains(&b));
+        let code = count(|b| COMMON_OPCODES.contains(&b));
+        let entropy = entropy(block);
+
+        let class = if padding > 0.9 {
+            BlockClass::Padding
+        } else if entropy >= PACKED_ENTROPY {
+            BlockClass::Packed
+        } else if text > 0.85 {
+            BlockClass::Text
+        } else if code > 0.3 {
+            BlockClass::Code
+        } else {
+            BlockClass::Data
+        };
+        BlockStats { offset, entropy, padding, text, code, class }
+    }
+}
+
+/// Estadísticas de todos los bloques de `data`
+pub fn block_stats(data: &[u8]) -> Vec<BlockStats> {
+    data.chunks(BLOCK_SIZE)
+        .enumerate()
+        .map(|(i, block)| BlockStats::measure(i * BLOCK_SIZE, block))
+        .collect()
+}
+
+/// Región con nombre: un slot del mapa de memoria o un banco del mapper
+#[derive(Clone, Debug)]
+pub struct RegionLabel {
+    pub offset: usize,
+    pub length: usize,
+    pub name: String,
+}
+
+/// Fila de la imagen y la región en la que empieza
+#[derive(Clone, Debug)]
+pub struct HeatmapRow {
+    pub offset: usize,
+    pub label: String,
+    /// Índice de la región en la lista recibida (None si no cae en ninguna)
+    pub region: Option<usize>,
+}
+
+pub struct Heatmap {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+    pub rows: Vec<HeatmapRow>,
+}
+
+/// Regiones de `bank_size` bytes llamadas "Banco N"
+pub fn bank_labels(len: usize, bank_size: usize) -> Vec<RegionLabel> {
+    (0..len.div_ceil(bank_size.max(1)))
+        .map(|bank| RegionLabel {
+            offset: bank * bank_size,
+            length: bank_size,
+            name: format!("Banco {}", bank),
+        })
+        .collect()
+}
+
+/// Pintar el mapa: el color dice la clase del bloque y el brillo su
+/// entropía; la franja izquierda, la región de la fila
+pub fn render(data: &[u8], regions: &[RegionLabel]) -> Heatmap {
+    let stats = block_stats(data);
+    let row_count = data.len().div_ceil(ROW_BYTES);
+    let width = GUTTER + COLUMNS * CELL;
+    let height = row_count * CELL;
+    let mut rgba = vec![0u8; width * height * 4];
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+
+    let mut fill = |x: usize, y: usize, w: usize, color: [u8; 3]| {
+        for row in y..y + CELL {
+            for column in x..x + w {
+                let at = (row * width + column) * 4;
+                rgba[at..at + 3].copy_from_slice(&color);
+            }
+        }
+    };
+
+    for (i, block) in stats.iter().enumerate() {
+        let brightness = match block.class {
+            BlockClass::Padding => 1.0,
+            _ => 0.35 + 0.65 * block.entropy / 8.0,
+        };
+        let color = block.class.color().map(|c| (c as f32 * brightness) as u8);
+        fill(GUTTER + (i % COLUMNS) * CELL, (i / COLUMNS) * CELL, CELL, color);
+    }
+
+    let rows: Vec<HeatmapRow> = (0..row_count)
+        .map(|row| {
+            let offset = row * ROW_BYTES;
+            let region = regions
+                .iter()
+                .position(|r| r.offset <= offset && offset < r.offset + r.length);
+            HeatmapRow {
+                offset,
+                label: region.map(|i| regions[i].name.clone()).unwrap_or_default(),
+                region,
+            }
+        })
+        .collect();
+    for (row, info) in rows.iter().enumerate() {
+        if let Some(region) = info.region {
+            fill(0, row * CELL, GUTTER - 2, REGION_COLORS[region % REGION_COLORS.len()]);
+        }
+    }
+
+    Heatmap { width, height, rgba, rows }
+}
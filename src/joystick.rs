@@ -0,0 +1,319 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  DISPOSITIVOS DE LOS PUERTOS DE JOYSTICK                       ║
+//! ║  - Joystick estándar de 2 botones                              ║
+//! ║  - Ratón MSX (protocolo de nibbles con strobe en pin 8)        ║
+//! ║  - Trackball (deltas de 4 bits por eje)                        ║
+//! ║  - Paddle (monoestable contado por lecturas)                   ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+// Bits de entrada del puerto (lógica negativa en el conector)
+pub const JOY_UP: u8 = 0x01;
+pub const JOY_DOWN: u8 = 0x02;
+pub const JOY_LEFT: u8 = 0x04;
+pub const JOY_RIGHT: u8 = 0x08;
+pub const JOY_BUTTON_A: u8 = 0x10;
+pub const JOY_BUTTON_B: u8 = 0x20;
+
+// Pines de salida que controla el registro 15 del PSG
+pub const PIN6_OUT: u8 = 0x01;
+pub const PIN7_OUT: u8 = 0x02;
+pub const PIN8_OUT: u8 = 0x04;
+
+// ═══════════════════════════════════════════════════════════════
+// JOYSTICK ESTÁNDAR
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Default)]
+pub struct Joystick {
+    pressed: u8,
+}
+
+impl Joystick {
+    pub fn new() -> Joystick {
+        Joystick { pressed: 0 }
+    }
+
+    /// Fijar el estado completo con la máscara JOY_* (bit a 1 = pulsado)
+    pub fn set_state(&mut self, pressed: u8) {
+        self.pressed = pressed & 0x3F;
+    }
+
+    pub fn state(&self) -> u8 {
+        self.pressed
+    }
+
+    fn read(&self) -> u8 {
+        !self.pressed & 0x3F
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// RATÓN MSX
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MousePhase {
+    XHigh,
+    XLow,
+    YHigh,
+    YLow,
+}
+
+/// Ratón MSX: cada cambio del pin 8 entrega el siguiente nibble
+/// (X alto, X bajo, Y alto, Y bajo). Los desplazamientos se capturan al
+/// empezar cada secuencia y se envían negados, como el hardware original
+/// (mover a la derecha o hacia abajo da valores negativos).
+pub struct Mouse {
+    pending_x: i32,
+    pending_y: i32,
+    latched_x: i8,
+    latched_y: i8,
+    buttons: u8,
+    phase: MousePhase,
+    last_strobe: bool,
+}
+
+impl Mouse {
+    pub fn new() -> Mouse {
+        Mouse {
+            pending_x: 0,
+            pending_y: 0,
+            latched_x: 0,
+            latched_y: 0,
+            buttons: 0,
+            phase: MousePhase::YLow,
+            last_strobe: false,
+        }
+    }
+
+    /// Acumular movimiento en coordenadas de pantalla del host
+    pub fn move_by(&mut self, dx: i32, dy: i32) {
+        self.pending_x = self.pending_x.saturating_sub(dx);
+        self.pending_y = self.pending_y.saturating_sub(dy);
+    }
+
+    pub fn set_buttons(&mut self, left: bool, right: bool) {
+        self.buttons = (if left { JOY_BUTTON_A } else { 0 }) | (if right { JOY_BUTTON_B } else { 0 });
+    }
+
+    /// Volver al inicio de la secuencia (equivale al timeout del hardware)
+    pub fn reset_phase(&mut self) {
+        self.phase = MousePhase::YLow;
+    }
+
+    fn read(&self) -> u8 {
+        let nibble = match self.phase {
+            MousePhase::XHigh => (self.latched_x as u8) >> 4,
+            MousePhase::XLow => self.latched_x as u8,
+            MousePhase::YHigh => (self.latched_y as u8) >> 4,
+            MousePhase::YLow => self.latched_y as u8,
+        } & 0x0F;
+        nibble | (!self.buttons & 0x30)
+    }
+
+    fn write(&mut self, pins: u8) {
+        let strobe = pins & PIN8_OUT != 0;
+        if strobe == self.last_strobe {
+            return;
+        }
+        self.last_strobe = strobe;
+
+        self.phase = match self.phase {
+            MousePhase::XHigh => MousePhase::XLow,
+            MousePhase::XLow => MousePhase::YHigh,
+            MousePhase::YHigh => MousePhase::YLow,
+            MousePhase::YLow => {
+                self.latched_x = self.pending_x.clamp(-128, 127) as i8;
+                self.latched_y = self.pending_y.clamp(-128, 127) as i8;
+                self.pending_x -= self.latched_x as i32;
+                self.pending_y -= self.latched_y as i32;
+                MousePhase::XHigh
+            }
+        };
+    }
+}
+
+impl Default for Mouse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TRACKBALL
+// ═══════════════════════════════════════════════════════════════
+
+/// Trackball: el pin 8 elige el eje (0 = X, 1 = Y) y la lectura devuelve
+/// un delta con signo de 4 bits saturado a -8..7. Cada cambio de eje
+/// descuenta lo que ya se entregó del eje anterior.
+pub struct Trackball {
+    pending_x: i32,
+    pending_y: i32,
+    buttons: u8,
+    select_y: bool,
+}
+
+impl Trackball {
+    pub fn new() -> Trackball {
+        Trackball {
+            pending_x: 0,
+            pending_y: 0,
+            buttons: 0,
+            select_y: false,
+        }
+    }
+
+    pub fn move_by(&mut self, dx: i32, dy: i32) {
+        self.pending_x = self.pending_x.saturating_add(dx);
+        self.pending_y = self.pending_y.saturating_add(dy);
+    }
+
+    pub fn set_buttons(&mut self, left: bool, right: bool) {
+        self.buttons = (if left { JOY_BUTTON_A } else { 0 }) | (if right { JOY_BUTTON_B } else { 0 });
+    }
+
+    fn current_delta(&self) -> i32 {
+        let pending = if self.select_y { self.pending_y } else { self.pending_x };
+        pending.clamp(-8, 7)
+    }
+
+    fn read(&self) -> u8 {
+        (self.current_delta() as u8 & 0x0F) | (!self.buttons & 0x30)
+    }
+
+    fn write(&mut self, pins: u8) {
+        let select_y = pins & PIN8_OUT != 0;
+        if select_y == self.select_y {
+            return;
+        }
+        let delta = self.current_delta();
+        if self.select_y {
+            self.pending_y -= delta;
+        } else {
+            self.pending_x -= delta;
+        }
+        self.select_y = select_y;
+    }
+}
+
+impl Default for Trackball {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// PADDLE
+// ═══════════════════════════════════════════════════════════════
+
+/// Paddle MSX: un pulso en el pin 8 dispara el monoestable y el bit 0
+/// queda a 1 durante un tiempo proporcional a la posición. Sin reloj
+/// de referencia, el tiempo se mide en lecturas del registro 14, que es
+/// justo lo que cuenta el bucle de la rutina PDL del BIOS.
+pub struct Paddle {
+    position: u8,
+    remaining: u16,
+    button: bool,
+    last_strobe: bool,
+}
+
+impl Paddle {
+    pub fn new() -> Paddle {
+        Paddle {
+            position: 128,
+            remaining: 0,
+            button: false,
+            last_strobe: false,
+        }
+    }
+
+    pub fn set_position(&mut self, position: u8) {
+        self.position = position;
+    }
+
+    pub fn set_button(&mut self, pressed: bool) {
+        self.button = pressed;
+    }
+
+    fn read(&mut self) -> u8 {
+        let pulse = if self.remaining > 0 {
+            self.remaining -= 1;
+            0x01
+        } else {
+            0x00
+        };
+        let button = if self.button { 0x00 } else { JOY_BUTTON_A };
+        0x2E | pulse | button
+    }
+
+    fn write(&mut self, pins: u8) {
+        let strobe = pins & PIN8_OUT != 0;
+        if strobe && !self.last_strobe {
+            self.remaining = self.position as u16 + 1;
+        }
+        self.last_strobe = strobe;
+    }
+}
+
+impl Default for Paddle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// DISPOSITIVO CONECTADO A UN PUERTO
+// ═══════════════════════════════════════════════════════════════
+
+pub enum PortDevice {
+    Empty,
+    Joystick(Joystick),
+    Mouse(Mouse),
+    Trackball(Trackball),
+    Paddle(Paddle),
+}
+
+impl PortDevice {
+    /// Crear un dispositivo por nombre ("joystick", "mouse", "trackball", "paddle", "none")
+    pub fn from_name(name: &str) -> Option<PortDevice> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" | "empty" => Some(PortDevice::Empty),
+            "joystick" => Some(PortDevice::Joystick(Joystick::new())),
+            "mouse" => Some(PortDevice::Mouse(Mouse::new())),
+            "trackball" => Some(PortDevice::Trackball(Trackball::new())),
+            "paddle" => Some(PortDevice::Paddle(Paddle::new())),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PortDevice::Empty => "none",
+            PortDevice::Joystick(_) => "joystick",
+            PortDevice::Mouse(_) => "mouse",
+            PortDevice::Trackball(_) => "trackball",
+            PortDevice::Paddle(_) => "paddle",
+        }
+    }
+
+    /// Leer los 6 bits de entrada (pines 1-4, 6 y 7); un puerto vacío lee todo a 1
+    pub fn read(&mut self) -> u8 {
+        match self {
+            PortDevice::Empty => 0x3F,
+            PortDevice::Joystick(joy) => joy.read(),
+            PortDevice::Mouse(mouse) => mouse.read(),
+            PortDevice::Trackball(ball) => ball.read(),
+            PortDevice::Paddle(paddle) => paddle.read(),
+        }
+    }
+
+    /// Recibir el estado de los pines de salida (PIN6_OUT, PIN7_OUT, PIN8_OUT)
+    pub fn write(&mut self, pins: u8) {
+        match self {
+            PortDevice::Mouse(mouse) => mouse.write(pins),
+            PortDevice::Trackball(ball) => ball.write(pins),
+            PortDevice::Paddle(paddle) => paddle.write(pins),
+            PortDevice::Empty | PortDevice::Joystick(_) => {}
+        }
+    }
+}
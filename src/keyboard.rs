@@ -0,0 +1,286 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  MATRIZ DE TECLADO MSX (11 filas x 8 columnas)                 ║
+//! ║  - Distribuciones internacionales elegidas por el BIOS         ║
+//! ║  - Mapeo desde `KeyboardEvent.code` del navegador              ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use std::collections::HashSet;
+
+/// Número de filas de la matriz (0-10, la 9 y 10 son el teclado numérico)
+pub const KEYBOARD_ROWS: usize = 11;
+
+/// Dirección del byte de región/teclado dentro del BIOS principal
+pub const BIOS_KEYBOARD_ID_ADDRESS: usize = 0x002C;
+
+/// Posición de una tecla dentro de la matriz
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MatrixPosition {
+    pub row: u8,
+    pub bit: u8,
+}
+
+impl MatrixPosition {
+    pub const fn new(row: u8, bit: u8) -> Self {
+        MatrixPosition { row, bit }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// DISTRIBUCIONES DE TECLADO
+// ═══════════════════════════════════════════════════════════════
+
+/// Distribuciones de teclado MSX conocidas
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyboardLayout {
+    Japanese,
+    International,
+    French,
+    UK,
+    German,
+    Russian,
+    Spanish,
+    Brazilian,
+}
+
+impl KeyboardLayout {
+    /// Decodificar el nibble bajo del byte 002Ch del BIOS.
+    ///
+    /// Los equipos brasileños (Gradiente Expert, Sharp Hotbit) se anuncian
+    /// como internacionales, por lo que solo se eligen de forma explícita.
+    pub fn from_region_byte(id: u8) -> KeyboardLayout {
+        match id & 0x0F {
+            0 => KeyboardLayout::Japanese,
+            2 => KeyboardLayout::French,
+            3 => KeyboardLayout::UK,
+            4 => KeyboardLayout::German,
+            5 => KeyboardLayout::Russian,
+            6 => KeyboardLayout::Spanish,
+            _ => KeyboardLayout::International,
+        }
+    }
+
+    /// Detectar la distribución a partir de una imagen de BIOS
+    pub fn from_bios(bios_data: &[u8]) -> KeyboardLayout {
+        match bios_data.get(BIOS_KEYBOARD_ID_ADDRESS) {
+            Some(&id) => KeyboardLayout::from_region_byte(id),
+            None => KeyboardLayout::International,
+        }
+    }
+
+    /// Buscar una distribución por su nombre corto ("jp", "es", "br"...)
+    pub fn from_name(name: &str) -> Option<KeyboardLayout> {
+        match name.to_ascii_lowercase().as_str() {
+            "jp" | "japanese" => Some(KeyboardLayout::Japanese),
+            "int" | "international" => Some(KeyboardLayout::International),
+            "fr" | "french" => Some(KeyboardLayout::French),
+            "uk" | "gb" => Some(KeyboardLayout::UK),
+            "de" | "german" => Some(KeyboardLayout::German),
+            "ru" | "russian" => Some(KeyboardLayout::Russian),
+            "es" | "spanish" => Some(KeyboardLayout::Spanish),
+            "br" | "brazilian" => Some(KeyboardLayout::Brazilian),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyboardLayout::Japanese => "jp",
+            KeyboardLayout::International => "int",
+            KeyboardLayout::French => "fr",
+            KeyboardLayout::UK => "uk",
+            KeyboardLayout::German => "de",
+            KeyboardLayout::Russian => "ru",
+            KeyboardLayout::Spanish => "es",
+            KeyboardLayout::Brazilian => "br",
+        }
+    }
+
+    /// Traducir un `KeyboardEvent.code` a su posición en la matriz.
+    ///
+    /// `code` es posicional (nombra la tecla física según QWERTY US), así
+    /// que solo las teclas propias de cada distribución necesitan excepción.
+    pub fn map_code(&self, code: &str) -> Option<MatrixPosition> {
+        let overrides: &[(&str, u8, u8)] = match self {
+            KeyboardLayout::Japanese => &[
+                ("IntlYen", 1, 4),
+                ("Backslash", 2, 1),
+                ("IntlRo", 2, 5),
+                ("KanaMode", 6, 4),
+            ],
+            KeyboardLayout::Brazilian => &[("IntlRo", 2, 4)],
+            _ => &[],
+        };
+
+        overrides
+            .iter()
+            .chain(COMMON_CODES.iter())
+            .find(|(name, _, _)| *name == code)
+            .map(|&(_, row, bit)| MatrixPosition::new(row, bit))
+    }
+
+    /// Leyenda impresa en la tecla para esta distribución
+    pub fn key_label(&self, pos: MatrixPosition) -> &'static str {
+        if pos.row as usize >= KEYBOARD_ROWS || pos.bit > 7 {
+            return "";
+        }
+
+        let overrides: &[(u8, u8, &str)] = match self {
+            KeyboardLayout::Japanese => &[
+                (1, 3, "^"), (1, 4, "¥"), (1, 5, "@"), (1, 6, "["), (1, 7, ";"),
+                (2, 0, ":"), (2, 1, "]"), (2, 5, "_"), (6, 4, "KANA"),
+            ],
+            KeyboardLayout::French => &[
+                (0, 0, "à"), (0, 1, "&"), (0, 2, "é"), (0, 3, "\""), (0, 4, "'"),
+                (0, 5, "("), (0, 6, "§"), (0, 7, "è"), (1, 0, "!"), (1, 1, "ç"),
+                (1, 2, ")"), (1, 3, "-"), (1, 7, "M"), (2, 6, "Q"), (4, 2, ","),
+                (4, 6, "A"), (5, 4, "Z"), (5, 7, "W"),
+            ],
+            KeyboardLayout::UK => &[(2, 1, "£")],
+            KeyboardLayout::German => &[
+                (1, 2, "ß"), (1, 3, "'"), (1, 4, "<"), (1, 5, "Ü"), (1, 6, "+"),
+                (1, 7, "Ö"), (2, 0, "Ä"), (2, 1, "#"), (2, 4, "-"), (5, 6, "Z"),
+                (5, 7, "Y"),
+            ],
+            KeyboardLayout::Spanish => &[(1, 7, "Ñ"), (2, 1, ";")],
+            KeyboardLayout::Brazilian => &[(1, 7, "Ç"), (2, 1, "~")],
+            KeyboardLayout::International | KeyboardLayout::Russian => &[],
+        };
+
+        overrides
+            .iter()
+            .find(|&&(row, bit, _)| row == pos.row && bit == pos.bit)
+            .map(|&(_, _, label)| label)
+            .unwrap_or(BASE_LABELS[pos.row as usize][pos.bit as usize])
+    }
+}
+
+/// Posiciones comunes a todas las distribuciones: (code, fila, bit)
+const COMMON_CODES: &[(&str, u8, u8)] = &[
+    ("Digit0", 0, 0), ("Digit1", 0, 1), ("Digit2", 0, 2), ("Digit3", 0, 3),
+    ("Digit4", 0, 4), ("Digit5", 0, 5), ("Digit6", 0, 6), ("Digit7", 0, 7),
+    ("Digit8", 1, 0), ("Digit9", 1, 1), ("Minus", 1, 2), ("Equal", 1, 3),
+    ("Backslash", 1, 4), ("BracketLeft", 1, 5), ("BracketRight", 1, 6), ("Semicolon", 1, 7),
+    ("Quote", 2, 0), ("Backquote", 2, 1), ("Comma", 2, 2), ("Period", 2, 3),
+    ("Slash", 2, 4), ("IntlBackslash", 2, 5), ("KeyA", 2, 6), ("KeyB", 2, 7),
+    ("KeyC", 3, 0), ("KeyD", 3, 1), ("KeyE", 3, 2), ("KeyF", 3, 3),
+    ("KeyG", 3, 4), ("KeyH", 3, 5), ("KeyI", 3, 6), ("KeyJ", 3, 7),
+    ("KeyK", 4, 0), ("KeyL", 4, 1), ("KeyM", 4, 2), ("KeyN", 4, 3),
+    ("KeyO", 4, 4), ("KeyP", 4, 5), ("KeyQ", 4, 6), ("KeyR", 4, 7),
+    ("KeyS", 5, 0), ("KeyT", 5, 1), ("KeyU", 5, 2), ("KeyV", 5, 3),
+    ("KeyW", 5, 4), ("KeyX", 5, 5), ("KeyY", 5, 6), ("KeyZ", 5, 7),
+    ("ShiftLeft", 6, 0), ("ShiftRight", 6, 0), ("ControlLeft", 6, 1), ("ControlRight", 6, 1),
+    ("AltLeft", 6, 2), ("CapsLock", 6, 3), ("AltRight", 6, 4),
+    ("F1", 6, 5), ("F2", 6, 6), ("F3", 6, 7),
+    ("F4", 7, 0), ("F5", 7, 1), ("Escape", 7, 2), ("Tab", 7, 3),
+    ("Pause", 7, 4), ("End", 7, 4), ("Backspace", 7, 5), ("PageUp", 7, 6),
+    ("Enter", 7, 7), ("NumpadEnter", 7, 7),
+    ("Space", 8, 0), ("Home", 8, 1), ("Insert", 8, 2), ("Delete", 8, 3),
+    ("ArrowLeft", 8, 4), ("ArrowUp", 8, 5), ("ArrowDown", 8, 6), ("ArrowRight", 8, 7),
+    ("NumpadMultiply", 9, 0), ("NumpadAdd", 9, 1), ("NumpadDivide", 9, 2), ("Numpad0", 9, 3),
+    ("Numpad1", 9, 4), ("Numpad2", 9, 5), ("Numpad3", 9, 6), ("Numpad4", 9, 7),
+    ("Numpad5", 10, 0), ("Numpad6", 10, 1), ("Numpad7", 10, 2), ("Numpad8", 10, 3),
+    ("Numpad9", 10, 4), ("NumpadSubtract", 10, 5), ("NumpadComma", 10, 6), ("NumpadDecimal", 10, 7),
+];
+
+/// Leyendas de la distribución internacional, indexadas por [fila][bit]
+const BASE_LABELS: [[&str; 8]; KEYBOARD_ROWS] = [
+    ["0", "1", "2", "3", "4", "5", "6", "7"],
+    ["8", "9", "-", "=", "\\", "[", "]", ";"],
+    ["'", "`", ",", ".", "/", "DEAD", "A", "B"],
+    ["C", "D", "E", "F", "G", "H", "I", "J"],
+    ["K", "L", "M", "N", "O", "P", "Q", "R"],
+    ["S", "T", "U", "V", "W", "X", "Y", "Z"],
+    ["SHIFT", "CTRL", "GRAPH", "CAPS", "CODE", "F1", "F2", "F3"],
+    ["F4", "F5", "ESC", "TAB", "STOP", "BS", "SELECT", "RETURN"],
+    ["SPACE", "HOME", "INS", "DEL", "LEFT", "UP", "DOWN", "RIGHT"],
+    ["NUM*", "NUM+", "NUM/", "NUM0", "NUM1", "NUM2", "NUM3", "NUM4"],
+    ["NUM5", "NUM6", "NUM7", "NUM8", "NUM9", "NUM-", "NUM,", "NUM."],
+];
+
+// ═══════════════════════════════════════════════════════════════
+// MATRIZ DE TECLADO
+// ═══════════════════════════════════════════════════════════════
+
+/// Estado de la matriz: un bit a 0 indica tecla pulsada (lógica negativa)
+pub struct KeyboardMatrix {
+    rows: [u8; KEYBOARD_ROWS],
+    layout: KeyboardLayout,
+    /// Códigos pulsados: varias teclas del PC comparten posición (las dos
+    /// mayúsculas) y la posición solo se suelta cuando no queda ninguna
+    held: HashSet<String>,
+}
+
+impl KeyboardMatrix {
+    pub fn new(layout: KeyboardLayout) -> KeyboardMatrix {
+        KeyboardMatrix {
+            rows: [0xFF; KEYBOARD_ROWS],
+            layout,
+            held: HashSet::new(),
+        }
+    }
+
+    pub fn layout(&self) -> KeyboardLayout {
+        self.layout
+    }
+
+    /// Cambiar de distribución suelta todas las teclas
+    pub fn set_layout(&mut self, layout: KeyboardLayout) {
+        self.layout = layout;
+        self.release_all();
+    }
+
+    pub fn press(&mut self, pos: MatrixPosition) {
+        if let Some(row) = self.rows.get_mut(pos.row as usize) {
+            *row &= !(1 << (pos.bit & 7));
+        }
+    }
+
+    pub fn release(&mut self, pos: MatrixPosition) {
+        if let Some(row) = self.rows.get_mut(pos.row as usize) {
+            *row |= 1 << (pos.bit & 7);
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        self.rows = [0xFF; KEYBOARD_ROWS];
+        self.held.clear();
+    }
+
+    pub fn is_pressed(&self, pos: MatrixPosition) -> bool {
+        self.read_row(pos.row) & (1 << (pos.bit & 7)) == 0
+    }
+
+    /// Leer una fila; las filas inexistentes devuelven 0xFF (nada pulsado)
+    pub fn read_row(&self, row: u8) -> u8 {
+        self.rows.get(row as usize).copied().unwrap_or(0xFF)
+    }
+
+    pub fn rows(&self) -> &[u8; KEYBOARD_ROWS] {
+        &self.rows
+    }
+
+    /// Pulsar la tecla asociada a un `KeyboardEvent.code`
+    pub fn key_down(&mut self, code: &str) -> Option<MatrixPosition> {
+        let pos = self.layout.map_code(code)?;
+        self.held.insert(code.to_string());
+        self.press(pos);
+        Some(pos)
+    }
+
+    /// Soltar la tecla asociada a un `KeyboardEvent.code`; la posición
+    /// sigue pulsada si otro código con la misma posición no se ha soltado
+    pub fn key_up(&mut self, code: &str) -> Option<MatrixPosition> {
+        let pos = self.layout.map_code(code)?;
+        self.held.remove(code);
+        if !self.held.iter().any(|held| self.layout.map_code(held) == Some(pos)) {
+            self.release(pos);
+        }
+        Some(pos)
+    }
+}
+
+impl Default for KeyboardMatrix {
+    fn default() -> Self {
+        Self::new(KeyboardLayout::International)
+    }
+}
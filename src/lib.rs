@@ -8,6 +8,86 @@
 
 use wasm_bindgen::prelude::*;
 use std::collections::HashMap;
+use z80::REGISTER_NAMES;
+
+pub mod asm;
+pub mod bload;
+pub mod bus;
+pub mod cas;
+pub mod cdl;
+pub mod codemap;
+pub mod cycles;
+pub mod dap;
+pub mod debugger;
+pub mod disasm;
+pub mod dsk;
+pub mod fdc;
+pub mod gdb;
+pub mod filetype;
+pub mod gfxfinder;
+pub mod heatmap;
+pub mod inflate;
+pub mod joystick;
+pub mod keyboard;
+pub mod link;
+pub mod lzh;
+pub mod machine;
+pub mod mapper;
+pub mod object;
+pub mod packers;
+pub mod ppi;
+pub mod profiler;
+pub mod psg;
+pub mod rom;
+pub mod rtc;
+pub mod screen;
+pub mod slots;
+pub mod symbols;
+pub mod tape;
+pub mod vdp;
+pub mod timing;
+pub mod trace;
+pub mod z80;
+pub mod zip;
+
+pub use asm::{AsmError, AsmLine, Assembler, Assembly, OutputFormat};
+pub use bload::BloadHeader;
+pub use bus::MsxBus;
+pub use cas::{CasBlock, CasFile, CasFileType, CasImage};
+pub use cdl::{BankMarks, CodeDataLog};
+pub use disasm::{Disassembler, Disassembly, Flow, Instruction, Line, Operand};
+pub use codemap::{BankSwitch, ByteKind, CodeAnalyzer, CodeMap};
+pub use cycles::{BasicBlock, BlockExit, CycleAnalyzer, CycleReport, LoopTiming, PathTiming, VdpWarning};
+pub use dap::DapServer;
+pub use debugger::{BreakKind, Breakpoint, Condition, Debugger, SourceLine, Stop, StopReason};
+pub use dsk::{DirEntry, DiskGeometry, DiskImage};
+pub use fdc::{DiskController, DiskDrive, Wd2793};
+pub use filetype::FileKind;
+pub use gdb::{Connection, GdbStub, Stdio};
+pub use gfxfinder::{DecodeMode, GraphicsFinder, GraphicsRegion};
+pub use heatmap::{BlockClass, BlockStats, Heatmap, HeatmapRow, RegionLabel};
+pub use joystick::{Joystick, Mouse, Paddle, PortDevice, Trackball};
+pub use keyboard::{KeyboardLayout, KeyboardMatrix, MatrixPosition};
+pub use link::{AreaKind, Linked, Linker, MemoryArea, Placement};
+pub use lzh::{LzhArchive, LzhEntry};
+pub use machine::{Access, AccessKind, Machine, StepInfo, FRAME_TSTATES};
+pub use mapper::MapperType;
+pub use object::{Location, ObjectFile, ObjectSection, ObjectSymbol, RelocPart, RelocTarget, Relocation};
+pub use packers::{Packer, ProbeHit, Unpacked};
+pub use ppi::Ppi8255;
+pub use profiler::{Hotspot, Profiler};
+pub use psg::Psg;
+pub use rtc::{ManualClock, Rp5c01, RtcDateTime, SystemClock, TimeSource};
+pub use rom::{RomHeader, ROM_HEADER_SIZE};
+pub use screen::ScreenMode;
+pub use slots::{SlotContent, SlotId, SlotSystem};
+pub use symbols::{Symbol, SymbolFormat, SymbolKind, SymbolTable};
+pub use tape::{BlockReport, TapeDecode};
+pub use timing::Timing;
+pub use trace::{TraceEntry, TraceFormat, Tracer};
+pub use vdp::V9938;
+pub use z80::{Registers, Z80Bus, Z80};
+pub use zip::{ZipArchive, ZipEntry};
 
 // ═══════════════════════════════════════════════════════════════
 // GESTIÓN DE BIOS MSX2
@@ -227,6 +307,19 @@ pub struct MSX2Processor {
     memory_map: HashMap<String, MemoryMapSlot>,
     bios_data: Vec<u8>,
     current_bios: Option<BiosInfo>,
+    machine: Machine,
+    debugger: Debugger,
+    /// Servidor GDB para un puente desde JavaScript
+    gdb: GdbStub,
+    /// Servidor DAP para un editor en el navegador
+    dap: DapServer,
+    cassette: Option<CasImage>,
+    tape_report: Vec<BlockReport>,
+    symbols: SymbolTable,
+    /// Ficheros para INCLUDE e INCBIN del ensamblador
+    asm_files: HashMap<String, Vec<u8>>,
+    /// Módulos reubicables para el enlazador
+    objects: Vec<ObjectFile>,
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -283,6 +376,15 @@ impl MSX2Processor {
             memory_map,
             bios_data: Vec::new(),
             current_bios: None,
+            machine: Machine::new(),
+            debugger: Debugger::new(),
+            gdb: GdbStub::new().limit(FRAME_TSTATES as u64 * 60),
+            dap: DapServer::new().limit(FRAME_TSTATES as u64 * 60),
+            cassette: None,
+            tape_report: Vec::new(),
+            symbols: SymbolTable::msx(),
+            asm_files: HashMap::new(),
+            objects: Vec::new(),
         }
     }
 
@@ -304,7 +406,11 @@ impl MSX2Processor {
     pub fn find_memory_slot(&self, address: u32) -> String {
         for (_name, slot) in self.memory_map.iter() {
             if address >= slot.address && address < (slot.address + slot.size) {
-                return format!("{} (0x{:04X})", slot.name, slot.address);
+                let base = format!("{} (0x{:04X})", slot.name, slot.address);
+                return match self.symbols.describe(address as u16) {
+                    Some(name) => format!("{} {}", base, name),
+                    None => base,
+                };
             }
         }
         "Desconocido".to_string()
@@ -358,91 +464,1624 @@ impl MSX2Processor {
         self.bios_data = bios_data.to_vec();
         self.current_bios = Some(bios_info);
 
+        // El byte 002Ch del BIOS indica la distribución del teclado
+        self.machine.bus
+            .ppi
+            .keyboard
+            .set_layout(KeyboardLayout::from_bios(bios_data));
+
+        format!(
+            "✅ BIOS cargado: {} ({} bytes) - Checksum: {}",
+            filename, size, checksum
+        )
+    }
+
+    /// Obtener información del BIOS cargado actualmente
+    pub fn get_current_bios_info(&self) -> String {
+        match &self.current_bios {
+            Some(info) => format!(
+                r#"{{"filename":"{}","size":{},"type":"{}","checksum":"{}","loaded":true}}"#,
+                info.get_filename(),
+                info.get_size(),
+                info.get_bios_type(),
+                info.get_checksum()
+            ),
+            None => r#"{"loaded":false,"message":"No hay BIOS cargado"}"#.to_string(),
+        }
+    }
+
+    /// Obtener los datos del BIOS en formato Base64 para transmisión
+    pub fn get_bios_data(&self) -> Vec<u8> {
+        self.bios_data.clone()
+    }
+
+    /// Verificar si hay BIOS cargado
+    pub fn has_bios_loaded(&self) -> bool {
+        self.current_bios.is_some()
+    }
+
+    /// Descargar BIOS actual
+    pub fn unload_bios(&mut self) -> String {
+        if let Some(info) = &self.current_bios {
+            let filename = info.get_filename();
+            self.bios_data.clear();
+            self.current_bios = None;
+            format!("✅ BIOS '{}' descargado correctamente", filename)
+        } else {
+            "ℹ️ No había BIOS cargado".to_string()
+        }
+    }
+
+    /// Obtener descripción completa del BIOS para debugging
+    pub fn get_bios_description(&self) -> String {
+        match &self.current_bios {
+            Some(info) => {
+                format!(
+                    "📋 INFORMACIÓN BIOS\n━━━━━━━━━━━━━━━━━━━━━━\n\
+                    Archivo: {}\n\
+                    Tamaño: {} bytes\n\
+                    Tipo: {}\n\
+                    Checksum: {}\n\
+                    Estado: {}\n\
+                    Ubicación: Slot 0 (0x0000-0x3FFF)",
+                    info.get_filename(),
+                    info.get_size(),
+                    info.get_bios_type(),
+                    info.get_checksum(),
+                    if info.is_loaded() { "✅ CARGADO" } else { "❌ NO CARGADO" }
+                )
+            }
+            None => "❌ No hay BIOS cargado en el sistema".to_string(),
+        }
+    }
+
+    /// Validar integridad BIOS comparando checksums
+    pub fn validate_bios_checksum(&self, expected_checksum: &str) -> bool {
+        match &self.current_bios {
+            Some(info) => info.get_checksum().eq_ignore_ascii_case(expected_checksum),
+            None => false,
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // PUERTOS DE E/S Y TECLADO (PPI 8255)
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Leer un puerto de E/S del Z80
+    pub fn io_read(&mut self, port: u8) -> u8 {
+        self.machine.bus.io_read(port)
+    }
+
+    /// Escribir en un puerto de E/S del Z80
+    pub fn io_write(&mut self, port: u8, value: u8) {
+        self.machine.bus.io_write(port, value);
+    }
+
+    /// Pulsar una tecla a partir de `KeyboardEvent.code`; false si no tiene posición
+    pub fn key_down(&mut self, code: &str) -> bool {
+        self.machine.bus.ppi.keyboard.key_down(code).is_some()
+    }
+
+    /// Soltar una tecla a partir de `KeyboardEvent.code`
+    pub fn key_up(&mut self, code: &str) -> bool {
+        self.machine.bus.ppi.keyboard.key_up(code).is_some()
+    }
+
+    /// Soltar todas las teclas (p. ej. al perder el foco la ventana)
+    pub fn release_all_keys(&mut self) {
+        self.machine.bus.ppi.keyboard.release_all();
+    }
+
+    /// Traducir un `KeyboardEvent.code` a su posición en la matriz como JSON
+    pub fn map_key_code(&self, code: &str) -> String {
+        let layout = self.machine.bus.ppi.keyboard.layout();
+        match layout.map_code(code) {
+            Some(pos) => format!(
+                r#"{{"code":"{}","mapped":true,"row":{},"bit":{},"label":"{}"}}"#,
+                json_escape(code),
+                pos.row,
+                pos.bit,
+                json_escape(layout.key_label(pos))
+            ),
+            None => format!(r#"{{"code":"{}","mapped":false}}"#, json_escape(code)),
+        }
+    }
+
+    /// Seleccionar la distribución por nombre ("jp", "int", "es", "br"...)
+    pub fn set_keyboard_layout(&mut self, name: &str) -> bool {
+        match KeyboardLayout::from_name(name) {
+            Some(layout) => {
+                self.machine.bus.ppi.keyboard.set_layout(layout);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Nombre corto de la distribución activa
+    pub fn get_keyboard_layout(&self) -> String {
+        self.machine.bus.ppi.keyboard.layout().name().to_string()
+    }
+
+    /// Estado crudo de las 11 filas de la matriz (bit a 0 = pulsada)
+    pub fn get_keyboard_matrix(&self) -> Vec<u8> {
+        self.machine.bus.ppi.keyboard.rows().to_vec()
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // PUERTOS DE JOYSTICK (PSG R14/R15)
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Conectar un dispositivo al puerto 1 o 2 ("joystick", "mouse", "trackball", "paddle", "none")
+    pub fn connect_controller(&mut self, port: u8, kind: &str) -> bool {
+        match (self.controller_mut(port), PortDevice::from_name(kind)) {
+            (Some(slot), Some(device)) => {
+                *slot = device;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Tipo de dispositivo conectado al puerto 1 o 2
+    pub fn get_controller_type(&self, port: u8) -> String {
+        match port {
+            1 | 2 => self.machine.bus.psg.ports[port as usize - 1].name().to_string(),
+            _ => "none".to_string(),
+        }
+    }
+
+    /// Estado del joystick como máscara: 1=arriba 2=abajo 4=izquierda 8=derecha 16=A 32=B
+    pub fn set_joystick_state(&mut self, port: u8, pressed: u8) -> bool {
+        match self.controller_mut(port) {
+            Some(PortDevice::Joystick(joy)) => {
+                joy.set_state(pressed);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Acumular movimiento del ratón o trackball conectado al puerto
+    pub fn move_pointer(&mut self, port: u8, dx: i32, dy: i32) -> bool {
+        match self.controller_mut(port) {
+            Some(PortDevice::Mouse(mouse)) => mouse.move_by(dx, dy),
+            Some(PortDevice::Trackball(ball)) => ball.move_by(dx, dy),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Botones del ratón o trackball conectado al puerto
+    pub fn set_pointer_buttons(&mut self, port: u8, left: bool, right: bool) -> bool {
+        match self.controller_mut(port) {
+            Some(PortDevice::Mouse(mouse)) => mouse.set_buttons(left, right),
+            Some(PortDevice::Trackball(ball)) => ball.set_buttons(left, right),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Posición (0-255) y botón del paddle conectado al puerto
+    pub fn set_paddle_state(&mut self, port: u8, position: u8, button: bool) -> bool {
+        match self.controller_mut(port) {
+            Some(PortDevice::Paddle(paddle)) => {
+                paddle.set_position(position);
+                paddle.set_button(button);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // RELOJ RP5C01 Y RAM CMOS
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Exportar la RAM CMOS del reloj para guardarla entre sesiones
+    pub fn export_rtc_ram(&mut self) -> Vec<u8> {
+        self.machine.bus.rtc.export_cmos()
+    }
+
+    /// Restaurar la RAM CMOS guardada con `export_rtc_ram`
+    pub fn import_rtc_ram(&mut self, data: &[u8]) -> String {
+        match self.machine.bus.rtc.import_cmos(data) {
+            Ok(()) => format!("✅ RAM CMOS restaurada ({} bytes)", data.len()),
+            Err(e) => format!("❌ Error: {}", e),
+        }
+    }
+
+    /// Fecha y hora del reloj del MSX como JSON
+    pub fn get_rtc_datetime(&mut self) -> String {
+        let dt = self.machine.bus.rtc.datetime();
+        format!(
+            r#"{{"year":{},"month":{},"day":{},"hour":{},"minute":{},"second":{}}}"#,
+            dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+        )
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // IMÁGENES DE DISCO .DSK
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Cargar una imagen .DSK en la unidad A validando su sector de arranque
+    pub fn load_disk_image(&mut self, data: &[u8]) -> String {
+        self.insert_disk(0, data)
+    }
+
+    /// Insertar (o cambiar en caliente) el disco de la unidad 0 (A) o 1 (B)
+    pub fn insert_disk(&mut self, drive: u8, data: &[u8]) -> String {
+        let Some(unit) = self.machine.bus.disk.drives.get_mut(drive as usize) else {
+            return format!("❌ Error: Unidad {} inexistente", drive);
+        };
+        match DiskImage::from_bytes(data) {
+            Ok(image) => {
+                let g = *image.geometry();
+                let message = format!(
+                    "✅ Disco cargado en {}: {} KB, {} cara(s), {} pistas - {} bytes libres",
+                    (b'A' + drive) as char,
+                    data.len() / 1024,
+                    g.heads,
+                    g.tracks(),
+                    image.free_space()
+                );
+                unit.insert(image);
+                message
+            }
+            Err(e) => format!("❌ Error: {}", e),
+        }
+    }
+
+    /// Expulsar el disco de una unidad
+    pub fn eject_disk(&mut self, drive: u8) -> bool {
+        self.machine.bus
+            .disk
+            .drives
+            .get_mut(drive as usize)
+            .and_then(|unit| unit.eject())
+            .is_some()
+    }
+
+    /// Activar o quitar la protección contra escritura de una unidad
+    pub fn set_disk_write_protect(&mut self, drive: u8, protected: bool) -> bool {
+        match self.machine.bus.disk.drives.get_mut(drive as usize) {
+            Some(unit) => {
+                unit.write_protected = protected;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Consultar (y limpiar) si el disco de una unidad cambió desde la última consulta
+    pub fn disk_changed(&mut self, drive: u8) -> bool {
+        self.machine.bus
+            .disk
+            .drives
+            .get_mut(drive as usize)
+            .map(|unit| unit.take_changed())
+            .unwrap_or(false)
+    }
+
+    /// Colocar la Disk-ROM (16 KB) en un slot; el subslot solo cuenta si está expandido
+    pub fn insert_disk_rom(&mut self, primary: u8, secondary: u8, rom: &[u8]) -> String {
+        if rom.is_empty() || rom.len() > 0x4000 {
+            return format!(
+                "❌ Error: Tamaño de Disk-ROM inválido ({} bytes, máximo 16KB)",
+                rom.len()
+            );
+        }
+        let slot = SlotId { primary, secondary };
+        self.machine.bus.slots.insert(slot, SlotContent::DiskRom(rom.to_vec()));
+        format!(
+            "✅ Disk-ROM en slot {}-{} con controlador WD2793",
+            primary & 3,
+            secondary & 3
+        )
+    }
+
+    /// Insertar un cartucho ROM lineal (hasta 48 KB) a partir de 4000h
+    pub fn insert_rom(&mut self, primary: u8, secondary: u8, rom: &[u8]) -> String {
+        if rom.is_empty() || rom.len() > 0xC000 {
+            return format!(
+                "❌ Error: Tamaño de ROM inválido ({} bytes, máximo 48KB)",
+                rom.len()
+            );
+        }
+        let slot = SlotId { primary, secondary };
+        self.machine.bus.slots.insert(
+            slot,
+            SlotContent::Rom {
+                data: rom.to_vec(),
+                base: 0x4000,
+            },
+        );
+        format!(
+            "✅ ROM de {} KB en slot {}-{}",
+            rom.len() / 1024,
+            primary & 3,
+            secondary & 3
+        )
+    }
+
+    /// Insertar una MegaROM con mapper ("konami", "konamiscc", "ascii8",
+    /// "ascii16"; vacío para adivinarlo) a partir de 4000h
+    pub fn insert_megarom(&mut self, primary: u8, secondary: u8, rom: &[u8], mapper: &str) -> String {
+        if rom.is_empty() || rom.len() > 0x200000 {
+            return format!("❌ Error: Tamaño de MegaROM inválido ({} bytes, máximo 2MB)", rom.len());
+        }
+        let mapper = match mapper {
+            "" => MapperType::guess(rom),
+            name => match MapperType::from_name(name) {
+                Some(mapper) => mapper,
+                None => return format!("❌ Error: Mapper desconocido: {}", name),
+            },
+        };
+        let slot = SlotId { primary, secondary };
+        self.machine.bus.slots.insert(slot, SlotContent::megarom(rom.to_vec(), mapper));
+        format!(
+            "✅ MegaROM {} de {} KB en slot {}-{}",
+            mapper.name(),
+            rom.len() / 1024,
+            primary & 3,
+            secondary & 3
+        )
+    }
+
+    /// Marcar un slot primario como expandido (registro secundario en FFFFh)
+    pub fn set_slot_expanded(&mut self, primary: u8, expanded: bool) {
+        self.machine.bus.slots.set_expanded(primary, expanded);
+    }
+
+    /// Colocar 64 KB de RAM en un slot
+    pub fn insert_ram(&mut self, primary: u8, secondary: u8) {
+        self.machine.bus
+            .slots
+            .insert(SlotId { primary, secondary }, SlotContent::ram());
+    }
+
+    /// Leer memoria a través de los slots activos
+    pub fn mem_read(&mut self, address: u16) -> u8 {
+        self.machine.bus.mem_read(address)
+    }
+
+    /// Escribir memoria a través de los slots activos
+    pub fn mem_write(&mut self, address: u16, value: u8) {
+        self.machine.bus.mem_write(address, value);
+    }
+
+    /// Listar los ficheros del disco de la unidad A como JSON
+    pub fn list_disk_files(&self) -> String {
+        let entries = match self.disk_image().map(|d| d.list_files()) {
+            Some(Ok(entries)) => entries,
+            _ => return "[]".to_string(),
+        };
+
+        let items: Vec<String> = entries
+            .iter()
+            .map(|e| {
+                let m = e.modified;
+                format!(
+                    r#"{{"name":"{}","size":{},"directory":{},"attributes":{},"date":"{:04}-{:02}-{:02}","time":"{:02}:{:02}:{:02}"}}"#,
+                    json_escape(&e.path),
+                    e.size,
+                    e.is_directory(),
+                    e.attributes,
+                    m.year, m.month, m.day, m.hour, m.minute, m.second
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    /// Extraer un fichero del disco (vacío si no existe)
+    pub fn extract_disk_file(&self, path: &str) -> Vec<u8> {
+        self.disk_image()
+            .and_then(|d| d.extract_file(path).ok())
+            .unwrap_or_default()
+    }
+
+    /// Escribir un fichero en el directorio raíz con la fecha del RTC
+    pub fn inject_disk_file(&mut self, name: &str, data: &[u8]) -> String {
+        let now = self.machine.bus.rtc.datetime();
+        let drive = &mut self.machine.bus.disk.drives[0];
+        if drive.write_protected {
+            return "❌ Error: Disco protegido contra escritura".to_string();
+        }
+        match drive.image_mut() {
+            Some(disk) => match disk.inject_file(name, data, now) {
+                Ok(()) => format!("✅ '{}' escrito en el disco ({} bytes)", name, data.len()),
+                Err(e) => format!("❌ Error: {}", e),
+            },
+            None => "❌ Error: No hay disco cargado".to_string(),
+        }
+    }
+
+    /// Imagen .DSK completa de la unidad A, con los cambios realizados
+    pub fn get_disk_image(&self) -> Vec<u8> {
+        self.disk_image().map(|d| d.data().to_vec()).unwrap_or_default()
+    }
+
+    /// Información de carga de un binario BLOAD o .COM del disco
+    pub fn disk_file_load_info(&self, path: &str) -> Option<LoadInfo> {
+        let data = self.disk_image()?.extract_file(path).ok()?;
+        if let Some((header, _)) = BloadHeader::parse(&data) {
+            return Some(self.create_bload_info(&header));
+        }
+        if path.to_ascii_uppercase().ends_with(".COM") {
+            // MSX-DOS carga los .COM en la TPA, a partir de 0100h
+            return Some(self.create_load_info(0x0100, data.len() as u32));
+        }
+        None
+    }
+
+    /// Convertir un gráfico BSAVE del disco (SCREEN 5) a RGBA
+    pub fn disk_file_to_rgba(&self, path: &str) -> Vec<u8> {
+        let data = self.extract_disk_file(path);
+        match BloadHeader::parse(&data) {
+            Some((_, body)) => self.transform_to_rgba(body),
+            None => self.transform_to_rgba(&data),
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // IMÁGENES DE CINTA .CAS
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Cargar una imagen .CAS y separar sus ficheros
+    pub fn load_cas_image(&mut self, data: &[u8]) -> String {
+        match CasImage::parse(data) {
+            Ok(image) => {
+                let message = format!(
+                    "✅ Cinta cargada: {} bloques, {} ficheros",
+                    image.blocks().len(),
+                    image.files().len()
+                );
+                self.cassette = Some(image);
+                message
+            }
+            Err(e) => format!("❌ Error: {}", e),
+        }
+    }
+
+    /// Listar los ficheros de la cinta como JSON
+    pub fn list_cas_files(&self) -> String {
+        let Some(image) = &self.cassette else {
+            return "[]".to_string();
+        };
+
+        let items: Vec<String> = image
+            .files()
+            .iter()
+            .enumerate()
+            .map(|(index, file)| {
+                let addresses = match file.bload_header() {
+                    Some(h) => format!(
+                        r#","start":{},"end":{},"exec":{}"#,
+                        h.start, h.end, h.exec
+                    ),
+                    None => String::new(),
+                };
+                format!(
+                    r#"{{"index":{},"name":"{}","type":"{}","size":{},"blocks":{},"loader":"{}"{}}}"#,
+                    index,
+                    json_escape(&file.name),
+                    file.file_type.name(),
+                    file.contents().len(),
+                    file.blocks.len(),
+                    json_escape(&file.loader_command()),
+                    addresses
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    /// Contenido de un fichero de la cinta (vacío si no existe)
+    pub fn extract_cas_file(&self, index: u32) -> Vec<u8> {
+        self.cas_file(index).map(|f| f.contents()).unwrap_or_default()
+    }
+
+    /// Información de carga: BLOAD con sus direcciones, BASIC en TXTTAB
+    pub fn cas_file_load_info(&self, index: u32) -> Option<LoadInfo> {
+        let file = self.cas_file(index)?;
+        match file.file_type {
+            CasFileType::Binary => Some(self.create_bload_info(&file.bload_header()?)),
+            CasFileType::Basic => Some(self.create_load_info(
+                cas::BASIC_TEXT_START as u32,
+                file.contents().len() as u32,
+            )),
+            CasFileType::Ascii | CasFileType::Custom => None,
+        }
+    }
+
+    /// Imagen .CAS de la cinta cargada, realineada a 8 bytes
+    pub fn get_cas_image(&self) -> Vec<u8> {
+        self.cassette.as_ref().map(|c| c.to_bytes()).unwrap_or_default()
+    }
+
+    /// Audio FSK de la cinta cargada a 1200 o 2400 baudios, como WAV
+    pub fn cas_to_wav(&self, baud: u32) -> Vec<u8> {
+        self.cassette
+            .as_ref()
+            .and_then(|c| tape::encode_wav(c, baud).ok())
+            .unwrap_or_default()
+    }
+
+    /// Decodificar una grabación WAV y cargarla como cinta
+    pub fn load_wav_tape(&mut self, wav: &[u8]) -> String {
+        match tape::decode_wav(wav) {
+            Ok(decoded) => {
+                let failed = decoded.reports.iter().filter(|r| !r.is_ok()).count();
+                let message = format!(
+                    "{} Cinta decodificada: {} bloques, {} con errores",
+                    if failed == 0 { "✅" } else { "⚠️" },
+                    decoded.reports.len(),
+                    failed
+                );
+                self.cassette = Some(decoded.image);
+                self.tape_report = decoded.reports;
+                message
+            }
+            Err(e) => {
+                self.tape_report.clear();
+                format!("❌ Error: {}", e)
+            }
+        }
+    }
+
+    /// Informe por bloque de la última decodificación de audio, como JSON
+    pub fn get_tape_report(&self) -> String {
+        let items: Vec<String> = self
+            .tape_report
+            .iter()
+            .map(|r| {
+                let errors: Vec<String> = r
+                    .errors
+                    .iter()
+                    .map(|e| format!("\"{}\"", json_escape(e)))
+                    .collect();
+                format!(
+                    r#"{{"index":{},"sample":{},"bytes":{},"baud":{},"ok":{},"errors":[{}]}}"#,
+                    r.index,
+                    r.sample_offset,
+                    r.bytes,
+                    r.baud,
+                    r.is_ok(),
+                    errors.join(",")
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // ARCHIVOS ZIP
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Listar las entradas de un ZIP como JSON, con el tipo detectado por
+    /// nombre y primeros bytes (no se descomprime ninguna entera)
+    pub fn list_zip_entries(&self, zip_data: &[u8]) -> String {
+        let Ok(archive) = ZipArchive::parse(zip_data) else {
+            return "[]".to_string();
+        };
+
+        let items: Vec<String> = archive
+            .entries()
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.is_directory())
+            .map(|(index, e)| {
+                let m = e.modified;
+                format!(
+                    r#"{{"index":{},"name":"{}","size":{},"compressed":{},"method":"{}","crc":"{:08X}","date":"{:04}-{:02}-{:02}","kind":"{}"}}"#,
+                    index,
+                    json_escape(&e.name),
+                    e.size,
+                    e.compressed_size,
+                    e.method_name(),
+                    e.crc32,
+                    m.year, m.month, m.day,
+                    archive.classify(index).name()
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    /// Descomprimir una entrada del ZIP (vacío si falla o el CRC no cuadra)
+    pub fn extract_zip_entry(&self, zip_data: &[u8], index: u32) -> Vec<u8> {
+        ZipArchive::parse(zip_data)
+            .and_then(|archive| archive.extract(index as usize))
+            .unwrap_or_default()
+    }
+
+    /// Índice de la entrada más apropiada para "rom", "bios", "disk", "tape",
+    /// "graphics" o "binary"; -1 si no hay ninguna. Solo se descomprime luego
+    /// la elegida, con `extract_zip_entry`
+    pub fn pick_zip_member(&self, zip_data: &[u8], kind: &str) -> i32 {
+        let (Ok(archive), Some(kind)) = (ZipArchive::parse(zip_data), FileKind::from_name(kind)) else {
+            return -1;
+        };
+        archive.pick(kind).map(|i| i as i32).unwrap_or(-1)
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // ARCHIVOS LZH / LHA
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Listar las entradas de un LZH como JSON, con el tipo detectado
+    pub fn list_lzh_entries(&self, lzh_data: &[u8]) -> String {
+        let Ok(archive) = LzhArchive::parse(lzh_data) else {
+            return "[]".to_string();
+        };
+
+        let items: Vec<String> = archive
+            .entries()
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.is_directory())
+            .map(|(index, e)| {
+                let m = e.modified;
+                format!(
+                    r#"{{"index":{},"name":"{}","size":{},"compressed":{},"method":"{}","crc":"{:04X}","date":"{:04}-{:02}-{:02}","level":{},"kind":"{}"}}"#,
+                    index,
+                    json_escape(&e.name),
+                    e.size,
+                    e.compressed_size,
+                    json_escape(&e.method),
+                    e.crc16,
+                    m.year, m.month, m.day,
+                    e.level,
+                    archive.classify(index).name()
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    /// Descomprimir una entrada del LZH (vacío si falla o el CRC no cuadra)
+    pub fn extract_lzh_entry(&self, lzh_data: &[u8], index: u32) -> Vec<u8> {
+        LzhArchive::parse(lzh_data)
+            .and_then(|archive| archive.extract(index as usize))
+            .unwrap_or_default()
+    }
+
+    /// Índice de la entrada más apropiada del tipo pedido; -1 si no hay ninguna
+    pub fn pick_lzh_member(&self, lzh_data: &[u8], kind: &str) -> i32 {
+        let (Ok(archive), Some(kind)) = (LzhArchive::parse(lzh_data), FileKind::from_name(kind)) else {
+            return -1;
+        };
+        archive.pick(kind).map(|i| i as i32).unwrap_or(-1)
+    }
+
+    /// Cargar una entrada de un ZIP o LZH según su tipo: BIOS, cartucho en
+    /// el slot 1 (MegaROM con el mapper adivinado si pasa de 48 KB), disco
+    /// en la unidad A o cinta
+    pub fn load_archive_member(&mut self, archive: &[u8], index: u32) -> String {
+        let (name, data) = match extract_archive_member(archive, index as usize) {
+            Ok(member) => member,
+            Err(e) => return format!("❌ Error: {}", e),
+        };
+        match FileKind::classify(&name, &data) {
+            FileKind::Bios => self.load_bios(&data, &name, "auto"),
+            FileKind::Rom if data.len() > 0xC000 => self.insert_megarom(1, 0, &data, ""),
+            FileKind::Rom => self.insert_rom(1, 0, &data),
+            FileKind::Disk => self.insert_disk(0, &data),
+            FileKind::Tape => self.load_cas_image(&data),
+            FileKind::Graphics | FileKind::Binary => match BloadHeader::parse(&data) {
+                Some((header, body)) => format!(
+                    "✅ '{}': BLOAD {:04X}h-{:04X}h, ejecución {:04X}h ({} bytes)",
+                    name, header.start, header.end, header.exec, body.len()
+                ),
+                None => format!("✅ '{}': {} bytes sin cabecera BLOAD", name, data.len()),
+            },
+            FileKind::Unknown => format!("❌ Error: Tipo de '{}' no reconocido", name),
+        }
+    }
+
+    /// Convertir un gráfico BSAVE de un ZIP o LZH (SCREEN 5) a RGBA
+    pub fn archive_member_to_rgba(&self, archive: &[u8], index: u32) -> Vec<u8> {
+        match extract_archive_member(archive, index as usize) {
+            Ok((_, data)) => match BloadHeader::parse(&data) {
+                Some((_, body)) => self.transform_to_rgba(body),
+                None => self.transform_to_rgba(&data),
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // DESCOMPRESORES DE GRÁFICOS
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Descomprimir con "pletter", "bitbuster", "aplib", "zx0", "zx7" o
+    /// "rle" desde `offset` (vacío si el formato no encaja)
+    pub fn unpack_data(&self, packer: &str, data: &[u8], offset: u32) -> Vec<u8> {
+        let (Some(packer), Some(block)) = (Packer::from_name(packer), data.get(offset as usize..)) else {
+            return Vec::new();
+        };
+        packers::unpack(packer, block).map(|u| u.data).unwrap_or_default()
+    }
+
+    /// Comprimir con "pletter", "bitbuster", "aplib" o "zx0" para volver a
+    /// meter un gráfico editado en la ROM (vacío si no hay compresor)
+    pub fn pack_data(&self, packer: &str, data: &[u8]) -> Vec<u8> {
+        Packer::from_name(packer)
+            .ok_or_else(|| format!("Formato '{}' desconocido", packer))
+            .and_then(|packer| packers::pack(packer, data))
+            .unwrap_or_default()
+    }
+
+    /// Probar todos los formatos en `offset`: JSON con los que descomprimen
+    /// limpio, el más prometedor primero
+    pub fn probe_packers(&self, data: &[u8], offset: u32) -> String {
+        let items: Vec<String> = packers::probe(data, offset as usize)
+            .iter()
+            .map(|hit| {
+                format!(
+                    r#"{{"packer":"{}","offset":{},"consumed":{},"size":{},"screen":{}}}"#,
+                    hit.packer.name(),
+                    hit.offset,
+                    hit.consumed,
+                    hit.size,
+                    hit.screen.map(|m| m.number().to_string()).unwrap_or("null".to_string())
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    /// Renderizar VRAM en SCREEN 2, 5 u 8; con 0 se deduce por el tamaño
+    pub fn render_screen(&self, data: &[u8], screen: u8) -> Vec<u8> {
+        let mode = match screen {
+            0 => ScreenMode::guess(data.len()),
+            n => ScreenMode::from_number(n),
+        };
+        match mode {
+            Some(ScreenMode::Screen2) => screen::render_screen2(data, &screen::TMS9918_PALETTE),
+            Some(ScreenMode::Screen5) => self.transform_to_rgba(&data[..data.len().min(screen::SC5_BITMAP)]),
+            Some(ScreenMode::Screen8) => screen::render_screen8(&data[..data.len().min(screen::SC8_BITMAP)]),
+            None => Vec::new(),
+        }
+    }
+
+    /// Descomprimir y pasar directamente al renderizador del modo pedido
+    pub fn unpack_to_rgba(&self, packer: &str, data: &[u8], offset: u32, screen: u8) -> Vec<u8> {
+        self.render_screen(&self.unpack_data(packer, data, offset), screen)
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // BUSCADOR DE GRÁFICOS
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Regiones de una ROM que parecen gráficos, de más a menos probable,
+    /// como JSON. `bank_size` 0 trocea solo las MegaROM en bancos de 8 KB;
+    /// el código alcanzable desde la cabecera no se propone
+    pub fn find_graphics(&self, rom: &[u8], bank_size: u32) -> String {
+        self.find_graphics_with_cdl(rom, bank_size, &[])
+    }
+
+    /// Como `find_graphics` con las marcas de un CDL exportado con
+    /// `cdl_export` de la misma ROM: lo ejecutado no se propone y lo que
+    /// subió a la VRAM sale primero con puntuación 100
+    pub fn find_graphics_with_cdl(&self, rom: &[u8], bank_size: u32, cdl: &[u8]) -> String {
+        let marks = match cdl_marks(rom, cdl) {
+            Ok(marks) => marks,
+            Err(e) => return format!("❌ Error: {}", e),
+        };
+        let code = CodeAnalyzer::for_rom(rom).cdl(&marks).trace(rom);
+        let items: Vec<String> = GraphicsFinder::new(bank_size as usize)
+            .skip_code(&code)
+            .hints(&marks)
+            .find(rom)
+            .iter()
+            .map(|r| {
+                let (packer, screen) = match r.mode {
+                    DecodeMode::Compressed { packer, screen } => (
+                        format!(r#""{}""#, packer.name()),
+                        screen.map(|m| m.number().to_string()).unwrap_or("null".to_string()),
+                    ),
+                    _ => ("null".to_string(), "null".to_string()),
+                };
+                format!(
+                    r#"{{"bank":{},"offset":{},"length":{},"mode":"{}","packer":{},"screen":{},"score":{},"entropy":{:.2}}}"#,
+                    r.bank, r.offset, r.length, r.mode.name(), packer, screen, r.score, r.entropy
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    /// Pintar una región candidata: "raw4bpp", "sc2", "sprites" o el nombre
+    /// de un compresor para descomprimirla y deducir la pantalla
+    pub fn graphics_region_to_rgba(&self, rom: &[u8], offset: u32, length: u32, mode: &str) -> Vec<u8> {
+        let start = (offset as usize).min(rom.len());
+        let region = &rom[start..(start + length as usize).min(rom.len())];
+        match mode {
+            "raw4bpp" => self.transform_to_rgba(region),
+            "sc2" => screen::render_screen2(region, &screen::TMS9918_PALETTE),
+            "sprites" => screen::render_sprites(region),
+            packer => self.unpack_to_rgba(packer, rom, offset, 0),
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // MAPA DE CALOR DE ROMS Y BIOS
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Mapa de calor RGBA de una ROM cargada en `base_address`. Con
+    /// `bank_size` 0 las filas se anotan con el mapa de memoria; si no, con
+    /// los bancos del mapper
+    pub fn render_heatmap(&self, data: &[u8], base_address: u32, bank_size: u32) -> Vec<u8> {
+        heatmap::render(data, &self.heatmap_regions(data.len(), base_address, bank_size)).rgba
+    }
+
+    /// Dimensiones y anotación de cada fila del mapa de calor, como JSON
+    pub fn heatmap_layout(&self, data: &[u8], base_address: u32, bank_size: u32) -> String {
+        let map = heatmap::render(data, &self.heatmap_regions(data.len(), base_address, bank_size));
+        let rows: Vec<String> = map
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                format!(
+                    r#"{{"row":{},"offset":{},"address":"0x{:04X}","label":"{}"}}"#,
+                    index,
+                    row.offset,
+                    base_address as usize + row.offset,
+                    json_escape(&row.label)
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"width":{},"height":{},"block":{},"row_bytes":{},"rows":[{}]}}"#,
+            map.width,
+            map.height,
+            heatmap::BLOCK_SIZE,
+            heatmap::ROW_BYTES,
+            rows.join(",")
+        )
+    }
+
+    /// Mapa de calor del BIOS cargado con `load_bios` (vacío si no hay)
+    pub fn bios_heatmap(&self) -> Vec<u8> {
+        self.render_heatmap(&self.bios_data, 0x0000, 0)
+    }
+
+    /// Entropía y clase de cada bloque de 64 bytes, como JSON
+    pub fn get_block_stats(&self, data: &[u8]) -> String {
+        let items: Vec<String> = heatmap::block_stats(data)
+            .iter()
+            .map(|b| {
+                format!(
+                    r#"{{"offset":{},"entropy":{:.2},"padding":{:.2},"text":{:.2},"code":{:.2},"class":"{}"}}"#,
+                    b.offset, b.entropy, b.padding, b.text, b.code, b.class.name()
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // DESENSAMBLADOR Z80
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Listado de un bloque cargado en `origin` desde `start`; lo anterior
+    /// a `start` sale como DB
+    pub fn disassemble(&self, data: &[u8], origin: u32, start: u32) -> String {
+        Disassembler::new(self.symbols.clone())
+            .disassemble(data, origin as u16, start as u16)
+            .listing()
+    }
+
+    /// Desensamblar un binario con su LoadInfo: origen en la dirección de
+    /// carga y entrada en la de ejecución
+    pub fn disassemble_load_info(&self, data: &[u8], info: &LoadInfo) -> String {
+        self.disassemble(data, info.load_address, info.start_address)
+    }
+
+    /// Desensamblar un cartucho siguiendo el flujo desde las entradas de su
+    /// cabecera: lo no alcanzado sale como texto o datos. Las MegaROM se
+    /// listan por bancos; sin cabecera se desensambla linealmente desde 4000h
+    pub fn disassemble_rom(&self, rom: &[u8]) -> String {
+        self.disassemble_rom_with_cdl(rom, &[])
+    }
+
+    /// Como `disassemble_rom` con las marcas de un CDL de la misma ROM: lo
+    /// ejecutado sale como instrucciones aunque el flujo estático no llegue
+    /// (saltos calculados, tablas) y lo leído o enviado al VDP como datos
+    pub fn disassemble_rom_with_cdl(&self, rom: &[u8], cdl: &[u8]) -> String {
+        let marks = match cdl_marks(rom, cdl) {
+            Ok(marks) => marks,
+            Err(e) => return format!("❌ Error: {}", e),
+        };
+        let Some(header) = RomHeader::parse(rom) else {
+            return self.disassemble(rom, 0x4000, 0x4000);
+        };
+        let map = CodeAnalyzer::for_rom(rom).cdl(&marks).analyze(rom);
+        let disassembler = header
+            .entries()
+            .into_iter()
+            .fold(Disassembler::new(self.symbols.clone()), |d, (address, name)| d.entry(address, name));
+        if map.mapper == MapperType::Plain {
+            return disassembler.disassemble_map(rom, &map, 0, rom.len(), header.origin(rom.len())).listing();
+        }
+        let bank_size = map.mapper.bank_size();
+        (0..rom.len().div_ceil(bank_size))
+            .map(|bank| {
+                let offset = bank * bank_size;
+                let origin = map.origin_of(offset).unwrap_or(0x8000);
+                format!(
+                    "; ═══ Banco {} ({}) ═══\n{}",
+                    bank,
+                    map.mapper.name(),
+                    disassembler.disassemble_map(rom, &map, offset, bank_size, origin).listing()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Desensamblar el BIOS cargado con `load_bios` (vacío si no hay)
+    /// siguiendo el flujo desde el reset y la tabla de saltos
+    pub fn disassemble_bios(&self) -> String {
+        if self.bios_data.is_empty() {
+            return String::new();
+        }
+        let map = CodeAnalyzer::for_bios().analyze(&self.bios_data);
+        Disassembler::new(self.symbols.clone())
+            .disassemble_map(&self.bios_data, &map, 0, self.bios_data.len(), 0x0000)
+            .listing()
+    }
+
+    /// Separar código y datos de un cartucho: resumen JSON con el mapper,
+    /// bytes de cada clase, entradas y cambios de banco
+    pub fn analyze_rom(&self, rom: &[u8]) -> String {
+        let map = CodeAnalyzer::for_rom(rom).analyze(rom);
+        let switches: Vec<String> = map
+            .switches
+            .iter()
+            .map(|s| {
+                format!(
+                    r#"{{"address":{},"offset":{},"page":{},"bank":{}}}"#,
+                    s.address,
+                    s.offset,
+                    s.page,
+                    s.bank.map(|b| b.to_string()).unwrap_or("null".to_string())
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"mapper":"{}","size":{},"code":{},"data":{},"text":{},"graphics":{},"instructions":{},"labels":{},"switches":[{}]}}"#,
+            map.mapper.name(),
+            rom.len(),
+            map.count(ByteKind::Code),
+            map.count(ByteKind::Data),
+            map.count(ByteKind::Text),
+            map.count(ByteKind::Graphics),
+            map.instructions.len(),
+            map.labels.len(),
+            switches.join(",")
+        )
+    }
+
+    /// Clase de cada byte del cartucho (0 desconocido, 1 código, 2 datos,
+    /// 3 texto, 4 gráficos)
+    pub fn rom_code_map(&self, rom: &[u8]) -> Vec<u8> {
+        CodeAnalyzer::for_rom(rom).analyze(rom).export()
+    }
+
+    /// Líneas del desensamblado como JSON, con etiqueta y referencias
+    pub fn disassemble_json(&self, data: &[u8], origin: u32, start: u32) -> String {
+        let listing = Disassembler::new(self.symbols.clone()).disassemble(data, origin as u16, start as u16);
+        let items: Vec<String> = listing
+            .lines
+            .iter()
+            .map(|line| {
+                let address = line.address();
+                let bytes: Vec<String> = line.bytes().iter().map(|b| format!("{:02X}", b)).collect();
+                let label = listing
+                    .label(address)
+                    .map(|name| format!(r#""{}""#, json_escape(name)))
+                    .unwrap_or_else(|| "null".to_string());
+                let xrefs: Vec<String> = listing
+                    .xrefs
+                    .get(&address)
+                    .map(|from| from.iter().map(|a| a.to_string()).collect())
+                    .unwrap_or_default();
+                format!(
+                    r#"{{"address":{},"bytes":"{}","text":"{}","label":{},"xrefs":[{}]}}"#,
+                    address,
+                    bytes.join(" "),
+                    json_escape(&line.text(&listing.symbols)),
+                    label,
+                    xrefs.join(",")
+                )
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // ENSAMBLADOR Z80
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Añadir un fichero para INCLUDE o INCBIN
+    pub fn add_asm_file(&mut self, name: &str, data: &[u8]) {
+        self.asm_files.insert(name.to_string(), data.to_vec());
+    }
+
+    pub fn clear_asm_files(&mut self) {
+        self.asm_files.clear();
+    }
+
+    /// Ensamblar y devolver el fichero "rom", "bin" o "com" (vacío si hay
+    /// errores; `assemble_report` los detalla)
+    pub fn assemble(&self, source: &str, format: &str) -> Vec<u8> {
+        self.assembled(source, format).map(|(_, _, output)| output).unwrap_or_default()
+    }
+
+    /// Resultado del ensamblado como JSON: origen, entrada, tamaño del
+    /// fichero, símbolos definidos y errores con fichero y línea
+    pub fn assemble_report(&self, source: &str, format: &str) -> String {
+        match self.assembled(source, format) {
+            Ok((format, assembly, output)) => format!(
+                r#"{{"ok":true,"format":"{}","origin":{},"end":{},"entry":{},"size":{},"symbols":{},"errors":[]}}"#,
+                format.name(),
+                assembly.origin,
+                assembly.end(),
+                assembly.entry,
+                output.len(),
+                assembly.symbols.len()
+            ),
+            Err(errors) => errors_json(&errors),
+        }
+    }
+
+    /// Información de carga del fichero ensamblado: la página del
+    /// cartucho con INIT, la cabecera de BLOAD o la TPA de MSX-DOS
+    pub fn assemble_load_info(&self, source: &str, format: &str) -> Option<LoadInfo> {
+        let (format, assembly, output) = self.assembled(source, format).ok()?;
+        Some(match format {
+            OutputFormat::Rom => {
+                let mut info = self.create_load_info(assembly.rom_base() as u32, output.len() as u32);
+                info.start_address = RomHeader::parse(&output)?.init as u32;
+                info
+            }
+            OutputFormat::Bin => self.create_bload_info(&BloadHeader::parse(&output)?.0),
+            OutputFormat::Com => self.create_load_info(0x0100, output.len() as u32),
+        })
+    }
+
+    /// Fichero de símbolos ("sjasm", "noice" u "openmsx") con las
+    /// etiquetas y constantes del fuente
+    pub fn assemble_symbols(&self, source: &str, format: &str, symbol_format: &str) -> String {
+        let Some(symbol_format) = SymbolFormat::from_name(symbol_format) else {
+            return String::new();
+        };
+        self.assembled(source, format)
+            .map(|(_, assembly, _)| assembly.symbol_file(symbol_format))
+            .unwrap_or_default()
+    }
+
+    /// Listado con dirección, bytes y estados T de cada línea
+    pub fn assemble_listing(&self, source: &str, format: &str) -> String {
+        self.assembled(source, format).map(|(_, assembly, _)| assembly.listing()).unwrap_or_default()
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // MÓDULOS REUBICABLES Y ENLAZADOR
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Ensamblar un módulo reubicable y devolver su fichero objeto (vacío
+    /// si hay errores)
+    pub fn assemble_object(&self, source: &str, module: &str) -> Vec<u8> {
+        self.asm_files
+            .iter()
+            .fold(Assembler::new(OutputFormat::Rom).symbols(&self.symbols), |a, (name, data)| a.file(name, data))
+            .assemble_object(module, source)
+            .map(|object| object.to_bytes())
+            .unwrap_or_default()
+    }
+
+    /// Añadir un fichero objeto para el siguiente enlazado
+    pub fn add_object(&mut self, data: &[u8]) -> String {
+        match ObjectFile::parse(data) {
+            Ok(object) => {
+                let status = format!("✅ Módulo {}: {} secciones", object.module, object.sections.len());
+                self.objects.push(object);
+                status
+            }
+            Err(e) => format!("❌ Error: {}", e),
+        }
+    }
+
+    pub fn clear_objects(&mut self) {
+        self.objects.clear();
+    }
+
+    /// Enlazar los módulos añadidos con un guion y devolver el fichero
+    /// final (vacío si hay errores; `link_report` los detalla)
+    pub fn link(&self, script: &str) -> Vec<u8> {
+        self.linked(script).ok().and_then(|linked| linked.output().ok()).unwrap_or_default()
+    }
+
+    /// Resultado del enlazado como JSON: salida, entrada, dónde quedó cada
+    /// sección y errores con fichero y línea
+    pub fn link_report(&self, script: &str) -> String {
+        let linked = match self.linked(script) {
+            Ok(linked) => linked,
+            Err(errors) => return errors_json(&errors),
+        };
+        let output = match linked.output() {
+            Ok(output) => output,
+            Err(e) => return errors_json(&[AsmError::new(&e)]),
+        };
+        let sections: Vec<String> = linked
+            .placements
+            .iter()
+            .map(|p| {
+                format!(
+                    r#"{{"section":"{}","module":"{}","region":"{}","bank":{},"address":{},"size":{}}}"#,
+                    json_escape(&p.section),
+                    json_escape(&p.module),
+                    json_escape(&p.region),
+                    p.bank.map_or("null".to_string(), |b| b.to_string()),
+                    p.address,
+                    p.size
+                )
+            })
+            .collect();
         format!(
-            "✅ BIOS cargado: {} ({} bytes) - Checksum: {}",
-            filename, size, checksum
+            r#"{{"ok":true,"format":"{}","mapper":"{}","size":{},"entry":{},"symbols":{},"sections":[{}],"errors":[]}}"#,
+            linked.format.name(),
+            linked.mapper.unwrap_or(MapperType::Plain).name(),
+            output.len(),
+            linked.entry,
+            linked.symbols.len(),
+            sections.join(",")
         )
     }
 
-    /// Obtener información del BIOS cargado actualmente
-    pub fn get_current_bios_info(&self) -> String {
-        match &self.current_bios {
-            Some(info) => format!(
-                r#"{{"filename":"{}","size":{},"type":"{}","checksum":"{}","loaded":true}}"#,
-                info.get_filename(),
-                info.get_size(),
-                info.get_bios_type(),
-                info.get_checksum()
-            ),
-            None => r#"{"loaded":false,"message":"No hay BIOS cargado"}"#.to_string(),
+    /// Mapa de enlazado en texto (vacío si hay errores)
+    pub fn link_map(&self, script: &str) -> String {
+        self.linked(script).map(|linked| linked.map()).unwrap_or_default()
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // TIEMPOS DE RUTINAS
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Estados T de la rutina de `entry` (bloques, caminos y bucles) con la
+    /// espera M1 y las escrituras al VDP demasiado juntas para el modo
+    /// `screen`, en JSON
+    pub fn routine_timing(&self, data: &[u8], origin: u32, entry: u32, screen: u8) -> String {
+        cycles_json(&CycleAnalyzer::new().screen(screen).analyze(data, origin as u16, entry as u16))
+    }
+
+    /// El mismo análisis como informe de texto con los símbolos conocidos
+    pub fn routine_timing_text(&self, data: &[u8], origin: u32, entry: u32, screen: u8) -> String {
+        CycleAnalyzer::new()
+            .screen(screen)
+            .analyze(data, origin as u16, entry as u16)
+            .text(&self.symbols)
+    }
+
+    /// Ensamblar y analizar la rutina de la etiqueta `routine`
+    pub fn assemble_timing(&self, source: &str, format: &str, routine: &str, screen: u8) -> String {
+        let assembly = match self.assembled(source, format) {
+            Ok((_, assembly, _)) => assembly,
+            Err(errors) => return errors_json(&errors),
+        };
+        match assembly.symbols.iter().find(|(name, _)| name.eq_ignore_ascii_case(routine)) {
+            Some((_, &entry)) => cycles_json(&CycleAnalyzer::new().screen(screen).analyze(
+                &assembly.code,
+                assembly.origin,
+                entry,
+            )),
+            None => errors_json(&[AsmError::new(&format!("Rutina no encontrada: {}", routine))]),
         }
     }
 
-    /// Obtener los datos del BIOS en formato Base64 para transmisión
-    pub fn get_bios_data(&self) -> Vec<u8> {
-        self.bios_data.clone()
+    // ═══════════════════════════════════════════════════════════════
+    // DEPURADOR
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Reset del Z80 (la memoria y los slots no cambian)
+    pub fn cpu_reset(&mut self) {
+        self.machine.cpu.reset();
     }
 
-    /// Verificar si hay BIOS cargado
-    pub fn has_bios_loaded(&self) -> bool {
-        self.current_bios.is_some()
+    /// Ensamblar, copiar el código a memoria por los slots activos, poner
+    /// PC en la entrada y tomar las líneas del fuente para `run_to_line`
+    pub fn debug_assemble(&mut self, source: &str, format: &str) -> String {
+        let assembly = match self.assembled(source, format) {
+            Ok((_, assembly, _)) => assembly,
+            Err(errors) => return errors_json(&errors),
+        };
+        self.machine.load(assembly.origin, &assembly.code);
+        self.machine.cpu.regs.pc = assembly.entry;
+        self.machine.cpu.halted = false;
+        self.debugger.set_source(&assembly);
+        format!(
+            r#"{{"ok":true,"origin":{},"end":{},"entry":{},"lines":{},"errors":[]}}"#,
+            assembly.origin,
+            assembly.end(),
+            assembly.entry,
+            self.debugger.source_lines().len()
+        )
     }
 
-    /// Descargar BIOS actual
-    pub fn unload_bios(&mut self) -> String {
-        if let Some(info) = &self.current_bios {
-            let filename = info.get_filename();
-            self.bios_data.clear();
-            self.current_bios = None;
-            format!("✅ BIOS '{}' descargado correctamente", filename)
-        } else {
-            "ℹ️ No había BIOS cargado".to_string()
+    /// Añadir un punto de ruptura: `kind` es "exec", "read", "write",
+    /// "access", "in", "out", "io" o "vdp"; `target` la dirección, rango,
+    /// puerto o registro; `condition` una expresión o vacía. Devuelve el
+    /// número asignado o el error en JSON
+    pub fn add_breakpoint(&mut self, kind: &str, target: &str, condition: &str) -> String {
+        let added = BreakKind::parse(kind, target).and_then(|kind| self.debugger.add_breakpoint(kind, condition));
+        match added {
+            Ok(id) => format!(r#"{{"ok":true,"id":{}}}"#, id),
+            Err(e) => format!(r#"{{"ok":false,"error":"{}"}}"#, json_escape(&e)),
         }
     }
 
-    /// Obtener descripción completa del BIOS para debugging
-    pub fn get_bios_description(&self) -> String {
-        match &self.current_bios {
-            Some(info) => {
+    pub fn remove_breakpoint(&mut self, id: u32) -> bool {
+        self.debugger.remove_breakpoint(id)
+    }
+
+    pub fn enable_breakpoint(&mut self, id: u32, enabled: bool) -> bool {
+        self.debugger.enable_breakpoint(id, enabled)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.debugger.clear_breakpoints();
+    }
+
+    pub fn list_breakpoints(&self) -> String {
+        let items: Vec<String> = self
+            .debugger
+            .breakpoints()
+            .iter()
+            .map(|b| {
                 format!(
-                    "📋 INFORMACIÓN BIOS\n━━━━━━━━━━━━━━━━━━━━━━\n\
-                    Archivo: {}\n\
-                    Tamaño: {} bytes\n\
-                    Tipo: {}\n\
-                    Checksum: {}\n\
-                    Estado: {}\n\
-                    Ubicación: Slot 0 (0x0000-0x3FFF)",
-                    info.get_filename(),
-                    info.get_size(),
-                    info.get_bios_type(),
-                    info.get_checksum(),
-                    if info.is_loaded() { "✅ CARGADO" } else { "❌ NO CARGADO" }
+                    r#"{{"id":{},"kind":"{}","target":"{}","condition":"{}","enabled":{},"hits":{}}}"#,
+                    b.id,
+                    b.kind.name(),
+                    b.kind.target(),
+                    json_escape(b.condition.as_ref().map_or("", |c| c.text())),
+                    b.enabled,
+                    b.hits
                 )
-            }
-            None => "❌ No hay BIOS cargado en el sistema".to_string(),
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    /// Ejecutar una instrucción; todos los pasos devuelven la parada en JSON
+    pub fn step_into(&mut self) -> String {
+        let stop = self.debugger.step_into(&mut self.machine);
+        self.stop_json(&stop)
+    }
+
+    /// Un paso que ejecuta enteras las llamadas y las instrucciones que se
+    /// repiten, con un máximo de `limit` estados T
+    pub fn step_over(&mut self, limit: u32) -> String {
+        let stop = self.debugger.step_over(&mut self.machine, limit as u64);
+        self.stop_json(&stop)
+    }
+
+    /// Ejecutar hasta salir de la rutina actual
+    pub fn step_out(&mut self, limit: u32) -> String {
+        let stop = self.debugger.step_out(&mut self.machine, limit as u64);
+        self.stop_json(&stop)
+    }
+
+    /// Ejecutar hasta un punto de ruptura o agotar `limit` estados T
+    pub fn run(&mut self, limit: u32) -> String {
+        let stop = self.debugger.run(&mut self.machine, limit as u64);
+        self.stop_json(&stop)
+    }
+
+    pub fn run_to(&mut self, address: u32, limit: u32) -> String {
+        let stop = self.debugger.run_to(&mut self.machine, address as u16, limit as u64);
+        self.stop_json(&stop)
+    }
+
+    /// Ejecutar hasta la primera línea con código desde `line` del fichero
+    /// `file` del último `debug_assemble`
+    pub fn run_to_line(&mut self, file: &str, line: u32, limit: u32) -> String {
+        match self.debugger.run_to_line(&mut self.machine, file, line as usize, limit as u64) {
+            Ok(stop) => self.stop_json(&stop),
+            Err(e) => format!(r#"{{"ok":false,"error":"{}"}}"#, json_escape(&e)),
         }
     }
 
-    /// Validar integridad BIOS comparando checksums
-    pub fn validate_bios_checksum(&self, expected_checksum: &str) -> bool {
-        match &self.current_bios {
-            Some(info) => info.get_checksum().eq_ignore_ascii_case(expected_checksum),
+    /// Registros del Z80, flags y contadores de tiempo en JSON
+    pub fn cpu_registers(&self) -> String {
+        let regs = &self.machine.cpu.regs;
+        let values: Vec<String> = REGISTER_NAMES
+            .iter()
+            .map(|name| format!(r#""{}":{}"#, name, regs.get(name).unwrap_or(0)))
+            .collect();
+        format!(
+            r#"{{{},"flags":"{}","halted":{},"cycles":{},"frames":{}}}"#,
+            values.join(","),
+            regs.flags_text(),
+            self.machine.cpu.halted,
+            self.machine.cycles,
+            self.machine.frames
+        )
+    }
+
+    /// Cambiar un registro por su nombre (A, HL, IX, AF', SP, IM...)
+    pub fn set_register(&mut self, name: &str, value: u32) -> bool {
+        self.machine.cpu.regs.set(name, value as u16)
+    }
+
+    /// Leer memoria sin efectos sobre el hardware
+    pub fn read_memory(&self, address: u32, length: u32) -> Vec<u8> {
+        (0..length.min(0x10000)).map(|i| self.machine.bus.peek((address + i) as u16)).collect()
+    }
+
+    /// Escribir memoria a través de los slots activos
+    pub fn write_memory(&mut self, address: u32, data: &[u8]) {
+        self.machine.load(address as u16, data);
+    }
+
+    pub fn vdp_registers(&self) -> Vec<u8> {
+        self.machine.bus.vdp.registers.to_vec()
+    }
+
+    pub fn set_vdp_register(&mut self, register: u8, value: u8) -> bool {
+        match self.machine.bus.vdp.registers.get_mut(register as usize) {
+            Some(slot) => {
+                *slot = value;
+                true
+            }
             None => false,
         }
     }
 
-    /// Convierte MSX2 4bpp a RGBA 32bpp
-    pub fn transform_to_rgba(&self, bin_data: &[u8]) -> Vec<u8> {
-        let mut rgba_output = Vec::with_capacity(bin_data.len() * 8);
+    /// Atender un paquete de GDB sin el marco `$...#cc` (un WebSocket hace
+    /// de puente); cada "c" corre como mucho un segundo de máquina
+    pub fn gdb_packet(&mut self, packet: &str) -> String {
+        self.gdb.handle(&mut self.machine, &mut self.debugger, packet)
+    }
+
+    /// Atender una petición DAP en JSON; devuelve la respuesta y los
+    /// eventos como lista JSON. En el navegador `launch` necesita el
+    /// fuente en `source`
+    pub fn dap_request(&mut self, request: &str) -> String {
+        format!("[{}]", self.dap.handle(&mut self.machine, &mut self.debugger, request).join(","))
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // TRAZAS
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Empezar a trazar con un histórico de `capacity` instrucciones.
+    /// `ranges` es una lista "4000h-7FFFh,C000h-C0FFh" y `slots` otra
+    /// "3-0,1"; vacías no filtran
+    pub fn trace_start(&mut self, capacity: u32, ranges: &str, slots: &str) -> String {
+        let mut tracer = Tracer::new(capacity as usize).symbols(self.symbols.clone());
+        for range in ranges.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            match link::range_of(range) {
+                Ok((start, end)) => tracer = tracer.range(start, end),
+                Err(e) => return format!("❌ Error: {}", e),
+            }
+        }
+        for slot in slots.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match trace::parse_slot(slot) {
+                Ok((primary, secondary)) => tracer = tracer.slot(primary, secondary),
+                Err(e) => return format!("❌ Error: {}", e),
+            }
+        }
+        self.machine.trace = Some(tracer);
+        format!("✅ Traza activa: últimas {} instrucciones", capacity)
+    }
+
+    /// Pausar la traza; el histórico se conserva
+    pub fn trace_stop(&mut self) {
+        if let Some(trace) = self.machine.trace.as_mut() {
+            trace.active = false;
+        }
+    }
+
+    pub fn trace_resume(&mut self) {
+        if let Some(trace) = self.machine.trace.as_mut() {
+            trace.active = true;
+        }
+    }
+
+    pub fn trace_clear(&mut self) {
+        if let Some(trace) = self.machine.trace.as_mut() {
+            trace.clear();
+        }
+    }
+
+    pub fn trace_text(&self) -> String {
+        self.machine.trace.as_ref().map(Tracer::text).unwrap_or_default()
+    }
+
+    pub fn trace_binary(&self) -> Vec<u8> {
+        self.machine.trace.as_ref().map(Tracer::binary).unwrap_or_default()
+    }
+
+    /// Comparar dos trazas binarias: índice de la primera instrucción
+    /// distinta, -1 si son iguales o -2 si alguna no es válida
+    pub fn trace_compare(&self, a: &[u8], b: &[u8]) -> i32 {
+        match (trace::read_binary(a), trace::read_binary(b)) {
+            (Ok(a), Ok(b)) => trace::first_difference(&a, &b).map(|i| i as i32).unwrap_or(-1),
+            _ => -2,
+        }
+    }
+
+    /// Texto de la primera diferencia entre dos trazas binarias
+    pub fn trace_diff(&self, a: &[u8], b: &[u8]) -> String {
+        match (trace::read_binary(a), trace::read_binary(b)) {
+            (Ok(a), Ok(b)) => trace::describe_difference(&a, &b, &self.symbols)
+                .unwrap_or_else(|| "✅ Trazas idénticas".to_string()),
+            (Err(e), _) | (_, Err(e)) => format!("❌ Error: {}", e),
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // PERFILADOR
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Empezar a medir desde cero; las rutinas salen de los símbolos
+    /// importados
+    pub fn profile_start(&mut self) -> String {
+        self.machine.profiler = Some(Profiler::new().symbols(self.symbols.clone()));
+        format!("✅ Perfilador activo con {} símbolos", self.symbols.len())
+    }
+
+    /// Pausar la medida; lo acumulado se conserva
+    pub fn profile_stop(&mut self) {
+        if let Some(profiler) = self.machine.profiler.as_mut() {
+            profiler.active = false;
+        }
+    }
+
+    pub fn profile_resume(&mut self) {
+        if let Some(profiler) = self.machine.profiler.as_mut() {
+            profiler.active = true;
+        }
+    }
+
+    pub fn profile_clear(&mut self) {
+        if let Some(profiler) = self.machine.profiler.as_mut() {
+            profiler.clear();
+        }
+    }
+
+    /// Informe ordenado con las `top` primeras direcciones, rutinas,
+    /// bancos e interrupciones
+    pub fn profile_report(&self, top: u32) -> String {
+        self.machine.profiler.as_ref().map(|p| p.report(top as usize)).unwrap_or_default()
+    }
+
+    /// Pilas plegadas para flame graphs
+    pub fn profile_folded(&self) -> String {
+        self.machine.profiler.as_ref().map(Profiler::folded).unwrap_or_default()
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // REGISTRO DE CÓDIGO Y DATOS (CDL)
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Empezar a marcar los bytes de la ROM insertada en el slot: código
+    /// ejecutado, datos leídos y lo enviado a la VRAM por 98h/99h
+    pub fn cdl_start(&mut self, primary: u8, secondary: u8) -> String {
+        if primary > 3 || secondary > 3 {
+            return format!("❌ Error: Slot inválido: {}-{}", primary, secondary);
+        }
+        let slot = SlotId { primary, secondary };
+        let size = self.machine.bus.slots.content(slot).rom_size();
+        if size == 0 {
+            return format!("❌ Error: No hay ROM en el slot {}-{}", primary, secondary);
+        }
+        self.machine.cdl = Some(CodeDataLog::new(slot, size));
+        format!("✅ CDL activo para {} KB del slot {}-{}", size / 1024, primary, secondary)
+    }
+
+    /// Pausar el registro; las marcas se conservan
+    pub fn cdl_stop(&mut self) {
+        if let Some(cdl) = self.machine.cdl.as_mut() {
+            cdl.active = false;
+        }
+    }
+
+    pub fn cdl_resume(&mut self) {
+        if let Some(cdl) = self.machine.cdl.as_mut() {
+            cdl.active = true;
+        }
+    }
+
+    pub fn cdl_clear(&mut self) {
+        if let Some(cdl) = self.machine.cdl.as_mut() {
+            cdl.clear();
+        }
+    }
+
+    /// Fichero CDL con las marcas (formato en el módulo `cdl`)
+    pub fn cdl_export(&self) -> Vec<u8> {
+        self.machine.cdl.as_ref().map(CodeDataLog::to_bytes).unwrap_or_default()
+    }
+
+    /// Cargar un CDL guardado: se suma al actual si es de la misma ROM y
+    /// si no lo sustituye
+    pub fn cdl_import(&mut self, data: &[u8]) -> String {
+        let imported = match CodeDataLog::from_bytes(data) {
+            Ok(cdl) => cdl,
+            Err(e) => return format!("❌ Error: {}", e),
+        };
+        let size = imported.marks().len();
+        match self.machine.cdl.as_mut() {
+            Some(cdl) if cdl.marks().len() == size => {
+                if let Err(e) = cdl.merge(imported.marks()) {
+                    return format!("❌ Error: {}", e);
+                }
+            }
+            _ => self.machine.cdl = Some(imported),
+        }
+        format!("✅ CDL de {} bytes cargado", size)
+    }
+
+    /// Resumen JSON: bytes de cada marca en total y por banco de
+    /// `bank_size` bytes (0 = 8 KB)
+    pub fn cdl_summary(&self, bank_size: u32) -> String {
+        let Some(cdl) = self.machine.cdl.as_ref() else {
+            return "null".to_string();
+        };
+        let size = match bank_size {
+            0 => mapper::PAGE_SIZE,
+            size => size as usize,
+        };
+        let banks: Vec<String> = cdl
+            .banks(size)
+            .iter()
+            .map(|b| format!(r#"{{"bank":{},"code":{},"data":{},"vram":{},"vdp":{}}}"#, b.bank, b.code, b.data, b.vram, b.vdp))
+            .collect();
+        format!(
+            r#"{{"slot":"{}-{}","size":{},"code":{},"data":{},"vram":{},"vdp":{},"uploads":{},"banks":[{}]}}"#,
+            cdl.slot.primary,
+            cdl.slot.secondary,
+            cdl.marks().len(),
+            cdl.count(cdl::CDL_CODE),
+            cdl.count(cdl::CDL_DATA),
+            cdl.count(cdl::CDL_VRAM),
+            cdl.count(cdl::CDL_VDP),
+            cdl::uploads(cdl.marks()).len(),
+            banks.join(",")
+        )
+    }
 
-        for &byte in bin_data {
-            let pixel1 = (byte >> 4) & 0x0F;
-            let pixel2 = byte & 0x0F;
+    // ═══════════════════════════════════════════════════════════════
+    // SÍMBOLOS
+    // ═══════════════════════════════════════════════════════════════
 
-            rgba_output.extend_from_slice(&self.palette[pixel1 as usize]);
-            rgba_output.extend_from_slice(&self.palette[pixel2 as usize]);
+    /// Añadir las etiquetas de un fichero de sjasm, NoICE u openMSX a las
+    /// del MSX; las usan el desensamblador, el ensamblador y las búsquedas
+    pub fn import_symbols(&mut self, text: &str) -> String {
+        match self.symbols.import(text) {
+            0 => "❌ Error: el fichero no contiene símbolos".to_string(),
+            count => format!("✅ {} símbolos importados", count),
         }
-        rgba_output
+    }
+
+    pub fn export_symbols(&self, format: &str) -> String {
+        SymbolFormat::from_name(format).map(|f| self.symbols.export(f)).unwrap_or_default()
+    }
+
+    /// Volver a la tabla de la BIOS y las variables de sistema
+    pub fn reset_symbols(&mut self) {
+        self.symbols = SymbolTable::msx();
+    }
+
+    /// Convierte MSX2 4bpp a RGBA 32bpp
+    pub fn transform_to_rgba(&self, bin_data: &[u8]) -> Vec<u8> {
+        screen::render_screen5(bin_data, &self.palette)
     }
 
     /// ════════════════════════════════════════════════════════
@@ -672,6 +2311,95 @@ impl MSX2Processor {
     // FUNCIONES AUXILIARES
     // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 
+    /// Regiones para anotar el mapa de calor: bancos del mapper o los slots
+    /// del mapa de memoria que caen dentro de los datos
+    fn heatmap_regions(&self, len: usize, base_address: u32, bank_size: u32) -> Vec<RegionLabel> {
+        if bank_size > 0 {
+            return heatmap::bank_labels(len, bank_size as usize);
+        }
+        let base = base_address as usize;
+        let mut slots: Vec<&MemoryMapSlot> = self.memory_map.values().collect();
+        slots.sort_by_key(|slot| slot.address);
+        slots
+            .into_iter()
+            .filter_map(|slot| {
+                let start = (slot.address as usize).max(base);
+                let end = (slot.address + slot.size) as usize;
+                (start < end && start < base + len).then(|| RegionLabel {
+                    offset: start - base,
+                    length: end - start,
+                    name: slot.name.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn cas_file(&self, index: u32) -> Option<&CasFile> {
+        self.cassette.as_ref()?.files().get(index as usize)
+    }
+
+    fn disk_image(&self) -> Option<&DiskImage> {
+        self.machine.bus.disk.drives[0].image()
+    }
+
+    /// Ensamblar con los ficheros añadidos y los símbolos del MSX
+    fn assembled(&self, source: &str, format: &str) -> Result<(OutputFormat, Assembly, Vec<u8>), Vec<AsmError>> {
+        let format = OutputFormat::from_name(format)
+            .ok_or_else(|| vec![AsmError::new(&format!("Formato de salida desconocido: {}", format))])?;
+        let assembler = self
+            .asm_files
+            .iter()
+            .fold(Assembler::new(format).symbols(&self.symbols), |a, (name, data)| a.file(name, data));
+        let assembly = assembler.assemble(source)?;
+        let output = assembly.output(format).map_err(|e| vec![AsmError::new(&e)])?;
+        Ok((format, assembly, output))
+    }
+
+    /// Parada del depurador en JSON, con la línea del fuente si se conoce
+    fn stop_json(&self, stop: &Stop) -> String {
+        let id = match stop.reason {
+            StopReason::Breakpoint(id) => id.to_string(),
+            _ => "null".to_string(),
+        };
+        let access = stop.access.map_or("null".to_string(), |a| {
+            format!(r#"{{"kind":"{}","address":{},"value":{}}}"#, a.kind.name(), a.address, a.value)
+        });
+        let source = self.debugger.source_line(stop.pc).map_or(r#""file":null,"line":null"#.to_string(), |l| {
+            format!(r#""file":"{}","line":{}"#, json_escape(&l.file), l.line)
+        });
+        format!(
+            r#"{{"ok":true,"reason":"{}","id":{},"pc":{},"tstates":{},"access":{},{}}}"#,
+            stop.reason.name(),
+            id,
+            stop.pc,
+            stop.tstates,
+            access,
+            source
+        )
+    }
+
+    fn linked(&self, script: &str) -> Result<Linked, Vec<AsmError>> {
+        self.objects
+            .iter()
+            .fold(Linker::new().symbols(&self.symbols), |linker, object| linker.object(object.clone()))
+            .link(script)
+    }
+
+    /// LoadInfo de un bloque BLOAD: la ejecución empieza en su dirección `exec`
+    fn create_bload_info(&self, header: &BloadHeader) -> LoadInfo {
+        let mut info = self.create_load_info(header.start as u32, header.length() as u32);
+        info.start_address = header.exec as u32;
+        info
+    }
+
+    /// Puertos numerados como en la carcasa: 1 y 2
+    fn controller_mut(&mut self, port: u8) -> Option<&mut PortDevice> {
+        match port {
+            1 | 2 => self.machine.bus.psg.controller_mut(port as usize - 1),
+            _ => None,
+        }
+    }
+
     fn get_luminance(&self, rgba: &[u8], x: i32, y: i32) -> u8 {
         let x = x.max(0).min((self.width - 1) as i32) as usize;
         let y = y.max(0).min((self.height - 1) as i32) as usize;
@@ -696,6 +2424,119 @@ impl MSX2Processor {
     }
 }
 
+/// Extraer una entrada de un ZIP (firma PK) o, si no, de un LZH
+fn extract_archive_member(archive: &[u8], index: usize) -> Result<(String, Vec<u8>), String> {
+    if archive.starts_with(b"PK") {
+        let zip = ZipArchive::parse(archive)?;
+        let name = zip.entries().get(index).map(|e| e.name.clone()).unwrap_or_default();
+        Ok((name, zip.extract(index)?))
+    } else {
+        let lzh = LzhArchive::parse(archive)?;
+        let name = lzh.entries().get(index).map(|e| e.name.clone()).unwrap_or_default();
+        Ok((name, lzh.extract(index)?))
+    }
+}
+
+/// Marcas de un CDL exportado para `rom`; vacío es "sin pistas"
+fn cdl_marks(rom: &[u8], cdl: &[u8]) -> Result<Vec<u8>, String> {
+    if cdl.is_empty() {
+        return Ok(Vec::new());
+    }
+    let log = CodeDataLog::from_bytes(cdl)?;
+    if log.marks().len() != rom.len() {
+        return Err(format!("El CDL es de otra ROM ({} bytes, la ROM tiene {})", log.marks().len(), rom.len()));
+    }
+    Ok(log.marks().to_vec())
+}
+
+/// Errores del ensamblador o del enlazador con fichero y línea
+fn errors_json(errors: &[AsmError]) -> String {
+    let items: Vec<String> = errors
+        .iter()
+        .map(|e| {
+            format!(
+                r#"{{"file":"{}","line":{},"message":"{}"}}"#,
+                json_escape(&e.file),
+                e.line,
+                json_escape(&e.message)
+            )
+        })
+        .collect();
+    format!(r#"{{"ok":false,"errors":[{}]}}"#, items.join(","))
+}
+
+/// Informe de tiempos en JSON; "min" y "max" a null si ningún camino sale
+fn cycles_json(report: &CycleReport) -> String {
+    let timing = |t: Timing| format!(r#""min":{},"max":{}"#, t.min, t.max);
+    let target = |t: Option<u16>| t.map_or("null".to_string(), |a| a.to_string());
+    let blocks: Vec<String> = report
+        .blocks
+        .iter()
+        .map(|b| {
+            let exits: Vec<String> = b.exits.iter().map(|e| target(e.target)).collect();
+            format!(
+                r#"{{"start":{},"end":{},"instructions":{},{},"repeats":{},"exits":[{}]}}"#,
+                b.start,
+                b.end(),
+                b.instructions.len(),
+                timing(b.timing),
+                b.repeats,
+                exits.join(",")
+            )
+        })
+        .collect();
+    let paths: Vec<String> = report
+        .paths
+        .iter()
+        .map(|p| {
+            let blocks: Vec<String> = p.blocks.iter().map(|b| b.to_string()).collect();
+            format!(r#"{{"blocks":[{}],{}}}"#, blocks.join(","), timing(p.timing))
+        })
+        .collect();
+    let loops: Vec<String> = report
+        .loops
+        .iter()
+        .map(|l| format!(r#"{{"head":{},"tail":{},{}}}"#, l.head, l.tail, timing(l.iteration)))
+        .collect();
+    let external: Vec<String> = report.external.iter().map(|a| a.to_string()).collect();
+    let warnings: Vec<String> = report
+        .warnings
+        .iter()
+        .map(|w| {
+            format!(
+                r#"{{"address":{},"previous":{},"port":{},"tstates":{},"required":{}}}"#,
+                w.address, w.previous, w.port, w.tstates, w.required
+            )
+        })
+        .collect();
+    format!(
+        r#"{{"ok":true,"entry":{},{},"truncated":{},"blocks":[{}],"paths":[{}],"loops":[{}],"external":[{}],"warnings":[{}]}}"#,
+        report.entry,
+        report.total.map_or(r#""min":null,"max":null"#.to_string(), timing),
+        report.truncated,
+        blocks.join(","),
+        paths.join(","),
+        loops.join(","),
+        external.join(","),
+        warnings.join(",")
+    )
+}
+
+/// Escapar una cadena para incrustarla en los JSON generados a mano
+pub(crate) fn json_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
 // ═══════════════════════════════════════════════════════════════
 // ESTRUCTURA RESULTADO FINAL
 // ═══════════════════════════════════════════════════════════════
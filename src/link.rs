@@ -0,0 +1,966 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  ENLAZADOR                                                     ║
+//! ║  - Guion: regiones ROM, bancos del mapper y RAM                ║
+//! ║  - Cada sección se comprueba contra el mapa de memoria         ║
+//! ║  - PUBLIC/EXTERN entre módulos y CALL/JP lejanos entre bancos  ║
+//! ║  - Salida ROM (plana o MegaROM), BIN de BLOAD o .COM           ║
+//! ╚════════════════════════════════════════════════════════════════╝
+//!
+//! El guion tiene una orden por línea y ";" empieza un comentario:
+//!
+//! ```text
+//! OUTPUT rom konami                   ; rom [mapper], bin o com
+//! REGION FIJO  ROM  4010h-5FFFh       ; parte fija del cartucho
+//! REGION BANCO BANK 8000h-9FFFh 2-15  ; ventana del mapper y sus bancos
+//! REGION VARS  RAM  C000h-F37Fh
+//! PLACE CODE  FIJO                    ; la sección de todos los módulos
+//! PLACE NIVEL BANCO 3                 ; en ese banco o en el primero libre
+//! PLACE DATOS VARS
+//! FAR FIJO VARS                       ; rutinas de salto lejano y bancos
+//! ENTRY INICIO
+//! ```
+//!
+//! Un CALL o JP a una sección de otro banco pasa por una rutina que el
+//! enlazador genera en la región ROM de FAR: cambia el banco, llama y al
+//! volver deja el anterior, sin tocar ningún registro. Como los registros
+//! del mapper no se leen, el banco de cada ventana se copia en el byte
+//! `__BANK_<REGIÓN>` de la región RAM de FAR; el programa lo inicializa y
+//! lo mantiene si cambia de banco por su cuenta
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::asm::{parse_number, AsmError, Assembler, Assembly, OutputFormat};
+use crate::disasm::hex16;
+use crate::mapper::{MapperType, PAGE_SIZE};
+use crate::object::{Location, ObjectFile, RelocPart, RelocTarget};
+use crate::rom::{RomHeader, ROM_HEADER_SIZE};
+use crate::symbols::{export_symbols, SymbolFormat, SymbolKind, SymbolTable};
+
+/// Nombre con el que se informa de los errores del guion
+const SCRIPT_NAME: &str = "guion";
+/// Módulo de lo que genera el propio enlazador en el mapa
+const LINKER_MODULE: &str = "(enlazador)";
+
+// ═══════════════════════════════════════════════════════════════
+// MAPA DE MEMORIA
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AreaKind {
+    /// Cartucho: código y datos fijos
+    Rom,
+    /// RAM libre para el programa
+    Ram,
+    /// BIOS, BASIC, MSX-DOS o variables de sistema
+    System,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryArea {
+    pub name: String,
+    pub start: u16,
+    /// Última dirección incluida
+    pub end: u16,
+    pub kind: AreaKind,
+}
+
+impl MemoryArea {
+    pub fn new(name: &str, start: u16, end: u16, kind: AreaKind) -> MemoryArea {
+        MemoryArea { name: name.to_string(), start, end, kind }
+    }
+
+    /// Mapa del MSX con que arranca cada tipo de programa: un cartucho
+    /// tiene la BIOS en la página 0, un BIN de BLOAD además el BASIC en la
+    /// 1 y un .COM la página cero y la BDOS de MSX-DOS alrededor de la TPA
+    pub fn machine_map(format: OutputFormat) -> Vec<MemoryArea> {
+        let area = MemoryArea::new;
+        let mut areas = match format {
+            OutputFormat::Rom => vec![
+                area("BIOS", 0x0000, 0x3FFF, AreaKind::System),
+                area("Cartucho", 0x4000, 0xBFFF, AreaKind::Rom),
+                area("RAM", 0xC000, 0xF37F, AreaKind::Ram),
+            ],
+            OutputFormat::Bin => vec![
+                area("BIOS", 0x0000, 0x3FFF, AreaKind::System),
+                area("BASIC", 0x4000, 0x7FFF, AreaKind::System),
+                area("RAM", 0x8000, 0xF37F, AreaKind::Ram),
+            ],
+            OutputFormat::Com => vec![
+                area("Página cero de MSX-DOS", 0x0000, 0x00FF, AreaKind::System),
+                area("TPA", 0x0100, 0xDBFF, AreaKind::Ram),
+                area("MSX-DOS", 0xDC00, 0xF37F, AreaKind::System),
+            ],
+        };
+        areas.push(area("Variables de sistema", 0xF380, 0xFFFF, AreaKind::System));
+        areas
+    }
+}
+
+/// `length` bytes desde `start` han de caer enteros en zonas de `kind`;
+/// el error dice qué zona invaden
+fn check_area(areas: &[MemoryArea], start: u32, length: u32, kind: AreaKind) -> Result<(), String> {
+    if length == 0 {
+        return Ok(());
+    }
+    let end = start + length - 1;
+    if end > 0xFFFF {
+        return Err("pasa de FFFFh".to_string());
+    }
+    let mut covered = 0;
+    for area in areas.iter().filter(|a| a.start as u32 <= end && a.end as u32 >= start) {
+        if area.kind != kind {
+            return Err(format!("invade {} ({}-{})", area.name, hex16(area.start), hex16(area.end)));
+        }
+        covered += area.end.min(end as u16) as u32 - area.start.max(start as u16) as u32 + 1;
+    }
+    if covered < length {
+        return Err("queda fuera del mapa de memoria".to_string());
+    }
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// GUION
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RegionKind {
+    Rom,
+    /// Ventana del mapper con el primer y el último banco que usa
+    Bank(u16, u16),
+    Ram,
+}
+
+struct Region {
+    name: String,
+    kind: RegionKind,
+    start: u16,
+    end: u16,
+}
+
+impl Region {
+    fn area_kind(&self) -> AreaKind {
+        match self.kind {
+            RegionKind::Ram => AreaKind::Ram,
+            _ => AreaKind::Rom,
+        }
+    }
+
+    fn first_bank(&self) -> u16 {
+        match self.kind {
+            RegionKind::Bank(first, _) => first,
+            _ => 0,
+        }
+    }
+
+    /// Espacios independientes: uno por banco en una ventana
+    fn spaces(&self) -> usize {
+        match self.kind {
+            RegionKind::Bank(first, last) => (last - first) as usize + 1,
+            _ => 1,
+        }
+    }
+}
+
+struct Place {
+    section: String,
+    region: usize,
+    bank: Option<u16>,
+    line: usize,
+}
+
+struct Script {
+    format: OutputFormat,
+    mapper: Option<MapperType>,
+    regions: Vec<Region>,
+    places: Vec<Place>,
+    /// Región ROM de las rutinas de salto lejano y región RAM de la copia
+    /// de los bancos
+    far: Option<(usize, usize)>,
+    entry: Option<(String, usize)>,
+}
+
+impl Script {
+    fn parse(text: &str) -> Result<Script, Vec<AsmError>> {
+        let mut script = Script {
+            format: OutputFormat::Rom,
+            mapper: None,
+            regions: Vec::new(),
+            places: Vec::new(),
+            far: None,
+            entry: None,
+        };
+        let mut errors = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let words: Vec<&str> = line.split(';').next().unwrap_or("").split_whitespace().collect();
+            let Some(command) = words.first() else {
+                continue;
+            };
+            if let Err(message) = script.command(&command.to_ascii_uppercase(), &words[1..], number + 1) {
+                errors.push(AsmError { file: SCRIPT_NAME.to_string(), line: number + 1, message });
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(script),
+            false => Err(errors),
+        }
+    }
+
+    fn region(&self, name: &str) -> Result<usize, String> {
+        self.regions
+            .iter()
+            .position(|r| r.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Región desconocida: {}", name))
+    }
+
+    fn command(&mut self, command: &str, args: &[&str], line: usize) -> Result<(), String> {
+        match (command, args) {
+            ("OUTPUT", [format, mapper @ ..]) if mapper.len() <= 1 => {
+                self.format =
+                    OutputFormat::from_name(format).ok_or_else(|| format!("Formato de salida desconocido: {}", format))?;
+                self.mapper = match mapper.first() {
+                    Some(name) => match MapperType::from_name(name).ok_or_else(|| format!("Mapper desconocido: {}", name))? {
+                        MapperType::Plain => None,
+                        mapper => Some(mapper),
+                    },
+                    None => None,
+                };
+                if self.mapper.is_some() && self.format != OutputFormat::Rom {
+                    return Err("Solo una ROM lleva mapper".to_string());
+                }
+            }
+            ("REGION", [name, kind, range, banks @ ..]) => {
+                if self.region(name).is_ok() {
+                    return Err(format!("Región duplicada: {}", name));
+                }
+                let (start, end) = range_of(range)?;
+                let kind = match (kind.to_ascii_uppercase().as_str(), banks) {
+                    ("ROM", []) => RegionKind::Rom,
+                    ("RAM", []) => RegionKind::Ram,
+                    ("BANK", [banks]) => {
+                        let (first, last) = range_of(banks)?;
+                        // El registro del mapper se escribe con LD (nn),A
+                        if last > 0xFF {
+                            return Err(format!("Banco {} fuera de los 256 del mapper", last));
+                        }
+                        RegionKind::Bank(first, last)
+                    }
+                    _ => return Err(format!("REGION {} no válida: ROM o RAM con un rango, BANK con rango y bancos", name)),
+                };
+                if kind != RegionKind::Ram && self.format != OutputFormat::Rom {
+                    return Err("Las regiones ROM y BANK solo sirven con OUTPUT rom".to_string());
+                }
+                if let RegionKind::Bank(..) = kind {
+                    let mapper = self.mapper.ok_or("Una región BANK necesita el mapper en OUTPUT")?;
+                    let size = mapper.bank_size();
+                    if !(start as usize).is_multiple_of(size) || (end - start) as usize >= size {
+                        return Err(format!("La ventana {} debe empezar en un múltiplo de {} y caber en un banco", name, hex16(size as u16)));
+                    }
+                    if mapper.select_address(start as usize / PAGE_SIZE).is_none() {
+                        return Err(format!("El mapper {} no cambia el banco de {}", mapper.name(), hex16(start)));
+                    }
+                }
+                self.regions.push(Region { name: name.to_string(), kind, start, end });
+            }
+            ("PLACE", [section, region, bank @ ..]) if bank.len() <= 1 => {
+                let region_index = self.region(region)?;
+                let bank = bank.first().map(|text| number(text)).transpose()?;
+                if let Some(bank) = bank {
+                    match self.regions[region_index].kind {
+                        RegionKind::Bank(first, last) if (first..=last).contains(&bank) => {}
+                        RegionKind::Bank(..) => return Err(format!("El banco {} no es de la región {}", bank, region)),
+                        _ => return Err(format!("{} no es una región BANK", region)),
+                    }
+                }
+                if self.places.iter().any(|p| p.section.eq_ignore_ascii_case(section)) {
+                    return Err(format!("Sección colocada dos veces: {}", section));
+                }
+                self.places.push(Place { section: section.to_string(), region: region_index, bank, line });
+            }
+            ("FAR", [code, ram]) => {
+                let (code, ram) = (self.region(code)?, self.region(ram)?);
+                if self.regions[code].kind != RegionKind::Rom {
+                    return Err("FAR necesita una región ROM fija para las rutinas de salto".to_string());
+                }
+                if self.regions[ram].kind != RegionKind::Ram {
+                    return Err("FAR necesita una región RAM para la copia de los bancos".to_string());
+                }
+                self.far = Some((code, ram));
+            }
+            ("ENTRY", [value]) => self.entry = Some((value.to_string(), line)),
+            _ => return Err(format!("Orden no válida: {} {}", command, args.join(" "))),
+        }
+        Ok(())
+    }
+}
+
+/// Número de 16 bits en cualquiera de las notaciones del ensamblador
+pub(crate) fn number(text: &str) -> Result<u16, String> {
+    let value = match text.strip_prefix(['$', '#']) {
+        Some(hex) => i64::from_str_radix(hex, 16).map_err(|_| format!("Número no válido: {}", text))?,
+        None => parse_number(text)?,
+    };
+    u16::try_from(value).map_err(|_| format!("Fuera de 16 bits: {}", text))
+}
+
+/// "4000h-7FFFh"
+pub(crate) fn range_of(text: &str) -> Result<(u16, u16), String> {
+    let (start, end) = text.split_once('-').ok_or_else(|| format!("Se esperaba un rango inicio-fin: {}", text))?;
+    let (start, end) = (number(start)?, number(end)?);
+    if start > end {
+        return Err(format!("Rango vacío: {}", text));
+    }
+    Ok((start, end))
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ENLAZADO
+// ═══════════════════════════════════════════════════════════════
+
+/// Sección de un módulo ya colocada
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub section: String,
+    pub module: String,
+    pub region: String,
+    /// Banco del mapper si la región es una ventana
+    pub bank: Option<u16>,
+    pub address: u16,
+    pub size: u16,
+}
+
+/// Dirección final y, si está en una ventana del mapper, región y banco
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Target {
+    address: u16,
+    window: Option<(usize, u16)>,
+}
+
+impl Target {
+    fn plus(self, addend: u16) -> Target {
+        Target { address: self.address.wrapping_add(addend), ..self }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Linker {
+    objects: Vec<ObjectFile>,
+    memory_map: Option<Vec<MemoryArea>>,
+    /// Símbolos que los módulos pueden usar como EXTERN sin definirlos
+    predefined: Vec<(String, u16)>,
+}
+
+impl Linker {
+    pub fn new() -> Linker {
+        Linker::default()
+    }
+
+    pub fn object(mut self, object: ObjectFile) -> Self {
+        self.objects.push(object);
+        self
+    }
+
+    /// Sustituir el mapa del MSX que corresponde a la salida
+    pub fn memory_map(mut self, areas: Vec<MemoryArea>) -> Self {
+        self.memory_map = Some(areas);
+        self
+    }
+
+    /// Resolver los externos que falten con una tabla (BIOS y variables)
+    pub fn symbols(mut self, table: &SymbolTable) -> Self {
+        self.predefined.extend(table.iter().map(|(address, symbol)| (symbol.name.clone(), address)));
+        self
+    }
+
+    pub fn link(&self, script: &str) -> Result<Linked, Vec<AsmError>> {
+        let script = Script::parse(script)?;
+        let mut link = Link::new(self, script);
+        link.place_sections();
+        link.allocate_banks();
+        link.collect_publics();
+        if !link.errors.is_empty() {
+            return Err(link.errors);
+        }
+        let externs = link.resolve_externs();
+        link.write_sections();
+        let redirects = link.far_calls(&externs);
+        link.relocate(&externs, &redirects);
+        let linked = link.finish();
+        match link.errors.is_empty() {
+            true => Ok(linked),
+            false => Err(link.errors),
+        }
+    }
+}
+
+struct Link<'a> {
+    objects: &'a [ObjectFile],
+    predefined: &'a [(String, u16)],
+    script: Script,
+    areas: Vec<MemoryArea>,
+    /// Dónde quedó cada sección de cada módulo: (módulo, sección)
+    homes: HashMap<(usize, u16), Target>,
+    placements: Vec<Placement>,
+    /// Primera dirección libre de cada región, por banco
+    cursors: Vec<Vec<u32>>,
+    /// Públicos y símbolos del enlazador, por nombre en mayúsculas
+    globals: HashMap<String, (String, Target)>,
+    /// Espacio del Z80 sin mapper o fichero de la MegaROM
+    bytes: Vec<u8>,
+    written: Vec<bool>,
+    errors: Vec<AsmError>,
+}
+
+impl<'a> Link<'a> {
+    fn new(linker: &'a Linker, script: Script) -> Link<'a> {
+        let areas = linker.memory_map.clone().unwrap_or_else(|| MemoryArea::machine_map(script.format));
+        let cursors = script.regions.iter().map(|r| vec![r.start as u32; r.spaces()]).collect();
+        // La MegaROM se rellena con FF; sin mapper los huecos quedan a cero
+        // como en el ensamblador
+        let (size, fill) = match script.mapper {
+            Some(mapper) => {
+                let mut size = mapper.bank_size() * 2;
+                for region in &script.regions {
+                    let end = match region.kind {
+                        RegionKind::Bank(_, last) => (last as usize + 1) * mapper.bank_size(),
+                        _ => fixed_offset(mapper, region.end).map_or(0, |offset| offset + 1),
+                    };
+                    size = size.max(end);
+                }
+                (size.next_power_of_two(), 0xFF)
+            }
+            None => (0x10000, 0x00),
+        };
+        Link {
+            objects: &linker.objects,
+            predefined: &linker.predefined,
+            script,
+            areas,
+            homes: HashMap::new(),
+            placements: Vec::new(),
+            cursors,
+            globals: HashMap::new(),
+            bytes: vec![fill; size],
+            written: vec![false; size],
+            errors: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, file: &str, line: usize, message: String) {
+        self.errors.push(AsmError { file: file.to_string(), line, message });
+    }
+
+    /// Reservar `size` bytes en una región: en el banco pedido o, si no
+    /// se pide, en el primero con sitio. Comprueba el mapa de memoria
+    fn allocate(&mut self, what: &str, region: usize, bank: Option<u16>, size: u32) -> Result<Target, String> {
+        let area = &self.script.regions[region];
+        let limit = area.end as u32 + 1;
+        let first = area.first_bank();
+        let space = match (area.kind, bank) {
+            (RegionKind::Bank(..), Some(bank)) => (bank - first) as usize,
+            _ => self.cursors[region].iter().position(|&cursor| cursor + size <= limit).unwrap_or(0),
+        };
+        let start = self.cursors[region][space];
+        if start + size > limit {
+            return Err(match (area.kind, bank) {
+                (RegionKind::Bank(..), None) => format!("{} ({} bytes) no cabe en ningún banco de {}", what, size, area.name),
+                _ => format!("{} ({} bytes) no cabe en {}: quedan {} bytes", what, size, area.name, limit - start),
+            });
+        }
+        check_area(&self.areas, start, size, area.area_kind()).map_err(|e| {
+            let end = (start + size - 1).min(0xFFFF) as u16;
+            format!("{} ({}-{}) {}", what, hex16(start as u16), hex16(end), e)
+        })?;
+        self.cursors[region][space] += size;
+        let window = match area.kind {
+            RegionKind::Bank(..) => Some((region, first + space as u16)),
+            _ => None,
+        };
+        Ok(Target { address: start as u16, window })
+    }
+
+    fn place_sections(&mut self) {
+        for index in 0..self.script.places.len() {
+            let place = &self.script.places[index];
+            let (name, region, bank, line) = (place.section.clone(), place.region, place.bank, place.line);
+            let chunks: Vec<(usize, u16, u16)> = self
+                .objects
+                .iter()
+                .enumerate()
+                .filter_map(|(o, object)| {
+                    let s = object.sections.iter().position(|s| s.name.eq_ignore_ascii_case(&name))?;
+                    Some((o, s as u16, object.sections[s].size))
+                })
+                .collect();
+            if chunks.is_empty() {
+                self.error(SCRIPT_NAME, line, format!("Ningún módulo tiene la sección {}", name));
+                continue;
+            }
+            let ram = self.script.regions[region].kind == RegionKind::Ram;
+            if ram && self.script.format == OutputFormat::Rom {
+                if let Some(&(o, _, _)) = chunks.iter().find(|&&(o, s, _)| !self.objects[o].sections[s as usize].data.is_empty()) {
+                    let message = format!("La sección {} tiene datos y va a RAM: en una ROM solo se reserva espacio con DS", name);
+                    self.error(&self.objects[o].module, 0, message);
+                    continue;
+                }
+            }
+            let total: u32 = chunks.iter().map(|&(_, _, size)| size as u32).sum();
+            let start = match self.allocate(&format!("La sección {}", name), region, bank, total) {
+                Ok(start) => start,
+                Err(e) => {
+                    self.error(SCRIPT_NAME, line, e);
+                    continue;
+                }
+            };
+            let mut offset = 0u16;
+            for (o, s, size) in chunks {
+                let home = start.plus(offset);
+                self.homes.insert((o, s), home);
+                self.placements.push(Placement {
+                    section: self.objects[o].sections[s as usize].name.clone(),
+                    module: self.objects[o].module.clone(),
+                    region: self.script.regions[region].name.clone(),
+                    bank: home.window.map(|(_, bank)| bank),
+                    address: home.address,
+                    size,
+                });
+                offset = offset.wrapping_add(size);
+            }
+        }
+        for object in self.objects {
+            for section in &object.sections {
+                let placed = self.script.places.iter().any(|p| p.section.eq_ignore_ascii_case(&section.name));
+                if !placed && section.size > 0 {
+                    self.error(&object.module, 0, format!("La sección {} no tiene PLACE en el guion", section.name));
+                }
+            }
+        }
+    }
+
+    /// Un byte en la RAM de FAR por cada ventana del mapper
+    fn allocate_banks(&mut self) {
+        let Some((_, ram)) = self.script.far else {
+            return;
+        };
+        for region in 0..self.script.regions.len() {
+            if !matches!(self.script.regions[region].kind, RegionKind::Bank(..)) {
+                continue;
+            }
+            let name = format!("__BANK_{}", self.script.regions[region].name.to_ascii_uppercase());
+            match self.allocate(&name, ram, None, 1) {
+                Ok(target) => self.generated(&name, target, ram, 1),
+                Err(e) => self.error(SCRIPT_NAME, 0, e),
+            }
+        }
+    }
+
+    /// Símbolo y entrada del mapa de algo que crea el enlazador
+    fn generated(&mut self, name: &str, target: Target, region: usize, size: u16) {
+        self.globals.insert(name.to_ascii_uppercase(), (name.to_string(), target));
+        self.placements.push(Placement {
+            section: name.to_string(),
+            module: LINKER_MODULE.to_string(),
+            region: self.script.regions[region].name.clone(),
+            bank: None,
+            address: target.address,
+            size,
+        });
+    }
+
+    fn locate(&self, module: usize, location: Location) -> Option<Target> {
+        match location {
+            Location::Absolute(value) => Some(Target { address: value, window: None }),
+            Location::Section(section, offset) => Some(self.homes.get(&(module, section))?.plus(offset)),
+        }
+    }
+
+    fn collect_publics(&mut self) {
+        for (o, object) in self.objects.iter().enumerate() {
+            for symbol in object.public_symbols() {
+                let Some(target) = self.locate(o, symbol.location) else {
+                    let message = format!("{} está en una sección que no se ha colocado", symbol.name);
+                    self.error(&object.module, 0, message);
+                    continue;
+                };
+                let key = symbol.name.to_ascii_uppercase();
+                if let Some((_, other)) = self.globals.get(&key) {
+                    let message = format!("Símbolo público duplicado: {} (ya en {})", symbol.name, hex16(other.address));
+                    self.error(&object.module, 0, message);
+                    continue;
+                }
+                self.globals.insert(key, (symbol.name.clone(), target));
+            }
+        }
+    }
+
+    /// Dirección de cada externo de cada módulo: públicos de los otros
+    /// módulos, símbolos del enlazador o la tabla predefinida
+    fn resolve_externs(&mut self) -> Vec<Vec<Option<Target>>> {
+        let mut resolved = Vec::new();
+        for object in self.objects {
+            let mut targets = Vec::new();
+            for name in &object.externs {
+                let target = self.globals.get(&name.to_ascii_uppercase()).map(|(_, target)| *target).or_else(|| {
+                    let (_, address) = self.predefined.iter().find(|(n, _)| n.eq_ignore_ascii_case(name))?;
+                    Some(Target { address: *address, window: None })
+                });
+                if target.is_none() {
+                    self.error(&object.module, 0, format!("Símbolo externo sin definir: {}", name));
+                }
+                targets.push(target);
+            }
+            resolved.push(targets);
+        }
+        resolved
+    }
+
+    fn target(&self, module: usize, target: RelocTarget, externs: &[Vec<Option<Target>>]) -> Option<Target> {
+        match target {
+            RelocTarget::Section(section) => self.homes.get(&(module, section)).copied(),
+            RelocTarget::Extern(index) => externs[module][index as usize],
+        }
+    }
+
+    /// Posición en la salida: la dirección sin mapper; con mapper, el
+    /// banco de la ventana o el que el cartucho tiene fijo tras el reset
+    fn offset(&self, address: u16, window: Option<(usize, u16)>) -> Option<usize> {
+        match (self.script.mapper, window) {
+            (None, _) => Some(address as usize),
+            (Some(mapper), Some((region, bank))) => {
+                Some(bank as usize * mapper.bank_size() + (address - self.script.regions[region].start) as usize)
+            }
+            (Some(mapper), None) => fixed_offset(mapper, address),
+        }
+    }
+
+    fn write(&mut self, target: Target, data: &[u8], overwrite: bool) -> Result<(), String> {
+        for (i, &byte) in data.iter().enumerate() {
+            let address = target.address.wrapping_add(i as u16);
+            let offset = self
+                .offset(address, target.window)
+                .filter(|&offset| offset < self.bytes.len())
+                .ok_or_else(|| format!("{} no está en el cartucho", hex16(address)))?;
+            if self.written[offset] && !overwrite {
+                return Err(format!("se solapa con otra sección en {}", hex16(address)));
+            }
+            self.bytes[offset] = byte;
+            self.written[offset] = true;
+        }
+        Ok(())
+    }
+
+    fn write_sections(&mut self) {
+        let mut homes: Vec<((usize, u16), Target)> = self.homes.iter().map(|(&key, &target)| (key, target)).collect();
+        homes.sort_by_key(|&(key, _)| key);
+        for ((o, s), home) in homes {
+            let object = &self.objects[o];
+            let section = &object.sections[s as usize];
+            if let Err(e) = self.write(home, &section.data, false) {
+                self.error(&object.module, 0, format!("La sección {} {}", section.name, e));
+            }
+        }
+    }
+
+    /// CALL y JP hacia otro banco: una rutina por destino en la región ROM
+    /// de FAR. Devuelve la dirección que sustituye a cada reubicación
+    /// desviada, por (módulo, reubicación)
+    fn far_calls(&mut self, externs: &[Vec<Option<Target>>]) -> HashMap<(usize, usize), u16> {
+        let mut stubs: Vec<(Target, bool, String)> = Vec::new();
+        let mut uses: Vec<((usize, usize), usize)> = Vec::new();
+        for (o, object) in self.objects.iter().enumerate() {
+            for (r, reloc) in object.relocations.iter().enumerate() {
+                if reloc.part != RelocPart::Call {
+                    continue;
+                }
+                let site = self.homes.get(&(o, reloc.section));
+                let Some(target) = self.target(o, reloc.target, externs).map(|t| t.plus(reloc.addend)) else {
+                    continue;
+                };
+                if target.window.is_none() || site.is_none_or(|site| site.window == target.window) {
+                    continue;
+                }
+                let data = &object.sections[reloc.section as usize].data;
+                let opcode = reloc.offset.checked_sub(1).and_then(|i| data.get(i as usize)).copied().unwrap_or(0xCD);
+                let call = opcode == 0xCD || opcode & 0xC7 == 0xC4;
+                let index = stubs.iter().position(|(t, c, _)| *t == target && *c == call).unwrap_or_else(|| {
+                    stubs.push((target, call, self.target_name(o, reloc.target, reloc.addend, target)));
+                    stubs.len() - 1
+                });
+                uses.push(((o, r), index));
+            }
+        }
+        if stubs.is_empty() {
+            return HashMap::new();
+        }
+        let Some((code, _)) = self.script.far else {
+            let (target, _, name) = &stubs[0];
+            let (region, bank) = target.window.expect("destino en un banco");
+            let region = self.script.regions[region].name.clone();
+            let message = format!("CALL o JP a {} (banco {} de {}) desde otro banco: falta FAR en el guion", name, bank, region);
+            self.error(SCRIPT_NAME, 0, message);
+            return HashMap::new();
+        };
+
+        let start = self.cursors[code][0] as u16;
+        let source = self.stub_source(start, &stubs);
+        let assembly = match Assembler::new(OutputFormat::Bin).assemble(&source) {
+            Ok(assembly) => assembly,
+            Err(errors) => {
+                self.errors.extend(errors);
+                return HashMap::new();
+            }
+        };
+        match self.allocate("Las rutinas de salto lejano", code, None, assembly.code.len() as u32) {
+            Ok(target) => {
+                if let Err(e) = self.write(target, &assembly.code, false) {
+                    self.error(SCRIPT_NAME, 0, format!("Las rutinas de salto lejano {}", e));
+                }
+            }
+            Err(e) => {
+                self.error(SCRIPT_NAME, 0, e);
+                return HashMap::new();
+            }
+        }
+        let mut addresses = Vec::new();
+        for (i, (_, call, name)) in stubs.iter().enumerate() {
+            let address = assembly.symbols[&format!("STUB{}", i)];
+            let symbol = format!("{}_{}", if *call { "__FAR" } else { "__FARJP" }, name);
+            let size = assembly.symbols.get(&format!("STUB{}", i + 1)).map_or(assembly.end() + 1, |&next| next) - address;
+            self.generated(&symbol, Target { address, window: None }, code, size);
+            addresses.push(address);
+        }
+        uses.into_iter().map(|(key, index)| (key, addresses[index])).collect()
+    }
+
+    /// Nombre legible de un destino: el externo, la etiqueta del módulo o
+    /// la dirección y el banco
+    fn target_name(&self, module: usize, target: RelocTarget, addend: u16, resolved: Target) -> String {
+        let object = &self.objects[module];
+        let name = match target {
+            RelocTarget::Extern(index) if addend == 0 => Some(object.externs[index as usize].clone()),
+            RelocTarget::Section(section) => object
+                .symbols
+                .iter()
+                .find(|s| s.location == Location::Section(section, addend))
+                .map(|s| s.name.clone()),
+            _ => None,
+        };
+        let bank = resolved.window.map_or(0, |(_, bank)| bank);
+        name.filter(|n| n.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| format!("{:04X}_{}", resolved.address, bank))
+    }
+
+    /// Cada rutina guarda el banco de la ventana en la pila, pone el del
+    /// destino y llama; al volver repone el anterior. Los JP no vuelven:
+    /// solo cambian de banco
+    fn stub_source(&self, start: u16, stubs: &[(Target, bool, String)]) -> String {
+        let mapper = self.script.mapper.expect("bancos con mapper");
+        let mut source = format!("        ORG {}\n", hex16(start));
+        for (i, (target, call, _)) in stubs.iter().enumerate() {
+            let (region, bank) = target.window.expect("destino en un banco");
+            let window = &self.script.regions[region];
+            let copy = hex16(self.globals[&format!("__BANK_{}", window.name.to_ascii_uppercase())].1.address);
+            let select = hex16(mapper.select_address(window.start as usize / PAGE_SIZE).expect("ventana validada"));
+            let address = hex16(target.address);
+            let lines: Vec<String> = match call {
+                true => vec![
+                    "PUSH HL".to_string(),
+                    format!("LD HL,({})", copy),
+                    "EX (SP),HL".to_string(),
+                    "PUSH AF".to_string(),
+                    format!("LD A,{}", bank),
+                    format!("LD ({}),A", copy),
+                    format!("LD ({}),A", select),
+                    "POP AF".to_string(),
+                    format!("CALL {}", address),
+                    "EX (SP),HL".to_string(),
+                    "PUSH AF".to_string(),
+                    "LD A,L".to_string(),
+                    format!("LD ({}),A", copy),
+                    format!("LD ({}),A", select),
+                    "POP AF".to_string(),
+                    "POP HL".to_string(),
+                    "RET".to_string(),
+                ],
+                false => vec![
+                    "PUSH AF".to_string(),
+                    format!("LD A,{}", bank),
+                    format!("LD ({}),A", copy),
+                    format!("LD ({}),A", select),
+                    "POP AF".to_string(),
+                    format!("JP {}", address),
+                ],
+            };
+            source.push_str(&format!("STUB{}:\n", i));
+            for line in lines {
+                source.push_str(&format!("        {}\n", line));
+            }
+        }
+        source
+    }
+
+    fn relocate(&mut self, externs: &[Vec<Option<Target>>], redirects: &HashMap<(usize, usize), u16>) {
+        for (o, object) in self.objects.iter().enumerate() {
+            for (r, reloc) in object.relocations.iter().enumerate() {
+                let (Some(site), Some(target)) = (self.homes.get(&(o, reloc.section)), self.target(o, reloc.target, externs))
+                else {
+                    continue;
+                };
+                let target = target.plus(reloc.addend);
+                let bytes = match reloc.part {
+                    RelocPart::Word => target.address.to_le_bytes().to_vec(),
+                    RelocPart::Call => redirects.get(&(o, r)).copied().unwrap_or(target.address).to_le_bytes().to_vec(),
+                    RelocPart::Low => vec![target.address as u8],
+                    RelocPart::High => vec![(target.address >> 8) as u8],
+                    RelocPart::Bank => match target.window {
+                        Some((_, bank)) => vec![bank as u8],
+                        None => {
+                            let message = format!("BANK() en {}: el destino no está en un banco del mapper", hex16(reloc.offset));
+                            self.error(&object.module, 0, message);
+                            continue;
+                        }
+                    },
+                };
+                if let Err(e) = self.write(site.plus(reloc.offset), &bytes, true) {
+                    self.error(&object.module, 0, format!("Reubicación: {}", e));
+                }
+            }
+        }
+    }
+
+    fn entry(&mut self) -> Option<u16> {
+        if let Some((text, line)) = self.script.entry.clone() {
+            let found = self.globals.get(&text.to_ascii_uppercase()).map(|(_, target)| target.address);
+            return match found.map(Ok).unwrap_or_else(|| number(&text)) {
+                Ok(address) => Some(address),
+                Err(_) => {
+                    self.error(SCRIPT_NAME, line, format!("ENTRY desconocido: {}", text));
+                    None
+                }
+            };
+        }
+        self.objects.iter().enumerate().find_map(|(o, object)| Some(self.locate(o, object.entry?)?.address))
+    }
+
+    fn finish(&mut self) -> Linked {
+        let entry = self.entry();
+        let symbols: BTreeMap<String, u16> =
+            self.globals.values().map(|(name, target)| (name.clone(), target.address)).collect();
+        let (origin, image, entry) = match self.script.mapper {
+            Some(_) => {
+                if !self.bytes.starts_with(b"AB") {
+                    match entry {
+                        _ if self.written[..ROM_HEADER_SIZE].iter().any(|&w| w) => self.error(
+                            SCRIPT_NAME,
+                            0,
+                            "Falta la cabecera AB: deja libres los 16 primeros bytes del banco 0 o ponla en una sección".to_string(),
+                        ),
+                        Some(init) => {
+                            let header = RomHeader { init, statement: 0, device: 0, text: 0 }.to_bytes();
+                            self.bytes[..ROM_HEADER_SIZE].copy_from_slice(&header);
+                        }
+                        None => self.error(SCRIPT_NAME, 0, "Falta ENTRY para la cabecera del cartucho".to_string()),
+                    }
+                }
+                (0x4000, self.bytes.clone(), entry.unwrap_or(0))
+            }
+            None => match (self.written.iter().position(|&w| w), self.written.iter().rposition(|&w| w)) {
+                (Some(first), Some(last)) => {
+                    (first as u16, self.bytes[first..=last].to_vec(), entry.unwrap_or(first as u16))
+                }
+                _ => {
+                    self.error(SCRIPT_NAME, 0, "El enlazado no genera ningún byte".to_string());
+                    (0, Vec::new(), 0)
+                }
+            },
+        };
+        Linked {
+            format: self.script.format,
+            mapper: self.script.mapper,
+            image,
+            origin,
+            entry,
+            symbols,
+            placements: std::mem::take(&mut self.placements),
+        }
+    }
+}
+
+/// Posición en la MegaROM de una dirección de la parte fija del cartucho:
+/// el banco que tiene esa página tras el reset
+fn fixed_offset(mapper: MapperType, address: u16) -> Option<usize> {
+    let page = address as usize / PAGE_SIZE;
+    let bank = mapper.initial_pages(0, 0x4000)[page]?;
+    Some(bank as usize * PAGE_SIZE + address as usize % PAGE_SIZE)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// RESULTADO
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Debug)]
+pub struct Linked {
+    pub format: OutputFormat,
+    pub mapper: Option<MapperType>,
+    /// Sin mapper, los bytes desde `origin` como en `Assembly::code`; con
+    /// mapper, la MegaROM completa
+    pub image: Vec<u8>,
+    pub origin: u16,
+    pub entry: u16,
+    /// Públicos de todos los módulos y símbolos del enlazador
+    pub symbols: BTreeMap<String, u16>,
+    pub placements: Vec<Placement>,
+}
+
+impl Linked {
+    /// Fichero final: la MegaROM tal cual o, sin mapper, la salida del
+    /// ensamblador (cabecera AB, BLOAD o .COM)
+    pub fn output(&self) -> Result<Vec<u8>, String> {
+        match self.mapper {
+            Some(_) => Ok(self.image.clone()),
+            None => {
+                let assembly = Assembly {
+                    origin: self.origin,
+                    code: self.image.clone(),
+                    entry: self.entry,
+                    symbols: self.symbols.clone(),
+                    lines: Vec::new(),
+                };
+                assembly.output(self.format)
+            }
+        }
+    }
+
+    pub fn symbol_table(&self) -> SymbolTable {
+        let mut table = SymbolTable::new();
+        for (name, &address) in &self.symbols {
+            table.insert(address, name, SymbolKind::Label);
+        }
+        table
+    }
+
+    pub fn symbol_file(&self, format: SymbolFormat) -> String {
+        export_symbols(self.symbols.iter().map(|(name, &address)| (name.as_str(), address)), format)
+    }
+
+    /// Mapa de enlazado: dónde quedó cada sección y cada símbolo
+    pub fn map(&self) -> String {
+        let mapper = self.mapper.map_or(String::new(), |m| format!(" {}", m.name()));
+        let mut out = format!("; Enlazado {}{}, entrada en {}\n", self.format.name(), mapper, hex16(self.entry));
+        out.push_str("; Sección          Módulo           Región       Banco  Dirección  Tamaño\n");
+        let mut placements: Vec<&Placement> = self.placements.iter().collect();
+        placements.sort_by_key(|p| (p.bank, p.address));
+        for p in placements {
+            let bank = p.bank.map_or("-".to_string(), |b| b.to_string());
+            out.push_str(&format!(
+                "{:<18} {:<16} {:<12} {:>5}  {:<9} {:>6}\n",
+                p.section, p.module, p.region, bank, hex16(p.address), p.size
+            ));
+        }
+        out.push_str("\n; Símbolos\n");
+        for (name, &address) in &self.symbols {
+            out.push_str(&format!("{:<24} {}\n", name, hex16(address)));
+        }
+        out
+    }
+}
@@ -0,0 +1,433 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  LECTOR DE ARCHIVOS LZH / LHA                                  ║
+//! ║  - Cabeceras de nivel 0, 1 y 2 con cabeceras extendidas        ║
+//! ║  - Métodos -lh0- (almacenado) y -lh4- a -lh7- (LZSS+Huffman)   ║
+//! ║  - CRC-16 de cada fichero                                      ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::filetype::FileKind;
+use crate::inflate::Huffman;
+use crate::rtc::RtcDateTime;
+
+/// Símbolos de literal/longitud: 256 bytes + longitudes de 3 a 256
+const NC: usize = 510;
+const CBIT: u32 = 9;
+/// Símbolos del código que transmite las longitudes de C
+const NT: usize = 19;
+const TBIT: u32 = 5;
+const THRESHOLD: usize = 3;
+
+/// CRC-16 de LHA (polinomio A001h reflejado, valor inicial 0)
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xA001 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([*data.get(pos)?, *data.get(pos + 1)?]))
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+#[derive(Clone, Debug)]
+pub struct LzhEntry {
+    /// Ruta dentro del archivo, con '/' como separador
+    pub name: String,
+    /// Identificador de método tal cual, p. ej. "-lh5-"
+    pub method: String,
+    pub compressed_size: u32,
+    pub size: u32,
+    pub crc16: u16,
+    pub modified: RtcDateTime,
+    pub level: u8,
+    data_offset: usize,
+}
+
+impl LzhEntry {
+    pub fn is_directory(&self) -> bool {
+        self.method == "-lhd-"
+    }
+
+    /// Bits de la ventana del diccionario (None si no es un método LZSS soportado)
+    fn dictionary_bits(&self) -> Option<u32> {
+        match self.method.as_str() {
+            "-lh4-" => Some(12),
+            "-lh5-" => Some(13),
+            "-lh6-" => Some(15),
+            "-lh7-" => Some(16),
+            _ => None,
+        }
+    }
+}
+
+pub struct LzhArchive<'a> {
+    data: &'a [u8],
+    entries: Vec<LzhEntry>,
+}
+
+impl<'a> LzhArchive<'a> {
+    /// Recorrer las cabeceras hasta el byte 0 final
+    pub fn parse(data: &'a [u8]) -> Result<LzhArchive<'a>, String> {
+        let mut entries = Vec::new();
+        let mut pos = 0;
+
+        while pos < data.len() && data[pos] != 0 {
+            let (entry, next) = parse_header(data, pos)?;
+            entries.push(entry);
+            pos = next;
+        }
+        if entries.is_empty() {
+            return Err("No es un archivo LZH: no hay ninguna cabecera".to_string());
+        }
+        Ok(LzhArchive { data, entries })
+    }
+
+    pub fn entries(&self) -> &[LzhEntry] {
+        &self.entries
+    }
+
+    /// Índice de una entrada por nombre, sin distinguir mayúsculas
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Descomprimir una entrada y comprobar su CRC
+    pub fn extract(&self, index: usize) -> Result<Vec<u8>, String> {
+        let entry = self
+            .entries
+            .get(index)
+            .ok_or_else(|| format!("Entrada {} inexistente", index))?;
+        let packed = self
+            .data
+            .get(entry.data_offset..entry.data_offset + entry.compressed_size as usize)
+            .ok_or_else(|| format!("Datos de '{}' truncados", entry.name))?;
+
+        let contents = match (entry.method.as_str(), entry.dictionary_bits()) {
+            ("-lh0-" | "-lz4-", _) => packed.to_vec(),
+            ("-lhd-", _) => Vec::new(),
+            (_, Some(bits)) => decode_lh(packed, entry.size as usize, bits)
+                .map_err(|e| format!("{}: {}", entry.name, e))?,
+            (method, None) => {
+                return Err(format!("Método {} no soportado en '{}'", method, entry.name))
+            }
+        };
+
+        if contents.len() != entry.size as usize || crc16(&contents) != entry.crc16 {
+            return Err(format!("CRC incorrecto en '{}'", entry.name));
+        }
+        Ok(contents)
+    }
+
+    /// Tipo de cada entrada: por contenido y, si no basta, por extensión
+    pub fn classify(&self, index: usize) -> FileKind {
+        match self.extract(index) {
+            Ok(data) => FileKind::classify(&self.entries[index].name, &data),
+            Err(_) => FileKind::Unknown,
+        }
+    }
+
+    /// Primera entrada del tipo pedido; la extensión se prueba antes que el contenido
+    pub fn pick(&self, kind: FileKind) -> Option<usize> {
+        let files = || (0..self.entries.len()).filter(|&i| !self.entries[i].is_directory());
+        files()
+            .find(|&i| FileKind::from_extension(&self.entries[i].name) == kind && self.classify(i) == kind)
+            .or_else(|| files().find(|&i| self.classify(i) == kind))
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// CABECERAS
+// ═══════════════════════════════════════════════════════════════
+
+/// Fecha y hora MS-DOS de las cabeceras de nivel 0 y 1 (hora en la palabra baja)
+fn dos_datetime(stamp: u32) -> RtcDateTime {
+    let time = stamp as u16;
+    let date = (stamp >> 16) as u16;
+    RtcDateTime {
+        year: 1980 + (date >> 9),
+        month: ((date >> 5) & 0x0F) as u8,
+        day: (date & 0x1F) as u8,
+        hour: (time >> 11) as u8,
+        minute: ((time >> 5) & 0x3F) as u8,
+        second: ((time & 0x1F) * 2) as u8,
+    }
+}
+
+/// Leer una cabecera; devuelve la entrada y la posición de la siguiente
+fn parse_header(data: &[u8], pos: usize) -> Result<(LzhEntry, usize), String> {
+    let truncated = || format!("Cabecera LZH truncada en {}", pos);
+    let level = *data.get(pos + 20).ok_or_else(truncated)?;
+    let method = data.get(pos + 2..pos + 7).ok_or_else(truncated)?;
+    if method[0] != b'-' || method[4] != b'-' {
+        return Err(format!("Cabecera LZH inválida en {}", pos));
+    }
+    let method = String::from_utf8_lossy(method).to_string();
+    let mut packed = u32_at(data, pos + 7).ok_or_else(truncated)?;
+    let size = u32_at(data, pos + 11).ok_or_else(truncated)?;
+    let stamp = u32_at(data, pos + 15).ok_or_else(truncated)?;
+
+    let mut name = Vec::new();
+    let mut directory = Vec::new();
+    let crc;
+    let modified;
+    let data_offset;
+
+    match level {
+        0 | 1 => {
+            let header_size = data[pos] as usize + 2;
+            let name_len = *data.get(pos + 21).ok_or_else(truncated)? as usize;
+            name = data.get(pos + 22..pos + 22 + name_len).ok_or_else(truncated)?.to_vec();
+            crc = u16_at(data, pos + 22 + name_len).ok_or_else(truncated)?;
+            modified = dos_datetime(stamp);
+
+            let mut next = pos + header_size;
+            if level == 1 {
+                // El tamaño de la siguiente cabecera extendida cierra la base
+                let mut ext_size = u16_at(data, next - 2).ok_or_else(truncated)? as usize;
+                while ext_size != 0 {
+                    // Tipo (1 byte) y tamaño de la siguiente (2) como mínimo
+                    if ext_size < 3 {
+                        return Err(format!("Cabecera extendida LZH inválida en {}", next));
+                    }
+                    let ext = data.get(next..next + ext_size).ok_or_else(truncated)?;
+                    read_extension(ext, &mut name, &mut directory);
+                    packed = packed.saturating_sub(ext_size as u32);
+                    next += ext_size;
+                    ext_size = u16_at(ext, ext_size - 2).unwrap_or(0) as usize;
+                }
+            }
+            data_offset = next;
+        }
+        2 => {
+            let header_size = u16_at(data, pos).ok_or_else(truncated)? as usize;
+            crc = u16_at(data, pos + 21).ok_or_else(truncated)?;
+            modified = RtcDateTime::from_unix(stamp as i64);
+
+            let mut next = pos + 26;
+            let mut ext_size = u16_at(data, pos + 24).ok_or_else(truncated)? as usize;
+            while ext_size != 0 && next < pos + header_size {
+                if ext_size < 3 {
+                    return Err(format!("Cabecera extendida LZH inválida en {}", next));
+                }
+                let ext = data.get(next..next + ext_size).ok_or_else(truncated)?;
+                read_extension(ext, &mut name, &mut directory);
+                next += ext_size;
+                ext_size = u16_at(ext, ext_size - 2).unwrap_or(0) as usize;
+            }
+            data_offset = pos + header_size;
+        }
+        _ => return Err(format!("Nivel de cabecera LZH {} no soportado", level)),
+    }
+
+    // Rutas con '\' o el separador 0xFF de LHA pasan a '/'
+    let mut path: Vec<u8> = directory;
+    path.extend_from_slice(&name);
+    let path: Vec<u8> = path
+        .iter()
+        .map(|&b| if b == b'\\' || b == 0xFF { b'/' } else { b })
+        .collect();
+    let name = String::from_utf8_lossy(&path).trim_start_matches('/').to_string();
+
+    let entry = LzhEntry {
+        name,
+        method,
+        compressed_size: packed,
+        size,
+        crc16: crc,
+        modified,
+        level,
+        data_offset,
+    };
+    let next = data_offset + packed as usize;
+    if next > data.len() {
+        return Err(format!("Datos de '{}' truncados", entry.name));
+    }
+    Ok((entry, next))
+}
+
+/// Cabecera extendida: tipo (1 byte), datos y tamaño de la siguiente (2 bytes)
+fn read_extension(ext: &[u8], name: &mut Vec<u8>, directory: &mut Vec<u8>) {
+    if ext.len() < 3 {
+        return;
+    }
+    let body = &ext[1..ext.len() - 2];
+    match ext[0] {
+        0x01 => *name = body.to_vec(),
+        0x02 => {
+            *directory = body.to_vec();
+            if !directory.ends_with(&[0xFF]) {
+                directory.push(0xFF);
+            }
+        }
+        _ => {}
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// DESCOMPRESOR -lh4- A -lh7-
+// ═══════════════════════════════════════════════════════════════
+
+/// Lector de bits MSB primero; más allá del final devuelve ceros, como LHA
+struct MsbReader<'a> {
+    data: &'a [u8],
+    bit_pos: usize,
+}
+
+impl MsbReader<'_> {
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        if self.bit_pos > (self.data.len() + 8) * 8 {
+            return Err("Datos comprimidos truncados".to_string());
+        }
+        let mut value = 0;
+        for _ in 0..count {
+            let byte = self.data.get(self.bit_pos / 8).copied().unwrap_or(0);
+            value = (value << 1) | ((byte >> (7 - self.bit_pos % 8)) & 1) as u32;
+            self.bit_pos += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// Tabla de un bloque: un código Huffman o un símbolo único sin bits
+enum Table {
+    Huffman(Huffman),
+    Single(u16),
+}
+
+impl Table {
+    fn decode(&self, reader: &mut MsbReader) -> Result<u16, String> {
+        match self {
+            Table::Huffman(huffman) => huffman.decode_with(|| reader.bits(1)),
+            Table::Single(symbol) => Ok(*symbol),
+        }
+    }
+}
+
+/// Tabla de un solo símbolo, que debe existir en un alfabeto de `count`
+fn single(symbol: u32, count: usize) -> Result<Table, String> {
+    if symbol as usize >= count {
+        return Err("Símbolo único fuera de la tabla".to_string());
+    }
+    Ok(Table::Single(symbol as u16))
+}
+
+/// Longitudes del código T o P: 3 bits, o 7 + unos en unario
+fn read_pt_len(reader: &mut MsbReader, count: usize, nbit: u32, special: Option<usize>) -> Result<Table, String> {
+    let n = reader.bits(nbit)? as usize;
+    if n == 0 {
+        return single(reader.bits(nbit)?, count);
+    }
+    if n > count {
+        return Err("Tabla de longitudes inválida".to_string());
+    }
+
+    let mut lengths = vec![0u8; count];
+    let mut i = 0;
+    while i < n {
+        let mut len = reader.bits(3)?;
+        if len == 7 {
+            while reader.bits(1)? == 1 {
+                len += 1;
+                if len > 16 {
+                    return Err("Longitud de código excesiva".to_string());
+                }
+            }
+        }
+        lengths[i] = len as u8;
+        i += 1;
+        if Some(i) == special {
+            // Tras las tres primeras, 2 bits dicen cuántas longitudes nulas siguen
+            i += reader.bits(2)? as usize;
+        }
+    }
+    Ok(Table::Huffman(Huffman::new(&lengths)?))
+}
+
+/// Longitudes del código C, transmitidas con el código T
+fn read_c_len(reader: &mut MsbReader, t_table: &Table) -> Result<Table, String> {
+    let n = reader.bits(CBIT)? as usize;
+    if n == 0 {
+        return single(reader.bits(CBIT)?, NC);
+    }
+    if n > NC {
+        return Err("Tabla de literales inválida".to_string());
+    }
+
+    let mut lengths = vec![0u8; NC];
+    let mut i = 0;
+    while i < n {
+        let symbol = t_table.decode(reader)?;
+        match symbol {
+            0 => i += 1,
+            1 => i += reader.bits(4)? as usize + 3,
+            2 => i += reader.bits(CBIT)? as usize + 20,
+            _ if symbol - 2 > 16 => return Err("Longitud de código excesiva".to_string()),
+            _ => {
+                lengths[i] = (symbol - 2) as u8;
+                i += 1;
+            }
+        }
+    }
+    if i > n {
+        return Err("Tabla de literales desbordada".to_string());
+    }
+    Ok(Table::Huffman(Huffman::new(&lengths)?))
+}
+
+/// Descomprimir un flujo -lh4-/-lh5-/-lh6-/-lh7- hasta `size` bytes
+fn decode_lh(data: &[u8], size: usize, dictionary_bits: u32) -> Result<Vec<u8>, String> {
+    let np = dictionary_bits as usize + 1;
+    let pbit = if dictionary_bits <= 13 { 4 } else { 5 };
+    let mut reader = MsbReader { data, bit_pos: 0 };
+    let mut out = Vec::with_capacity(size);
+    let mut remaining_in_block = 0u32;
+    let mut tables: Option<(Table, Table)> = None;
+
+    while out.len() < size {
+        if remaining_in_block == 0 {
+            remaining_in_block = reader.bits(16)?;
+            let t_table = read_pt_len(&mut reader, NT, TBIT, Some(3))?;
+            let c_table = read_c_len(&mut reader, &t_table)?;
+            let p_table = read_pt_len(&mut reader, np, pbit, None)?;
+            tables = Some((c_table, p_table));
+            if remaining_in_block == 0 {
+                return Err("Bloque LZH vacío".to_string());
+            }
+        }
+        remaining_in_block -= 1;
+        let (c_table, p_table) = tables.as_ref().ok_or("Bloque LZH sin tablas")?;
+
+        let symbol = c_table.decode(&mut reader)? as usize;
+        if symbol < 256 {
+            out.push(symbol as u8);
+            continue;
+        }
+
+        let length = symbol - 256 + THRESHOLD;
+        let p = p_table.decode(&mut reader)? as u32;
+        let offset = match p {
+            0 => 0,
+            _ => (1 << (p - 1)) + reader.bits(p - 1)? as usize,
+        };
+        let distance = offset + 1;
+        if distance > out.len() {
+            return Err("Distancia fuera de la ventana".to_string());
+        }
+        let start = out.len() - distance;
+        for i in 0..length {
+            if out.len() == size {
+                break;
+            }
+            out.push(out[start + i]);
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,206 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  MÁQUINA MSX2                                                  ║
+//! ║  - Z80 sobre el bus: slots, puertos de E/S y VDP               ║
+//! ║  - Interrupción del VDP al final de cada cuadro                ║
+//! ║  - Accesos de cada instrucción para depurador y trazas         ║
+//! ║  - Traza, perfilador y CDL enganchados a cada paso             ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::bus::MsxBus;
+use crate::cdl::CodeDataLog;
+use crate::profiler::Profiler;
+use crate::slots::{SlotContent, SlotId};
+use crate::trace::{TraceEntry, Tracer};
+use crate::z80::{Z80Bus, Z80};
+
+/// Estados T de un cuadro a 60 Hz con el Z80 a 3,58 MHz
+pub const FRAME_TSTATES: u32 = 59736;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    /// Lectura de código: opcode, desplazamiento o dato inmediato
+    Fetch,
+    Read,
+    Write,
+    In,
+    Out,
+    /// Escritura en un registro del VDP: la dirección es el número de
+    /// registro
+    VdpRegister,
+}
+
+impl AccessKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccessKind::Fetch => "fetch",
+            AccessKind::Read => "read",
+            AccessKind::Write => "write",
+            AccessKind::In => "in",
+            AccessKind::Out => "out",
+            AccessKind::VdpRegister => "vdp",
+        }
+    }
+}
+
+/// Acceso al bus; en E/S la dirección es el puerto de 8 bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Access {
+    pub kind: AccessKind,
+    pub address: u16,
+    pub value: u8,
+}
+
+/// Resultado de un paso: la instrucción (o la interrupción aceptada)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepInfo {
+    /// PC antes del paso
+    pub address: u16,
+    pub tstates: u32,
+    /// El paso fue aceptar una interrupción, no ejecutar una instrucción
+    pub interrupt: bool,
+}
+
+pub struct Machine {
+    pub cpu: Z80,
+    pub bus: MsxBus,
+    /// Estados T desde el arranque
+    pub cycles: u64,
+    /// Estados T dentro del cuadro en curso
+    pub frame_cycles: u32,
+    pub frames: u64,
+    /// Accesos del último paso, en orden
+    pub accesses: Vec<Access>,
+    /// Traza de ejecución, si está activa
+    pub trace: Option<Tracer>,
+    /// Perfilador, si está activo
+    pub profiler: Option<Profiler>,
+    /// Registro de código y datos de un cartucho, si está activo
+    pub cdl: Option<CodeDataLog>,
+}
+
+impl Machine {
+    pub fn new() -> Machine {
+        Machine::with_bus(MsxBus::new())
+    }
+
+    pub fn with_bus(bus: MsxBus) -> Machine {
+        Machine {
+            cpu: Z80::new(),
+            bus,
+            cycles: 0,
+            frame_cycles: 0,
+            frames: 0,
+            accesses: Vec::new(),
+            trace: None,
+            profiler: None,
+            cdl: None,
+        }
+    }
+
+    /// 64 KB de RAM en el slot 3, visible en las cuatro páginas: para
+    /// ejecutar código sin BIOS
+    pub fn with_ram() -> Machine {
+        let mut machine = Machine::new();
+        machine.bus.slots.insert(SlotId { primary: 3, secondary: 0 }, SlotContent::ram());
+        machine.bus.io_write(0xA8, 0xFF);
+        machine
+    }
+
+    /// Copiar bytes a memoria a través de los slots activos
+    pub fn load(&mut self, address: u16, data: &[u8]) {
+        for (i, &byte) in data.iter().enumerate() {
+            self.bus.mem_write(address.wrapping_add(i as u16), byte);
+        }
+    }
+
+    /// Slot visible en cada una de las cuatro páginas
+    pub fn slot_map(&self) -> [SlotId; 4] {
+        let primary = self.bus.ppi.primary_slot_register();
+        [0, 1, 2, 3].map(|page: u16| self.bus.slots.resolve(page << 14, primary))
+    }
+
+    /// Aceptar la interrupción pendiente o ejecutar una instrucción
+    pub fn step(&mut self) -> StepInfo {
+        self.accesses.clear();
+        let address = self.cpu.regs.pc;
+        let observed = self.trace.is_some() || self.profiler.is_some();
+        let before = observed.then(|| (self.cycles, self.cpu.regs, self.slot_map(), self.bus.bank(address)));
+        let cdl_pages = self.cdl.as_ref().map(|cdl| cdl.pages(&self.bus));
+        let interrupt = self.bus.vdp.irq() && self.cpu.interrupts_enabled();
+        let mut port = Port { bus: &mut self.bus, accesses: &mut self.accesses };
+        // En el MSX el bus de datos queda a FFh durante la interrupción
+        let tstates = if interrupt { self.cpu.interrupt(&mut port, 0xFF) } else { self.cpu.step(&mut port) };
+        if let (Some(cdl), Some(pages)) = (self.cdl.as_mut(), cdl_pages) {
+            cdl.record(&pages, &self.accesses);
+        }
+        self.cycles += tstates as u64;
+        self.frame_cycles += tstates;
+        if self.frame_cycles >= FRAME_TSTATES {
+            self.frame_cycles -= FRAME_TSTATES;
+            self.frames += 1;
+            self.bus.vdp.vblank();
+        }
+        if let Some((cycles, regs, slots, (slot, bank))) = before {
+            let entry = TraceEntry::new(cycles, regs, slots, self.cpu.opcode(), tstates, interrupt);
+            if let Some(trace) = self.trace.as_mut() {
+                trace.record(entry);
+            }
+            if let Some(profiler) = self.profiler.as_mut() {
+                let bank = bank.map(|bank| (slot.primary, slot.secondary, bank));
+                profiler.record(&entry, &self.cpu.regs, bank, self.frames);
+            }
+        }
+        StepInfo { address, tstates, interrupt }
+    }
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// El bus visto por el Z80, apuntando cada acceso
+struct Port<'a> {
+    bus: &'a mut MsxBus,
+    accesses: &'a mut Vec<Access>,
+}
+
+impl Port<'_> {
+    fn record(&mut self, kind: AccessKind, address: u16, value: u8) {
+        self.accesses.push(Access { kind, address, value });
+    }
+}
+
+impl Z80Bus for Port<'_> {
+    fn read(&mut self, address: u16) -> u8 {
+        let value = self.bus.mem_read(address);
+        self.record(AccessKind::Read, address, value);
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.bus.mem_write(address, value);
+        self.record(AccessKind::Write, address, value);
+    }
+
+    fn input(&mut self, port: u16) -> u8 {
+        let value = self.bus.io_read(port as u8);
+        self.record(AccessKind::In, port & 0xFF, value);
+        value
+    }
+
+    fn output(&mut self, port: u16, value: u8) {
+        self.bus.io_write(port as u8, value);
+        self.record(AccessKind::Out, port & 0xFF, value);
+        if let Some((register, value)) = self.bus.vdp.take_register_write() {
+            self.record(AccessKind::VdpRegister, register as u16, value);
+        }
+    }
+
+    fn fetch(&mut self, address: u16) -> u8 {
+        let value = self.bus.mem_read(address);
+        self.record(AccessKind::Fetch, address, value);
+        value
+    }
+}
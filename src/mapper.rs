@@ -0,0 +1,164 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  MAPPERS DE MEGAROM                                            ║
+//! ║  - Konami, Konami SCC, ASCII 8K y ASCII 16K                    ║
+//! ║  - Registro de banco que cambia cada escritura                 ║
+//! ║  - Detección por las escrituras LD (nn),A de la ROM            ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+/// Unidad de paginación: las páginas de 8 KB del espacio del Z80
+pub const PAGE_SIZE: usize = 0x2000;
+/// Páginas de 8 KB en los 64 KB direccionables
+pub const PAGES: usize = 8;
+
+/// Banco de 8 KB visible en cada página (None: fuera del cartucho)
+pub type PageMap = [Option<u16>; PAGES];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapperType {
+    /// ROM lineal de hasta 48 KB sin mapper
+    Plain,
+    /// Konami sin SCC: 4000h fijo, registros en 6000h, 8000h y A000h
+    Konami,
+    /// Konami con SCC: registros en 5000h, 7000h, 9000h y B000h
+    KonamiScc,
+    /// ASCII 8K: registros en 6000h, 6800h, 7000h y 7800h
+    Ascii8,
+    /// ASCII 16K: registros en 6000h y 7000h
+    Ascii16,
+}
+
+impl MapperType {
+    pub const ALL: [MapperType; 5] = [
+        MapperType::Plain,
+        MapperType::Konami,
+        MapperType::KonamiScc,
+        MapperType::Ascii8,
+        MapperType::Ascii16,
+    ];
+
+    pub fn from_name(name: &str) -> Option<MapperType> {
+        match name.to_ascii_lowercase().as_str() {
+            "plain" | "normal" => Some(MapperType::Plain),
+            "konami" | "konami4" => Some(MapperType::Konami),
+            "konamiscc" | "scc" | "konami5" => Some(MapperType::KonamiScc),
+            "ascii8" => Some(MapperType::Ascii8),
+            "ascii16" => Some(MapperType::Ascii16),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapperType::Plain => "plain",
+            MapperType::Konami => "konami",
+            MapperType::KonamiScc => "konamiscc",
+            MapperType::Ascii8 => "ascii8",
+            MapperType::Ascii16 => "ascii16",
+        }
+    }
+
+    /// Tamaño del banco que se conmuta de una vez
+    pub fn bank_size(&self) -> usize {
+        match self {
+            MapperType::Ascii16 => 2 * PAGE_SIZE,
+            _ => PAGE_SIZE,
+        }
+    }
+
+    /// Bancos visibles tras el reset. Una ROM sin mapper ocupa páginas
+    /// consecutivas desde `origin`
+    pub fn initial_pages(&self, rom_len: usize, origin: u16) -> PageMap {
+        let mut pages = [None; PAGES];
+        match self {
+            MapperType::Plain => {
+                let first = origin as usize / PAGE_SIZE;
+                for bank in 0..rom_len.div_ceil(PAGE_SIZE).min(PAGES - first) {
+                    pages[first + bank] = Some(bank as u16);
+                }
+            }
+            MapperType::Konami | MapperType::KonamiScc => {
+                for (i, page) in pages[2..6].iter_mut().enumerate() {
+                    *page = Some(i as u16);
+                }
+            }
+            MapperType::Ascii8 => pages[2..6].fill(Some(0)),
+            MapperType::Ascii16 => {
+                pages[2..6].copy_from_slice(&[Some(0), Some(1), Some(0), Some(1)]);
+            }
+        }
+        pages
+    }
+
+    /// Páginas que cambia una escritura en `address`: primera página y
+    /// cuántas (2 en ASCII 16K)
+    pub fn register(&self, address: u16) -> Option<(usize, usize)> {
+        match (self, address) {
+            (MapperType::Konami, 0x6000..=0x7FFF) => Some((3, 1)),
+            (MapperType::Konami, 0x8000..=0x9FFF) => Some((4, 1)),
+            (MapperType::Konami, 0xA000..=0xBFFF) => Some((5, 1)),
+            (MapperType::KonamiScc, 0x5000..=0x57FF) => Some((2, 1)),
+            (MapperType::KonamiScc, 0x7000..=0x77FF) => Some((3, 1)),
+            (MapperType::KonamiScc, 0x9000..=0x97FF) => Some((4, 1)),
+            (MapperType::KonamiScc, 0xB000..=0xB7FF) => Some((5, 1)),
+            (MapperType::Ascii8, 0x6000..=0x7FFF) => Some((2 + ((address as usize - 0x6000) >> 11), 1)),
+            (MapperType::Ascii16, 0x6000..=0x67FF) => Some((2, 2)),
+            (MapperType::Ascii16, 0x7000..=0x77FF) => Some((4, 2)),
+            _ => None,
+        }
+    }
+
+    /// Dirección con la que el programa cambia el banco de `page` (la
+    /// primera de las dos páginas en ASCII 16K)
+    pub fn select_address(&self, page: usize) -> Option<u16> {
+        (0x4000..0xC000u16).step_by(0x800).find(|&address| self.register(address).is_some_and(|(first, _)| first == page))
+    }
+
+    /// Aplicar la escritura de `value` en un registro del mapper; devuelve
+    /// la primera página afectada
+    pub fn switch(&self, pages: &mut PageMap, rom_len: usize, address: u16, value: Option<u8>) -> Option<usize> {
+        let (first, count) = self.register(address)?;
+        let banks = rom_len.div_ceil(PAGE_SIZE).max(1);
+        for i in 0..count {
+            pages[first + i] = value.map(|v| ((v as usize * count + i) % banks) as u16);
+        }
+        Some(first)
+    }
+
+    /// Mapper más probable según las escrituras LD (nn),A que contiene la
+    /// ROM (mismo criterio que openMSX); hasta 48 KB no hay mapper
+    pub fn guess(rom: &[u8]) -> MapperType {
+        if rom.len() <= 0xC000 {
+            return MapperType::Plain;
+        }
+        // Konami, Konami SCC, ASCII 8K, ASCII 16K
+        let mut votes = [0i32; 4];
+        for window in rom.windows(3) {
+            if window[0] != 0x32 {
+                continue;
+            }
+            match u16::from_le_bytes([window[1], window[2]]) {
+                0x5000 | 0x9000 | 0xB000 => votes[1] += 1,
+                0x8000 | 0xA000 => votes[0] += 1,
+                0x6800 | 0x7800 => votes[2] += 1,
+                0x6000 => {
+                    votes[0] += 1;
+                    votes[2] += 1;
+                    votes[3] += 1;
+                }
+                0x7000 => {
+                    votes[1] += 1;
+                    votes[2] += 1;
+                    votes[3] += 1;
+                }
+                0x77FF => votes[3] += 1,
+                _ => {}
+            }
+        }
+        // En empate ASCII 8K pierde frente a los demás
+        if votes[2] > 0 {
+            votes[2] -= 1;
+        }
+        let best = (0..4).rev().max_by_key(|&i| votes[i]).unwrap_or(0);
+        [MapperType::Konami, MapperType::KonamiScc, MapperType::Ascii8, MapperType::Ascii16][best]
+    }
+}
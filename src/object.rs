@@ -0,0 +1,310 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  FICHEROS OBJETO REUBICABLES (.rel)                            ║
+//! ║  - Secciones con nombre que coloca el enlazador                ║
+//! ║  - Símbolos públicos y externos entre módulos                  ║
+//! ║  - Reubicaciones de palabra, LOW, HIGH, banco y CALL/JP        ║
+//! ╚════════════════════════════════════════════════════════════════╝
+//!
+//! Formato binario, enteros en little endian y cadenas como u16 con la
+//! longitud seguida de los bytes UTF-8:
+//!
+//! ```text
+//! "MSXREL" 01                          firma y versión
+//! cadena   módulo
+//! u16      secciones   × { cadena nombre, u16 tamaño, u16 n, n bytes }
+//! u16      externos    × { cadena nombre }
+//! u16      símbolos    × { cadena nombre, u8 público, ubicación }
+//! u16      reubicación × { u16 sección, u16 posición, u8 tipo,
+//!                          u8 destino (0 sección, 1 externo), u16 índice,
+//!                          u16 sumando }
+//! u8       entrada (0 no hay, 1 sí) [ubicación]
+//!
+//! ubicación: u8 (0 absoluta, 1 en sección) [u16 sección] u16 valor
+//! tipo:      0 palabra, 1 CALL/JP, 2 LOW, 3 HIGH, 4 banco
+//! ```
+//!
+//! Los bytes de una sección pueden ser menos que su tamaño: el resto es
+//! espacio reservado con DS, que en RAM no ocupa sitio en la salida
+
+/// Firma al principio de cada fichero objeto
+pub const OBJECT_MAGIC: &[u8; 6] = b"MSXREL";
+const OBJECT_VERSION: u8 = 1;
+
+/// Valor de un símbolo: absoluto (EQU) o desplazamiento en una sección
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Location {
+    Absolute(u16),
+    Section(u16, u16),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectSection {
+    pub name: String,
+    /// Tamaño incluido lo reservado con DS al final
+    pub size: u16,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectSymbol {
+    pub name: String,
+    /// Declarado con PUBLIC: visible para los otros módulos
+    pub public: bool,
+    pub location: Location,
+}
+
+/// Qué se escribe en la posición reubicada
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelocPart {
+    /// Dirección de 16 bits
+    Word,
+    /// Destino de CALL o JP: si está en otro banco se desvía por una
+    /// rutina de salto lejano
+    Call,
+    Low,
+    High,
+    /// Número de banco del mapper donde quedó el destino
+    Bank,
+}
+
+impl RelocPart {
+    pub fn size(&self) -> usize {
+        match self {
+            RelocPart::Word | RelocPart::Call => 2,
+            _ => 1,
+        }
+    }
+
+    fn code(&self) -> u8 {
+        match self {
+            RelocPart::Word => 0,
+            RelocPart::Call => 1,
+            RelocPart::Low => 2,
+            RelocPart::High => 3,
+            RelocPart::Bank => 4,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<RelocPart> {
+        [RelocPart::Word, RelocPart::Call, RelocPart::Low, RelocPart::High, RelocPart::Bank].get(code as usize).copied()
+    }
+}
+
+/// Aquello de lo que depende el valor: una sección del módulo o un
+/// símbolo externo, por su índice
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelocTarget {
+    Section(u16),
+    Extern(u16),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    pub section: u16,
+    pub offset: u16,
+    pub part: RelocPart,
+    pub target: RelocTarget,
+    pub addend: u16,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObjectFile {
+    pub module: String,
+    pub sections: Vec<ObjectSection>,
+    pub externs: Vec<String>,
+    pub symbols: Vec<ObjectSymbol>,
+    pub relocations: Vec<Relocation>,
+    /// Dirección de END, si el módulo la tiene
+    pub entry: Option<Location>,
+}
+
+impl ObjectFile {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = OBJECT_MAGIC.to_vec();
+        out.push(OBJECT_VERSION);
+        write_str(&mut out, &self.module);
+        write_u16(&mut out, self.sections.len() as u16);
+        for section in &self.sections {
+            write_str(&mut out, &section.name);
+            write_u16(&mut out, section.size);
+            write_u16(&mut out, section.data.len() as u16);
+            out.extend_from_slice(&section.data);
+        }
+        write_u16(&mut out, self.externs.len() as u16);
+        for name in &self.externs {
+            write_str(&mut out, name);
+        }
+        write_u16(&mut out, self.symbols.len() as u16);
+        for symbol in &self.symbols {
+            write_str(&mut out, &symbol.name);
+            out.push(symbol.public as u8);
+            write_location(&mut out, symbol.location);
+        }
+        write_u16(&mut out, self.relocations.len() as u16);
+        for reloc in &self.relocations {
+            write_u16(&mut out, reloc.section);
+            write_u16(&mut out, reloc.offset);
+            out.push(reloc.part.code());
+            let (kind, index) = match reloc.target {
+                RelocTarget::Section(index) => (0, index),
+                RelocTarget::Extern(index) => (1, index),
+            };
+            out.push(kind);
+            write_u16(&mut out, index);
+            write_u16(&mut out, reloc.addend);
+        }
+        match self.entry {
+            Some(location) => {
+                out.push(1);
+                write_location(&mut out, location);
+            }
+            None => out.push(0),
+        }
+        out
+    }
+
+    pub fn parse(data: &[u8]) -> Result<ObjectFile, String> {
+        if !data.starts_with(OBJECT_MAGIC) {
+            return Err("No es un fichero objeto: falta la firma MSXREL".to_string());
+        }
+        let mut reader = Reader { data, pos: OBJECT_MAGIC.len() };
+        let version = reader.u8()?;
+        if version != OBJECT_VERSION {
+            return Err(format!("Versión de fichero objeto no soportada: {}", version));
+        }
+        let module = reader.str()?;
+        let mut sections = Vec::new();
+        for _ in 0..reader.u16()? {
+            let name = reader.str()?;
+            let size = reader.u16()?;
+            let length = reader.u16()? as usize;
+            let data = reader.bytes(length)?.to_vec();
+            if data.len() > size as usize {
+                return Err(format!("La sección {} tiene más bytes que tamaño", name));
+            }
+            sections.push(ObjectSection { name, size, data });
+        }
+        let mut externs = Vec::new();
+        for _ in 0..reader.u16()? {
+            externs.push(reader.str()?);
+        }
+        let mut symbols = Vec::new();
+        for _ in 0..reader.u16()? {
+            let name = reader.str()?;
+            let public = reader.u8()? != 0;
+            let location = reader.location()?;
+            symbols.push(ObjectSymbol { name, public, location });
+        }
+        let mut relocations = Vec::new();
+        for _ in 0..reader.u16()? {
+            let section = reader.u16()?;
+            let offset = reader.u16()?;
+            let part = RelocPart::from_code(reader.u8()?).ok_or("Tipo de reubicación desconocido")?;
+            let target = match (reader.u8()?, reader.u16()?) {
+                (0, index) => RelocTarget::Section(index),
+                (1, index) => RelocTarget::Extern(index),
+                _ => return Err("Destino de reubicación desconocido".to_string()),
+            };
+            let addend = reader.u16()?;
+            relocations.push(Relocation { section, offset, part, target, addend });
+        }
+        let entry = match reader.u8()? {
+            0 => None,
+            _ => Some(reader.location()?),
+        };
+        let object = ObjectFile { module, sections, externs, symbols, relocations, entry };
+        object.validate()?;
+        Ok(object)
+    }
+
+    /// Índices y posiciones dentro de lo que declara el propio fichero
+    fn validate(&self) -> Result<(), String> {
+        let section_size = |index: u16| self.sections.get(index as usize).map(|s| s.size as usize);
+        for reloc in &self.relocations {
+            let size = section_size(reloc.section).ok_or("Reubicación en una sección que no existe")?;
+            if reloc.offset as usize + reloc.part.size() > size {
+                return Err(format!("Reubicación fuera de la sección en {:04X}h", reloc.offset));
+            }
+            let valid = match reloc.target {
+                RelocTarget::Section(index) => section_size(index).is_some(),
+                RelocTarget::Extern(index) => (index as usize) < self.externs.len(),
+            };
+            if !valid {
+                return Err("Reubicación hacia un símbolo que no existe".to_string());
+            }
+        }
+        let locations = self.symbols.iter().map(|s| s.location).chain(self.entry);
+        for location in locations {
+            if let Location::Section(index, _) = location {
+                section_size(index).ok_or("Símbolo en una sección que no existe")?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn section(&self, name: &str) -> Option<&ObjectSection> {
+        self.sections.iter().find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn public_symbols(&self) -> impl Iterator<Item = &ObjectSymbol> {
+        self.symbols.iter().filter(|s| s.public)
+    }
+}
+
+fn write_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_str(out: &mut Vec<u8>, text: &str) {
+    write_u16(out, text.len() as u16);
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn write_location(out: &mut Vec<u8>, location: Location) {
+    match location {
+        Location::Absolute(value) => {
+            out.push(0);
+            write_u16(out, value);
+        }
+        Location::Section(section, offset) => {
+            out.push(1);
+            write_u16(out, section);
+            write_u16(out, offset);
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let bytes = self.data.get(self.pos..self.pos + count).ok_or("Fichero objeto truncado")?;
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn str(&mut self) -> Result<String, String> {
+        let length = self.u16()? as usize;
+        String::from_utf8(self.bytes(length)?.to_vec()).map_err(|_| "Nombre no válido en el fichero objeto".to_string())
+    }
+
+    fn location(&mut self) -> Result<Location, String> {
+        match self.u8()? {
+            0 => Ok(Location::Absolute(self.u16()?)),
+            1 => Ok(Location::Section(self.u16()?, self.u16()?)),
+            _ => Err("Ubicación de símbolo desconocida".to_string()),
+        }
+    }
+}
@@ -0,0 +1,957 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  DESCOMPRESORES DE GRÁFICOS DE JUEGOS MSX                      ║
+//! ║  - Pletter 0.5, BitBuster 1.2, aPLib, ZX0 (v2) y ZX7           ║
+//! ║  - RLE estilo PackBits                                         ║
+//! ║  - Sondeo: probar todos los formatos en un desplazamiento      ║
+//! ║  - Compresores compatibles: Pletter, BitBuster, aPLib y ZX0    ║
+//! ╚════════════════════════════════════════════════════════════════╝
+//!
+//! Los formatos LZ leen los bits de un byte de banderas que se carga cuando
+//! hace falta, intercalado con los literales, igual que los descompresores
+//! Z80 originales.
+
+use crate::screen::ScreenMode;
+
+/// Nada que quepa en la VRAM de un MSX2 descomprime a más de 128 KB
+const MAX_OUTPUT: usize = 0x20000;
+/// Salida mínima para que el sondeo dé un resultado por bueno
+const MIN_PROBE_OUTPUT: usize = 64;
+/// Marca de fin de PackBits
+const RLE_END: u8 = 0x80;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Packer {
+    Pletter,
+    BitBuster,
+    Aplib,
+    Zx0,
+    Zx7,
+    Rle,
+}
+
+impl Packer {
+    pub const ALL: [Packer; 6] = [
+        Packer::Pletter,
+        Packer::BitBuster,
+        Packer::Aplib,
+        Packer::Zx0,
+        Packer::Zx7,
+        Packer::Rle,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Packer> {
+        match name.to_ascii_lowercase().as_str() {
+            "pletter" | "plet5" => Some(Packer::Pletter),
+            "bitbuster" | "bb" => Some(Packer::BitBuster),
+            "aplib" | "appack" => Some(Packer::Aplib),
+            "zx0" => Some(Packer::Zx0),
+            "zx7" => Some(Packer::Zx7),
+            "rle" | "packbits" => Some(Packer::Rle),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Packer::Pletter => "pletter",
+            Packer::BitBuster => "bitbuster",
+            Packer::Aplib => "aplib",
+            Packer::Zx0 => "zx0",
+            Packer::Zx7 => "zx7",
+            Packer::Rle => "rle",
+        }
+    }
+}
+
+/// Datos descomprimidos y bytes de entrada que ocupaba el bloque
+#[derive(Clone, Debug)]
+pub struct Unpacked {
+    pub data: Vec<u8>,
+    pub consumed: usize,
+}
+
+/// Un formato que descomprime limpio en el desplazamiento probado
+#[derive(Clone, Debug)]
+pub struct ProbeHit {
+    pub packer: Packer,
+    pub offset: usize,
+    pub consumed: usize,
+    pub size: usize,
+    /// Modo de pantalla que sugiere el tamaño de la salida
+    pub screen: Option<ScreenMode>,
+}
+
+/// Descomprimir un bloque que empieza al principio de `data`
+pub fn unpack(packer: Packer, data: &[u8]) -> Result<Unpacked, String> {
+    let mut decoder = Decoder::new(data);
+    match packer {
+        Packer::Pletter => unpack_pletter(&mut decoder)?,
+        Packer::BitBuster => unpack_bitbuster(&mut decoder)?,
+        Packer::Aplib => unpack_aplib(&mut decoder)?,
+        Packer::Zx0 => unpack_zx0(&mut decoder)?,
+        Packer::Zx7 => unpack_zx7(&mut decoder)?,
+        Packer::Rle => unpack_rle(&mut decoder)?,
+    }
+    Ok(Unpacked { data: decoder.output, consumed: decoder.pos })
+}
+
+/// Probar todos los formatos en `offset`. Los resultados con tamaño de
+/// pantalla reconocible van primero y, dentro de cada grupo, los que más
+/// expanden
+pub fn probe(data: &[u8], offset: usize) -> Vec<ProbeHit> {
+    let Some(block) = data.get(offset..) else {
+        return Vec::new();
+    };
+
+    let mut hits: Vec<ProbeHit> = Packer::ALL
+        .iter()
+        .filter_map(|&packer| {
+            let unpacked = unpack(packer, block).ok()?;
+            let size = unpacked.data.len();
+            if size < MIN_PROBE_OUTPUT || size <= unpacked.consumed {
+                return None;
+            }
+            // Casi cualquier cosa es PackBits válido: exigir la marca de fin
+            if packer == Packer::Rle && block[unpacked.consumed - 1] != RLE_END {
+                return None;
+            }
+            Some(ProbeHit { packer, offset, consumed: unpacked.consumed, size, screen: ScreenMode::guess(size) })
+        })
+        .collect();
+
+    hits.sort_by(|a, b| {
+        b.screen
+            .is_some()
+            .cmp(&a.screen.is_some())
+            .then((b.size * a.consumed).cmp(&(a.size * b.consumed)))
+    });
+    hits
+}
+
+// ═══════════════════════════════════════════════════════════════
+// LECTOR DE BITS Y VENTANA
+// ═══════════════════════════════════════════════════════════════
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+    bits: u8,
+    mask: u8,
+    /// ZX0 relee el bit bajo del byte de desplazamiento
+    backtrack: bool,
+    output: Vec<u8>,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Decoder { input, pos: 0, bits: 0, mask: 0, backtrack: false, output: Vec::new() }
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self.input.get(self.pos).ok_or("Datos comprimidos truncados")?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Siguiente bit, del más alto al más bajo; el byte de banderas se lee
+    /// solo cuando se acaba el anterior
+    fn bit(&mut self) -> Result<bool, String> {
+        if self.backtrack {
+            self.backtrack = false;
+            return Ok(self.input[self.pos - 1] & 1 != 0);
+        }
+        if self.mask == 0 {
+            self.bits = self.byte()?;
+            self.mask = 0x80;
+        }
+        let bit = self.bits & self.mask != 0;
+        self.mask >>= 1;
+        Ok(bit)
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.bit()? as u32;
+        }
+        Ok(value)
+    }
+
+    fn push(&mut self, byte: u8) -> Result<(), String> {
+        if self.output.len() >= MAX_OUTPUT {
+            return Err("La salida supera los 128 KB de VRAM".to_string());
+        }
+        self.output.push(byte);
+        Ok(())
+    }
+
+    fn literal(&mut self) -> Result<(), String> {
+        let byte = self.byte()?;
+        self.push(byte)
+    }
+
+    /// Copiar `length` bytes desde `distance` atrás (pueden solaparse)
+    fn copy(&mut self, distance: usize, length: usize) -> Result<(), String> {
+        if distance == 0 || distance > self.output.len() {
+            return Err(format!(
+                "Distancia {} fuera de los {} bytes ya descomprimidos",
+                distance,
+                self.output.len()
+            ));
+        }
+        for _ in 0..length {
+            self.push(self.output[self.output.len() - distance])?;
+        }
+        Ok(())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// FORMATOS
+// ═══════════════════════════════════════════════════════════════
+
+/// Pletter 0.5: los 3 primeros bits eligen cuántos bits extra llevan los
+/// desplazamientos largos; longitud gamma intercalada con fin al desbordar
+/// 16 bits
+fn unpack_pletter(d: &mut Decoder) -> Result<(), String> {
+    let mode = d.bits(3)?;
+    d.literal()?;
+    loop {
+        if !d.bit()? {
+            d.literal()?;
+            continue;
+        }
+        let mut length = 1u32;
+        if d.bit()? {
+            loop {
+                length = (length << 1) | d.bit()? as u32;
+                if length > 0xFFFF {
+                    return Ok(());
+                }
+                if !d.bit()? {
+                    break;
+                }
+            }
+        }
+        let low = d.byte()? as u32;
+        let mut offset = low;
+        if low & 0x80 != 0 && mode > 0 {
+            let mut high = d.bits(mode)?;
+            if d.bit()? {
+                high += 1;
+                offset &= 0x7F;
+            }
+            offset += high << 8;
+        }
+        d.copy(offset as usize + 1, length as usize + 1)?;
+    }
+}
+
+/// BitBuster 1.2: tamaño original en 4 bytes, desplazamiento de 7 u 11 bits
+/// y longitud gamma; el fin es una longitud de más de 16 bits
+fn unpack_bitbuster(d: &mut Decoder) -> Result<(), String> {
+    let header: [u8; 4] = d.input.get(..4).ok_or("Falta la cabecera de BitBuster")?.try_into().unwrap();
+    let expected = u32::from_le_bytes(header) as usize;
+    d.pos = 4;
+    loop {
+        if !d.bit()? {
+            d.literal()?;
+            continue;
+        }
+        let low = d.byte()? as u32;
+        let mut offset = low & 0x7F;
+        if low & 0x80 != 0 {
+            offset |= d.bits(4)? << 7;
+        }
+        let mut count = 1;
+        while d.bit()? {
+            count += 1;
+        }
+        let mut length = 1u32;
+        for _ in 1..count {
+            length = (length << 1) | d.bit()? as u32;
+            if length > 0xFFFF {
+                return if d.output.len() == expected {
+                    Ok(())
+                } else {
+                    Err(format!("Salida de {} bytes; la cabecera indica {}", d.output.len(), expected))
+                };
+            }
+        }
+        d.copy(offset as usize + 1, length as usize + 1)?;
+    }
+}
+
+fn aplib_gamma(d: &mut Decoder) -> Result<usize, String> {
+    let mut value = 1usize;
+    loop {
+        value = (value << 1) | d.bit()? as usize;
+        if value > MAX_OUTPUT {
+            return Err("Valor gamma de aPLib desbordado".to_string());
+        }
+        if !d.bit()? {
+            return Ok(value);
+        }
+    }
+}
+
+/// aPLib (aPPack): códigos 0, 10, 110 y 111 con desplazamiento repetido
+fn unpack_aplib(d: &mut Decoder) -> Result<(), String> {
+    d.literal()?;
+    let mut last_offset = 0usize;
+    let mut after_match = false;
+    loop {
+        if !d.bit()? {
+            d.literal()?;
+            after_match = false;
+        } else if !d.bit()? {
+            let high = aplib_gamma(d)?;
+            if !after_match && high == 2 {
+                let length = aplib_gamma(d)?;
+                d.copy(last_offset, length)?;
+            } else {
+                let high = high - if after_match { 2 } else { 3 };
+                let offset = (high << 8) | d.byte()? as usize;
+                let mut length = aplib_gamma(d)?;
+                if offset >= 32000 {
+                    length += 1;
+                }
+                if offset >= 1280 {
+                    length += 1;
+                }
+                if offset < 128 {
+                    length += 2;
+                }
+                d.copy(offset, length)?;
+                last_offset = offset;
+            }
+            after_match = true;
+        } else if !d.bit()? {
+            let byte = d.byte()? as usize;
+            let offset = byte >> 1;
+            if offset == 0 {
+                return Ok(());
+            }
+            d.copy(offset, 2 + (byte & 1))?;
+            last_offset = offset;
+            after_match = true;
+        } else {
+            match d.bits(4)? as usize {
+                0 => d.push(0)?,
+                offset => d.copy(offset, 1)?,
+            }
+            after_match = false;
+        }
+    }
+}
+
+/// Gamma de ZX0: cada bit 0 va seguido de un bit de dato; un 1 termina
+fn zx0_gamma(d: &mut Decoder, inverted: bool) -> Result<usize, String> {
+    let mut value = 1usize;
+    while !d.bit()? {
+        value = (value << 1) | (d.bit()? ^ inverted) as usize;
+        if value > MAX_OUTPUT {
+            return Err("Valor gamma de ZX0 desbordado".to_string());
+        }
+    }
+    Ok(value)
+}
+
+/// ZX0 v2: bloques de literales, repetición del último desplazamiento y
+/// desplazamientos nuevos; el bit bajo del byte de desplazamiento es el
+/// primero de la longitud
+fn unpack_zx0(d: &mut Decoder) -> Result<(), String> {
+    let mut last_offset = 1usize;
+    loop {
+        for _ in 0..zx0_gamma(d, false)? {
+            d.literal()?;
+        }
+        let mut new_offset = d.bit()?;
+        if !new_offset {
+            let length = zx0_gamma(d, false)?;
+            d.copy(last_offset, length)?;
+            new_offset = d.bit()?;
+        }
+        while new_offset {
+            let high = zx0_gamma(d, true)?;
+            if high == 256 {
+                return Ok(());
+            }
+            let low = d.byte()? as usize;
+            last_offset = high * 128 - (low >> 1);
+            d.backtrack = true;
+            let length = zx0_gamma(d, false)? + 1;
+            d.copy(last_offset, length)?;
+            new_offset = d.bit()?;
+        }
+    }
+}
+
+/// ZX7: longitud gamma (ceros, 1, bits) y desplazamiento de 7 u 11 bits;
+/// dieciséis ceros seguidos marcan el fin
+fn unpack_zx7(d: &mut Decoder) -> Result<(), String> {
+    d.literal()?;
+    loop {
+        if !d.bit()? {
+            d.literal()?;
+            continue;
+        }
+        let mut zeros = 0;
+        while !d.bit()? {
+            zeros += 1;
+        }
+        if zeros >= 16 {
+            return Ok(());
+        }
+        let length = d.bits(zeros)? | (1 << zeros);
+        let low = d.byte()? as u32;
+        let offset = if low & 0x80 != 0 {
+            ((d.bits(4)? << 7) | (low & 0x7F)) + 128
+        } else {
+            low
+        };
+        d.copy(offset as usize + 1, length as usize + 1)?;
+    }
+}
+
+/// PackBits: n < 80h copia n+1 literales, n > 80h repite el siguiente
+/// byte 257-n veces y 80h termina (o el final de los datos)
+fn unpack_rle(d: &mut Decoder) -> Result<(), String> {
+    while d.pos < d.input.len() {
+        match d.byte()? {
+            RLE_END => return Ok(()),
+            count @ 0..=0x7F => {
+                for _ in 0..=count {
+                    d.literal()?;
+                }
+            }
+            count => {
+                let byte = d.byte()?;
+                for _ in 0..257 - count as usize {
+                    d.push(byte)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// COMPRESORES
+// ═══════════════════════════════════════════════════════════════
+
+/// Las longitudes se codifican como mucho con 16 bits bajo el 1 inicial
+const MAX_MATCH: usize = 0x10000;
+/// Candidatos que se miran en cada cadena de coincidencias
+const CHAIN_LIMIT: usize = 256;
+/// Por encima de esta longitud el análisis óptimo solo prueba la más larga
+const EXHAUSTIVE_LENGTH: usize = 32;
+
+const PLETTER_WINDOW: usize = (1 << 13) + 128;
+const BITBUSTER_WINDOW: usize = 2048;
+const APLIB_WINDOW: usize = 0xFFFF;
+const ZX0_WINDOW: usize = 255 * 128;
+
+/// Comprimir con el formato pedido; la salida la descomprimen tal cual las
+/// rutinas Z80 de referencia
+pub fn pack(packer: Packer, data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.is_empty() {
+        return Err("No hay datos que comprimir".to_string());
+    }
+    if data.len() > MAX_OUTPUT {
+        return Err("Los datos superan los 128 KB de VRAM".to_string());
+    }
+    match packer {
+        Packer::Pletter => Ok(pack_pletter(data)),
+        Packer::BitBuster => Ok(pack_bitbuster(data)),
+        Packer::Aplib => Ok(pack_aplib(data)),
+        Packer::Zx0 => Ok(pack_zx0(data)),
+        other => Err(format!("No hay compresor para {}", other.name())),
+    }
+}
+
+/// Escritor simétrico del `Decoder`: el byte de banderas se reserva en la
+/// salida en el momento en que hace falta su primer bit
+struct BitWriter {
+    out: Vec<u8>,
+    flags: usize,
+    mask: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter { out: Vec::new(), flags: 0, mask: 0 }
+    }
+
+    fn bit(&mut self, bit: bool) {
+        if self.mask == 0 {
+            self.flags = self.out.len();
+            self.out.push(0);
+            self.mask = 0x80;
+        }
+        if bit {
+            self.out[self.flags] |= self.mask;
+        }
+        self.mask >>= 1;
+    }
+
+    fn bits(&mut self, value: usize, count: u32) {
+        for i in (0..count).rev() {
+            self.bit((value >> i) & 1 != 0);
+        }
+    }
+
+    fn byte(&mut self, byte: u8) {
+        self.out.push(byte);
+    }
+}
+
+/// Bits de `value` por debajo del 1 más alto, del más alto al más bajo
+fn gamma_digits(value: usize) -> impl Iterator<Item = bool> {
+    let count = usize::BITS - 1 - value.leading_zeros();
+    (0..count).rev().map(move |i| (value >> i) & 1 != 0)
+}
+
+/// Tamaño en bits de cualquiera de las variantes gamma
+fn gamma_cost(value: usize) -> usize {
+    2 * (usize::BITS - 1 - value.leading_zeros()) as usize + 1
+}
+
+#[derive(Clone, Copy)]
+enum Token {
+    Literal,
+    Match { distance: usize, length: usize },
+}
+
+impl Token {
+    fn length(&self) -> usize {
+        match self {
+            Token::Literal => 1,
+            Token::Match { length, .. } => *length,
+        }
+    }
+}
+
+const NO_POSITION: usize = usize::MAX;
+
+/// Cadenas de posiciones que empiezan por los mismos dos bytes
+struct MatchFinder<'a> {
+    data: &'a [u8],
+    previous: Vec<usize>,
+    /// Por distancia: posición siguiente a la última medida y su longitud;
+    /// en las rachas largas evita volver a comparar lo ya comparado
+    measured: Vec<(usize, usize)>,
+}
+
+impl<'a> MatchFinder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        let mut head = vec![NO_POSITION; 0x10000];
+        let mut previous = vec![NO_POSITION; data.len()];
+        for pos in 0..data.len().saturating_sub(1) {
+            let key = ((data[pos] as usize) << 8) | data[pos + 1] as usize;
+            previous[pos] = head[key];
+            head[key] = pos;
+        }
+        MatchFinder { data, previous, measured: vec![(0, 0); 0x10000] }
+    }
+
+    /// Bytes que coinciden con los de `distance` atrás
+    fn length(&mut self, pos: usize, distance: usize, max_length: usize) -> usize {
+        if distance == 0 || distance > pos {
+            return 0;
+        }
+        let limit = max_length.min(self.data.len() - pos);
+        let (next, previous_length) = self.measured[distance];
+        let mut length = if next == pos { previous_length.saturating_sub(1).min(limit) } else { 0 };
+        while length < limit && self.data[pos + length] == self.data[pos + length - distance] {
+            length += 1;
+        }
+        self.measured[distance] = (pos + 1, length);
+        length
+    }
+
+    /// Coincidencias desde la más cercana, cada una más larga que la anterior
+    fn candidates(&mut self, pos: usize, max_distance: usize) -> Vec<(usize, usize)> {
+        let limit = MAX_MATCH.min(self.data.len() - pos);
+        let mut found = Vec::new();
+        let mut best = 1;
+        let mut candidate = self.previous[pos];
+        for _ in 0..CHAIN_LIMIT {
+            if candidate == NO_POSITION || pos - candidate > max_distance {
+                break;
+            }
+            let length = self.length(pos, pos - candidate, limit);
+            if length > best {
+                best = length;
+                found.push((pos - candidate, length));
+                if length == limit {
+                    break;
+                }
+            }
+            candidate = self.previous[candidate];
+        }
+        found
+    }
+}
+
+/// Análisis óptimo hacia atrás para formatos sin estado. `match_cost` da
+/// los bits de una copia o None si el formato no la admite
+fn optimal_parse(
+    candidates: &[Vec<(usize, usize)>],
+    start: usize,
+    literal_cost: usize,
+    match_cost: impl Fn(usize, usize) -> Option<usize>,
+) -> (usize, Vec<Token>) {
+    let n = candidates.len();
+    let mut cost = vec![0usize; n + 1];
+    let mut choice = vec![Token::Literal; n];
+
+    for pos in (start..n).rev() {
+        cost[pos] = literal_cost + cost[pos + 1];
+        let mut shorter = 1;
+        for &(distance, longest) in &candidates[pos] {
+            let exhaustive = shorter + 1..=longest.min(EXHAUSTIVE_LENGTH);
+            let jump = (longest > EXHAUSTIVE_LENGTH).then_some(longest);
+            for length in exhaustive.chain(jump) {
+                if let Some(bits) = match_cost(distance, length) {
+                    if bits + cost[pos + length] < cost[pos] {
+                        cost[pos] = bits + cost[pos + length];
+                        choice[pos] = Token::Match { distance, length };
+                    }
+                }
+            }
+            shorter = longest;
+        }
+    }
+
+    let mut tokens = Vec::new();
+    let mut pos = start;
+    while pos < n {
+        tokens.push(choice[pos]);
+        pos += choice[pos].length();
+    }
+    (cost[start], tokens)
+}
+
+fn all_candidates(data: &[u8], max_distance: usize) -> Vec<Vec<(usize, usize)>> {
+    let mut finder = MatchFinder::new(data);
+    (0..data.len()).map(|pos| finder.candidates(pos, max_distance)).collect()
+}
+
+/// Pletter: se prueban los seis modos de desplazamiento y gana el más corto
+fn pack_pletter(data: &[u8]) -> Vec<u8> {
+    let candidates = all_candidates(data, PLETTER_WINDOW);
+    let offset_bits = |mode: u32, offset: usize| match mode {
+        1 if offset < 256 => Some(8),
+        1 => None,
+        _ if offset < 128 => Some(8),
+        _ if offset < (1 << (mode + 7)) + 128 => Some(8 + mode as usize),
+        _ => None,
+    };
+
+    let (mode, tokens) = (1..=6)
+        .map(|mode| {
+            let (bits, tokens) = optimal_parse(&candidates, 1, 9, |distance, length| {
+                Some(1 + gamma_cost(length - 1) + offset_bits(mode, distance - 1)?)
+            });
+            (bits, mode, tokens)
+        })
+        .min_by_key(|(bits, ..)| *bits)
+        .map(|(_, mode, tokens)| (mode, tokens))
+        .unwrap();
+
+    let mut w = BitWriter::new();
+    w.bits(mode as usize - 1, 3);
+    w.byte(data[0]);
+    let mut pos = 1;
+    for token in tokens {
+        match token {
+            Token::Literal => {
+                w.bit(false);
+                w.byte(data[pos]);
+            }
+            Token::Match { distance, length } => {
+                w.bit(true);
+                pletter_gamma(&mut w, length - 1);
+                let offset = distance - 1;
+                if mode == 1 || offset < 128 {
+                    w.byte(offset as u8);
+                } else {
+                    let long = offset - 128;
+                    w.byte((long & 0x7F) as u8 | 0x80);
+                    w.bits(long >> 8, mode - 1);
+                    w.bit(long & 0x80 != 0);
+                }
+            }
+        }
+        pos += token.length();
+    }
+    // Fin: una longitud que desborda los 16 bits
+    w.bit(true);
+    w.bit(true);
+    for i in 0..16 {
+        w.bit(false);
+        if i < 15 {
+            w.bit(true);
+        }
+    }
+    w.out
+}
+
+/// Gamma de Pletter: un 1 delante de cada bit de dato y un 0 al final
+fn pletter_gamma(w: &mut BitWriter, value: usize) {
+    for digit in gamma_digits(value) {
+        w.bit(true);
+        w.bit(digit);
+    }
+    w.bit(false);
+}
+
+fn pack_bitbuster(data: &[u8]) -> Vec<u8> {
+    let candidates = all_candidates(data, BITBUSTER_WINDOW);
+    let (_, tokens) = optimal_parse(&candidates, 0, 9, |distance, length| {
+        let extension = if distance > 128 { 4 } else { 0 };
+        Some(9 + extension + gamma_cost(length - 1))
+    });
+
+    let mut w = BitWriter::new();
+    for byte in (data.len() as u32).to_le_bytes() {
+        w.byte(byte);
+    }
+    let mut pos = 0;
+    for token in tokens {
+        match token {
+            Token::Literal => {
+                w.bit(false);
+                w.byte(data[pos]);
+            }
+            Token::Match { distance, length } => {
+                w.bit(true);
+                let offset = distance - 1;
+                if offset < 128 {
+                    w.byte(offset as u8);
+                } else {
+                    w.byte((offset & 0x7F) as u8 | 0x80);
+                    w.bits(offset >> 7, 4);
+                }
+                bitbuster_gamma(&mut w, length - 1);
+            }
+        }
+        pos += token.length();
+    }
+    // Fin: desplazamiento cualquiera y una longitud de 17 bits
+    w.bit(true);
+    w.byte(0);
+    w.bits(0xFFFF, 16);
+    w.bit(false);
+    w.bits(0, 16);
+    w.out
+}
+
+/// Gamma de BitBuster: tantos unos como bits de dato, un 0 y los datos
+fn bitbuster_gamma(w: &mut BitWriter, value: usize) {
+    for _ in gamma_digits(value) {
+        w.bit(true);
+    }
+    w.bit(false);
+    for digit in gamma_digits(value) {
+        w.bit(digit);
+    }
+}
+
+/// Gamma de aPLib: cada bit de dato va seguido de 1 si quedan más
+fn aplib_gamma_write(w: &mut BitWriter, value: usize) {
+    let digits: Vec<bool> = gamma_digits(value).collect();
+    for (i, &digit) in digits.iter().enumerate() {
+        w.bit(digit);
+        w.bit(i + 1 < digits.len());
+    }
+}
+
+/// Longitud que aPLib suma por su cuenta según el desplazamiento
+fn aplib_length_bonus(offset: usize) -> usize {
+    (offset >= 32000) as usize + (offset >= 1280) as usize + if offset < 128 { 2 } else { 0 }
+}
+
+enum AplibCode {
+    Literal,
+    /// 111 + 4 bits: un byte cercano o un cero
+    Nibble(usize),
+    /// 110 + byte: 2 o 3 bytes a menos de 128
+    Short(usize, usize),
+    Long(usize, usize),
+    Repeat(usize),
+}
+
+/// aPLib: el estado (último desplazamiento y si venimos de una copia)
+/// cambia los códigos, así que se elige en cada paso el que más ahorra
+fn pack_aplib(data: &[u8]) -> Vec<u8> {
+    let mut finder = MatchFinder::new(data);
+    let mut w = BitWriter::new();
+    w.byte(data[0]);
+    let (mut pos, mut last_offset, mut after_match) = (1, 0, false);
+
+    while pos < data.len() {
+        // Ahorro en bits frente a emitir literales de 9 bits
+        let mut best = (0isize, AplibCode::Literal);
+        let mut consider = |saving: isize, code: AplibCode| {
+            if saving > best.0 {
+                best = (saving, code);
+            }
+        };
+
+        if data[pos] == 0 {
+            consider(2, AplibCode::Nibble(0));
+        } else if let Some(distance) = (1..=15.min(pos)).find(|&d| data[pos - d] == data[pos]) {
+            consider(2, AplibCode::Nibble(distance));
+        }
+        if !after_match && last_offset > 0 {
+            let length = finder.length(pos, last_offset, MAX_MATCH);
+            if length >= 2 {
+                let bits = 2 + gamma_cost(2) + gamma_cost(length);
+                consider(9 * length as isize - bits as isize, AplibCode::Repeat(length));
+            }
+        }
+        for (distance, length) in finder.candidates(pos, APLIB_WINDOW) {
+            if distance < 128 {
+                let short = length.min(3);
+                consider(9 * short as isize - 11, AplibCode::Short(distance, short));
+            }
+            let bonus = aplib_length_bonus(distance);
+            if length >= bonus + 2 {
+                let high = (distance >> 8) + if after_match { 2 } else { 3 };
+                let bits = 2 + gamma_cost(high) + 8 + gamma_cost(length - bonus);
+                consider(9 * length as isize - bits as isize, AplibCode::Long(distance, length));
+            }
+        }
+
+        match best.1 {
+            AplibCode::Literal => {
+                w.bit(false);
+                w.byte(data[pos]);
+                pos += 1;
+                after_match = false;
+            }
+            AplibCode::Nibble(distance) => {
+                w.bits(0b111, 3);
+                w.bits(distance, 4);
+                pos += 1;
+                after_match = false;
+            }
+            AplibCode::Short(distance, length) => {
+                w.bits(0b110, 3);
+                w.byte(((distance << 1) | (length - 2)) as u8);
+                (pos, last_offset, after_match) = (pos + length, distance, true);
+            }
+            AplibCode::Long(distance, length) => {
+                w.bits(0b10, 2);
+                aplib_gamma_write(&mut w, (distance >> 8) + if after_match { 2 } else { 3 });
+                w.byte(distance as u8);
+                aplib_gamma_write(&mut w, length - aplib_length_bonus(distance));
+                (pos, last_offset, after_match) = (pos + length, distance, true);
+            }
+            AplibCode::Repeat(length) => {
+                w.bits(0b10, 2);
+                aplib_gamma_write(&mut w, 2);
+                aplib_gamma_write(&mut w, length);
+                (pos, after_match) = (pos + length, true);
+            }
+        }
+    }
+    // Fin: código corto con desplazamiento 0
+    w.bits(0b110, 3);
+    w.byte(0);
+    w.out
+}
+
+/// Gamma de ZX0: un 0 delante de cada bit de dato y un 1 al final
+fn zx0_gamma_bits(value: usize, inverted: bool) -> Vec<bool> {
+    let mut bits = Vec::new();
+    for digit in gamma_digits(value) {
+        bits.push(false);
+        bits.push(digit ^ inverted);
+    }
+    bits.push(true);
+    bits
+}
+
+fn zx0_gamma_write(w: &mut BitWriter, value: usize, inverted: bool) {
+    for bit in zx0_gamma_bits(value, inverted) {
+        w.bit(bit);
+    }
+}
+
+/// ZX0: la repetición del último desplazamiento solo cabe tras literales,
+/// así que también se elige paso a paso
+fn pack_zx0(data: &[u8]) -> Vec<u8> {
+    let mut finder = MatchFinder::new(data);
+    let mut tokens = vec![Token::Literal];
+    let (mut pos, mut last_offset) = (1, 1);
+
+    while pos < data.len() {
+        let after_literal = matches!(tokens.last(), Some(Token::Literal));
+        let mut best = (0isize, Token::Literal);
+        if after_literal {
+            let length = finder.length(pos, last_offset, MAX_MATCH);
+            if length >= 1 {
+                let saving = 9 * length as isize - (1 + gamma_cost(length)) as isize;
+                best = (saving, Token::Match { distance: last_offset, length });
+            }
+        }
+        for (distance, length) in finder.candidates(pos, ZX0_WINDOW) {
+            let bits = gamma_cost(distance.div_ceil(128)) + 8 + gamma_cost(length - 1);
+            let saving = 9 * length as isize - bits as isize;
+            if saving > best.0 {
+                best = (saving, Token::Match { distance, length });
+            }
+        }
+        if let Token::Match { distance, .. } = best.1 {
+            last_offset = distance;
+        }
+        pos += best.1.length();
+        tokens.push(best.1);
+    }
+
+    let mut w = BitWriter::new();
+    let (mut pos, mut last_offset, mut i) = (0, 1, 0);
+    while i < tokens.len() {
+        match tokens[i] {
+            Token::Literal => {
+                let run = tokens[i..].iter().take_while(|t| matches!(t, Token::Literal)).count();
+                if i > 0 {
+                    w.bit(false);
+                }
+                zx0_gamma_write(&mut w, run, false);
+                for &byte in &data[pos..pos + run] {
+                    w.byte(byte);
+                }
+                pos += run;
+                i += run;
+            }
+            Token::Match { distance, length } => {
+                let after_literal = i > 0 && matches!(tokens[i - 1], Token::Literal);
+                if after_literal && distance == last_offset {
+                    w.bit(false);
+                    zx0_gamma_write(&mut w, length, false);
+                } else {
+                    w.bit(true);
+                    let high = distance.div_ceil(128);
+                    zx0_gamma_write(&mut w, high, true);
+                    // El primer bit de la longitud viaja en el bit bajo del byte
+                    let length_bits = zx0_gamma_bits(length - 1, false);
+                    w.byte((((high * 128 - distance) << 1) | length_bits[0] as usize) as u8);
+                    for &bit in &length_bits[1..] {
+                        w.bit(bit);
+                    }
+                }
+                last_offset = distance;
+                pos += length;
+                i += 1;
+            }
+        }
+    }
+    // Fin: desplazamiento nuevo con parte alta 256
+    w.bit(true);
+    zx0_gamma_write(&mut w, 256, true);
+    w.out
+}
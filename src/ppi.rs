@@ -0,0 +1,119 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  PPI 8255 (puertos A8h-ABh)                                    ║
+//! ║  - Puerto A: registro de slot primario                         ║
+//! ║  - Puerto B: lectura de la fila de teclado seleccionada        ║
+//! ║  - Puerto C: fila, motor de cassette, LED CAPS y click         ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::keyboard::KeyboardMatrix;
+
+pub const PORT_A: u8 = 0xA8;
+pub const PORT_B: u8 = 0xA9;
+pub const PORT_C: u8 = 0xAA;
+pub const PORT_CONTROL: u8 = 0xAB;
+
+/// Palabra de control que programa el BIOS: A salida, B entrada, C salida
+pub const MSX_CONTROL_WORD: u8 = 0x82;
+
+pub struct Ppi8255 {
+    port_a: u8,
+    port_c: u8,
+    control: u8,
+    pub keyboard: KeyboardMatrix,
+}
+
+impl Ppi8255 {
+    pub fn new(keyboard: KeyboardMatrix) -> Ppi8255 {
+        Ppi8255 {
+            port_a: 0x00,
+            // Motor de cassette apagado (bit 4 a 1) y fila 0
+            port_c: 0x10,
+            control: MSX_CONTROL_WORD,
+            keyboard,
+        }
+    }
+
+    /// Reinicio: slots a 0 y fila 0, conservando la distribución
+    pub fn reset(&mut self) {
+        self.port_a = 0x00;
+        self.port_c = 0x10;
+        self.control = MSX_CONTROL_WORD;
+        self.keyboard.release_all();
+    }
+
+    pub fn read(&self, port: u8) -> u8 {
+        match port & 0x03 {
+            0 => self.port_a,
+            1 => self.keyboard.read_row(self.selected_row()),
+            2 => self.port_c,
+            // El registro de control no se puede leer en el 8255
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, port: u8, value: u8) {
+        match port & 0x03 {
+            0 => self.port_a = value,
+            1 => {} // Puerto B es de entrada en el MSX
+            2 => self.port_c = value,
+            _ => {
+                if value & 0x80 != 0 {
+                    // Selección de modo: reprograma el chip y limpia las salidas
+                    self.control = value;
+                    self.port_a = 0x00;
+                    self.port_c = 0x00;
+                } else {
+                    // Set/reset de un bit individual del puerto C
+                    let bit = (value >> 1) & 0x07;
+                    if value & 0x01 != 0 {
+                        self.port_c |= 1 << bit;
+                    } else {
+                        self.port_c &= !(1 << bit);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Registro de slot primario: 2 bits por página (0000h, 4000h, 8000h, C000h)
+    pub fn primary_slot_register(&self) -> u8 {
+        self.port_a
+    }
+
+    /// Slot primario visible en la página indicada (0-3)
+    pub fn primary_slot(&self, page: u8) -> u8 {
+        (self.port_a >> ((page & 0x03) * 2)) & 0x03
+    }
+
+    pub fn selected_row(&self) -> u8 {
+        self.port_c & 0x0F
+    }
+
+    /// El motor del cassette se activa con el bit 4 a 0
+    pub fn cassette_motor_on(&self) -> bool {
+        self.port_c & 0x10 == 0
+    }
+
+    pub fn cassette_output(&self) -> bool {
+        self.port_c & 0x20 != 0
+    }
+
+    /// El LED de CAPS se enciende con el bit 6 a 0
+    pub fn caps_led_on(&self) -> bool {
+        self.port_c & 0x40 == 0
+    }
+
+    pub fn key_click(&self) -> bool {
+        self.port_c & 0x80 != 0
+    }
+
+    pub fn control_word(&self) -> u8 {
+        self.control
+    }
+}
+
+impl Default for Ppi8255 {
+    fn default() -> Self {
+        Self::new(KeyboardMatrix::default())
+    }
+}
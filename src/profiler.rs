@@ -0,0 +1,334 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  PERFILADOR DE EJECUCIÓN                                       ║
+//! ║  - Estados T por dirección, rutina, banco y interrupción       ║
+//! ║  - Pila de llamadas seguida por CALL/RST, RET e interrupciones ║
+//! ║  - Informe ordenado y pilas plegadas para flame graphs         ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::disasm::{decode, hex16, Flow};
+use crate::symbols::{SymbolKind, SymbolTable};
+use crate::trace::TraceEntry;
+use crate::z80::Registers;
+use std::collections::{BTreeMap, HashMap};
+
+/// Profundidad máxima de la pila de llamadas que se sigue
+pub const MAX_DEPTH: usize = 64;
+
+/// Nombre de lo que no cae en ninguna rutina conocida
+const UNKNOWN: &str = "(sin símbolo)";
+
+/// Fila de un informe: dirección, rutina, banco o manejador
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hotspot {
+    pub label: String,
+    pub address: Option<u16>,
+    pub tstates: u64,
+    /// Instrucciones ejecutadas (llamadas en los manejadores)
+    pub count: u64,
+}
+
+/// Nodo de una pila plegada
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    /// Rutina desde la que empiezan las llamadas
+    Root(Option<u16>),
+    Call(u16),
+    Interrupt(u16),
+}
+
+/// Llamada en curso; se cierra cuando SP sube por encima de `sp`
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    node: Node,
+    sp: u16,
+}
+
+/// Acumula estados T por instrucción ejecutada
+pub struct Profiler {
+    symbols: SymbolTable,
+    /// Rutina de cada dirección: su símbolo o la etiqueta anterior
+    routines: Vec<Option<u16>>,
+    tstates: Vec<u64>,
+    counts: Vec<u64>,
+    /// (slot primario, subslot, banco) → (estados T, instrucciones)
+    banks: BTreeMap<(u8, u8, u16), (u64, u64)>,
+    /// Entrada del manejador → (estados T, interrupciones)
+    handlers: BTreeMap<u16, (u64, u64)>,
+    stacks: HashMap<Vec<Node>, u64>,
+    frames: Vec<Frame>,
+    root: Option<u16>,
+    total: u64,
+    first_frame: Option<u64>,
+    last_frame: u64,
+    /// En pausa (false) no se acumula nada
+    pub active: bool,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            symbols: SymbolTable::new(),
+            routines: vec![None; 0x10000],
+            tstates: vec![0; 0x10000],
+            counts: vec![0; 0x10000],
+            banks: BTreeMap::new(),
+            handlers: BTreeMap::new(),
+            stacks: HashMap::new(),
+            frames: Vec::new(),
+            root: None,
+            total: 0,
+            first_frame: None,
+            last_frame: 0,
+            active: true,
+        }
+    }
+
+    /// Símbolos para agrupar por rutina: las etiquetas globales abarcan
+    /// hasta la siguiente (las locales "RUTINA.bucle" no cortan); las
+    /// entradas de la BIOS solo su dirección
+    pub fn symbols(mut self, symbols: SymbolTable) -> Self {
+        let labels: Vec<u16> = symbols
+            .iter()
+            .filter(|(_, s)| s.kind == SymbolKind::Label && !s.name.contains('.'))
+            .map(|(a, _)| a)
+            .collect();
+        for (i, &start) in labels.iter().enumerate() {
+            let end = labels.get(i + 1).map(|&a| a as usize).unwrap_or(0x10000);
+            self.routines[start as usize..end].fill(Some(start));
+        }
+        for (address, symbol) in symbols.iter() {
+            if symbol.kind == SymbolKind::Bios {
+                self.routines[address as usize] = Some(address);
+            }
+        }
+        self.symbols = symbols;
+        self
+    }
+
+    /// Acumular un paso: `entry` con los registros de antes, `after` los
+    /// de después, el banco del PC y el cuadro en curso
+    pub fn record(&mut self, entry: &TraceEntry, after: &Registers, bank: Option<(u8, u8, u16)>, frame: u64) {
+        if !self.active {
+            return;
+        }
+        self.first_frame.get_or_insert(frame);
+        self.last_frame = frame;
+        let t = entry.tstates as u64;
+        let pc = entry.pc as usize;
+        self.total += t;
+        self.tstates[pc] += t;
+        self.counts[pc] += 1;
+        if let Some(key) = bank {
+            let slot = self.banks.entry(key).or_default();
+            slot.0 += t;
+            slot.1 += 1;
+        }
+        // Fuera de toda llamada la raíz es la rutina en curso
+        if self.frames.is_empty() {
+            self.root = self.routines[pc];
+        }
+
+        // La aceptación de una interrupción ya cuenta para su manejador
+        if entry.interrupt {
+            self.handlers.entry(after.pc).or_default().1 += 1;
+            self.push(Node::Interrupt(after.pc), after.sp);
+            self.charge(t);
+            return;
+        }
+        self.charge(t);
+        // Cerrar las llamadas cuyo retorno ya se sacó de la pila
+        while self.frames.last().is_some_and(|f| f.sp < after.sp) {
+            self.frames.pop();
+        }
+        if after.sp == entry.regs.sp.wrapping_sub(2) {
+            let instruction = decode(entry.opcode(), entry.pc);
+            let next = entry.pc.wrapping_add(instruction.bytes.len() as u16);
+            if matches!(instruction.flow, Flow::Call | Flow::ConditionalCall) && after.pc != next {
+                self.push(Node::Call(after.pc), after.sp);
+            }
+        }
+    }
+
+    fn push(&mut self, node: Node, sp: u16) {
+        if self.frames.len() < MAX_DEPTH {
+            self.frames.push(Frame { node, sp });
+        }
+    }
+
+    /// Apuntar estados T a la pila actual y al manejador más interno
+    fn charge(&mut self, t: u64) {
+        let mut key = Vec::with_capacity(self.frames.len() + 1);
+        key.push(Node::Root(self.root));
+        key.extend(self.frames.iter().map(|f| f.node));
+        match self.stacks.get_mut(&key) {
+            Some(total) => *total += t,
+            None => {
+                self.stacks.insert(key, t);
+            }
+        }
+        if let Some(handler) = self.frames.iter().rev().find_map(|f| match f.node {
+            Node::Interrupt(address) => Some(address),
+            _ => None,
+        }) {
+            self.handlers.entry(handler).or_default().0 += t;
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Cuadros completos durante la medida
+    pub fn frames(&self) -> u64 {
+        self.first_frame.map(|first| self.last_frame - first).unwrap_or(0)
+    }
+
+    pub fn clear(&mut self) {
+        self.tstates.fill(0);
+        self.counts.fill(0);
+        self.banks.clear();
+        self.handlers.clear();
+        self.stacks.clear();
+        self.frames.clear();
+        self.root = None;
+        self.total = 0;
+        self.first_frame = None;
+    }
+
+    fn routine_name(&self, address: Option<u16>) -> String {
+        match address {
+            Some(address) => self.symbols.name(address).map(str::to_string).unwrap_or_else(|| hex16(address)),
+            None => UNKNOWN.to_string(),
+        }
+    }
+
+    /// Direcciones ejecutadas, de la más costosa a la menos
+    pub fn by_address(&self) -> Vec<Hotspot> {
+        let rows = (0..0x10000usize).filter(|&a| self.counts[a] > 0).map(|a| Hotspot {
+            label: self.symbols.describe(a as u16).unwrap_or_default(),
+            address: Some(a as u16),
+            tstates: self.tstates[a],
+            count: self.counts[a],
+        });
+        sorted(rows.collect())
+    }
+
+    /// Estados T por rutina (etiqueta anterior más cercana)
+    pub fn by_symbol(&self) -> Vec<Hotspot> {
+        let mut routines: BTreeMap<Option<u16>, (u64, u64)> = BTreeMap::new();
+        for a in (0..0x10000usize).filter(|&a| self.counts[a] > 0) {
+            let row = routines.entry(self.routines[a]).or_default();
+            row.0 += self.tstates[a];
+            row.1 += self.counts[a];
+        }
+        let rows = routines.into_iter().map(|(address, (tstates, count))| Hotspot {
+            label: self.routine_name(address),
+            address,
+            tstates,
+            count,
+        });
+        sorted(rows.collect())
+    }
+
+    /// Estados T por banco de ROM, como "1-0:5"
+    pub fn by_bank(&self) -> Vec<Hotspot> {
+        let rows = self.banks.iter().map(|(&(primary, secondary, bank), &(tstates, count))| Hotspot {
+            label: format!("{}-{}:{}", primary, secondary, bank),
+            address: None,
+            tstates,
+            count,
+        });
+        sorted(rows.collect())
+    }
+
+    /// Manejadores de interrupción con el tiempo total dentro de ellos,
+    /// incluidas sus llamadas; `count` son las interrupciones atendidas
+    pub fn by_handler(&self) -> Vec<Hotspot> {
+        let rows = self.handlers.iter().map(|(&address, &(tstates, count))| Hotspot {
+            label: self.symbols.describe(address).unwrap_or_else(|| hex16(address)),
+            address: Some(address),
+            tstates,
+            count,
+        });
+        sorted(rows.collect())
+    }
+
+    /// Pilas plegadas ("RAIZ;RUTINA;INT:0038h 1234"), una por línea y
+    /// ordenadas, para flamegraph.pl, inferno o speedscope
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .map(|(stack, tstates)| {
+                let names: Vec<String> = stack.iter().map(|&node| self.node_name(node)).collect();
+                format!("{} {}", names.join(";"), tstates)
+            })
+            .collect();
+        lines.sort();
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    fn node_name(&self, node: Node) -> String {
+        let name = match node {
+            Node::Root(address) => self.routine_name(address),
+            Node::Call(address) => self.symbols.describe(address).unwrap_or_else(|| hex16(address)),
+            Node::Interrupt(address) => format!("INT:{}", self.symbols.describe(address).unwrap_or_else(|| hex16(address))),
+        };
+        // ';' separa los nodos y el último espacio el total
+        name.replace([';', ' '], "_")
+    }
+
+    /// Informe de texto con las `top` primeras filas de cada tabla
+    pub fn report(&self, top: usize) -> String {
+        let frames = self.frames();
+        let mut out = format!("Perfil: {} estados T en {} cuadros", self.total, frames);
+        if let Some(per_frame) = self.total.checked_div(frames) {
+            out.push_str(&format!(" ({} por cuadro)", per_frame));
+        }
+        out.push('\n');
+        let sections = [
+            ("DIRECCIONES", self.by_address()),
+            ("RUTINAS", self.by_symbol()),
+            ("BANCOS", self.by_bank()),
+            ("INTERRUPCIONES", self.by_handler()),
+        ];
+        for (title, rows) in sections {
+            if rows.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n{}\n", title));
+            for row in rows.iter().take(top) {
+                let address = row.address.map(hex16).unwrap_or_default();
+                out.push_str(&format!(
+                    "{:>12} {:>6.2}% {:>10}  {:<7} {}\n",
+                    row.tstates,
+                    percent(row.tstates, self.total),
+                    row.count,
+                    address,
+                    row.label
+                ));
+            }
+        }
+        out
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Más estados T primero; a igualdad, por dirección y nombre
+fn sorted(mut rows: Vec<Hotspot>) -> Vec<Hotspot> {
+    rows.sort_by(|a, b| b.tstates.cmp(&a.tstates).then(a.address.cmp(&b.address)).then(a.label.cmp(&b.label)));
+    rows
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
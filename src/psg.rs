@@ -0,0 +1,138 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  PSG AY-3-8910 (puertos A0h-A2h)                               ║
+//! ║  Banco de registros y puertos de propósito general:            ║
+//! ║  - R14: entrada del puerto de joystick seleccionado            ║
+//! ║  - R15: pines de salida 6/7/8 y selección de puerto            ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::joystick::{PortDevice, PIN6_OUT, PIN7_OUT, PIN8_OUT};
+
+pub const PORT_ADDRESS: u8 = 0xA0;
+pub const PORT_WRITE: u8 = 0xA1;
+pub const PORT_READ: u8 = 0xA2;
+
+/// Bits útiles de cada registro (el resto se lee a 0)
+const REGISTER_MASKS: [u8; 16] = [
+    0xFF, 0x0F, 0xFF, 0x0F, 0xFF, 0x0F, 0x1F, 0xFF,
+    0x1F, 0x1F, 0x1F, 0xFF, 0xFF, 0x0F, 0xFF, 0xFF,
+];
+
+pub struct Psg {
+    address: u8,
+    registers: [u8; 16],
+    /// Dispositivos conectados a los puertos 1 (A) y 2 (B)
+    pub ports: [PortDevice; 2],
+    /// Bit 6 de R14: 1 = teclado JIS (también en equipos no japoneses)
+    pub jis_layout: bool,
+    /// Bit 7 de R14: nivel de la entrada de cassette
+    pub cassette_input: bool,
+}
+
+impl Psg {
+    pub fn new() -> Psg {
+        Psg {
+            address: 0,
+            registers: [0; 16],
+            ports: [PortDevice::Joystick(Default::default()), PortDevice::Empty],
+            jis_layout: true,
+            cassette_input: false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.address = 0;
+        self.registers = [0; 16];
+        self.update_output_pins();
+    }
+
+    pub fn read(&mut self, port: u8) -> u8 {
+        match port {
+            PORT_READ => self.read_register(self.address),
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, port: u8, value: u8) {
+        match port {
+            PORT_ADDRESS => self.address = value & 0x0F,
+            PORT_WRITE => self.write_register(self.address, value),
+            _ => {}
+        }
+    }
+
+    pub fn read_register(&mut self, reg: u8) -> u8 {
+        match reg & 0x0F {
+            14 => self.read_joystick_port(),
+            r => self.registers[r as usize],
+        }
+    }
+
+    pub fn write_register(&mut self, reg: u8, value: u8) {
+        let reg = (reg & 0x0F) as usize;
+        self.registers[reg] = value & REGISTER_MASKS[reg];
+
+        if reg == 7 || reg == 15 {
+            self.update_output_pins();
+        }
+    }
+
+    /// Valor presente en el puerto B del PSG. Mientras R7 lo deja como
+    /// entrada (bit 7 a 0) las resistencias de pull-up lo mantienen a 1.
+    pub fn output_pins(&self) -> u8 {
+        if self.registers[7] & 0x80 != 0 {
+            self.registers[15]
+        } else {
+            0xFF
+        }
+    }
+
+    /// Puerto de joystick elegido por el bit 6 de R15 (0 = puerto 1)
+    pub fn selected_port(&self) -> usize {
+        ((self.output_pins() >> 6) & 0x01) as usize
+    }
+
+    /// El LED de KANA/CODE se enciende con el bit 7 de R15 a 0
+    pub fn kana_led_on(&self) -> bool {
+        self.output_pins() & 0x80 == 0
+    }
+
+    pub fn controller_mut(&mut self, port: usize) -> Option<&mut PortDevice> {
+        self.ports.get_mut(port)
+    }
+
+    fn update_output_pins(&mut self) {
+        let value = self.output_pins();
+        let pins_a = (value & 0x03) | if value & 0x10 != 0 { PIN8_OUT } else { 0 };
+        let pins_b = ((value >> 2) & 0x03) | if value & 0x20 != 0 { PIN8_OUT } else { 0 };
+        self.ports[0].write(pins_a);
+        self.ports[1].write(pins_b);
+    }
+
+    fn read_joystick_port(&mut self) -> u8 {
+        let selected = self.selected_port();
+        let mut value = self.ports[selected].read() & 0x3F;
+
+        // Los pines 6 y 7 son colector abierto: leen 0 si la salida está a 0
+        let outputs = self.output_pins() >> (selected * 2);
+        if outputs & PIN6_OUT == 0 {
+            value &= !0x10;
+        }
+        if outputs & PIN7_OUT == 0 {
+            value &= !0x20;
+        }
+
+        if self.jis_layout {
+            value |= 0x40;
+        }
+        if self.cassette_input {
+            value |= 0x80;
+        }
+        value
+    }
+}
+
+impl Default for Psg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,62 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  CABECERA DE CARTUCHO ROM                                      ║
+//! ║  "AB" + INIT + STATEMENT + DEVICE + TEXT + 6 bytes reservados  ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+/// Tamaño de la cabecera de un cartucho
+pub const ROM_HEADER_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RomHeader {
+    /// Rutina de arranque (0 si no hay)
+    pub init: u16,
+    /// Manejador de CALL de BASIC
+    pub statement: u16,
+    /// Manejador de dispositivos
+    pub device: u16,
+    /// Programa BASIC en la ROM
+    pub text: u16,
+}
+
+impl RomHeader {
+    pub fn parse(data: &[u8]) -> Option<RomHeader> {
+        if data.len() < ROM_HEADER_SIZE || !data.starts_with(b"AB") {
+            return None;
+        }
+        let word = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        Some(RomHeader {
+            init: word(2),
+            statement: word(4),
+            device: word(6),
+            text: word(8),
+        })
+    }
+
+    /// Cabecera en el formato del cartucho
+    pub fn to_bytes(&self) -> [u8; ROM_HEADER_SIZE] {
+        let mut bytes = [0u8; ROM_HEADER_SIZE];
+        bytes[..2].copy_from_slice(b"AB");
+        for (i, word) in [self.init, self.statement, self.device, self.text].into_iter().enumerate() {
+            bytes[2 + i * 2..4 + i * 2].copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Página en la que se mapea la ROM: 8000h si es de 16 KB o menos y
+    /// arranca ahí; 4000h en otro caso
+    pub fn origin(&self, len: usize) -> u16 {
+        if len <= 0x4000 && (0x8000..0xC000).contains(&self.init) {
+            0x8000
+        } else {
+            0x4000
+        }
+    }
+
+    /// Punteros con nombre distintos de cero, en orden de cabecera
+    pub fn entries(&self) -> Vec<(u16, &'static str)> {
+        [(self.init, "INIT"), (self.statement, "STATEMENT"), (self.device, "DEVICE")]
+            .into_iter()
+            .filter(|&(address, _)| address != 0)
+            .collect()
+    }
+}
@@ -0,0 +1,422 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  RELOJ DE TIEMPO REAL RICOH RP5C01 (puertos B4h/B5h)           ║
+//! ║  - Bloque 0: hora y fecha    - Bloque 1: alarma y 12/24h       ║
+//! ║  - Bloques 2 y 3: RAM CMOS con los ajustes del usuario         ║
+//! ║  - Fuente de tiempo inyectable para tests deterministas        ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+pub const PORT_ADDRESS: u8 = 0xB4;
+pub const PORT_DATA: u8 = 0xB5;
+
+/// Registros por bloque (el 13, 14 y 15 son comunes a todos)
+pub const BLOCK_REGISTERS: usize = 13;
+
+/// Tamaño del volcado de la RAM CMOS: 4 bloques, modo y desfase horario
+pub const CMOS_BLOB_SIZE: usize = 4 * BLOCK_REGISTERS + 1 + 8;
+
+/// El año del RP5C01 en un MSX cuenta desde 1980
+const BASE_YEAR: i64 = 1980;
+
+/// Bits implementados de cada registro, por bloque
+const REGISTER_MASKS: [[u8; BLOCK_REGISTERS]; 4] = [
+    [0x0F, 0x07, 0x0F, 0x07, 0x0F, 0x03, 0x07, 0x0F, 0x03, 0x0F, 0x01, 0x0F, 0x0F],
+    [0x00, 0x00, 0x0F, 0x07, 0x0F, 0x03, 0x07, 0x0F, 0x03, 0x00, 0x01, 0x03, 0x00],
+    [0x0F; BLOCK_REGISTERS],
+    [0x0F; BLOCK_REGISTERS],
+];
+
+// ═══════════════════════════════════════════════════════════════
+// FUENTES DE TIEMPO
+// ═══════════════════════════════════════════════════════════════
+
+/// Fuente de segundos desde la época Unix que hace avanzar el reloj
+pub trait TimeSource {
+    fn now(&self) -> i64;
+}
+
+/// Reloj del sistema anfitrión (`Date.now()` en wasm)
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    #[cfg(target_arch = "wasm32")]
+    fn now(&self) -> i64 {
+        (js_sys::Date::now() / 1000.0) as i64
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> i64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    }
+}
+
+/// Reloj manual compartido: los clones ven y mueven el mismo instante
+#[derive(Clone)]
+pub struct ManualClock {
+    seconds: Rc<Cell<i64>>,
+}
+
+impl ManualClock {
+    pub fn new(unix_seconds: i64) -> ManualClock {
+        ManualClock {
+            seconds: Rc::new(Cell::new(unix_seconds)),
+        }
+    }
+
+    pub fn set(&self, unix_seconds: i64) {
+        self.seconds.set(unix_seconds);
+    }
+
+    pub fn advance(&self, seconds: i64) {
+        self.seconds.set(self.seconds.get() + seconds);
+    }
+}
+
+impl TimeSource for ManualClock {
+    fn now(&self) -> i64 {
+        self.seconds.get()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// FECHA Y HORA
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RtcDateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl RtcDateTime {
+    pub fn from_unix(seconds: i64) -> RtcDateTime {
+        let days = seconds.div_euclid(86400);
+        let secs = seconds.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        RtcDateTime {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (secs / 3600) as u8,
+            minute: (secs / 60 % 60) as u8,
+            second: (secs % 60) as u8,
+        }
+    }
+
+    pub fn to_unix(&self) -> i64 {
+        let days = days_from_civil(self.year as i64, self.month as i64, self.day as i64);
+        days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+
+    /// Día de la semana con 0 = domingo, como lo cuenta el BIOS
+    pub fn weekday(&self) -> u8 {
+        let days = days_from_civil(self.year as i64, self.month as i64, self.day as i64);
+        (days + 4).rem_euclid(7) as u8
+    }
+}
+
+/// Días desde 1970-01-01 (algoritmo de H. Hinnant)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    days_from_civil(year + month / 12, month % 12 + 1, 1) - days_from_civil(year, month, 1)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// CHIP RP5C01
+// ═══════════════════════════════════════════════════════════════
+
+pub struct Rp5c01 {
+    registers: [[u8; BLOCK_REGISTERS]; 4],
+    mode: u8,
+    address: u8,
+    clock: Box<dyn TimeSource>,
+    last_sync: i64,
+}
+
+impl Rp5c01 {
+    /// Crear el chip con la hora actual de la fuente, como una pila recién puesta en hora
+    pub fn new(clock: Box<dyn TimeSource>) -> Rp5c01 {
+        let now = clock.now();
+        let mut rtc = Rp5c01 {
+            registers: [[0; BLOCK_REGISTERS]; 4],
+            // Timer activo, bloque 0
+            mode: 0x08,
+            address: 0,
+            clock,
+            last_sync: now,
+        };
+        // Modo 24 horas
+        rtc.registers[1][10] = 0x01;
+        rtc.set_datetime(RtcDateTime::from_unix(now));
+        rtc
+    }
+
+    /// Cambiar la fuente de tiempo sin perder lo que ya contó el reloj
+    pub fn set_time_source(&mut self, clock: Box<dyn TimeSource>) {
+        self.sync();
+        self.last_sync = clock.now();
+        self.clock = clock;
+    }
+
+    pub fn read(&mut self, port: u8) -> u8 {
+        match port {
+            PORT_DATA => 0xF0 | self.read_register(self.address),
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, port: u8, value: u8) {
+        match port {
+            PORT_ADDRESS => self.address = value & 0x0F,
+            PORT_DATA => self.write_register(self.address, value),
+            _ => {}
+        }
+    }
+
+    /// Leer un registro del bloque activo (solo los 4 bits bajos son válidos)
+    pub fn read_register(&mut self, reg: u8) -> u8 {
+        match reg & 0x0F {
+            13 => self.mode,
+            // Test y reset son de solo escritura
+            14 | 15 => 0x0F,
+            r => {
+                let block = self.block();
+                if block == 0 {
+                    self.sync();
+                }
+                self.registers[block][r as usize]
+            }
+        }
+    }
+
+    pub fn write_register(&mut self, reg: u8, value: u8) {
+        let value = value & 0x0F;
+        match reg & 0x0F {
+            13 => {
+                // Al parar o arrancar el timer se consolida el tiempo transcurrido
+                self.sync();
+                self.mode = value;
+            }
+            14 => {}
+            15 => {
+                if value & 0x01 != 0 {
+                    self.sync();
+                    for reg in 2..=8 {
+                        self.registers[1][reg] = 0;
+                    }
+                }
+            }
+            r => {
+                let block = self.block();
+                if block == 0 {
+                    self.sync();
+                }
+                self.registers[block][r as usize] = value & REGISTER_MASKS[block][r as usize];
+            }
+        }
+    }
+
+    pub fn block(&self) -> usize {
+        (self.mode & 0x03) as usize
+    }
+
+    pub fn timer_enabled(&self) -> bool {
+        self.mode & 0x08 != 0
+    }
+
+    pub fn alarm_enabled(&self) -> bool {
+        self.mode & 0x04 != 0
+    }
+
+    /// Salida de alarma: minuto, hora, día y día de la semana coinciden
+    /// (los campos de alarma a 0 no se comparan, como en el chip)
+    pub fn alarm_active(&mut self) -> bool {
+        if !self.alarm_enabled() {
+            return false;
+        }
+        self.sync();
+        let alarm = &self.registers[1];
+        let time = &self.registers[0];
+        let fields: [&[usize]; 4] = [&[2, 3], &[4, 5], &[6], &[7, 8]];
+        fields.iter().all(|regs| {
+            let set = regs.iter().any(|&r| alarm[r] != 0);
+            !set || regs.iter().all(|&r| alarm[r] == time[r])
+        })
+    }
+
+    /// Fecha y hora actuales según los contadores del bloque 0
+    pub fn datetime(&mut self) -> RtcDateTime {
+        self.sync();
+        self.registers_to_datetime()
+    }
+
+    /// Poner el reloj en hora (equivale a SET DATE y SET TIME)
+    pub fn set_datetime(&mut self, dt: RtcDateTime) {
+        self.sync();
+        self.datetime_to_registers(dt);
+        self.registers[0][6] = dt.weekday();
+    }
+
+    /// Exportar la memoria respaldada por pila.
+    ///
+    /// Formato: 52 nibbles (bloques 0-3, 13 registros cada uno), el
+    /// registro de modo y el desfase en segundos entre el reloj del MSX y la
+    /// fuente de tiempo (i64 little-endian), de modo que una hora puesta a
+    /// mano desde BASIC sobreviva entre sesiones.
+    pub fn export_cmos(&mut self) -> Vec<u8> {
+        self.sync();
+        let mut blob = Vec::with_capacity(CMOS_BLOB_SIZE);
+        for block in &self.registers {
+            blob.extend_from_slice(block);
+        }
+        blob.push(self.mode);
+        let offset = self.registers_to_datetime().to_unix() - self.clock.now();
+        blob.extend_from_slice(&offset.to_le_bytes());
+        blob
+    }
+
+    /// Restaurar un volcado creado con `export_cmos`
+    pub fn import_cmos(&mut self, blob: &[u8]) -> Result<(), String> {
+        if blob.len() != CMOS_BLOB_SIZE {
+            return Err(format!(
+                "Tamaño de RAM CMOS inválido ({} bytes, se esperaban {})",
+                blob.len(),
+                CMOS_BLOB_SIZE
+            ));
+        }
+
+        for (block, chunk) in blob.chunks(BLOCK_REGISTERS).take(4).enumerate() {
+            for (reg, &value) in chunk.iter().enumerate() {
+                self.registers[block][reg] = value & REGISTER_MASKS[block][reg];
+            }
+        }
+        self.mode = blob[52] & 0x0F;
+
+        let mut offset = [0u8; 8];
+        offset.copy_from_slice(&blob[53..61]);
+        let now = self.clock.now();
+        self.last_sync = now;
+        let dt = RtcDateTime::from_unix(now + i64::from_le_bytes(offset));
+        self.datetime_to_registers(dt);
+        self.registers[0][6] = dt.weekday();
+        Ok(())
+    }
+
+    // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+    // FUNCIONES AUXILIARES
+    // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+    /// Avanzar los contadores con el tiempo transcurrido desde la última consulta
+    fn sync(&mut self) {
+        let now = self.clock.now();
+        let elapsed = now - self.last_sync;
+        self.last_sync = now;
+        if elapsed <= 0 || !self.timer_enabled() {
+            return;
+        }
+
+        let before = self.registers_to_datetime().to_unix();
+        let after = before + elapsed;
+        let days = after.div_euclid(86400) - before.div_euclid(86400);
+        let weekday = (self.registers[0][6] as i64 + days).rem_euclid(7) as u8;
+        self.datetime_to_registers(RtcDateTime::from_unix(after));
+        self.registers[0][6] = weekday;
+    }
+
+    fn is_24h(&self) -> bool {
+        self.registers[1][10] & 0x01 != 0
+    }
+
+    /// Leer los contadores BCD, saturando los valores fuera de rango
+    fn registers_to_datetime(&self) -> RtcDateTime {
+        let r = &self.registers[0];
+        let bcd = |units: usize, tens: usize| r[tens] as i64 * 10 + r[units] as i64;
+
+        let year = BASE_YEAR + bcd(11, 12);
+        let month = bcd(9, 10).clamp(1, 12);
+        let day = bcd(7, 8).clamp(1, days_in_month(year, month));
+        let hour = if self.is_24h() {
+            bcd(4, 5)
+        } else {
+            // En 12 horas el bit 1 de las decenas indica PM
+            let pm = r[5] & 0x02 != 0;
+            let h = (r[5] & 0x01) as i64 * 10 + r[4] as i64;
+            (h % 12) + if pm { 12 } else { 0 }
+        };
+
+        RtcDateTime {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: hour.clamp(0, 23) as u8,
+            minute: bcd(2, 3).clamp(0, 59) as u8,
+            second: bcd(0, 1).clamp(0, 59) as u8,
+        }
+    }
+
+    fn datetime_to_registers(&mut self, dt: RtcDateTime) {
+        let year = (dt.year as i64 - BASE_YEAR).rem_euclid(100) as u8;
+        let hour = if self.is_24h() {
+            dt.hour
+        } else {
+            let h12 = match dt.hour % 12 {
+                0 => 12,
+                h => h,
+            };
+            h12 + if dt.hour >= 12 { 20 } else { 0 }
+        };
+
+        let r = &mut self.registers[0];
+        r[0] = dt.second % 10;
+        r[1] = dt.second / 10;
+        r[2] = dt.minute % 10;
+        r[3] = dt.minute / 10;
+        r[4] = hour % 10;
+        r[5] = hour / 10;
+        r[7] = dt.day % 10;
+        r[8] = dt.day / 10;
+        r[9] = dt.month % 10;
+        r[10] = dt.month / 10;
+        r[11] = year % 10;
+        r[12] = year / 10;
+        // Contador de años bisiestos (0 = bisiesto)
+        self.registers[1][11] = year & 0x03;
+    }
+}
+
+impl Default for Rp5c01 {
+    fn default() -> Self {
+        Self::new(Box::new(SystemClock))
+    }
+}
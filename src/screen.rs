@@ -0,0 +1,148 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  RENDERIZADO DE MODOS DE PANTALLA                              ║
+//! ║  - SCREEN 2: patrones + colores del TMS9918 (256x192)          ║
+//! ║  - SCREEN 5: 4bpp con paleta (256x212)                         ║
+//! ║  - SCREEN 8: 8bpp GRB 3-3-2 sin paleta (256x212)               ║
+//! ║  - Hojas de sprites de 16x16                                   ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+/// Tamaños de las tablas de SCREEN 2 en VRAM
+pub const SC2_PATTERNS: usize = 0x1800;
+pub const SC2_NAMES: usize = 0x1800;
+pub const SC2_COLORS: usize = 0x2000;
+/// Bitmap completo de SCREEN 5 (128 bytes x 212 líneas) y de SCREEN 8
+pub const SC5_BITMAP: usize = 0x6A00;
+pub const SC8_BITMAP: usize = 0xD400;
+
+/// Paleta fija del TMS9918 (la que tiene el V9938 tras el reset)
+pub const TMS9918_PALETTE: [[u8; 4]; 16] = [
+    [0, 0, 0, 255],       // Transparente (se ve el fondo negro)
+    [0, 0, 0, 255],       // Negro
+    [33, 200, 66, 255],   // Verde
+    [94, 220, 120, 255],  // Verde claro
+    [84, 85, 237, 255],   // Azul oscuro
+    [125, 118, 252, 255], // Azul claro
+    [212, 82, 77, 255],   // Rojo oscuro
+    [66, 235, 245, 255],  // Cian
+    [252, 85, 84, 255],   // Rojo
+    [255, 121, 120, 255], // Rojo claro
+    [212, 193, 84, 255],  // Amarillo oscuro
+    [230, 206, 128, 255], // Amarillo claro
+    [33, 176, 59, 255],   // Verde oscuro
+    [201, 91, 186, 255],  // Magenta
+    [204, 204, 204, 255], // Gris
+    [255, 255, 255, 255], // Blanco
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenMode {
+    Screen2,
+    Screen5,
+    Screen8,
+}
+
+impl ScreenMode {
+    pub fn from_number(number: u8) -> Option<ScreenMode> {
+        match number {
+            2 => Some(ScreenMode::Screen2),
+            5 => Some(ScreenMode::Screen5),
+            8 => Some(ScreenMode::Screen8),
+            _ => None,
+        }
+    }
+
+    pub fn number(&self) -> u8 {
+        match self {
+            ScreenMode::Screen2 => 2,
+            ScreenMode::Screen5 => 5,
+            ScreenMode::Screen8 => 8,
+        }
+    }
+
+    /// Modo más probable para un bloque de VRAM de ese tamaño
+    pub fn guess(len: usize) -> Option<ScreenMode> {
+        match len {
+            SC2_PATTERNS | 0x3800 | 0x4000 => Some(ScreenMode::Screen2),
+            SC5_BITMAP..=0x8000 => Some(ScreenMode::Screen5),
+            SC8_BITMAP..=0x10000 => Some(ScreenMode::Screen8),
+            _ => None,
+        }
+    }
+}
+
+/// SCREEN 2 desde un volcado de VRAM que empieza en 0000h. Sin tabla de
+/// nombres cada tercio muestra sus 256 patrones en orden; sin tabla de
+/// colores se pinta blanco sobre negro
+pub fn render_screen2(vram: &[u8], palette: &[[u8; 4]; 16]) -> Vec<u8> {
+    let has_names = vram.len() >= SC2_NAMES + 0x300;
+    let has_colors = vram.len() >= SC2_COLORS + SC2_PATTERNS;
+    let mut rgba = Vec::with_capacity(256 * 192 * 4);
+
+    for y in 0..192 {
+        let (row, line) = (y / 8, y % 8);
+        for column in 0..32 {
+            let cell = row * 32 + column;
+            let name = if has_names { vram[SC2_NAMES + cell] as usize } else { cell & 0xFF };
+            let offset = (row / 8) * 0x800 + name * 8 + line;
+            let pattern = vram.get(offset).copied().unwrap_or(0);
+            let color = if has_colors { vram[SC2_COLORS + offset] } else { 0xF1 };
+
+            for bit in (0..8).rev() {
+                let index = if pattern & (1 << bit) != 0 { color >> 4 } else { color & 0x0F };
+                rgba.extend_from_slice(&palette[index as usize]);
+            }
+        }
+    }
+    rgba
+}
+
+/// SCREEN 5: dos píxeles por byte, el nibble alto a la izquierda
+pub fn render_screen5(bitmap: &[u8], palette: &[[u8; 4]; 16]) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(bitmap.len() * 8);
+    for &byte in bitmap {
+        rgba.extend_from_slice(&palette[(byte >> 4) as usize]);
+        rgba.extend_from_slice(&palette[(byte & 0x0F) as usize]);
+    }
+    rgba
+}
+
+/// SCREEN 8: cada byte es un píxel GGGRRRBB; el azul de 2 bits se lleva a
+/// los niveles 0, 2, 4 y 7 como hace el V9938
+pub fn render_screen8(bitmap: &[u8]) -> Vec<u8> {
+    const BLUE: [u32; 4] = [0, 2, 4, 7];
+    let level = |value: u32| (value * 255 / 7) as u8;
+
+    let mut rgba = Vec::with_capacity(bitmap.len() * 4);
+    for &byte in bitmap {
+        let byte = byte as u32;
+        rgba.extend_from_slice(&[level((byte >> 2) & 7), level(byte >> 5), level(BLUE[(byte & 3) as usize]), 255]);
+    }
+    rgba
+}
+
+/// Patrones de sprites de 16x16 (cuatro cuadrantes de 8x8 en el orden
+/// del VDP: arriba-izquierda, abajo-izquierda, arriba-derecha y
+/// abajo-derecha), dieciséis por fila y en blanco sobre negro
+pub fn render_sprites(patterns: &[u8]) -> Vec<u8> {
+    let count = patterns.len().div_ceil(32);
+    let height = count.div_ceil(16) * 16;
+    let mut rgba = vec![0u8; 256 * height * 4];
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+
+    for (index, sprite) in patterns.chunks(32).enumerate() {
+        let (left, top) = ((index % 16) * 16, (index / 16) * 16);
+        for (i, &byte) in sprite.iter().enumerate() {
+            let x = left + (i / 16) * 8;
+            let y = top + i % 16;
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    let at = (y * 256 + x + bit) * 4;
+                    rgba[at..at + 3].fill(255);
+                }
+            }
+        }
+    }
+    rgba
+}
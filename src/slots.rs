@@ -0,0 +1,198 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  SISTEMA DE SLOTS DEL MSX2                                     ║
+//! ║  - 4 slots primarios (puerto A8h del PPI)                      ║
+//! ║  - Slots expandidos con registro secundario en FFFFh           ║
+//! ║  - Contenido por subslot: RAM, ROM, MegaROM o Disk-ROM         ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::mapper::{MapperType, PageMap, PAGE_SIZE};
+
+/// Contenido de un subslot
+#[derive(Default)]
+pub enum SlotContent {
+    #[default]
+    Empty,
+    /// RAM plana de 64 KB
+    Ram(Vec<u8>),
+    /// ROM lineal que empieza en la dirección `base`
+    Rom { data: Vec<u8>, base: u16 },
+    /// ROM con mapper: bancos de 8 KB que cambian al escribir en sus
+    /// registros
+    MegaRom { data: Vec<u8>, mapper: MapperType, pages: PageMap },
+    /// ROM del controlador de disco (16 KB en 4000h-7FFFh); los registros
+    /// del FDC los atiende el bus
+    DiskRom(Vec<u8>),
+}
+
+impl SlotContent {
+    pub fn ram() -> SlotContent {
+        SlotContent::Ram(vec![0; 0x10000])
+    }
+
+    /// Cartucho con mapper, con los bancos del reset
+    pub fn megarom(data: Vec<u8>, mapper: MapperType) -> SlotContent {
+        let pages = mapper.initial_pages(data.len(), 0x4000);
+        SlotContent::MegaRom { data, mapper, pages }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SlotContent::Empty => "empty",
+            SlotContent::Ram(_) => "ram",
+            SlotContent::Rom { .. } => "rom",
+            SlotContent::MegaRom { .. } => "megarom",
+            SlotContent::DiskRom(_) => "diskrom",
+        }
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match self {
+            SlotContent::Empty => 0xFF,
+            SlotContent::Ram(ram) => ram[address as usize],
+            SlotContent::Rom { data, base } => address
+                .checked_sub(*base)
+                .and_then(|offset| data.get(offset as usize))
+                .copied()
+                .unwrap_or(0xFF),
+            SlotContent::MegaRom { data, pages, .. } => pages[address as usize / PAGE_SIZE]
+                .and_then(|bank| data.get(bank as usize * PAGE_SIZE + address as usize % PAGE_SIZE))
+                .copied()
+                .unwrap_or(0xFF),
+            SlotContent::DiskRom(rom) => match address {
+                0x4000..=0x7FFF => rom.get(address as usize - 0x4000).copied().unwrap_or(0xFF),
+                _ => 0xFF,
+            },
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match self {
+            SlotContent::Ram(ram) => ram[address as usize] = value,
+            SlotContent::MegaRom { data, mapper, pages } => {
+                mapper.switch(pages, data.len(), address, Some(value));
+            }
+            _ => {}
+        }
+    }
+
+    /// Posición en la ROM del byte visible en una dirección (None en RAM
+    /// o fuera de la ROM)
+    pub fn rom_offset(&self, address: u16) -> Option<usize> {
+        let offset = match self {
+            SlotContent::Rom { base, .. } => address.checked_sub(*base)? as usize,
+            SlotContent::MegaRom { pages, .. } => {
+                pages[address as usize / PAGE_SIZE]? as usize * PAGE_SIZE + address as usize % PAGE_SIZE
+            }
+            SlotContent::DiskRom(_) if (0x4000..=0x7FFF).contains(&address) => address as usize - 0x4000,
+            _ => return None,
+        };
+        (offset < self.rom_size()).then_some(offset)
+    }
+
+    /// Bytes de la ROM (0 en RAM o vacío)
+    pub fn rom_size(&self) -> usize {
+        match self {
+            SlotContent::Rom { data, .. } | SlotContent::MegaRom { data, .. } | SlotContent::DiskRom(data) => data.len(),
+            _ => 0,
+        }
+    }
+
+    /// Banco de 8 KB de la ROM visible en una dirección
+    pub fn bank(&self, address: u16) -> Option<u16> {
+        self.rom_offset(address).map(|offset| (offset / PAGE_SIZE) as u16)
+    }
+}
+
+/// Slot primario y subslot visibles en una página
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlotId {
+    pub primary: u8,
+    pub secondary: u8,
+}
+
+pub struct SlotSystem {
+    slots: [[SlotContent; 4]; 4],
+    expanded: [bool; 4],
+    secondary_registers: [u8; 4],
+}
+
+impl SlotSystem {
+    pub fn new() -> SlotSystem {
+        SlotSystem {
+            slots: Default::default(),
+            expanded: [false; 4],
+            secondary_registers: [0; 4],
+        }
+    }
+
+    pub fn set_expanded(&mut self, primary: u8, expanded: bool) {
+        self.expanded[(primary & 3) as usize] = expanded;
+    }
+
+    pub fn is_expanded(&self, primary: u8) -> bool {
+        self.expanded[(primary & 3) as usize]
+    }
+
+    pub fn secondary_register(&self, primary: u8) -> u8 {
+        self.secondary_registers[(primary & 3) as usize]
+    }
+
+    pub fn insert(&mut self, slot: SlotId, content: SlotContent) {
+        self.slots[(slot.primary & 3) as usize][(slot.secondary & 3) as usize] = content;
+    }
+
+    pub fn content(&self, slot: SlotId) -> &SlotContent {
+        &self.slots[(slot.primary & 3) as usize][(slot.secondary & 3) as usize]
+    }
+
+    pub fn content_mut(&mut self, slot: SlotId) -> &mut SlotContent {
+        &mut self.slots[(slot.primary & 3) as usize][(slot.secondary & 3) as usize]
+    }
+
+    /// Slot visible en una dirección según el registro de slot primario
+    pub fn resolve(&self, address: u16, primary_register: u8) -> SlotId {
+        let page = address >> 14;
+        let primary = (primary_register >> (page * 2)) & 3;
+        let secondary = if self.expanded[primary as usize] {
+            (self.secondary_registers[primary as usize] >> (page * 2)) & 3
+        } else {
+            0
+        };
+        SlotId { primary, secondary }
+    }
+
+    /// Slot primario de la página 3, cuyo FFFFh es el registro secundario
+    fn page3_expanded(&self, primary_register: u8) -> Option<usize> {
+        let primary = (primary_register >> 6) as usize & 3;
+        self.expanded[primary].then_some(primary)
+    }
+
+    /// Lectura de FFFFh en un slot expandido: registro secundario invertido
+    pub fn read_secondary_register(&self, address: u16, primary_register: u8) -> Option<u8> {
+        if address != 0xFFFF {
+            return None;
+        }
+        self.page3_expanded(primary_register)
+            .map(|primary| !self.secondary_registers[primary])
+    }
+
+    /// Escritura en FFFFh de un slot expandido; devuelve true si se consumió
+    pub fn write_secondary_register(&mut self, address: u16, value: u8, primary_register: u8) -> bool {
+        if address != 0xFFFF {
+            return false;
+        }
+        match self.page3_expanded(primary_register) {
+            Some(primary) => {
+                self.secondary_registers[primary] = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for SlotSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,432 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  SÍMBOLOS DEL MSX                                              ║
+//! ║  - Puntos de entrada de la BIOS (tabla de saltos 0000h-017Fh)  ║
+//! ║  - Variables de sistema y ganchos en F380h-FFFFh               ║
+//! ║  - Puertos de E/S por nombre                                   ║
+//! ║  - Ficheros de símbolos de sjasm, NoICE y openMSX              ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use std::collections::BTreeMap;
+
+use crate::asm::parse_number;
+use crate::disasm::hex16;
+
+/// Tabla de saltos de la BIOS principal (MSX1, MSX2 y MSX2+)
+pub const BIOS_ENTRIES: &[(u16, &str)] = &[
+    (0x0000, "CHKRAM"),
+    (0x0008, "SYNCHR"),
+    (0x000C, "RDSLT"),
+    (0x0010, "CHRGTR"),
+    (0x0014, "WRSLT"),
+    (0x0018, "OUTDO"),
+    (0x001C, "CALSLT"),
+    (0x0020, "DCOMPR"),
+    (0x0024, "ENASLT"),
+    (0x0028, "GETYPR"),
+    (0x0030, "CALLF"),
+    (0x0038, "KEYINT"),
+    (0x003B, "INITIO"),
+    (0x003E, "INIFNK"),
+    (0x0041, "DISSCR"),
+    (0x0044, "ENASCR"),
+    (0x0047, "WRTVDP"),
+    (0x004A, "RDVRM"),
+    (0x004D, "WRTVRM"),
+    (0x0050, "SETRD"),
+    (0x0053, "SETWRT"),
+    (0x0056, "FILVRM"),
+    (0x0059, "LDIRMV"),
+    (0x005C, "LDIRVM"),
+    (0x005F, "CHGMOD"),
+    (0x0062, "CHGCLR"),
+    (0x0066, "NMI"),
+    (0x0069, "CLRSPR"),
+    (0x006C, "INITXT"),
+    (0x006F, "INIT32"),
+    (0x0072, "INIGRP"),
+    (0x0075, "INIMLT"),
+    (0x0078, "SETTXT"),
+    (0x007B, "SETT32"),
+    (0x007E, "SETGRP"),
+    (0x0081, "SETMLT"),
+    (0x0084, "CALPAT"),
+    (0x0087, "CALATR"),
+    (0x008A, "GSPSIZ"),
+    (0x008D, "GRPPRT"),
+    (0x0090, "GICINI"),
+    (0x0093, "WRTPSG"),
+    (0x0096, "RDPSG"),
+    (0x0099, "STRTMS"),
+    (0x009C, "CHSNS"),
+    (0x009F, "CHGET"),
+    (0x00A2, "CHPUT"),
+    (0x00A5, "LPTOUT"),
+    (0x00A8, "LPTSTT"),
+    (0x00AB, "CNVCHR"),
+    (0x00AE, "PINLIN"),
+    (0x00B1, "INLIN"),
+    (0x00B4, "QINLIN"),
+    (0x00B7, "BREAKX"),
+    (0x00BA, "ISCNTC"),
+    (0x00BD, "CKCNTC"),
+    (0x00C0, "BEEP"),
+    (0x00C3, "CLS"),
+    (0x00C6, "POSIT"),
+    (0x00C9, "FNKSB"),
+    (0x00CC, "ERAFNK"),
+    (0x00CF, "DSPFNK"),
+    (0x00D2, "TOTEXT"),
+    (0x00D5, "GTSTCK"),
+    (0x00D8, "GTTRIG"),
+    (0x00DB, "GTPAD"),
+    (0x00DE, "GTPDL"),
+    (0x00E1, "TAPION"),
+    (0x00E4, "TAPIN"),
+    (0x00E7, "TAPIOF"),
+    (0x00EA, "TAPOON"),
+    (0x00ED, "TAPOUT"),
+    (0x00F0, "TAPOOF"),
+    (0x00F3, "STMOTR"),
+    (0x00F6, "LFTQ"),
+    (0x00F9, "PUTQ"),
+    (0x00FC, "RIGHTC"),
+    (0x00FF, "LEFTC"),
+    (0x0102, "UPC"),
+    (0x0105, "TUPC"),
+    (0x0108, "DOWNC"),
+    (0x010B, "TDOWNC"),
+    (0x010E, "SCALXY"),
+    (0x0111, "MAPXY"),
+    (0x0114, "FETCHC"),
+    (0x0117, "STOREC"),
+    (0x011A, "SETATR"),
+    (0x011D, "READC"),
+    (0x0120, "SETC"),
+    (0x0123, "NSETCX"),
+    (0x0126, "GTASPC"),
+    (0x0129, "PNTINI"),
+    (0x012C, "SCANR"),
+    (0x012F, "SCANL"),
+    (0x0132, "CHGCAP"),
+    (0x0135, "CHGSND"),
+    (0x0138, "RSLREG"),
+    (0x013B, "WSLREG"),
+    (0x013E, "RDVDP"),
+    (0x0141, "SNSMAT"),
+    (0x0144, "PHYDIO"),
+    (0x0147, "FORMAT"),
+    (0x014A, "ISFLIO"),
+    (0x014D, "OUTDLP"),
+    (0x0150, "GETVCP"),
+    (0x0153, "GETVC2"),
+    (0x0156, "KILBUF"),
+    (0x0159, "CALBAS"),
+    (0x015C, "SUBROM"),
+    (0x015F, "EXTROM"),
+    (0x0162, "CHKSLZ"),
+    (0x0165, "CHKNEW"),
+    (0x0168, "EOL"),
+    (0x016B, "BIGFIL"),
+    (0x016E, "NSETRD"),
+    (0x0171, "NSTWRT"),
+    (0x0174, "NRDVRM"),
+    (0x0177, "NWRVRM"),
+    (0x017A, "RDRES"),
+    (0x017D, "WRRES"),
+];
+
+/// Variables de sistema y ganchos más usados (página 3)
+pub const SYSTEM_VARIABLES: &[(u16, &str)] = &[
+    (0xF380, "RDPRIM"),
+    (0xF385, "WRPRIM"),
+    (0xF38C, "CLPRIM"),
+    (0xF3AE, "LINL40"),
+    (0xF3AF, "LINL32"),
+    (0xF3B0, "LINLEN"),
+    (0xF3B1, "CRTCNT"),
+    (0xF3B3, "TXTNAM"),
+    (0xF3B5, "TXTCOL"),
+    (0xF3B7, "TXTCGP"),
+    (0xF3BD, "T32NAM"),
+    (0xF3BF, "T32COL"),
+    (0xF3C1, "T32CGP"),
+    (0xF3C3, "T32ATR"),
+    (0xF3C5, "T32PAT"),
+    (0xF3C7, "GRPNAM"),
+    (0xF3C9, "GRPCOL"),
+    (0xF3CB, "GRPCGP"),
+    (0xF3CD, "GRPATR"),
+    (0xF3CF, "GRPPAT"),
+    (0xF3DB, "CLIKSW"),
+    (0xF3DC, "CSRY"),
+    (0xF3DD, "CSRX"),
+    (0xF3DE, "CNSDFG"),
+    (0xF3DF, "RG0SAV"),
+    (0xF3E0, "RG1SAV"),
+    (0xF3E1, "RG2SAV"),
+    (0xF3E2, "RG3SAV"),
+    (0xF3E3, "RG4SAV"),
+    (0xF3E4, "RG5SAV"),
+    (0xF3E5, "RG6SAV"),
+    (0xF3E6, "RG7SAV"),
+    (0xF3E7, "STATFL"),
+    (0xF3E8, "TRGFLG"),
+    (0xF3E9, "FORCLR"),
+    (0xF3EA, "BAKCLR"),
+    (0xF3EB, "BDRCLR"),
+    (0xF3F2, "ATRBYT"),
+    (0xF3F6, "SCNCNT"),
+    (0xF3F7, "REPCNT"),
+    (0xF3F8, "PUTPNT"),
+    (0xF3FA, "GETPNT"),
+    (0xF41F, "KBUF"),
+    (0xF55E, "BUF"),
+    (0xF676, "TXTTAB"),
+    (0xF6C2, "VARTAB"),
+    (0xF6C4, "ARYTAB"),
+    (0xF6C6, "STREND"),
+    (0xFAF5, "DPPAGE"),
+    (0xFAF6, "ACPAGE"),
+    (0xFAF8, "EXBRSA"),
+    (0xFAFC, "MODE"),
+    (0xFBB0, "ENSTOP"),
+    (0xFBB1, "BASROM"),
+    (0xFBDA, "OLDKEY"),
+    (0xFBE5, "NEWKEY"),
+    (0xFC48, "BOTTOM"),
+    (0xFC4A, "HIMEM"),
+    (0xFC9B, "INTFLG"),
+    (0xFC9E, "JIFFY"),
+    (0xFCA2, "INTCNT"),
+    (0xFCAB, "CAPST"),
+    (0xFCAC, "KANAST"),
+    (0xFCAF, "SCRMOD"),
+    (0xFCB0, "OLDSCR"),
+    (0xFCC1, "EXPTBL"),
+    (0xFCC5, "SLTTBL"),
+    (0xFCC9, "SLTATR"),
+    (0xFD09, "SLTWRK"),
+    (0xFD89, "PROCNM"),
+    (0xFD99, "DEVICE"),
+    (0xFD9A, "H.KEYI"),
+    (0xFD9F, "H.TIMI"),
+    (0xFDA4, "H.CHPU"),
+    (0xFEDA, "H.STKE"),
+    (0xFFE7, "RG8SAV"),
+    (0xFFE8, "RG9SAV"),
+    (0xFFE9, "RG10SA"),
+    (0xFFEA, "RG11SA"),
+    (0xFFEB, "RG12SA"),
+    (0xFFEC, "RG13SA"),
+    (0xFFED, "RG14SA"),
+    (0xFFEE, "RG15SA"),
+    (0xFFEF, "RG16SA"),
+    (0xFFF0, "RG17SA"),
+    (0xFFF1, "RG18SA"),
+    (0xFFF2, "RG19SA"),
+    (0xFFF3, "RG20SA"),
+    (0xFFF4, "RG21SA"),
+    (0xFFF5, "RG22SA"),
+    (0xFFF6, "RG23SA"),
+    (0xFFFA, "RG25SA"),
+    (0xFFFB, "RG26SA"),
+    (0xFFFC, "RG27SA"),
+];
+
+/// Puertos de E/S estándar del MSX2
+pub const IO_PORTS: &[(u8, &str)] = &[
+    (0x7C, "OPLL_ADDR"),
+    (0x7D, "OPLL_DATA"),
+    (0x90, "PRN_STATUS"),
+    (0x91, "PRN_DATA"),
+    (0x98, "VDP_DATA"),
+    (0x99, "VDP_CTRL"),
+    (0x9A, "VDP_PALETTE"),
+    (0x9B, "VDP_INDIRECT"),
+    (0xA0, "PSG_ADDR"),
+    (0xA1, "PSG_WRITE"),
+    (0xA2, "PSG_READ"),
+    (0xA8, "PPI_SLOT"),
+    (0xA9, "PPI_KEYS"),
+    (0xAA, "PPI_CTRL_C"),
+    (0xAB, "PPI_MODE"),
+    (0xB4, "RTC_ADDR"),
+    (0xB5, "RTC_DATA"),
+    (0xF5, "SYS_CTRL"),
+    (0xFC, "MAPPER_P0"),
+    (0xFD, "MAPPER_P1"),
+    (0xFE, "MAPPER_P2"),
+    (0xFF, "MAPPER_P3"),
+];
+
+/// Nombre de un puerto de E/S conocido
+pub fn port_name(port: u8) -> Option<&'static str> {
+    IO_PORTS.iter().find(|&&(p, _)| p == port).map(|&(_, name)| name)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// Rutina de la BIOS: solo nombra destinos de salto y accesos a memoria
+    Bios,
+    /// Variable de sistema: también nombra constantes de 16 bits
+    Variable,
+    /// Etiqueta del programa o importada
+    Label,
+}
+
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+}
+
+/// Direcciones con nombre; una por dirección, la última gana
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    symbols: BTreeMap<u16, Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    /// BIOS y variables de sistema del MSX
+    pub fn msx() -> SymbolTable {
+        let mut table = SymbolTable::new();
+        for &(address, name) in BIOS_ENTRIES {
+            table.insert(address, name, SymbolKind::Bios);
+        }
+        for &(address, name) in SYSTEM_VARIABLES {
+            table.insert(address, name, SymbolKind::Variable);
+        }
+        table
+    }
+
+    pub fn insert(&mut self, address: u16, name: &str, kind: SymbolKind) {
+        self.symbols.insert(address, Symbol { name: name.to_string(), kind });
+    }
+
+    pub fn get(&self, address: u16) -> Option<&Symbol> {
+        self.symbols.get(&address)
+    }
+
+    pub fn name(&self, address: u16) -> Option<&str> {
+        self.get(address).map(|s| s.name.as_str())
+    }
+
+    /// Dirección de un símbolo (sin distinguir mayúsculas)
+    pub fn address_of(&self, name: &str) -> Option<u16> {
+        self.symbols
+            .iter()
+            .find(|(_, s)| s.name.eq_ignore_ascii_case(name))
+            .map(|(&address, _)| address)
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &Symbol)> {
+        self.symbols.iter().map(|(&address, symbol)| (address, symbol))
+    }
+
+    /// Nombre de una dirección, o "ETIQUETA+n" si cae poco después de una
+    /// etiqueta del programa (las de la BIOS y del sistema solo exactas)
+    pub fn describe(&self, address: u16) -> Option<String> {
+        if let Some(name) = self.name(address) {
+            return Some(name.to_string());
+        }
+        let (&start, symbol) = self.symbols.range(address.saturating_sub(MAX_LABEL_OFFSET)..address).next_back()?;
+        (symbol.kind == SymbolKind::Label).then(|| format!("{}+{}", symbol.name, address - start))
+    }
+
+    /// Añadir como etiquetas los símbolos de un fichero en cualquiera de
+    /// los formatos de `SymbolFormat`; devuelve cuántos se leyeron
+    pub fn import(&mut self, text: &str) -> usize {
+        let symbols = parse_symbol_file(text);
+        for (name, address) in &symbols {
+            self.insert(*address, name, SymbolKind::Label);
+        }
+        symbols.len()
+    }
+
+    pub fn export(&self, format: SymbolFormat) -> String {
+        export_symbols(self.iter().map(|(address, symbol)| (symbol.name.as_str(), address)), format)
+    }
+}
+
+/// Distancia máxima a la que `describe` usa "ETIQUETA+n"
+const MAX_LABEL_OFFSET: u16 = 0x100;
+
+// ═══════════════════════════════════════════════════════════════
+// FICHEROS DE SÍMBOLOS
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolFormat {
+    /// sjasm/sjasmplus: "INICIO: EQU 0x00004010"
+    Sjasm,
+    /// NoICE: "DEF INICIO 4010H"
+    NoIce,
+    /// Formato genérico del depurador de openMSX: "INICIO: equ 4010h"
+    OpenMsx,
+}
+
+impl SymbolFormat {
+    pub fn from_name(name: &str) -> Option<SymbolFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "sjasm" | "sjasmplus" | "sym" => Some(SymbolFormat::Sjasm),
+            "noice" => Some(SymbolFormat::NoIce),
+            "openmsx" | "generic" => Some(SymbolFormat::OpenMsx),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SymbolFormat::Sjasm => "sjasm",
+            SymbolFormat::NoIce => "noice",
+            SymbolFormat::OpenMsx => "openmsx",
+        }
+    }
+}
+
+/// Un símbolo por línea en el formato pedido
+pub fn export_symbols<'a>(symbols: impl Iterator<Item = (&'a str, u16)>, format: SymbolFormat) -> String {
+    symbols
+        .map(|(name, address)| match format {
+            SymbolFormat::Sjasm => format!("{}: EQU 0x{:08X}\n", name, address),
+            SymbolFormat::NoIce => format!("DEF {} {:04X}H\n", name, address),
+            SymbolFormat::OpenMsx => format!("{}: equ {}\n", name, hex16(address)),
+        })
+        .collect()
+}
+
+/// Leer un fichero de símbolos: "NOMBRE: EQU valor", "NOMBRE EQU valor",
+/// "NOMBRE = valor" o "DEF NOMBRE valor", con el valor en cualquiera de
+/// las notaciones de los ensambladores (0x, $, #, sufijo h). Las líneas
+/// que no encajan, como los comentarios, se saltan
+pub fn parse_symbol_file(text: &str) -> Vec<(String, u16)> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.split(';').next().unwrap_or("");
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let (name, value) = match words[..] {
+                [def, name, value] if def.eq_ignore_ascii_case("DEF") => (name, value),
+                [name, equ, value] if equ.eq_ignore_ascii_case("EQU") || equ == "=" => (name, value),
+                _ => return None,
+            };
+            let name = name.strip_suffix(':').unwrap_or(name);
+            let value = match value.strip_prefix(['$', '#']) {
+                Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+                None => parse_number(value).ok()?,
+            };
+            Some((name.to_string(), value as u16))
+        })
+        .collect()
+}
@@ -0,0 +1,383 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  SEÑAL DE CINTA FSK (CAS ↔ WAV)                                ║
+//! ║  - 1200 baudios: "0" = 1 ciclo a 1200 Hz, "1" = 2 a 2400 Hz    ║
+//! ║  - 2400 baudios: frecuencias dobles                            ║
+//! ║  - Byte: bit de inicio 0, 8 bits (LSB primero), 2 de parada 1  ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::cas::{file_header_type, CasImage};
+
+/// Frecuencia de muestreo del WAV generado (múltiplo de 1200, 2400 y 4800 Hz)
+pub const SAMPLE_RATE: u32 = 43200;
+
+/// Ciclos del tono de sincronía a 1200 baudios (a 2400 se duplican)
+pub const LONG_HEADER_CYCLES: usize = 16000;
+pub const SHORT_HEADER_CYCLES: usize = 4000;
+
+/// Semiciclos cortos seguidos que el decodificador acepta como tono de sincronía
+const MIN_HEADER_HALVES: usize = 400;
+
+/// Tras los bits de parada, más unos seguidos que esto indican el tono del bloque siguiente
+const MAX_IDLE_HALVES: usize = 16;
+
+const AMPLITUDE: f64 = 100.0;
+
+// ═══════════════════════════════════════════════════════════════
+// CODIFICADOR
+// ═══════════════════════════════════════════════════════════════
+
+/// Generar el WAV (PCM 8 bits mono) de todos los bloques de la cinta
+pub fn encode_wav(image: &CasImage, baud: u32) -> Result<Vec<u8>, String> {
+    if baud != 1200 && baud != 2400 {
+        return Err(format!("Velocidad no soportada: {} baudios (1200 o 2400)", baud));
+    }
+    let scale = (baud / 1200) as usize;
+    let low = (SAMPLE_RATE / baud) as usize;
+    let high = low / 2;
+
+    let mut samples = Vec::new();
+    for block in image.blocks() {
+        // Las cabeceras de fichero llevan silencio y tono largos, los datos cortos
+        let (silence, cycles) = match file_header_type(&block.data) {
+            Some(_) => (SAMPLE_RATE as usize * 2, LONG_HEADER_CYCLES),
+            None => (SAMPLE_RATE as usize, SHORT_HEADER_CYCLES),
+        };
+        samples.resize(samples.len() + silence, 128);
+        push_cycles(&mut samples, high, cycles * scale);
+
+        for &byte in &block.data {
+            push_bit(&mut samples, low, false);
+            for bit in 0..8 {
+                push_bit(&mut samples, low, byte & (1 << bit) != 0);
+            }
+            push_bit(&mut samples, low, true);
+            push_bit(&mut samples, low, true);
+        }
+    }
+    samples.resize(samples.len() + SAMPLE_RATE as usize / 2, 128);
+
+    Ok(write_wav(SAMPLE_RATE, &samples))
+}
+
+fn push_bit(samples: &mut Vec<u8>, low: usize, one: bool) {
+    if one {
+        push_cycles(samples, low / 2, 2);
+    } else {
+        push_cycles(samples, low, 1);
+    }
+}
+
+fn push_cycles(samples: &mut Vec<u8>, period: usize, count: usize) {
+    for _ in 0..count {
+        for i in 0..period {
+            let phase = 2.0 * std::f64::consts::PI * i as f64 / period as f64;
+            samples.push((128.0 + AMPLITUDE * phase.sin()).round() as u8);
+        }
+    }
+}
+
+fn write_wav(sample_rate: u32, samples: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(44 + samples.len());
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + samples.len() as u32).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes()); // bytes por segundo
+    out.extend_from_slice(&1u16.to_le_bytes()); // alineación de bloque
+    out.extend_from_slice(&8u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+    out.extend_from_slice(samples);
+    out
+}
+
+// ═══════════════════════════════════════════════════════════════
+// LECTURA DE WAV
+// ═══════════════════════════════════════════════════════════════
+
+/// Leer un WAV PCM de 8 o 16 bits; devuelve la frecuencia y el primer canal en -1..1
+pub fn read_wav(data: &[u8]) -> Result<(u32, Vec<f32>), String> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err("No es un fichero WAV".to_string());
+    }
+
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let id = &data[pos..pos + 4];
+        let size = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+        // Un tamaño que desborda (wasm32) cuenta como fichero truncado
+        let end = (pos + 8).checked_add(size);
+        let body = &data[pos + 8..end.map_or(data.len(), |end| end.min(data.len()))];
+
+        if id == b"fmt " && body.len() >= 16 {
+            let word = |i: usize| u16::from_le_bytes([body[i], body[i + 1]]);
+            let code = word(0);
+            let channels = word(2).max(1) as usize;
+            let rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+            let bits = word(14);
+            // 0xFFFE (extensible) guarda el formato real más adelante; se acepta como PCM
+            if code != 1 && code != 0xFFFE {
+                return Err(format!("Formato WAV no soportado: {}", code));
+            }
+            if bits != 8 && bits != 16 {
+                return Err(format!("Profundidad no soportada: {} bits", bits));
+            }
+            format = Some((channels, rate, bits));
+        } else if id == b"data" {
+            let (channels, rate, bits) = format.ok_or("Bloque data sin bloque fmt")?;
+            let frame = channels * bits as usize / 8;
+            let samples = body
+                .chunks_exact(frame)
+                .map(|f| match bits {
+                    8 => (f[0] as f32 - 128.0) / 128.0,
+                    _ => i16::from_le_bytes([f[0], f[1]]) as f32 / 32768.0,
+                })
+                .collect();
+            return Ok((rate, samples));
+        }
+        match end.and_then(|end| end.checked_add(size & 1)) {
+            Some(next) => pos = next,
+            None => break,
+        }
+    }
+    Err("WAV sin bloque de datos".to_string())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// DECODIFICADOR
+// ═══════════════════════════════════════════════════════════════
+
+/// Resultado de decodificar un bloque de audio
+#[derive(Clone, Debug)]
+pub struct BlockReport {
+    pub index: usize,
+    /// Muestra donde empieza el tono de sincronía
+    pub sample_offset: usize,
+    pub bytes: usize,
+    /// Velocidad estimada a partir del tono de sincronía
+    pub baud: u32,
+    pub errors: Vec<String>,
+}
+
+impl BlockReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+pub struct TapeDecode {
+    pub image: CasImage,
+    pub reports: Vec<BlockReport>,
+}
+
+/// Semiciclo entre dos pasos por cero: posición y duración en muestras
+#[derive(Clone, Copy)]
+struct Half {
+    start: f64,
+    length: f64,
+}
+
+enum Bit {
+    Value(bool),
+    /// Silencio o fin de la señal
+    Gap,
+    /// Secuencia de pulsos que no forma un bit
+    Invalid,
+}
+
+/// Convertir una grabación WAV en bloques CAS
+pub fn decode_wav(data: &[u8]) -> Result<TapeDecode, String> {
+    let (rate, samples) = read_wav(data)?;
+    let halves = find_halves(&samples);
+
+    let mut blocks = Vec::new();
+    let mut reports = Vec::new();
+    let mut i = 0;
+
+    while let Some((header_start, body_start, short)) = find_header(&halves, i) {
+        let decoder = BlockDecoder {
+            halves: &halves,
+            threshold: short * 1.5,
+            gap: short * 6.0,
+        };
+        let (bytes, errors, next) = decoder.decode(body_start);
+        i = next;
+        if bytes.is_empty() && errors.is_empty() {
+            continue;
+        }
+        reports.push(BlockReport {
+            index: reports.len(),
+            sample_offset: halves[header_start].start as usize,
+            bytes: bytes.len(),
+            baud: (rate as f64 / (4.0 * short) / 1200.0).round() as u32 * 1200,
+            errors,
+        });
+        blocks.push(bytes);
+    }
+
+    if blocks.is_empty() {
+        return Err("No se encontró ningún bloque de cinta en el audio".to_string());
+    }
+    Ok(TapeDecode {
+        image: CasImage::from_blocks(blocks),
+        reports,
+    })
+}
+
+/// Pasos por cero con histéresis para ignorar el ruido del silencio
+fn find_halves(samples: &[f32]) -> Vec<Half> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let mean = samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len() as f64;
+    let peak = samples
+        .iter()
+        .map(|&s| (s as f64 - mean).abs())
+        .fold(0.0, f64::max);
+    let hysteresis = peak * 0.1;
+    if hysteresis == 0.0 {
+        return Vec::new();
+    }
+
+    let mut halves = Vec::new();
+    let mut positive: Option<bool> = None;
+    let mut last_cross: Option<f64> = None;
+    let mut previous = 0.0;
+
+    for (n, &s) in samples.iter().enumerate() {
+        let value = s as f64 - mean;
+        let level = if positive == Some(true) { -hysteresis } else { hysteresis };
+        let crossed = match positive {
+            None => value.abs() > hysteresis,
+            Some(true) => value < level,
+            Some(false) => value > level,
+        };
+        if crossed {
+            // Posición fraccionaria del cruce del umbral
+            let t = if positive.is_some() && value != previous {
+                n as f64 - 1.0 + (level - previous) / (value - previous)
+            } else {
+                n as f64
+            };
+            if let Some(start) = last_cross {
+                halves.push(Half { start, length: t - start });
+            }
+            last_cross = Some(t);
+            positive = Some(value > 0.0);
+        }
+        previous = value;
+    }
+    halves
+}
+
+/// Buscar un tono de sincronía; devuelve su inicio, el primer semiciclo
+/// de datos y la duración media del semiciclo corto
+fn find_header(halves: &[Half], from: usize) -> Option<(usize, usize, f64)> {
+    let mut run_start = from;
+    let mut mean = 0.0;
+    let mut count = 0;
+
+    for (j, half) in halves.iter().enumerate().skip(from) {
+        if count > 0 && (half.length - mean).abs() <= mean * 0.25 {
+            mean = (mean * count as f64 + half.length) / (count + 1) as f64;
+            count += 1;
+        } else if count >= MIN_HEADER_HALVES {
+            return Some((run_start, j, mean));
+        } else {
+            run_start = j;
+            mean = half.length;
+            count = 1;
+        }
+    }
+    None
+}
+
+struct BlockDecoder<'a> {
+    halves: &'a [Half],
+    /// Semiciclos más largos que esto son de un bit 0
+    threshold: f64,
+    /// Semiciclos más largos que esto son silencio
+    gap: f64,
+}
+
+impl BlockDecoder<'_> {
+    /// Decodificar bytes hasta un silencio o el siguiente tono de sincronía
+    fn decode(&self, mut k: usize) -> (Vec<u8>, Vec<String>, usize) {
+        let mut bytes = Vec::new();
+        let mut errors = Vec::new();
+
+        loop {
+            // Unos entre bytes (bits de parada); demasiados son ya otro tono
+            let idle_start = k;
+            while k < self.halves.len() && self.halves[k].length < self.threshold {
+                k += 1;
+                if k - idle_start > MAX_IDLE_HALVES {
+                    return (bytes, errors, idle_start);
+                }
+            }
+            if k >= self.halves.len() || self.halves[k].length > self.gap {
+                return (bytes, errors, k + 1);
+            }
+
+            match self.read_byte(&mut k) {
+                Ok(byte) => bytes.push(byte),
+                Err(Some(message)) => errors.push(format!("Byte {}: {}", bytes.len(), message)),
+                Err(None) => {
+                    errors.push(format!("Byte {}: señal interrumpida", bytes.len()));
+                    return (bytes, errors, k + 1);
+                }
+            }
+        }
+    }
+
+    /// Err(None) si la señal se corta; Err(Some) con el error de formato
+    fn read_byte(&self, k: &mut usize) -> Result<u8, Option<String>> {
+        let mut byte = 0u8;
+        for bit in 0..11 {
+            let value = match self.read_bit(k) {
+                Bit::Value(v) => v,
+                // El último semiciclo antes de un silencio no tiene cruce de cierre
+                Bit::Gap if bit >= 9 => return Ok(byte),
+                Bit::Gap => return Err(None),
+                Bit::Invalid => {
+                    *k += 1;
+                    return Err(Some("pulsos de duración incorrecta".to_string()));
+                }
+            };
+            match bit {
+                0 if value => return Err(Some("bit de inicio incorrecto".to_string())),
+                1..=8 if value => byte |= 1 << (bit - 1),
+                9 | 10 if !value => return Err(Some("bit de parada incorrecto".to_string())),
+                _ => {}
+            }
+        }
+        Ok(byte)
+    }
+
+    fn read_bit(&self, k: &mut usize) -> Bit {
+        let long = |h: &Half| h.length >= self.threshold;
+        let Some(first) = self.halves.get(*k) else {
+            return Bit::Gap;
+        };
+        if first.length > self.gap {
+            return Bit::Gap;
+        }
+        // Un 0 son dos semiciclos largos; un 1, cuatro cortos
+        let (count, zero) = if long(first) { (2, true) } else { (4, false) };
+        let Some(pulses) = self.halves.get(*k..*k + count) else {
+            return Bit::Gap;
+        };
+        if pulses.iter().any(|h| h.length > self.gap) {
+            return Bit::Gap;
+        }
+        if pulses.iter().any(|h| long(h) != zero) {
+            return Bit::Invalid;
+        }
+        *k += count;
+        Bit::Value(!zero)
+    }
+}
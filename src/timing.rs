@@ -0,0 +1,145 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  TIEMPOS DEL Z80                                               ║
+//! ║  - Estados T de cada instrucción según sus bytes               ║
+//! ║  - Saltos, llamadas, retornos y repeticiones: mínimo y máximo  ║
+//! ║  - Espera M1 del MSX: un estado T más por lectura de opcode    ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+/// Estados T de una instrucción: iguales salvo en las condicionales
+/// (sin y con salto) y en las de bloque (última vuelta y las demás)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timing {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Timing {
+    pub fn fixed(t: u32) -> Timing {
+        Timing { min: t, max: t }
+    }
+
+    pub fn new(min: u32, max: u32) -> Timing {
+        Timing { min, max }
+    }
+
+    /// "11" o, si varía, "17/10" (con salto/sin salto, como en los manuales)
+    pub fn text(&self) -> String {
+        if self.min == self.max {
+            self.min.to_string()
+        } else {
+            format!("{}/{}", self.max, self.min)
+        }
+    }
+}
+
+impl std::ops::Add for Timing {
+    type Output = Timing;
+
+    fn add(self, other: Timing) -> Timing {
+        Timing::new(self.min + other.min, self.max + other.max)
+    }
+}
+
+/// Estados T de la instrucción que empieza en `code[0]`, sin las esperas
+/// que añada la máquina
+pub fn timing(code: &[u8]) -> Timing {
+    match *code {
+        [0xCB, op, ..] => cb(op),
+        [0xED, op, ..] => ed(op),
+        [0xDD | 0xFD, 0xCB, _, op] => Timing::fixed(if op >> 6 == 1 { 20 } else { 23 }),
+        [0xDD | 0xFD, op, ..] => indexed(op),
+        [op, ..] => main(op),
+        [] => Timing::fixed(0),
+    }
+}
+
+/// Ciclos M1 de la instrucción: uno por prefijo y otro por el código.
+/// En DDCB y FDCB el último byte se lee como dato, así que son dos
+pub fn m1_cycles(code: &[u8]) -> u32 {
+    match *code {
+        [] => 0,
+        [0xCB | 0xED | 0xDD | 0xFD, ..] => 2,
+        _ => 1,
+    }
+}
+
+/// Estados T en un MSX, que añade una espera a cada ciclo M1
+pub fn msx_timing(code: &[u8]) -> Timing {
+    let m1 = m1_cycles(code);
+    timing(code) + Timing::fixed(m1)
+}
+
+fn main(op: u8) -> Timing {
+    let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+    let (p, q) = (y >> 1, y & 1);
+    let t = Timing::fixed;
+    match (x, z) {
+        (0, 0) => match y {
+            0 | 1 => t(4),
+            2 => Timing::new(8, 13),
+            3 => t(12),
+            _ => Timing::new(7, 12),
+        },
+        (0, 1) => t(if q == 0 { 10 } else { 11 }),
+        (0, 2) => t([7, 7, 16, 13][p as usize]),
+        (0, 3) => t(6),
+        (0, 4 | 5) => t(if y == 6 { 11 } else { 4 }),
+        (0, 6) => t(if y == 6 { 10 } else { 7 }),
+        (0, _) => t(4),
+        (1, _) if op == 0x76 => t(4),
+        (1, _) => t(if y == 6 || z == 6 { 7 } else { 4 }),
+        (2, _) => t(if z == 6 { 7 } else { 4 }),
+        (_, 0) => Timing::new(5, 11),
+        (_, 1) if q == 0 => t(10),
+        (_, 1) => t([10, 4, 4, 6][p as usize]),
+        (_, 2) => t(10),
+        (_, 3) => t([10, 8, 11, 11, 19, 4, 4, 4][y as usize]),
+        (_, 4) => Timing::new(10, 17),
+        (_, 5) => t(if q == 0 { 11 } else { 17 }),
+        (_, 6) => t(7),
+        _ => t(11),
+    }
+}
+
+/// Con DD o FD: (HL) pasa a (IX+d), que cuesta el desplazamiento y su
+/// suma; el resto paga solo el prefijo
+fn indexed(op: u8) -> Timing {
+    let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+    match (x, z) {
+        (0, 4 | 5) if y == 6 => Timing::fixed(23),
+        (0, 6) if y == 6 => Timing::fixed(19),
+        (1, _) if op != 0x76 && (y == 6 || z == 6) => Timing::fixed(19),
+        (2, 6) => Timing::fixed(19),
+        _ => {
+            let base = main(op);
+            Timing::new(base.min + 4, base.max + 4)
+        }
+    }
+}
+
+fn cb(op: u8) -> Timing {
+    let memory = op & 7 == 6;
+    Timing::fixed(match (op >> 6, memory) {
+        (1, true) => 12,
+        (_, true) => 15,
+        _ => 8,
+    })
+}
+
+fn ed(op: u8) -> Timing {
+    let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+    let t = Timing::fixed;
+    match (x, z) {
+        (1, 0 | 1) => t(12),
+        (1, 2) => t(15),
+        (1, 3) => t(20),
+        (1, 4) | (1, 6) => t(8),
+        (1, 5) => t(14),
+        (1, 7) if y < 4 => t(9),
+        (1, 7) if y < 6 => t(18),
+        (2, 0..=3) if y >= 6 => Timing::new(16, 21),
+        (2, 0..=3) if y >= 4 => t(16),
+        // ED sin instrucción: dos NOP
+        _ => t(8),
+    }
+}
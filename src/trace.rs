@@ -0,0 +1,400 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  TRAZA DE EJECUCIÓN                                            ║
+//! ║  - PC, opcode, registros, slots y ciclos de cada instrucción   ║
+//! ║  - Filtros por rango de direcciones y por slot                 ║
+//! ║  - Histórico circular de las últimas N instrucciones           ║
+//! ║  - Volcado a fichero de texto o binario para comparar corridas ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::disasm::{decode, hex16};
+use crate::slots::SlotId;
+use crate::symbols::SymbolTable;
+use crate::z80::Registers;
+use std::collections::VecDeque;
+use std::io::Write;
+
+/// Cabecera de la traza binaria, seguida de la versión
+pub const TRACE_MAGIC: &[u8; 8] = b"MSXTRACE";
+pub const TRACE_VERSION: u8 = 1;
+/// Bytes de cada instrucción en la traza binaria
+pub const RECORD_SIZE: usize = 46;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Una línea por instrucción, legible y comparable con diff
+    Text,
+    /// Registros de `RECORD_SIZE` bytes tras la cabecera
+    Binary,
+}
+
+impl TraceFormat {
+    pub fn from_name(name: &str) -> Option<TraceFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Some(TraceFormat::Text),
+            "binary" | "bin" => Some(TraceFormat::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// Una instrucción ejecutada (o una interrupción aceptada)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// Estados T desde el arranque, antes de la instrucción
+    pub cycles: u64,
+    pub pc: u16,
+    /// Estados T que tardó, incluida la espera M1
+    pub tstates: u32,
+    /// Registros antes de ejecutarla
+    pub regs: Registers,
+    /// Slot visible en cada página
+    pub slots: [SlotId; 4],
+    /// El paso fue aceptar una interrupción
+    pub interrupt: bool,
+    bytes: [u8; 4],
+    len: u8,
+}
+
+impl TraceEntry {
+    pub fn new(cycles: u64, regs: Registers, slots: [SlotId; 4], opcode: &[u8], tstates: u32, interrupt: bool) -> TraceEntry {
+        let len = opcode.len().min(4);
+        let mut bytes = [0; 4];
+        bytes[..len].copy_from_slice(&opcode[..len]);
+        TraceEntry { cycles, pc: regs.pc, tstates, regs, slots, interrupt, bytes, len: len as u8 }
+    }
+
+    /// Bytes de la instrucción; vacío en una interrupción
+    pub fn opcode(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    /// Slot de la página del PC
+    pub fn slot(&self) -> SlotId {
+        self.slots[(self.pc >> 14) as usize]
+    }
+
+    /// Línea de la traza: ciclos, PC, bytes, instrucción, registros,
+    /// flags, slots de las cuatro páginas y la etiqueta más cercana
+    pub fn text(&self, symbols: &SymbolTable) -> String {
+        let bytes: Vec<String> = self.opcode().iter().map(|b| format!("{:02X}", b)).collect();
+        let instruction = if self.interrupt {
+            "INT".to_string()
+        } else {
+            decode(self.opcode(), self.pc).text(symbols)
+        };
+        let r = &self.regs;
+        let slots: Vec<String> = self.slots.iter().map(|s| format!("{}-{}", s.primary, s.secondary)).collect();
+        let mut line = format!(
+            "{:>12} {:04X} {:<11} {:<18} AF={:04X} BC={:04X} DE={:04X} HL={:04X} IX={:04X} IY={:04X} SP={:04X} \
+             AF'={:04X} BC'={:04X} DE'={:04X} HL'={:04X} I={:02X} R={:02X} {} {}",
+            self.cycles,
+            self.pc,
+            bytes.join(" "),
+            instruction,
+            r.af(),
+            r.bc(),
+            r.de(),
+            r.hl(),
+            r.ix,
+            r.iy,
+            r.sp,
+            r.af2,
+            r.bc2,
+            r.de2,
+            r.hl2,
+            r.i,
+            r.r,
+            r.flags_text(),
+            slots.join(" ")
+        );
+        if let Some(name) = symbols.describe(self.pc) {
+            line.push_str(" ; ");
+            line.push_str(&name);
+        }
+        line
+    }
+
+    /// Registro binario de `RECORD_SIZE` bytes, en little endian
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let r = &self.regs;
+        let mut out = [0u8; RECORD_SIZE];
+        out[0..8].copy_from_slice(&self.cycles.to_le_bytes());
+        out[8..10].copy_from_slice(&self.pc.to_le_bytes());
+        out[10..12].copy_from_slice(&(self.tstates.min(0xFFFF) as u16).to_le_bytes());
+        out[12] = self.interrupt as u8 | (r.iff1 as u8) << 1 | (r.iff2 as u8) << 2 | (r.im & 3) << 3;
+        out[13] = self.len;
+        out[14..18].copy_from_slice(&self.bytes);
+        let words = [r.af(), r.bc(), r.de(), r.hl(), r.ix, r.iy, r.sp, r.af2, r.bc2, r.de2, r.hl2];
+        for (i, word) in words.iter().enumerate() {
+            out[18 + i * 2..20 + i * 2].copy_from_slice(&word.to_le_bytes());
+        }
+        out[40] = r.i;
+        out[41] = r.r;
+        for (i, slot) in self.slots.iter().enumerate() {
+            out[42 + i] = (slot.primary & 3) | (slot.secondary & 3) << 2;
+        }
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Option<TraceEntry> {
+        if data.len() < RECORD_SIZE || data[13] > 4 {
+            return None;
+        }
+        let word = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+        let mut regs = Registers {
+            pc: word(8),
+            ix: word(26),
+            iy: word(28),
+            sp: word(30),
+            af2: word(32),
+            bc2: word(34),
+            de2: word(36),
+            hl2: word(38),
+            i: data[40],
+            r: data[41],
+            iff1: data[12] & 0x02 != 0,
+            iff2: data[12] & 0x04 != 0,
+            im: (data[12] >> 3) & 3,
+            ..Registers::default()
+        };
+        regs.set_af(word(18));
+        regs.set_bc(word(20));
+        regs.set_de(word(22));
+        regs.set_hl(word(24));
+        let slot = |b: u8| SlotId { primary: b & 3, secondary: (b >> 2) & 3 };
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&data[14..18]);
+        Some(TraceEntry {
+            cycles: u64::from_le_bytes(data[0..8].try_into().ok()?),
+            pc: regs.pc,
+            tstates: word(10) as u32,
+            regs,
+            slots: [slot(data[42]), slot(data[43]), slot(data[44]), slot(data[45])],
+            interrupt: data[12] & 0x01 != 0,
+            bytes,
+            len: data[13],
+        })
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TRAZADOR
+// ═══════════════════════════════════════════════════════════════
+
+/// Recoge las instrucciones que pasan los filtros en un histórico
+/// circular y, si se pide, las vuelca a un fichero según se ejecutan
+pub struct Tracer {
+    capacity: usize,
+    history: VecDeque<TraceEntry>,
+    /// Rangos de PC (inclusivos); vacío = todos
+    ranges: Vec<(u16, u16)>,
+    /// Slots del PC; sin subslot vale cualquiera; vacío = todos
+    slots: Vec<(u8, Option<u8>)>,
+    symbols: SymbolTable,
+    output: Option<(Box<dyn Write>, TraceFormat)>,
+    header_written: bool,
+    error: Option<String>,
+    /// Instrucciones que pasaron los filtros
+    recorded: u64,
+    /// En pausa (false) no se apunta nada pero se conserva el histórico
+    pub active: bool,
+}
+
+impl Tracer {
+    /// Histórico de las últimas `capacity` instrucciones; con 0 solo se
+    /// vuelca al fichero
+    pub fn new(capacity: usize) -> Tracer {
+        Tracer {
+            capacity,
+            history: VecDeque::with_capacity(capacity.min(0x10000)),
+            ranges: Vec::new(),
+            slots: Vec::new(),
+            symbols: SymbolTable::new(),
+            output: None,
+            header_written: false,
+            error: None,
+            recorded: 0,
+            active: true,
+        }
+    }
+
+    /// Apuntar solo las instrucciones con el PC en `start..=end`; se
+    /// pueden añadir varios rangos
+    pub fn range(mut self, start: u16, end: u16) -> Self {
+        self.ranges.push((start.min(end), start.max(end)));
+        self
+    }
+
+    /// Apuntar solo el código que corre desde un slot; sin subslot vale
+    /// cualquiera del primario
+    pub fn slot(mut self, primary: u8, secondary: Option<u8>) -> Self {
+        self.slots.push((primary & 3, secondary.map(|s| s & 3)));
+        self
+    }
+
+    /// Etiquetas para las instrucciones y el comentario de cada línea
+    pub fn symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Volcar cada instrucción a `output` según se ejecuta
+    pub fn stream(mut self, output: Box<dyn Write>, format: TraceFormat) -> Self {
+        self.output = Some((output, format));
+        self.header_written = false;
+        self
+    }
+
+    /// La instrucción pasa los filtros de rango y de slot
+    pub fn accepts(&self, entry: &TraceEntry) -> bool {
+        let in_range = self.ranges.is_empty() || self.ranges.iter().any(|&(start, end)| (start..=end).contains(&entry.pc));
+        let slot = entry.slot();
+        let in_slot = self.slots.is_empty()
+            || self
+                .slots
+                .iter()
+                .any(|&(primary, secondary)| primary == slot.primary && secondary.is_none_or(|s| s == slot.secondary));
+        in_range && in_slot
+    }
+
+    /// Apuntar una instrucción si está activo y pasa los filtros
+    pub fn record(&mut self, entry: TraceEntry) {
+        if !self.active || !self.accepts(&entry) {
+            return;
+        }
+        self.recorded += 1;
+        if self.output.is_some() {
+            self.write(&entry);
+        }
+        if self.capacity > 0 {
+            if self.history.len() == self.capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back(entry);
+        }
+    }
+
+    fn write(&mut self, entry: &TraceEntry) {
+        let Some((output, format)) = self.output.as_mut() else {
+            return;
+        };
+        let result = match format {
+            TraceFormat::Text => writeln!(output, "{}", entry.text(&self.symbols)),
+            TraceFormat::Binary if !self.header_written => output
+                .write_all(TRACE_MAGIC)
+                .and_then(|_| output.write_all(&[TRACE_VERSION]))
+                .and_then(|_| output.write_all(&entry.to_bytes())),
+            TraceFormat::Binary => output.write_all(&entry.to_bytes()),
+        };
+        self.header_written = true;
+        // Tras un error se deja de volcar, pero el histórico sigue
+        if let Err(e) = result {
+            self.error = Some(format!("Error al escribir la traza: {}", e));
+            self.output = None;
+        }
+    }
+
+    /// Vaciar el búfer del fichero de salida
+    pub fn flush(&mut self) {
+        if let Some((output, _)) = self.output.as_mut() {
+            if let Err(e) = output.flush() {
+                self.error = Some(format!("Error al escribir la traza: {}", e));
+            }
+        }
+    }
+
+    /// Histórico, de la más antigua a la más reciente
+    pub fn history(&self) -> &VecDeque<TraceEntry> {
+        &self.history
+    }
+
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+
+    /// Primer error al volcar al fichero
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.recorded = 0;
+    }
+
+    /// Histórico como texto, una línea por instrucción
+    pub fn text(&self) -> String {
+        self.history.iter().map(|entry| entry.text(&self.symbols) + "\n").collect()
+    }
+
+    /// Histórico como traza binaria, con cabecera
+    pub fn binary(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(TRACE_MAGIC.len() + 1 + self.history.len() * RECORD_SIZE);
+        out.extend_from_slice(TRACE_MAGIC);
+        out.push(TRACE_VERSION);
+        for entry in &self.history {
+            out.extend_from_slice(&entry.to_bytes());
+        }
+        out
+    }
+}
+
+/// "3-0" (primario y subslot) o "1" (cualquier subslot del primario)
+pub(crate) fn parse_slot(text: &str) -> Result<(u8, Option<u8>), String> {
+    let digit = |t: &str| match t.trim().parse::<u8>() {
+        Ok(n) if n < 4 => Ok(n),
+        _ => Err(format!("Slot no válido: {}", text)),
+    };
+    match text.split_once('-') {
+        Some((primary, secondary)) => Ok((digit(primary)?, Some(digit(secondary)?))),
+        None => Ok((digit(text)?, None)),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// LECTURA Y COMPARACIÓN
+// ═══════════════════════════════════════════════════════════════
+
+/// Leer una traza binaria completa
+pub fn read_binary(data: &[u8]) -> Result<Vec<TraceEntry>, String> {
+    let body = data
+        .strip_prefix(TRACE_MAGIC.as_slice())
+        .ok_or_else(|| "No es una traza binaria".to_string())?;
+    match body.split_first() {
+        Some((&TRACE_VERSION, records)) => {
+            if !records.len().is_multiple_of(RECORD_SIZE) {
+                return Err(format!("Traza truncada: {} bytes sobrantes", records.len() % RECORD_SIZE));
+            }
+            records
+                .chunks(RECORD_SIZE)
+                .enumerate()
+                .map(|(i, record)| TraceEntry::from_bytes(record).ok_or_else(|| format!("Registro {} no válido", i)))
+                .collect()
+        }
+        Some((version, _)) => Err(format!("Versión de traza no soportada: {}", version)),
+        None => Err("Traza sin versión".to_string()),
+    }
+}
+
+/// Posición de la primera instrucción en la que difieren dos trazas; si
+/// una es prefijo de la otra, la longitud de la más corta
+pub fn first_difference(a: &[TraceEntry], b: &[TraceEntry]) -> Option<usize> {
+    match a.iter().zip(b).position(|(x, y)| x != y) {
+        Some(index) => Some(index),
+        None if a.len() != b.len() => Some(a.len().min(b.len())),
+        None => None,
+    }
+}
+
+/// Descripción de la primera diferencia entre dos trazas, para mostrar
+pub fn describe_difference(a: &[TraceEntry], b: &[TraceEntry], symbols: &SymbolTable) -> Option<String> {
+    let index = first_difference(a, b)?;
+    let line = |trace: &[TraceEntry]| {
+        trace
+            .get(index)
+            .map(|entry| entry.text(symbols))
+            .unwrap_or_else(|| "(fin de la traza)".to_string())
+    };
+    let at = a.get(index).or(b.get(index)).map(|entry| hex16(entry.pc)).unwrap_or_default();
+    Some(format!("Instrucción {} ({}):\n< {}\n> {}", index, at, line(a), line(b)))
+}
@@ -0,0 +1,167 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  VDP V9938 (MÍNIMO)                                            ║
+//! ║  - Puertos 98h (VRAM), 99h (control), 9Ah (paleta), 9Bh        ║
+//! ║  - 128 KB de VRAM con la dirección alta en R#14                ║
+//! ║  - Registros de estado e interrupción de fin de cuadro         ║
+//! ║  - Sin motor de comandos ni dibujo: lo justo para el depurador ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+pub const VRAM_SIZE: usize = 0x20000;
+/// Registros de control R#0 a R#46
+pub const VDP_REGISTERS: usize = 47;
+/// S#2 en reposo: transferencia lista y los bits que siempre valen 1
+const STATUS2_IDLE: u8 = 0x8C;
+
+pub struct V9938 {
+    pub vram: Vec<u8>,
+    pub registers: [u8; VDP_REGISTERS],
+    pub status: [u8; 10],
+    /// Paleta en el formato de los puertos: 0RRR0BBB en el byte alto y
+    /// 00000GGG en el bajo
+    pub palette: [u16; 16],
+    /// Dirección de VRAM (17 bits) para el siguiente acceso por 98h
+    pub address: u32,
+    /// Primer byte de una escritura en 99h
+    latch: Option<u8>,
+    palette_latch: Option<u8>,
+    /// Byte leído por adelantado para el siguiente IN de 98h
+    read_ahead: u8,
+    /// Última escritura en un registro, para quien la quiera observar
+    register_write: Option<(u8, u8)>,
+}
+
+impl V9938 {
+    pub fn new() -> V9938 {
+        let mut status = [0; 10];
+        status[2] = STATUS2_IDLE;
+        V9938 {
+            vram: vec![0; VRAM_SIZE],
+            registers: [0; VDP_REGISTERS],
+            status,
+            palette: [0; 16],
+            address: 0,
+            latch: None,
+            palette_latch: None,
+            read_ahead: 0,
+            register_write: None,
+        }
+    }
+
+    pub fn read(&mut self, port: u8) -> u8 {
+        match port & 3 {
+            0 => {
+                self.latch = None;
+                let value = self.read_ahead;
+                self.read_ahead = self.vram[self.address as usize];
+                self.advance();
+                value
+            }
+            1 => {
+                self.latch = None;
+                let pointer = (self.registers[15] & 0x0F) as usize;
+                let value = self.status.get(pointer).copied().unwrap_or(0xFF);
+                // Leer S#0 baja la interrupción de fin de cuadro
+                if pointer == 0 {
+                    self.status[0] &= 0x7F;
+                }
+                value
+            }
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, port: u8, value: u8) {
+        match port & 3 {
+            0 => {
+                self.latch = None;
+                self.vram[self.address as usize] = value;
+                self.read_ahead = value;
+                self.advance();
+            }
+            1 => match self.latch.take() {
+                None => self.latch = Some(value),
+                Some(data) if value & 0x80 != 0 => self.write_register(value & 0x3F, data),
+                Some(low) => {
+                    let high = (self.registers[14] as u32 & 7) << 14;
+                    self.address = high | ((value as u32 & 0x3F) << 8) | low as u32;
+                    if value & 0x40 == 0 {
+                        self.read_ahead = self.vram[self.address as usize];
+                        self.advance();
+                    }
+                }
+            },
+            2 => match self.palette_latch.take() {
+                None => self.palette_latch = Some(value),
+                Some(red_blue) => {
+                    let entry = (self.registers[16] & 0x0F) as usize;
+                    self.palette[entry] = u16::from_be_bytes([red_blue & 0x77, value & 0x07]);
+                    self.registers[16] = (entry as u8 + 1) & 0x0F;
+                }
+            },
+            _ => {
+                let pointer = self.registers[17];
+                self.write_register(pointer & 0x3F, value);
+                if pointer & 0x80 == 0 {
+                    self.registers[17] = (pointer & 0xC0) | ((pointer + 1) & 0x3F);
+                }
+            }
+        }
+    }
+
+    /// Escribir un registro de control como lo haría el programa
+    pub fn write_register(&mut self, register: u8, value: u8) {
+        let Some(slot) = self.registers.get_mut(register as usize) else { return };
+        *slot = value;
+        if register == 16 {
+            self.palette_latch = None;
+        }
+        self.register_write = Some((register, value));
+    }
+
+    /// Recoger la última escritura en un registro desde la anterior llamada
+    pub fn take_register_write(&mut self) -> Option<(u8, u8)> {
+        self.register_write.take()
+    }
+
+    /// Fin de cuadro: marca F en S#0
+    pub fn vblank(&mut self) {
+        self.status[0] |= 0x80;
+    }
+
+    /// Línea de interrupción: F en S#0 con IE0 activado en R#1
+    pub fn irq(&self) -> bool {
+        self.registers[1] & 0x20 != 0 && self.status[0] & 0x80 != 0
+    }
+
+    /// Número de SCREEN del BASIC según los bits M1-M5, o None si la
+    /// combinación no corresponde a ningún modo
+    pub fn screen_mode(&self) -> Option<u8> {
+        let m1 = (self.registers[1] >> 4) & 1;
+        let m2 = (self.registers[1] >> 3) & 1;
+        let m345 = (self.registers[0] >> 1) & 7;
+        match (m345, m2, m1) {
+            (0, 0, 1) | (2, 0, 1) => Some(0),
+            (0, 0, 0) => Some(1),
+            (1, 0, 0) => Some(2),
+            (0, 1, 0) => Some(3),
+            (2, 0, 0) => Some(4),
+            (3, 0, 0) => Some(5),
+            (4, 0, 0) => Some(6),
+            (5, 0, 0) => Some(7),
+            (7, 0, 0) => Some(8),
+            _ => None,
+        }
+    }
+
+    fn advance(&mut self) {
+        self.address = (self.address + 1) & (VRAM_SIZE as u32 - 1);
+        // Sin los modos de 16 KB, el acarreo pasa a R#14
+        self.registers[14] = (self.address >> 14) as u8 & 7;
+    }
+}
+
+impl Default for V9938 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
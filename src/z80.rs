@@ -0,0 +1,1092 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  CPU Z80                                                       ║
+//! ║  - Instrucciones documentadas y no documentadas                ║
+//! ║  - Flags completos, incluidos los bits 3 y 5                   ║
+//! ║  - Interrupciones en IM 0/1/2, NMI y HALT                      ║
+//! ║  - Estados T de las tablas de `timing` y espera M1 del MSX     ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::timing::{m1_cycles, timing};
+
+pub const FLAG_C: u8 = 0x01;
+pub const FLAG_N: u8 = 0x02;
+pub const FLAG_PV: u8 = 0x04;
+pub const FLAG_X: u8 = 0x08;
+pub const FLAG_H: u8 = 0x10;
+pub const FLAG_Y: u8 = 0x20;
+pub const FLAG_Z: u8 = 0x40;
+pub const FLAG_S: u8 = 0x80;
+
+/// Memoria y puertos que ve el Z80
+pub trait Z80Bus {
+    fn read(&mut self, address: u16) -> u8;
+    fn write(&mut self, address: u16, value: u8);
+    /// El puerto lleva en el byte alto A o B, según la instrucción
+    fn input(&mut self, port: u16) -> u8;
+    fn output(&mut self, port: u16, value: u8);
+    /// Lectura del código: opcode, desplazamiento o dato inmediato
+    fn fetch(&mut self, address: u16) -> u8 {
+        self.read(address)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    /// Juego alternativo (AF', BC', DE', HL')
+    pub af2: u16,
+    pub bc2: u16,
+    pub de2: u16,
+    pub hl2: u16,
+    pub ix: u16,
+    pub iy: u16,
+    pub sp: u16,
+    pub pc: u16,
+    pub i: u8,
+    pub r: u8,
+    pub iff1: bool,
+    pub iff2: bool,
+    pub im: u8,
+}
+
+/// Registros con nombre, en el orden en que los muestra el depurador
+pub const REGISTER_NAMES: [&str; 16] = [
+    "AF", "BC", "DE", "HL", "IX", "IY", "SP", "PC", "AF'", "BC'", "DE'", "HL'", "I", "R", "IM", "IFF",
+];
+
+/// Nombres de los flags del bit 7 al 0
+pub const FLAG_NAMES: [&str; 8] = ["S", "Z", "Y", "H", "X", "PV", "N", "C"];
+
+impl Registers {
+    pub fn af(&self) -> u16 {
+        u16::from_be_bytes([self.a, self.f])
+    }
+
+    pub fn bc(&self) -> u16 {
+        u16::from_be_bytes([self.b, self.c])
+    }
+
+    pub fn de(&self) -> u16 {
+        u16::from_be_bytes([self.d, self.e])
+    }
+
+    pub fn hl(&self) -> u16 {
+        u16::from_be_bytes([self.h, self.l])
+    }
+
+    pub fn set_af(&mut self, value: u16) {
+        [self.a, self.f] = value.to_be_bytes();
+    }
+
+    pub fn set_bc(&mut self, value: u16) {
+        [self.b, self.c] = value.to_be_bytes();
+    }
+
+    pub fn set_de(&mut self, value: u16) {
+        [self.d, self.e] = value.to_be_bytes();
+    }
+
+    pub fn set_hl(&mut self, value: u16) {
+        [self.h, self.l] = value.to_be_bytes();
+    }
+
+    /// Valor de un registro de 8 o 16 bits por su nombre ("A", "HL",
+    /// "IXH", "AF'", "IFF"...), sin distinguir mayúsculas
+    pub fn get(&self, name: &str) -> Option<u16> {
+        let [ixh, ixl] = self.ix.to_be_bytes();
+        let [iyh, iyl] = self.iy.to_be_bytes();
+        Some(match name.to_ascii_uppercase().as_str() {
+            "A" => self.a as u16,
+            "F" => self.f as u16,
+            "B" => self.b as u16,
+            "C" => self.c as u16,
+            "D" => self.d as u16,
+            "E" => self.e as u16,
+            "H" => self.h as u16,
+            "L" => self.l as u16,
+            "I" => self.i as u16,
+            "R" => self.r as u16,
+            "IXH" => ixh as u16,
+            "IXL" => ixl as u16,
+            "IYH" => iyh as u16,
+            "IYL" => iyl as u16,
+            "AF" => self.af(),
+            "BC" => self.bc(),
+            "DE" => self.de(),
+            "HL" => self.hl(),
+            "IX" => self.ix,
+            "IY" => self.iy,
+            "SP" => self.sp,
+            "PC" => self.pc,
+            "AF'" => self.af2,
+            "BC'" => self.bc2,
+            "DE'" => self.de2,
+            "HL'" => self.hl2,
+            "IM" => self.im as u16,
+            "IFF" | "IFF1" => self.iff1 as u16,
+            "IFF2" => self.iff2 as u16,
+            _ => return None,
+        })
+    }
+
+    /// Cambiar un registro por su nombre; false si no existe
+    pub fn set(&mut self, name: &str, value: u16) -> bool {
+        let byte = value as u8;
+        match name.to_ascii_uppercase().as_str() {
+            "A" => self.a = byte,
+            "F" => self.f = byte,
+            "B" => self.b = byte,
+            "C" => self.c = byte,
+            "D" => self.d = byte,
+            "E" => self.e = byte,
+            "H" => self.h = byte,
+            "L" => self.l = byte,
+            "I" => self.i = byte,
+            "R" => self.r = byte,
+            "IXH" => self.ix = (self.ix & 0x00FF) | (value << 8),
+            "IXL" => self.ix = (self.ix & 0xFF00) | (value & 0xFF),
+            "IYH" => self.iy = (self.iy & 0x00FF) | (value << 8),
+            "IYL" => self.iy = (self.iy & 0xFF00) | (value & 0xFF),
+            "AF" => self.set_af(value),
+            "BC" => self.set_bc(value),
+            "DE" => self.set_de(value),
+            "HL" => self.set_hl(value),
+            "IX" => self.ix = value,
+            "IY" => self.iy = value,
+            "SP" => self.sp = value,
+            "PC" => self.pc = value,
+            "AF'" => self.af2 = value,
+            "BC'" => self.bc2 = value,
+            "DE'" => self.de2 = value,
+            "HL'" => self.hl2 = value,
+            "IM" if value <= 2 => self.im = byte,
+            "IFF" => (self.iff1, self.iff2) = (value != 0, value != 0),
+            "IFF1" => self.iff1 = value != 0,
+            "IFF2" => self.iff2 = value != 0,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Flag por su nombre (S, Z, Y, H, X, PV o P/V, N, C)
+    pub fn flag(&self, name: &str) -> Option<bool> {
+        let bit = match name.to_ascii_uppercase().as_str() {
+            "S" => FLAG_S,
+            "Z" => FLAG_Z,
+            "Y" => FLAG_Y,
+            "H" => FLAG_H,
+            "X" => FLAG_X,
+            "PV" | "P/V" | "P" | "V" => FLAG_PV,
+            "N" => FLAG_N,
+            "C" => FLAG_C,
+            _ => return None,
+        };
+        Some(self.f & bit != 0)
+    }
+
+    /// Flags como "SZYHXPNC", con un guion en los que están a 0
+    pub fn flags_text(&self) -> String {
+        "SZYHXPNC"
+            .chars()
+            .enumerate()
+            .map(|(i, c)| if self.f & (0x80 >> i) != 0 { c } else { '-' })
+            .collect()
+    }
+}
+
+/// Registro que sustituye a HL con los prefijos DD y FD
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Index {
+    Hl,
+    Ix,
+    Iy,
+}
+
+pub struct Z80 {
+    pub regs: Registers,
+    /// Parado en un HALT hasta la siguiente interrupción
+    pub halted: bool,
+    /// Espera que añade el MSX a cada ciclo M1
+    pub m1_wait: bool,
+    /// Justo después de EI no se aceptan interrupciones
+    ei_delay: bool,
+    /// Bytes de la instrucción en curso, para sus estados T
+    bytes: [u8; 4],
+    len: usize,
+    /// Salto, llamada o repetición tomada: cuenta el tiempo mayor
+    taken: bool,
+}
+
+impl Z80 {
+    pub fn new() -> Z80 {
+        let mut cpu = Z80 {
+            regs: Registers::default(),
+            halted: false,
+            m1_wait: true,
+            ei_delay: false,
+            bytes: [0; 4],
+            len: 0,
+            taken: false,
+        };
+        cpu.reset();
+        cpu
+    }
+
+    /// Estado tras el reset: PC a 0, interrupciones desactivadas y AF y SP
+    /// a FFFFh
+    pub fn reset(&mut self) {
+        self.regs = Registers {
+            sp: 0xFFFF,
+            ..Registers::default()
+        };
+        self.regs.set_af(0xFFFF);
+        self.halted = false;
+        self.ei_delay = false;
+    }
+
+    /// Bytes de la última instrucción ejecutada; vacío tras aceptar una
+    /// interrupción
+    pub fn opcode(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    /// Se puede aceptar una interrupción enmascarable ahora
+    pub fn interrupts_enabled(&self) -> bool {
+        self.regs.iff1 && !self.ei_delay
+    }
+
+    /// Ejecutar una instrucción y devolver sus estados T
+    pub fn step(&mut self, bus: &mut impl Z80Bus) -> u32 {
+        self.ei_delay = false;
+        if self.halted {
+            // Repite el HALT sin avanzar el PC
+            self.bytes[0] = 0x76;
+            self.len = 1;
+            self.increment_r(1);
+            return 4 + self.wait(1);
+        }
+        self.len = 0;
+        self.taken = false;
+        let op = self.fetch(bus);
+        match op {
+            0xCB => self.cb(bus),
+            0xED => self.ed(bus),
+            0xDD | 0xFD => {
+                // Un prefijo seguido de otro hace de NOP
+                if matches!(bus.fetch(self.regs.pc), 0xDD | 0xED | 0xFD) {
+                    self.increment_r(1);
+                    return 4 + self.wait(1);
+                }
+                let index = if op == 0xDD { Index::Ix } else { Index::Iy };
+                match self.fetch(bus) {
+                    0xCB => self.indexed_cb(bus, index),
+                    op => self.main(bus, op, index),
+                }
+            }
+            _ => self.main(bus, op, Index::Hl),
+        }
+        let code = &self.bytes[..self.len];
+        let t = timing(code);
+        let m1 = m1_cycles(code);
+        self.increment_r(m1);
+        (if self.taken { t.max } else { t.min }) + self.wait(m1)
+    }
+
+    /// Aceptar una interrupción: `data` es el byte del bus (RST en IM 0,
+    /// byte bajo del vector en IM 2)
+    pub fn interrupt(&mut self, bus: &mut impl Z80Bus, data: u8) -> u32 {
+        self.len = 0;
+        self.leave_halt();
+        self.regs.iff1 = false;
+        self.regs.iff2 = false;
+        self.increment_r(1);
+        let pc = self.regs.pc;
+        self.push(bus, pc);
+        let t = match self.regs.im {
+            2 => {
+                let vector = u16::from_be_bytes([self.regs.i, data]);
+                self.regs.pc = self.read_word(bus, vector);
+                19
+            }
+            0 => {
+                self.regs.pc = (data & 0x38) as u16;
+                13
+            }
+            _ => {
+                self.regs.pc = 0x0038;
+                13
+            }
+        };
+        t + self.wait(1)
+    }
+
+    /// Interrupción no enmascarable: salta a 0066h
+    pub fn nmi(&mut self, bus: &mut impl Z80Bus) -> u32 {
+        self.len = 0;
+        self.leave_halt();
+        self.regs.iff2 = self.regs.iff1;
+        self.regs.iff1 = false;
+        self.increment_r(1);
+        let pc = self.regs.pc;
+        self.push(bus, pc);
+        self.regs.pc = 0x0066;
+        11 + self.wait(1)
+    }
+
+    fn leave_halt(&mut self) {
+        if self.halted {
+            self.halted = false;
+            self.regs.pc = self.regs.pc.wrapping_add(1);
+        }
+    }
+
+    fn wait(&self, m1: u32) -> u32 {
+        if self.m1_wait {
+            m1
+        } else {
+            0
+        }
+    }
+
+    fn increment_r(&mut self, count: u32) {
+        let r = self.regs.r;
+        self.regs.r = (r & 0x80) | (r.wrapping_add(count as u8) & 0x7F);
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // ACCESO A MEMORIA Y REGISTROS
+    // ═══════════════════════════════════════════════════════════════
+
+    fn fetch(&mut self, bus: &mut impl Z80Bus) -> u8 {
+        let value = bus.fetch(self.regs.pc);
+        self.regs.pc = self.regs.pc.wrapping_add(1);
+        if self.len < self.bytes.len() {
+            self.bytes[self.len] = value;
+            self.len += 1;
+        }
+        value
+    }
+
+    fn fetch_word(&mut self, bus: &mut impl Z80Bus) -> u16 {
+        let low = self.fetch(bus);
+        u16::from_le_bytes([low, self.fetch(bus)])
+    }
+
+    fn read_word(&mut self, bus: &mut impl Z80Bus, address: u16) -> u16 {
+        u16::from_le_bytes([bus.read(address), bus.read(address.wrapping_add(1))])
+    }
+
+    fn write_word(&mut self, bus: &mut impl Z80Bus, address: u16, value: u16) {
+        let [low, high] = value.to_le_bytes();
+        bus.write(address, low);
+        bus.write(address.wrapping_add(1), high);
+    }
+
+    fn push(&mut self, bus: &mut impl Z80Bus, value: u16) {
+        let [low, high] = value.to_le_bytes();
+        self.regs.sp = self.regs.sp.wrapping_sub(1);
+        bus.write(self.regs.sp, high);
+        self.regs.sp = self.regs.sp.wrapping_sub(1);
+        bus.write(self.regs.sp, low);
+    }
+
+    fn pop(&mut self, bus: &mut impl Z80Bus) -> u16 {
+        let value = self.read_word(bus, self.regs.sp);
+        self.regs.sp = self.regs.sp.wrapping_add(2);
+        value
+    }
+
+    /// HL, IX o IY
+    fn index(&self, index: Index) -> u16 {
+        match index {
+            Index::Hl => self.regs.hl(),
+            Index::Ix => self.regs.ix,
+            Index::Iy => self.regs.iy,
+        }
+    }
+
+    fn set_index(&mut self, index: Index, value: u16) {
+        match index {
+            Index::Hl => self.regs.set_hl(value),
+            Index::Ix => self.regs.ix = value,
+            Index::Iy => self.regs.iy = value,
+        }
+    }
+
+    /// Dirección de (HL) o (IX+d), leyendo el desplazamiento
+    fn address(&mut self, bus: &mut impl Z80Bus, index: Index) -> u16 {
+        match index {
+            Index::Hl => self.regs.hl(),
+            _ => {
+                let d = self.fetch(bus) as i8;
+                self.index(index).wrapping_add(d as u16)
+            }
+        }
+    }
+
+    /// Registro de 8 bits por su código (B C D E H L - A); con prefijo,
+    /// H y L son las mitades de IX o IY
+    fn reg(&self, r: u8, index: Index) -> u8 {
+        match (r, index) {
+            (4, Index::Ix | Index::Iy) => (self.index(index) >> 8) as u8,
+            (5, Index::Ix | Index::Iy) => self.index(index) as u8,
+            (0, _) => self.regs.b,
+            (1, _) => self.regs.c,
+            (2, _) => self.regs.d,
+            (3, _) => self.regs.e,
+            (4, _) => self.regs.h,
+            (5, _) => self.regs.l,
+            _ => self.regs.a,
+        }
+    }
+
+    fn set_reg(&mut self, r: u8, index: Index, value: u8) {
+        match (r, index) {
+            (4, Index::Ix | Index::Iy) => {
+                let low = self.index(index) & 0xFF;
+                self.set_index(index, low | ((value as u16) << 8));
+            }
+            (5, Index::Ix | Index::Iy) => {
+                let high = self.index(index) & 0xFF00;
+                self.set_index(index, high | value as u16);
+            }
+            (0, _) => self.regs.b = value,
+            (1, _) => self.regs.c = value,
+            (2, _) => self.regs.d = value,
+            (3, _) => self.regs.e = value,
+            (4, _) => self.regs.h = value,
+            (5, _) => self.regs.l = value,
+            _ => self.regs.a = value,
+        }
+    }
+
+    /// Operando r[z] de 8 bits, (HL) o (IX+d) incluido
+    fn operand(&mut self, bus: &mut impl Z80Bus, r: u8, index: Index) -> u8 {
+        if r == 6 {
+            let address = self.address(bus, index);
+            bus.read(address)
+        } else {
+            self.reg(r, index)
+        }
+    }
+
+    /// BC, DE, HL (o IX/IY) y SP
+    fn pair(&self, p: u8, index: Index) -> u16 {
+        match p {
+            0 => self.regs.bc(),
+            1 => self.regs.de(),
+            2 => self.index(index),
+            _ => self.regs.sp,
+        }
+    }
+
+    fn set_pair(&mut self, p: u8, index: Index, value: u16) {
+        match p {
+            0 => self.regs.set_bc(value),
+            1 => self.regs.set_de(value),
+            2 => self.set_index(index, value),
+            _ => self.regs.sp = value,
+        }
+    }
+
+    fn condition(&self, cc: u8) -> bool {
+        let f = self.regs.f;
+        match cc {
+            0 => f & FLAG_Z == 0,
+            1 => f & FLAG_Z != 0,
+            2 => f & FLAG_C == 0,
+            3 => f & FLAG_C != 0,
+            4 => f & FLAG_PV == 0,
+            5 => f & FLAG_PV != 0,
+            6 => f & FLAG_S == 0,
+            _ => f & FLAG_S != 0,
+        }
+    }
+
+    fn jump_relative(&mut self, d: u8) {
+        self.regs.pc = self.regs.pc.wrapping_add(d as i8 as u16);
+        self.taken = true;
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // INSTRUCCIONES SIN PREFIJO (Y CON DD/FD)
+    // ═══════════════════════════════════════════════════════════════
+
+    fn main(&mut self, bus: &mut impl Z80Bus, op: u8, index: Index) {
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+        match (x, z) {
+            (0, 0) => match y {
+                0 => {}
+                1 => {
+                    let af = self.regs.af();
+                    self.regs.set_af(self.regs.af2);
+                    self.regs.af2 = af;
+                }
+                2 => {
+                    let d = self.fetch(bus);
+                    self.regs.b = self.regs.b.wrapping_sub(1);
+                    if self.regs.b != 0 {
+                        self.jump_relative(d);
+                    }
+                }
+                3 => {
+                    let d = self.fetch(bus);
+                    self.jump_relative(d);
+                }
+                _ => {
+                    let d = self.fetch(bus);
+                    if self.condition(y - 4) {
+                        self.jump_relative(d);
+                    }
+                }
+            },
+            (0, 1) if q == 0 => {
+                let value = self.fetch_word(bus);
+                self.set_pair(p, index, value);
+            }
+            (0, 1) => {
+                let (a, b) = (self.index(index), self.pair(p, index));
+                let result = self.add16(a, b);
+                self.set_index(index, result);
+            }
+            (0, 2) => {
+                let address = match p {
+                    0 => self.regs.bc(),
+                    1 => self.regs.de(),
+                    _ => self.fetch_word(bus),
+                };
+                match (q, p) {
+                    (0, 2) => {
+                        let value = self.index(index);
+                        self.write_word(bus, address, value);
+                    }
+                    (0, _) => bus.write(address, self.regs.a),
+                    (_, 2) => {
+                        let value = self.read_word(bus, address);
+                        self.set_index(index, value);
+                    }
+                    _ => self.regs.a = bus.read(address),
+                }
+            }
+            (0, 3) => {
+                let value = self.pair(p, index);
+                let value = if q == 0 { value.wrapping_add(1) } else { value.wrapping_sub(1) };
+                self.set_pair(p, index, value);
+            }
+            (0, 4 | 5) => {
+                let address = (y == 6).then(|| self.address(bus, index));
+                let value = match address {
+                    Some(address) => bus.read(address),
+                    None => self.reg(y, index),
+                };
+                let result = if z == 4 { self.inc8(value) } else { self.dec8(value) };
+                match address {
+                    Some(address) => bus.write(address, result),
+                    None => self.set_reg(y, index, result),
+                }
+            }
+            (0, 6) => {
+                if y == 6 {
+                    let address = self.address(bus, index);
+                    let value = self.fetch(bus);
+                    bus.write(address, value);
+                } else {
+                    let value = self.fetch(bus);
+                    self.set_reg(y, index, value);
+                }
+            }
+            (0, _) => self.accumulator(y),
+            (1, _) if op == 0x76 => {
+                self.halted = true;
+                self.regs.pc = self.regs.pc.wrapping_sub(1);
+            }
+            (1, _) => {
+                // Con (IX+d), el otro operando es H o L de verdad
+                if y == 6 {
+                    let address = self.address(bus, index);
+                    bus.write(address, self.reg(z, Index::Hl));
+                } else if z == 6 {
+                    let address = self.address(bus, index);
+                    let value = bus.read(address);
+                    self.set_reg(y, Index::Hl, value);
+                } else {
+                    let value = self.reg(z, index);
+                    self.set_reg(y, index, value);
+                }
+            }
+            (2, _) => {
+                let value = self.operand(bus, z, index);
+                self.alu(y, value);
+            }
+            (_, 0) => {
+                if self.condition(y) {
+                    self.regs.pc = self.pop(bus);
+                    self.taken = true;
+                }
+            }
+            (_, 1) if q == 0 => {
+                let value = self.pop(bus);
+                match p {
+                    3 => self.regs.set_af(value),
+                    _ => self.set_pair(p, index, value),
+                }
+            }
+            (_, 1) => match p {
+                0 => self.regs.pc = self.pop(bus),
+                1 => {
+                    let regs = &mut self.regs;
+                    let (bc, de, hl) = (regs.bc(), regs.de(), regs.hl());
+                    regs.set_bc(regs.bc2);
+                    regs.set_de(regs.de2);
+                    regs.set_hl(regs.hl2);
+                    (regs.bc2, regs.de2, regs.hl2) = (bc, de, hl);
+                }
+                2 => self.regs.pc = self.index(index),
+                _ => self.regs.sp = self.index(index),
+            },
+            (_, 2) => {
+                let target = self.fetch_word(bus);
+                if self.condition(y) {
+                    self.regs.pc = target;
+                }
+            }
+            (_, 3) => match y {
+                0 => self.regs.pc = self.fetch_word(bus),
+                2 => {
+                    let port = u16::from_be_bytes([self.regs.a, self.fetch(bus)]);
+                    bus.output(port, self.regs.a);
+                }
+                3 => {
+                    let port = u16::from_be_bytes([self.regs.a, self.fetch(bus)]);
+                    self.regs.a = bus.input(port);
+                }
+                4 => {
+                    let sp = self.regs.sp;
+                    let value = self.read_word(bus, sp);
+                    let current = self.index(index);
+                    self.write_word(bus, sp, current);
+                    self.set_index(index, value);
+                }
+                5 => {
+                    let de = self.regs.de();
+                    self.regs.set_de(self.regs.hl());
+                    self.regs.set_hl(de);
+                }
+                6 => {
+                    self.regs.iff1 = false;
+                    self.regs.iff2 = false;
+                }
+                7 => {
+                    self.regs.iff1 = true;
+                    self.regs.iff2 = true;
+                    self.ei_delay = true;
+                }
+                // CB llega por su propio camino
+                _ => {}
+            },
+            (_, 4) => {
+                let target = self.fetch_word(bus);
+                if self.condition(y) {
+                    let pc = self.regs.pc;
+                    self.push(bus, pc);
+                    self.regs.pc = target;
+                    self.taken = true;
+                }
+            }
+            (_, 5) if q == 0 => {
+                let value = match p {
+                    3 => self.regs.af(),
+                    _ => self.pair(p, index),
+                };
+                self.push(bus, value);
+            }
+            (_, 5) => {
+                let target = self.fetch_word(bus);
+                let pc = self.regs.pc;
+                self.push(bus, pc);
+                self.regs.pc = target;
+            }
+            (_, 6) => {
+                let value = self.fetch(bus);
+                self.alu(y, value);
+            }
+            _ => {
+                let pc = self.regs.pc;
+                self.push(bus, pc);
+                self.regs.pc = (y * 8) as u16;
+            }
+        }
+    }
+
+    /// RLCA, RRCA, RLA, RRA, DAA, CPL, SCF y CCF
+    fn accumulator(&mut self, y: u8) {
+        let (a, f) = (self.regs.a, self.regs.f);
+        let keep = f & (FLAG_S | FLAG_Z | FLAG_PV);
+        let (result, flags) = match y {
+            0 => {
+                let r = a.rotate_left(1);
+                (r, keep | (r & FLAG_C))
+            }
+            1 => {
+                let r = a.rotate_right(1);
+                (r, keep | (a & FLAG_C))
+            }
+            2 => ((a << 1) | (f & FLAG_C), keep | (a >> 7)),
+            3 => ((a >> 1) | ((f & FLAG_C) << 7), keep | (a & FLAG_C)),
+            4 => return self.daa(),
+            5 => (!a, (f & (FLAG_S | FLAG_Z | FLAG_PV | FLAG_C)) | FLAG_H | FLAG_N),
+            6 => (a, keep | FLAG_C),
+            _ => (a, keep | ((f & FLAG_C) << 4) | ((f & FLAG_C) ^ FLAG_C)),
+        };
+        self.regs.a = result;
+        self.regs.f = flags | (result & (FLAG_Y | FLAG_X));
+    }
+
+    fn daa(&mut self) {
+        let (a, f) = (self.regs.a, self.regs.f);
+        let subtract = f & FLAG_N != 0;
+        let mut correction = 0;
+        let mut carry = f & FLAG_C;
+        if f & FLAG_H != 0 || a & 0x0F > 9 {
+            correction |= 0x06;
+        }
+        if carry != 0 || a > 0x99 {
+            correction |= 0x60;
+            carry = FLAG_C;
+        }
+        let (result, half) = if subtract {
+            (a.wrapping_sub(correction), f & FLAG_H != 0 && a & 0x0F < 6)
+        } else {
+            (a.wrapping_add(correction), a & 0x0F > 9)
+        };
+        self.regs.a = result;
+        self.regs.f = sz53p(result) | (f & FLAG_N) | carry | if half { FLAG_H } else { 0 };
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // ARITMÉTICA Y FLAGS
+    // ═══════════════════════════════════════════════════════════════
+
+    fn alu(&mut self, op: u8, value: u8) {
+        let carry = self.regs.f & FLAG_C != 0;
+        match op {
+            0 => self.add8(value, false),
+            1 => self.add8(value, carry),
+            2 => self.sub8(value, false, true),
+            3 => self.sub8(value, carry, true),
+            4 => {
+                self.regs.a &= value;
+                self.regs.f = sz53p(self.regs.a) | FLAG_H;
+            }
+            5 => {
+                self.regs.a ^= value;
+                self.regs.f = sz53p(self.regs.a);
+            }
+            6 => {
+                self.regs.a |= value;
+                self.regs.f = sz53p(self.regs.a);
+            }
+            _ => self.sub8(value, false, false),
+        }
+    }
+
+    fn add8(&mut self, value: u8, carry: bool) {
+        let a = self.regs.a;
+        let sum = a as u16 + value as u16 + carry as u16;
+        let result = sum as u8;
+        let overflow = (a ^ !value) & (a ^ result) & 0x80 != 0;
+        self.regs.f = sz53(result)
+            | ((a ^ value ^ result) & FLAG_H)
+            | if overflow { FLAG_PV } else { 0 }
+            | if sum > 0xFF { FLAG_C } else { 0 };
+        self.regs.a = result;
+    }
+
+    /// SUB, SBC y CP (sin guardar, con los bits 3 y 5 del operando)
+    fn sub8(&mut self, value: u8, carry: bool, store: bool) {
+        let a = self.regs.a;
+        let difference = (a as u16).wrapping_sub(value as u16).wrapping_sub(carry as u16);
+        let result = difference as u8;
+        let overflow = (a ^ value) & (a ^ result) & 0x80 != 0;
+        let undocumented = if store { result } else { value };
+        self.regs.f = (sz53(result) & !(FLAG_Y | FLAG_X))
+            | (undocumented & (FLAG_Y | FLAG_X))
+            | FLAG_N
+            | ((a ^ value ^ result) & FLAG_H)
+            | if overflow { FLAG_PV } else { 0 }
+            | if difference > 0xFF { FLAG_C } else { 0 };
+        if store {
+            self.regs.a = result;
+        }
+    }
+
+    fn inc8(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_add(1);
+        self.regs.f = (self.regs.f & FLAG_C)
+            | sz53(result)
+            | if value & 0x0F == 0x0F { FLAG_H } else { 0 }
+            | if value == 0x7F { FLAG_PV } else { 0 };
+        result
+    }
+
+    fn dec8(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        self.regs.f = (self.regs.f & FLAG_C)
+            | FLAG_N
+            | sz53(result)
+            | if value & 0x0F == 0 { FLAG_H } else { 0 }
+            | if value == 0x80 { FLAG_PV } else { 0 };
+        result
+    }
+
+    fn add16(&mut self, a: u16, b: u16) -> u16 {
+        let sum = a as u32 + b as u32;
+        let result = sum as u16;
+        self.regs.f = (self.regs.f & (FLAG_S | FLAG_Z | FLAG_PV))
+            | ((result >> 8) as u8 & (FLAG_Y | FLAG_X))
+            | if (a ^ b ^ result) & 0x1000 != 0 { FLAG_H } else { 0 }
+            | if sum > 0xFFFF { FLAG_C } else { 0 };
+        result
+    }
+
+    /// ADC HL,rr y SBC HL,rr
+    fn adc16(&mut self, value: u16, subtract: bool) {
+        let (hl, carry) = (self.regs.hl(), (self.regs.f & FLAG_C) as u32);
+        let (total, overflow) = if subtract {
+            let total = (hl as u32).wrapping_sub(value as u32).wrapping_sub(carry);
+            (total, (hl ^ value) & (hl ^ total as u16) & 0x8000 != 0)
+        } else {
+            let total = hl as u32 + value as u32 + carry;
+            (total, (hl ^ !value) & (hl ^ total as u16) & 0x8000 != 0)
+        };
+        let result = total as u16;
+        self.regs.f = ((result >> 8) as u8 & (FLAG_S | FLAG_Y | FLAG_X))
+            | if result == 0 { FLAG_Z } else { 0 }
+            | if (hl ^ value ^ result) & 0x1000 != 0 { FLAG_H } else { 0 }
+            | if overflow { FLAG_PV } else { 0 }
+            | if subtract { FLAG_N } else { 0 }
+            | if total > 0xFFFF { FLAG_C } else { 0 };
+        self.regs.set_hl(result);
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // PREFIJOS CB, DDCB/FDCB Y ED
+    // ═══════════════════════════════════════════════════════════════
+
+    fn cb(&mut self, bus: &mut impl Z80Bus) {
+        let op = self.fetch(bus);
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let address = self.regs.hl();
+        let value = if z == 6 { bus.read(address) } else { self.reg(z, Index::Hl) };
+        if x == 1 {
+            let undocumented = if z == 6 { (address >> 8) as u8 } else { value };
+            self.bit(y, value, undocumented);
+            return;
+        }
+        let result = self.bit_operation(x, y, value);
+        if z == 6 {
+            bus.write(address, result);
+        } else {
+            self.set_reg(z, Index::Hl, result);
+        }
+    }
+
+    /// DD CB d op: el desplazamiento va antes del código. Fuera de BIT, el
+    /// resultado se copia además en el registro r[z] si no es (HL)
+    fn indexed_cb(&mut self, bus: &mut impl Z80Bus, index: Index) {
+        let address = self.address(bus, index);
+        let op = self.fetch(bus);
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let value = bus.read(address);
+        if x == 1 {
+            self.bit(y, value, (address >> 8) as u8);
+            return;
+        }
+        let result = self.bit_operation(x, y, value);
+        bus.write(address, result);
+        if z != 6 {
+            self.set_reg(z, Index::Hl, result);
+        }
+    }
+
+    /// Rotaciones y desplazamientos (x = 0), RES (2) y SET (3)
+    fn bit_operation(&mut self, x: u8, y: u8, value: u8) -> u8 {
+        match x {
+            0 => {
+                let carry_in = self.regs.f & FLAG_C;
+                let (result, carry) = match y {
+                    0 => (value.rotate_left(1), value >> 7),
+                    1 => (value.rotate_right(1), value & 1),
+                    2 => ((value << 1) | carry_in, value >> 7),
+                    3 => ((value >> 1) | (carry_in << 7), value & 1),
+                    4 => (value << 1, value >> 7),
+                    5 => ((value >> 1) | (value & 0x80), value & 1),
+                    6 => ((value << 1) | 1, value >> 7),
+                    _ => (value >> 1, value & 1),
+                };
+                self.regs.f = sz53p(result) | carry;
+                result
+            }
+            2 => value & !(1 << y),
+            _ => value | (1 << y),
+        }
+    }
+
+    fn bit(&mut self, y: u8, value: u8, undocumented: u8) {
+        let set = value & (1 << y) != 0;
+        self.regs.f = (self.regs.f & FLAG_C)
+            | FLAG_H
+            | (undocumented & (FLAG_Y | FLAG_X))
+            | if set { 0 } else { FLAG_Z | FLAG_PV }
+            | if set && y == 7 { FLAG_S } else { 0 };
+    }
+
+    fn ed(&mut self, bus: &mut impl Z80Bus) {
+        let op = self.fetch(bus);
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+        match (x, z) {
+            (1, 0) => {
+                let value = bus.input(self.regs.bc());
+                if y != 6 {
+                    self.set_reg(y, Index::Hl, value);
+                }
+                self.regs.f = (self.regs.f & FLAG_C) | sz53p(value);
+            }
+            (1, 1) => {
+                let value = if y == 6 { 0 } else { self.reg(y, Index::Hl) };
+                bus.output(self.regs.bc(), value);
+            }
+            (1, 2) => {
+                let value = self.pair(p, Index::Hl);
+                self.adc16(value, q == 0);
+            }
+            (1, 3) => {
+                let address = self.fetch_word(bus);
+                if q == 0 {
+                    let value = self.pair(p, Index::Hl);
+                    self.write_word(bus, address, value);
+                } else {
+                    let value = self.read_word(bus, address);
+                    self.set_pair(p, Index::Hl, value);
+                }
+            }
+            (1, 4) => {
+                let value = self.regs.a;
+                self.regs.a = 0;
+                self.sub8(value, false, true);
+            }
+            (1, 5) => {
+                self.regs.pc = self.pop(bus);
+                self.regs.iff1 = self.regs.iff2;
+            }
+            (1, 6) => self.regs.im = [0, 0, 1, 2][(y & 3) as usize],
+            (1, 7) => match y {
+                0 => self.regs.i = self.regs.a,
+                1 => self.regs.r = self.regs.a,
+                2 | 3 => {
+                    self.regs.a = if y == 2 { self.regs.i } else { self.regs.r };
+                    self.regs.f = (self.regs.f & FLAG_C) | sz53(self.regs.a) | if self.regs.iff2 { FLAG_PV } else { 0 };
+                }
+                4 | 5 => {
+                    let (address, a) = (self.regs.hl(), self.regs.a);
+                    let value = bus.read(address);
+                    let (memory, a) = if y == 4 {
+                        ((a << 4) | (value >> 4), (a & 0xF0) | (value & 0x0F))
+                    } else {
+                        ((value << 4) | (a & 0x0F), (a & 0xF0) | (value >> 4))
+                    };
+                    bus.write(address, memory);
+                    self.regs.a = a;
+                    self.regs.f = (self.regs.f & FLAG_C) | sz53p(a);
+                }
+                _ => {}
+            },
+            (2, 0..=3) if y >= 4 => self.block(bus, y, z),
+            _ => {}
+        }
+    }
+
+    /// LDI, CPI, INI, OUTI y sus variantes D, IR y DR
+    fn block(&mut self, bus: &mut impl Z80Bus, y: u8, z: u8) {
+        let step = if y & 1 == 0 { 1u16 } else { 0xFFFF };
+        let repeat = y >= 6;
+        let hl = self.regs.hl();
+        let again = match z {
+            0 => {
+                let value = bus.read(hl);
+                let de = self.regs.de();
+                bus.write(de, value);
+                self.regs.set_de(de.wrapping_add(step));
+                let bc = self.regs.bc().wrapping_sub(1);
+                self.regs.set_bc(bc);
+                let n = value.wrapping_add(self.regs.a);
+                self.regs.f = (self.regs.f & (FLAG_S | FLAG_Z | FLAG_C))
+                    | (n & FLAG_X)
+                    | ((n << 4) & FLAG_Y)
+                    | if bc != 0 { FLAG_PV } else { 0 };
+                bc != 0
+            }
+            1 => {
+                let (a, value) = (self.regs.a, bus.read(hl));
+                let result = a.wrapping_sub(value);
+                let half = (a ^ value ^ result) & FLAG_H;
+                let n = result.wrapping_sub((half != 0) as u8);
+                let bc = self.regs.bc().wrapping_sub(1);
+                self.regs.set_bc(bc);
+                self.regs.f = (self.regs.f & FLAG_C)
+                    | FLAG_N
+                    | (result & FLAG_S)
+                    | if result == 0 { FLAG_Z } else { 0 }
+                    | half
+                    | (n & FLAG_X)
+                    | ((n << 4) & FLAG_Y)
+                    | if bc != 0 { FLAG_PV } else { 0 };
+                bc != 0 && result != 0
+            }
+            2 => {
+                let value = bus.input(self.regs.bc());
+                bus.write(hl, value);
+                self.regs.b = self.regs.b.wrapping_sub(1);
+                self.regs.f = sz53(self.regs.b) | FLAG_N | (self.regs.f & FLAG_C);
+                self.regs.b != 0
+            }
+            _ => {
+                let value = bus.read(hl);
+                self.regs.b = self.regs.b.wrapping_sub(1);
+                bus.output(self.regs.bc(), value);
+                self.regs.f = sz53(self.regs.b) | FLAG_N | (self.regs.f & FLAG_C);
+                self.regs.b != 0
+            }
+        };
+        self.regs.set_hl(hl.wrapping_add(step));
+        if repeat && again {
+            self.regs.pc = self.regs.pc.wrapping_sub(2);
+            self.taken = true;
+        }
+    }
+}
+
+impl Default for Z80 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// S, Z y los bits 3 y 5 del resultado
+fn sz53(value: u8) -> u8 {
+    (value & (FLAG_S | FLAG_Y | FLAG_X)) | if value == 0 { FLAG_Z } else { 0 }
+}
+
+/// Como `sz53` más la paridad en P/V
+fn sz53p(value: u8) -> u8 {
+    sz53(value) | if value.count_ones() & 1 == 0 { FLAG_PV } else { 0 }
+}
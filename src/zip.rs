@@ -0,0 +1,196 @@
+//! ╔════════════════════════════════════════════════════════════════╗
+//! ║  LECTOR DE ARCHIVOS ZIP                                        ║
+//! ║  - Directorio central (EOCD al final del fichero)              ║
+//! ║  - Entradas almacenadas (0) y DEFLATE (8), con CRC32           ║
+//! ╚════════════════════════════════════════════════════════════════╝
+
+use crate::filetype::{FileKind, SNIFF_SIZE};
+use crate::inflate::{inflate, inflate_prefix};
+use crate::rtc::RtcDateTime;
+
+const LOCAL_SIGNATURE: u32 = 0x0403_4B50;
+const CENTRAL_SIGNATURE: u32 = 0x0201_4B50;
+const END_SIGNATURE: u32 = 0x0605_4B50;
+
+pub const METHOD_STORED: u16 = 0;
+pub const METHOD_DEFLATE: u16 = 8;
+
+/// CRC-32 (polinomio EDB88320) de ZIP, PNG y demás
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([*data.get(pos)?, *data.get(pos + 1)?]))
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+/// Fecha y hora en formato MS-DOS (la misma que usan los directorios FAT)
+fn dos_datetime(date: u16, time: u16) -> RtcDateTime {
+    RtcDateTime {
+        year: 1980 + (date >> 9),
+        month: ((date >> 5) & 0x0F) as u8,
+        day: (date & 0x1F) as u8,
+        hour: (time >> 11) as u8,
+        minute: ((time >> 5) & 0x3F) as u8,
+        second: ((time & 0x1F) * 2) as u8,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ZipEntry {
+    /// Ruta dentro del archivo, con '/' como separador
+    pub name: String,
+    pub method: u16,
+    pub compressed_size: u32,
+    pub size: u32,
+    pub crc32: u32,
+    pub modified: RtcDateTime,
+    local_offset: u32,
+}
+
+impl ZipEntry {
+    pub fn is_directory(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn method_name(&self) -> &'static str {
+        match self.method {
+            METHOD_STORED => "stored",
+            METHOD_DEFLATE => "deflate",
+            _ => "unsupported",
+        }
+    }
+}
+
+pub struct ZipArchive<'a> {
+    data: &'a [u8],
+    entries: Vec<ZipEntry>,
+}
+
+impl<'a> ZipArchive<'a> {
+    /// Leer el directorio central
+    pub fn parse(data: &'a [u8]) -> Result<ZipArchive<'a>, String> {
+        // El registro final mide 22 bytes más un comentario de hasta 64 KB
+        let search_start = data.len().saturating_sub(22 + 0xFFFF);
+        let end = (search_start..data.len().saturating_sub(21))
+            .rev()
+            .find(|&pos| u32_at(data, pos) == Some(END_SIGNATURE))
+            .ok_or("No es un archivo ZIP: falta el directorio central")?;
+
+        let count = u16_at(data, end + 10).unwrap_or(0) as usize;
+        let mut pos = u32_at(data, end + 16).unwrap_or(0) as usize;
+        let truncated = || "Directorio central del ZIP truncado".to_string();
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if u32_at(data, pos) != Some(CENTRAL_SIGNATURE) {
+                return Err(truncated());
+            }
+            let field16 = |offset: usize| u16_at(data, pos + offset).ok_or_else(truncated);
+            let field32 = |offset: usize| u32_at(data, pos + offset).ok_or_else(truncated);
+
+            let name_len = field16(28)? as usize;
+            let extra_len = field16(30)? as usize;
+            let comment_len = field16(32)? as usize;
+            let name_bytes = data.get(pos + 46..pos + 46 + name_len).ok_or_else(truncated)?;
+
+            entries.push(ZipEntry {
+                name: String::from_utf8_lossy(name_bytes).replace('\\', "/"),
+                method: field16(10)?,
+                modified: dos_datetime(field16(14)?, field16(12)?),
+                crc32: field32(16)?,
+                compressed_size: field32(20)?,
+                size: field32(24)?,
+                local_offset: field32(42)?,
+            });
+            pos += 46 + name_len + extra_len + comment_len;
+        }
+
+        Ok(ZipArchive { data, entries })
+    }
+
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    /// Índice de una entrada por nombre, sin distinguir mayúsculas
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Datos comprimidos de una entrada, tras su cabecera local
+    fn compressed(&self, index: usize) -> Result<(&ZipEntry, &'a [u8]), String> {
+        let entry = self
+            .entries
+            .get(index)
+            .ok_or_else(|| format!("Entrada {} inexistente", index))?;
+
+        let local = entry.local_offset as usize;
+        if u32_at(self.data, local) != Some(LOCAL_SIGNATURE) {
+            return Err(format!("Cabecera local de '{}' corrupta", entry.name));
+        }
+        let name_len = u16_at(self.data, local + 26).unwrap_or(0) as usize;
+        let extra_len = u16_at(self.data, local + 28).unwrap_or(0) as usize;
+        let start = local + 30 + name_len + extra_len;
+        let compressed = self
+            .data
+            .get(start..start + entry.compressed_size as usize)
+            .ok_or_else(|| format!("Datos de '{}' truncados", entry.name))?;
+        Ok((entry, compressed))
+    }
+
+    /// Descomprimir una entrada y comprobar su CRC
+    pub fn extract(&self, index: usize) -> Result<Vec<u8>, String> {
+        let (entry, compressed) = self.compressed(index)?;
+        let contents = match entry.method {
+            METHOD_STORED => compressed.to_vec(),
+            METHOD_DEFLATE => inflate(compressed).map_err(|e| format!("{}: {}", entry.name, e))?,
+            method => return Err(format!("Método de compresión {} no soportado en '{}'", method, entry.name)),
+        };
+
+        if contents.len() != entry.size as usize || crc32(&contents) != entry.crc32 {
+            return Err(format!("CRC incorrecto en '{}'", entry.name));
+        }
+        Ok(contents)
+    }
+
+    /// Primeros `count` bytes de una entrada, descomprimiendo solo lo
+    /// necesario (sin CRC: eso queda para `extract`)
+    pub fn head(&self, index: usize, count: usize) -> Result<Vec<u8>, String> {
+        let (entry, compressed) = self.compressed(index)?;
+        match entry.method {
+            METHOD_STORED => Ok(compressed[..count.min(compressed.len())].to_vec()),
+            METHOD_DEFLATE => inflate_prefix(compressed, count).map_err(|e| format!("{}: {}", entry.name, e)),
+            method => Err(format!("Método de compresión {} no soportado en '{}'", method, entry.name)),
+        }
+    }
+
+    /// Tipo de cada entrada por su nombre, tamaño y primeros bytes; solo se
+    /// descomprime el principio
+    pub fn classify(&self, index: usize) -> FileKind {
+        let entry = &self.entries[index];
+        match self.head(index, SNIFF_SIZE) {
+            Ok(head) => FileKind::classify_head(&entry.name, &head, entry.size as usize),
+            Err(_) => FileKind::Unknown,
+        }
+    }
+
+    /// Primera entrada del tipo pedido; la extensión se prueba antes que el contenido
+    pub fn pick(&self, kind: FileKind) -> Option<usize> {
+        let files = || (0..self.entries.len()).filter(|&i| !self.entries[i].is_directory());
+        files()
+            .find(|&i| FileKind::from_extension(&self.entries[i].name) == kind && self.classify(i) == kind)
+            .or_else(|| files().find(|&i| self.classify(i) == kind))
+    }
+}
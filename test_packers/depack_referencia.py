@@ -0,0 +1,190 @@
+"""Depackers de referencia traducidos paso a paso (aPLib depack.c, dzx0.c,
+y las rutinas Z80 de Pletter 0.5 y BitBuster 1.2) para comprobar vectores."""
+import sys
+
+class Z80Bits:
+    """Registro A con centinela, como GET_BIT de las rutinas Z80"""
+    def __init__(self, src, pos, a):
+        self.src, self.pos, self.a = src, pos, a
+    def getbit(self):
+        self.a = (self.a << 1) & 0x1FF
+        carry = self.a >> 8
+        self.a &= 0xFF
+        if self.a == 0:               # call z,getbit: ld a,(hl); inc hl; rla
+            v = self.src[self.pos]; self.pos += 1
+            carry, self.a = v >> 7, ((v << 1) | 1) & 0xFF
+        return carry
+
+def pletter(src):
+    a = src[0]
+    # add a,a / inc a / rl e ... tres bits de modo
+    e = 0
+    b = Z80Bits(src, 1, 0)
+    a = (a << 1); c = a >> 8; a = (a & 0xFF) + 1; e = (e << 1) | c
+    a = (a << 1); c = a >> 8; a &= 0xFF; e = (e << 1) | c
+    a = (a << 1); c = a >> 8; a &= 0xFF; e = (e << 1) | c
+    mode = e
+    assert mode <= 6
+    b.a = a
+    out = bytearray()
+    out.append(src[b.pos]); b.pos += 1            # ldi
+    while True:
+        if not b.getbit():
+            out.append(src[b.pos]); b.pos += 1
+            continue
+        hl = 1
+        if b.getbit():
+            while True:
+                hl = (hl << 1) | b.getbit()
+                if hl > 0xFFFF:
+                    return bytes(out), b.pos
+                if not b.getbit():
+                    break
+        hl += 1
+        cc = src[b.pos]; b.pos += 1
+        bb = 0
+        if cc & 0x80 and mode:
+            for _ in range(mode):
+                bb = ((bb << 1) | b.getbit()) & 0xFF
+            if b.getbit():
+                bb += 1
+                cc &= 0x7F
+        bc = ((bb << 8) | cc) + 1
+        for _ in range(hl):
+            out.append(out[len(out) - bc])
+
+def bitbuster(src):
+    length = src[0] | src[1] << 8 | src[2] << 16 | src[3] << 24
+    b = Z80Bits(src, 4, 128)
+    out = bytearray()
+    while True:
+        if not b.getbit():
+            out.append(src[b.pos]); b.pos += 1
+            continue
+        c = src[b.pos]; b.pos += 1
+        hb = 0
+        if c & 0x80:
+            for _ in range(3):
+                hb = (hb << 1) | b.getbit()
+            if not b.getbit():
+                c &= 0x7F
+        bc = ((hb << 8) | c) + 1
+        count = 1
+        while b.getbit():
+            count += 1
+        hl = 1
+        for _ in range(count - 1):
+            hl = (hl << 1) | b.getbit()
+            if hl > 0xFFFF:
+                assert length == len(out), (length, len(out))
+                return bytes(out), b.pos
+        hl += 1
+        for _ in range(hl):
+            out.append(out[len(out) - bc])
+
+def aplib(src):
+    st = {"pos": 0, "tag": 0, "bitcount": 0}
+    def getbit():
+        if st["bitcount"] == 0:
+            st["tag"] = src[st["pos"]]; st["pos"] += 1; st["bitcount"] = 8
+        st["bitcount"] -= 1
+        bit = (st["tag"] >> 7) & 1
+        st["tag"] = (st["tag"] << 1) & 0xFF
+        return bit
+    def getgamma():
+        result = 1
+        while True:
+            result = (result << 1) + getbit()
+            if not getbit():
+                return result
+    def byte():
+        v = src[st["pos"]]; st["pos"] += 1; return v
+    out = bytearray([byte()])
+    R0, LWM = None, 0
+    while True:
+        if getbit():
+            if getbit():
+                if getbit():
+                    offs = 0
+                    for _ in range(4):
+                        offs = (offs << 1) + getbit()
+                    out.append(out[-offs] if offs else 0)
+                    LWM = 0
+                else:
+                    offs = byte()
+                    ln = 2 + (offs & 1)
+                    offs >>= 1
+                    if not offs:
+                        return bytes(out), st["pos"]
+                    for _ in range(ln):
+                        out.append(out[-offs])
+                    R0, LWM = offs, 1
+            else:
+                offs = getgamma()
+                if LWM == 0 and offs == 2:
+                    offs = R0
+                    ln = getgamma()
+                    for _ in range(ln):
+                        out.append(out[-offs])
+                else:
+                    offs -= 3 if LWM == 0 else 2
+                    offs = (offs << 8) + byte()
+                    ln = getgamma()
+                    if offs >= 32000: ln += 1
+                    if offs >= 1280: ln += 1
+                    if offs < 128: ln += 2
+                    for _ in range(ln):
+                        out.append(out[-offs])
+                    R0 = offs
+                LWM = 1
+        else:
+            out.append(byte())
+            LWM = 0
+
+def zx0(src):
+    st = {"pos": 0, "mask": 0, "value": 0, "last": 0, "backtrack": False}
+    def read_byte():
+        v = src[st["pos"]]; st["pos"] += 1; st["last"] = v; return v
+    def read_bit():
+        if st["backtrack"]:
+            st["backtrack"] = False
+            return st["last"] & 1
+        st["mask"] >>= 1
+        if st["mask"] == 0:
+            st["mask"] = 128
+            st["value"] = read_byte()
+        return 1 if st["value"] & st["mask"] else 0
+    def gamma(inverted):
+        value = 1
+        while not read_bit():
+            value = (value << 1) | (read_bit() ^ inverted)
+        return value
+    out = bytearray()
+    last_offset = 1
+    state = "literals"
+    while True:
+        if state == "literals":
+            for _ in range(gamma(0)):
+                out.append(read_byte())
+            state = "new" if read_bit() else "last"
+        elif state == "last":
+            for _ in range(gamma(0)):
+                out.append(out[-last_offset])
+            state = "new" if read_bit() else "literals"
+        else:
+            last_offset = gamma(1)
+            if last_offset == 256:
+                return bytes(out), st["pos"]
+            last_offset = last_offset * 128 - (read_byte() >> 1)
+            st["backtrack"] = True
+            for _ in range(gamma(0) + 1):
+                out.append(out[-last_offset])
+            state = "new" if read_bit() else "literals"
+
+# Uso: python3 depack_referencia.py plet5 pengadv_4k.plet5 primeros_4k_de_rom.u7
+if __name__ == "__main__":
+    fmt, packed, expected = sys.argv[1], sys.argv[2], sys.argv[3]
+    src = open(packed, "rb").read()
+    out, used = {"plet5": pletter, "bb": bitbuster, "apl": aplib, "zx0": zx0}[fmt](src)
+    exp = open(expected, "rb").read()
+    print(fmt, "ok" if out == exp else "MISMATCH", len(out), "consumed", used, "of", len(src))
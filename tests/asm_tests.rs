@@ -0,0 +1,253 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - ENSAMBLADOR Z80                                  ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::disasm::decode;
+    use msx2_processor::{Assembler, BloadHeader, MSX2Processor, OutputFormat, RomHeader, SymbolTable};
+
+    fn assemble(source: &str) -> Vec<u8> {
+        match Assembler::new(OutputFormat::Bin).assemble(source) {
+            Ok(assembly) => assembly.code,
+            Err(errors) => panic!("{}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n")),
+        }
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        match Assembler::new(OutputFormat::Bin).assemble(source) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_instruction_encoding() {
+        let code = assemble(
+            "        ORG 4000h
+        LD A,0Fh
+        LD (IX+5),0AAh
+        BIT 2,(IY-1)
+        SET 0,(IX+1),B
+        LD A,(IX)
+        EX AF,AF'
+        IM 1
+        RST 38h
+        OUT (C),0
+        IN A,(99h)
+        LD HL,(0F3E9h)
+        SUB A,B
+        ADD C
+        JP (IY)
+        JR $",
+        );
+        assert_eq!(
+            code,
+            [
+                0x3E, 0x0F, 0xDD, 0x36, 0x05, 0xAA, 0xFD, 0xCB, 0xFF, 0x56, 0xDD, 0xCB, 0x01, 0xC0, 0xDD, 0x7E, 0x00,
+                0x08, 0xED, 0x56, 0xFF, 0xED, 0x71, 0xDB, 0x99, 0x2A, 0xE9, 0xF3, 0x90, 0x81, 0xFD, 0xE9, 0x18, 0xFE,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_every_decoded_instruction_assembles_back() {
+        let symbols = SymbolTable::new();
+        for op in 0..=255u8 {
+            for prefix in [&[][..], &[0xCB], &[0xED], &[0xDD], &[0xFD], &[0xDD, 0xCB, 0x05], &[0xFD, 0xCB, 0x05]] {
+                let code = [prefix, &[op, 0x34, 0x12, 0x00]].concat();
+                let instruction = decode(&code, 0x4000);
+                if instruction.is_data() {
+                    continue;
+                }
+                let text = instruction.text(&symbols);
+                let bytes = assemble(&format!("        ORG 4000h\n        {}", text));
+                // Los duplicados (ED 63h, DD CB d 40h...) vuelven en su forma
+                // documentada, que nunca es más larga
+                assert_eq!(decode(&bytes, 0x4000).text(&symbols), text, "{:02X?}", code);
+                assert!(bytes.len() <= instruction.len(), "{}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_labels_locals_and_expressions() {
+        let code = assemble(
+            "SIZE    EQU FIN-INICIO        ; referencia adelantada
+        ORG 0C000h
+INICIO: LD BC,SIZE
+uno:    LD B,3
+.loop   DJNZ .loop
+dos     LD B,4
+.loop:  DJNZ .loop
+        JP uno.loop
+        DB LOW(1234h),HIGH(1234h),'A'+1,%101,101b,0x1F,$20,#30,17o,-1
+        DW $,2*(3+4),1<<4|1,(10>3)&&(2=2)
+        DB \"a;b\",0
+FIN:",
+        );
+        assert_eq!(&code[..3], [0x01, 36, 0]);
+        assert_eq!(&code[3..7], [0x06, 0x03, 0x10, 0xFE]);
+        assert_eq!(&code[11..14], [0xC3, 0x05, 0xC0]);
+        assert_eq!(&code[14..24], [0x34, 0x12, 0x42, 5, 5, 0x1F, 0x20, 0x30, 15, 0xFF]);
+        assert_eq!(&code[24..32], [0x18, 0xC0, 14, 0, 0x11, 0, 1, 0]);
+        assert_eq!(&code[32..], b"a;b\0");
+
+        let assembly = Assembler::new(OutputFormat::Bin).assemble("start: nop\n.x: ret").unwrap();
+        assert_eq!(assembly.symbols.get("start.x"), Some(&0xC001));
+    }
+
+    #[test]
+    fn test_conditionals_and_macros() {
+        let code = assemble(
+            "DEBUG = 0
+        IF DEBUG
+        DB 1
+        ELSE
+          IFNDEF NADA
+        DB 2
+          ENDIF
+        ENDIF
+        IFDEF DEBUG
+        DB 3
+        ENDIF
+ESPERA  MACRO veces, valor
+        LD B,veces
+espera\\@:
+        DJNZ espera\\@
+        LD A,valor
+        ENDM
+        MACRO SALIDA puerto
+        OUT (puerto),A
+        ENDM
+        ESPERA 10, 'x'
+        ESPERA 20, 1
+        SALIDA 98h",
+        );
+        assert_eq!(code, [2, 3, 0x06, 10, 0x10, 0xFE, 0x3E, b'x', 0x06, 20, 0x10, 0xFE, 0x3E, 1, 0xD3, 0x98]);
+        assert!(errors("  IF 1\n  NOP").iter().any(|e| e.contains("Falta ENDIF")));
+        assert!(errors("  IF ADELANTE\n  ENDIF\nADELANTE:").iter().any(|e| e.contains("IF necesita un valor")));
+    }
+
+    #[test]
+    fn test_include_and_incbin() {
+        let assembly = Assembler::new(OutputFormat::Bin)
+            .file("vdp.inc", b"VDP_DATA EQU 98h\nSALTO:  JP SALTO\n")
+            .file("logo.bin", &[1, 2, 3, 4, 5, 6])
+            .assemble("        ORG 9000h\n        INCLUDE \"vdp.inc\"\n        OUT (VDP_DATA),A\n        INCBIN \"logo.bin\",2,3\n")
+            .unwrap();
+        assert_eq!(assembly.code, [0xC3, 0x00, 0x90, 0xD3, 0x98, 3, 4, 5]);
+        assert!(assembly.lines.iter().any(|l| l.file == "vdp.inc" && l.line == 2 && l.bytes == [0xC3, 0x00, 0x90]));
+
+        let failed = Assembler::new(OutputFormat::Bin).assemble("  NOP\n  INCLUDE \"falta.asm\"").unwrap_err();
+        assert_eq!((failed[0].file.as_str(), failed[0].line), ("main.asm", 2));
+        assert!(failed[0].message.contains("falta.asm"));
+
+        let incbin = |args: &str| {
+            let source = format!("  INCBIN \"logo.bin\",{}", args);
+            match Assembler::new(OutputFormat::Bin).file("logo.bin", &[1, 2, 3]).assemble(&source) {
+                Ok(assembly) => Ok(assembly.code),
+                Err(errors) => Err(errors[0].message.clone()),
+            }
+        };
+        assert_eq!(incbin("1"), Ok(vec![2, 3]));
+        assert!(incbin("-1").unwrap_err().contains("negativos"));
+        assert!(incbin("1,-1").unwrap_err().contains("negativos"));
+        assert!(incbin("2,7FFFFFFFFFFFFFFFh").unwrap_err().contains("no llega"));
+        assert!(incbin("7FFFFFFFFFFFFFFFh").unwrap_err().contains("no llega"));
+    }
+
+    #[test]
+    fn test_rom_output_with_automatic_header() {
+        let assembly = Assembler::new(OutputFormat::Rom).assemble("START:  DI\n        JR START\n").unwrap();
+        assert_eq!((assembly.origin, assembly.entry), (0x4010, 0x4010));
+        let rom = assembly.to_rom().unwrap();
+        assert_eq!(rom.len(), 0x2000);
+        assert_eq!(RomHeader::parse(&rom).unwrap().init, 0x4010);
+        assert_eq!(&rom[0x10..0x13], [0xF3, 0x18, 0xFD]);
+        assert!(rom[0x13..].iter().all(|&b| b == 0xFF));
+
+        // Con su propia cabecera no se añade otra; 8 KB y un byte pasan a 16 KB
+        let source = "        ORG 8000h\n        DB \"AB\"\n        DW INICIO\n        DS 12\nINICIO: RET\n        DS 2000h-11h,0\n        DB 1";
+        let rom = Assembler::new(OutputFormat::Rom).assemble(source).unwrap().to_rom().unwrap();
+        assert_eq!(rom.len(), 0x4000);
+        assert_eq!(RomHeader::parse(&rom).unwrap().init, 0x8010);
+
+        let overlap = Assembler::new(OutputFormat::Rom).assemble("        ORG 4000h\n        NOP").unwrap();
+        assert!(overlap.to_rom().unwrap_err().contains("ORG 4010h"));
+    }
+
+    #[test]
+    fn test_bin_and_com_output() {
+        let assembly = Assembler::new(OutputFormat::Bin)
+            .assemble("        ORG 0D000h\nVARS:   DS 4\nMAIN:   LD HL,VARS\n        RET\n        END MAIN")
+            .unwrap();
+        let bin = assembly.to_bin().unwrap();
+        let (header, body) = BloadHeader::parse(&bin).unwrap();
+        assert_eq!((header.start, header.end, header.exec), (0xD004, 0xD007, 0xD004));
+        assert_eq!(body, [0x21, 0x00, 0xD0, 0xC9]);
+
+        let com = Assembler::new(OutputFormat::Com).assemble("        LD C,0\n        JP 5").unwrap();
+        assert_eq!(com.to_com().unwrap(), [0x0E, 0x00, 0xC3, 0x05, 0x00]);
+        let misplaced = Assembler::new(OutputFormat::Com).assemble("        ORG 200h\n        RET").unwrap();
+        assert!(misplaced.to_com().is_err());
+    }
+
+    #[test]
+    fn test_errors_carry_file_and_line() {
+        // Los errores de la primera pasada se dan todos juntos
+        let found = errors("  NOP\n  LD A,300\n  JR 1000h\nX: NOP\nX: NOP\n  LD Q,1\n  FOO 3\n  JP NINGUNO");
+        assert_eq!(found.len(), 5, "{:?}", found);
+        assert_eq!(found[0], "main.asm:2: Valor fuera de 8 bits: 300");
+        assert!(found[1].starts_with("main.asm:3: Salto relativo fuera de alcance"));
+        assert_eq!(found[2], "main.asm:5: Símbolo duplicado: X");
+        assert_eq!(found[3], "main.asm:6: Operandos no válidos para LD: Q,1");
+        assert_eq!(found[4], "main.asm:7: Instrucción desconocida: FOO");
+
+        // Los símbolos que faltan se ven al terminar la primera
+        let found = errors("  JP NINGUNO\n  LD (IX+OTRO),A\n  JR LEJOS\n  DS 200\nLEJOS:");
+        assert_eq!(found.len(), 3, "{:?}", found);
+        assert_eq!(found[0], "main.asm:1: Símbolo no definido: NINGUNO");
+        assert_eq!(found[1], "main.asm:2: Símbolo no definido: OTRO");
+        assert_eq!(found[2], "main.asm:3: Salto relativo fuera de alcance: 200 bytes");
+
+        // Aritmética en los límites de 64 bits: error de ensamblado, no pánico
+        let found = errors("MIN EQU -7FFFFFFFFFFFFFFFh-1
+  DB 1/0
+  DB 5%0
+  DB MIN/-1
+  DB MIN%-1
+  DB LOW(-MIN)");
+        assert_eq!(found.len(), 4, "{:?}", found);
+        assert_eq!(found[0], "main.asm:2: División por cero o desbordada");
+        assert_eq!(found[3], "main.asm:5: División por cero o desbordada");
+    }
+
+    #[test]
+    fn test_processor_round_trip_and_load_info() {
+        let mut processor = MSX2Processor::new(256, 212);
+        let ikari = std::fs::read(format!("{}/rooms/Ikari (Japan).rom", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        // El desensamblado de un cartucho vuelve a dar el mismo cartucho
+        let listing = processor.disassemble_rom(&ikari[..0x4000]);
+        assert_eq!(processor.assemble(&listing, "rom"), &ikari[..0x4000]);
+
+        // Los símbolos del MSX se conocen sin EQU
+        processor.add_asm_file("texto.inc", b"DB \"HOLA\",0");
+        let source = "START:  LD HL,MENSAJE\n.bucle: LD A,(HL)\n        OR A\n        RET Z\n        CALL CHPUT\n        INC HL\n        JR .bucle\nMENSAJE: INCLUDE \"texto.inc\"";
+        let rom = processor.assemble(source, "rom");
+        assert_eq!(&rom[0x16..0x19], [0xCD, 0xA2, 0x00]);
+        let info = processor.assemble_load_info(source, "rom").unwrap();
+        assert_eq!((info.get_load_address(), info.get_binary_size(), info.get_start_address()), (0x4000, 0x2000, 0x4010));
+
+        let info = processor.assemble_load_info("  ORG 0C000h\n  RET", "bin").unwrap();
+        assert_eq!((info.get_load_address(), info.get_binary_size(), info.get_start_address()), (0xC000, 1, 0xC000));
+        assert_eq!(processor.assemble_load_info("  RET", "com").unwrap().get_load_address(), 0x0100);
+
+        let report = processor.assemble_report("  RET", "rom");
+        assert_eq!(report, r#"{"ok":true,"format":"rom","origin":16400,"end":16400,"entry":16400,"size":8192,"symbols":0,"errors":[]}"#);
+        let report = processor.assemble_report("  JP NADA", "bin");
+        assert_eq!(report, r#"{"ok":false,"errors":[{"file":"main.asm","line":1,"message":"Símbolo no definido: NADA"}]}"#);
+        assert!(processor.assemble("  RET", "dsk").is_empty());
+    }
+}
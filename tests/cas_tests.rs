@@ -0,0 +1,152 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - IMÁGENES DE CINTA .CAS                           ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::cas::{BASIC_TEXT_START, CAS_HEADER};
+    use msx2_processor::{BloadHeader, CasFileType, CasImage, MSX2Processor};
+
+    fn file_header(id: u8, name: &str) -> Vec<u8> {
+        let mut block = vec![id; 10];
+        block.extend(format!("{:<6}", name).bytes().take(6));
+        block
+    }
+
+    fn binary_block(header: BloadHeader, body: &[u8]) -> Vec<u8> {
+        let mut block = header.to_raw_bytes().to_vec();
+        block.extend_from_slice(body);
+        block
+    }
+
+    /// Cinta típica de juego: cargador BASIC ASCII + binario
+    fn sample_tape() -> Vec<u8> {
+        let mut text = b"10 BLOAD\"CAS:\",R\r\n".to_vec();
+        text.push(0x1A);
+        text.resize(256, 0x1A);
+
+        let body: Vec<u8> = (0..0x20u8).collect();
+        CasImage::from_blocks(vec![
+            file_header(0xEA, "LOADER"),
+            text,
+            file_header(0xD0, "GAME"),
+            binary_block(BloadHeader::new(0x9000, 0x901F, 0x9010), &body),
+        ])
+        .to_bytes()
+    }
+
+    #[test]
+    fn test_blocks_split_on_aligned_headers() {
+        let raw = sample_tape();
+        let image = CasImage::parse(&raw).unwrap();
+        assert_eq!(image.blocks().len(), 4);
+        for block in image.blocks() {
+            assert_eq!(block.offset % 8, 0);
+            assert_eq!(raw[block.offset..block.offset + 8], CAS_HEADER);
+        }
+    }
+
+    #[test]
+    fn test_files_identified_by_type_and_name() {
+        let image = CasImage::parse(&sample_tape()).unwrap();
+        let files = image.files();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].file_type, CasFileType::Ascii);
+        assert_eq!(files[0].name, "LOADER");
+        assert_eq!(files[1].file_type, CasFileType::Binary);
+        assert_eq!(files[1].name, "GAME");
+    }
+
+    #[test]
+    fn test_bload_header_decoded() {
+        let image = CasImage::parse(&sample_tape()).unwrap();
+        let game = &image.files()[1];
+        assert_eq!(game.bload_header(), Some(BloadHeader::new(0x9000, 0x901F, 0x9010)));
+        assert_eq!(game.contents(), (0..0x20u8).collect::<Vec<_>>());
+        assert_eq!(game.loader_command(), "BLOAD\"CAS:GAME\",R");
+    }
+
+    #[test]
+    fn test_ascii_file_stops_at_eof() {
+        let image = CasImage::parse(&sample_tape()).unwrap();
+        let loader = &image.files()[0];
+        assert_eq!(loader.contents(), b"10 BLOAD\"CAS:\",R\r\n");
+        assert_eq!(loader.loader_command(), "RUN\"CAS:LOADER\"");
+    }
+
+    #[test]
+    fn test_ascii_file_spanning_several_blocks() {
+        let first = vec![b'A'; 256];
+        let mut second = vec![b'B'; 10];
+        second.resize(256, 0x1A);
+        let raw = CasImage::from_blocks(vec![file_header(0xEA, "LONG"), first, second]).to_bytes();
+
+        let image = CasImage::parse(&raw).unwrap();
+        assert_eq!(image.files().len(), 1);
+        let contents = image.files()[0].contents();
+        assert_eq!(contents.len(), 266);
+        assert!(contents.ends_with(b"BBBBBBBBBB"));
+    }
+
+    #[test]
+    fn test_basic_program_trimmed_at_end_marker() {
+        // 10 PRINT: enlace, número de línea, token 91h, fin de línea, enlace nulo
+        let program = vec![0x07, 0x80, 0x0A, 0x00, 0x91, 0x00, 0x00, 0x00];
+        let mut block = program.clone();
+        block.extend_from_slice(&[0, 0, 0, 0]);
+        let raw = CasImage::from_blocks(vec![file_header(0xD3, "PRG"), block]).to_bytes();
+
+        let image = CasImage::parse(&raw).unwrap();
+        let file = &image.files()[0];
+        assert_eq!(file.file_type, CasFileType::Basic);
+        assert_eq!(file.contents(), program);
+        assert_eq!(file.loader_command(), "CLOAD\"PRG\":RUN");
+    }
+
+    #[test]
+    fn test_headerless_blocks_are_custom() {
+        let raw = CasImage::from_blocks(vec![vec![1, 2, 3, 4], vec![5, 6]]).to_bytes();
+        let image = CasImage::parse(&raw).unwrap();
+        assert_eq!(image.files().len(), 2);
+        assert!(image.files().iter().all(|f| f.file_type == CasFileType::Custom));
+    }
+
+    #[test]
+    fn test_invalid_image_rejected() {
+        assert!(CasImage::parse(&[0u8; 64]).is_err());
+        let mut processor = MSX2Processor::new(256, 212);
+        assert!(processor.load_cas_image(&[0u8; 64]).starts_with("❌"));
+        assert_eq!(processor.list_cas_files(), "[]");
+    }
+
+    #[test]
+    fn test_processor_load_info_and_listing() {
+        let mut processor = MSX2Processor::new(256, 212);
+        assert!(processor.load_cas_image(&sample_tape()).starts_with("✅"));
+
+        let listing = processor.list_cas_files();
+        assert!(listing.contains(r#""name":"GAME","type":"binary""#));
+        assert!(listing.contains(r#""start":36864,"end":36895,"exec":36880"#));
+
+        let info = processor.cas_file_load_info(1).unwrap();
+        assert_eq!(info.get_load_address(), 0x9000);
+        assert_eq!(info.get_binary_size(), 0x20);
+        assert_eq!(info.get_start_address(), 0x9010);
+        assert!(processor.cas_file_load_info(0).is_none());
+        assert_eq!(processor.extract_cas_file(1).len(), 0x20);
+        assert_eq!(processor.get_cas_image(), sample_tape());
+    }
+
+    #[test]
+    fn test_basic_load_info_at_txttab() {
+        let program = vec![0x07, 0x80, 0x0A, 0x00, 0x91, 0x00, 0x00, 0x00];
+        let raw = CasImage::from_blocks(vec![file_header(0xD3, "PRG"), program]).to_bytes();
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.load_cas_image(&raw);
+
+        let info = processor.cas_file_load_info(0).unwrap();
+        assert_eq!(info.get_load_address(), BASIC_TEXT_START as u32);
+        assert_eq!(info.get_binary_size(), 8);
+    }
+}
@@ -0,0 +1,314 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - REGISTRO DE CÓDIGO Y DATOS (CDL)                 ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::cdl::{uploads, CDL_CODE, CDL_DATA, CDL_MAGIC, CDL_OPCODE, CDL_VDP, CDL_VRAM};
+    use msx2_processor::{
+        BankMarks, ByteKind, CodeAnalyzer, CodeDataLog, GraphicsFinder, MSX2Processor, Machine, MapperType,
+        SlotContent, SlotId,
+    };
+
+    const CARTUCHO: SlotId = SlotId { primary: 1, secondary: 0 };
+
+    /// Cartucho de 16 KB en 4000h con `code` en 4010h y `data` en 4040h
+    fn cartridge(code: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut rom = vec![0u8; 0x4000];
+        rom[..4].copy_from_slice(&[b'A', b'B', 0x10, 0x40]);
+        rom[0x10..0x10 + code.len()].copy_from_slice(code);
+        rom[0x40..0x40 + data.len()].copy_from_slice(data);
+        rom
+    }
+
+    /// Máquina con la ROM en 1-0 (página 1) y el CDL ya puesto
+    fn logged(rom: &[u8], steps: usize) -> Machine {
+        let mut machine = Machine::with_ram();
+        machine.bus.slots.insert(CARTUCHO, SlotContent::Rom { data: rom.to_vec(), base: 0x4000 });
+        machine.bus.io_write(0xA8, 0xF7);
+        machine.cpu.regs.pc = 0x4010;
+        machine.cdl = Some(CodeDataLog::new(CARTUCHO, rom.len()));
+        for _ in 0..steps {
+            machine.step();
+        }
+        machine
+    }
+
+    fn read_room(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/rooms/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    #[test]
+    fn test_code_and_data_marks() {
+        let code = [
+            0x21, 0x40, 0x40, // LD HL,4040h
+            0x7E, // LD A,(HL)
+            0xD3, 0x98, // OUT (98h),A
+            0x23, // INC HL
+            0x7E, // LD A,(HL)
+            0x18, 0xFE, // JR $
+        ];
+        let machine = logged(&cartridge(&code, &[0x11, 0x22]), 10);
+        let cdl = machine.cdl.as_ref().unwrap();
+        assert_eq!(cdl.flags(0x10), CDL_CODE | CDL_OPCODE);
+        // Operandos: ejecutados pero no principio de instrucción
+        assert_eq!((cdl.flags(0x11), cdl.flags(0x12)), (CDL_CODE, CDL_CODE));
+        assert_eq!(cdl.flags(0x15), CDL_CODE);
+        assert_eq!(cdl.flags(0x40), CDL_DATA | CDL_VRAM);
+        // Leído pero no enviado al VDP
+        assert_eq!(cdl.flags(0x41), CDL_DATA);
+        assert_eq!((cdl.flags(0x00), cdl.flags(0x1A)), (0, 0));
+        assert_eq!(cdl.count(CDL_OPCODE), 6);
+        assert_eq!(cdl.count(CDL_CODE), 10);
+    }
+
+    #[test]
+    fn test_vram_uploads() {
+        let code = [
+            0x21, 0x40, 0x40, // LD HL,4040h
+            0x01, 0x98, 0x20, // LD BC,2098h
+            0xED, 0xB3, // OTIR
+            0x3A, 0x70, 0x40, // LD A,(4070h)
+            0xD3, 0x99, // OUT (99h),A
+            0x18, 0xFE, // JR $
+        ];
+        let mut data: Vec<u8> = (0..32u8).map(|i| i.wrapping_mul(7)).collect();
+        data.resize(0x30, 0);
+        data.push(0x87);
+        let machine = logged(&cartridge(&code, &data), 40);
+        let cdl = machine.cdl.as_ref().unwrap();
+        assert!((0x40..0x60).all(|o| cdl.flags(o) == CDL_DATA | CDL_VRAM));
+        assert_eq!(cdl.flags(0x60), 0);
+        assert_eq!(cdl.flags(0x70), CDL_DATA | CDL_VDP);
+        assert_eq!(uploads(cdl.marks()), vec![(0x40, 32)]);
+
+        // Los huecos cortos se juntan; los tramos sueltos no cuentan
+        let mut marks = vec![0u8; 0x100];
+        marks[0x10..0x20].fill(CDL_VRAM);
+        marks[0x28..0x30].fill(CDL_VRAM);
+        marks[0x80..0x84].fill(CDL_VRAM);
+        assert_eq!(uploads(&marks), vec![(0x10, 0x20)]);
+        marks[0x24] = CDL_CODE;
+        assert_eq!(uploads(&marks), vec![(0x10, 0x10), (0x28, 8)]);
+    }
+
+    #[test]
+    fn test_megarom_banks() {
+        // ASCII 8K: 7000h elige el banco de 8000h-9FFFh
+        let mut rom = vec![0xFFu8; 4 * 0x2000];
+        rom[0x2000] = 0x55;
+        rom[0x4000..0x4002].copy_from_slice(&[0x00, 0xC9]);
+        let mut machine = Machine::with_ram();
+        machine.bus.slots.insert(CARTUCHO, SlotContent::megarom(rom.clone(), MapperType::Ascii8));
+        machine.bus.io_write(0xA8, 0xD7);
+        let code = [
+            0x31, 0x00, 0xF0, // LD SP,0F000h
+            0x3E, 0x01, 0x32, 0x00, 0x70, // LD A,1 / LD (7000h),A
+            0x3A, 0x00, 0x80, // LD A,(8000h)
+            0x3E, 0x02, 0x32, 0x00, 0x70, // LD A,2 / LD (7000h),A
+            0xCD, 0x00, 0x80, // CALL 8000h
+            0x18, 0xFE, // JR $
+        ];
+        machine.load(0xC000, &code);
+        machine.cpu.regs.pc = 0xC000;
+        assert_eq!(machine.bus.slots.content(CARTUCHO).rom_size(), rom.len());
+        machine.cdl = Some(CodeDataLog::new(CARTUCHO, rom.len()));
+        for _ in 0..10 {
+            machine.step();
+        }
+        let cdl = machine.cdl.as_ref().unwrap();
+        // Misma dirección 8000h, bancos distintos
+        assert_eq!(cdl.flags(0x2000), CDL_DATA);
+        assert_eq!(cdl.flags(0x4000), CDL_CODE | CDL_OPCODE);
+        assert_eq!(cdl.flags(0x4001), CDL_CODE | CDL_OPCODE);
+        assert_eq!(cdl.flags(0x0000), 0);
+        let banks = cdl.banks(0x2000);
+        assert_eq!(banks.len(), 4);
+        assert_eq!(banks[1], BankMarks { bank: 1, code: 0, data: 1, vram: 0, vdp: 0 });
+        assert_eq!(banks[2], BankMarks { bank: 2, code: 2, data: 0, vram: 0, vdp: 0 });
+        // El código que corre en la RAM no se marca
+        assert_eq!(cdl.count(0xFF), 3);
+    }
+
+    #[test]
+    fn test_rooms_vram_upload_hints() {
+        let rom = read_room("Vampire Killer (Japan, Europe).rom");
+        let mut machine = Machine::with_ram();
+        machine.bus.slots.insert(CARTUCHO, SlotContent::megarom(rom.clone(), MapperType::Konami));
+        machine.bus.io_write(0xA8, 0xD7);
+        let uploader = [
+            0x3E, 0x05, 0x32, 0x00, 0x80, // LD A,5 / LD (8000h),A
+            0x21, 0x00, 0x80, // LD HL,8000h
+            0x01, 0x98, 0x00, // LD BC,0098h
+            0xED, 0xB3, // OTIR (256 bytes)
+            0xED, 0xB3, // OTIR (256 más)
+            0x18, 0xFE, // JR $
+        ];
+        machine.load(0xC000, &uploader);
+        machine.cpu.regs.pc = 0xC000;
+        machine.cdl = Some(CodeDataLog::new(CARTUCHO, rom.len()));
+        for _ in 0..600 {
+            machine.step();
+        }
+        let cdl = machine.cdl.as_ref().unwrap();
+        let start = 5 * 0x2000;
+        assert!((start..start + 512).all(|o| cdl.flags(o) & CDL_VRAM != 0));
+        assert_eq!(cdl.count(CDL_VRAM), 512);
+        assert_eq!(uploads(cdl.marks()), vec![(start, 512)]);
+
+        let regions = GraphicsFinder::new(0).hints(cdl.marks()).find(&rom);
+        let first = &regions[0];
+        assert_eq!((first.offset, first.length, first.score), (start, 512, 100));
+        assert_eq!((first.bank, first.bank_offset), (5, 0));
+        // Nada de lo demás se solapa con la subida
+        assert!(regions[1..].iter().all(|r| r.offset + r.length <= start || start + 512 <= r.offset));
+    }
+
+    #[test]
+    fn test_disassembler_hints() {
+        let code = [
+            0x21, 0x20, 0x40, // LD HL,4020h
+            0xE5, // PUSH HL
+            0xC9, // RET: el flujo estático se pierde aquí
+        ];
+        let mut rom = cartridge(&code, &[]);
+        rom[0x20..0x25].copy_from_slice(&[0x3A, 0x30, 0x40, 0x18, 0xFE]); // LD A,(4030h) / JR $
+        rom[0x30] = 0x3E;
+        let mut machine = Machine::with_ram();
+        machine.cpu.regs.sp = 0xF000;
+        machine.bus.slots.insert(CARTUCHO, SlotContent::Rom { data: rom.clone(), base: 0x4000 });
+        machine.bus.io_write(0xA8, 0xF7);
+        machine.cpu.regs.pc = 0x4010;
+        machine.cdl = Some(CodeDataLog::new(CARTUCHO, rom.len()));
+        for _ in 0..6 {
+            machine.step();
+        }
+        let marks = machine.cdl.as_ref().unwrap().marks().to_vec();
+
+        let plain = CodeAnalyzer::for_rom(&rom).trace(&rom);
+        assert!(!plain.instructions.contains(&0x20));
+        let hinted = CodeAnalyzer::for_rom(&rom).cdl(&marks).analyze(&rom);
+        assert!(hinted.instructions.contains(&0x20) && hinted.instructions.contains(&0x23));
+        assert!((0x20..0x25).all(|o| hinted.kinds[o] == ByteKind::Code));
+        assert_eq!(hinted.kinds[0x30], ByteKind::Data);
+        // Los operandos no se vuelven instrucciones
+        assert!(!hinted.instructions.contains(&0x21));
+    }
+
+    #[test]
+    fn test_export_import() {
+        let mut cdl = CodeDataLog::new(SlotId { primary: 2, secondary: 1 }, 0x4000);
+        cdl.merge(&{
+            let mut marks = vec![0u8; 0x4000];
+            marks[0x10] = CDL_CODE | CDL_OPCODE;
+            marks[0x3FFF] = CDL_VRAM | CDL_DATA;
+            marks
+        })
+        .unwrap();
+        let bytes = cdl.to_bytes();
+        assert!(bytes.starts_with(CDL_MAGIC));
+        assert_eq!(&bytes[6..12], &[1, 2 | 1 << 2, 0x00, 0x40, 0x00, 0x00]);
+        assert_eq!(bytes.len(), 12 + 0x4000);
+        let back = CodeDataLog::from_bytes(&bytes).unwrap();
+        assert_eq!(back.slot, SlotId { primary: 2, secondary: 1 });
+        assert_eq!(back.marks(), cdl.marks());
+
+        assert!(CodeDataLog::from_bytes(b"MSXTRACE").unwrap_err().contains("No es"));
+        assert!(CodeDataLog::from_bytes(&bytes[..8]).unwrap_err().contains("incompleta"));
+        assert!(CodeDataLog::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err().contains("truncado"));
+        let mut version = bytes.clone();
+        version[6] = 2;
+        assert!(CodeDataLog::from_bytes(&version).unwrap_err().contains("Versión"));
+    }
+
+    #[test]
+    fn test_merge_pause_and_clear() {
+        let code = [0x3A, 0x40, 0x40, 0x18, 0xFE]; // LD A,(4040h) / JR $
+        let rom = cartridge(&code, &[0x99]);
+        let mut machine = Machine::with_ram();
+        machine.bus.slots.insert(CARTUCHO, SlotContent::Rom { data: rom.clone(), base: 0x4000 });
+        machine.bus.io_write(0xA8, 0xF7);
+        machine.cpu.regs.pc = 0x4010;
+        let mut cdl = CodeDataLog::new(CARTUCHO, rom.len());
+        cdl.active = false;
+        machine.cdl = Some(cdl);
+        for _ in 0..4 {
+            machine.step();
+        }
+        assert_eq!(machine.cdl.as_ref().unwrap().count(0xFF), 0);
+
+        let machine = logged(&rom, 4);
+        let mut cdl = machine.cdl.clone().unwrap();
+        let mut other = vec![0u8; rom.len()];
+        other[0x100] = CDL_VRAM;
+        other[0x40] = CDL_VDP;
+        cdl.merge(&other).unwrap();
+        assert_eq!(cdl.flags(0x40), CDL_DATA | CDL_VDP);
+        assert_eq!(cdl.flags(0x100), CDL_VRAM);
+        assert!(cdl.merge(&[0; 16]).unwrap_err().contains("otra ROM"));
+        cdl.clear();
+        assert_eq!(cdl.count(0xFF), 0);
+        assert_eq!(cdl.marks().len(), rom.len());
+    }
+
+    #[test]
+    fn test_processor_cdl() {
+        let code = [
+            0x21, 0x40, 0x40, // LD HL,4040h
+            0x01, 0x98, 0x10, // LD BC,1098h
+            0xED, 0xB3, // OTIR
+            0x18, 0xFE, // JR $
+        ];
+        let data: Vec<u8> = (1..=16).collect();
+        let rom = cartridge(&code, &data);
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.insert_ram(3, 0);
+        processor.io_write(0xA8, 0xF7);
+        assert!(processor.cdl_start(1, 0).contains("No hay ROM"));
+        assert!(processor.cdl_start(4, 0).starts_with("❌"));
+        assert!(processor.cdl_export().is_empty());
+        assert_eq!(processor.cdl_summary(0), "null");
+        processor.insert_rom(1, 0, &rom);
+        assert_eq!(processor.cdl_start(1, 0), "✅ CDL activo para 16 KB del slot 1-0");
+        processor.set_register("PC", 0x4010);
+        processor.run_to(0x4018, 10_000);
+        let summary = processor.cdl_summary(0);
+        assert!(summary.starts_with(r#"{"slot":"1-0","size":16384,"code":8,"data":16,"vram":16,"vdp":0,"uploads":1,"#), "{}", summary);
+        assert!(summary.contains(r#"{"bank":1,"code":0"#), "{}", summary);
+
+        let export = processor.cdl_export();
+        processor.cdl_stop();
+        processor.step_into();
+        processor.cdl_clear();
+        assert!(processor.cdl_summary(0).contains(r#""code":0,"#));
+        assert_eq!(processor.cdl_import(&export), "✅ CDL de 16384 bytes cargado");
+        assert_eq!(processor.cdl_export(), export);
+        assert!(processor.cdl_import(b"MSXCDL").starts_with("❌"));
+        processor.cdl_resume();
+    }
+
+    #[test]
+    fn test_processor_hints() {
+        let code = [0x21, 0x20, 0x40, 0xE5, 0xC9]; // LD HL,4020h / PUSH HL / RET
+        let mut rom = cartridge(&code, &[]);
+        rom[0x20..0x24].copy_from_slice(&[0x3E, 0x42, 0x18, 0xFE]); // LD A,42h / JR $
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.insert_ram(3, 0);
+        processor.io_write(0xA8, 0xF7);
+        processor.insert_rom(1, 0, &rom);
+        processor.set_register("SP", 0xF000);
+        processor.set_register("PC", 0x4010);
+        processor.cdl_start(1, 0);
+        processor.run_to(0x4022, 10_000);
+        let cdl = processor.cdl_export();
+
+        assert!(!processor.disassemble_rom(&rom).contains("LD A,42h"));
+        let listing = processor.disassemble_rom_with_cdl(&rom, &cdl);
+        assert!(listing.contains("LD A,42h"), "{}", listing);
+        assert_eq!(processor.disassemble_rom_with_cdl(&rom, &[]), processor.disassemble_rom(&rom));
+        assert!(processor.disassemble_rom_with_cdl(&rom[..0x2000], &cdl).contains("otra ROM"));
+        assert!(processor.find_graphics_with_cdl(&rom, 0, b"nada").starts_with("❌"));
+        assert_eq!(processor.find_graphics_with_cdl(&rom, 0, &[]), processor.find_graphics(&rom, 0));
+    }
+}
@@ -0,0 +1,256 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - SEPARACIÓN DE CÓDIGO Y DATOS                     ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::{
+        ByteKind, CodeAnalyzer, Disassembler, GraphicsFinder, MSX2Processor, MapperType, RomHeader, SymbolTable,
+    };
+
+    /// Cartucho de 16 KB en 4000h con INIT en 4010h y `code` a partir de ahí
+    fn cartridge(code: &[u8]) -> Vec<u8> {
+        let mut rom = vec![0u8; 0x4000];
+        rom[..16].copy_from_slice(&RomHeader { init: 0x4010, statement: 0, device: 0, text: 0 }.to_bytes());
+        rom[0x10..0x10 + code.len()].copy_from_slice(code);
+        rom
+    }
+
+    fn read_rom(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/rooms/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    #[test]
+    fn test_follows_calls_and_branches() {
+        let mut rom = cartridge(&[
+            0xCD, 0x20, 0x40, // 4010 CALL 4020h
+            0x28, 0x03, // 4013 JR Z,4018h
+            0xC3, 0x10, 0x40, // 4015 JP 4010h
+            0xC9, // 4018 RET
+        ]);
+        rom[0x20..0x22].copy_from_slice(&[0xAF, 0xC9]); // 4020 XOR A / RET
+        rom[0x22..0x30].copy_from_slice(b"PULSA ESPACIO!!\0"[..14].as_ref());
+
+        let map = CodeAnalyzer::for_rom(&rom).analyze(&rom);
+        assert_eq!(map.mapper, MapperType::Plain);
+        for offset in [0x10, 0x13, 0x15, 0x18, 0x20, 0x21] {
+            assert!(map.instructions.contains(&offset), "{:04X}", offset);
+        }
+        assert_eq!(map.kind(0x19), ByteKind::Data);
+        assert_eq!(map.kind(0x22), ByteKind::Text);
+        assert_eq!(map.kind(0x05), ByteKind::Data);
+        assert_eq!(map.labels.get(&0x20), Some(&0x4020));
+        assert_eq!(map.origin_of(0x20), Some(0x4000));
+    }
+
+    #[test]
+    fn test_pointer_jump_table() {
+        let mut rom = cartridge(&[
+            0x21, 0x00, 0x41, // 4010 LD HL,4100h
+            0x87, // 4013 ADD A,A
+            0x5F, // 4014 LD E,A
+            0x16, 0x00, // 4015 LD D,00h
+            0x19, // 4017 ADD HL,DE
+            0x7E, // 4018 LD A,(HL)
+            0x23, // 4019 INC HL
+            0x66, // 401A LD H,(HL)
+            0x6F, // 401B LD L,A
+            0xE9, // 401C JP (HL)
+        ]);
+        rom[0x100..0x106].copy_from_slice(&[0x00, 0x42, 0x10, 0x42, 0x00, 0x00]);
+        rom[0x200] = 0xC9;
+        rom[0x210] = 0xC9;
+
+        let map = CodeAnalyzer::for_rom(&rom).trace(&rom);
+        assert_eq!(map.kind(0x100), ByteKind::Data);
+        assert_eq!(map.kind(0x103), ByteKind::Data);
+        assert!(map.instructions.contains(&0x200));
+        assert!(map.instructions.contains(&0x210));
+        // 0000h no es una dirección del cartucho: la tabla termina ahí
+        assert_eq!(map.kind(0x104), ByteKind::Unknown);
+    }
+
+    #[test]
+    fn test_jp_instruction_table() {
+        let mut rom = cartridge(&[
+            0x21, 0x00, 0x41, // 4010 LD HL,4100h
+            0xE9, // 4013 JP (HL)
+        ]);
+        rom[0x100..0x106].copy_from_slice(&[0xC3, 0x00, 0x42, 0xC3, 0x10, 0x42]);
+        rom[0x200] = 0xC9;
+        rom[0x210] = 0xC9;
+
+        let map = CodeAnalyzer::for_rom(&rom).trace(&rom);
+        assert!(map.instructions.contains(&0x100));
+        assert!(map.instructions.contains(&0x103));
+        assert!(map.instructions.contains(&0x200));
+        assert!(map.instructions.contains(&0x210));
+    }
+
+    #[test]
+    fn test_interrupt_hook_routine() {
+        let mut rom = cartridge(&[
+            0x21, 0x00, 0x42, // 4010 LD HL,4200h
+            0x22, 0xA0, 0xFD, // 4013 LD (H.TIMI+1),HL
+            0xFB, // 4016 EI
+            0x18, 0xFE, // 4017 JR 4017h
+        ]);
+        rom[0x200..0x203].copy_from_slice(&[0x3E, 0x01, 0xC9]);
+
+        let map = CodeAnalyzer::for_rom(&rom).trace(&rom);
+        assert!(map.instructions.contains(&0x200));
+        assert!(map.instructions.contains(&0x202));
+    }
+
+    #[test]
+    fn test_konami_bank_switch() {
+        let mut rom = vec![0u8; 0x20000];
+        rom[..16].copy_from_slice(&RomHeader { init: 0x4010, statement: 0, device: 0, text: 0 }.to_bytes());
+        rom[0x10..0x19].copy_from_slice(&[
+            0x3E, 0x05, // 4010 LD A,05h
+            0x32, 0x00, 0x80, // 4012 LD (8000h),A
+            0xCD, 0x00, 0x80, // 4015 CALL 8000h
+            0xC9, // 4018 RET
+        ]);
+        // Banco 5, visto en 8000h
+        rom[5 * 0x2000..5 * 0x2000 + 2].copy_from_slice(&[0x00, 0xC9]);
+
+        let map = CodeAnalyzer::new(MapperType::Konami, 0x4000).entry(0x4010).trace(&rom);
+        assert_eq!(map.switches.len(), 1);
+        assert_eq!(map.switches[0].page, 0x8000);
+        assert_eq!(map.switches[0].bank, Some(5));
+        assert!(map.instructions.contains(&(5 * 0x2000 + 1)));
+        assert_eq!(map.origin_of(5 * 0x2000), Some(0x8000));
+        // El banco 2 que había en 8000h tras el reset no se tocó
+        assert_eq!(map.kind(2 * 0x2000), ByteKind::Unknown);
+    }
+
+    #[test]
+    fn test_ascii16_switch_and_unknown_bank() {
+        let mut rom = vec![0u8; 0x20000];
+        rom[0x10..0x1C].copy_from_slice(&[
+            0x3E, 0x03, // 4010 LD A,03h
+            0x32, 0x00, 0x70, // 4012 LD (7000h),A
+            0xCD, 0x10, 0x80, // 4015 CALL 8010h
+            0x32, 0x00, 0x60, // 4018 LD (6000h),A (A ya no se conoce)
+            0xC9, // 401B RET
+        ]);
+        rom[3 * 0x4000 + 0x10] = 0xC9;
+
+        let map = CodeAnalyzer::new(MapperType::Ascii16, 0x4000).entry(0x4010).trace(&rom);
+        assert!(map.instructions.contains(&(3 * 0x4000 + 0x10)));
+        assert_eq!(map.switches[0].bank, Some(6));
+        assert_eq!(map.switches[1].page, 0x4000);
+        assert_eq!(map.switches[1].bank, None);
+        // Tras un cambio a banco desconocido la página 4000h deja de seguirse
+        assert!(!map.instructions.contains(&0x1B));
+    }
+
+    #[test]
+    fn test_cb_ops_on_a_forget_its_value() {
+        let mut rom = vec![0u8; 0x20000];
+        rom[0x10..0x26].copy_from_slice(&[
+            0x3E, 0x03, // 4010 LD A,03h
+            0xCB, 0xD7, // 4012 SET 2,A
+            0x32, 0x00, 0x80, // 4014 LD (8000h),A
+            0x3E, 0x04, // 4017 LD A,04h
+            0xCB, 0x97, // 4019 RES 2,A
+            0x32, 0x00, 0xA0, // 401B LD (A000h),A
+            0x3E, 0x05, // 401E LD A,05h
+            0xCB, 0x47, // 4020 BIT 0,A
+            0x32, 0x00, 0x60, // 4022 LD (6000h),A
+            0xC9, // 4025 RET
+        ]);
+
+        let map = CodeAnalyzer::new(MapperType::Konami, 0x4000).entry(0x4010).trace(&rom);
+        let banks: Vec<_> = map.switches.iter().map(|s| s.bank).collect();
+        // BIT no escribe en A
+        assert_eq!(banks, [None, None, Some(5)]);
+    }
+
+    #[test]
+    fn test_mapper_guess() {
+        assert_eq!(MapperType::guess(&[0x32, 0x00, 0x50]), MapperType::Plain);
+        let mut rom = vec![0u8; 0x20000];
+        for (i, address) in [0x5000u16, 0x7000, 0x9000, 0xB000].iter().enumerate() {
+            rom[i * 3] = 0x32;
+            rom[i * 3 + 1..i * 3 + 3].copy_from_slice(&address.to_le_bytes());
+        }
+        assert_eq!(MapperType::guess(&rom), MapperType::KonamiScc);
+        assert_eq!(MapperType::from_name("ASCII16"), Some(MapperType::Ascii16));
+        assert_eq!(MapperType::Ascii16.bank_size(), 0x4000);
+        assert_eq!(MapperType::guess(&read_rom("Vampire Killer (Japan, Europe).rom")), MapperType::Konami);
+    }
+
+    #[test]
+    fn test_map_drives_disassembler_and_finder() {
+        let mut rom = cartridge(&[0x21, 0x20, 0x40, 0xC9]); // LD HL,4020h / RET
+        rom[0x20..0x2B].copy_from_slice(b"GAME \"OVER\"");
+        let map = CodeAnalyzer::for_rom(&rom).analyze(&rom);
+
+        let listing = Disassembler::new(SymbolTable::msx()).disassemble_map(&rom, &map, 0, 0x40, 0x4000).listing();
+        assert!(listing.contains("        LD HL,4020h"));
+        assert!(listing.contains("        RET "));
+        assert!(listing.contains(r#"DB "GAME ",22h,"OVER",22h"#));
+        // Tras el RET ya no se desensambla: son datos
+        assert!(listing.contains("        DB 00h,00h,00h,00h,00h,00h,00h,00h ; 4014"));
+
+        // Un bloque que el análisis marca como código deja de ser candidato
+        let stripes: Vec<u8> = (0..0x800).map(|i| ((i / 10 + i / 768) % 13 + 2) as u8 * 0x11).collect();
+        let mut rom = cartridge(&[0xCD, 0x00, 0x48, 0xC9]);
+        rom[0x800..0x1000].copy_from_slice(&stripes);
+        let mut map = CodeAnalyzer::for_rom(&rom).trace(&rom);
+        for offset in (0x800..0x1000).step_by(8) {
+            map.instructions.insert(offset);
+            map.kinds[offset..offset + 8].fill(ByteKind::Code);
+        }
+        let covers = |finder: GraphicsFinder| finder.find(&rom).iter().any(|r| r.offset < 0x1000 && r.offset + r.length > 0x800);
+        assert!(covers(GraphicsFinder::default()));
+        assert!(!covers(GraphicsFinder::default().skip_code(&map)));
+    }
+
+    #[test]
+    fn test_rom_not_multiple_of_page_size() {
+        // 20 bytes: la página 4000h queda a medias y el código se sale de la ROM
+        let mut rom = RomHeader { init: 0x4010, statement: 0, device: 0, text: 0 }.to_bytes().to_vec();
+        rom.extend_from_slice(&[0x21, 0x20, 0x40, 0xE9]); // 4010 LD HL,4020h / JP (HL)
+        let map = CodeAnalyzer::for_rom(&rom).analyze(&rom);
+        assert!(map.instructions.contains(&0x10) && map.instructions.contains(&0x13));
+        assert_eq!(map.export().len(), rom.len());
+
+        rom.truncate(0x10);
+        rom.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // 4010 NOP... y fin de los datos
+        assert_eq!(CodeAnalyzer::for_rom(&rom).analyze(&rom).instructions.len(), 4);
+
+        // Konami da bancos de 8 KB en 4000h-BFFFh aunque la ROM no llegue
+        rom.extend_from_slice(&[0xC3, 0x00, 0x80]); // 4014 JP 8000h
+        let map = CodeAnalyzer::new(MapperType::Konami, 0x4000).entry(0x4010).analyze(&rom);
+        assert_eq!(map.instructions.len(), 5);
+
+        let processor = MSX2Processor::new(256, 212);
+        assert!(processor.analyze_rom(&rom).contains(r#""size":23"#));
+        assert_eq!(processor.rom_code_map(&rom).len(), rom.len());
+        assert!(processor.disassemble_rom(&rom).contains("JP"));
+        processor.find_graphics(&rom, 0x2000);
+    }
+
+    #[test]
+    fn test_processor_analysis_api() {
+        let processor = MSX2Processor::new(256, 212);
+        let rom = read_rom("Vampire Killer (Japan, Europe).rom");
+        let json = processor.analyze_rom(&rom);
+        assert!(json.starts_with(r#"{"mapper":"konami","size":131072,"code":"#));
+        assert!(json.contains(r#""page":32768,"bank":"#));
+
+        let kinds = processor.rom_code_map(&rom);
+        assert_eq!(kinds.len(), rom.len());
+        assert_eq!((kinds[0], kinds[0x75]), (2, 1));
+        assert!(kinds.iter().all(|&k| (1..=4).contains(&k)));
+
+        let listing = processor.disassemble_rom(&rom);
+        assert!(listing.starts_with("; ═══ Banco 0 (konami) ═══\n"));
+        assert!(listing.contains("; ═══ Banco 15 (konami) ═══\n"));
+        assert!(listing.contains("INIT:"));
+    }
+}
@@ -0,0 +1,131 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - COMPRESORES DE GRÁFICOS                          ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::packers::{pack, unpack};
+    use msx2_processor::{MSX2Processor, Packer};
+
+    const PACKERS: [Packer; 4] = [Packer::Pletter, Packer::BitBuster, Packer::Aplib, Packer::Zx0];
+
+    /// Bytes pseudoaleatorios reproducibles (LCG de Numerical Recipes)
+    fn noise(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    /// Tabla de patrones de SCREEN 2 con pocos tiles distintos
+    fn sc2_patterns() -> Vec<u8> {
+        let tiles = noise(8 * 24, 7);
+        (0..0x1800).map(|i| tiles[(i / 8 * 37 % 24) * 8 + i % 8]).collect()
+    }
+
+    fn round_trip(packer: Packer, data: &[u8]) -> Vec<u8> {
+        let packed = pack(packer, data).unwrap();
+        let unpacked = unpack(packer, &packed).unwrap();
+        assert_eq!(unpacked.data, data, "{}", packer.name());
+        assert_eq!(unpacked.consumed, packed.len(), "{}", packer.name());
+        packed
+    }
+
+    #[test]
+    fn test_round_trip_text() {
+        let text = b"SCREEN 5:COLOR 15,0,0:BLOAD\"TITLE.SC5\",S:SCREEN 5:COLOR 15,0,0".repeat(4);
+        for packer in PACKERS {
+            assert!(round_trip(packer, &text).len() < text.len() / 2);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_sc2_patterns() {
+        let patterns = sc2_patterns();
+        for packer in PACKERS {
+            assert!(round_trip(packer, &patterns).len() < 1024, "{}", packer.name());
+        }
+    }
+
+    #[test]
+    fn test_round_trip_incompressible_noise() {
+        let data = noise(3000, 42);
+        for packer in PACKERS {
+            // Los literales cuestan 9 bits: poco más de 1/8 de sobrecarga
+            assert!(round_trip(packer, &data).len() < data.len() * 9 / 8 + 16);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_long_runs_and_sc5_bitmap() {
+        let mut bitmap = vec![0u8; 0x6A00];
+        bitmap[0x3000..0x3400].fill(0x77);
+        for packer in PACKERS {
+            assert!(round_trip(packer, &bitmap).len() < 64, "{}", packer.name());
+        }
+    }
+
+    #[test]
+    fn test_round_trip_far_offsets() {
+        // Repeticiones a 5000 bytes: fuera de la ventana de BitBuster pero
+        // dentro de los modos largos de Pletter, aPLib y ZX0
+        let block = noise(5000, 3);
+        let data = [block.clone(), block].concat();
+        for packer in PACKERS {
+            let packed = round_trip(packer, &data);
+            if packer == Packer::BitBuster {
+                assert!(packed.len() > 5000);
+            } else {
+                assert!(packed.len() < 5800, "{}", packer.name());
+            }
+        }
+        assert_ne!(pack(Packer::Pletter, &data).unwrap()[0] >> 5, 0);
+    }
+
+    #[test]
+    fn test_tiny_inputs() {
+        for packer in PACKERS {
+            round_trip(packer, b"A");
+            round_trip(packer, b"AB");
+            round_trip(packer, &[0; 3]);
+        }
+    }
+
+    #[test]
+    fn test_matches_hand_assembled_streams() {
+        // Los mismos flujos que se montaron a mano para los descompresores
+        assert_eq!(
+            pack(Packer::Pletter, b"ABABABAB").unwrap(),
+            [0x0D, b'A', b'B', 0xB5, 0x01, 0x55, 0x55, 0x55, 0x40]
+        );
+        assert_eq!(
+            pack(Packer::BitBuster, b"ABABABAB").unwrap(),
+            [8, 0, 0, 0, 0x39, b'A', b'B', 0x01, 0xFF, 0x00, 0xFF, 0x80, 0x00, 0x00]
+        );
+        assert_eq!(pack(Packer::Zx0, b"ABABABAB").unwrap(), [0x39, b'A', b'B', 0xFC, 0xD5, 0x55, 0x60]);
+    }
+
+    #[test]
+    fn test_unsupported_and_empty() {
+        assert!(pack(Packer::Zx7, b"data").is_err());
+        assert!(pack(Packer::Rle, b"data").is_err());
+        assert!(pack(Packer::Zx0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_processor_pack_api() {
+        let processor = MSX2Processor::new(256, 212);
+        let patterns = sc2_patterns();
+        for name in ["pletter", "bitbuster", "aplib", "zx0"] {
+            let packed = processor.pack_data(name, &patterns);
+            assert!(!packed.is_empty());
+            assert_eq!(processor.unpack_data(name, &packed, 0), patterns);
+        }
+        assert!(processor.pack_data("zx7", &patterns).is_empty());
+        assert!(processor.pack_data("lzma", &patterns).is_empty());
+    }
+}
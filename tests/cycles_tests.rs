@@ -0,0 +1,178 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - ANÁLISIS ESTÁTICO DE TIEMPOS                     ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::timing::{m1_cycles, msx_timing};
+    use msx2_processor::{Assembler, CycleAnalyzer, CycleReport, MSX2Processor, OutputFormat, SymbolTable, Timing};
+
+    fn analyze(analyzer: CycleAnalyzer, source: &str) -> CycleReport {
+        let source = format!("        ORG 0C000h\n{}", source);
+        let assembly = Assembler::new(OutputFormat::Bin).symbols(&SymbolTable::msx()).assemble(&source).unwrap();
+        analyzer.analyze(&assembly.code, assembly.origin, assembly.origin)
+    }
+
+    #[test]
+    fn test_m1_wait_states() {
+        assert_eq!(m1_cycles(&[0x00]), 1);
+        assert_eq!(m1_cycles(&[0xCB, 0x46]), 2);
+        assert_eq!(m1_cycles(&[0xDD, 0x7E, 0x05]), 2);
+        // DDCB: el código de operación va detrás del desplazamiento y no es M1
+        assert_eq!(m1_cycles(&[0xFD, 0xCB, 0x01, 0xC6]), 2);
+        assert_eq!(msx_timing(&[0x00]), Timing::fixed(5));
+        assert_eq!(msx_timing(&[0xDD, 0x7E, 0x05]), Timing::fixed(21));
+        assert_eq!(msx_timing(&[0xFD, 0xCB, 0x01, 0xC6]), Timing::fixed(25));
+        assert_eq!(msx_timing(&[0xED, 0xB0]), Timing::new(18, 23));
+        assert_eq!(msx_timing(&[0x18, 0xFE]), Timing::fixed(13));
+        assert_eq!(Timing::new(1, 2) + Timing::fixed(3), Timing::new(4, 5));
+    }
+
+    #[test]
+    fn test_straight_line_routine() {
+        let report = analyze(CycleAnalyzer::new(), "        LD A,1\n        OUT (99h),A\n        RET\n");
+        assert_eq!(report.blocks.len(), 1);
+        assert_eq!(report.blocks[0].costs, vec![Timing::fixed(8), Timing::fixed(12), Timing::fixed(11)]);
+        assert_eq!(report.blocks[0].end(), 0xC005);
+        assert_eq!(report.total, Some(Timing::fixed(31)));
+        // Sin la espera M1, los tiempos del Z80 de los manuales
+        let plain = analyze(CycleAnalyzer::new().m1_wait(false), "        LD A,1\n        OUT (99h),A\n        RET\n");
+        assert_eq!(plain.total, Some(Timing::fixed(28)));
+    }
+
+    #[test]
+    fn test_branch_paths() {
+        let report = analyze(
+            CycleAnalyzer::new(),
+            "        OR A\n        JR Z,.cero\n        LD A,1\n        RET\n.cero:  XOR A\n        RET NZ\n        RET\n",
+        );
+        let starts: Vec<u16> = report.blocks.iter().map(|b| b.start).collect();
+        assert_eq!(starts, vec![0xC000, 0xC003, 0xC006, 0xC008]);
+        let entry = report.block(0xC000).unwrap();
+        assert_eq!(entry.exits.len(), 2);
+        assert_eq!((entry.exits[0].target, entry.exits[0].timing), (Some(0xC006), Timing::fixed(18)));
+        assert_eq!((entry.exits[1].target, entry.exits[1].timing), (Some(0xC003), Timing::fixed(13)));
+        assert_eq!(entry.timing, Timing::new(13, 18));
+
+        let paths: Vec<(Vec<u16>, Timing)> = report.paths.iter().map(|p| (p.blocks.clone(), p.timing)).collect();
+        assert_eq!(
+            paths,
+            vec![
+                (vec![0xC000, 0xC006], Timing::fixed(35)),
+                (vec![0xC000, 0xC006, 0xC008], Timing::fixed(40)),
+                (vec![0xC000, 0xC003], Timing::fixed(32)),
+            ]
+        );
+        assert_eq!(report.total, Some(Timing::new(32, 40)));
+    }
+
+    #[test]
+    fn test_loops_are_listed_once() {
+        let report = analyze(CycleAnalyzer::new(), "        LD B,10\n.bucle: NOP\n        DJNZ .bucle\n        LDIR\n        RET\n");
+        assert_eq!(report.loops.len(), 1);
+        assert_eq!((report.loops[0].head, report.loops[0].tail), (0xC002, 0xC002));
+        assert_eq!(report.loops[0].iteration, Timing::fixed(19));
+        // Una pasada: LD 8 + NOP 5 + DJNZ sin saltar 9 + LDIR 18/23 + RET 11
+        assert_eq!(report.total, Some(Timing::new(51, 56)));
+        assert!(report.block(0xC005).unwrap().repeats);
+        assert!(!report.block(0xC002).unwrap().repeats);
+
+        // Un bucle sin salida no deja ningún camino
+        let endless = analyze(CycleAnalyzer::new(), ".fin:   JR .fin\n");
+        assert_eq!(endless.total, None);
+        assert_eq!(endless.loops[0].iteration, Timing::fixed(13));
+    }
+
+    #[test]
+    fn test_calls_add_routine_time() {
+        let report = analyze(
+            CycleAnalyzer::new(),
+            "        CALL SUB\n        CALL CHPUT\n        CALL Z,SUB\n        RET\nSUB:    NOP\n        RET\n",
+        );
+        let entry = report.block(0xC000).unwrap();
+        // SUB tarda 16; CALL Z sin llamar 11, llamando 18 + 16
+        assert_eq!(entry.costs[..3], [Timing::fixed(34), Timing::fixed(18), Timing::new(11, 34)]);
+        assert_eq!(report.total, Some(Timing::new(74, 97)));
+        assert_eq!(report.external, vec![0x00A2]);
+        // La rutina llamada no forma parte de los bloques
+        assert_eq!(report.blocks.len(), 1);
+
+        let jump = analyze(CycleAnalyzer::new(), "        JP CHGET\n");
+        assert_eq!((jump.total, jump.external.clone()), (Some(Timing::fixed(11)), vec![0x009F]));
+    }
+
+    #[test]
+    fn test_vdp_writes_too_close() {
+        let source = "        OUT (98h),A\n        OUT (98h),A\n        NOP\n        NOP\n        OUT (98h),A\n        RET\n";
+        let report = analyze(CycleAnalyzer::new().screen(5), source);
+        assert_eq!(report.warnings.len(), 1);
+        let warning = report.warnings[0];
+        assert_eq!((warning.address, warning.previous, warning.port), (0xC002, 0xC000, 0x98));
+        assert_eq!((warning.tstates, warning.required), (12, 15));
+
+        // En SCREEN 2 tampoco bastan los dos NOP
+        let report = analyze(CycleAnalyzer::new().screen(2), source);
+        let found: Vec<(u16, u32)> = report.warnings.iter().map(|w| (w.address, w.tstates)).collect();
+        assert_eq!(found, vec![(0xC002, 12), (0xC006, 22)]);
+        // Ni en un modo sin datos ni sin pedirlo se comprueba nada
+        assert!(analyze(CycleAnalyzer::new().screen(9), source).warnings.is_empty());
+        assert!(analyze(CycleAnalyzer::new(), source).warnings.is_empty());
+        // Escribir en 99h (registros) no tiene mínimo, pero cuenta como acceso
+        let report = analyze(CycleAnalyzer::new().screen(5), "        OUT (99h),A\n        OUT (99h),A\n        OUT (98h),A\n");
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].previous, 0xC002);
+    }
+
+    #[test]
+    fn test_vdp_writes_in_loops() {
+        // OUTI con C conocido: vuelta de 18 + 13
+        let outi = "        LD C,98h\n        LD B,16\n.copia: OUTI\n        JR NZ,.copia\n        RET\n";
+        assert!(analyze(CycleAnalyzer::new().screen(2), outi).warnings.is_empty());
+        let report = analyze(CycleAnalyzer::new().vram_gap(32), outi);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!((report.warnings[0].address, report.warnings[0].previous, report.warnings[0].tstates), (0xC004, 0xC004, 31));
+
+        // OTIR repite cada 23 estados T: vale en SCREEN 5, no en SCREEN 2
+        let otir = "        LD BC,1098h\n        OTIR\n        RET\n";
+        assert!(analyze(CycleAnalyzer::new().screen(5), otir).warnings.is_empty());
+        let report = analyze(CycleAnalyzer::new().screen(2), otir);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!((report.warnings[0].address, report.warnings[0].tstates), (0xC003, 23));
+
+        // Con C desconocido (tras una llamada) no se sabe a qué puerto va
+        let unknown = "        LD C,98h\n        CALL CHPUT\n        OTIR\n        RET\n";
+        assert!(analyze(CycleAnalyzer::new().screen(2), unknown).warnings.is_empty());
+
+        // La vuelta de un DJNZ con un OUT dentro
+        let report = analyze(CycleAnalyzer::new().screen(2), ".bucle: OUT (98h),A\n        DJNZ .bucle\n        RET\n");
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!((report.warnings[0].address, report.warnings[0].previous, report.warnings[0].tstates), (0xC000, 0xC000, 26));
+    }
+
+    #[test]
+    fn test_text_report() {
+        let report = analyze(CycleAnalyzer::new().screen(5), "        OUT (98h),A\n        OUT (98h),A\n.fin:   DJNZ .fin\n        RET\n");
+        let text = report.text(&SymbolTable::msx());
+        assert!(text.starts_with("; Rutina 0C000h: 44-44 estados T, 1 caminos\n"), "{}", text);
+        assert!(text.contains("; Bucles\n0C004h → 0C004h: vuelta de 14-14\n"), "{}", text);
+        assert!(text.contains("0C002h: escritura en 98h a 12 estados T de 0C000h (mínimo 15)"), "{}", text);
+    }
+
+    #[test]
+    fn test_processor_timing_api() {
+        let processor = MSX2Processor::new(256, 212);
+        let source = "        ORG 0C000h\nINICIO: NOP\nVOLCADO: OUT (98h),A\n        OUT (98h),A\n        RET\n";
+        let report = processor.assemble_timing(source, "bin", "volcado", 5);
+        assert!(report.starts_with(r#"{"ok":true,"entry":49153,"min":35,"max":35,"truncated":false,"#), "{}", report);
+        assert!(report.contains(r#""warnings":[{"address":49155,"previous":49153,"port":152,"tstates":12,"required":15}]"#));
+        assert!(processor.assemble_timing(source, "bin", "NADA", 5).contains("Rutina no encontrada: NADA"));
+        assert!(processor.assemble_timing("        FOO\n", "bin", "X", 5).starts_with(r#"{"ok":false"#));
+
+        let code = [0x18, 0xFE];
+        let report = processor.routine_timing(&code, 0x4000, 0x4000, 0);
+        assert!(report.contains(r#""min":null,"max":null"#), "{}", report);
+        assert!(report.contains(r#""loops":[{"head":16384,"tail":16384,"min":13,"max":13}]"#), "{}", report);
+        assert!(processor.routine_timing_text(&[0xCD, 0xA2, 0x00, 0xC9], 0x4000, 0x4000, 0).contains("29-29"));
+    }
+}
@@ -0,0 +1,303 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - SERVIDOR DAP                                     ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::dap::encode;
+    use msx2_processor::{DapServer, Debugger, MSX2Processor, Machine};
+    use std::io::{Cursor, Read};
+    use std::thread;
+    use std::time::Duration;
+
+    const PROGRAMA: &str = "        ORG 8000h
+INICIO: LD SP,0F000h
+        LD A,5
+        CALL SUB
+; guardar el resultado
+        LD (0C000h),A
+FIN:    JR FIN
+SUB:    INC A
+        RET
+";
+
+    fn request(seq: u32, command: &str, arguments: &str) -> String {
+        format!(r#"{{"seq":{},"type":"request","command":"{}","arguments":{}}}"#, seq, command, arguments)
+    }
+
+    struct Session {
+        server: DapServer,
+        machine: Machine,
+        debugger: Debugger,
+        seq: u32,
+    }
+
+    impl Session {
+        fn new() -> Session {
+            let mut session = Session {
+                server: DapServer::new().limit(100_000),
+                machine: Machine::with_ram(),
+                debugger: Debugger::new(),
+                seq: 0,
+            };
+            session.send("initialize", r#"{"adapterID":"msx","linesStartAt1":true}"#);
+            let source = PROGRAMA.replace('\n', "\\n");
+            let launched = session.send(
+                "launch",
+                &format!(r#"{{"program":"/src/juego.asm","source":"{}","stopOnEntry":true}}"#, source),
+            );
+            assert!(launched[0].contains(r#""success":true"#), "{:?}", launched);
+            session
+        }
+
+        fn send(&mut self, command: &str, arguments: &str) -> Vec<String> {
+            self.seq += 1;
+            let text = request(self.seq, command, arguments);
+            self.server.handle(&mut self.machine, &mut self.debugger, &text)
+        }
+    }
+
+    /// Entrada que entrega una parte enseguida y el resto más tarde
+    struct Editor {
+        now: Cursor<Vec<u8>>,
+        later: Cursor<Vec<u8>>,
+    }
+
+    impl Read for Editor {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.now.read(buf)? {
+                0 => {
+                    thread::sleep(Duration::from_millis(100));
+                    self.later.read(buf)
+                }
+                n => Ok(n),
+            }
+        }
+    }
+
+    #[test]
+    fn test_initialize_and_launch() {
+        let mut server = DapServer::new();
+        let (mut machine, mut debugger) = (Machine::with_ram(), Debugger::new());
+        let reply = server.handle(&mut machine, &mut debugger, &request(1, "initialize", "{}"));
+        assert_eq!(reply.len(), 1);
+        assert!(reply[0].starts_with(r#"{"seq":1,"type":"response","request_seq":1,"command":"initialize","success":true"#));
+        assert!(reply[0].contains(r#""supportsConditionalBreakpoints":true"#));
+
+        let bad = server.handle(&mut machine, &mut debugger, &request(2, "launch", r#"{"program":"x.asm","source":"  FOO\n"}"#));
+        assert!(bad[0].contains(r#""success":false"#) && bad[0].contains("main.asm:1"), "{:?}", bad);
+        let missing = server.handle(&mut machine, &mut debugger, &request(3, "launch", r#"{"program":"/no/existe.asm"}"#));
+        assert!(missing[0].contains("No se puede leer"), "{:?}", missing);
+
+        let session = Session::new();
+        assert_eq!(session.machine.cpu.regs.pc, 0x8000);
+        assert!(server.handle(&mut machine, &mut debugger, &request(4, "foo", "{}"))[0].contains("Petición no soportada: foo"));
+        // Un mensaje que no es JSON no tiene respuesta
+        assert!(server.handle(&mut machine, &mut debugger, "{").is_empty());
+    }
+
+    #[test]
+    fn test_set_breakpoints_snaps_to_code() {
+        let mut session = Session::new();
+        let reply = session.send(
+            "setBreakpoints",
+            r#"{"source":{"path":"/src/juego.asm"},"breakpoints":[{"line":5},{"line":8,"condition":"A = 9"},{"line":40},{"line":3,"condition":"A ="}]}"#,
+        );
+        let body = &reply[0];
+        assert!(body.contains(r#"{"id":1,"verified":true,"line":6}"#), "{}", body);
+        assert!(body.contains(r#"{"id":2,"verified":true,"line":8}"#), "{}", body);
+        assert!(body.contains(r#"{"verified":false,"line":40,"#), "{}", body);
+        assert_eq!(session.debugger.breakpoints().len(), 2);
+        // Volver a mandar el fichero sustituye sus puntos
+        session.send("setBreakpoints", r#"{"source":{"path":"/src/juego.asm"},"breakpoints":[]}"#);
+        assert!(session.debugger.breakpoints().is_empty());
+    }
+
+    #[test]
+    fn test_continue_to_breakpoint() {
+        let mut session = Session::new();
+        session.send("setBreakpoints", r#"{"source":{"path":"/src/juego.asm"},"breakpoints":[{"line":6}]}"#);
+        let entry = session.send("configurationDone", "{}");
+        assert!(entry[1].contains(r#""event":"stopped","body":{"reason":"entry""#), "{:?}", entry);
+        let reply = session.send("continue", r#"{"threadId":1}"#);
+        assert!(reply[0].contains(r#""allThreadsContinued":true"#));
+        assert!(reply[1].contains(r#""reason":"breakpoint","threadId":1,"allThreadsStopped":true,"hitBreakpointIds":[1]"#), "{:?}", reply);
+        assert_eq!(session.machine.cpu.regs.pc, 0x8008);
+
+        let trace = session.send("stackTrace", r#"{"threadId":1}"#);
+        assert!(trace[0].contains(r#""name":"INICIO+8","line":6,"column":1,"source":{"name":"main.asm","path":"/src/juego.asm"}"#), "{:?}", trace);
+        assert!(trace[0].contains(r#""instructionPointerReference":"0x8008""#));
+        // Sin más puntos se para al agotar el límite
+        let reply = session.send("continue", "{}");
+        assert!(reply[1].contains(r#""reason":"pause""#), "{:?}", reply);
+    }
+
+    #[test]
+    fn test_stepping() {
+        let mut session = Session::new();
+        session.send("next", "{}");
+        session.send("next", "{}");
+        // Saltar por encima de la llamada
+        let reply = session.send("next", "{}");
+        assert!(reply[1].contains(r#""reason":"step""#));
+        assert_eq!((session.machine.cpu.regs.pc, session.machine.cpu.regs.a), (0x8008, 6));
+
+        let mut session = Session::new();
+        for _ in 0..3 {
+            session.send("stepIn", "{}");
+        }
+        assert_eq!(session.machine.cpu.regs.pc, 0x800D);
+        session.send("stepOut", "{}");
+        assert_eq!(session.machine.cpu.regs.pc, 0x8008);
+        let reply = session.send("pause", "{}");
+        assert!(reply[1].contains(r#""reason":"pause""#));
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut session = Session::new();
+        let scopes = session.send("scopes", r#"{"frameId":1}"#);
+        assert!(scopes[0].contains(r#"{"name":"Registros","variablesReference":1,"expensive":false}"#));
+        assert!(scopes[0].contains(r#""name":"Slots","variablesReference":4"#));
+
+        session.send("stepIn", "{}");
+        let registers = session.send("variables", r#"{"variablesReference":1}"#);
+        assert!(registers[0].contains(r#"{"name":"SP","value":"0F000h","variablesReference":0,"memoryReference":"0xF000"}"#), "{:?}", registers);
+        assert!(registers[0].contains(r#"{"name":"IM","value":"0","variablesReference":0}"#));
+        let flags = session.send("variables", r#"{"variablesReference":2}"#);
+        assert!(flags[0].contains(r#"{"name":"Z","value":"1","variablesReference":0}"#), "{:?}", flags);
+
+        session.machine.bus.vdp.write_register(0, 0x06);
+        let vdp = session.send("variables", r#"{"variablesReference":3}"#);
+        assert!(vdp[0].contains(r#"{"name":"SCREEN","value":"5","variablesReference":0}"#), "{:?}", vdp);
+        assert!(vdp[0].contains(r#"{"name":"R#0","value":"06h","variablesReference":0}"#));
+        let slots = session.send("variables", r#"{"variablesReference":4}"#);
+        assert!(slots[0].contains(r#"{"name":"A8h","value":"0FFh","variablesReference":0}"#), "{:?}", slots);
+        assert!(slots[0].contains(r#""name":"Página 2 (8000h)","value":"3 ram""#));
+        assert!(session.send("variables", r#"{"variablesReference":9}"#)[0].contains(r#""success":false"#));
+    }
+
+    #[test]
+    fn test_set_variable() {
+        let mut session = Session::new();
+        let reply = session.send("setVariable", r#"{"variablesReference":1,"name":"HL","value":"1234h"}"#);
+        assert!(reply[0].contains(r#""body":{"value":"1234h"}"#), "{:?}", reply);
+        assert_eq!(session.machine.cpu.regs.hl(), 0x1234);
+        session.send("setVariable", r#"{"variablesReference":2,"name":"C","value":"0"}"#);
+        assert_eq!(session.machine.cpu.regs.f & 1, 0);
+        session.send("setVariable", r#"{"variablesReference":3,"name":"R#7","value":"0F4h"}"#);
+        assert_eq!(session.machine.bus.vdp.registers[7], 0xF4);
+        let bad = session.send("setVariable", r#"{"variablesReference":1,"name":"W","value":"1"}"#);
+        assert!(bad[0].contains(r#""success":false"#));
+    }
+
+    #[test]
+    fn test_memory_and_evaluate() {
+        let mut session = Session::new();
+        let read = session.send("readMemory", r#"{"memoryReference":"0x8000","count":3}"#);
+        assert!(read[0].contains(r#"{"address":"0x8000","data":"MQDw","unreadableBytes":0}"#), "{:?}", read);
+        let read = session.send("readMemory", r#"{"memoryReference":"SUB","offset":1,"count":1}"#);
+        assert!(read[0].contains(r#""address":"0x800E","data":"yQ==""#), "{:?}", read);
+        let written = session.send("writeMemory", r#"{"memoryReference":"0xC000","data":"AQID"}"#);
+        assert!(written[0].contains(r#""bytesWritten":3"#));
+        assert_eq!(session.machine.bus.peek(0xC002), 3);
+
+        let value = session.send("evaluate", r#"{"expression":"fin","context":"hover"}"#);
+        assert!(value[0].contains(r#""result":"800Bh","variablesReference":0,"memoryReference":"0x800B""#), "{:?}", value);
+        let value = session.send("evaluate", r#"{"expression":"PEEK(0C001h) + 1"}"#);
+        assert!(value[0].contains(r#""result":"0003h (3)""#), "{:?}", value);
+        assert!(session.send("evaluate", r#"{"expression":"1 +"}"#)[0].contains(r#""success":false"#));
+    }
+
+    #[test]
+    fn test_include_files_and_function_breakpoints() {
+        let dir = std::env::temp_dir().join(format!("dap_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.asm"), "        ORG 8000h\n        CALL RUTINA\nFIN:    JR FIN\n        INCLUDE \"rutina.asm\"\n").unwrap();
+        std::fs::write(dir.join("rutina.asm"), "; rutina aparte\nRUTINA: LD A,1\n        RET\n").unwrap();
+        let program = dir.join("main.asm").to_string_lossy().replace('\\', "/");
+        let include = dir.join("rutina.asm").to_string_lossy().replace('\\', "/");
+
+        let mut server = DapServer::new().limit(10_000);
+        let (mut machine, mut debugger) = (Machine::with_ram(), Debugger::new());
+        let mut send = |seq, command, arguments: &str| server.handle(&mut machine, &mut debugger, &request(seq, command, arguments));
+        let launched = send(1, "launch", &format!(r#"{{"program":"{}"}}"#, program));
+        assert!(launched[1].contains(r#""event":"initialized""#), "{:?}", launched);
+        let reply = send(2, "setBreakpoints", &format!(r#"{{"source":{{"path":"{}"}},"breakpoints":[{{"line":1}}]}}"#, include));
+        assert!(reply[0].contains(r#""verified":true,"line":2"#), "{:?}", reply);
+        let stop = send(3, "continue", "{}");
+        assert!(stop[1].contains(r#""reason":"breakpoint""#), "{:?}", stop);
+        let trace = send(4, "stackTrace", "{}");
+        assert!(trace[0].contains(&format!(r#""name":"RUTINA","line":2,"column":1,"source":{{"name":"rutina.asm","path":"{}"}}"#, include)), "{:?}", trace);
+
+        let reply = send(5, "setFunctionBreakpoints", r#"{"breakpoints":[{"name":"fin"},{"name":"NADA"}]}"#);
+        assert!(reply[0].contains(r#"{"id":2,"verified":true}"#) && reply[0].contains("Etiqueta desconocida: NADA"), "{:?}", reply);
+        let stop = send(6, "continue", "{}");
+        assert!(stop[1].contains(r#""hitBreakpointIds":[2]"#), "{:?}", stop);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_serve_over_streams() {
+        let source = PROGRAMA.replace('\n', "\\n");
+        let messages = [
+            request(1, "initialize", "{}"),
+            request(2, "launch", &format!(r#"{{"program":"juego.asm","source":"{}","stopOnEntry":true}}"#, source)),
+            request(3, "setBreakpoints", r#"{"source":{"path":"juego.asm"},"breakpoints":[{"line":8}]}"#),
+            request(4, "configurationDone", "{}"),
+            request(5, "continue", "{}"),
+            request(6, "disconnect", "{}"),
+        ];
+        // El editor manda "disconnect" un rato después, con la máquina ya parada
+        let input = Editor {
+            now: Cursor::new(messages[..5].iter().flat_map(|m| encode(m)).collect()),
+            later: Cursor::new(encode(&messages[5])),
+        };
+        let mut output = Vec::new();
+        let (mut machine, mut debugger) = (Machine::with_ram(), Debugger::new());
+        DapServer::new().serve(&mut machine, &mut debugger, input, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("Content-Length: "));
+        let bodies: Vec<&str> = output.split("Content-Length: ").skip(1).map(|m| m.split_once("\r\n\r\n").unwrap().1).collect();
+        for (text, body) in output.split("Content-Length: ").skip(1).zip(&bodies) {
+            assert_eq!(text.split_once("\r\n").unwrap().0.parse::<usize>().unwrap(), body.len());
+        }
+        let events: Vec<&str> = bodies.iter().filter(|b| b.contains(r#""type":"event""#)).copied().collect();
+        assert_eq!(events.len(), 3, "{:?}", events);
+        assert!(events[2].contains(r#""reason":"breakpoint""#));
+        assert!(bodies.last().unwrap().contains(r#""command":"disconnect","success":true"#));
+        assert_eq!(machine.cpu.regs.pc, 0x800D);
+
+        // El mismo protocolo desde JavaScript
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.insert_ram(3, 0);
+        processor.io_write(0xA8, 0xFF);
+        let reply = processor.dap_request(&messages[1]);
+        assert!(reply.starts_with('[') && reply.contains(r#""event":"initialized""#), "{}", reply);
+        assert!(processor.dap_request(&messages[2]).contains(r#""verified":true,"line":8"#));
+    }
+
+    #[test]
+    fn test_oversized_and_deeply_nested_requests() {
+        // Un mensaje de más de 4 MB corta la sesión sin leer el cuerpo
+        let mut input = encode(&request(1, "initialize", "{}"));
+        input.extend(format!("Content-Length: {}\r\n\r\n", (4 << 20) + 1).bytes());
+        input.extend(vec![b' '; (4 << 20) + 1]);
+        input.extend(encode(&request(2, "initialize", "{}")));
+        let input = Editor { now: Cursor::new(input), later: Cursor::new(Vec::new()) };
+        let mut output = Vec::new();
+        let (mut machine, mut debugger) = (Machine::with_ram(), Debugger::new());
+        DapServer::new().serve(&mut machine, &mut debugger, input, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches(r#""command":"initialize""#).count(), 1, "{}", output);
+
+        // El anidamiento excesivo se descarta como cualquier JSON no válido
+        let mut session = Session::new();
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(session.send("evaluate", &deep).is_empty());
+        let nested = format!("{}{}", "[".repeat(60), "]".repeat(60));
+        assert!(session.send("threads", &nested)[0].contains(r#""success":true"#));
+    }
+}
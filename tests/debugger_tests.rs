@@ -0,0 +1,184 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - DEPURADOR                                        ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::{
+        AccessKind, Assembler, BreakKind, Condition, Debugger, MSX2Processor, Machine, OutputFormat, StopReason,
+        SymbolTable,
+    };
+
+    const PROGRAMA: &str = "        ORG 8000h
+INICIO: LD SP,0F000h
+        LD HL,0C000h
+        LD B,3
+.bucle: LD (HL),B
+        INC HL
+        DJNZ .bucle
+        CALL SUB
+        LD A,1
+        OUT (98h),A
+        LD A,5
+        OUT (99h),A
+        LD A,87h
+        OUT (99h),A
+FIN:    JR FIN
+SUB:    LD A,(0C000h)
+        RET
+";
+
+    fn machine() -> (Machine, Debugger) {
+        let assembly = Assembler::new(OutputFormat::Bin).symbols(&SymbolTable::msx()).assemble(PROGRAMA).unwrap();
+        let mut machine = Machine::with_ram();
+        machine.load(assembly.origin, &assembly.code);
+        machine.cpu.regs.pc = assembly.entry;
+        let mut debugger = Debugger::new();
+        debugger.set_source(&assembly);
+        (machine, debugger)
+    }
+
+    #[test]
+    fn test_breakpoint_kinds_parse() {
+        assert_eq!(BreakKind::parse("exec", "8010h"), Ok(BreakKind::Execute(0x8010)));
+        assert_eq!(BreakKind::parse("write", "C000h-C0FFh"), Ok(BreakKind::Write(0xC000, 0xC0FF)));
+        assert_eq!(BreakKind::parse("READ", "$FD9F"), Ok(BreakKind::Read(0xFD9F, 0xFD9F)));
+        assert_eq!(BreakKind::parse("out", "98h-9Bh"), Ok(BreakKind::PortOut(0x98, 0x9B)));
+        assert_eq!(BreakKind::parse("vdp", "7"), Ok(BreakKind::VdpRegister(7)));
+        assert!(BreakKind::parse("out", "100h").is_err());
+        assert!(BreakKind::parse("vdp", "47").is_err());
+        assert!(BreakKind::parse("salto", "0").is_err());
+        assert_eq!(BreakKind::Write(0xC000, 0xC0FF).target(), "0C000h-0C0FFh");
+    }
+
+    #[test]
+    fn test_conditions() {
+        let (mut machine, _) = machine();
+        machine.cpu.regs.set_hl(0xC000);
+        machine.cpu.regs.a = 3;
+        machine.load(0xC000, &[0x34, 0x12]);
+        let holds = |text: &str| Condition::parse(text).unwrap().holds(&machine, None);
+        assert!(holds("A = 3 && HL == 0C000h"));
+        assert!(holds("PEEK(HL) + 1 = 35h"));
+        assert!(holds("DPEEK(HL) = 1234h"));
+        assert!(holds("(A & 1) <> 0 || ZF"));
+        assert!(!holds("-A > 0"));
+        assert!(holds("!(A < 2)"));
+        assert!(Condition::parse("A = ").is_err());
+        assert!(Condition::parse("FOO = 1").is_err());
+        assert!(Condition::parse("(A = 1").is_err());
+    }
+
+    #[test]
+    fn test_step_into_and_over() {
+        let (mut machine, mut debugger) = machine();
+        let stop = debugger.step_into(&mut machine);
+        assert_eq!((stop.reason, stop.pc, stop.tstates), (StopReason::Step, 0x8003, 11));
+        // Hasta la llamada y por encima de ella
+        let stop = debugger.run_to(&mut machine, 0x800C, 10_000);
+        assert_eq!(stop.reason, StopReason::Target);
+        let stop = debugger.step_over(&mut machine, 10_000);
+        assert_eq!((stop.reason, stop.pc), (StopReason::Step, 0x800F));
+        assert_eq!(machine.cpu.regs.a, 3);
+        assert_eq!(machine.cpu.regs.sp, 0xF000);
+    }
+
+    #[test]
+    fn test_step_out() {
+        let (mut machine, mut debugger) = machine();
+        debugger.run_to(&mut machine, 0x800C, 10_000);
+        let stop = debugger.step_into(&mut machine);
+        assert_eq!(stop.pc, 0x801D);
+        let stop = debugger.step_out(&mut machine, 10_000);
+        assert_eq!((stop.reason, stop.pc), (StopReason::Step, 0x800F));
+    }
+
+    #[test]
+    fn test_execute_breakpoint_with_condition() {
+        let (mut machine, mut debugger) = machine();
+        let id = debugger.add_breakpoint(BreakKind::Execute(0x8008), "B = 1").unwrap();
+        let stop = debugger.run(&mut machine, 100_000);
+        assert_eq!((stop.reason, stop.pc, machine.cpu.regs.b), (StopReason::Breakpoint(id), 0x8008, 1));
+        assert_eq!(debugger.breakpoints()[0].hits, 1);
+        // Seguir desde el punto de ruptura no vuelve a pararse en él
+        let stop = debugger.run(&mut machine, 1000);
+        assert_eq!(stop.reason, StopReason::Limit);
+        assert!(stop.tstates >= 1000);
+        // Desactivado no se para
+        assert!(debugger.enable_breakpoint(id, false));
+        machine.cpu.regs.pc = 0x8000;
+        assert_eq!(debugger.run(&mut machine, 1000).reason, StopReason::Limit);
+        assert!(debugger.remove_breakpoint(id));
+        assert!(!debugger.remove_breakpoint(id));
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let (mut machine, mut debugger) = machine();
+        let write = debugger.add_breakpoint(BreakKind::Write(0xC001, 0xC0FF), "VALUE = 2").unwrap();
+        let stop = debugger.run(&mut machine, 100_000);
+        assert_eq!(stop.reason, StopReason::Breakpoint(write));
+        let access = stop.access.unwrap();
+        assert_eq!((access.kind, access.address, access.value), (AccessKind::Write, 0xC001, 2));
+        // La parada es tras la instrucción que escribió
+        assert_eq!(stop.pc, 0x8009);
+
+        let read = debugger.add_breakpoint(BreakKind::Read(0xC000, 0xC000), "").unwrap();
+        let stop = debugger.run(&mut machine, 100_000);
+        assert_eq!((stop.reason, stop.pc), (StopReason::Breakpoint(read), 0x8020));
+    }
+
+    #[test]
+    fn test_port_and_vdp_breakpoints() {
+        let (mut machine, mut debugger) = machine();
+        let out = debugger.add_breakpoint(BreakKind::PortOut(0x98, 0x98), "").unwrap();
+        let vdp = debugger.add_breakpoint(BreakKind::VdpRegister(7), "VALUE = 5").unwrap();
+        let stop = debugger.run(&mut machine, 100_000);
+        assert_eq!(stop.reason, StopReason::Breakpoint(out));
+        assert_eq!(stop.access.unwrap().value, 1);
+        let stop = debugger.run(&mut machine, 100_000);
+        assert_eq!((stop.reason, stop.pc), (StopReason::Breakpoint(vdp), 0x801B));
+        assert_eq!(machine.bus.vdp.registers[7], 5);
+    }
+
+    #[test]
+    fn test_run_to_line() {
+        let (mut machine, mut debugger) = machine();
+        assert_eq!(debugger.line_address("main.asm", 9), Some(0x800F));
+        // Una línea sin código se ajusta a la siguiente que tenga
+        assert_eq!(debugger.line_address("main.asm", 1), Some(0x8000));
+        assert_eq!(debugger.source_line(0x8000).map(|l| l.line), Some(2));
+        let stop = debugger.run_to_line(&mut machine, "MAIN.ASM", 9, 100_000).unwrap();
+        assert_eq!((stop.reason, stop.pc), (StopReason::Target, 0x800F));
+        assert!(debugger.run_to_line(&mut machine, "main.asm", 99, 100).is_err());
+    }
+
+    #[test]
+    fn test_processor_debugger_api() {
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.insert_ram(3, 0);
+        processor.io_write(0xA8, 0xFF);
+        let loaded = processor.debug_assemble(PROGRAMA, "bin");
+        assert!(loaded.starts_with(r#"{"ok":true,"origin":32768,"#), "{}", loaded);
+
+        assert_eq!(processor.add_breakpoint("write", "C000h", "VALUE = 3"), r#"{"ok":true,"id":1}"#);
+        assert!(processor.add_breakpoint("exec", "8000h", "X =").starts_with(r#"{"ok":false"#));
+        let stop = processor.run(100_000);
+        assert!(stop.contains(r#""reason":"breakpoint","id":1"#), "{}", stop);
+        assert!(stop.contains(r#""access":{"kind":"write","address":49152,"value":3}"#), "{}", stop);
+        assert!(processor.list_breakpoints().contains(r#""kind":"write","target":"0C000h","condition":"VALUE = 3","enabled":true,"hits":1"#));
+
+        assert!(processor.set_register("hl", 0x1234));
+        assert!(!processor.set_register("W", 0));
+        assert!(processor.cpu_registers().contains(r#""HL":4660"#));
+        processor.write_memory(0xC100, &[1, 2, 3]);
+        assert_eq!(processor.read_memory(0xC100, 3), vec![1, 2, 3]);
+        assert!(processor.set_vdp_register(7, 0x44));
+        assert_eq!(processor.vdp_registers()[7], 0x44);
+
+        let stop = processor.run_to_line("main.asm", 14, 100_000);
+        assert!(stop.contains(r#""reason":"target""#) && stop.contains(r#""file":"main.asm","line":14"#), "{}", stop);
+        assert!(processor.step_into().contains(r#""pc":32795"#));
+    }
+}
@@ -0,0 +1,192 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - DESENSAMBLADOR Z80                               ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::disasm::{decode, Flow};
+    use msx2_processor::{Disassembler, MSX2Processor, SymbolTable};
+
+    fn text(code: &[u8]) -> String {
+        decode(code, 0x4000).text(&SymbolTable::msx())
+    }
+
+    /// Pequeño programa en 4000h: bucle que imprime con CHPUT
+    fn program() -> Vec<u8> {
+        vec![
+            0x3E, 0x0F, // 4000 LD A,0Fh
+            0x32, 0xE9, 0xF3, // 4002 LD (FORCLR),A
+            0x06, 0x05, // 4005 LD B,05h
+            0x3E, 0x2A, // 4007 LD A,2Ah
+            0xCD, 0xA2, 0x00, // 4009 CALL CHPUT
+            0x10, 0xF9, // 400C DJNZ 4007h
+            0xD3, 0x99, // 400E OUT (VDP_CTRL),A
+            0x21, 0x14, 0x40, // 4010 LD HL,4014h
+            0xC9, // 4013 RET
+            0x18, 0xFE, // 4014 JR 4014h
+        ]
+    }
+
+    #[test]
+    fn test_unprefixed_opcodes_with_msx_symbols() {
+        assert_eq!(text(&[0x3E, 0x0F]), "LD A,0Fh");
+        assert_eq!(text(&[0xCD, 0xA2, 0x00]), "CALL CHPUT");
+        assert_eq!(text(&[0x32, 0xE9, 0xF3]), "LD (FORCLR),A");
+        assert_eq!(text(&[0xD3, 0x98]), "OUT (VDP_DATA),A");
+        assert_eq!(text(&[0xDB, 0x12]), "IN A,(12h)");
+        assert_eq!(text(&[0x21, 0x9F, 0xFD]), "LD HL,H.TIMI");
+        // Las rutinas de la BIOS no nombran constantes
+        assert_eq!(text(&[0x01, 0x0C, 0x00]), "LD BC,000Ch");
+        assert_eq!(text(&[0xC3, 0x00, 0xC0]), "JP 0C000h");
+        assert_eq!(text(&[0x18, 0xFE]), "JR 4000h");
+        assert_eq!(text(&[0xFF]), "RST 38h");
+        assert_eq!(text(&[0x08]), "EX AF,AF'");
+        assert_eq!(text(&[0x76]), "HALT");
+        assert_eq!(text(&[0x9E]), "SBC A,(HL)");
+        assert_eq!(text(&[0xE6, 0x7F]), "AND 7Fh");
+    }
+
+    #[test]
+    fn test_cb_table() {
+        assert_eq!(text(&[0xCB, 0x00]), "RLC B");
+        assert_eq!(text(&[0xCB, 0x36]), "SLL (HL)");
+        assert_eq!(text(&[0xCB, 0x7E]), "BIT 7,(HL)");
+        assert_eq!(text(&[0xCB, 0xC7]), "SET 0,A");
+        assert_eq!(text(&[0xCB, 0x99]), "RES 3,C");
+    }
+
+    #[test]
+    fn test_ed_table() {
+        assert_eq!(text(&[0xED, 0xB0]), "LDIR");
+        assert_eq!(text(&[0xED, 0xB3]), "OTIR");
+        assert_eq!(text(&[0xED, 0x78]), "IN A,(C)");
+        assert_eq!(text(&[0xED, 0x70]), "IN F,(C)");
+        assert_eq!(text(&[0xED, 0x71]), "OUT (C),0");
+        assert_eq!(text(&[0xED, 0x43, 0x00, 0xC0]), "LD (0C000h),BC");
+        assert_eq!(text(&[0xED, 0x5A]), "ADC HL,DE");
+        assert_eq!(text(&[0xED, 0x56]), "IM 1");
+        assert_eq!(text(&[0xED, 0x6F]), "RLD");
+        assert_eq!(decode(&[0xED, 0x4D], 0).flow, Flow::Return);
+        // Opcode ED sin instrucción: dos bytes de datos
+        let invalid = decode(&[0xED, 0x00, 0xC9], 0);
+        assert_eq!(invalid.text(&SymbolTable::new()), "DB 0EDh,00h");
+        assert_eq!(invalid.len(), 2);
+    }
+
+    #[test]
+    fn test_index_prefixes() {
+        assert_eq!(text(&[0xDD, 0x21, 0x00, 0x80]), "LD IX,8000h");
+        assert_eq!(text(&[0xDD, 0x36, 0x05, 0xAA]), "LD (IX+05h),0AAh");
+        assert_eq!(text(&[0xFD, 0x7E, 0xFE]), "LD A,(IY-02h)");
+        assert_eq!(text(&[0xDD, 0x66, 0x01]), "LD H,(IX+01h)");
+        assert_eq!(text(&[0xDD, 0x67]), "LD IXH,A");
+        assert_eq!(text(&[0xFD, 0x85]), "ADD A,IYL");
+        assert_eq!(text(&[0xDD, 0x29]), "ADD IX,IX");
+        assert_eq!(text(&[0xFD, 0xE3]), "EX (SP),IY");
+        assert_eq!(text(&[0xEB]), "EX DE,HL");
+        let jump = decode(&[0xDD, 0xE9], 0);
+        assert_eq!(jump.text(&SymbolTable::new()), "JP (IX)");
+        assert_eq!(jump.flow, Flow::IndirectJump);
+        // Un prefijo que no afecta a la instrucción siguiente va como dato
+        let stray = decode(&[0xDD, 0x3E, 0x01], 0);
+        assert_eq!(stray.text(&SymbolTable::new()), "DB 0DDh");
+        assert_eq!(stray.len(), 1);
+    }
+
+    #[test]
+    fn test_indexed_bit_operations() {
+        assert_eq!(text(&[0xDD, 0xCB, 0x03, 0x06]), "RLC (IX+03h)");
+        assert_eq!(text(&[0xFD, 0xCB, 0xFF, 0x56]), "BIT 2,(IY-01h)");
+        assert_eq!(text(&[0xDD, 0xCB, 0x01, 0xC0]), "SET 0,(IX+01h),B");
+        assert_eq!(text(&[0xFD, 0xCB, 0x00, 0x3F]), "SRL (IY+00h),A");
+        assert_eq!(decode(&[0xDD, 0xCB, 0x03, 0x06], 0).len(), 4);
+    }
+
+    #[test]
+    fn test_every_opcode_decodes() {
+        let mut valid_ed = 0;
+        for op in 0..=255u8 {
+            for prefix in [&[][..], &[0xCB], &[0xED], &[0xDD], &[0xFD], &[0xDD, 0xCB, 0x05], &[0xFD, 0xCB, 0x05]] {
+                let code = [prefix, &[op, 0x34, 0x12, 0x00]].concat();
+                let instruction = decode(&code, 0x4000);
+                assert!((1..=4).contains(&instruction.len()), "{:02X?}", code);
+                assert!(!instruction.text(&SymbolTable::new()).is_empty());
+                let prefix_byte = prefix.is_empty() && [0xCB, 0xDD, 0xED, 0xFD].contains(&op);
+                if (prefix.is_empty() && !prefix_byte) || prefix == [0xCB] || prefix.len() == 3 {
+                    assert!(!instruction.is_data(), "{:02X?}", code);
+                }
+            }
+            valid_ed += !decode(&[0xED, op, 0, 0], 0).is_data() as u32;
+        }
+        // 62 de ED 40h-7Fh más las 16 de bloque
+        assert_eq!(valid_ed, 78);
+        // Instrucción cortada al final de los datos
+        assert_eq!(decode(&[0xCD, 0x00], 0).text(&SymbolTable::new()), "DB 0CDh");
+    }
+
+    #[test]
+    fn test_labels_and_cross_references() {
+        let listing = Disassembler::default().disassemble(&program(), 0x4000, 0x4000);
+        assert_eq!(listing.label(0x4000), Some("START"));
+        assert_eq!(listing.label(0x4007), Some("L4007"));
+        assert_eq!(listing.xrefs[&0x4007], [0x400C]);
+        assert_eq!(listing.xrefs[&0x4014], [0x4010, 0x4014]);
+        assert_eq!(listing.xrefs[&0x00A2], [0x4009]);
+
+        let text = listing.listing();
+        assert!(text.contains("CHPUT        EQU 00A2h\n"));
+        assert!(text.contains("FORCLR       EQU 0F3E9h\n"));
+        assert!(text.contains("VDP_CTRL     EQU 99h\n"));
+        assert!(text.contains("        ORG 4000h\n"));
+        assert!(text.contains("L4007:                                  ; XREF 400Ch\n"));
+        assert!(text.contains("        DJNZ L4007                      ; 400C  10 F9\n"));
+        assert!(text.contains("        LD HL,L4014"));
+        assert!(!text.contains("L4007      EQU"));
+    }
+
+    #[test]
+    fn test_rom_header_sets_entry_point() {
+        let processor = MSX2Processor::new(256, 212);
+        let mut rom = vec![0u8; 0x20];
+        rom[..4].copy_from_slice(&[b'A', b'B', 0x10, 0x40]);
+        rom[0x10..0x14].copy_from_slice(&[0xF3, 0xC3, 0x10, 0x40]);
+
+        let text = processor.disassemble_rom(&rom);
+        assert!(text.starts_with("; Desensamblado de 4000h-401Fh, entrada en 4010h\n"));
+        assert!(text.contains("        DB 41h,42h,10h,40h,00h,00h,00h,00h ; 4000"));
+        assert!(text.contains("INIT:                                   ; XREF 4011h\n        DI"));
+        assert!(text.contains("JP INIT"));
+
+        // Cartucho real: la rutina INIT empieza con DI y consulta el slot
+        let ikari = std::fs::read(format!("{}/rooms/Ikari (Japan).rom", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        let text = processor.disassemble_rom(&ikari[..0x4000]);
+        assert!(text.contains("        DI                              ; 4010  F3\n"));
+        assert!(text.contains("        LD SP,0F2FFh"));
+        assert!(text.contains("        CALL RSLREG"));
+    }
+
+    #[test]
+    fn test_load_info_bios_and_json() {
+        let mut processor = MSX2Processor::new(256, 212);
+        let mut body = vec![0x00; 4];
+        body.extend_from_slice(&[0xC9]);
+        let info = msx2_processor::LoadInfo::new(0xC000, 5, 0xC004, 0xC005, String::new());
+        let text = processor.disassemble_load_info(&body, &info);
+        assert!(text.contains("        DB 00h,00h,00h,00h"));
+        assert!(text.contains("START:\n        RET"));
+
+        assert!(processor.disassemble_bios().is_empty());
+        let mut bios = vec![0xC9; 0x8000];
+        bios[..4].copy_from_slice(&[0xF3, 0xC3, 0xA2, 0x00]);
+        processor.load_bios(&bios, "MSX2.ROM", "msx2");
+        let text = processor.disassemble_bios();
+        assert!(text.contains("CHKRAM:\n        DI"));
+        assert!(text.contains("JP CHPUT"));
+        assert!(text.contains("CHPUT:                                  ; XREF 0001h\n"));
+
+        let json = processor.disassemble_json(&program(), 0x4000, 0x4000);
+        assert!(json.starts_with(r#"[{"address":16384,"bytes":"3E 0F","text":"LD A,0Fh","label":"START","xrefs":[]}"#));
+        assert!(json.contains(r#"{"address":16391,"bytes":"3E 2A","text":"LD A,2Ah","label":"L4007","xrefs":[16396]}"#));
+    }
+}
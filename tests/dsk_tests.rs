@@ -0,0 +1,157 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - IMÁGENES DE DISCO .DSK (FAT12)                   ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::dsk::{DISK_360K, DISK_720K, SECTOR_SIZE};
+    use msx2_processor::{BloadHeader, DiskImage, MSX2Processor, RtcDateTime};
+
+    fn date() -> RtcDateTime {
+        RtcDateTime { year: 1988, month: 7, day: 14, hour: 10, minute: 30, second: 42 }
+    }
+
+    #[test]
+    fn test_blank_images_have_standard_sizes() {
+        assert_eq!(DiskImage::blank(false).data().len(), DISK_360K);
+        assert_eq!(DiskImage::blank(true).data().len(), DISK_720K);
+
+        let disk = DiskImage::from_bytes(DiskImage::blank(true).data()).unwrap();
+        assert_eq!(disk.geometry().heads, 2);
+        assert_eq!(disk.geometry().tracks(), 80);
+        assert!(disk.list_files().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_images_rejected() {
+        assert!(DiskImage::from_bytes(&[0u8; 1000]).is_err());
+        assert!(DiskImage::from_bytes(&vec![0u8; DISK_720K]).is_err());
+    }
+
+    #[test]
+    fn test_media_byte_fallback_without_bpb() {
+        let mut raw = DiskImage::blank(true).data().to_vec();
+        raw[0x0B..0x1E].fill(0);
+        let disk = DiskImage::from_bytes(&raw).unwrap();
+        assert_eq!(disk.geometry().media, 0xF9);
+        assert_eq!(disk.geometry().total_sectors, 1440);
+    }
+
+    #[test]
+    fn test_inject_and_extract_multi_cluster_file() {
+        let mut disk = DiskImage::blank(true);
+        let free_before = disk.free_space();
+        let contents: Vec<u8> = (0..5000u32).map(|i| (i * 7) as u8).collect();
+
+        disk.inject_file("game.bin", &contents, date()).unwrap();
+        assert_eq!(disk.extract_file("GAME.BIN").unwrap(), contents);
+        // 5000 bytes ocupan 5 clusters de 1 KB
+        assert_eq!(free_before - disk.free_space(), 5 * 1024);
+
+        let files = disk.list_files().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "GAME.BIN");
+        assert_eq!(files[0].size, 5000);
+        assert_eq!(files[0].modified, date());
+    }
+
+    #[test]
+    fn test_replace_file_frees_old_chain() {
+        let mut disk = DiskImage::blank(false);
+        let free_before = disk.free_space();
+        disk.inject_file("DATA.DAT", &[1u8; 4000], date()).unwrap();
+        disk.inject_file("DATA.DAT", &[2u8; 100], date()).unwrap();
+
+        assert_eq!(disk.list_files().unwrap().len(), 1);
+        assert_eq!(disk.extract_file("data.dat").unwrap(), vec![2u8; 100]);
+        assert_eq!(free_before - disk.free_space(), 1024);
+    }
+
+    #[test]
+    fn test_reject_bad_names_and_full_disk() {
+        let mut disk = DiskImage::blank(false);
+        assert!(disk.inject_file("TOOLONGNAME.BIN", &[0], date()).is_err());
+        assert!(disk.inject_file("A.B.C", &[0], date()).is_err());
+        assert!(disk.inject_file("BIG.DAT", &vec![0u8; DISK_360K], date()).is_err());
+        assert!(disk.extract_file("MISSING.TXT").is_err());
+    }
+
+    #[test]
+    fn test_image_survives_reload() {
+        let mut disk = DiskImage::blank(true);
+        disk.inject_file("AUTOEXEC.BAS", b"10 PRINT\"HOLA\"", date()).unwrap();
+        let reloaded = DiskImage::from_bytes(disk.data()).unwrap();
+        assert_eq!(reloaded.extract_file("AUTOEXEC.BAS").unwrap(), b"10 PRINT\"HOLA\"");
+    }
+
+    #[test]
+    fn test_directory_and_fat_loops_stop() {
+        // "SUB" es un directorio cuyo único hijo apunta otra vez a SUB
+        let mut child = b"LOOP       ".to_vec();
+        child.push(0x10);
+        child.resize(26, 0);
+        child.extend_from_slice(&[2, 0, 0, 0, 0, 0]);
+        let mut disk = DiskImage::blank(true);
+        disk.inject_file("SUB", &child, date()).unwrap();
+        let mut raw = disk.data().to_vec();
+        let entry = raw.windows(11).position(|w| w == b"SUB        ").unwrap();
+        raw[entry + 11] = 0x10;
+        let disk = DiskImage::from_bytes(&raw).unwrap();
+        let paths: Vec<String> = disk.list_files().unwrap().into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, vec!["SUB", "SUB/LOOP"]);
+
+        // Cadena 2 → 3 → 4 → 2 en la FAT (primera copia en el sector 1)
+        let mut disk = DiskImage::blank(true);
+        disk.inject_file("GAME.BIN", &[0x55; 3000], date()).unwrap();
+        let mut raw = disk.data().to_vec();
+        raw[SECTOR_SIZE + 6] = 0x02;
+        raw[SECTOR_SIZE + 7] &= 0xF0;
+        let disk = DiskImage::from_bytes(&raw).unwrap();
+        assert!(disk.cluster_chain(2).unwrap_err().contains("circular"));
+        assert!(disk.extract_file("GAME.BIN").is_err());
+    }
+
+    #[test]
+    fn test_logical_sector_mapping() {
+        let disk = DiskImage::blank(true);
+        assert_eq!(disk.logical_sector(0, 0, 1), Some(0));
+        assert_eq!(disk.logical_sector(0, 1, 1), Some(9));
+        assert_eq!(disk.logical_sector(1, 0, 9), Some(26));
+        assert_eq!(disk.logical_sector(0, 0, 0), None);
+        assert_eq!(disk.sector(1).unwrap().len(), SECTOR_SIZE);
+    }
+
+    #[test]
+    fn test_processor_disk_loaders() {
+        let mut disk = DiskImage::blank(true);
+        let header = BloadHeader::new(0xC000, 0xC0FF, 0xC010);
+        let mut bin = header.to_bytes().to_vec();
+        bin.extend_from_slice(&[0u8; 256]);
+        disk.inject_file("LOADER.BIN", &bin, date()).unwrap();
+
+        let mut screen = BloadHeader::new(0x0000, 0x0003, 0x0000).to_bytes().to_vec();
+        screen.extend_from_slice(&[0x12, 0x12, 0x12, 0x12]);
+        disk.inject_file("TITLE.SC5", &screen, date()).unwrap();
+
+        let mut processor = MSX2Processor::new(256, 212);
+        assert!(processor.load_disk_image(disk.data()).starts_with("✅"));
+
+        let listing = processor.list_disk_files();
+        assert!(listing.contains(r#""name":"LOADER.BIN""#));
+        assert!(listing.contains(r#""date":"1988-07-14""#));
+
+        let info = processor.disk_file_load_info("LOADER.BIN").unwrap();
+        assert_eq!(info.get_load_address(), 0xC000);
+        assert_eq!(info.get_binary_size(), 256);
+        assert_eq!(info.get_start_address(), 0xC010);
+
+        let rgba = processor.disk_file_to_rgba("TITLE.SC5");
+        assert_eq!(rgba.len(), 4 * 2 * 4);
+        assert_eq!(rgba[0..4], [255, 0, 0, 255]);
+
+        assert!(processor.inject_disk_file("NEW.TXT", b"hola").starts_with("✅"));
+        assert_eq!(processor.extract_disk_file("NEW.TXT"), b"hola");
+        assert_eq!(processor.get_disk_image().len(), DISK_720K);
+    }
+}
@@ -0,0 +1,232 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - WD2793, DISK-ROM Y SLOTS                         ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::fdc::{ST_NOT_READY, ST_RECORD_NOT_FOUND, ST_TRACK0, ST_WRITE_PROTECT};
+    use msx2_processor::{DiskImage, MSX2Processor, RtcDateTime};
+
+    const STATUS: u16 = 0x7FF8;
+    const TRACK: u16 = 0x7FF9;
+    const SECTOR: u16 = 0x7FFA;
+    const DATA: u16 = 0x7FFB;
+    const SIDE: u16 = 0x7FFC;
+    const DRIVE: u16 = 0x7FFD;
+    const LINES: u16 = 0x7FFF;
+
+    fn date() -> RtcDateTime {
+        RtcDateTime { year: 1990, month: 1, day: 2, hour: 3, minute: 4, second: 6 }
+    }
+
+    /// Disk-ROM en el slot 1 visible en la página 1, disco de 720 KB en A
+    fn setup(disk: &DiskImage) -> MSX2Processor {
+        let mut processor = MSX2Processor::new(256, 212);
+        let mut rom = vec![0u8; 0x4000];
+        rom[0] = b'A';
+        rom[1] = b'B';
+        assert!(processor.insert_disk_rom(1, 0, &rom).starts_with("✅"));
+        processor.io_write(0xA8, 0x04);
+        assert!(processor.load_disk_image(disk.data()).starts_with("✅"));
+        processor.mem_write(DRIVE, 0x80);
+        processor
+    }
+
+    fn wait_idle(processor: &mut MSX2Processor) -> u8 {
+        processor.mem_read(STATUS)
+    }
+
+    fn read_sector(processor: &mut MSX2Processor, sector: u8) -> (Vec<u8>, u8) {
+        processor.mem_write(SECTOR, sector);
+        processor.mem_write(STATUS, 0x80);
+        let mut out = Vec::new();
+        while processor.mem_read(LINES) & 0x40 == 0 {
+            out.push(processor.mem_read(DATA));
+        }
+        (out, wait_idle(processor))
+    }
+
+    fn seek(processor: &mut MSX2Processor, track: u8) {
+        processor.mem_write(DATA, track);
+        processor.mem_write(STATUS, 0x18);
+        wait_idle(processor);
+    }
+
+    #[test]
+    fn test_rom_and_register_window() {
+        let mut processor = setup(&DiskImage::blank(true));
+        assert_eq!(processor.mem_read(0x4000), b'A');
+        assert_eq!(processor.mem_read(0x4001), b'B');
+        processor.mem_write(TRACK, 0x27);
+        assert_eq!(processor.mem_read(TRACK), 0x27);
+        // Con otro slot en la página 1 los registros desaparecen
+        processor.io_write(0xA8, 0x00);
+        assert_eq!(processor.mem_read(TRACK), 0xFF);
+    }
+
+    #[test]
+    fn test_restore_and_read_boot_sector() {
+        let disk = DiskImage::blank(true);
+        let mut processor = setup(&disk);
+
+        processor.mem_write(STATUS, 0x00);
+        // INTRQ activo (bit 7 de xFFFh a 0) hasta leer el estado
+        assert_eq!(processor.mem_read(LINES) & 0x80, 0x00);
+        assert_ne!(processor.mem_read(STATUS) & ST_TRACK0, 0);
+        assert_eq!(processor.mem_read(LINES) & 0x80, 0x80);
+
+        let (data, status) = read_sector(&mut processor, 1);
+        assert_eq!(status & 0x1D, 0);
+        assert_eq!(data, disk.sector(0).unwrap());
+    }
+
+    #[test]
+    fn test_seek_and_side_select() {
+        let mut disk = DiskImage::blank(true);
+        let mut marker = vec![0u8; 512];
+        marker[0] = 0x5A;
+        // Pista 3, cara 1, sector 4
+        let index = disk.logical_sector(3, 1, 4).unwrap();
+        disk.write_sector(index, &marker).unwrap();
+
+        let mut processor = setup(&disk);
+        seek(&mut processor, 3);
+        processor.mem_write(SIDE, 1);
+        let (data, _) = read_sector(&mut processor, 4);
+        assert_eq!(data, marker);
+    }
+
+    #[test]
+    fn test_track_register_mismatch_is_record_not_found() {
+        let mut processor = setup(&DiskImage::blank(true));
+        seek(&mut processor, 2);
+        processor.mem_write(TRACK, 5);
+        let (data, status) = read_sector(&mut processor, 1);
+        assert!(data.is_empty());
+        assert_ne!(status & ST_RECORD_NOT_FOUND, 0);
+    }
+
+    #[test]
+    fn test_write_sector_updates_image() {
+        let mut processor = setup(&DiskImage::blank(true));
+        seek(&mut processor, 1);
+        processor.mem_write(SECTOR, 2);
+        processor.mem_write(STATUS, 0xA0);
+        for i in 0..512u32 {
+            assert_eq!(processor.mem_read(LINES) & 0x40, 0);
+            processor.mem_write(DATA, i as u8);
+        }
+        assert_eq!(wait_idle(&mut processor) & 0x5D, 0);
+
+        let image = DiskImage::from_bytes(&processor.get_disk_image()).unwrap();
+        let index = image.logical_sector(1, 0, 2).unwrap();
+        assert_eq!(image.sector(index).unwrap()[255], 255);
+    }
+
+    #[test]
+    fn test_write_protect() {
+        let disk = DiskImage::blank(true);
+        let mut processor = setup(&disk);
+        processor.set_disk_write_protect(0, true);
+
+        processor.mem_write(SECTOR, 1);
+        processor.mem_write(STATUS, 0xA0);
+        assert_ne!(wait_idle(&mut processor) & ST_WRITE_PROTECT, 0);
+        assert_eq!(processor.mem_read(LINES) & 0x40, 0x40);
+        assert_eq!(processor.get_disk_image(), disk.data());
+        assert!(processor.inject_disk_file("X.TXT", b"x").starts_with("❌"));
+    }
+
+    #[test]
+    fn test_multi_sector_read_stops_at_end_of_track() {
+        let mut processor = setup(&DiskImage::blank(true));
+        processor.mem_write(SECTOR, 8);
+        processor.mem_write(STATUS, 0x90);
+        let mut count = 0;
+        while processor.mem_read(LINES) & 0x40 == 0 {
+            processor.mem_read(DATA);
+            count += 1;
+        }
+        assert_eq!(count, 2 * 512);
+        assert_ne!(wait_idle(&mut processor) & ST_RECORD_NOT_FOUND, 0);
+    }
+
+    #[test]
+    fn test_disk_swap_and_change_detection() {
+        let mut first = DiskImage::blank(true);
+        first.inject_file("ONE.TXT", b"1", date()).unwrap();
+        let mut second = DiskImage::blank(true);
+        second.inject_file("TWO.TXT", b"2", date()).unwrap();
+
+        let mut processor = setup(&first);
+        assert!(processor.disk_changed(0));
+        assert!(!processor.disk_changed(0));
+
+        assert!(processor.insert_disk(0, second.data()).starts_with("✅"));
+        assert!(processor.disk_changed(0));
+        assert_eq!(processor.extract_disk_file("TWO.TXT"), b"2");
+
+        assert!(processor.eject_disk(0));
+        assert!(processor.disk_changed(0));
+        processor.mem_write(STATUS, 0x00);
+        assert_ne!(processor.mem_read(STATUS) & ST_NOT_READY, 0);
+    }
+
+    #[test]
+    fn test_second_drive_select() {
+        let mut processor = setup(&DiskImage::blank(true));
+        processor.mem_write(DRIVE, 0x81);
+        processor.mem_write(STATUS, 0x00);
+        assert_ne!(processor.mem_read(STATUS) & ST_NOT_READY, 0);
+
+        processor.insert_disk(1, DiskImage::blank(false).data());
+        let (data, _) = read_sector(&mut processor, 1);
+        assert_eq!(data.len(), 512);
+    }
+
+    #[test]
+    fn test_write_track_formats_sectors() {
+        let mut processor = setup(&DiskImage::blank(true));
+        seek(&mut processor, 10);
+
+        let mut raw = Vec::new();
+        for sector in 1..=9u8 {
+            raw.extend_from_slice(&[0x4E; 22]);
+            raw.extend_from_slice(&[0x00; 12]);
+            raw.extend_from_slice(&[0xF5, 0xF5, 0xF5, 0xFE, 10, 0, sector, 2, 0xF7]);
+            raw.extend_from_slice(&[0x4E; 22]);
+            raw.extend_from_slice(&[0x00; 12]);
+            raw.extend_from_slice(&[0xF5, 0xF5, 0xF5, 0xFB]);
+            raw.extend_from_slice(&[sector; 512]);
+            raw.push(0xF7);
+        }
+        raw.resize(6250, 0x4E);
+
+        processor.mem_write(STATUS, 0xF0);
+        for &byte in &raw {
+            processor.mem_write(DATA, byte);
+        }
+        wait_idle(&mut processor);
+
+        let image = DiskImage::from_bytes(&processor.get_disk_image()).unwrap();
+        let index = image.logical_sector(10, 0, 7).unwrap();
+        assert_eq!(image.sector(index).unwrap(), &[7u8; 512][..]);
+    }
+
+    #[test]
+    fn test_expanded_slot_secondary_register() {
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.set_slot_expanded(3, true);
+        processor.insert_ram(3, 2);
+        // Página 3 en el slot 3, subslot 2 en todas las páginas
+        processor.io_write(0xA8, 0xC0);
+        processor.mem_write(0xFFFF, 0xAA);
+        assert_eq!(processor.mem_read(0xFFFF), 0x55);
+
+        processor.mem_write(0xC000, 0x42);
+        assert_eq!(processor.mem_read(0xC000), 0x42);
+        processor.mem_write(0xFFFF, 0x00);
+        assert_eq!(processor.mem_read(0xC000), 0xFF);
+    }
+}
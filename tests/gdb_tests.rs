@@ -0,0 +1,225 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - SERVIDOR GDB                                     ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::gdb::frame;
+    use msx2_processor::{Assembler, Debugger, GdbStub, MSX2Processor, Machine, OutputFormat};
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    const PROGRAMA: &str = "        ORG 8000h
+        LD SP,0F000h
+        LD HL,0C000h
+        LD (HL),12h
+        CALL SUB
+FIN:    JR FIN
+SUB:    LD A,(0C000h)
+        RET
+";
+
+    fn machine() -> (Machine, Debugger) {
+        let assembly = Assembler::new(OutputFormat::Bin).assemble(PROGRAMA).unwrap();
+        let mut machine = Machine::with_ram();
+        machine.load(assembly.origin, &assembly.code);
+        machine.cpu.regs.pc = assembly.entry;
+        (machine, Debugger::new())
+    }
+
+    /// Enviar un paquete con su marco y leer el acuse y la respuesta
+    fn exchange(stream: &mut TcpStream, packet: &str) -> String {
+        stream.write_all(&frame(packet)).unwrap();
+        read_reply(stream)
+    }
+
+    fn read_reply(stream: &mut TcpStream) -> String {
+        let mut reply = Vec::new();
+        let mut byte = [0u8];
+        loop {
+            stream.read_exact(&mut byte).unwrap();
+            match byte[0] {
+                b'+' if reply.is_empty() => continue,
+                b'#' => break,
+                b => reply.push(b),
+            }
+        }
+        let mut checksum = [0u8; 2];
+        stream.read_exact(&mut checksum).unwrap();
+        stream.write_all(b"+").unwrap();
+        let body = &reply[1..];
+        let sum = body.iter().fold(0u8, |s, &b| s.wrapping_add(b));
+        assert_eq!(std::str::from_utf8(&checksum).unwrap(), format!("{:02x}", sum));
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_framing() {
+        assert_eq!(frame("OK"), b"$OK#9a".to_vec());
+        assert_eq!(frame(""), b"$#00".to_vec());
+        // Los caracteres especiales van escapados
+        assert_eq!(frame("a#b"), b"$a}\x03b#43".to_vec());
+    }
+
+    #[test]
+    fn test_registers() {
+        let (mut machine, mut debugger) = machine();
+        let mut stub = GdbStub::new();
+        machine.cpu.regs.set_hl(0x1234);
+        machine.cpu.regs.i = 0x3F;
+        let registers = stub.handle(&mut machine, &mut debugger, "g");
+        assert_eq!(registers.len(), 13 * 4);
+        // HL es el cuarto y PC el sexto, el byte bajo primero
+        assert_eq!(&registers[12..16], "3412");
+        assert_eq!(&registers[20..24], "0080");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "pc"), "003f");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "P1=cdab"), "OK");
+        assert_eq!(machine.cpu.regs.bc(), 0xABCD);
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "p20"), "E01");
+
+        let mut all = registers.clone();
+        all.replace_range(0..4, "ff01");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, &format!("G{}", all)), "OK");
+        assert_eq!((machine.cpu.regs.a, machine.cpu.regs.f), (0x01, 0xFF));
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "G00"), "E01");
+    }
+
+    #[test]
+    fn test_memory() {
+        let (mut machine, mut debugger) = machine();
+        let mut stub = GdbStub::new();
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "m8000,3"), "3100f0");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "Mc100,2:aa55"), "OK");
+        assert_eq!(machine.bus.peek(0xC101), 0x55);
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "Mc100,2:aa"), "E01");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "mzz"), "E01");
+    }
+
+    #[test]
+    fn test_breakpoints_and_continue() {
+        let (mut machine, mut debugger) = machine();
+        let mut stub = GdbStub::new().limit(100_000);
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "Z0,800d,1"), "OK");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "c"), "T05swbreak:;");
+        assert_eq!(machine.cpu.regs.pc, 0x800D);
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "?"), "T05swbreak:;");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "z0,800d,1"), "OK");
+        assert!(debugger.breakpoints().is_empty());
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "z0,800d,1"), "E01");
+        // Sin puntos corre hasta el límite
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "c"), "S05");
+        // Tipo desconocido: respuesta vacía
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "Z7,800d,1"), "");
+    }
+
+    #[test]
+    fn test_watchpoints_and_step() {
+        let (mut machine, mut debugger) = machine();
+        let mut stub = GdbStub::new().limit(100_000);
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "Z2,c000,1"), "OK");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "Z3,c000,2"), "OK");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "c"), "T05watch:c000;");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "c"), "T05rwatch:c000;");
+        assert_eq!(machine.cpu.regs.a, 0x12);
+
+        // Paso a paso, también con vCont
+        machine.cpu.regs.pc = 0x8000;
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "s"), "S05");
+        assert_eq!(machine.cpu.regs.pc, 0x8003);
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "vCont;s:1"), "S05");
+        assert_eq!(machine.cpu.regs.pc, 0x8006);
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "vCont?"), "vCont;c;C;s;S");
+        // Con dirección, salta antes de ejecutar
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "s8000"), "S05");
+        assert_eq!(machine.cpu.regs.pc, 0x8003);
+    }
+
+    #[test]
+    fn test_queries() {
+        let (mut machine, mut debugger) = machine();
+        let mut stub = GdbStub::new();
+        let supported = stub.handle(&mut machine, &mut debugger, "qSupported:multiprocess+;swbreak+");
+        assert!(supported.contains("qXfer:features:read+"), "{}", supported);
+        let xml = stub.handle(&mut machine, &mut debugger, "qXfer:features:read:target.xml:0,1000");
+        assert!(xml.starts_with("l<?xml") && xml.contains("<architecture>z80</architecture>"), "{}", xml);
+        let part = stub.handle(&mut machine, &mut debugger, "qXfer:features:read:target.xml:0,a");
+        assert_eq!(part, "m<?xml vers");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "qAttached"), "1");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "Hg0"), "OK");
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "vMustReplyEmpty"), "");
+        // monitor reset
+        machine.cpu.regs.pc = 0x1234;
+        assert_eq!(stub.handle(&mut machine, &mut debugger, "qRcmd,7265736574"), "OK");
+        assert_eq!(machine.cpu.regs.pc, 0);
+    }
+
+    #[test]
+    fn test_loopback_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut machine, mut debugger) = machine();
+            let mut stub = GdbStub::new();
+            stub.accept(&mut machine, &mut debugger, &listener).unwrap();
+            (machine.cpu.regs.pc, debugger.breakpoints().len())
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+        assert!(exchange(&mut client, "qSupported").starts_with("PacketSize="));
+        assert_eq!(exchange(&mut client, "Z0,8008,1"), "OK");
+        assert_eq!(exchange(&mut client, "c"), "T05swbreak:;");
+        assert_eq!(&exchange(&mut client, "g")[20..24], "0880");
+        // Una suma mal calculada se rechaza con '-'
+        client.write_all(b"$g#00").unwrap();
+        let mut nack = [0u8];
+        client.read_exact(&mut nack).unwrap();
+        assert_eq!(nack[0], b'-');
+        assert_eq!(exchange(&mut client, "QStartNoAckMode"), "OK");
+        assert_eq!(exchange(&mut client, "m8000,1"), "31");
+        assert_eq!(exchange(&mut client, "D"), "OK");
+
+        let (pc, breakpoints) = server.join().unwrap();
+        assert_eq!(pc, 0x8008);
+        // Al desconectarse se quitan sus puntos de ruptura
+        assert_eq!(breakpoints, 0);
+    }
+
+    #[test]
+    fn test_loopback_interrupt() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut machine, mut debugger) = machine();
+            GdbStub::new().accept(&mut machine, &mut debugger, &listener).unwrap();
+            (machine.cpu.regs.pc, machine.cycles)
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+        // Sin puntos de ruptura el programa se queda en FIN hasta el Ctrl-C
+        client.write_all(&frame("c")).unwrap();
+        thread::sleep(std::time::Duration::from_millis(50));
+        client.write_all(&[0x03]).unwrap();
+        assert_eq!(read_reply(&mut client), "S02");
+        client.write_all(&frame("k")).unwrap();
+        let (pc, cycles) = server.join().unwrap();
+        assert_eq!(pc, 0x800B);
+        assert!(cycles > 0);
+    }
+
+    #[test]
+    fn test_processor_gdb_packet() {
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.insert_ram(3, 0);
+        processor.io_write(0xA8, 0xFF);
+        processor.debug_assemble(PROGRAMA, "bin");
+        assert_eq!(processor.gdb_packet("Z0,800d,1"), "OK");
+        assert_eq!(processor.gdb_packet("c"), "T05swbreak:;");
+        assert!(processor.list_breakpoints().contains(r#""kind":"exec","target":"800Dh""#));
+        // Sin puntos, un "c" vuelve tras un segundo de máquina
+        assert_eq!(processor.gdb_packet("z0,800d,1"), "OK");
+        assert_eq!(processor.gdb_packet("c"), "S05");
+        assert_eq!(processor.gdb_packet("m800b,2"), "18fe");
+    }
+}
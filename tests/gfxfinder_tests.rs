@@ -0,0 +1,181 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - BUSCADOR DE GRÁFICOS EN ROMS                     ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::gfxfinder::entropy;
+    use msx2_processor::packers::pack;
+    use msx2_processor::{DecodeMode, GraphicsFinder, GraphicsRegion, MSX2Processor, Packer, ScreenMode, ZipArchive};
+
+    fn noise(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    /// Código de mentira: un opcode habitual y un operando al azar
+    fn code(len: usize, seed: u32) -> Vec<u8> {
+        const OPS: [u8; 12] = [0xCD, 0xC9, 0x3E, 0x21, 0x11, 0x32, 0x3A, 0xC3, 0x18, 0x20, 0x7E, 0x23];
+        noise(len, seed).chunks(2).flat_map(|p| [OPS[p[0] as usize % 12], p[1]]).take(len).collect()
+    }
+
+    /// Tiles de 8x8 con filas típicas de dibujo y muchas repeticiones
+    fn tiles(count: usize, seed: u32) -> Vec<u8> {
+        const ROWS: [u8; 10] = [0x00, 0x18, 0x3C, 0x7E, 0xFF, 0x66, 0xC3, 0x81, 0xF0, 0x0F];
+        let shapes: Vec<u8> = noise(count * 8, seed).iter().map(|&r| ROWS[r as usize % 10]).collect();
+        let order = noise(count, seed + 1);
+        (0..count)
+            .flat_map(|i| {
+                let t = order[i] as usize % (count / 4);
+                shapes[t * 8..t * 8 + 8].to_vec()
+            })
+            .collect()
+    }
+
+    /// Bitmap 4bpp en franjas de color, 128 bytes por línea
+    fn bitmap(len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| {
+                let (x, y) = (i % 128, i / 128);
+                let c = ((x / 10 + y / 6) % 13 + 2) as u8;
+                if x % 10 == 9 { (c << 4) | (c % 13 + 3) } else { (c << 4) | c }
+            })
+            .collect()
+    }
+
+    /// Sprites de 16x16 con las filas de arriba y abajo vacías
+    fn sprites(count: usize, seed: u32) -> Vec<u8> {
+        const ROWS: [u8; 6] = [0x18, 0x3C, 0x7E, 0xFF, 0x66, 0x24];
+        let r = noise(count * 32, seed);
+        (0..count * 32)
+            .map(|i| if !(3..=12).contains(&(i % 16)) { 0 } else { ROWS[r[i] as usize % 6] })
+            .collect()
+    }
+
+    fn put(rom: &mut [u8], at: usize, data: &[u8]) {
+        rom[at..at + data.len()].copy_from_slice(data);
+    }
+
+    /// Cartucho de 32 KB con código, patrones, bitmap, sprites y un
+    /// bloque Pletter que descomprime a una tabla de SCREEN 2
+    fn synthetic_rom() -> Vec<u8> {
+        let mut rom = vec![0xFF; 0x8000];
+        put(&mut rom, 0x0000, &code(0x1000, 1));
+        put(&mut rom, 0x1000, &tiles(256, 5));
+        put(&mut rom, 0x1800, &code(0x800, 2));
+        put(&mut rom, 0x2000, &bitmap(0x1000));
+        put(&mut rom, 0x3000, &code(0x400, 3));
+        put(&mut rom, 0x3400, &sprites(64, 9));
+        put(&mut rom, 0x3C00, &code(0x400, 4));
+        let packed = pack(Packer::Pletter, &tiles(768, 11)).unwrap();
+        put(&mut rom, 0x4000, &packed);
+        put(&mut rom, 0x4000 + packed.len(), &code(0x1000, 5));
+        rom[0] = b'A';
+        rom[1] = b'B';
+        rom
+    }
+
+    fn covering(regions: &[GraphicsRegion], offset: usize) -> Option<&GraphicsRegion> {
+        regions.iter().find(|r| r.offset <= offset && offset < r.offset + r.length)
+    }
+
+    #[test]
+    fn test_entropy_bounds() {
+        assert_eq!(entropy(&[]), 0.0);
+        assert_eq!(entropy(&[7; 100]), 0.0);
+        let all: Vec<u8> = (0..=255).collect();
+        assert!((entropy(&all) - 8.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_finds_each_kind_of_graphics() {
+        let regions = GraphicsFinder::default().find(&synthetic_rom());
+        assert_eq!(covering(&regions, 0x1200).unwrap().mode, DecodeMode::Screen2Patterns);
+        assert_eq!(covering(&regions, 0x2800).unwrap().mode, DecodeMode::Raw4bpp);
+        assert_eq!(covering(&regions, 0x3600).unwrap().mode, DecodeMode::SpritePatterns);
+    }
+
+    #[test]
+    fn test_compressed_stream_located_exactly() {
+        let rom = synthetic_rom();
+        let regions = GraphicsFinder::default().find(&rom);
+        let packed = regions.iter().find(|r| matches!(r.mode, DecodeMode::Compressed { .. })).unwrap();
+        assert_eq!(packed.offset, 0x4000);
+        assert_eq!(packed.length, pack(Packer::Pletter, &tiles(768, 11)).unwrap().len());
+        assert_eq!(
+            packed.mode,
+            DecodeMode::Compressed { packer: Packer::Pletter, screen: Some(ScreenMode::Screen2) }
+        );
+    }
+
+    #[test]
+    fn test_code_and_padding_are_not_graphics() {
+        let regions = GraphicsFinder::default().find(&synthetic_rom());
+        assert!(covering(&regions, 0x0400).is_none());
+        assert!(covering(&regions, 0x7000).is_none());
+        assert!(GraphicsFinder::default().find(&[0xFF; 0x4000]).is_empty());
+    }
+
+    #[test]
+    fn test_regions_ranked_by_score() {
+        let regions = GraphicsFinder::default().find(&synthetic_rom());
+        assert!(regions.len() >= 4);
+        assert!(regions.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(regions.iter().all(|r| r.score >= 50 && r.score <= 100));
+    }
+
+    #[test]
+    fn test_megarom_banks() {
+        // 128 KB: el bitmap cae en el banco 5 de 8 KB
+        let mut rom = code(0x20000, 8);
+        put(&mut rom, 5 * 0x2000 + 0x400, &bitmap(0x1000));
+        let regions = GraphicsFinder::default().find(&rom);
+        let region = covering(&regions, 5 * 0x2000 + 0x800).unwrap();
+        assert_eq!(region.bank, 5);
+        assert_eq!(region.offset, 5 * 0x2000 + region.bank_offset);
+        assert!(region.bank_offset + region.length <= 0x2000);
+
+        // Con bancos de 16 KB el mismo bitmap es del banco 2
+        let regions = GraphicsFinder::new(0x4000).find(&rom);
+        assert_eq!(covering(&regions, 5 * 0x2000 + 0x800).unwrap().bank, 2);
+    }
+
+    #[test]
+    fn test_real_rom_scan_is_consistent() {
+        let zip = std::fs::read(format!("{}/rooms/pengadvb.zip", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        let archive = ZipArchive::parse(&zip).unwrap();
+        let rom = archive.extract(archive.find("rom.u7").unwrap()).unwrap();
+        for region in GraphicsFinder::default().find(&rom) {
+            assert!(region.offset + region.length <= rom.len());
+            assert!(region.entropy >= 0.0 && region.entropy <= 8.0);
+        }
+    }
+
+    #[test]
+    fn test_processor_find_graphics_json() {
+        let processor = MSX2Processor::new(256, 212);
+        let json = processor.find_graphics(&synthetic_rom(), 0);
+        assert!(json.starts_with("[{"));
+        assert!(json.contains(r#""offset":16384,"#));
+        assert!(json.contains(r#""mode":"compressed","packer":"pletter","screen":2,"score":95"#));
+        assert!(json.contains(r#""mode":"sprites","packer":null,"screen":null"#));
+        assert_eq!(processor.find_graphics(&[], 0), "[]");
+    }
+
+    #[test]
+    fn test_processor_renders_candidates() {
+        let processor = MSX2Processor::new(256, 212);
+        let rom = synthetic_rom();
+        assert_eq!(processor.graphics_region_to_rgba(&rom, 0x2000, 0x1000, "raw4bpp").len(), 0x1000 * 8);
+        assert_eq!(processor.graphics_region_to_rgba(&rom, 0x1000, 0x800, "sc2").len(), 256 * 192 * 4);
+        assert_eq!(processor.graphics_region_to_rgba(&rom, 0x3400, 0x800, "sprites").len(), 256 * 64 * 4);
+        assert_eq!(processor.graphics_region_to_rgba(&rom, 0x4000, 0, "pletter").len(), 256 * 192 * 4);
+        assert!(processor.graphics_region_to_rgba(&rom, 0x9000, 16, "nada").is_empty());
+    }
+}
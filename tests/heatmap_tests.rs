@@ -0,0 +1,158 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - MAPA DE CALOR DE ROMS Y BIOS                     ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::heatmap::{bank_labels, block_stats, render, BLOCK_SIZE, CELL, GUTTER, ROW_BYTES};
+    use msx2_processor::{BlockClass, MSX2Processor, RegionLabel};
+
+    /// Bytes pseudoaleatorios reproducibles (LCG de Numerical Recipes)
+    fn noise(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    /// Rutina típica: LD A,n / CALL nn / LD (nn),A / RET...
+    fn code(len: usize) -> Vec<u8> {
+        [0x3E, 0x0F, 0xCD, 0x5F, 0x00, 0x32, 0xE9, 0xF3, 0x21, 0x00, 0x40, 0xC9]
+            .iter()
+            .copied()
+            .cycle()
+            .take(len)
+            .collect()
+    }
+
+    /// BIOS de 32 KB que empieza con DI / JP como el de verdad
+    fn bios() -> Vec<u8> {
+        let mut data = code(0x8000);
+        data[..3].copy_from_slice(&[0xF3, 0xC3, 0x0D]);
+        data
+    }
+
+    fn pixel(rgba: &[u8], width: usize, x: usize, y: usize) -> [u8; 4] {
+        let at = (y * width + x) * 4;
+        [rgba[at], rgba[at + 1], rgba[at + 2], rgba[at + 3]]
+    }
+
+    #[test]
+    fn test_block_classes() {
+        let text = b"MSX BASIC version 3.0 Copyright 1988 by Microsoft ".repeat(2);
+        let mut data = vec![0xFF; BLOCK_SIZE];
+        data.extend_from_slice(&text[..BLOCK_SIZE]);
+        data.extend(code(BLOCK_SIZE));
+        data.extend(noise(BLOCK_SIZE * 4, 9));
+
+        let classes: Vec<BlockClass> = block_stats(&data).iter().map(|b| b.class).collect();
+        assert_eq!(&classes[..3], &[BlockClass::Padding, BlockClass::Text, BlockClass::Code]);
+        // 64 bytes no bastan para 7.2 bits de entropía: al azar es "data"
+        assert!(classes[3..].iter().all(|&c| c == BlockClass::Data));
+    }
+
+    #[test]
+    fn test_block_stats_offsets_and_entropy() {
+        let stats = block_stats(&noise(BLOCK_SIZE * 3 + 10, 1));
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats[3].offset, BLOCK_SIZE * 3);
+        assert!(stats[0].entropy > 5.0);
+        assert_eq!(block_stats(&[0; 64])[0].entropy, 0.0);
+    }
+
+    #[test]
+    fn test_image_dimensions() {
+        let map = render(&vec![0u8; ROW_BYTES * 3 + 1], &[]);
+        assert_eq!(map.width, GUTTER + 32 * CELL);
+        assert_eq!(map.height, 4 * CELL);
+        assert_eq!(map.rgba.len(), map.width * map.height * 4);
+        assert_eq!(map.rows.len(), 4);
+        assert!(render(&[], &[]).rgba.is_empty());
+    }
+
+    #[test]
+    fn test_cells_use_class_colors() {
+        let mut data = code(BLOCK_SIZE);
+        data.extend(vec![0u8; BLOCK_SIZE]);
+        let map = render(&data, &[]);
+        let code_cell = pixel(&map.rgba, map.width, GUTTER, 0);
+        let padding_cell = pixel(&map.rgba, map.width, GUTTER + CELL, 0);
+        assert!(code_cell[2] > code_cell[0] && code_cell[2] > code_cell[1]);
+        assert_eq!(padding_cell, [40, 40, 40, 255]);
+        // Celdas sin datos y franja sin región quedan en negro
+        assert_eq!(pixel(&map.rgba, map.width, GUTTER + 3 * CELL, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel(&map.rgba, map.width, 0, 0), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_rows_take_region_labels_and_gutter_colors() {
+        let regions = [
+            RegionLabel { offset: 0, length: ROW_BYTES, name: "BIOS".into() },
+            RegionLabel { offset: ROW_BYTES, length: ROW_BYTES, name: "SUB-ROM".into() },
+        ];
+        let map = render(&vec![0xC9; ROW_BYTES * 3], &regions);
+        let labels: Vec<&str> = map.rows.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, ["BIOS", "SUB-ROM", ""]);
+        assert_eq!(map.rows[1].region, Some(1));
+        assert_eq!(map.rows[2].region, None);
+
+        let first = pixel(&map.rgba, map.width, 0, 0);
+        let second = pixel(&map.rgba, map.width, 0, CELL);
+        assert_ne!(first, second);
+        assert_ne!(first, [0, 0, 0, 255]);
+        assert_eq!(pixel(&map.rgba, map.width, 0, 2 * CELL), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_bank_labels() {
+        let banks = bank_labels(0x5000, 0x2000);
+        assert_eq!(banks.len(), 3);
+        assert_eq!(banks[2].offset, 0x4000);
+        assert_eq!(banks[2].name, "Banco 2");
+        assert!(bank_labels(0, 0x2000).is_empty());
+    }
+
+    #[test]
+    fn test_bios_heatmap_uses_memory_map() {
+        let mut processor = MSX2Processor::new(256, 212);
+        assert!(processor.bios_heatmap().is_empty());
+        assert!(processor.load_bios(&bios(), "MSX2.ROM", "msx2").starts_with("✅"));
+
+        let rgba = processor.bios_heatmap();
+        assert_eq!(rgba.len(), (GUTTER + 32 * CELL) * 16 * CELL * 4);
+
+        let layout = processor.heatmap_layout(&bios(), 0x0000, 0);
+        assert!(layout.contains(r#"{"row":0,"offset":0,"address":"0x0000","label":"BIOS/ROM"}"#));
+        assert!(layout.contains(r#"{"row":8,"offset":16384,"address":"0x4000","label":"Cartridge"}"#));
+    }
+
+    #[test]
+    fn test_layout_with_base_address_and_banks() {
+        let processor = MSX2Processor::new(256, 212);
+        let rom = code(0x8000);
+
+        let layout = processor.heatmap_layout(&rom, 0x4000, 0);
+        assert!(layout.starts_with(r#"{"width":264,"height":128,"block":64,"row_bytes":2048,"rows":["#));
+        assert!(layout.contains(r#"{"row":0,"offset":0,"address":"0x4000","label":"Cartridge"}"#));
+        assert!(layout.contains(r#"{"row":15,"offset":30720,"address":"0xB800","label":"RAM (Slot 2)"}"#));
+
+        let layout = processor.heatmap_layout(&rom, 0x4000, 0x2000);
+        assert!(layout.contains(r#"{"row":4,"offset":8192,"address":"0x6000","label":"Banco 1"}"#));
+        assert_eq!(processor.render_heatmap(&rom, 0x4000, 0x2000).len(), 264 * 128 * 4);
+    }
+
+    #[test]
+    fn test_block_stats_json() {
+        let processor = MSX2Processor::new(256, 212);
+        let json = processor.get_block_stats(&[0u8; 100]);
+        assert!(json.starts_with(
+            r#"[{"offset":0,"entropy":0.00,"padding":1.00,"text":0.00,"code":0.00,"class":"padding"}"#
+        ));
+        assert!(json.contains(r#""offset":64,"#));
+        assert_eq!(processor.get_block_stats(&[]), "[]");
+    }
+}
@@ -0,0 +1,139 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - JOYSTICK, RATÓN, TRACKBALL Y PADDLE              ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::joystick::{JOY_BUTTON_A, JOY_RIGHT, JOY_UP};
+    use msx2_processor::MSX2Processor;
+
+    fn write_psg(processor: &mut MSX2Processor, reg: u8, value: u8) {
+        processor.io_write(0xA0, reg);
+        processor.io_write(0xA1, value);
+    }
+
+    fn read_r14(processor: &mut MSX2Processor) -> u8 {
+        processor.io_write(0xA0, 14);
+        processor.io_read(0xA2)
+    }
+
+    /// Igual que el BIOS: puerto A del PSG entrada, puerto B salida
+    fn init_psg(processor: &mut MSX2Processor) {
+        write_psg(processor, 7, 0xBF);
+    }
+
+    #[test]
+    fn test_joystick_state_on_port_1() {
+        let mut processor = MSX2Processor::new(256, 212);
+        init_psg(&mut processor);
+        write_psg(&mut processor, 15, 0x3F);
+
+        assert_eq!(read_r14(&mut processor) & 0x3F, 0x3F);
+
+        assert!(processor.set_joystick_state(1, JOY_UP | JOY_RIGHT | JOY_BUTTON_A));
+        assert_eq!(read_r14(&mut processor) & 0x3F, 0x26);
+    }
+
+    #[test]
+    fn test_port_select_and_empty_port() {
+        let mut processor = MSX2Processor::new(256, 212);
+        init_psg(&mut processor);
+        processor.set_joystick_state(1, JOY_UP);
+
+        // Bit 6 de R15 selecciona el puerto 2, que está vacío
+        write_psg(&mut processor, 15, 0x7F);
+        assert_eq!(read_r14(&mut processor) & 0x3F, 0x3F);
+        assert_eq!(processor.get_controller_type(2), "none");
+    }
+
+    #[test]
+    fn test_trigger_pins_are_open_collector() {
+        let mut processor = MSX2Processor::new(256, 212);
+        init_psg(&mut processor);
+
+        // Pines 6 y 7 del puerto 1 forzados a 0 por R15
+        write_psg(&mut processor, 15, 0x00);
+        assert_eq!(read_r14(&mut processor) & 0x30, 0x00);
+    }
+
+    #[test]
+    fn test_mouse_nibble_protocol() {
+        let mut processor = MSX2Processor::new(256, 212);
+        init_psg(&mut processor);
+        assert!(processor.connect_controller(1, "mouse"));
+
+        // Mover 18 a la derecha y 3 hacia arriba: el ratón envía -18 y +3
+        processor.move_pointer(1, 18, -3);
+
+        let mut nibbles = Vec::new();
+        let mut strobe = false;
+        for _ in 0..4 {
+            strobe = !strobe;
+            write_psg(&mut processor, 15, if strobe { 0x13 } else { 0x03 });
+            nibbles.push(read_r14(&mut processor) & 0x0F);
+        }
+
+        let x = ((nibbles[0] << 4) | nibbles[1]) as i8;
+        let y = ((nibbles[2] << 4) | nibbles[3]) as i8;
+        assert_eq!(x, -18);
+        assert_eq!(y, 3);
+    }
+
+    #[test]
+    fn test_mouse_buttons() {
+        let mut processor = MSX2Processor::new(256, 212);
+        init_psg(&mut processor);
+        processor.connect_controller(1, "mouse");
+        write_psg(&mut processor, 15, 0x03);
+
+        processor.set_pointer_buttons(1, true, false);
+        assert_eq!(read_r14(&mut processor) & 0x30, 0x20);
+    }
+
+    #[test]
+    fn test_trackball_axis_select() {
+        let mut processor = MSX2Processor::new(256, 212);
+        init_psg(&mut processor);
+        processor.connect_controller(2, "trackball");
+        processor.move_pointer(2, 20, -2);
+
+        // Puerto 2 seleccionado, pin 8 a 0: eje X saturado a +7
+        write_psg(&mut processor, 15, 0x4C);
+        assert_eq!(read_r14(&mut processor) & 0x0F, 0x07);
+
+        // Pin 8 a 1: eje Y
+        write_psg(&mut processor, 15, 0x6C);
+        assert_eq!(read_r14(&mut processor) & 0x0F, 0x0E);
+
+        // Al volver a X quedan los 13 restantes, otra vez saturados
+        write_psg(&mut processor, 15, 0x4C);
+        assert_eq!(read_r14(&mut processor) & 0x0F, 0x07);
+    }
+
+    #[test]
+    fn test_paddle_pulse_length() {
+        let mut processor = MSX2Processor::new(256, 212);
+        init_psg(&mut processor);
+        processor.connect_controller(1, "paddle");
+        processor.set_paddle_state(1, 10, false);
+
+        write_psg(&mut processor, 15, 0x03);
+        write_psg(&mut processor, 15, 0x13);
+
+        let mut count = 0;
+        while read_r14(&mut processor) & 0x01 != 0 {
+            count += 1;
+            assert!(count < 300);
+        }
+        assert_eq!(count, 11);
+    }
+
+    #[test]
+    fn test_setters_reject_wrong_device() {
+        let mut processor = MSX2Processor::new(256, 212);
+        assert!(!processor.move_pointer(1, 1, 1));
+        assert!(!processor.set_joystick_state(3, JOY_UP));
+        assert!(!processor.connect_controller(1, "lightgun"));
+    }
+}
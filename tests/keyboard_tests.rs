@@ -0,0 +1,129 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - PPI 8255 Y MATRIZ DE TECLADO                     ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::{KeyboardLayout, MSX2Processor, MatrixPosition};
+
+    #[test]
+    fn test_key_press_visible_on_port_b() {
+        let mut processor = MSX2Processor::new(256, 212);
+
+        // "A" está en la fila 2, bit 6
+        assert!(processor.key_down("KeyA"));
+        processor.io_write(0xAA, 0x02);
+        assert_eq!(processor.io_read(0xA9), 0xBF);
+
+        // Otras filas no se ven afectadas
+        processor.io_write(0xAA, 0x03);
+        assert_eq!(processor.io_read(0xA9), 0xFF);
+
+        assert!(processor.key_up("KeyA"));
+        processor.io_write(0xAA, 0x02);
+        assert_eq!(processor.io_read(0xA9), 0xFF);
+    }
+
+    #[test]
+    fn test_unknown_code_is_not_mapped() {
+        let mut processor = MSX2Processor::new(256, 212);
+        assert!(!processor.key_down("LaunchMail"));
+        assert!(processor.map_key_code("LaunchMail").contains(r#""mapped":false"#));
+    }
+
+    #[test]
+    fn test_rows_beyond_matrix_read_as_released() {
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.key_down("Space");
+        processor.io_write(0xAA, 0x0B);
+        assert_eq!(processor.io_read(0xA9), 0xFF);
+        processor.io_write(0xAA, 0x08);
+        assert_eq!(processor.io_read(0xA9), 0xFE);
+    }
+
+    #[test]
+    fn test_port_c_bit_set_reset() {
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.io_write(0xAA, 0x00);
+
+        // Bit 7 (key click) a 1 y luego bit 4 (motor) a 1
+        processor.io_write(0xAB, 0x0F);
+        processor.io_write(0xAB, 0x09);
+        assert_eq!(processor.io_read(0xAA), 0x90);
+
+        processor.io_write(0xAB, 0x0E);
+        assert_eq!(processor.io_read(0xAA), 0x10);
+    }
+
+    #[test]
+    fn test_primary_slot_register() {
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.io_write(0xA8, 0xF0);
+        assert_eq!(processor.io_read(0xA8), 0xF0);
+    }
+
+    #[test]
+    fn test_layout_from_bios_region_byte() {
+        let mut bios = vec![0u8; 0x8000];
+        bios[0x2C] = 0x16; // Teclado español, BASIC v1
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.load_bios(&bios, "es.rom", "msxbios");
+        assert_eq!(processor.get_keyboard_layout(), "es");
+
+        bios[0x2C] = 0x00;
+        processor.load_bios(&bios, "jp.rom", "msxbios");
+        assert_eq!(processor.get_keyboard_layout(), "jp");
+    }
+
+    #[test]
+    fn test_japanese_specific_keys() {
+        let jp = KeyboardLayout::Japanese;
+        assert_eq!(jp.map_code("IntlYen"), Some(MatrixPosition::new(1, 4)));
+        assert_eq!(jp.map_code("IntlRo"), Some(MatrixPosition::new(2, 5)));
+        assert_eq!(jp.key_label(MatrixPosition::new(1, 5)), "@");
+        assert_eq!(KeyboardLayout::International.map_code("IntlYen"), None);
+    }
+
+    #[test]
+    fn test_layout_labels() {
+        let pos = MatrixPosition::new(1, 7);
+        assert_eq!(KeyboardLayout::Spanish.key_label(pos), "Ñ");
+        assert_eq!(KeyboardLayout::Brazilian.key_label(pos), "Ç");
+        assert_eq!(KeyboardLayout::International.key_label(pos), ";");
+        assert!(KeyboardLayout::from_name("br").is_some());
+    }
+
+    #[test]
+    fn test_map_key_code_json() {
+        let processor = MSX2Processor::new(256, 212);
+        let json = processor.map_key_code("Enter");
+        assert!(json.contains(r#""row":7"#));
+        assert!(json.contains(r#""bit":7"#));
+        assert!(json.contains(r#""label":"RETURN""#));
+    }
+
+    #[test]
+    fn test_shared_position_released_by_last_key() {
+        let mut processor = MSX2Processor::new(256, 212);
+        // Las dos mayúsculas van a la fila 6, bit 0
+        processor.key_down("ShiftLeft");
+        processor.key_down("ShiftRight");
+        processor.key_up("ShiftLeft");
+        processor.io_write(0xAA, 0x06);
+        assert_eq!(processor.io_read(0xA9), 0xFE);
+        // La repetición automática no acumula pulsaciones
+        processor.key_down("ShiftRight");
+        processor.key_up("ShiftRight");
+        assert_eq!(processor.io_read(0xA9), 0xFF);
+
+        processor.key_down("ShiftLeft");
+        processor.key_down("ControlLeft");
+        processor.key_up("ShiftLeft");
+        assert_eq!(processor.io_read(0xA9), 0xFD);
+        processor.release_all_keys();
+        processor.key_down("ShiftRight");
+        processor.key_up("ShiftRight");
+        assert_eq!(processor.io_read(0xA9), 0xFF);
+    }
+}
@@ -0,0 +1,312 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - MÓDULOS REUBICABLES Y ENLAZADOR                  ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::{
+        Assembler, Disassembler, Linker, Location, MSX2Processor, ObjectFile, OutputFormat, RelocPart, RelocTarget,
+        RomHeader, SymbolTable,
+    };
+
+    const MAIN: &str = "        EXTERN IMPRIME, MENSAJE
+        PUBLIC INICIO
+INICIO: LD HL,MENSAJE
+        CALL IMPRIME
+.fin:   JR .fin
+        SECTION VARS
+CONTADOR: DS 2
+";
+
+    const LIB: &str = "        PUBLIC IMPRIME, MENSAJE
+IMPRIME: LD A,(HL)
+        OR A
+        RET Z
+        CALL CHPUT
+        INC HL
+        JR IMPRIME
+        SECTION DATA
+MENSAJE: DB \"HOLA\",0
+";
+
+    fn object(module: &str, source: &str) -> ObjectFile {
+        match Assembler::new(OutputFormat::Rom).symbols(&SymbolTable::msx()).assemble_object(module, source) {
+            Ok(object) => object,
+            Err(errors) => panic!("{}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n")),
+        }
+    }
+
+    fn object_errors(source: &str) -> Vec<String> {
+        match Assembler::new(OutputFormat::Rom).assemble_object("m.asm", source) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    fn link_errors(linker: Linker, script: &str) -> Vec<String> {
+        match linker.link(script) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_object_sections_and_relocations() {
+        let main = object("main.asm", MAIN);
+        assert_eq!(main.module, "main.asm");
+        assert_eq!(main.sections.len(), 2);
+        assert_eq!((main.sections[0].name.as_str(), main.sections[0].size), ("CODE", 8));
+        // DS al final de VARS solo reserva
+        assert_eq!((main.sections[1].name.as_str(), main.sections[1].size), ("VARS", 2));
+        assert!(main.sections[1].data.is_empty());
+        assert_eq!(main.externs, vec!["IMPRIME", "MENSAJE"]);
+        assert_eq!(main.relocations.len(), 2);
+        assert_eq!(main.relocations[0].offset, 1);
+        assert_eq!(main.relocations[0].part, RelocPart::Word);
+        assert_eq!(main.relocations[0].target, RelocTarget::Extern(1));
+        assert_eq!((main.relocations[1].offset, main.relocations[1].part), (4, RelocPart::Call));
+        // El JR dentro de la sección no necesita reubicación
+        assert_eq!(main.sections[0].data[6..8], [0x18, 0xFE]);
+
+        let inicio = main.symbols.iter().find(|s| s.name == "INICIO").unwrap();
+        assert!(inicio.public);
+        assert_eq!(inicio.location, Location::Section(0, 0));
+        let contador = main.symbols.iter().find(|s| s.name == "CONTADOR").unwrap();
+        assert_eq!((contador.public, contador.location), (false, Location::Section(1, 0)));
+
+        let parts = object("m.asm", "        EXTERN TABLA\n        LD A,LOW(TABLA+3)\n        LD B,HIGH(TABLA)\n        LD C,BANK(TABLA)\nAQUI:   DW AQUI+2, TABLA+5\n        DB FIN-AQUI\nFIN:\n");
+        let kinds: Vec<(u16, RelocPart, u16)> = parts.relocations.iter().map(|r| (r.offset, r.part, r.addend)).collect();
+        assert_eq!(
+            kinds,
+            vec![(1, RelocPart::Low, 3), (3, RelocPart::High, 0), (5, RelocPart::Bank, 0), (6, RelocPart::Word, 8), (8, RelocPart::Word, 5)]
+        );
+        assert_eq!(parts.sections[0].data[10], 5);
+    }
+
+    #[test]
+    fn test_object_file_round_trip() {
+        for object in [object("main.asm", MAIN), object("lib.asm", LIB)] {
+            let bytes = object.to_bytes();
+            assert!(bytes.starts_with(b"MSXREL\x01"));
+            assert_eq!(ObjectFile::parse(&bytes).unwrap(), object);
+            assert!(ObjectFile::parse(&bytes[..bytes.len() - 1]).unwrap_err().contains("truncado"));
+        }
+        assert!(ObjectFile::parse(b"MSXDOS").unwrap_err().contains("MSXREL"));
+        // Una reubicación fuera de su sección no se acepta
+        let mut bad = object("main.asm", MAIN);
+        bad.relocations[0].offset = 7;
+        assert!(ObjectFile::parse(&bad.to_bytes()).unwrap_err().contains("fuera de la sección"));
+    }
+
+    #[test]
+    fn test_relocatable_source_errors() {
+        let errors = object_errors("        ORG 4000h\n");
+        assert!(errors[0].contains("ORG no se usa"), "{:?}", errors);
+        let errors = object_errors("        EXTERN LEJOS\n        JR LEJOS\n");
+        assert!(errors[0].starts_with("m.asm:2: Salto relativo a otra sección"), "{:?}", errors);
+        let errors = object_errors("        PUBLIC NADA\n");
+        assert!(errors[0].contains("PUBLIC de un símbolo no definido: NADA"), "{:?}", errors);
+        let errors = object_errors("AQUI:   LD A,AQUI\n");
+        assert!(errors[0].contains("no cabe en un byte"), "{:?}", errors);
+        let errors = object_errors("AQUI:   LD HL,AQUI*2\n");
+        assert!(errors[0].contains("Expresión no reubicable"), "{:?}", errors);
+        let errors = object_errors("AQUI:   DS AQUI\n");
+        assert!(errors[0].contains("DS necesita un valor absoluto"), "{:?}", errors);
+        // En un ensamblado normal no hay secciones, pero PUBLIC se admite
+        match Assembler::new(OutputFormat::Bin).assemble("        PUBLIC X\n        SECTION DATA\nX:      NOP\n") {
+            Err(errors) => assert_eq!(errors[0].to_string(), "main.asm:2: SECTION solo se usa en módulos reubicables"),
+            Ok(_) => panic!("SECTION sin módulo"),
+        }
+    }
+
+    #[test]
+    fn test_link_matches_single_source() {
+        let script = "OUTPUT rom
+REGION PAGE1 ROM 4010h-7FFFh
+REGION RAM RAM C000h-F37Fh
+PLACE CODE PAGE1
+PLACE DATA PAGE1
+PLACE VARS RAM
+ENTRY INICIO
+";
+        let linked = Linker::new()
+            .symbols(&SymbolTable::msx())
+            .object(object("main.asm", MAIN))
+            .object(object("lib.asm", LIB))
+            .link(script)
+            .unwrap();
+        assert_eq!(linked.symbols["INICIO"], 0x4010);
+        assert_eq!(linked.symbols["IMPRIME"], 0x4018);
+        assert_eq!(linked.symbols["MENSAJE"], 0x4021);
+
+        // Lo mismo en un solo fuente, en el orden que da el guion
+        let single = "        ORG 4010h
+INICIO: LD HL,MENSAJE
+        CALL IMPRIME
+.fin:   JR .fin
+IMPRIME: LD A,(HL)
+        OR A
+        RET Z
+        CALL CHPUT
+        INC HL
+        JR IMPRIME
+MENSAJE: DB \"HOLA\",0
+";
+        let expected = Assembler::new(OutputFormat::Rom).symbols(&SymbolTable::msx()).assemble(single).unwrap();
+        let rom = linked.output().unwrap();
+        assert_eq!(rom, expected.to_rom().unwrap());
+        assert_eq!(RomHeader::parse(&rom).unwrap().init, 0x4010);
+
+        let map = linked.map();
+        assert!(map.starts_with("; Enlazado rom, entrada en 4010h\n"), "{}", map);
+        assert!(map.contains("VARS               main.asm         RAM              -  0C000h         2"), "{}", map);
+    }
+
+    #[test]
+    fn test_link_bin_and_com() {
+        let program = object("prog.asm", "        PUBLIC INICIO\nINICIO: LD DE,TEXTO\n        LD C,9\n        JP 5\n        SECTION DATA\nTEXTO:  DB \"OK$\"\n");
+        let com = Linker::new()
+            .object(program.clone())
+            .link("OUTPUT com\nREGION TPA RAM 0100h-0BFFFh\nPLACE CODE TPA\nPLACE DATA TPA\n")
+            .unwrap();
+        let bytes = com.output().unwrap();
+        assert_eq!(bytes[..3], [0x11, 0x08, 0x01]);
+        assert_eq!(&bytes[8..11], b"OK$");
+
+        let bin = Linker::new()
+            .object(program)
+            .link("OUTPUT bin\nREGION ALTA RAM 0C000h-0DFFFh\nPLACE DATA ALTA\nPLACE CODE ALTA\nENTRY INICIO\n")
+            .unwrap();
+        let bytes = bin.output().unwrap();
+        // Cabecera de BLOAD: FE, inicio, fin y ejecución
+        assert_eq!(bytes[..7], [0xFE, 0x00, 0xC0, 0x0A, 0xC0, 0x03, 0xC0]);
+        assert_eq!(bytes[7 + 3..7 + 6], [0x11, 0x00, 0xC0]);
+    }
+
+    #[test]
+    fn test_placements_checked_against_memory_map() {
+        let code = object("m.asm", "        DS 200h,0\n");
+        let errors = link_errors(Linker::new().object(code.clone()), "REGION BAJA ROM 3F00h-7FFFh\nPLACE CODE BAJA\n");
+        assert_eq!(errors, vec!["guion:2: La sección CODE (3F00h-40FFh) invade BIOS (0000h-3FFFh)"]);
+
+        let vars = object("m.asm", "        SECTION VARS\n        DS 100h\n");
+        let errors = link_errors(Linker::new().object(vars), "REGION ALTA RAM 0F300h-0FFFFh\nPLACE VARS ALTA\n");
+        assert!(errors[0].contains("invade Variables de sistema (0F380h-0FFFFh)"), "{:?}", errors);
+
+        // Un BIN no puede cargarse sobre el BASIC
+        let errors = link_errors(Linker::new().object(code.clone()), "OUTPUT bin\nREGION R RAM 7F00h-0BFFFh\nPLACE CODE R\n");
+        assert!(errors[0].contains("invade BASIC"), "{:?}", errors);
+
+        // En una ROM la RAM solo se reserva
+        let errors = link_errors(Linker::new().object(code), "REGION R RAM 0C000h-0DFFFh\nPLACE CODE R\n");
+        assert!(errors[0].contains("tiene datos y va a RAM"), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_script_and_resolution_errors() {
+        let code = object("m.asm", "        DS 100h,0\n        SECTION OTRA\n        NOP\n");
+        let errors = link_errors(Linker::new().object(code.clone()), "REGION P ROM 4000h-407Fh\nPLACE CODE P\nPLACE OTRA Q\n");
+        assert_eq!(errors, vec!["guion:3: Región desconocida: Q"]);
+        let errors = link_errors(Linker::new().object(code.clone()), "REGION P ROM 4000h-407Fh\nPLACE CODE P\n");
+        assert_eq!(errors[0], "guion:2: La sección CODE (256 bytes) no cabe en P: quedan 128 bytes");
+        assert!(errors[1].ends_with("La sección OTRA no tiene PLACE en el guion"), "{:?}", errors);
+        let errors = link_errors(Linker::new(), "REGION B BANK 8000h-9FFFh 1-3\nOUTPUT tap\n");
+        assert_eq!(errors, vec!["guion:1: Una región BANK necesita el mapper en OUTPUT", "guion:2: Formato de salida desconocido: tap"]);
+        let errors = link_errors(Linker::new(), "OUTPUT rom konami\nREGION B BANK 4000h-5FFFh 1-3\n");
+        assert_eq!(errors, vec!["guion:2: El mapper konami no cambia el banco de 4000h"]);
+        let errors = link_errors(Linker::new(), "OUTPUT rom konami\nREGION B BANK 8000h-9FFFh 1-256\n");
+        assert_eq!(errors, vec!["guion:2: Banco 256 fuera de los 256 del mapper"]);
+        let errors = link_errors(Linker::new(), "OUTPUT rom ascii16\nREGION B BANK 8000h-BFFFh 2-FFFFh\n");
+        assert_eq!(errors, vec!["guion:2: Banco 65535 fuera de los 256 del mapper"]);
+
+        let user = object("u.asm", "        EXTERN FALTA, CHPUT\n        CALL FALTA\n        CALL CHPUT\n");
+        let errors = link_errors(Linker::new().object(user.clone()), "REGION P ROM 4010h-7FFFh\nPLACE CODE P\n");
+        assert_eq!(errors, vec!["Símbolo externo sin definir: FALTA", "Símbolo externo sin definir: CHPUT"]);
+        let twice = object("d.asm", "        PUBLIC FALTA\nFALTA:  RET\n");
+        let linker = Linker::new().symbols(&SymbolTable::msx()).object(user).object(twice.clone()).object(twice);
+        let errors = link_errors(linker, "REGION P ROM 4010h-7FFFh\nPLACE CODE P\n");
+        assert!(errors[0].starts_with("Símbolo público duplicado: FALTA"), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_megarom_banks_and_far_calls() {
+        let main = object(
+            "main.asm",
+            "        EXTERN MUSICA, __BANK_BANCOS
+        PUBLIC INICIO
+INICIO: LD A,BANK(MUSICA)
+        LD (__BANK_BANCOS),A
+        LD (8000h),A
+        CALL MUSICA
+        JP MUSICA
+",
+        );
+        let music = object(
+            "musica.asm",
+            "        SECTION MUSICA
+        PUBLIC MUSICA
+MUSICA: CALL NOTA
+        RET
+NOTA:   RET
+",
+        );
+        let script = "OUTPUT rom konami
+REGION FIJO ROM 4010h-5FFFh
+REGION BANCOS BANK 8000h-9FFFh 1-7
+REGION VARS RAM 0E000h-0EFFFh
+PLACE CODE FIJO
+PLACE MUSICA BANCOS 3
+FAR FIJO VARS
+ENTRY INICIO
+";
+        let linked = Linker::new().object(main.clone()).object(music.clone()).link(script).unwrap();
+        let rom = linked.output().unwrap();
+        assert_eq!(rom.len(), 0x10000);
+        assert_eq!(RomHeader::parse(&rom).unwrap().init, 0x4010);
+        assert_eq!(linked.symbols["__BANK_BANCOS"], 0xE000);
+        assert_eq!(linked.symbols["MUSICA"], 0x8000);
+        // LD A,3 / LD (0E000h),A / LD (8000h),A
+        assert_eq!(rom[0x10..0x18], [0x3E, 0x03, 0x32, 0x00, 0xE0, 0x32, 0x00, 0x80]);
+        // Banco 3 en el fichero; la llamada dentro del banco es directa
+        assert_eq!(rom[3 * 0x2000..3 * 0x2000 + 5], [0xCD, 0x04, 0x80, 0xC9, 0xC9]);
+
+        // El CALL y el JP del código fijo pasan por rutinas del enlazador
+        let (call, jump) = (linked.symbols["__FAR_MUSICA"], linked.symbols["__FARJP_MUSICA"]);
+        assert_eq!(rom[0x18..0x1E], [0xCD, call as u8, (call >> 8) as u8, 0xC3, jump as u8, (jump >> 8) as u8]);
+        let stub = Disassembler::new(SymbolTable::new()).disassemble(&rom[..0x2000], 0x4000, call).listing();
+        for text in ["LD HL,(0E000h)", "LD A,03h", "LD (8000h),A", "CALL 8000h", "LD A,L", "POP HL"] {
+            assert!(stub.contains(text), "{}\n{}", text, stub);
+        }
+        assert!(linked.map().contains("MUSICA             musica.asm       BANCOS           3  8000h"), "{}", linked.map());
+
+        // Sin FAR no hay cómo llegar al otro banco
+        let errors = link_errors(Linker::new().object(main).object(music), &script.replace("FAR FIJO VARS\n", ""));
+        assert!(errors.iter().any(|e| e.contains("MUSICA (banco 3 de BANCOS) desde otro banco: falta FAR")), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_processor_link_api() {
+        let mut processor = MSX2Processor::new(256, 212);
+        let main = processor.assemble_object(MAIN, "main.asm");
+        assert!(main.starts_with(b"MSXREL"));
+        assert!(processor.assemble_object("        ORG 0\n", "x.asm").is_empty());
+        assert_eq!(processor.add_object(&main), "✅ Módulo main.asm: 2 secciones");
+        assert!(processor.add_object(b"basura").starts_with("❌ Error"));
+
+        let script = "REGION P ROM 4010h-7FFFh\nREGION R RAM 0C000h-0CFFFh\nPLACE CODE P\nPLACE DATA P\nPLACE VARS R\n";
+        let report = processor.link_report(script);
+        assert!(report.contains(r#""ok":false"#) && report.contains("Ningún módulo tiene la sección DATA"), "{}", report);
+        assert!(processor.link(script).is_empty());
+
+        processor.add_object(&processor.assemble_object(LIB, "lib.asm"));
+        let report = processor.link_report(script);
+        assert!(report.starts_with(r#"{"ok":true,"format":"rom","mapper":"plain","size":8192,"entry":16400,"#), "{}", report);
+        assert!(report.contains(r#"{"section":"VARS","module":"main.asm","region":"R","bank":null,"address":49152,"size":2}"#));
+        assert_eq!(processor.link(script).len(), 0x2000);
+        assert!(processor.link_map(script).contains("IMPRIME"));
+
+        processor.clear_objects();
+        assert!(processor.link_report(script).contains("Ningún módulo tiene la sección CODE"));
+    }
+}
@@ -0,0 +1,200 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - LECTOR DE ARCHIVOS LZH / LHA                     ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::lzh::crc16;
+    use msx2_processor::{FileKind, LzhArchive, MSX2Processor, RtcDateTime, ZipArchive};
+
+    fn repo_file(path: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path)).unwrap()
+    }
+
+    fn pengadv_rom() -> Vec<u8> {
+        let zip = repo_file("rooms/pengadvb.zip");
+        let archive = ZipArchive::parse(&zip).unwrap();
+        archive.extract(archive.find("rom.u7").unwrap()).unwrap()
+    }
+
+    /// Archivo de nivel 0 con una entrada -lh0- (cabecera y checksum a mano)
+    fn level0_stored(name: &str, data: &[u8]) -> Vec<u8> {
+        level0("-lh0-", name, data, data.len())
+    }
+
+    /// Entrada de nivel 0 con `packed` ya comprimido que da `size` bytes;
+    /// el CRC es el de `packed`, solo vale para -lh0-
+    fn level0(method: &str, name: &str, packed: &[u8], size: usize) -> Vec<u8> {
+        let data = packed;
+        let mut header = method.as_bytes().to_vec();
+        header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(size as u32).to_le_bytes());
+        header.extend_from_slice(&[0x00, 0x50, 0x2E, 0x11]); // 1988-09-14 10:00:00
+        header.extend_from_slice(&[0x20, 0x00, name.len() as u8]);
+        header.extend_from_slice(name.as_bytes());
+        header.extend_from_slice(&crc16(data).to_le_bytes());
+
+        let checksum = header.iter().fold(0u8, |a, &b| a.wrapping_add(b));
+        let mut out = vec![header.len() as u8, checksum];
+        out.extend_from_slice(&header);
+        out.extend_from_slice(data);
+        out.push(0);
+        out
+    }
+
+    #[test]
+    fn test_crc16_reference_value() {
+        assert_eq!(crc16(b"123456789"), 0xBB3D);
+    }
+
+    #[test]
+    fn test_mixed_levels_and_methods() {
+        let lzh = repo_file("test_lzh/pengadvb.lzh");
+        let archive = LzhArchive::parse(&lzh).unwrap();
+        let summary: Vec<(&str, &str, u8)> = archive
+            .entries()
+            .iter()
+            .map(|e| (e.name.as_str(), e.method.as_str(), e.level))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("block0.bin", "-lh5-", 2),
+                ("block1.bin", "-lh6-", 1),
+                ("block2.bin", "-lh0-", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_members_match_loose_files() {
+        let lzh = repo_file("test_lzh/pengadvb.lzh");
+        let archive = LzhArchive::parse(&lzh).unwrap();
+        for i in 0..3 {
+            let expected = repo_file(&format!("test_zips/block{}.bin", i));
+            assert_eq!(archive.extract(i).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_header_timestamps() {
+        let lzh = repo_file("test_lzh/pengadvb.lzh");
+        let archive = LzhArchive::parse(&lzh).unwrap();
+        // Nivel 1: fecha MS-DOS local
+        let dos = RtcDateTime { year: 1988, month: 9, day: 14, hour: 10, minute: 30, second: 0 };
+        assert_eq!(archive.entries()[1].modified, dos);
+        // Nivel 2: segundos Unix (UTC)
+        assert_eq!(archive.entries()[0].modified.to_unix(), 590_149_800);
+    }
+
+    #[test]
+    fn test_lh7_multi_block_rom_with_directory() {
+        let lzh = repo_file("test_lzh/pengadv_rom.lzh");
+        let archive = LzhArchive::parse(&lzh).unwrap();
+        let entry = &archive.entries()[0];
+        assert_eq!(entry.name, "GAMES/PENGADV.ROM");
+        assert_eq!(entry.method, "-lh7-");
+        assert!(entry.compressed_size < entry.size);
+        assert_eq!(archive.extract(0).unwrap(), pengadv_rom());
+        assert_eq!(archive.pick(FileKind::Rom), Some(0));
+    }
+
+    #[test]
+    fn test_level0_stored_built_by_hand() {
+        let lzh = level0_stored("HELLO.BIN", b"hello msx");
+        let archive = LzhArchive::parse(&lzh).unwrap();
+        assert_eq!(archive.find("hello.bin"), Some(0));
+        assert_eq!(archive.extract(0).unwrap(), b"hello msx");
+    }
+
+    #[test]
+    fn test_corrupted_data_fails_crc() {
+        let mut lzh = repo_file("test_lzh/pengadv_rom.lzh");
+        let middle = lzh.len() / 2;
+        lzh[middle] ^= 0x5A;
+        let archive = LzhArchive::parse(&lzh).unwrap();
+        assert!(archive.extract(0).is_err());
+    }
+
+    #[test]
+    fn test_single_symbol_out_of_table_rejected() {
+        // Tabla T de símbolo único 31 (hay 19) y una longitud C que la usa
+        let lzh = level0("-lh5-", "BAD.ROM", &[0x00, 0x01, 0x07, 0xC0, 0x20, 0x00], 16);
+        let error = LzhArchive::parse(&lzh).unwrap().extract(0).unwrap_err();
+        assert!(error.contains("Símbolo único"), "{}", error);
+        // T válida (símbolo 0) y C de símbolo único 511 (hay 510)
+        let lzh = level0("-lh5-", "BAD.ROM", &[0x00, 0x01, 0x00, 0x00, 0x1F, 0xF0, 0x00], 16);
+        let error = LzhArchive::parse(&lzh).unwrap().extract(0).unwrap_err();
+        assert!(error.contains("Símbolo único"), "{}", error);
+    }
+
+    #[test]
+    fn test_invalid_archives_rejected() {
+        assert!(LzhArchive::parse(&[]).is_err());
+        assert!(LzhArchive::parse(b"\x00").is_err());
+        assert!(LzhArchive::parse(b"\x20\x00not an lzh header at all....").is_err());
+        let mut truncated = repo_file("test_lzh/pengadv_rom.lzh");
+        truncated.truncate(200);
+        assert!(LzhArchive::parse(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_extended_header_too_short_rejected() {
+        for ext_size in [1u8, 2] {
+            // Nivel 1: la base termina con el tamaño de la primera extensión
+            let mut header = b"-lh0-".to_vec();
+            header.extend_from_slice(&[0; 12]);
+            header.extend_from_slice(&[0x20, 0x01, 0x01, b'A', 0x00, 0x00, b'M', ext_size, 0x00]);
+            let checksum = header.iter().fold(0u8, |a, &b| a.wrapping_add(b));
+            let mut lzh = vec![header.len() as u8, checksum];
+            lzh.extend_from_slice(&header);
+            lzh.extend_from_slice(&[0; 8]);
+            let error = LzhArchive::parse(&lzh).err().unwrap();
+            assert!(error.contains("extendida"), "{}", error);
+
+            // Nivel 2: tamaño total de la cabecera en los dos primeros bytes
+            let mut lzh = vec![32, 0];
+            lzh.extend_from_slice(b"-lh0-");
+            lzh.extend_from_slice(&[0; 12]);
+            lzh.extend_from_slice(&[0x20, 0x02, 0x00, 0x00, b'M', ext_size, 0x00]);
+            lzh.extend_from_slice(&[0; 8]);
+            let error = LzhArchive::parse(&lzh).err().unwrap();
+            assert!(error.contains("extendida"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_processor_lzh_api_and_loader() {
+        let mut processor = MSX2Processor::new(256, 212);
+        let lzh = repo_file("test_lzh/pengadv_rom.lzh");
+
+        let listing = processor.list_lzh_entries(&lzh);
+        assert!(listing.contains(r#""name":"GAMES/PENGADV.ROM","size":32768"#));
+        assert!(listing.contains(r#""method":"-lh7-""#));
+        assert!(listing.contains(r#""level":2,"kind":"rom""#));
+        assert_eq!(processor.pick_lzh_member(&lzh, "rom"), 0);
+        assert_eq!(processor.pick_lzh_member(&lzh, "disk"), -1);
+        assert_eq!(processor.extract_lzh_entry(&lzh, 0), pengadv_rom());
+
+        // El cartucho queda en el slot 1 y se ve desde 4000h
+        assert!(processor.load_archive_member(&lzh, 0).starts_with("✅ ROM"));
+        processor.io_write(0xA8, 0b0000_0100);
+        assert_eq!(processor.mem_read(0x4000), b'A');
+        assert_eq!(processor.mem_read(0x4001), b'B');
+
+        assert!(processor.load_archive_member(&lzh, 5).starts_with("❌"));
+    }
+
+    #[test]
+    fn test_archive_megarom_loads_into_slot() {
+        let rom = repo_file("rooms/Vampire Killer (Japan, Europe).rom");
+        assert_eq!(rom.len(), 128 * 1024);
+        let lzh = level0_stored("VAMPIRE.ROM", &rom);
+        let mut processor = MSX2Processor::new(256, 212);
+        assert_eq!(processor.load_archive_member(&lzh, 0), "✅ MegaROM konami de 128 KB en slot 1-0");
+        processor.io_write(0xA8, 0b0001_0100);
+        assert_eq!(processor.mem_read(0x4000), b'A');
+        assert_eq!(processor.mem_read(0x8000), rom[0x4000]);
+    }
+}
@@ -0,0 +1,169 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - DESCOMPRESORES DE GRÁFICOS                       ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::packers::{probe, unpack};
+    use msx2_processor::screen::{render_screen2, render_screen8, TMS9918_PALETTE};
+    use msx2_processor::{MSX2Processor, Packer, ScreenMode, ZipArchive};
+
+    fn repo_file(path: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path)).unwrap()
+    }
+
+    // "ABABABAB" en cada formato: dos literales y una copia de 6 a distancia 2
+    const PLETTER: [u8; 9] = [0x0D, b'A', b'B', 0xB5, 0x01, 0x55, 0x55, 0x55, 0x40];
+    const BITBUSTER: [u8; 14] = [8, 0, 0, 0, 0x39, b'A', b'B', 0x01, 0xFF, 0x00, 0xFF, 0x80, 0x00, 0x00];
+    const APLIB: [u8; 6] = [b'A', 0x52, b'B', 0x02, 0x60, 0x00];
+    const ZX0: [u8; 7] = [0x39, b'A', b'B', 0xFC, 0xD5, 0x55, 0x60];
+    const ZX7: [u8; 7] = [b'A', 0x4B, b'B', 0x01, 0x00, 0x00, 0x80];
+
+    /// PackBits que rellena una tabla de patrones de SCREEN 2 con F0h
+    fn rle_patterns() -> Vec<u8> {
+        let mut data = Vec::new();
+        for _ in 0..48 {
+            data.extend_from_slice(&[0x81, 0xF0]);
+        }
+        data.push(0x80);
+        data
+    }
+
+    #[test]
+    fn test_lz_formats_decode_reference_streams() {
+        let cases: [(Packer, &[u8]); 5] = [
+            (Packer::Pletter, &PLETTER),
+            (Packer::BitBuster, &BITBUSTER),
+            (Packer::Aplib, &APLIB),
+            (Packer::Zx0, &ZX0),
+            (Packer::Zx7, &ZX7),
+        ];
+        for (packer, stream) in cases {
+            let unpacked = unpack(packer, stream).unwrap();
+            assert_eq!(unpacked.data, b"ABABABAB", "{}", packer.name());
+            assert_eq!(unpacked.consumed, stream.len(), "{}", packer.name());
+        }
+    }
+
+    /// test_packers/pengadv_4k.*: los primeros 4 KB de rom.u7 comprimidos en
+    /// cada formato y comprobados con test_packers/depack_referencia.py, que
+    /// traduce paso a paso depack.c de aPLib, dzx0.c y las rutinas Z80 de
+    /// Pletter 0.5 y BitBuster 1.2
+    #[test]
+    fn test_fixed_vectors_match_reference_depackers() {
+        let zip = repo_file("rooms/pengadvb.zip");
+        let archive = ZipArchive::parse(&zip).unwrap();
+        let rom = archive.extract(archive.find("rom.u7").unwrap()).unwrap();
+
+        for (packer, extension) in [
+            (Packer::Pletter, "plet5"),
+            (Packer::BitBuster, "bb"),
+            (Packer::Aplib, "apl"),
+            (Packer::Zx0, "zx0"),
+        ] {
+            let stream = repo_file(&format!("test_packers/pengadv_4k.{}", extension));
+            let unpacked = unpack(packer, &stream).unwrap();
+            assert_eq!(unpacked.data, &rom[..4096], "{}", packer.name());
+            assert_eq!(unpacked.consumed, stream.len(), "{}", packer.name());
+        }
+
+        // BitBuster: tamaño original en 4 bytes little-endian delante del flujo
+        let stream = repo_file("test_packers/pengadv_4k.bb");
+        assert_eq!(&stream[..4], &[0x00, 0x10, 0x00, 0x00]);
+        // Pletter: los 3 bits altos del primer byte dan los bits extra del desplazamiento
+        assert_eq!(repo_file("test_packers/pengadv_4k.plet5")[0] >> 5, 4);
+    }
+
+    #[test]
+    fn test_rle_packbits() {
+        let unpacked = unpack(Packer::Rle, &[0x01, b'A', b'B', 0xFD, b'C', 0x80, 0x55]).unwrap();
+        assert_eq!(unpacked.data, b"ABCCCC");
+        assert_eq!(unpacked.consumed, 6);
+        // Sin marca de fin llega hasta el final de los datos
+        assert_eq!(unpack(Packer::Rle, &[0x00, b'X', 0xFF, b'Y']).unwrap().data, b"XYY");
+    }
+
+    #[test]
+    fn test_trailing_data_is_not_consumed() {
+        let mut stream = ZX7.to_vec();
+        stream.extend_from_slice(b"siguiente bloque");
+        assert_eq!(unpack(Packer::Zx7, &stream).unwrap().consumed, ZX7.len());
+    }
+
+    #[test]
+    fn test_truncated_streams_fail() {
+        for (packer, stream) in [
+            (Packer::Pletter, &PLETTER[..5]),
+            (Packer::BitBuster, &BITBUSTER[..9]),
+            (Packer::Aplib, &APLIB[..4]),
+            (Packer::Zx0, &ZX0[..4]),
+            (Packer::Zx7, &ZX7[..4]),
+        ] {
+            assert!(unpack(packer, stream).is_err(), "{}", packer.name());
+        }
+    }
+
+    #[test]
+    fn test_offset_before_start_of_output_fails() {
+        // ZX7: un literal y luego una copia desde 5 bytes atrás
+        let error = unpack(Packer::Zx7, &[b'A', 0xC0, 0x04]).unwrap_err();
+        assert!(error.contains("Distancia 5"));
+    }
+
+    #[test]
+    fn test_bitbuster_checks_header_size() {
+        let mut stream = BITBUSTER;
+        stream[0] = 9;
+        assert!(unpack(Packer::BitBuster, &stream).unwrap_err().contains("cabecera"));
+    }
+
+    #[test]
+    fn test_probe_finds_screen2_patterns_at_offset() {
+        let mut data = b"basura delante".to_vec();
+        let offset = data.len();
+        data.extend(rle_patterns());
+
+        let hits = probe(&data, offset);
+        let hit = hits.iter().find(|h| h.packer == Packer::Rle).unwrap();
+        assert_eq!(hit.size, 0x1800);
+        assert_eq!(hit.consumed, 97);
+        assert_eq!(hit.screen, Some(ScreenMode::Screen2));
+        assert!(probe(&data, data.len() + 1).is_empty());
+        // Salidas demasiado pequeñas no cuentan como acierto
+        assert!(probe(&ZX7, 0).is_empty());
+    }
+
+    #[test]
+    fn test_screen_renderers() {
+        let rgba = render_screen2(&vec![0xF0; 0x1800], &TMS9918_PALETTE);
+        assert_eq!(rgba.len(), 256 * 192 * 4);
+        assert_eq!(&rgba[0..4], &[255, 255, 255, 255]);
+        assert_eq!(&rgba[16..20], &[0, 0, 0, 255]);
+
+        let rgba = render_screen8(&[0xE0, 0x1C, 0x03, 0xFF]);
+        assert_eq!(rgba, [0, 255, 0, 255, 255, 0, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255]);
+
+        assert_eq!(ScreenMode::guess(0x6A00), Some(ScreenMode::Screen5));
+        assert_eq!(ScreenMode::guess(0xD400), Some(ScreenMode::Screen8));
+        assert_eq!(ScreenMode::guess(100), None);
+    }
+
+    #[test]
+    fn test_processor_packer_api() {
+        let processor = MSX2Processor::new(256, 212);
+        let mut data = vec![0xAA; 3];
+        data.extend(rle_patterns());
+
+        assert_eq!(processor.unpack_data("aplib", &APLIB, 0), b"ABABABAB");
+        assert!(processor.unpack_data("lzma", &APLIB, 0).is_empty());
+        assert!(processor.unpack_data("zx0", &ZX0, 99).is_empty());
+
+        let json = processor.probe_packers(&data, 3);
+        assert!(json.contains(r#"{"packer":"rle","offset":3,"consumed":97,"size":6144,"screen":2}"#));
+
+        assert_eq!(processor.unpack_to_rgba("rle", &data, 3, 0).len(), 256 * 192 * 4);
+        assert_eq!(processor.unpack_to_rgba("rle", &data, 3, 8).len(), 6144 * 4);
+        assert!(processor.render_screen(&[0; 10], 0).is_empty());
+    }
+}
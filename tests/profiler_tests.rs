@@ -0,0 +1,251 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - PERFILADOR                                       ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::{
+        Assembler, MSX2Processor, Machine, MapperType, OutputFormat, Profiler, SlotContent, SlotId, SymbolKind,
+        SymbolTable,
+    };
+
+    const PROGRAMA: &str = "        ORG 8000h
+INICIO: LD SP,0F000h
+        LD B,4
+.bucle: DJNZ .bucle
+        CALL SUB
+FIN:    JR FIN
+SUB:    LD A,1
+        RET
+";
+
+    const ANIDADO: &str = "        ORG 8000h
+INICIO: LD SP,0F000h
+        XOR A
+        CALL NZ,EXTERNA
+        CALL EXTERNA
+        CALL EXTERNA
+FIN:    JR FIN
+EXTERNA:
+        CALL INTERNA
+        RET
+INTERNA:
+        NOP
+        RET
+";
+
+    const PRINCIPAL: &str = "        ORG 8000h
+INICIO: LD SP,0F000h
+        IM 1
+        EI
+BUCLE:  HALT
+        JR BUCLE
+";
+
+    const MANEJADOR: &str = "        ORG 0038h
+VBLANK: PUSH AF
+        IN A,(99h)
+        CALL TICK
+        POP AF
+        EI
+        RET
+TICK:   LD HL,0C000h
+        INC (HL)
+        RET
+";
+
+    fn loaded(source: &str) -> (Machine, SymbolTable) {
+        let assembly = Assembler::new(OutputFormat::Bin).assemble(source).unwrap();
+        let mut machine = Machine::with_ram();
+        machine.load(assembly.origin, &assembly.code);
+        machine.cpu.regs.pc = assembly.entry;
+        (machine, assembly.symbol_table())
+    }
+
+    fn profiled(source: &str, steps: usize) -> Machine {
+        let (mut machine, symbols) = loaded(source);
+        machine.profiler = Some(Profiler::new().symbols(symbols));
+        for _ in 0..steps {
+            machine.step();
+        }
+        machine
+    }
+
+    #[test]
+    fn test_per_address() {
+        let machine = profiled(PROGRAMA, 12);
+        let profiler = machine.profiler.as_ref().unwrap();
+        assert_eq!(profiler.total(), machine.cycles);
+        let rows = profiler.by_address();
+        // DJNZ: tres saltos de 13+1 y la salida de 8+1
+        assert_eq!((rows[0].address, rows[0].tstates, rows[0].count), (Some(0x8005), 51, 4));
+        assert_eq!(rows[0].label, "INICIO.bucle");
+        let fin = rows.iter().find(|r| r.address == Some(0x800A)).unwrap();
+        assert_eq!((fin.tstates, fin.count, fin.label.as_str()), (39, 3, "FIN"));
+        assert!(rows.windows(2).all(|w| w[0].tstates >= w[1].tstates));
+    }
+
+    #[test]
+    fn test_per_symbol() {
+        let machine = profiled(PROGRAMA, 12);
+        let rows = machine.profiler.as_ref().unwrap().by_symbol();
+        let names: Vec<(&str, u64)> = rows.iter().map(|r| (r.label.as_str(), r.tstates)).collect();
+        // Las etiquetas locales no parten la rutina
+        assert_eq!(names, vec![("INICIO", 11 + 8 + 51 + 18), ("FIN", 39), ("SUB", 8 + 11)]);
+
+        // Sin símbolos todo cae en la misma fila
+        let (mut machine, _) = loaded(PROGRAMA);
+        machine.profiler = Some(Profiler::new());
+        for _ in 0..12 {
+            machine.step();
+        }
+        let rows = machine.profiler.as_ref().unwrap().by_symbol();
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].label.as_str(), rows[0].address), ("(sin símbolo)", None));
+    }
+
+    #[test]
+    fn test_folded_call_stacks() {
+        let machine = profiled(ANIDADO, 20);
+        let profiler = machine.profiler.as_ref().unwrap();
+        let folded = profiler.folded();
+        let lines: Vec<&str> = folded.lines().collect();
+        assert!(lines.contains(&"INICIO;EXTERNA;INTERNA 32"), "{}", folded);
+        assert!(lines.contains(&"INICIO;EXTERNA 58"), "{}", folded);
+        assert!(lines.contains(&"INICIO 63") && lines.iter().any(|l| l.starts_with("FIN ")), "{}", folded);
+        // El CALL NZ no tomado no abre ninguna llamada
+        assert!(!lines.iter().any(|l| l.contains("EXTERNA;EXTERNA")), "{}", folded);
+        let sum: u64 = lines.iter().map(|l| l.rsplit(' ').next().unwrap().parse::<u64>().unwrap()).sum();
+        assert_eq!(sum, profiler.total());
+    }
+
+    #[test]
+    fn test_interrupt_handlers() {
+        let (mut machine, mut symbols) = loaded(PRINCIPAL);
+        let handler = Assembler::new(OutputFormat::Bin).assemble(MANEJADOR).unwrap();
+        machine.load(handler.origin, &handler.code);
+        for (address, symbol) in handler.symbol_table().iter() {
+            symbols.insert(address, &symbol.name, SymbolKind::Label);
+        }
+        machine.bus.vdp.registers[1] |= 0x20;
+        machine.profiler = Some(Profiler::new().symbols(symbols));
+        while machine.frames < 4 {
+            machine.step();
+        }
+        for _ in 0..100 {
+            machine.step();
+        }
+        assert_eq!(machine.bus.peek(0xC000), 4);
+        let profiler = machine.profiler.as_ref().unwrap();
+        let handlers = profiler.by_handler();
+        assert_eq!(handlers.len(), 1);
+        assert_eq!((handlers[0].address, handlers[0].count, handlers[0].label.as_str()), (Some(0x0038), 4, "VBLANK"));
+        let folded = profiler.folded();
+        let inside: u64 = folded
+            .lines()
+            .filter(|l| l.contains("INT:VBLANK"))
+            .map(|l| l.rsplit(' ').next().unwrap().parse::<u64>().unwrap())
+            .sum();
+        assert_eq!(inside, handlers[0].tstates);
+        assert!(folded.lines().any(|l| l.starts_with("BUCLE;INT:VBLANK;TICK ")), "{}", folded);
+        assert_eq!(profiler.frames(), 4);
+    }
+
+    #[test]
+    fn test_megarom_banks() {
+        // ASCII 8K: 7000h elige el banco de 8000h-9FFFh
+        let mut rom = vec![0xFFu8; 4 * 0x2000];
+        rom[0x2000..0x2002].copy_from_slice(&[0x00, 0xC9]);
+        rom[0x4000..0x4004].copy_from_slice(&[0x00, 0x00, 0x00, 0xC9]);
+        let mut machine = Machine::with_ram();
+        machine.bus.slots.insert(SlotId { primary: 1, secondary: 0 }, SlotContent::megarom(rom, MapperType::Ascii8));
+        machine.bus.io_write(0xA8, 0xD7);
+        let code = [
+            0x31, 0x00, 0xF0, // LD SP,0F000h
+            0x3E, 0x01, 0x32, 0x00, 0x70, // LD A,1 / LD (7000h),A
+            0xCD, 0x00, 0x80, // CALL 8000h
+            0x3E, 0x02, 0x32, 0x00, 0x70, // LD A,2 / LD (7000h),A
+            0xCD, 0x00, 0x80, // CALL 8000h
+            0x18, 0xFE, // JR $
+        ];
+        machine.load(0xC000, &code);
+        machine.cpu.regs.pc = 0xC000;
+        assert_eq!(machine.bus.bank(0x8000).1, Some(0));
+        machine.profiler = Some(Profiler::new());
+        for _ in 0..14 {
+            machine.step();
+        }
+        assert_eq!(machine.bus.peek(0x8003), 0xC9);
+        let rows = machine.profiler.as_ref().unwrap().by_bank();
+        let banks: Vec<(&str, u64, u64)> = rows.iter().map(|r| (r.label.as_str(), r.tstates, r.count)).collect();
+        assert_eq!(banks, vec![("1-0:2", 3 * 5 + 11, 4), ("1-0:1", 5 + 11, 2)]);
+
+        let content = SlotContent::Rom { data: vec![0; 0x6000], base: 0x4000 };
+        assert_eq!((content.bank(0x4000), content.bank(0x8000), content.bank(0xA000)), (Some(0), Some(2), None));
+        assert_eq!(SlotContent::ram().bank(0x4000), None);
+    }
+
+    #[test]
+    fn test_pause_and_clear() {
+        let (mut machine, _) = loaded(PROGRAMA);
+        let mut profiler = Profiler::new();
+        profiler.active = false;
+        machine.profiler = Some(profiler);
+        for _ in 0..5 {
+            machine.step();
+        }
+        assert_eq!(machine.profiler.as_ref().unwrap().total(), 0);
+
+        let mut machine = profiled(PROGRAMA, 5);
+        let profiler = machine.profiler.as_mut().unwrap();
+        assert!(profiler.total() > 0);
+        profiler.clear();
+        assert_eq!(profiler.total(), 0);
+        assert!(profiler.by_address().is_empty() && profiler.folded().is_empty());
+    }
+
+    #[test]
+    fn test_report() {
+        let machine = profiled(PROGRAMA, 12);
+        let report = machine.profiler.as_ref().unwrap().report(2);
+        assert!(report.starts_with(&format!("Perfil: {} estados T en 0 cuadros\n", machine.cycles)), "{}", report);
+        assert!(report.contains("\nDIRECCIONES\n") && report.contains("\nRUTINAS\n"), "{}", report);
+        // Sin MegaROM ni interrupciones esas tablas no salen
+        assert!(!report.contains("BANCOS") && !report.contains("INTERRUPCIONES"), "{}", report);
+        let section: Vec<&str> = report.split("\nDIRECCIONES\n").nth(1).unwrap().lines().take_while(|l| !l.is_empty()).collect();
+        assert_eq!(section.len(), 2);
+        assert!(section[0].contains("8005h") && section[0].ends_with("INICIO.bucle"), "{}", section[0]);
+        assert!(section[0].contains("%"), "{}", section[0]);
+    }
+
+    #[test]
+    fn test_processor_profile() {
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.insert_ram(3, 0);
+        processor.io_write(0xA8, 0xFF);
+        processor.debug_assemble(PROGRAMA, "bin");
+        assert_eq!(processor.import_symbols("SUB: EQU 0x800C\n"), "✅ 1 símbolos importados");
+        assert!(processor.profile_start().starts_with("✅"));
+        processor.run_to(0x800A, 10_000);
+        let report = processor.profile_report(10);
+        assert!(report.contains("SUB"), "{}", report);
+        assert!(processor.profile_folded().lines().any(|l| l.ends_with(";SUB 19")), "{}", processor.profile_folded());
+
+        processor.profile_stop();
+        processor.step_into();
+        assert_eq!(processor.profile_report(10), report);
+        processor.profile_clear();
+        assert!(processor.profile_folded().is_empty());
+    }
+
+    #[test]
+    fn test_processor_megarom() {
+        let mut processor = MSX2Processor::new(256, 212);
+        assert!(processor.insert_megarom(1, 0, &[], "").starts_with("❌"));
+        assert!(processor.insert_megarom(1, 0, &[0; 0x20000], "mbc5").contains("Mapper desconocido"));
+        assert_eq!(processor.insert_megarom(1, 0, &[0; 0x20000], "konami"), "✅ MegaROM konami de 128 KB en slot 1-0");
+        // Sin escrituras LD (nn),A se queda con Konami
+        assert!(processor.insert_megarom(2, 0, &[0; 0x20000], "").contains("konami"));
+    }
+}
@@ -0,0 +1,146 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - RELOJ RP5C01 Y RAM CMOS                          ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::rtc::CMOS_BLOB_SIZE;
+    use msx2_processor::{ManualClock, Rp5c01, RtcDateTime};
+
+    // 2026-02-28 23:59:58 UTC
+    const START: i64 = 1_772_323_198;
+
+    fn read_reg(rtc: &mut Rp5c01, reg: u8) -> u8 {
+        rtc.write(0xB4, reg);
+        rtc.read(0xB5) & 0x0F
+    }
+
+    fn write_reg(rtc: &mut Rp5c01, reg: u8, value: u8) {
+        rtc.write(0xB4, reg);
+        rtc.write(0xB5, value);
+    }
+
+    #[test]
+    fn test_datetime_conversion_roundtrip() {
+        let dt = RtcDateTime::from_unix(START);
+        assert_eq!((dt.year, dt.month, dt.day), (2026, 2, 28));
+        assert_eq!((dt.hour, dt.minute, dt.second), (23, 59, 58));
+        assert_eq!(dt.to_unix(), START);
+        // 28 de febrero de 2026 es sábado
+        assert_eq!(dt.weekday(), 6);
+    }
+
+    #[test]
+    fn test_time_registers_follow_clock() {
+        let clock = ManualClock::new(START);
+        let mut rtc = Rp5c01::new(Box::new(clock.clone()));
+
+        assert_eq!(read_reg(&mut rtc, 0), 8);
+        assert_eq!(read_reg(&mut rtc, 1), 5);
+        // Año 2026 = 46 desde 1980
+        assert_eq!(read_reg(&mut rtc, 12), 4);
+        assert_eq!(read_reg(&mut rtc, 11), 6);
+
+        clock.advance(3);
+        let dt = rtc.datetime();
+        assert_eq!((dt.month, dt.day, dt.hour, dt.second), (3, 1, 0, 1));
+        // El domingo llega con el cambio de día
+        assert_eq!(read_reg(&mut rtc, 6), 0);
+    }
+
+    #[test]
+    fn test_upper_nibble_reads_as_ones() {
+        let mut rtc = Rp5c01::new(Box::new(ManualClock::new(START)));
+        rtc.write(0xB4, 0);
+        assert_eq!(rtc.read(0xB5) & 0xF0, 0xF0);
+    }
+
+    #[test]
+    fn test_timer_stop() {
+        let clock = ManualClock::new(START);
+        let mut rtc = Rp5c01::new(Box::new(clock.clone()));
+        write_reg(&mut rtc, 13, 0x00);
+        clock.advance(120);
+        assert_eq!(rtc.datetime().second, 58);
+        assert_eq!(rtc.datetime().minute, 59);
+    }
+
+    #[test]
+    fn test_ram_blocks_and_mode() {
+        let mut rtc = Rp5c01::new(Box::new(ManualClock::new(START)));
+        write_reg(&mut rtc, 13, 0x0A);
+        write_reg(&mut rtc, 0, 0x0A);
+        write_reg(&mut rtc, 5, 0x1F);
+        assert_eq!(read_reg(&mut rtc, 0), 0x0A);
+        assert_eq!(read_reg(&mut rtc, 5), 0x0F);
+
+        write_reg(&mut rtc, 13, 0x0B);
+        assert_eq!(read_reg(&mut rtc, 0), 0x00);
+        assert_eq!(read_reg(&mut rtc, 13), 0x0B);
+    }
+
+    #[test]
+    fn test_written_time_keeps_counting() {
+        let clock = ManualClock::new(START);
+        let mut rtc = Rp5c01::new(Box::new(clock.clone()));
+
+        // Poner los minutos en 12 desde el bus
+        write_reg(&mut rtc, 3, 1);
+        write_reg(&mut rtc, 2, 2);
+        clock.advance(62);
+        let dt = rtc.datetime();
+        assert_eq!((dt.hour, dt.minute, dt.second), (23, 14, 0));
+    }
+
+    #[test]
+    fn test_twelve_hour_mode() {
+        let mut rtc = Rp5c01::new(Box::new(ManualClock::new(START)));
+        write_reg(&mut rtc, 13, 0x09);
+        write_reg(&mut rtc, 10, 0x00);
+        write_reg(&mut rtc, 13, 0x08);
+        rtc.set_datetime(RtcDateTime::from_unix(START));
+
+        // 23h = 11 PM: decenas con el bit de PM
+        assert_eq!(read_reg(&mut rtc, 5), 0x03);
+        assert_eq!(read_reg(&mut rtc, 4), 0x01);
+        assert_eq!(rtc.datetime().hour, 23);
+    }
+
+    #[test]
+    fn test_alarm_match() {
+        let clock = ManualClock::new(START);
+        let mut rtc = Rp5c01::new(Box::new(clock.clone()));
+        write_reg(&mut rtc, 13, 0x09);
+        // Alarma en el minuto 01, sin hora ni día fijados
+        write_reg(&mut rtc, 2, 1);
+        write_reg(&mut rtc, 13, 0x0C);
+        assert!(!rtc.alarm_active());
+
+        clock.advance(62);
+        assert!(rtc.alarm_active());
+    }
+
+    #[test]
+    fn test_cmos_export_import() {
+        let clock = ManualClock::new(START);
+        let mut rtc = Rp5c01::new(Box::new(clock.clone()));
+        write_reg(&mut rtc, 13, 0x0A);
+        write_reg(&mut rtc, 0, 0x0A);
+        write_reg(&mut rtc, 13, 0x08);
+        rtc.set_datetime(RtcDateTime::from_unix(START - 3600));
+
+        let blob = rtc.export_cmos();
+        assert_eq!(blob.len(), CMOS_BLOB_SIZE);
+
+        // Otra sesión, un día después
+        let later = ManualClock::new(START + 86400);
+        let mut restored = Rp5c01::new(Box::new(later));
+        restored.import_cmos(&blob).unwrap();
+        write_reg(&mut restored, 13, 0x0A);
+        assert_eq!(read_reg(&mut restored, 0), 0x0A);
+        assert_eq!(restored.datetime().to_unix(), START + 86400 - 3600);
+
+        assert!(restored.import_cmos(&blob[..10]).is_err());
+    }
+}
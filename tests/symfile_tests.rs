@@ -0,0 +1,155 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - FICHEROS DE SÍMBOLOS Y LISTADOS                  ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::symbols::parse_symbol_file;
+    use msx2_processor::timing::timing;
+    use msx2_processor::{Assembler, Disassembler, MSX2Processor, OutputFormat, SymbolFormat, SymbolKind, SymbolTable, Timing};
+
+    const PROGRAM: &str = "        ORG 4010h
+INICIO: LD B,10
+.bucle: DJNZ .bucle
+        CALL CHPUT
+        RET
+TEXTO:  DB \"HOLA\",0
+TABLA:  DW INICIO,TEXTO,0
+";
+
+    fn assembly() -> msx2_processor::Assembly {
+        Assembler::new(OutputFormat::Rom).symbols(&SymbolTable::msx()).assemble(PROGRAM).unwrap()
+    }
+
+    #[test]
+    fn test_instruction_timing() {
+        assert_eq!(timing(&[0x00]), Timing::fixed(4));
+        assert_eq!(timing(&[0x10, 0xFE]), Timing::new(8, 13));
+        assert_eq!(timing(&[0xCD, 0xA2, 0x00]), Timing::fixed(17));
+        assert_eq!(timing(&[0xC4, 0xA2, 0x00]), Timing::new(10, 17));
+        assert_eq!(timing(&[0xDD, 0x7E, 0x05]), Timing::fixed(19));
+        assert_eq!(timing(&[0xDD, 0x21, 0x00, 0x40]), Timing::fixed(14));
+        assert_eq!(timing(&[0xCB, 0x46]), Timing::fixed(12));
+        assert_eq!(timing(&[0xFD, 0xCB, 0x01, 0xC6]), Timing::fixed(23));
+        assert_eq!(timing(&[0xED, 0xB0]), Timing::new(16, 21));
+        assert_eq!(timing(&[0xED, 0xB0]).text(), "21/16");
+        assert_eq!(timing(&[0xC9]).text(), "10");
+    }
+
+    #[test]
+    fn test_describe_label_offsets() {
+        let mut table = SymbolTable::msx();
+        table.insert(0x4010, "INICIO", SymbolKind::Label);
+        assert_eq!(table.describe(0x4010).as_deref(), Some("INICIO"));
+        assert_eq!(table.describe(0x4013).as_deref(), Some("INICIO+3"));
+        assert_eq!(table.describe(0x00A2).as_deref(), Some("CHPUT"));
+        // Las entradas de la BIOS solo se nombran exactas
+        assert_eq!(table.describe(0x00A3), None);
+        // Ni demasiado lejos de la etiqueta
+        assert_eq!(table.describe(0x4200), None);
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let assembly = assembly();
+        let sjasm = assembly.symbol_file(SymbolFormat::Sjasm);
+        assert!(sjasm.contains("INICIO: EQU 0x00004010\n"));
+        assert!(sjasm.contains("INICIO.bucle: EQU 0x00004012\n"));
+        assert!(assembly.symbol_file(SymbolFormat::NoIce).contains("DEF TEXTO 4018H\n"));
+        assert!(assembly.symbol_file(SymbolFormat::OpenMsx).contains("TABLA: equ 401Dh\n"));
+        assert_eq!(SymbolFormat::from_name("sjasmplus"), Some(SymbolFormat::Sjasm));
+        assert_eq!(SymbolFormat::from_name("NoICE").map(|f| f.name()), Some("noice"));
+        assert_eq!(SymbolFormat::from_name("elf"), None);
+    }
+
+    #[test]
+    fn test_parse_symbol_files() {
+        let text = "; símbolos\n\
+                    INICIO: EQU 0x00004010\n\
+                    DEF TEXTO 4018H\n\
+                    TABLA: equ 401Dh\n\
+                    PUNTERO equ $C000\n\
+                    CONTADOR = #C002 ; variable\n\
+                    esto no es un símbolo\n";
+        assert_eq!(
+            parse_symbol_file(text),
+            vec![
+                ("INICIO".to_string(), 0x4010),
+                ("TEXTO".to_string(), 0x4018),
+                ("TABLA".to_string(), 0x401D),
+                ("PUNTERO".to_string(), 0xC000),
+                ("CONTADOR".to_string(), 0xC002),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_formats_round_trip() {
+        let assembly = assembly();
+        for format in [SymbolFormat::Sjasm, SymbolFormat::NoIce, SymbolFormat::OpenMsx] {
+            let parsed = parse_symbol_file(&assembly.symbol_file(format));
+            let expected: Vec<(String, u16)> = assembly.symbols.iter().map(|(n, &a)| (n.clone(), a)).collect();
+            assert_eq!(parsed, expected, "{}", format.name());
+        }
+    }
+
+    #[test]
+    fn test_imported_symbols_in_disassembly() {
+        let assembly = assembly();
+        let mut table = SymbolTable::msx();
+        assert_eq!(table.import(&assembly.symbol_file(SymbolFormat::NoIce)), assembly.symbols.len());
+        let listing = Disassembler::new(table).disassemble(&assembly.code, 0x4010, 0x4010).listing();
+        assert!(listing.contains("INICIO:"), "{}", listing);
+        assert!(listing.contains("CALL CHPUT"), "{}", listing);
+        assert!(listing.contains("DJNZ INICIO.bucle"), "{}", listing);
+    }
+
+    #[test]
+    fn test_listing_columns() {
+        let listing = assembly().listing();
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines[0], "; ── main.asm ──");
+        assert!(lines.iter().any(|l| l.starts_with("    2  4010  06 0A           7  INICIO: LD B,10")), "{}", listing);
+        assert!(lines.iter().any(|l| l.starts_with("    3  4012  10 FE        13/8  .bucle: DJNZ .bucle")), "{}", listing);
+        assert!(lines.iter().any(|l| l.starts_with("    4  4014  CD A2 00       17")), "{}", listing);
+        // Los datos no llevan estados T y siguen en otra fila tras 4 bytes
+        assert!(lines.iter().any(|l| l.starts_with("    6  4018  48 4F 4C 41        TEXTO:")), "{}", listing);
+        assert!(lines.iter().any(|l| l.trim_end() == "       401C  00"), "{}", listing);
+        // ORG no genera bytes ni dirección
+        assert!(lines[1].starts_with("    1                            "), "{}", listing);
+    }
+
+    #[test]
+    fn test_macro_lines_follow_invocation() {
+        let source = "        ORG 0C000h
+ESPERA  MACRO
+        NOP
+        ENDM
+        ESPERA
+        RET
+";
+        let listing = Assembler::new(OutputFormat::Bin).assemble(source).unwrap().listing();
+        let invocation = listing.find("        ESPERA\n").unwrap();
+        let body = listing.find("  C000  00").unwrap();
+        let ret = listing.find("  C001  C9").unwrap();
+        assert!(invocation < body && body < ret, "{}", listing);
+    }
+
+    #[test]
+    fn test_processor_symbol_api() {
+        let mut processor = MSX2Processor::new(256, 212);
+        assert!(processor.find_memory_slot(0x00A2).ends_with(" CHPUT"));
+        assert!(processor.import_symbols("nada que leer").starts_with("❌"));
+        assert_eq!(processor.import_symbols("INICIO: EQU 0x00004010\nBUCLE: EQU 0x00004012"), "✅ 2 símbolos importados");
+        assert!(processor.find_memory_slot(0x4015).ends_with(" BUCLE+3"));
+        assert!(processor.export_symbols("noice").contains("DEF INICIO 4010H\n"));
+        assert_eq!(processor.export_symbols("elf"), "");
+
+        processor.reset_symbols();
+        assert!(!processor.export_symbols("sjasm").contains("INICIO"));
+        assert!(processor.assemble_symbols(PROGRAM, "rom", "openmsx").contains("TEXTO: equ 4018h\n"));
+        assert!(processor.assemble_listing(PROGRAM, "rom").contains("CALL CHPUT"));
+        assert_eq!(processor.assemble_listing("        FOO", "rom"), "");
+    }
+}
@@ -0,0 +1,145 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - SEÑAL DE CINTA FSK (CAS ↔ WAV)                   ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::tape::{decode_wav, encode_wav, read_wav, SAMPLE_RATE};
+    use msx2_processor::{BloadHeader, CasFileType, CasImage, MSX2Processor};
+
+    fn sample_image() -> CasImage {
+        let mut header = vec![0xD0; 10];
+        header.extend_from_slice(b"TEST  ");
+        let mut data = BloadHeader::new(0xC000, 0xC00F, 0xC000).to_raw_bytes().to_vec();
+        data.extend((0..16u8).map(|b| b.wrapping_mul(37)));
+        data.extend_from_slice(&[0xFF, 0x00]);
+        CasImage::from_blocks(vec![header, data])
+    }
+
+    fn data_offset(wav: &[u8]) -> usize {
+        wav.windows(4).position(|w| w == b"data").unwrap() + 8
+    }
+
+    #[test]
+    fn test_wav_header_is_pcm_8bit_mono() {
+        let wav = encode_wav(&sample_image(), 1200).unwrap();
+        let (rate, samples) = read_wav(&wav).unwrap();
+        assert_eq!(rate, SAMPLE_RATE);
+        assert_eq!(samples.len(), wav.len() - 44);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u16::from_le_bytes([wav[34], wav[35]]), 8);
+    }
+
+    #[test]
+    fn test_unsupported_baud_rejected() {
+        assert!(encode_wav(&sample_image(), 600).is_err());
+        assert!(encode_wav(&sample_image(), 4800).is_err());
+    }
+
+    #[test]
+    fn test_2400_baud_is_shorter() {
+        let slow = encode_wav(&sample_image(), 1200).unwrap();
+        let fast = encode_wav(&sample_image(), 2400).unwrap();
+        // Los tonos duran lo mismo, pero los datos van al doble de velocidad
+        assert!(fast.len() < slow.len() * 2);
+        let data_bits = sample_image().blocks().iter().map(|b| b.data.len() * 11).sum::<usize>();
+        let saved = slow.len() - fast.len();
+        assert!(saved >= data_bits * (SAMPLE_RATE / 2400) as usize);
+    }
+
+    #[test]
+    fn test_round_trip_1200_baud() {
+        let image = sample_image();
+        let decoded = decode_wav(&encode_wav(&image, 1200).unwrap()).unwrap();
+        assert_eq!(decoded.reports.len(), 2);
+        assert!(decoded.reports.iter().all(|r| r.is_ok() && r.baud == 1200));
+        assert_eq!(decoded.image.to_bytes(), image.to_bytes());
+    }
+
+    #[test]
+    fn test_round_trip_2400_baud() {
+        let image = sample_image();
+        let decoded = decode_wav(&encode_wav(&image, 2400).unwrap()).unwrap();
+        assert!(decoded.reports.iter().all(|r| r.is_ok() && r.baud == 2400));
+        assert_eq!(decoded.image.files()[0].file_type, CasFileType::Binary);
+        assert_eq!(decoded.image.to_bytes(), image.to_bytes());
+    }
+
+    #[test]
+    fn test_16bit_inverted_recording_decodes() {
+        // Grabación de 16 bits con la polaridad invertida, como sale de muchas tarjetas
+        let wav = encode_wav(&sample_image(), 1200).unwrap();
+        let body = &wav[data_offset(&wav)..];
+        let mut out = wav[..44].to_vec();
+        out[22..24].copy_from_slice(&1u16.to_le_bytes());
+        out[28..32].copy_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        out[32..34].copy_from_slice(&2u16.to_le_bytes());
+        out[34..36].copy_from_slice(&16u16.to_le_bytes());
+        out[40..44].copy_from_slice(&(body.len() as u32 * 2).to_le_bytes());
+        for &s in body {
+            let v = -((s as i16 - 128) * 200);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let decoded = decode_wav(&out).unwrap();
+        assert!(decoded.reports.iter().all(|r| r.is_ok()));
+        assert_eq!(decoded.image.to_bytes(), sample_image().to_bytes());
+    }
+
+    #[test]
+    fn test_damaged_block_reports_errors() {
+        let mut wav = encode_wav(&sample_image(), 1200).unwrap();
+        let start = data_offset(&wav);
+        // Dañar una zona de datos del segundo bloque sustituyendo pulsos por un tono intermedio
+        let (_, samples) = read_wav(&wav).unwrap();
+        let last_sound = samples.iter().rposition(|s| s.abs() > 0.1).unwrap();
+        let damaged = start + last_sound - 120 * 36;
+        for (i, s) in wav[damaged..damaged + 3 * 36].iter_mut().enumerate() {
+            *s = if (i / 13) % 2 == 0 { 228 } else { 28 };
+        }
+
+        let decoded = decode_wav(&wav).unwrap();
+        assert!(decoded.reports[0].is_ok());
+        let report = &decoded.reports[1];
+        assert!(!report.is_ok());
+        assert!(report.errors[0].starts_with("Byte"));
+    }
+
+    #[test]
+    fn test_silence_and_garbage_rejected() {
+        assert!(read_wav(b"not a wav file").is_err());
+        let silent = encode_wav(&CasImage::from_blocks(Vec::new()), 1200).unwrap();
+        assert!(decode_wav(&silent).is_err());
+
+        // Tamaños de bloque enormes: el fichero se da por truncado
+        let wav = encode_wav(&sample_image(), 1200).unwrap();
+        let mut huge = wav.clone();
+        huge[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(read_wav(&huge).unwrap().1.len(), wav.len() - 44);
+        let mut skipped = wav[..12].to_vec();
+        skipped.extend_from_slice(b"LIST");
+        skipped.extend_from_slice(&u32::MAX.to_le_bytes());
+        skipped.extend_from_slice(&wav[12..]);
+        assert!(read_wav(&skipped).unwrap_err().contains("sin bloque de datos"));
+    }
+
+    #[test]
+    fn test_processor_wav_conversion() {
+        let mut processor = MSX2Processor::new(256, 212);
+        assert!(processor.cas_to_wav(1200).is_empty());
+        processor.load_cas_image(&sample_image().to_bytes());
+        let wav = processor.cas_to_wav(2400);
+        assert!(!wav.is_empty());
+
+        let mut other = MSX2Processor::new(256, 212);
+        assert!(other.load_wav_tape(&wav).starts_with("✅"));
+        assert_eq!(other.get_cas_image(), sample_image().to_bytes());
+        assert!(other.list_cas_files().contains(r#""name":"TEST","type":"binary""#));
+        let report = other.get_tape_report();
+        assert!(report.contains(r#""baud":2400,"ok":true,"errors":[]"#));
+
+        assert!(other.load_wav_tape(b"RIFF").starts_with("❌"));
+        assert_eq!(other.get_tape_report(), "[]");
+    }
+}
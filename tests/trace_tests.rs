@@ -0,0 +1,235 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - TRAZA DE EJECUCIÓN                               ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::trace::{describe_difference, first_difference, read_binary, TRACE_MAGIC};
+    use msx2_processor::{
+        Assembler, MSX2Processor, Machine, OutputFormat, SlotId, SymbolTable, TraceEntry, TraceFormat, Tracer,
+    };
+
+    const PROGRAMA: &str = "        ORG 8000h
+INICIO: LD SP,0F000h
+        LD B,4
+.bucle: DJNZ .bucle
+        CALL SUB
+FIN:    JR FIN
+SUB:    LD A,1
+        RET
+";
+
+    fn traced() -> (Machine, SymbolTable) {
+        let assembly = Assembler::new(OutputFormat::Bin).assemble(PROGRAMA).unwrap();
+        let mut machine = Machine::with_ram();
+        machine.load(assembly.origin, &assembly.code);
+        machine.cpu.regs.pc = assembly.entry;
+        (machine, assembly.symbol_table())
+    }
+
+    fn run(machine: &mut Machine, steps: usize) {
+        for _ in 0..steps {
+            machine.step();
+        }
+    }
+
+    fn history(machine: &Machine) -> Vec<TraceEntry> {
+        machine.trace.as_ref().unwrap().history().iter().copied().collect()
+    }
+
+    #[test]
+    fn test_entry_state() {
+        let (mut machine, _) = traced();
+        machine.trace = Some(Tracer::new(10));
+        run(&mut machine, 2);
+        let entries = history(&machine);
+        assert_eq!(entries.len(), 2);
+        let first = &entries[0];
+        assert_eq!((first.pc, first.cycles, first.tstates), (0x8000, 0, 11));
+        assert_eq!(first.opcode(), &[0x31, 0x00, 0xF0]);
+        // Registros de antes de ejecutar
+        assert_eq!(first.regs.sp, 0xFFFF);
+        assert_eq!(first.slots, [SlotId { primary: 3, secondary: 0 }; 4]);
+        assert!(!first.interrupt);
+        assert_eq!((entries[1].cycles, entries[1].regs.sp), (11, 0xF000));
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let (mut machine, _) = traced();
+        machine.trace = Some(Tracer::new(3));
+        run(&mut machine, 20);
+        let tracer = machine.trace.as_ref().unwrap();
+        assert_eq!(tracer.recorded(), 20);
+        let entries = history(&machine);
+        assert_eq!(entries.len(), 3);
+        // Solo quedan las últimas, ya en el bucle de FIN
+        assert!(entries.iter().all(|e| e.pc == 0x800A));
+        assert!(entries.windows(2).all(|w| w[1].cycles == w[0].cycles + w[0].tstates as u64));
+    }
+
+    #[test]
+    fn test_range_and_slot_filters() {
+        let (mut machine, _) = traced();
+        machine.trace = Some(Tracer::new(100).range(0x800C, 0x800F));
+        run(&mut machine, 20);
+        let pcs: Vec<u16> = history(&machine).iter().map(|e| e.pc).collect();
+        assert_eq!(pcs, vec![0x800C, 0x800E]);
+
+        let (mut machine, _) = traced();
+        machine.trace = Some(Tracer::new(100).slot(1, None).slot(3, Some(1)));
+        run(&mut machine, 20);
+        assert!(history(&machine).is_empty());
+
+        let (mut machine, _) = traced();
+        machine.trace = Some(Tracer::new(100).slot(3, None).range(0x8005, 0x8006));
+        run(&mut machine, 20);
+        // DJNZ cuatro veces
+        assert_eq!(history(&machine).len(), 4);
+
+        // En pausa no se apunta nada
+        let (mut machine, _) = traced();
+        let mut tracer = Tracer::new(100);
+        tracer.active = false;
+        machine.trace = Some(tracer);
+        run(&mut machine, 5);
+        assert!(history(&machine).is_empty());
+    }
+
+    #[test]
+    fn test_text_lines() {
+        let (mut machine, symbols) = traced();
+        machine.trace = Some(Tracer::new(100).symbols(symbols));
+        run(&mut machine, 9);
+        let text = machine.trace.as_ref().unwrap().text();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 9);
+        let first = lines[0];
+        assert!(first.trim_start().starts_with("0 8000 31 00 F0"), "{}", first);
+        assert!(first.contains("LD SP,0F000h"), "{}", first);
+        assert!(first.contains("SP=FFFF") && first.contains("3-0 3-0 3-0 3-0"), "{}", first);
+        assert!(first.ends_with("; INICIO"), "{}", first);
+        assert!(lines[6].contains("CALL SUB"), "{}", lines[6]);
+        assert!(lines[8].contains("RET") && lines[8].ends_with("; SUB+2"), "{}", lines[8]);
+    }
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let (mut machine, _) = traced();
+        machine.trace = Some(Tracer::new(100));
+        run(&mut machine, 12);
+        let binary = machine.trace.as_ref().unwrap().binary();
+        assert!(binary.starts_with(TRACE_MAGIC));
+        assert_eq!(read_binary(&binary).unwrap(), history(&machine));
+
+        assert!(read_binary(b"MSXTRAZA").is_err());
+        assert!(read_binary(&binary[..binary.len() - 1]).unwrap_err().contains("truncada"));
+        let mut version = binary.clone();
+        version[TRACE_MAGIC.len()] = 9;
+        assert!(read_binary(&version).unwrap_err().contains("Versión"));
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let trace = |counter: u8| {
+            let (mut machine, _) = traced();
+            machine.load(0x8004, &[counter]);
+            machine.trace = Some(Tracer::new(100));
+            run(&mut machine, 12);
+            history(&machine)
+        };
+        let (a, b, c) = (trace(4), trace(4), trace(5));
+        assert_eq!(first_difference(&a, &b), None);
+        // LD B,4 frente a LD B,5: la segunda instrucción ya difiere
+        assert_eq!(first_difference(&a, &c), Some(1));
+        assert_eq!(first_difference(&a, &a[..5]), Some(5));
+
+        let diff = describe_difference(&a, &c, &SymbolTable::new()).unwrap();
+        assert!(diff.starts_with("Instrucción 1 (8003h)"), "{}", diff);
+        assert!(diff.contains("< ") && diff.contains("LD B,04h") && diff.contains("LD B,05h"), "{}", diff);
+        assert!(describe_difference(&a, &a[..2], &SymbolTable::new()).unwrap().contains("(fin de la traza)"));
+    }
+
+    #[test]
+    fn test_stream_to_files() {
+        let dir = std::env::temp_dir().join(format!("trace_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (text_path, binary_path) = (dir.join("traza.txt"), dir.join("traza.bin"));
+
+        // Solo al fichero, sin histórico
+        let (mut machine, symbols) = traced();
+        let file = std::fs::File::create(&text_path).unwrap();
+        machine.trace = Some(Tracer::new(0).symbols(symbols).stream(Box::new(file), TraceFormat::Text));
+        run(&mut machine, 10);
+        let mut tracer = machine.trace.take().unwrap();
+        tracer.flush();
+        assert!(tracer.history().is_empty() && tracer.error().is_none());
+        let text = std::fs::read_to_string(&text_path).unwrap();
+        assert_eq!(text.lines().count(), 10);
+        assert!(text.lines().next().unwrap().ends_with("; INICIO"));
+
+        // Binario y los últimos en el histórico a la vez
+        let (mut machine, _) = traced();
+        let file = std::fs::File::create(&binary_path).unwrap();
+        machine.trace = Some(Tracer::new(4).stream(Box::new(file), TraceFormat::Binary));
+        run(&mut machine, 10);
+        let recent = history(&machine);
+        drop(machine);
+        let streamed = read_binary(&std::fs::read(&binary_path).unwrap()).unwrap();
+        assert_eq!(streamed.len(), 10);
+        assert_eq!(&streamed[6..], &recent[..]);
+
+        assert_eq!(TraceFormat::from_name("BIN"), Some(TraceFormat::Binary));
+        assert_eq!(TraceFormat::from_name("csv"), None);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_interrupt_and_halt() {
+        let mut machine = Machine::with_ram();
+        // IM 1, EI, HALT; en 0038h un RETI... basta con RET
+        machine.load(0x8000, &[0x31, 0x00, 0xF0, 0xED, 0x56, 0xFB, 0x76]);
+        machine.load(0x0038, &[0xC9]);
+        machine.cpu.regs.pc = 0x8000;
+        machine.bus.vdp.registers[1] |= 0x20;
+        machine.trace = Some(Tracer::new(100));
+        run(&mut machine, 5);
+        machine.bus.vdp.vblank();
+        run(&mut machine, 2);
+        let entries = history(&machine);
+        let halts = entries.iter().filter(|e| e.opcode() == [0x76]).count();
+        assert_eq!(halts, 2);
+        let interrupt = entries.iter().find(|e| e.interrupt).unwrap();
+        assert!(interrupt.opcode().is_empty());
+        assert_eq!(interrupt.pc, 0x8006);
+        assert!(interrupt.text(&SymbolTable::new()).contains(" INT "));
+        assert_eq!(entries.last().unwrap().pc, 0x0038);
+    }
+
+    #[test]
+    fn test_processor_trace() {
+        let mut processor = MSX2Processor::new(256, 212);
+        processor.insert_ram(3, 0);
+        processor.io_write(0xA8, 0xFF);
+        processor.debug_assemble(PROGRAMA, "bin");
+        assert!(processor.trace_start(100, "8000h-800Fh, zz", "").starts_with("❌"));
+        assert!(processor.trace_start(100, "", "4-0").starts_with("❌"));
+        assert!(processor.trace_start(100, "8000h-800Bh", "3-0").starts_with("✅"));
+        processor.run_to(0x800A, 10_000);
+        let text = processor.trace_text();
+        assert_eq!(text.lines().count(), 7);
+        assert!(text.lines().next().unwrap().contains("8000 31 00 F0    LD SP,0F000h"), "{}", text);
+
+        let binary = processor.trace_binary();
+        assert_eq!(processor.trace_compare(&binary, &binary), -1);
+        assert_eq!(processor.trace_compare(&binary, b"nada"), -2);
+        assert_eq!(processor.trace_diff(&binary, &binary), "✅ Trazas idénticas");
+
+        processor.trace_stop();
+        processor.step_into();
+        assert_eq!(processor.trace_text().lines().count(), 7);
+        processor.trace_clear();
+        assert!(processor.trace_text().is_empty());
+    }
+}
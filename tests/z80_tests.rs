@@ -0,0 +1,178 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - Z80, V9938 Y MÁQUINA                             ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::z80::{FLAG_C, FLAG_H, FLAG_N, FLAG_PV, FLAG_S, FLAG_Z};
+    use msx2_processor::{AccessKind, Assembler, Machine, OutputFormat, SymbolTable, V9938};
+
+    /// Ensamblar en 8000h, cargar en una máquina con RAM y ejecutar hasta
+    /// el HALT final
+    fn run(source: &str) -> Machine {
+        let source = format!("        ORG 8000h\n        LD SP,0F000h\n{}        HALT\n", source);
+        let assembly = Assembler::new(OutputFormat::Bin).symbols(&SymbolTable::msx()).assemble(&source).unwrap();
+        let mut machine = Machine::with_ram();
+        machine.load(assembly.origin, &assembly.code);
+        machine.cpu.regs.pc = assembly.origin;
+        for _ in 0..100_000 {
+            if machine.cpu.halted {
+                return machine;
+            }
+            machine.step();
+        }
+        panic!("El programa no llegó al HALT");
+    }
+
+    #[test]
+    fn test_arithmetic_flags() {
+        let m = run("        LD A,7Fh\n        ADD A,1\n");
+        assert_eq!(m.cpu.regs.a, 0x80);
+        assert_eq!(m.cpu.regs.f & (FLAG_S | FLAG_Z | FLAG_H | FLAG_PV | FLAG_N | FLAG_C), FLAG_S | FLAG_H | FLAG_PV);
+
+        let m = run("        XOR A\n        SUB 1\n");
+        assert_eq!(m.cpu.regs.a, 0xFF);
+        assert_eq!(m.cpu.regs.f & (FLAG_S | FLAG_Z | FLAG_N | FLAG_C), FLAG_S | FLAG_N | FLAG_C);
+
+        let m = run("        LD A,5\n        CP 5\n");
+        assert_eq!(m.cpu.regs.f & (FLAG_Z | FLAG_N | FLAG_C), FLAG_Z | FLAG_N);
+
+        // INC no toca el acarreo
+        let m = run("        SCF\n        LD B,0FFh\n        INC B\n");
+        assert_eq!((m.cpu.regs.b, m.cpu.regs.f & (FLAG_Z | FLAG_C)), (0, FLAG_Z | FLAG_C));
+    }
+
+    #[test]
+    fn test_daa_and_16_bit() {
+        let m = run("        LD A,19h\n        ADD A,28h\n        DAA\n");
+        assert_eq!(m.cpu.regs.a, 0x47);
+        let m = run("        LD A,47h\n        SUB 19h\n        DAA\n");
+        assert_eq!(m.cpu.regs.a, 0x28);
+
+        let m = run("        LD HL,0FFFFh\n        LD DE,1\n        ADD HL,DE\n");
+        assert_eq!(m.cpu.regs.hl(), 0);
+        assert_ne!(m.cpu.regs.f & FLAG_C, 0);
+        let m = run("        LD HL,1000h\n        LD BC,1\n        SCF\n        SBC HL,BC\n");
+        assert_eq!(m.cpu.regs.hl(), 0x0FFE);
+    }
+
+    #[test]
+    fn test_stack_calls_and_exchange() {
+        let source = "        LD BC,1234h\n        PUSH BC\n        POP DE\n        CALL SUMA\n        EXX\n        JR FIN\nSUMA:   INC DE\n        RET\nFIN:\n";
+        let m = run(source);
+        assert_eq!(m.cpu.regs.sp, 0xF000);
+        // Tras EXX los registros principales son los alternativos
+        assert_eq!(m.cpu.regs.de2, 0x1235);
+        assert_eq!(m.cpu.regs.bc2, 0x1234);
+        assert_eq!(m.cpu.regs.de(), 0);
+    }
+
+    #[test]
+    fn test_block_instructions() {
+        let source = "        LD HL,ORIGEN\n        LD DE,0C000h\n        LD BC,4\n        LDIR\n        JR FIN\nORIGEN: DB 1,2,3,4\nFIN:\n";
+        let m = run(source);
+        assert_eq!((0..4).map(|i| m.bus.peek(0xC000 + i)).collect::<Vec<u8>>(), vec![1, 2, 3, 4]);
+        assert_eq!((m.cpu.regs.bc(), m.cpu.regs.de()), (0, 0xC004));
+        assert_eq!(m.cpu.regs.f & FLAG_PV, 0);
+
+        let source = "        LD HL,TABLA\n        LD BC,10\n        LD A,3\n        CPIR\n        JR FIN\nTABLA:  DB 1,2,3,4\nFIN:\n";
+        let m = run(source);
+        assert_eq!(m.cpu.regs.bc(), 7);
+        assert_ne!(m.cpu.regs.f & FLAG_Z, 0);
+    }
+
+    #[test]
+    fn test_index_registers_and_bits() {
+        let source = "        LD IX,0C000h\n        LD (IX+5),81h\n        SET 1,(IX+5)\n        RES 7,(IX+5)\n        LD A,(IX+5)\n        BIT 0,(IX+5)\n";
+        let m = run(source);
+        assert_eq!(m.cpu.regs.a, 0x03);
+        assert_eq!(m.cpu.regs.f & FLAG_Z, 0);
+        let m = run("        LD IY,1234h\n        LD A,IYH\n        RLCA\n        LD B,80h\n        SRL B\n");
+        assert_eq!((m.cpu.regs.a, m.cpu.regs.b), (0x24, 0x40));
+    }
+
+    #[test]
+    fn test_msx_tstates() {
+        let mut m = run("");
+        m.load(0x9000, &[0x00, 0xDD, 0x7E, 0x05, 0xED, 0xB0]);
+        m.cpu.regs.pc = 0x9000;
+        m.cpu.halted = false;
+        assert_eq!(m.step().tstates, 5);
+        assert_eq!(m.step().tstates, 21);
+        // LDIR con BC=2: una vuelta que repite y otra que termina
+        m.cpu.regs.set_bc(2);
+        assert_eq!(m.step().tstates, 23);
+        assert_eq!(m.step().tstates, 18);
+        // Sin la espera M1, los tiempos del Z80
+        m.cpu.m1_wait = false;
+        m.cpu.regs.pc = 0x9000;
+        assert_eq!(m.step().tstates, 4);
+    }
+
+    #[test]
+    fn test_vdp_interrupt_wakes_halt() {
+        // IM 1, interrupción del VDP activada en R#1 y HALT
+        let source = "        IM 1\n        LD A,20h\n        OUT (99h),A\n        LD A,81h\n        OUT (99h),A\n        EI\n";
+        let mut m = run(source);
+        let halt = m.cpu.regs.pc;
+        assert_eq!(m.bus.vdp.registers[1], 0x20);
+        let frames = m.frames;
+        while m.cpu.regs.pc != 0x0038 {
+            m.step();
+        }
+        assert_eq!(m.frames, frames + 1);
+        assert!(!m.cpu.halted && !m.cpu.regs.iff1);
+        // La dirección de vuelta es la siguiente al HALT
+        let back = m.bus.peek(m.cpu.regs.sp) as u16 | (m.bus.peek(m.cpu.regs.sp + 1) as u16) << 8;
+        assert_eq!(back, halt + 1);
+    }
+
+    #[test]
+    fn test_accesses_are_recorded() {
+        let mut m = run("");
+        m.load(0x9000, &[0x32, 0x00, 0xC0, 0xD3, 0x98]);
+        m.cpu.regs.pc = 0x9000;
+        m.cpu.halted = false;
+        m.cpu.regs.a = 0x55;
+        m.step();
+        let kinds: Vec<(AccessKind, u16)> = m.accesses.iter().map(|a| (a.kind, a.address)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (AccessKind::Fetch, 0x9000),
+                (AccessKind::Fetch, 0x9001),
+                (AccessKind::Fetch, 0x9002),
+                (AccessKind::Write, 0xC000)
+            ]
+        );
+        m.step();
+        assert_eq!(m.accesses.last().map(|a| (a.kind, a.address, a.value)), Some((AccessKind::Out, 0x98, 0x55)));
+        assert_eq!(m.bus.vdp.vram[0], 0x55);
+    }
+
+    #[test]
+    fn test_vdp_ports() {
+        let mut vdp = V9938::new();
+        // Dirección de escritura 1234h y dos bytes
+        vdp.write(0x99, 0x34);
+        vdp.write(0x99, 0x52);
+        vdp.write(0x98, 0xAA);
+        vdp.write(0x98, 0xBB);
+        assert_eq!((vdp.vram[0x1234], vdp.vram[0x1235]), (0xAA, 0xBB));
+        // Lectura desde 1234h
+        vdp.write(0x99, 0x34);
+        vdp.write(0x99, 0x12);
+        assert_eq!(vdp.read(0x98), 0xAA);
+        // Registro 7 y escritura indirecta en R#8 por R#17
+        vdp.write(0x99, 0xF4);
+        vdp.write(0x99, 0x87);
+        assert_eq!(vdp.take_register_write(), Some((7, 0xF4)));
+        vdp.write_register(17, 8);
+        vdp.write(0x9B, 0x0A);
+        assert_eq!((vdp.registers[8], vdp.registers[17]), (0x0A, 9));
+        // SCREEN 5: M4 y M3 en R#0
+        vdp.write_register(0, 0x06);
+        assert_eq!(vdp.screen_mode(), Some(5));
+    }
+}
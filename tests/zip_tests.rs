@@ -0,0 +1,181 @@
+//! ╔═════════════════════════════════════════════════════════════════╗
+//! ║         TESTS - LECTOR DE ARCHIVOS ZIP                           ║
+//! ║    © 2026 PAPIWEB DESARROLLOS INFORMATICOS                       ║
+//! ╚═════════════════════════════════════════════════════════════════╝
+
+#[cfg(test)]
+mod tests {
+    use msx2_processor::inflate::{inflate, inflate_prefix};
+    use msx2_processor::zip::crc32;
+    use msx2_processor::{BloadHeader, FileKind, MSX2Processor, ZipArchive};
+
+    fn repo_file(path: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path)).unwrap()
+    }
+
+    /// ZIP mínimo con entradas almacenadas (método 0)
+    fn stored_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data) in files {
+            let offset = out.len() as u32;
+            let crc = crc32(data);
+            let mut common = Vec::new();
+            common.extend_from_slice(&[20, 0, 0, 0, 0, 0]); // versión, flags, método
+            common.extend_from_slice(&[0x00, 0x60, 0x21, 0x11]); // hora y fecha (1988-09-01)
+            common.extend_from_slice(&crc.to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            common.extend_from_slice(&[0, 0]);
+
+            out.extend_from_slice(b"PK\x03\x04");
+            out.extend_from_slice(&common);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+
+            central.extend_from_slice(b"PK\x01\x02");
+            central.extend_from_slice(&[20, 0]);
+            central.extend_from_slice(&common);
+            central.extend_from_slice(&[0; 10]); // comentario, disco y atributos
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let central_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(b"PK\x05\x06");
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&central_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    #[test]
+    fn test_crc32_reference_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_inflate_stored_and_fixed_blocks() {
+        // Bloque almacenado: BFINAL=1, BTYPE=00, LEN=3
+        assert_eq!(inflate(&[0x01, 0x03, 0x00, 0xFC, 0xFF, b'M', b'S', b'X']).unwrap(), b"MSX");
+        // "aaaaaaaaaa" con Huffman fijo (zlib -9, sin envoltorio)
+        assert_eq!(inflate(&[0x4B, 0x4C, 0x84, 0x01, 0x00]).unwrap(), b"aaaaaaaaaa");
+        assert!(inflate(&[0x07]).is_err());
+    }
+
+    #[test]
+    fn test_test_zips_blocks_match_loose_files() {
+        let zip = repo_file("test_zips/pengadvb.zip");
+        let archive = ZipArchive::parse(&zip).unwrap();
+        assert_eq!(archive.entries().len(), 3);
+        for (i, name) in ["block0.bin", "block1.bin", "block2.bin"].iter().enumerate() {
+            assert_eq!(archive.entries()[i].name, *name);
+            assert_eq!(archive.entries()[i].method_name(), "deflate");
+            assert_eq!(archive.extract(i).unwrap(), repo_file(&format!("test_zips/{}", name)));
+        }
+    }
+
+    #[test]
+    fn test_rooms_zip_deflate_and_crc() {
+        let zip = repo_file("rooms/pengadvb.zip");
+        let archive = ZipArchive::parse(&zip).unwrap();
+        assert_eq!(archive.entries().len(), 5);
+        for i in 0..5 {
+            let data = archive.extract(i).unwrap();
+            assert_eq!(data.len(), 32768);
+            assert_eq!(crc32(&data), archive.entries()[i].crc32);
+        }
+    }
+
+    #[test]
+    fn test_pick_rom_by_sniffing() {
+        let zip = repo_file("rooms/pengadvb.zip");
+        let archive = ZipArchive::parse(&zip).unwrap();
+        // Ninguna entrada tiene extensión reconocible; solo rom.u7 lleva la cabecera "AB"
+        let index = archive.pick(FileKind::Rom).unwrap();
+        assert_eq!(archive.entries()[index].name, "rom.u7");
+        assert_eq!(archive.pick(FileKind::Disk), None);
+    }
+
+    #[test]
+    fn test_pick_by_extension_and_content() {
+        let mut bios = vec![0u8; 0x8000];
+        bios[0] = 0xF3;
+        bios[1] = 0xC3;
+        let mut screen = BloadHeader::new(0x0000, 0x69FF, 0x0000).to_bytes().to_vec();
+        screen.extend(vec![0x11; 0x6A00]);
+        let zip = stored_zip(&[
+            ("readme.txt", b"hola"),
+            ("msx2.rom", &bios),
+            ("game.rom", b"AB\x10\x40"),
+            ("title.sc5", &screen),
+        ]);
+        let archive = ZipArchive::parse(&zip).unwrap();
+        assert_eq!(archive.pick(FileKind::Bios), Some(1));
+        assert_eq!(archive.pick(FileKind::Rom), Some(2));
+        assert_eq!(archive.pick(FileKind::Graphics), Some(3));
+        assert_eq!(archive.classify(0), FileKind::Unknown);
+    }
+
+    #[test]
+    fn test_classify_reads_only_the_head() {
+        let mut zip = repo_file("rooms/pengadvb.zip");
+        let archive = ZipArchive::parse(&zip).unwrap();
+        let index = archive.find("rom.u7").unwrap();
+        let full = archive.extract(index).unwrap();
+        assert_eq!(archive.head(index, 16).unwrap(), &full[..16]);
+        assert_eq!(inflate_prefix(&[0x4B, 0x4C, 0x84, 0x01, 0x00], 4).unwrap(), b"aaaa");
+
+        // Con el final de los datos comprimidos estropeado la entrada ya no se
+        // extrae, pero su principio sigue bastando para reconocerla
+        let entry = &archive.entries()[index];
+        let name_len = entry.name.len();
+        let local = (0..zip.len() - 36)
+            .find(|&p| zip[p..].starts_with(b"PK\x03\x04") && zip[p + 30..].starts_with(b"rom.u7"))
+            .unwrap();
+        let end = local + 30 + name_len + entry.compressed_size as usize;
+        zip[end - 8..end].fill(0xFF);
+        let archive = ZipArchive::parse(&zip).unwrap();
+        assert!(archive.extract(index).is_err());
+        assert_eq!(archive.classify(index), FileKind::Rom);
+        assert_eq!(archive.pick(FileKind::Rom), Some(index));
+    }
+
+    #[test]
+    fn test_corrupted_entry_fails_crc() {
+        let mut zip = stored_zip(&[("a.bin", b"ABCDEFGH")]);
+        let pos = zip.windows(8).position(|w| w == b"ABCDEFGH").unwrap();
+        zip[pos + 3] ^= 0xFF;
+        let archive = ZipArchive::parse(&zip).unwrap();
+        assert!(archive.extract(0).unwrap_err().contains("CRC"));
+    }
+
+    #[test]
+    fn test_not_a_zip_rejected() {
+        assert!(ZipArchive::parse(b"PK\x03\x04 but no directory").is_err());
+        assert!(ZipArchive::parse(&[]).is_err());
+    }
+
+    #[test]
+    fn test_processor_zip_api() {
+        let processor = MSX2Processor::new(256, 212);
+        let zip = repo_file("rooms/pengadvb.zip");
+
+        let listing = processor.list_zip_entries(&zip);
+        assert!(listing.contains(r#""name":"rom.u7","size":32768"#));
+        assert!(listing.contains(r#""crc":"D4B4A4A4","date":"1996-12-24","kind":"rom""#));
+
+        let index = processor.pick_zip_member(&zip, "rom");
+        assert!(index >= 0);
+        assert_eq!(&processor.extract_zip_entry(&zip, index as u32)[..2], b"AB");
+        assert_eq!(processor.pick_zip_member(&zip, "tape"), -1);
+        assert_eq!(processor.pick_zip_member(&zip, "nonsense"), -1);
+        assert!(processor.extract_zip_entry(&zip, 99).is_empty());
+        assert_eq!(processor.list_zip_entries(b"garbage"), "[]");
+    }
+}